@@ -5,25 +5,38 @@ use bevy::ecs::entity::{Entity, EntityHashSet};
 use bevy::ecs::hierarchy::ChildOf;
 use bevy::ecs::message::MessageReader;
 use bevy::ecs::query::{Has, With, Without};
-use bevy::ecs::system::{Commands, Query, Res, Single};
+use bevy::ecs::system::{Commands, Query, Res, ResMut, Single};
 use bevy::math::IRect;
+use serde::{Deserialize, Serialize};
 use tracing::{Level, instrument};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
+mod expect;
+mod properties;
 mod query;
+pub(crate) mod slots;
+
+pub use expect::{Expectation, PendingExpectations, parse_expect_request};
+pub use properties::{PropertyRequest, WindowTarget, parse_property_request};
 
 use crate::config::Config;
 use crate::ecs::display::FloatingLayer;
 use crate::ecs::focus::FocusHistory;
-use crate::ecs::layout::{Column, LayoutStrip, StackItem};
+use crate::ecs::layout::{
+    Column, ColumnOrientation, LayoutStrip, StackItem, StackedArrangement, next_size_ratio,
+};
 use crate::ecs::params::{ActiveDisplay, ActiveDisplayMut, Windows};
+use crate::ecs::state::PaneruState;
 use crate::ecs::{
     ActiveDisplayMarker, ActiveWorkspaceMarker, Bounds, DockPosition, FocusedMarker,
-    FullWidthMarker, NativeFullscreenMarker, SelectedVirtualMarker, SendMessageTrigger,
-    SpawnCommandsExt, Timeout, Unmanaged,
+    FullWidthMarker, InsertionPoint, LayoutLocked, LowPowerMode, NativeFullscreenMarker, Pinned,
+    PreviousManagedStrip, SelectedVirtualMarker, SendMessageTrigger, SizeRatios, SpawnCommandsExt,
+    Timeout, Unmanaged, WindowProperties,
 };
 use crate::events::Event;
-use crate::manager::{Application, Display, Origin, Size, Window, WindowManager, origin_from};
+use crate::manager::{
+    Application, Display, Origin, Size, Window, WindowManager, most_overlapping, origin_from,
+};
 use crate::platform::WorkspaceId;
 
 /// Represents a cardinal or directional choice for window manipulation.
@@ -57,6 +70,32 @@ pub enum ResizeDirection {
     Shrink,
 }
 
+/// Amount by which a window edge is moved, either in pixels or as a percentage
+/// of the viewport extent along the resized axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResizeAmount {
+    Pixels(i32),
+    Percent(f64),
+}
+
+impl ResizeAmount {
+    /// Converts the amount into pixels, relative to the `extent` of the viewport.
+    pub fn to_pixels(self, extent: i32) -> i32 {
+        match self {
+            ResizeAmount::Pixels(pixels) => pixels,
+            ResizeAmount::Percent(percent) => (f64::from(extent) * percent / 100.0).round() as i32,
+        }
+    }
+
+    /// Returns the same amount with the opposite sign, turning a grow into a shrink.
+    pub const fn negate(self) -> Self {
+        match self {
+            ResizeAmount::Pixels(pixels) => ResizeAmount::Pixels(-pixels),
+            ResizeAmount::Percent(percent) => ResizeAmount::Percent(-percent),
+        }
+    }
+}
+
 /// Controls whether focus follows the window after a move operation.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MoveFocus {
@@ -64,19 +103,52 @@ pub enum MoveFocus {
     Stay,
 }
 
+/// Where the next spawned window goes, relative to the column marked with
+/// `window_set_insertion_point`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InsertionPlacement {
+    /// A new column to the left of the marked one.
+    West,
+    /// A new column to the right of the marked one.
+    East,
+    /// At the bottom of the marked column.
+    Stack,
+}
+
 /// Defines the various operations that can be performed on windows.
 #[derive(Clone, Debug)]
 pub enum Operation {
     /// Focuses on a window in the specified `Direction`.
     Focus(Direction),
+    /// Focuses the next (`East`) or previous (`West`) column among those on
+    /// screen, wrapping around at the edges of the display.
+    FocusVisible(Direction),
     /// Swaps the current window with another in the specified `Direction`.
     Swap(Direction),
-    /// Centers the currently focused window on the display.
-    Center,
+    /// Focuses the column at the zero-based index of the strip, or its last
+    /// column when the strip is shorter.
+    FocusIndex(usize),
+    /// Moves the focused column to the zero-based index of the strip, or to
+    /// its end when the strip is shorter.
+    MoveIndex(usize),
+    /// Swaps the focused column with the first column of the strip.
+    Promote,
+    /// Swaps the focused column with the last column of the strip.
+    Demote,
+    /// Centers the currently focused window on the display. Floating windows can be
+    /// centered on a specific display, numbered from 0 in arrangement order.
+    Center(Option<usize>),
     /// Resizes the focused window in the given direction.
     Resize(ResizeDirection),
+    /// Moves the edge of the focused window facing `Direction` by the given amount,
+    /// taking the space from (or giving it back to) the adjacent window on that side.
+    GrowEdge(Direction, ResizeAmount),
     /// Toggles the focused window to full width or a preset width.
     FullWidth,
+    /// Zooms the focused window the way macOS does, as its green zoom button
+    /// would without paneru.
+    ZoomNative,
     /// Moves the focused window to the next available display.
     ToNextDisplay(MoveFocus),
     /// Distributes heights equally among windows in the focused stack.
@@ -87,9 +159,22 @@ pub enum Operation {
     Manage,
     /// Stacks or unstacks a window. The boolean indicates whether to stack (`true`) or unstack (`false`).
     Stack(bool),
+    /// Collapses the whole strip into a single stack (`true`), or splits it back
+    /// into the columns it had before (`false`).
+    StackAll(bool),
+    /// Lays the stacked column of the focused window out side by side, or back
+    /// from top to bottom.
+    ToggleSplitOrientation,
+    /// Moves the focused window past the whole neighbouring column in the given direction,
+    /// leaving any stack there intact.
+    Move(Direction),
+    /// Pushes the focused window onto the neighbouring column in the given direction.
+    MoveIntoStack(Direction),
     /// Resizes and repositions the focused window to fit within the visible viewport
     /// (including edge padding).
     Snap,
+    /// Moves the focused window fully onto the display holding most of it.
+    FitDisplay,
     /// Cyclically selects the virtual strip for the current workspace.
     Virtual(Direction),
     /// Selects a virtual strip by its zero-based index for the current workspace.
@@ -109,15 +194,51 @@ pub enum Operation {
     /// Flips `FloatingLayer`, raises the other windows in the new top tier,
     /// and focuses the tier's last-focused window.
     ToggleFloatingLayer,
+    /// Pins the focused column in place on the screen, or unpins it.
+    TogglePin,
+    /// Marks the focused column as the place for the next spawned window.
+    /// Repeating the same placement clears the mark again.
+    SetInsertionPoint(InsertionPlacement),
+    /// Closes the focused window.
+    Close,
+    /// Closes all other windows in the current space, except for those whose
+    /// application asks for a confirmation before closing.
+    CloseOthers,
+    /// Brings the window whose app or title matches the query over from any
+    /// space or display, next to the focused column, and focuses it.
+    Pull(String),
 }
 
 /// Defines operations that can be performed on the mouse.
 #[derive(Clone, Debug)]
 pub enum MouseMove {
-    /// Moves the mouse pointer to the next available display.
+    /// Moves the mouse pointer to the next available display, and focuses the window
+    /// last focused there.
     ToNextDisplay,
 }
 
+/// Selects the macOS space to switch to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpaceTarget {
+    /// The space to the right of the current one.
+    Next,
+    /// The space to the left of the current one.
+    Previous,
+    /// The numbered desktop, starting at 1, in the order of Mission Control.
+    Number(usize),
+}
+
+/// Operations on the native macOS spaces.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpaceOperation {
+    /// Switches to another space.
+    Focus(SpaceTarget),
+    /// Adds a new space to the active display.
+    Create,
+    /// Removes the current space of the active display.
+    Destroy,
+}
+
 /// Represents a command that can be issued to the window manager.
 #[derive(Clone, Debug)]
 pub enum Command {
@@ -125,23 +246,74 @@ pub enum Command {
     Window(Operation),
     /// A command targeting the mouse with a specific `MouseOperation`.
     Mouse(MouseMove),
+    /// A command targeting the native macOS spaces.
+    Space(SpaceOperation),
     /// A command to quit the window manager application.
     Quit,
     /// A command to restart the window manager service.
     Restart,
+    /// A command to re-execute the daemon in place, handing the current layout
+    /// over to the new process.
+    RestartDaemon,
+    /// Switches to a named configuration profile, or back to the base
+    /// configuration when `None`.
+    Profile(Option<String>),
+    /// Toggles freezing the layout of the active strip, so windows can be
+    /// arranged by hand.
+    ToggleLayoutLock,
+    /// Saves the column order, widths and stacks of the active strip in a named slot.
+    LayoutSave(String),
+    /// Arranges the active strip as saved in a named slot.
+    LayoutLoad(String),
+    /// Groups the focused column with the column to its right, so that they are
+    /// swapped and moved between displays as a unit, or dissolves its group (`false`).
+    GroupColumns(bool),
+    /// Types a key combination, like `cmd-w`, into the focused window.
+    SendKeys(String),
+    /// Forces the power saving on or off, or back to following the power
+    /// source and the macOS Low Power Mode (`None`).
+    LowPower(Option<bool>),
+    /// Suspends the window management without quitting, passing the key
+    /// bindings through to the apps unless `true`.
+    Pause(bool),
+    /// Resumes the window management suspended by `Pause`.
+    Resume,
     PrintState,
 }
 
 pub fn register_commands(app: &mut bevy::app::App) {
     query::register_query_commands(app);
+    expect::register_expect_commands(app);
+    properties::register_property_commands(app);
+    slots::register_slot_commands(app);
     app.add_systems(
         PreUpdate,
         (
-            command_quit_handler,
-            command_restart_handler,
+            (
+                command_quit_handler,
+                command_restart_handler,
+                command_restart_daemon_handler,
+                command_space_handler,
+                close_window_handler,
+                insertion_point_handler,
+                pin_column_handler,
+                layout_lock_handler,
+                column_group_handler,
+                move_window_handler,
+                command_focus_visible,
+                column_index_handler,
+                split_orientation_handler,
+                send_keys_handler,
+                pull_window_handler,
+                low_power_handler,
+                stack_all_handler,
+                fit_display_handler,
+                zoom_native_handler,
+            ),
             print_internal_state_handler,
             mouse_to_next_display,
             resize_window,
+            grow_edge_window,
             command_center_window,
             full_width_window,
             to_next_display,
@@ -155,6 +327,7 @@ pub fn register_commands(app: &mut bevy::app::App) {
             command_raise_floating,
             command_toggle_floating_layer,
             command_swap_focus,
+            command_promote_window,
             snap_window,
         ),
     );
@@ -194,8 +367,6 @@ fn get_window_in_direction(
     entity: Entity,
     strip: &LayoutStrip,
 ) -> Option<Entity> {
-    let index = strip.index_of(entity).ok()?;
-
     match direction {
         Direction::West => strip.left_neighbour(entity),
         Direction::East => strip.right_neighbour(entity),
@@ -204,32 +375,77 @@ fn get_window_in_direction(
 
         Direction::Last => strip.last().ok().and_then(|column| column.top()),
 
-        Direction::North => match strip.get(index).ok()? {
-            Column::Single(_) | Column::Tabs(_) | Column::Fullscren(_) => None,
-            Column::Stack(stack) => stack
-                .iter()
-                .enumerate()
-                .find(|(_, item)| item.contains(entity))
-                .and_then(|(index, _)| (index > 0).then(|| stack.get(index - 1)).flatten())
-                .and_then(StackItem::top),
-        },
-
-        Direction::South => match strip.get(index).ok()? {
-            Column::Single(_) | Column::Tabs(_) | Column::Fullscren(_) => None,
-            Column::Stack(stack) => stack
-                .iter()
-                .enumerate()
-                .find(|(_, item)| item.contains(entity))
-                .and_then(|(index, _)| {
-                    (index < stack.len() - 1)
-                        .then(|| stack.get(index + 1))
-                        .flatten()
-                })
-                .and_then(StackItem::top),
-        },
+        Direction::North => strip.above(entity),
+
+        Direction::South => strip.below(entity),
     }
 }
 
+/// Returns the top windows of the columns showing more than a sliver on the
+/// display, from left to right.
+fn visible_columns(
+    strip: &LayoutStrip,
+    windows: &Windows,
+    bounds: IRect,
+    sliver_width: i32,
+) -> Vec<Entity> {
+    strip
+        .all_columns()
+        .into_iter()
+        .filter(|entity| {
+            windows
+                .frame(*entity)
+                .is_some_and(|frame| bounds.intersect(frame).width() > sliver_width)
+        })
+        .collect()
+}
+
+/// Picks the visible column after (`East`) or before (`West`) the one holding
+/// `focused`, wrapping around. Without a visible focused column, the first or
+/// the last visible one is picked.
+fn cycle_visible_columns(
+    direction: &Direction,
+    focused: Entity,
+    strip: &LayoutStrip,
+    visible: &[Entity],
+) -> Option<Entity> {
+    let column = strip.index_of(focused).ok();
+    let position = visible
+        .iter()
+        .position(|entity| strip.index_of(*entity).ok() == column);
+    let count = visible.len();
+    let index = match (direction, position) {
+        (Direction::East, Some(at)) => (at + 1) % count,
+        (Direction::West, Some(at)) => (at + count - 1) % count,
+        (Direction::East, None) => 0,
+        (Direction::West, None) => count.checked_sub(1)?,
+        _ => return None,
+    };
+    visible.get(index).copied()
+}
+
+/// Picks the display beyond the west or east edge of `bounds`: the closest one,
+/// preferring displays which overlap it vertically. Other directions give `None`.
+fn display_in_direction<T>(
+    direction: &Direction,
+    bounds: IRect,
+    displays: impl IntoIterator<Item = (T, IRect)>,
+) -> Option<T> {
+    displays
+        .into_iter()
+        .filter_map(|(display, other)| {
+            let gap = match direction {
+                Direction::West => bounds.min.x - other.max.x,
+                Direction::East => other.min.x - bounds.max.x,
+                _ => return None,
+            };
+            let overlaps = other.min.y < bounds.max.y && other.max.y > bounds.min.y;
+            (gap >= 0).then_some((display, (!overlaps, gap)))
+        })
+        .min_by_key(|(_, rank)| *rank)
+        .map(|(display, _)| display)
+}
+
 /// 45° direction cone, closest by squared Euclidean distance.
 /// `First` / `Last` are strip-only and return `None`.
 fn pick_nearest_in_direction(
@@ -320,6 +536,7 @@ fn command_move_focus(
     workspaces: Query<(&LayoutStrip, Entity, Option<&NativeFullscreenMarker>)>,
     active_display: ActiveDisplay,
     window_manager: Res<WindowManager>,
+    config: Res<Config>,
     mut commands: Commands,
 ) {
     let Some(Operation::Focus(direction)) =
@@ -372,6 +589,25 @@ fn command_move_focus(
         return;
     }
 
+    if config.focus_wrap_visible()
+        && matches!(direction, Direction::West | Direction::East)
+        && active_strip.contains(focused_entity)
+    {
+        let visible = visible_columns(
+            active_strip,
+            &windows,
+            active_display.bounds(),
+            config.sliver_width(),
+        );
+        if let Some(entity) =
+            cycle_visible_columns(direction, focused_entity, active_strip, &visible)
+        {
+            commands.focus_entity(entity, true);
+            commands.reshuffle_around(entity);
+            return;
+        }
+    }
+
     // If focus is on a window that no longer lives in the active strip
     // (e.g. it just became floating, was minimised on another row, or
     // the OS handed focus to a window we don't track on this strip),
@@ -412,8 +648,43 @@ fn command_move_focus(
         return;
     }
 
+    if config.focus_wraps_displays()
+        && let Some(other) = display_in_direction(
+            direction,
+            active_display.bounds(),
+            active_display
+                .other_managed()
+                .map(|display| (display, display.bounds())),
+        )
+    {
+        // Arrive on the column facing the display we came from.
+        let entity = window_manager
+            .active_display_space(other.id())
+            .ok()
+            .and_then(|id| workspaces.iter().find(|(strip, _, _)| strip.id() == id))
+            .and_then(|(strip, _, _)| match direction {
+                Direction::West => strip.last().ok(),
+                _ => strip.first().ok(),
+            })
+            .and_then(|column| column.top());
+        if let Some(entity) = entity {
+            debug!("moving focus west or east to display {}", other.id());
+            if let Some(frame) = windows.frame(entity) {
+                let visible_frame = other.bounds().intersect(frame);
+                if !visible_frame.is_empty() {
+                    window_manager.warp_mouse(visible_frame.center());
+                }
+            }
+            commands.focus_entity(entity, true);
+            commands.reshuffle_around(entity);
+        } else {
+            window_manager.warp_mouse(other.bounds().center());
+        }
+        return;
+    }
+
     // Check if the movement can switch to another display.
-    let Some(other_display) = active_display.other().next() else {
+    let Some(other_display) = active_display.other_managed().next() else {
         return;
     };
     let change_display = match direction {
@@ -429,6 +700,39 @@ fn command_move_focus(
     }
 }
 
+/// Cycles the focus among the columns on screen, so that it never scrolls the
+/// strip further than bringing a partly hidden column into view.
+#[allow(clippy::needless_pass_by_value)]
+fn command_focus_visible(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    active_display: ActiveDisplay,
+    config: Res<Config>,
+    mut commands: Commands,
+) {
+    let Some(Operation::FocusVisible(direction)) =
+        filter_window_operations(&mut messages, |op| matches!(op, Operation::FocusVisible(_)))
+            .next()
+    else {
+        return;
+    };
+    let Some((_, focused_entity)) = windows.focused() else {
+        return;
+    };
+
+    let active_strip = active_display.active_strip();
+    let visible = visible_columns(
+        active_strip,
+        &windows,
+        active_display.bounds(),
+        config.sliver_width(),
+    );
+    if let Some(entity) = cycle_visible_columns(direction, focused_entity, active_strip, &visible) {
+        commands.focus_entity(entity, true);
+        commands.reshuffle_around(entity);
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 fn command_focus_unmanaged(
     mut messages: MessageReader<Event>,
@@ -623,6 +927,16 @@ fn command_swap_focus(
     };
 
     let active_strip = active_display.active_strip();
+    // Grouped columns pass their neighbours as a unit.
+    if let Some((_, current)) = windows.focused()
+        && matches!(direction, Direction::West | Direction::East)
+        && active_strip
+            .move_group(current, matches!(direction, Direction::East))
+            .unwrap_or(false)
+    {
+        commands.ensure_visible(current);
+        return;
+    }
     let mut handler = || {
         let (_, current) = windows.focused()?;
         let index = active_strip.index_of(current).ok()?;
@@ -671,7 +985,7 @@ fn command_swap_focus(
     {
         // Check if the movement can swap to another display.
         let bounds = active_display.bounds();
-        let Some(other_display) = active_display.other().next() else {
+        let Some(other_display) = active_display.other_managed().next() else {
             return;
         };
         let change_display = match direction {
@@ -688,41 +1002,182 @@ fn command_swap_focus(
     }
 }
 
-/// Centers the focused window on the active display.
+/// Swaps the focused column directly with the first (promote) or the last (demote)
+/// column, leaving the columns in between where they are. Focus stays on the
+/// moved window.
+#[instrument(level = Level::DEBUG, skip_all)]
+#[allow(clippy::needless_pass_by_value)]
+fn command_promote_window(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    mut active_display: ActiveDisplayMut,
+    mut commands: Commands,
+) {
+    let Some(operation) = filter_window_operations(&mut messages, |op| {
+        matches!(op, Operation::Promote | Operation::Demote)
+    })
+    .next() else {
+        return;
+    };
+
+    let Some((_, current)) = windows.focused() else {
+        return;
+    };
+    let active_strip = active_display.active_strip();
+    let Ok(index) = active_strip.index_of(current) else {
+        return;
+    };
+    let target = match operation {
+        Operation::Demote => active_strip.len().saturating_sub(1),
+        _ => 0,
+    };
+    if index == target {
+        return;
+    }
+
+    debug!("{operation:?}: swapping column {index} with {target}");
+    active_strip.swap(index, target);
+    commands.ensure_visible(current);
+}
+
+/// Jumps the focus straight to a numbered column of the active strip, or moves
+/// the focused column there. Numbers past the end of the strip pick its last column.
+#[instrument(level = Level::DEBUG, skip_all)]
+#[allow(clippy::needless_pass_by_value)]
+fn column_index_handler(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    mut active_display: ActiveDisplayMut,
+    mut commands: Commands,
+) {
+    let Some(operation) = filter_window_operations(&mut messages, |op| {
+        matches!(op, Operation::FocusIndex(_) | Operation::MoveIndex(_))
+    })
+    .next() else {
+        return;
+    };
+
+    let active_strip = active_display.active_strip();
+    let Some(last) = active_strip.len().checked_sub(1) else {
+        return;
+    };
+    match operation {
+        Operation::FocusIndex(index) => {
+            let Some(entity) = active_strip
+                .get((*index).min(last))
+                .ok()
+                .and_then(|column| column.top())
+            else {
+                return;
+            };
+            commands.focus_entity(entity, true);
+            commands.reshuffle_around(entity);
+        }
+        Operation::MoveIndex(index) => {
+            let Some((_, current)) = windows.focused() else {
+                return;
+            };
+            let Ok(from) = active_strip.index_of(current) else {
+                return;
+            };
+            let to = (*index).min(last);
+            if from == to {
+                return;
+            }
+            debug!("{operation:?}: moving column {from} to {to}");
+            active_strip.move_column(from, to);
+            commands.ensure_visible(current);
+        }
+        _ => (),
+    }
+}
+
+/// Returns the displays ordered by their position in the arrangement, left to right and
+/// top to bottom. This is the order used to number displays in commands.
+fn arranged_displays<'a>(displays: impl IntoIterator<Item = &'a Display>) -> Vec<&'a Display> {
+    let mut displays = displays.into_iter().collect::<Vec<_>>();
+    displays.sort_by_key(|display| (display.bounds().min.x, display.bounds().min.y));
+    displays
+}
+
+/// Centers the focused window. Tiled windows are centered horizontally on the active display
+/// by scrolling their strip. Floating windows are centered on the requested display, or on the
+/// display they mostly cover.
 #[allow(clippy::needless_pass_by_value)]
 fn command_center_window(
     mut messages: MessageReader<Event>,
     windows: Windows,
     active_display: ActiveDisplay,
+    displays: Query<(&Display, Option<&DockPosition>)>,
     window_manager: Res<WindowManager>,
+    config: Res<Config>,
     mut commands: Commands,
 ) {
-    if filter_window_operations(&mut messages, |op| matches!(op, Operation::Center))
-        .next()
-        .is_none()
-    {
+    let Some(Operation::Center(target)) =
+        filter_window_operations(&mut messages, |op| matches!(op, Operation::Center(_))).next()
+    else {
         return;
-    }
+    };
 
-    if let Some((_, entity)) = windows.focused()
-        && let Some(size) = windows.size(entity)
-        && let Some(mut origin) = windows.origin(entity)
-    {
-        let center = active_display.bounds().center().x;
-        origin.x = center - size.x / 2;
+    let Some((_, entity, unmanaged)) = windows
+        .focused()
+        .and_then(|(_, entity)| windows.get_managed(entity))
+    else {
+        return;
+    };
+    let (Some(size), Some(mut origin)) = (windows.size(entity), windows.origin(entity)) else {
+        return;
+    };
 
-        if active_display.active_strip().contains(entity)
-            && let Some(layout_position) = windows.layout_position(entity)
-        {
+    let arranged = arranged_displays(displays.iter().map(|(display, _)| display));
+    let target_display = match target {
+        Some(index) => {
+            let Some(display) = arranged.get(*index) else {
+                debug!("no display number {} to center on.", index + 1);
+                return;
+            };
+            Some(*display)
+        }
+        None => None,
+    };
+
+    if unmanaged.is_none() && active_display.active_strip().contains(entity) {
+        if target_display.is_some_and(|display| display.id() != active_display.id()) {
+            debug!("tiled windows can only be centered on the active display.");
+            return;
+        }
+        origin.x = active_display.bounds().center().x - size.x / 2;
+        if let Some(layout_position) = windows.layout_position(entity) {
             // Directly reposition the strip (bypasses hidden_ratio check).
             let strip_position = origin - layout_position.0;
             commands.reposition_entity(active_display.active_strip_entity(), strip_position);
         } else {
             commands.reposition_entity(entity, origin);
         }
-
         window_manager.warp_mouse(active_display.bounds().center());
+        return;
     }
+
+    let frame = IRect::from_corners(origin, origin + size);
+    let display = target_display
+        .or_else(|| {
+            most_overlapping(frame, arranged.iter().map(|display| display.bounds()))
+                .and_then(|index| arranged.get(index).copied())
+        })
+        .unwrap_or(active_display.display());
+    let dock = displays
+        .iter()
+        .find_map(|(other, dock)| (other.id() == display.id()).then_some(dock))
+        .flatten();
+    let bounds = display.actual_display_bounds(dock, &config);
+    let center = bounds.center();
+    let origin = Origin::new(
+        center.x - size.x / 2,
+        (center.y - size.y / 2).max(bounds.min.y),
+    );
+    debug!("centering floating {entity} on display {}", display.id());
+    commands.reposition_entity(entity, origin);
+    window_manager.warp_mouse(center);
 }
 
 /// Resizes the focused window based on preset column widths.
@@ -738,6 +1193,7 @@ fn command_center_window(
 fn resize_window(
     mut messages: MessageReader<Event>,
     windows: Windows,
+    size_ratios: Query<&SizeRatios>,
     active_display: ActiveDisplay,
     config: Res<Config>,
     mut commands: Commands,
@@ -762,34 +1218,27 @@ fn resize_window(
 
     let viewport = active_display.actual_bounds(&config);
     let current_ratio = f64::from(frame.width()) / f64::from(viewport.width());
-    let widths = config.preset_column_widths();
-    let fallback = *widths.first().unwrap_or(&0.5);
-    let cycle = config.window_resize_cycle();
-    let next_ratio = match direction {
-        ResizeDirection::Grow => widths
-            .iter()
-            .copied()
-            .find(|&r| r > current_ratio + 0.05)
-            .unwrap_or_else(|| {
-                if cycle {
-                    fallback
-                } else {
-                    *widths.last().unwrap_or(&fallback)
-                }
-            }),
-        ResizeDirection::Shrink => widths
-            .iter()
-            .rev()
-            .copied()
-            .find(|&r| r < current_ratio - 0.05)
-            .unwrap_or_else(|| {
-                if cycle {
-                    *widths.last().unwrap_or(&fallback)
-                } else {
-                    fallback
-                }
-            }),
-    };
+    let strip = active_display.active_strip();
+    let visible_columns = strip
+        .all_columns()
+        .into_iter()
+        .filter(|column| {
+            windows
+                .frame(*column)
+                .is_some_and(|frame| frame.min.x < viewport.max.x && frame.max.x > viewport.min.x)
+        })
+        .count();
+    // A window rule's own presets take precedence over the global ones.
+    let widths = size_ratios.get(entity).map_or_else(
+        |_| config.size_ratios(visible_columns),
+        |ratios| ratios.0.clone(),
+    );
+    let next_ratio = next_size_ratio(
+        &widths,
+        current_ratio,
+        matches!(direction, ResizeDirection::Grow),
+        config.window_resize_cycle(),
+    );
 
     let new_width = (next_ratio * f64::from(viewport.width())).round() as i32;
     let size = Size::new(new_width, frame.height());
@@ -799,7 +1248,6 @@ fn resize_window(
     commands.reposition_entity(entity, origin);
 
     // Resize all windows in the column so stacked siblings share the new width.
-    let strip = active_display.active_strip();
     if let Some(Column::Stack(stack)) = strip
         .index_of(entity)
         .ok()
@@ -818,33 +1266,204 @@ fn resize_window(
     commands.reshuffle_around(entity);
 }
 
+/// Limits a step of `grow_edge` so that neither the window nor its neighbour
+/// gets smaller than `MIN_EDGE_SIZE`. A window already below that size does not
+/// turn a step the other way: a grow only grows, and a shrink only shrinks.
+fn clamp_edge_delta(delta: i32, size: i32, neighbour: Option<i32>) -> i32 {
+    const MIN_EDGE_SIZE: i32 = 100;
+
+    let delta = delta.max((MIN_EDGE_SIZE - size).min(0));
+    neighbour.map_or(delta, |neighbour| {
+        delta.min((neighbour - MIN_EDGE_SIZE).max(0))
+    })
+}
+
+/// Grows or shrinks the focused window towards a direction by a fixed step.
+///
+/// Horizontal steps resize the focused column and its neighbour on that side in
+/// opposite directions, so the shared edge moves while the outer edges (and the
+/// rest of the strip) stay put. Vertical steps do the same for windows in a stack.
+/// Without a neighbour only the focused window changes size.
 #[allow(clippy::needless_pass_by_value)]
-fn full_width_window(
+fn grow_edge_window(
     mut messages: MessageReader<Event>,
     windows: Windows,
-    mut active_display: ActiveDisplayMut,
+    active_display: ActiveDisplay,
     config: Res<Config>,
     mut commands: Commands,
 ) {
-    if filter_window_operations(&mut messages, |op| matches!(op, Operation::FullWidth))
-        .next()
-        .is_none()
-    {
+    let Some(Operation::GrowEdge(direction, amount)) =
+        filter_window_operations(&mut messages, |op| matches!(op, Operation::GrowEdge(..))).next()
+    else {
         return;
-    }
+    };
 
     let Some((_, entity)) = windows.focused() else {
         return;
     };
-
+    let strip = active_display.active_strip();
+    let Ok(column) = strip.index_of(entity).and_then(|index| strip.get(index)) else {
+        return;
+    };
     let viewport = active_display.actual_bounds(&config);
+    let neighbour = get_window_in_direction(direction, entity, strip);
+
+    match direction {
+        Direction::West | Direction::East => {
+            let Some(width) = strip.column_width(&column, &|e| windows.frame(e)) else {
+                return;
+            };
+            let neighbour_column = neighbour
+                .and_then(|neighbour| strip.index_of(neighbour).ok())
+                .and_then(|index| strip.get(index).ok());
+            let neighbour_width = neighbour_column
+                .as_ref()
+                .and_then(|column| strip.column_width(column, &|e| windows.frame(e)));
+
+            let delta =
+                clamp_edge_delta(amount.to_pixels(viewport.width()), width, neighbour_width);
+            if delta == 0 {
+                return;
+            }
+
+            let mut resize_column = |column: &Column, width: i32, new_width: i32| {
+                let side_by_side = strip.orientation(column) == ColumnOrientation::Horizontal;
+                for window in column.window_iter() {
+                    if windows.full_width(window).is_some()
+                        && let Ok(mut cmds) = commands.get_entity(window)
+                    {
+                        cmds.try_remove::<FullWidthMarker>();
+                    }
+                    if let Some(size) = windows.size(window) {
+                        // Items side by side keep their share of the column.
+                        let new_width = if side_by_side {
+                            size.x * new_width / width.max(1)
+                        } else {
+                            new_width
+                        };
+                        commands.resize_entity(window, size.with_x(new_width));
+                    }
+                }
+            };
+            resize_column(&column, width, width + delta);
+            if let Some((column, width)) = neighbour_column.zip(neighbour_width) {
+                resize_column(&column, width, width - delta);
+            }
+        }
+        Direction::North | Direction::South => {
+            let Column::Stack(stack) = column else {
+                return;
+            };
+            let item_height =
+                |item: &StackItem| item.top().and_then(|e| windows.size(e)).map(|size| size.y);
+            let Some(item) = stack.iter().find(|item| item.contains(entity)) else {
+                return;
+            };
+            let Some(neighbour_item) =
+                neighbour.and_then(|neighbour| stack.iter().find(|item| item.contains(neighbour)))
+            else {
+                return;
+            };
+            let (Some(height), Some(neighbour_height)) =
+                (item_height(item), item_height(neighbour_item))
+            else {
+                return;
+            };
+
+            let delta = clamp_edge_delta(
+                amount.to_pixels(viewport.height()),
+                height,
+                Some(neighbour_height),
+            );
+            if delta == 0 {
+                return;
+            }
+
+            for (item, new_height) in [
+                (item, height + delta),
+                (neighbour_item, neighbour_height - delta),
+            ] {
+                for window in item.window_iter() {
+                    if let Some(size) = windows.size(window) {
+                        commands.resize_entity(window, size.with_y(new_height));
+                    }
+                }
+            }
+        }
+        Direction::First | Direction::Last => return,
+    }
+
+    commands.ensure_visible(entity);
+}
+
+/// Toggles the focused window between full width and its previous width. A
+/// click on the zoom button of a tiled window does the same for that window,
+/// other windows are zoomed as usual.
+#[allow(clippy::needless_pass_by_value)]
+fn full_width_window(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    mut active_display: ActiveDisplayMut,
+    window_manager: Res<WindowManager>,
+    config: Res<Config>,
+    mut commands: Commands,
+) {
+    for event in messages.read() {
+        let entity = match event {
+            Event::Command {
+                command: Command::Window(Operation::FullWidth),
+            } => windows.focused().map(|(_, entity)| entity),
+            Event::ZoomButtonClicked { point } => {
+                let Some((window, entity)) = window_manager
+                    .find_window_at_point(point)
+                    .ok()
+                    .and_then(|window_id| windows.find(window_id))
+                else {
+                    debug!("no window under the zoom button at {point:?}.");
+                    continue;
+                };
+                if windows
+                    .get_managed(entity)
+                    .is_some_and(|(_, _, unmanaged)| unmanaged.is_none())
+                    && active_display.active_strip().contains(entity)
+                {
+                    Some(entity)
+                } else {
+                    _ = window
+                        .zoom_native()
+                        .inspect_err(|err| warn!("unable to zoom window {}: {err}", window.id()));
+                    None
+                }
+            }
+            _ => continue,
+        };
+        if let Some(entity) = entity {
+            toggle_full_width(
+                entity,
+                &windows,
+                &mut active_display,
+                &config,
+                &mut commands,
+            );
+        }
+    }
+}
+
+fn toggle_full_width(
+    entity: Entity,
+    windows: &Windows,
+    active_display: &mut ActiveDisplayMut,
+    config: &Config,
+    commands: &mut Commands,
+) {
+    let viewport = active_display.actual_bounds(config);
 
     if let Some(marker) = windows.full_width(entity) {
         if let Ok(mut entity_commands) = commands.get_entity(entity) {
             entity_commands.try_remove::<FullWidthMarker>();
         }
         let w = (marker.width_ratio * f64::from(viewport.width())).round() as i32;
-        let bounds = active_display.actual_bounds(&config).size().with_x(w);
+        let bounds = active_display.actual_bounds(config).size().with_x(w);
         commands.resize_entity(entity, bounds);
     } else {
         let strip = active_display.active_strip();
@@ -888,10 +1507,19 @@ fn manage_window(
         return;
     }
 
-    let Some((window, entity, unmanaged)) = windows
-        .focused()
-        .and_then(|(_, entity)| windows.get_managed(entity))
-    else {
+    if let Some((_, entity)) = windows.focused() {
+        toggle_managed(entity, &windows, &mut workspaces, &mut commands);
+    }
+}
+
+/// Turns a tiled window into a floating one, or a floating window back into a tiled one.
+pub(super) fn toggle_managed(
+    entity: Entity,
+    windows: &Windows,
+    workspaces: &mut Query<(&mut LayoutStrip, Has<ActiveWorkspaceMarker>)>,
+    commands: &mut Commands,
+) {
+    let Some((window, entity, unmanaged)) = windows.get_managed(entity) else {
         return;
     };
     debug!(
@@ -900,10 +1528,25 @@ fn manage_window(
         unmanaged.is_some()
     );
     let was_unmanaged = unmanaged.is_some();
+    // Remember the tiled column and width, so toggling back restores them.
+    let previous = workspaces.iter().find_map(|(strip, _)| {
+        strip
+            .index_of(entity)
+            .ok()
+            .map(|index| PreviousManagedStrip {
+                workspace_id: strip.id(),
+                virtual_index: strip.virtual_index,
+                index,
+                width_ratio: windows.width_ratio(entity),
+            })
+    });
     if let Ok(mut entity_commands) = commands.get_entity(entity) {
         if was_unmanaged {
             entity_commands.try_remove::<Unmanaged>();
         } else {
+            if let Some(previous) = previous {
+                entity_commands.try_insert(previous);
+            }
             entity_commands.try_insert(Unmanaged::Floating);
         }
     }
@@ -966,7 +1609,7 @@ fn to_next_display(
         return;
     }
 
-    let Some(other) = active_display.other().next() else {
+    let Some(other) = active_display.other_managed().next() else {
         debug!("no other display to move window to.");
         return;
     };
@@ -987,15 +1630,41 @@ fn to_next_display(
     commands.reposition_entity(entity, dest);
 
     if matches!(move_focus, MoveFocus::Follow) {
-        window_manager.warp_mouse(other.bounds().center());
+        // Land on the moved window itself, so it becomes the one remembered
+        // for the target display.
+        let landed = IRect::from_corners(dest, dest + size).intersect(other.bounds());
+        window_manager.warp_mouse(if landed.is_empty() {
+            other.bounds().center()
+        } else {
+            landed.center()
+        });
+        commands.focus_entity(entity, true);
     }
 
-    // Remove the window from the source strip.
-    let source_neighbour = active_display
-        .active_strip()
-        .left_neighbour(entity)
-        .or_else(|| active_display.active_strip().right_neighbour(entity));
-    active_display.active_strip().remove(entity);
+    // Remove the window from the source strip. A grouped column takes its
+    // whole group along, keeping the columns and stacks as they are.
+    let source = active_display.active_strip();
+    let (source_neighbour, group) = if source.is_grouped(entity) {
+        let Ok((group, index)) = source.take_group(entity) else {
+            return;
+        };
+        let neighbour = source
+            .get(index)
+            .or_else(|_| source.get(index.saturating_sub(1)))
+            .ok()
+            .and_then(|column| column.top());
+        (neighbour, Some(group))
+    } else {
+        let neighbour = source
+            .left_neighbour(entity)
+            .or_else(|| source.right_neighbour(entity));
+        source.remove(entity);
+        (neighbour, None)
+    };
+    let moved = group.as_ref().map_or_else(
+        || vec![entity],
+        |group| group.iter().flat_map(Column::window_iter).collect(),
+    );
     if let Some(neighbour) = source_neighbour {
         commands.reshuffle_around(neighbour);
     }
@@ -1012,12 +1681,14 @@ fn to_next_display(
             .iter_mut()
             .find(|(strip, _)| strip.id() == target_space_id)
     {
-        target_strip.append(entity);
+        match group {
+            Some(group) => target_strip.append_group(group),
+            None => target_strip.append(entity),
+        }
         commands.reshuffle_around(entity);
 
         // Add a delayed refresh of the window size - because the otehr display can have different bounds.
         let display_entity = child.parent();
-        let moved_window = entity;
         let refresh_size = move |windows: Query<&Bounds, With<Window>>,
                                  displays: Query<(&Display, Option<&DockPosition>)>,
                                  mut commands: Commands,
@@ -1026,11 +1697,14 @@ fn to_next_display(
                 .get(display_entity)
                 .ok()
                 .map(|(display, dock)| display.actual_display_bounds(dock, &config));
-            if let Some(viewport_bounds) = viewport
-                && let Ok(Bounds(bounds)) = windows.get(moved_window)
-            {
-                debug!("Refreshing size of window {entity}");
-                commands.resize_entity(moved_window, bounds.with_y(viewport_bounds.height()));
+            let Some(viewport_bounds) = viewport else {
+                return;
+            };
+            for &moved_window in &moved {
+                if let Ok(Bounds(bounds)) = windows.get(moved_window) {
+                    debug!("Refreshing size of window {moved_window}");
+                    commands.resize_entity(moved_window, bounds.with_y(viewport_bounds.height()));
+                }
             }
         };
         let system_id = commands.register_system(refresh_size);
@@ -1046,6 +1720,7 @@ fn mouse_to_next_display(
     windows: Windows,
     layout_strips: Query<(&LayoutStrip, Entity)>,
     displays: Query<&Display>,
+    focus_history: Res<FocusHistory>,
     window_manager: Res<WindowManager>,
     mut commands: Commands,
 ) {
@@ -1078,18 +1753,9 @@ fn mouse_to_next_display(
         return;
     };
 
-    let visible_width = |frame: IRect| other.bounds().intersect(frame).width();
-    let Some((frame, entity)) = other_strip
-        .all_windows()
-        .iter()
-        .filter_map(|entity| windows.frame(*entity).zip(Some(*entity)))
-        .max_by(|left, right| {
-            if visible_width(left.0) < visible_width(right.0) {
-                std::cmp::Ordering::Less
-            } else {
-                std::cmp::Ordering::Greater
-            }
-        })
+    let Some((frame, entity)) =
+        arrival_window(other_strip, other.bounds(), &windows, &focus_history)
+            .and_then(|entity| windows.frame(entity).zip(Some(entity)))
     else {
         debug!("no suitable windows on the other display to move the mouse.");
         window_manager.warp_mouse(other.bounds().center());
@@ -1097,12 +1763,37 @@ fn mouse_to_next_display(
     };
 
     let visible_frame = other.bounds().intersect(frame);
-    debug!("warping mouse to {visible_frame:?}",);
-    window_manager.warp_mouse(visible_frame.center());
+    if visible_frame.is_empty() {
+        window_manager.warp_mouse(other.bounds().center());
+    } else {
+        debug!("warping mouse to {visible_frame:?}",);
+        window_manager.warp_mouse(visible_frame.center());
+    }
 
     commands.focus_entity(entity, true);
 }
 
+/// Picks the window to focus when arriving on a display: the last window focused
+/// in its strip, or the window of the leftmost column visible within `bounds`.
+fn arrival_window(
+    strip: &LayoutStrip,
+    bounds: IRect,
+    windows: &Windows,
+    focus_history: &FocusHistory,
+) -> Option<Entity> {
+    focus_history
+        .last_managed(strip.id())
+        .filter(|entity| strip.contains(*entity))
+        .or_else(|| {
+            strip.all_columns().into_iter().find(|entity| {
+                windows
+                    .frame(*entity)
+                    .is_some_and(|frame| !bounds.intersect(frame).is_empty())
+            })
+        })
+        .or_else(|| strip.first().ok().and_then(|column| column.top()))
+}
+
 /// Distributes heights equally among all windows in the currently focused stack.
 #[allow(clippy::needless_pass_by_value)]
 fn equalize_column(
@@ -1232,6 +1923,270 @@ fn snap_window(
     commands.reposition_entity(active_display.active_strip_entity(), strip_position);
 }
 
+/// Zooms the focused window with its own zoom button, bypassing paneru's full width.
+#[allow(clippy::needless_pass_by_value)]
+fn zoom_native_handler(mut messages: MessageReader<Event>, windows: Windows) {
+    if filter_window_operations(&mut messages, |op| matches!(op, Operation::ZoomNative))
+        .next()
+        .is_none()
+    {
+        return;
+    }
+    if let Some((window, _)) = windows.focused() {
+        _ = window
+            .zoom_native()
+            .inspect_err(|err| warn!("unable to zoom window {}: {err}", window.id()));
+    }
+}
+
+/// Shrinks `frame` to fit within `bounds` where it is larger, and moves it
+/// inside them.
+fn fit_within(frame: IRect, bounds: IRect) -> IRect {
+    let size = frame.size().min(bounds.size());
+    let min = frame.min.clamp(bounds.min, bounds.max - size);
+    IRect::from_corners(min, min + size)
+}
+
+/// Moves a floating window straddling displays fully onto the display holding
+/// most of it, shrinking it if it does not fit there. Tiled windows are brought
+/// into view by their strip instead.
+#[allow(clippy::needless_pass_by_value)]
+fn fit_display_handler(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    active_display: ActiveDisplay,
+    displays: Query<(&Display, Option<&DockPosition>)>,
+    config: Res<Config>,
+    mut commands: Commands,
+) {
+    if filter_window_operations(&mut messages, |op| matches!(op, Operation::FitDisplay))
+        .next()
+        .is_none()
+    {
+        return;
+    }
+    let Some((_, entity, unmanaged)) = windows
+        .focused()
+        .and_then(|(_, entity)| windows.get_managed(entity))
+    else {
+        return;
+    };
+    if unmanaged.is_none() && active_display.active_strip().contains(entity) {
+        commands.reshuffle_around(entity);
+        return;
+    }
+
+    let Some(frame) = windows.frame(entity) else {
+        return;
+    };
+    let Some((display, dock)) =
+        most_overlapping(frame, displays.iter().map(|(display, _)| display.bounds()))
+            .and_then(|index| displays.iter().nth(index))
+    else {
+        return;
+    };
+    let fitted = fit_within(frame, display.actual_display_bounds(dock, &config));
+    if fitted == frame {
+        return;
+    }
+    debug!("fitting {entity} onto display {}: {fitted:?}", display.id());
+    if fitted.size() != frame.size() {
+        commands.resize_entity(entity, fitted.size());
+    }
+    commands.reposition_entity(entity, fitted.min);
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn pin_column_handler(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    pinned: Query<Entity, With<Pinned>>,
+    active_display: ActiveDisplay,
+    config: Res<Config>,
+    mut commands: Commands,
+) {
+    if filter_window_operations(&mut messages, |op| matches!(op, Operation::TogglePin))
+        .next()
+        .is_none()
+    {
+        return;
+    }
+    let Some((_, entity)) = windows.focused() else {
+        return;
+    };
+    let strip = active_display.active_strip();
+    let Ok(column) = strip.index_of(entity).and_then(|index| strip.get(index)) else {
+        return;
+    };
+    let was_pinned = column.window_iter().any(|entity| pinned.contains(entity));
+
+    // A strip has at most one pinned column.
+    for pinned_entity in pinned.iter().filter(|entity| strip.contains(*entity)) {
+        if let Ok(mut entity_commands) = commands.get_entity(pinned_entity) {
+            entity_commands.try_remove::<Pinned>();
+        }
+    }
+    if was_pinned {
+        debug!("unpinned column of {entity}.");
+        return;
+    }
+
+    let Some(frame) = windows.frame(entity) else {
+        return;
+    };
+    let viewport = active_display.actual_bounds(&config);
+    let offset = (frame.min.x - viewport.min.x).clamp(0, (viewport.width() - frame.width()).max(0));
+    debug!("pinned column of {entity} at {offset}.");
+    if let Ok(mut entity_commands) = commands.get_entity(entity) {
+        entity_commands.try_insert(Pinned(offset));
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn layout_lock_handler(
+    mut messages: MessageReader<Event>,
+    active_display: ActiveDisplay,
+    locked: Query<Has<LayoutLocked>>,
+    mut commands: Commands,
+) {
+    let toggles = messages
+        .read()
+        .filter(|event| {
+            matches!(
+                event,
+                Event::Command {
+                    command: Command::ToggleLayoutLock
+                }
+            )
+        })
+        .count();
+    if toggles % 2 == 0 {
+        return;
+    }
+    let strip_entity = active_display.active_strip_entity();
+    let Ok(mut entity_commands) = commands.get_entity(strip_entity) else {
+        return;
+    };
+    if locked.get(strip_entity).unwrap_or_default() {
+        debug!("unlocked layout of {strip_entity}.");
+        entity_commands.try_remove::<LayoutLocked>();
+    } else {
+        debug!("locked layout of {strip_entity}.");
+        entity_commands.try_insert(LayoutLocked);
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn column_group_handler(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    mut active_display: ActiveDisplayMut,
+) {
+    for event in messages.read() {
+        let Event::Command {
+            command: Command::GroupColumns(group),
+        } = event
+        else {
+            continue;
+        };
+        let Some((_, entity)) = windows.focused() else {
+            continue;
+        };
+        let strip = active_display.active_strip();
+        if *group {
+            _ = strip
+                .group_with_next(entity)
+                .inspect_err(|err| debug!("grouping columns: {err}"));
+        } else {
+            strip.ungroup(entity);
+        }
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn insertion_point_handler(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    current: Option<Res<InsertionPoint>>,
+    mut commands: Commands,
+) {
+    let Some(Operation::SetInsertionPoint(placement)) =
+        filter_window_operations(&mut messages, |op| {
+            matches!(op, Operation::SetInsertionPoint(_))
+        })
+        .next()
+    else {
+        return;
+    };
+    let Some((_, entity)) = windows.focused() else {
+        return;
+    };
+
+    let point = InsertionPoint {
+        target: entity,
+        placement: *placement,
+    };
+    if current.is_some_and(|current| *current == point) {
+        debug!("clearing insertion point at {entity}.");
+        commands.remove_resource::<InsertionPoint>();
+    } else {
+        debug!("next window goes {placement:?} of {entity}.");
+        commands.insert_resource(point);
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn close_window_handler(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    apps: Query<&Application>,
+    active_display: ActiveDisplay,
+    window_manager: Res<WindowManager>,
+    config: Res<Config>,
+) {
+    let Some(operation) = filter_window_operations(&mut messages, |op| {
+        matches!(op, Operation::Close | Operation::CloseOthers)
+    })
+    .next() else {
+        return;
+    };
+    let Some((focused, _)) = windows.focused() else {
+        return;
+    };
+
+    if matches!(operation, Operation::Close) {
+        _ = focused
+            .close()
+            .inspect_err(|err| warn!("unable to close window {}: {err}", focused.id()));
+        return;
+    }
+
+    let Ok(window_ids) = window_manager
+        .windows_in_workspace(active_display.active_strip().id())
+        .inspect_err(|err| warn!("unable to list windows in the current space: {err}"))
+    else {
+        return;
+    };
+    for window_id in window_ids {
+        if window_id == focused.id() {
+            continue;
+        }
+        let Some((window, _, app_entity)) = windows.find_parent(window_id) else {
+            continue;
+        };
+        let Ok(app) = apps.get(app_entity) else {
+            continue;
+        };
+        if !WindowProperties::new(app, window, &config).close_with_others() {
+            debug!("not closing window {window_id}, no rule marks it as safe to close.");
+            continue;
+        }
+        _ = window
+            .close()
+            .inspect_err(|err| warn!("unable to close window {window_id}: {err}"));
+    }
+}
+
 #[instrument(level = Level::DEBUG, skip_all)]
 #[allow(clippy::needless_pass_by_value)]
 pub fn stack_windows_handler(
@@ -1273,6 +2228,170 @@ pub fn stack_windows_handler(
     }
 }
 
+/// Collapses the active strip into one stack, keeping its previous arrangement
+/// on the strip, or restores that arrangement again. Without one, unstacking
+/// gives every stacked window a column of its own.
+#[instrument(level = Level::DEBUG, skip_all)]
+#[allow(clippy::needless_pass_by_value)]
+fn stack_all_handler(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    mut active_strip: Single<
+        (Entity, &mut LayoutStrip, Option<&StackedArrangement>),
+        With<ActiveWorkspaceMarker>,
+    >,
+    mut commands: Commands,
+) {
+    let Some(Operation::StackAll(stack)) =
+        filter_window_operations(&mut messages, |op| matches!(op, Operation::StackAll(_))).next()
+    else {
+        return;
+    };
+    let (strip_entity, ref mut strip, arrangement) = *active_strip;
+
+    if *stack {
+        if arrangement.is_some() {
+            // Keep the arrangement from before the strip was first stacked.
+            _ = strip.stack_all();
+        } else if let Some(mut arrangement) = strip.stack_all() {
+            arrangement.widths = strip
+                .all_windows()
+                .into_iter()
+                .filter_map(|entity| windows.size(entity).map(|size| (entity, size.x)))
+                .collect();
+            commands.entity(strip_entity).try_insert(arrangement);
+        }
+    } else if let Some(arrangement) = arrangement.cloned() {
+        for (entity, width) in &arrangement.widths {
+            if strip.contains(*entity)
+                && let Some(size) = windows.size(*entity)
+            {
+                commands.resize_entity(*entity, size.with_x(*width));
+            }
+        }
+        strip.restore_arrangement(arrangement);
+        commands
+            .entity(strip_entity)
+            .try_remove::<StackedArrangement>();
+    } else {
+        strip.unstack_all();
+    }
+
+    let focused = windows
+        .focused()
+        .map(|(_, entity)| entity)
+        .filter(|entity| strip.contains(*entity));
+    if let Some(entity) = focused.or_else(|| strip.all_windows().first().copied()) {
+        commands.reshuffle_around(entity);
+    }
+}
+
+/// Switches the stack of the focused column between top-to-bottom and side by
+/// side. The column keeps its width: side by side the items split it evenly,
+/// and stacked again each of them takes all of it.
+#[instrument(level = Level::DEBUG, skip_all)]
+#[allow(clippy::needless_pass_by_value)]
+fn split_orientation_handler(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    mut active_display: ActiveDisplayMut,
+    mut commands: Commands,
+) {
+    if filter_window_operations(&mut messages, |op| {
+        matches!(op, Operation::ToggleSplitOrientation)
+    })
+    .next()
+    .is_none()
+    {
+        return;
+    }
+    let Some((_, entity)) = windows.focused() else {
+        return;
+    };
+
+    let strip = active_display.active_strip();
+    let Ok(Column::Stack(items)) = strip.index_of(entity).and_then(|index| strip.get(index)) else {
+        return;
+    };
+    let get_window_frame = |entity| windows.frame(entity);
+    let Some(column_width) = strip.column_width(&Column::Stack(items.clone()), &get_window_frame)
+    else {
+        return;
+    };
+    let Ok(orientation) = strip.toggle_orientation(entity) else {
+        return;
+    };
+    debug!("column of {entity} is now {orientation:?}.");
+
+    let count = i32::try_from(items.len()).unwrap_or(i32::MAX);
+    for (index, item) in (0..).zip(&items) {
+        let width = match orientation {
+            ColumnOrientation::Vertical => column_width,
+            // The last item takes what is left over from the division.
+            ColumnOrientation::Horizontal if index == count - 1 => {
+                column_width - column_width / count * (count - 1)
+            }
+            ColumnOrientation::Horizontal => column_width / count,
+        };
+        for entity in item.window_iter() {
+            if let Some(size) = windows.size(entity) {
+                commands.resize_entity(entity, size.with_x(width));
+            }
+        }
+    }
+    commands.reshuffle_around(entity);
+}
+
+#[instrument(level = Level::DEBUG, skip_all)]
+#[allow(clippy::needless_pass_by_value)]
+pub fn move_window_handler(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    mut active_display: ActiveDisplayMut,
+    mut commands: Commands,
+) {
+    let (direction, into_stack) = match filter_window_operations(&mut messages, |op| {
+        matches!(op, Operation::Move(_) | Operation::MoveIntoStack(_))
+    })
+    .next()
+    {
+        Some(Operation::Move(direction)) => (direction, false),
+        Some(Operation::MoveIntoStack(direction)) => (direction, true),
+        _ => return,
+    };
+    let east = match direction {
+        Direction::East => true,
+        Direction::West => false,
+        _ => return,
+    };
+
+    if let Some((_, entity, unmanaged)) = windows
+        .focused()
+        .and_then(|(_, entity)| windows.get_managed(entity))
+        && unmanaged.is_none()
+    {
+        let strip = active_display.active_strip();
+        let moved = if into_stack {
+            strip.move_into_stack(entity, east)
+        } else {
+            strip.move_past(entity, east)
+        };
+        if moved
+            .inspect_err(|err| warn!("moving window: {err}"))
+            .is_err()
+        {
+            return;
+        }
+        if into_stack
+            && windows.full_width(entity).is_some()
+            && let Ok(mut entity_commands) = commands.get_entity(entity)
+        {
+            entity_commands.try_remove::<FullWidthMarker>();
+        }
+        commands.reshuffle_around(entity);
+    }
+}
+
 /// Dispatches a command based on the `CommandTrigger` event.
 /// This function is a Bevy system that reacts to `CommandTrigger` events and executes the corresponding window manager command.
 ///
@@ -1318,6 +2437,219 @@ pub fn command_restart_handler(mut messages: MessageReader<Event>) {
     }
 }
 
+/// Drives the native macOS spaces. The resulting space changes arrive as
+/// regular space events, which keep the strips in sync.
+#[instrument(level = Level::DEBUG, skip_all)]
+#[allow(clippy::needless_pass_by_value)]
+pub fn command_space_handler(
+    mut messages: MessageReader<Event>,
+    window_manager: Res<WindowManager>,
+) {
+    for event in messages.read() {
+        let Event::Command {
+            command: Command::Space(operation),
+        } = event
+        else {
+            continue;
+        };
+        let result = match operation {
+            SpaceOperation::Focus(target) => window_manager.focus_space(*target),
+            SpaceOperation::Create => window_manager.create_space(),
+            SpaceOperation::Destroy => window_manager.destroy_space(),
+        };
+        _ = result.inspect_err(|err| error!("space operation {operation:?}: {err}"));
+    }
+}
+
+/// Forces the power saving on or off, or lets it follow the power source again.
+#[allow(clippy::needless_pass_by_value)]
+fn low_power_handler(
+    mut messages: MessageReader<Event>,
+    low_power_mode: Option<ResMut<LowPowerMode>>,
+    config: Res<Config>,
+) {
+    let Some(mut low_power_mode) = low_power_mode else {
+        return;
+    };
+    for event in messages.read() {
+        if let Event::Command {
+            command: Command::LowPower(forced),
+        } = event
+        {
+            low_power_mode.forced = *forced;
+            info!(
+                "power saving {}{}.",
+                if low_power_mode.active(&config) {
+                    "on"
+                } else {
+                    "off"
+                },
+                if forced.is_none() { ", automatic" } else { "" }
+            );
+        }
+    }
+}
+
+/// Returns `true` if the name or the bundle id of the app contains the
+/// lowercase query of `window pull`.
+fn pull_matches_app(query: &str, app: &Application) -> bool {
+    app.name().to_lowercase().contains(query)
+        || app
+            .bundle_id()
+            .is_some_and(|bundle_id| bundle_id.to_lowercase().contains(query))
+}
+
+/// Moves the window matching a `window pull` query from wherever it is to the
+/// current space, next to the focused column, and focuses it.
+#[instrument(level = Level::DEBUG, skip_all)]
+#[allow(clippy::needless_pass_by_value)]
+fn pull_window_handler(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    apps: Query<&Application>,
+    mut active_display: ActiveDisplayMut,
+    mut other_workspaces: Query<&mut LayoutStrip, Without<ActiveWorkspaceMarker>>,
+    window_manager: Res<WindowManager>,
+    mut commands: Commands,
+) {
+    let Some(Operation::Pull(query)) =
+        filter_window_operations(&mut messages, |op| matches!(op, Operation::Pull(_))).next()
+    else {
+        return;
+    };
+
+    let candidates = windows
+        .iter()
+        .filter_map(|(window, entity)| {
+            let (_, _, parent) = windows.find_parent(window.id())?;
+            Some((window, entity, apps.get(parent).ok()?))
+        })
+        .collect::<Vec<_>>();
+    let lowercase = query.to_lowercase();
+    let first_match = |matches: &dyn Fn(&Window, &Application) -> bool| {
+        candidates
+            .iter()
+            .filter(|(window, _, app)| matches(window, app))
+            .map(|(window, entity, _)| (window.id(), *entity))
+            .min_by_key(|(window_id, _)| *window_id)
+    };
+    // Matching the app ranks above matching the title. Reading a title may ask
+    // the application, so titles are only read when no app matches.
+    let found = first_match(&|_, app| pull_matches_app(&lowercase, app)).or_else(|| {
+        first_match(&|window, _| {
+            window
+                .title()
+                .is_ok_and(|title| title.to_lowercase().contains(&lowercase))
+        })
+    });
+    let Some((window_id, entity)) = found else {
+        info!("no window matching '{query}' to pull.");
+        return;
+    };
+
+    let focused = windows.focused().map(|(_, entity)| entity);
+    let strip = active_display.active_strip();
+    if strip.contains(entity) || focused == Some(entity) {
+        commands.focus_entity(entity, true);
+        return;
+    }
+    if strip.is_fullscreen() {
+        debug!("not pulling window {window_id} into a full screen strip.");
+        return;
+    }
+    let space_id = strip.id();
+
+    let source = other_workspaces
+        .iter_mut()
+        .find(|strip| strip.contains(entity));
+    if source.as_ref().is_none_or(|source| source.id() != space_id)
+        && let Err(err) = window_manager.move_window_to_space(window_id, space_id)
+    {
+        warn!("unable to pull window {window_id} to space {space_id}: {err}");
+        return;
+    }
+    debug!("pulling window {window_id} ({entity}) matching '{query}' to space {space_id}.");
+
+    // Floating windows are only brought over, managed ones join the strip.
+    if let Some(mut source) = source {
+        let neighbour = source
+            .left_neighbour(entity)
+            .or_else(|| source.right_neighbour(entity));
+        source.remove(entity);
+        if let Some(neighbour) = neighbour {
+            commands.reshuffle_around(neighbour);
+        }
+
+        let strip = active_display.active_strip();
+        let index = focused
+            .and_then(|focused| strip.index_of(focused).ok())
+            .map_or(strip.len(), |index| index + 1);
+        strip.insert_at(index, entity);
+        commands.reshuffle_around(entity);
+    }
+    commands.focus_entity(entity, true);
+}
+
+/// Posts the key combination of a `send_keys` command to the focused window.
+#[instrument(level = Level::DEBUG, skip_all)]
+#[allow(clippy::needless_pass_by_value)]
+pub fn send_keys_handler(
+    mut messages: MessageReader<Event>,
+    window_manager: Res<WindowManager>,
+    config: Res<Config>,
+) {
+    for event in messages.read() {
+        let Event::Command {
+            command: Command::SendKeys(keys),
+        } = event
+        else {
+            continue;
+        };
+        let result = config
+            .resolve_key_combination(keys)
+            .and_then(|(keycode, modifiers)| {
+                debug!("sending keys '{keys}': keycode {keycode} modifiers {modifiers:?}");
+                window_manager.send_keys(keycode, modifiers)
+            });
+        _ = result.inspect_err(|err| error!("send keys '{keys}': {err}"));
+    }
+}
+
+/// Saves the current layout for a new daemon process and quits, after which
+/// the daemon re-executes itself and restores the layout on startup.
+#[instrument(level = Level::DEBUG, skip_all)]
+#[allow(clippy::needless_pass_by_value)]
+pub fn command_restart_daemon_handler(
+    mut messages: MessageReader<Event>,
+    workspaces: Query<(Option<&ChildOf>, &LayoutStrip, Has<ActiveWorkspaceMarker>)>,
+    displays: Query<(&Display, Entity, Has<ActiveDisplayMarker>)>,
+    windows: Windows,
+    apps: Query<&Application>,
+    window_manager: Res<WindowManager>,
+) {
+    if !messages.read().any(|event| {
+        matches!(
+            event,
+            Event::Command {
+                command: Command::RestartDaemon
+            }
+        )
+    }) {
+        return;
+    }
+
+    let state = PaneruState::extract(&workspaces, &displays, &windows, &apps);
+    match state.hand_off() {
+        Ok(path) => {
+            info!("handing over state in {}, restarting", path.display());
+            _ = window_manager
+                .quit()
+                .inspect_err(|err| error!("failed to quit: {err}"));
+        }
+        Err(err) => error!("failed to hand over state: {err}"),
+    }
+}
+
 #[instrument(level = Level::DEBUG, skip_all)]
 #[allow(clippy::needless_pass_by_value, clippy::type_complexity)]
 fn print_internal_state_handler(
@@ -1462,6 +2794,69 @@ mod tests {
         assert_eq!(get_window_in_direction(&west, e0, &strip), None);
     }
 
+    #[test]
+    fn test_cycle_visible_columns_wraps() {
+        let (_world, strip, entities) = setup_world_with_layout();
+        let (e0, e1, e2, e3) = (entities[0], entities[1], entities[2], entities[3]);
+        let east = Direction::East;
+        let west = Direction::West;
+        // The stack of e0 and e1 scrolled off screen.
+        let visible = [e2, e3];
+
+        assert_eq!(cycle_visible_columns(&east, e2, &strip, &visible), Some(e3));
+        assert_eq!(cycle_visible_columns(&east, e3, &strip, &visible), Some(e2));
+        assert_eq!(cycle_visible_columns(&west, e2, &strip, &visible), Some(e3));
+        // From a hidden column, enter the visible ones from the matching side.
+        assert_eq!(cycle_visible_columns(&east, e1, &strip, &visible), Some(e2));
+        assert_eq!(cycle_visible_columns(&west, e0, &strip, &visible), Some(e3));
+        assert_eq!(cycle_visible_columns(&east, e2, &strip, &[]), None);
+        // A stacked window counts as its column.
+        assert_eq!(
+            cycle_visible_columns(&east, e1, &strip, &[e0, e2]),
+            Some(e2)
+        );
+    }
+
+    #[test]
+    fn test_clamp_edge_delta() {
+        assert_eq!(clamp_edge_delta(50, 400, Some(400)), 50);
+        assert_eq!(clamp_edge_delta(50, 400, Some(120)), 20);
+        assert_eq!(clamp_edge_delta(-350, 400, Some(400)), -300);
+        // A neighbour narrower than the minimum stops a grow, and never reverses it.
+        assert_eq!(clamp_edge_delta(50, 400, Some(80)), 0);
+        assert_eq!(clamp_edge_delta(-50, 80, Some(400)), 0);
+        assert_eq!(clamp_edge_delta(50, 400, None), 50);
+    }
+
+    #[test]
+    fn test_display_in_direction() {
+        let main = IRect::new(0, 0, 1000, 800);
+        let displays = [
+            ("left", IRect::new(-1200, 0, 0, 900)),
+            ("far_left", IRect::new(-2400, 0, -1200, 900)),
+            ("above_left", IRect::new(-500, -1000, -100, -800)),
+            ("right", IRect::new(1000, 100, 2000, 700)),
+        ];
+
+        assert_eq!(
+            display_in_direction(&Direction::West, main, displays),
+            Some("left")
+        );
+        assert_eq!(
+            display_in_direction(&Direction::East, main, displays),
+            Some("right")
+        );
+        // A display off to the side counts when it is the only one there.
+        assert_eq!(
+            display_in_direction(&Direction::West, main, [displays[2]]),
+            Some("above_left")
+        );
+        assert_eq!(
+            display_in_direction(&Direction::North, main, displays),
+            None
+        );
+    }
+
     #[test]
     fn test_get_window_in_direction_stacked() {
         let (_world, strip, entities) = setup_world_with_layout();
@@ -1520,6 +2915,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn fit_within_moves_and_shrinks() {
+        let bounds = IRect::new(0, 25, 1000, 800);
+        let straddling = IRect::new(800, 100, 1200, 500);
+        assert_eq!(
+            fit_within(straddling, bounds),
+            IRect::new(600, 100, 1000, 500)
+        );
+        let inside = IRect::new(100, 100, 300, 300);
+        assert_eq!(fit_within(inside, bounds), inside);
+        let oversized = IRect::new(-100, 0, 1500, 500);
+        assert_eq!(fit_within(oversized, bounds), IRect::new(0, 25, 1000, 525));
+    }
+
+    #[test]
+    fn most_overlapping_picks_largest_area() {
+        let left = IRect::new(0, 0, 1000, 800);
+        let right = IRect::new(1000, 0, 2000, 800);
+        let frame = IRect::new(900, 100, 1300, 500);
+        assert_eq!(most_overlapping(frame, [left, right]), Some(1));
+
+        let offscreen = IRect::new(-500, -500, -100, -100);
+        assert_eq!(most_overlapping(offscreen, [left, right]), None);
+    }
+
     #[test]
     fn pick_nearest_in_direction_east_picks_closer() {
         let mut world = World::new();
@@ -0,0 +1,302 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+use bevy::app::{App, PostUpdate, PreUpdate};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::hierarchy::ChildOf;
+use bevy::ecs::message::MessageReader;
+use bevy::ecs::query::Added;
+use bevy::ecs::resource::Resource;
+use bevy::ecs::system::{Commands, Local, Query, Res, ResMut};
+use bevy::time::Time;
+use regex::Regex;
+use stdext::function_name;
+use tracing::debug;
+
+use super::Command;
+use super::properties::{PropertyRequest, PropertyValue, WindowTarget, parse_property_request};
+use crate::config::parse_command;
+use crate::ecs::{SendMessageTrigger, SpawnCommandsExt};
+use crate::errors::{Error, Result};
+use crate::events::Event;
+use crate::manager::{Application, Window};
+
+/// Something done to the expected window once it opens.
+#[derive(Clone, Debug)]
+pub enum ExpectAction {
+    /// A command, executed once the new window has been focused.
+    Command(Command),
+    /// A property of the new window, as set by `paneru window <id> set`.
+    Property(PropertyValue),
+}
+
+/// A one-shot rule registered by `paneru expect`. It applies its actions to the
+/// next window matching it and is then discarded.
+#[derive(Clone, Debug)]
+pub struct Expectation {
+    bundle_id: Option<String>,
+    title: Regex,
+    actions: Vec<ExpectAction>,
+}
+
+impl Expectation {
+    fn matches(&self, title: &str, bundle_id: &str) -> bool {
+        self.bundle_id.as_deref().is_none_or(|id| id == bundle_id) && self.title.is_match(title)
+    }
+}
+
+/// How long an expectation waits for its window, and its commands for the
+/// window to be focused.
+const EXPECTATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Expectations waiting for their window, oldest first, with the time they expire.
+#[derive(Default, Resource)]
+pub struct PendingExpectations(Vec<(Duration, Expectation)>);
+
+/// Parses `expect [--bundle <id>] [--title <regex>] --then <actions>`.
+/// Returns `None` for other requests.
+pub fn parse_expect_request(argv: &[&str]) -> Option<Result<Expectation>> {
+    let ["expect", rest @ ..] = argv else {
+        return None;
+    };
+    let invalid = |what: String| Error::InvalidInput(format!("{}: {what}", function_name!()));
+
+    let (mut bundle_id, mut title, mut then) = (None, None, None);
+    let mut args = rest.iter();
+    while let Some(flag) = args.next() {
+        let slot = match *flag {
+            "--bundle" => &mut bundle_id,
+            "--title" => &mut title,
+            "--then" => &mut then,
+            flag => return Some(Err(invalid(format!("unknown argument '{flag}'")))),
+        };
+        let Some(value) = args.next() else {
+            return Some(Err(invalid(format!("missing value for '{flag}'"))));
+        };
+        *slot = Some(value.to_string());
+    }
+
+    let expectation = move || -> Result<Expectation> {
+        let title = Regex::new(title.as_deref().unwrap_or(".*"))
+            .map_err(|err| invalid(format!("invalid title: {err}")))?;
+        let actions = then
+            .as_deref()
+            .ok_or_else(|| invalid("missing '--then'".to_string()))?
+            .split(';')
+            .map(str::trim)
+            .filter(|action| !action.is_empty())
+            .map(parse_action)
+            .collect::<Result<Vec<_>>>()?;
+        if actions.is_empty() {
+            return Err(invalid("no actions in '--then'".to_string()));
+        }
+        Ok(Expectation {
+            bundle_id,
+            title,
+            actions,
+        })
+    };
+    Some(expectation())
+}
+
+/// Parses one action, written like a binding name with an optional value:
+/// `window_center` or `window_set_index 0`.
+fn parse_action(action: &str) -> Result<ExpectAction> {
+    let mut words = action.split_whitespace();
+    let mut argv: Vec<&str> = words.next().unwrap_or_default().split('_').collect();
+    argv.extend(words);
+
+    if let ["window", "set", property @ ..] = argv.as_slice() {
+        let request = [&["window", "focused", "set"][..], property].concat();
+        return match parse_property_request(&request) {
+            Some(Ok((_, PropertyRequest::Set(value)))) => Ok(ExpectAction::Property(value)),
+            Some(Err(err)) => Err(err),
+            _ => Err(Error::InvalidInput(format!(
+                "{}: invalid action '{action}'",
+                function_name!()
+            ))),
+        };
+    }
+    parse_command(&argv).map(ExpectAction::Command)
+}
+
+pub(super) fn register_expect_commands(app: &mut App) {
+    app.init_resource::<PendingExpectations>()
+        .add_systems(PreUpdate, expect_handler)
+        // After Update, where the titles of renamed windows are refreshed.
+        .add_systems(PostUpdate, apply_expectations);
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn expect_handler(
+    mut messages: MessageReader<Event>,
+    time: Res<Time>,
+    mut pending: ResMut<PendingExpectations>,
+) {
+    let now = time.elapsed();
+    pending.0.retain(|(expires, expectation)| {
+        let alive = *expires > now;
+        if !alive {
+            debug!("expectation expired: {expectation:?}");
+        }
+        alive
+    });
+    for event in messages.read() {
+        if let Event::ExpectWindow { expectation } = event {
+            debug!("expecting window: {expectation:?}");
+            pending
+                .0
+                .push((now + EXPECTATION_TIMEOUT, expectation.clone()));
+        }
+    }
+}
+
+/// Consumes the first expectation matching each new window. Windows opened
+/// while expectations are pending are checked again when their title changes,
+/// as apps often name a window only after it was created. Properties are set
+/// right away, while commands wait until the window is confirmed to be focused,
+/// so they are not executed on the previously focused one.
+#[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
+fn apply_expectations(
+    mut messages: MessageReader<Event>,
+    added: Query<Entity, Added<Window>>,
+    windows: Query<(&Window, &ChildOf)>,
+    apps: Query<&Application>,
+    time: Res<Time>,
+    mut pending: ResMut<PendingExpectations>,
+    mut unmatched: Local<HashSet<Entity>>,
+    mut awaiting_focus: Local<HashMap<Entity, (Duration, Vec<Command>)>>,
+    mut commands: Commands,
+) {
+    let now = time.elapsed();
+    let (mut retitled, mut focused) = (vec![], vec![]);
+    for event in messages.read() {
+        match event {
+            Event::WindowTitleChanged { window_id } => retitled.push(*window_id),
+            Event::WindowFocused { window_id } => focused.push(*window_id),
+            _ => (),
+        }
+    }
+
+    if pending.0.is_empty() {
+        unmatched.clear();
+    } else {
+        unmatched.extend(&added);
+        let candidates = unmatched
+            .iter()
+            .copied()
+            .filter(|entity| {
+                added.contains(*entity)
+                    || windows
+                        .get(*entity)
+                        .is_ok_and(|(window, _)| retitled.contains(&window.id()))
+            })
+            .collect::<Vec<_>>();
+        for entity in candidates {
+            let Ok((window, childof)) = windows.get(entity) else {
+                unmatched.remove(&entity);
+                continue;
+            };
+            let bundle_id = apps
+                .get(childof.parent())
+                .ok()
+                .and_then(|app| app.bundle_id())
+                .unwrap_or_default();
+            let title = window.title().unwrap_or_default();
+            let Some(index) = pending
+                .0
+                .iter()
+                .position(|(_, expectation)| expectation.matches(&title, &bundle_id))
+            else {
+                continue;
+            };
+            let (_, expectation) = pending.0.remove(index);
+            unmatched.remove(&entity);
+            debug!("window {} fulfils {expectation:?}", window.id());
+
+            let mut expected_commands = vec![];
+            for action in expectation.actions {
+                match action {
+                    ExpectAction::Command(command) => expected_commands.push(command),
+                    ExpectAction::Property(value) => {
+                        // Nobody waits for the outcome of the request.
+                        let (respond_to, _) = channel();
+                        commands.trigger(SendMessageTrigger(Event::WindowPropertyRequest {
+                            target: WindowTarget::Id(window.id()),
+                            request: PropertyRequest::Set(value),
+                            respond_to,
+                        }));
+                    }
+                }
+            }
+            if !expected_commands.is_empty() {
+                // Raised as well, the window has just opened.
+                commands.focus_entity(entity, true);
+                awaiting_focus.insert(entity, (now + EXPECTATION_TIMEOUT, expected_commands));
+            }
+        }
+    }
+
+    awaiting_focus.retain(|entity, (expires, expected_commands)| {
+        let Ok((window, _)) = windows.get(*entity) else {
+            return false;
+        };
+        if focused.contains(&window.id()) {
+            debug!(
+                "window {} focused, running {expected_commands:?}",
+                window.id()
+            );
+            for command in expected_commands.drain(..) {
+                commands.trigger(SendMessageTrigger(Event::Command { command }));
+            }
+            return false;
+        }
+        *expires > now
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_expect_request() {
+        assert!(parse_expect_request(&["window", "center"]).is_none());
+
+        let expectation = parse_expect_request(&[
+            "expect",
+            "--bundle",
+            "com.foo",
+            "--title",
+            "^Report",
+            "--then",
+            "window_set_index 0; window_center",
+        ])
+        .unwrap()
+        .unwrap();
+        assert!(expectation.matches("Report 1", "com.foo"));
+        assert!(!expectation.matches("Report 1", "com.bar"));
+        assert!(!expectation.matches("Old Report", "com.foo"));
+        assert!(matches!(
+            expectation.actions.as_slice(),
+            [
+                ExpectAction::Property(PropertyValue::Index(0)),
+                ExpectAction::Command(_)
+            ]
+        ));
+
+        for argv in [
+            &["expect", "--title", "x"][..],
+            &["expect", "--then", "window_set_colour red"],
+            &["expect", "--then", "window_fly"],
+            &["expect", "--title", "(", "--then", "window_center"],
+            &["expect", "--bundle"],
+        ] {
+            assert!(
+                matches!(parse_expect_request(argv), Some(Err(_))),
+                "{argv:?} should be rejected"
+            );
+        }
+    }
+}
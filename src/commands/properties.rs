@@ -0,0 +1,286 @@
+use bevy::app::{App, PreUpdate};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::message::MessageReader;
+use bevy::ecs::query::{Has, With};
+use bevy::ecs::system::{Commands, Query, Res};
+use stdext::function_name;
+
+use super::toggle_managed;
+use crate::config::Config;
+use crate::ecs::layout::{Column, LayoutStrip, StackItem};
+use crate::ecs::params::Windows;
+use crate::ecs::{
+    ActiveDisplayMarker, ActiveWorkspaceMarker, DockPosition, SpawnCommandsExt, Unmanaged,
+};
+use crate::errors::{Error, Result};
+use crate::events::Event;
+use crate::manager::Display;
+use crate::platform::WinID;
+
+/// The window addressed by `paneru window <target> get|set`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindowTarget {
+    Focused,
+    Id(WinID),
+}
+
+/// A property of a window which scripts can read.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindowProperty {
+    /// Width as a ratio of the display width.
+    Width,
+    /// Position and size on the screen.
+    Frame,
+    /// Whether the window floats above the strip.
+    Floating,
+    /// Position of the window's column in the strip, from 0.
+    Index,
+}
+
+/// A new value for a window property.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PropertyValue {
+    Width(f64),
+    Floating(bool),
+    Index(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PropertyRequest {
+    Get(WindowProperty),
+    Set(PropertyValue),
+}
+
+/// Parses `window <id|focused> get <property>` and `window <id|focused> set <property> <value>`.
+/// Returns `None` for other requests, so that they can be parsed as commands.
+pub fn parse_property_request(argv: &[&str]) -> Option<Result<(WindowTarget, PropertyRequest)>> {
+    let ["window", target, verb @ ("get" | "set"), rest @ ..] = argv else {
+        return None;
+    };
+    let invalid = |what: String| Error::InvalidInput(format!("{}: {what}", function_name!()));
+    let target = match *target {
+        "focused" => Ok(WindowTarget::Focused),
+        id => id
+            .parse()
+            .map(WindowTarget::Id)
+            .map_err(|_| invalid(format!("invalid window '{id}'"))),
+    };
+    let request = match (*verb, rest) {
+        ("get", [property]) => match *property {
+            "width" => Ok(WindowProperty::Width),
+            "frame" => Ok(WindowProperty::Frame),
+            "floating" => Ok(WindowProperty::Floating),
+            "index" => Ok(WindowProperty::Index),
+            property => Err(invalid(format!("unknown property '{property}'"))),
+        }
+        .map(PropertyRequest::Get),
+        ("set", [property, value]) => match *property {
+            "width" => value
+                .parse()
+                .ok()
+                .filter(|ratio| *ratio > 0.0 && *ratio <= 1.0)
+                .map(PropertyValue::Width)
+                .ok_or_else(|| invalid(format!("width '{value}' is not a ratio of 0.0-1.0"))),
+            "floating" => value
+                .parse()
+                .map(PropertyValue::Floating)
+                .map_err(|_| invalid(format!("floating '{value}' is not true or false"))),
+            "index" => value
+                .parse()
+                .map(PropertyValue::Index)
+                .map_err(|_| invalid(format!("invalid index '{value}'"))),
+            property => Err(invalid(format!("property '{property}' can not be set"))),
+        }
+        .map(PropertyRequest::Set),
+        _ => Err(invalid(
+            "expected 'get <property>' or 'set <property> <value>'".to_string(),
+        )),
+    };
+    Some(target.and_then(|target| request.map(|request| (target, request))))
+}
+
+pub(super) fn register_property_commands(app: &mut App) {
+    app.add_systems(PreUpdate, window_property_handler);
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn window_property_handler(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    mut workspaces: Query<(&mut LayoutStrip, Has<ActiveWorkspaceMarker>)>,
+    active_display: Query<(&Display, Option<&DockPosition>), With<ActiveDisplayMarker>>,
+    config: Res<Config>,
+    mut commands: Commands,
+) {
+    for event in messages.read() {
+        let Event::WindowPropertyRequest {
+            target,
+            request,
+            respond_to,
+        } = event
+        else {
+            continue;
+        };
+        let entity = match target {
+            WindowTarget::Focused => windows.focused().map(|(_, entity)| entity),
+            WindowTarget::Id(window_id) => windows.find(*window_id).map(|(_, entity)| entity),
+        };
+        let Some(entity) = entity else {
+            _ = respond_to.send(Err(Error::NotFound(format!("no window {target:?}"))));
+            continue;
+        };
+        let viewport = active_display
+            .single()
+            .map(|(display, dock)| display.actual_display_bounds(dock, &config))
+            .unwrap_or_default();
+
+        let response = match request {
+            PropertyRequest::Get(property) => {
+                get_property(entity, *property, viewport.width(), &windows, &workspaces)
+            }
+            PropertyRequest::Set(value) => set_property(
+                entity,
+                *value,
+                viewport.width(),
+                &windows,
+                &mut workspaces,
+                &mut commands,
+            )
+            .map(|()| String::new()),
+        };
+        _ = respond_to.send(response);
+    }
+}
+
+fn is_floating(entity: Entity, windows: &Windows) -> bool {
+    windows
+        .get_managed(entity)
+        .is_some_and(|(_, _, unmanaged)| matches!(unmanaged, Some(Unmanaged::Floating)))
+}
+
+fn get_property(
+    entity: Entity,
+    property: WindowProperty,
+    viewport_width: i32,
+    windows: &Windows,
+    workspaces: &Query<(&mut LayoutStrip, Has<ActiveWorkspaceMarker>)>,
+) -> Result<String> {
+    let not_found = || Error::NotFound(format!("{}: window {entity}", function_name!()));
+    let value = match property {
+        WindowProperty::Width => {
+            let frame = windows.frame(entity).ok_or_else(not_found)?;
+            format!(
+                "{:.3}",
+                f64::from(frame.width()) / f64::from(viewport_width.max(1))
+            )
+        }
+        WindowProperty::Frame => {
+            let frame = windows.frame(entity).ok_or_else(not_found)?;
+            format!(
+                "{},{} {}x{}",
+                frame.min.x,
+                frame.min.y,
+                frame.width(),
+                frame.height()
+            )
+        }
+        WindowProperty::Floating => is_floating(entity, windows).to_string(),
+        WindowProperty::Index => workspaces
+            .iter()
+            .find_map(|(strip, _)| strip.index_of(entity).ok())
+            .ok_or_else(|| Error::NotFound(format!("window {entity} is not tiled")))?
+            .to_string(),
+    };
+    Ok(value + "\n")
+}
+
+fn set_property(
+    entity: Entity,
+    value: PropertyValue,
+    viewport_width: i32,
+    windows: &Windows,
+    workspaces: &mut Query<(&mut LayoutStrip, Has<ActiveWorkspaceMarker>)>,
+    commands: &mut Commands,
+) -> Result<()> {
+    match value {
+        PropertyValue::Width(ratio) => {
+            let width = (ratio * f64::from(viewport_width)).round() as i32;
+            // Stacked windows share the width of their column.
+            let column = workspaces.iter().find_map(|(strip, _)| {
+                strip
+                    .index_of(entity)
+                    .ok()
+                    .and_then(|index| strip.get(index).ok())
+            });
+            let members = match column {
+                Some(Column::Stack(stack)) => {
+                    stack.iter().flat_map(StackItem::window_iter).collect()
+                }
+                _ => vec![entity],
+            };
+            for member in members {
+                if let Some(size) = windows.size(member) {
+                    commands.resize_entity(member, size.with_x(width));
+                }
+            }
+            commands.reshuffle_around(entity);
+        }
+        PropertyValue::Floating(floating) => {
+            if floating != is_floating(entity, windows) {
+                toggle_managed(entity, windows, workspaces, commands);
+            }
+        }
+        PropertyValue::Index(index) => {
+            // The index is the one of the column, so a stacked window takes its
+            // whole stack along.
+            let mut strip = workspaces
+                .iter_mut()
+                .find_map(|(strip, _)| strip.contains(entity).then_some(strip))
+                .ok_or_else(|| Error::NotFound(format!("window {entity} is not tiled")))?;
+            let from = strip.index_of(entity)?;
+            let to = index.min(strip.len().saturating_sub(1));
+            strip.move_column(from, to);
+            commands.reshuffle_around(entity);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_property_request() {
+        assert!(parse_property_request(&["window", "focus", "west"]).is_none());
+        assert!(matches!(
+            parse_property_request(&["window", "focused", "get", "width"]),
+            Some(Ok((
+                WindowTarget::Focused,
+                PropertyRequest::Get(WindowProperty::Width)
+            )))
+        ));
+        assert!(matches!(
+            parse_property_request(&["window", "42", "set", "width", "0.5"]),
+            Some(Ok((WindowTarget::Id(42), PropertyRequest::Set(PropertyValue::Width(ratio)))))
+                if (ratio - 0.5).abs() < f64::EPSILON
+        ));
+        assert!(matches!(
+            parse_property_request(&["window", "focused", "set", "floating", "true"]),
+            Some(Ok((_, PropertyRequest::Set(PropertyValue::Floating(true)))))
+        ));
+
+        for argv in [
+            &["window", "focused", "get", "colour"][..],
+            &["window", "focused", "set", "width", "2"],
+            &["window", "focused", "set", "frame", "0"],
+            &["window", "nope", "get", "index"],
+            &["window", "focused", "get"],
+        ] {
+            assert!(
+                matches!(parse_property_request(argv), Some(Err(_))),
+                "{argv:?} should be rejected"
+            );
+        }
+    }
+}
@@ -5,20 +5,27 @@ use bevy::ecs::message::MessageReader;
 use bevy::ecs::query::{Added, Has};
 use bevy::ecs::resource::Resource;
 use bevy::ecs::schedule::IntoScheduleConfigs;
-use bevy::ecs::system::{Query, Res, ResMut};
+use bevy::ecs::system::{Commands, Query, Res, ResMut};
 use serde_json::{Value, json};
 use std::collections::{BTreeMap, BTreeSet};
 use std::io::Write;
 use std::os::unix::net::UnixStream;
+use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
 
 use super::{Command, Operation};
+use crate::config::Config;
 use crate::ecs::layout::LayoutStrip;
 use crate::ecs::params::Windows;
-use crate::ecs::state::{PaneruActiveState, PaneruQueryState, PaneruVirtualWorkspaceState};
-use crate::ecs::{ActiveDisplayMarker, ActiveWorkspaceMarker, FocusedMarker};
+use crate::ecs::state::{
+    PaneruActiveState, PaneruQueryState, PaneruStripState, PaneruVirtualWorkspaceState,
+    PaneruWindowStats, StateQueryKind,
+};
+use crate::ecs::{
+    ActiveDisplayMarker, ActiveWorkspaceMarker, FocusedMarker, InsertionPoint, SpawnCommandsExt,
+};
 use crate::events::Event;
-use crate::manager::{Application, Display};
+use crate::manager::{Application, Display, WindowManager};
 use crate::platform::WinID;
 
 #[derive(Default, Resource)]
@@ -26,6 +33,12 @@ struct StateSubscribers {
     streams: Vec<Arc<Mutex<UnixStream>>>,
 }
 
+/// Socket clients of `paneru inspect`, waiting for the click which picks a window.
+#[derive(Default, Resource)]
+struct PendingInspections {
+    requests: Vec<Sender<String>>,
+}
+
 #[derive(Default, Resource)]
 struct StateBroadcastCache {
     workspace: Option<WorkspaceBroadcastSnapshot>,
@@ -85,6 +98,7 @@ struct StateBroadcastIntent {
     title_changes: BTreeSet<WinID>,
     display_changes: Vec<Option<u32>>,
     active_display_changed: bool,
+    bindings_reloaded: Option<usize>,
 }
 
 impl StateBroadcastIntent {
@@ -122,7 +136,8 @@ impl StateBroadcastIntent {
                 | Event::DisplayRemoved { display_id }
                 | Event::DisplayMoved { display_id }
                 | Event::DisplayResized { display_id }
-                | Event::DisplayConfigured { display_id } => {
+                | Event::DisplayConfigured { display_id }
+                | Event::MainDisplayChanged { display_id } => {
                     let display_id = Some(*display_id);
                     if !intent.display_changes.contains(&display_id) {
                         intent.display_changes.push(display_id);
@@ -131,6 +146,7 @@ impl StateBroadcastIntent {
                 Event::DisplayChanged => {
                     intent.active_display_changed = true;
                 }
+                Event::BindingsReloaded { count } => intent.bindings_reloaded = Some(*count),
                 _ => {}
             }
         }
@@ -146,7 +162,10 @@ impl StateBroadcastIntent {
     }
 
     fn is_empty(&self) -> bool {
-        !self.requires_state() && self.title_changes.is_empty() && self.display_changes.is_empty()
+        !self.requires_state()
+            && self.title_changes.is_empty()
+            && self.display_changes.is_empty()
+            && self.bindings_reloaded.is_none()
     }
 }
 
@@ -157,7 +176,16 @@ pub(super) fn register_query_commands(app: &mut App) {
 
     app.init_resource::<StateSubscribers>();
     app.init_resource::<StateBroadcastCache>();
-    app.add_systems(PreUpdate, (state_subscribe_handler, state_query_handler));
+    app.init_resource::<PendingInspections>();
+    app.add_systems(
+        PreUpdate,
+        (
+            state_subscribe_handler,
+            state_query_handler,
+            inspect_request_handler,
+            inspect_click_handler,
+        ),
+    );
     app.add_systems(
         PostUpdate,
         state_event_broadcast_handler.run_if(active_subscribers),
@@ -171,16 +199,35 @@ fn state_query_handler(
     displays: Query<(&Display, Entity, Has<ActiveDisplayMarker>)>,
     windows: Windows,
     apps: Query<&Application>,
+    insertion_point: Option<Res<InsertionPoint>>,
+    config: Res<Config>,
 ) {
     for event in messages.read() {
         let Event::StateQuery { kind, respond_to } = event else {
             continue;
         };
 
-        let state = PaneruQueryState::extract(&workspaces, &displays, &windows, &apps);
-        let response = state
-            .to_query_json(*kind)
-            .unwrap_or_else(|err| json!({ "error": err.to_string() }).to_string());
+        let response = match kind {
+            StateQueryKind::Strip => serde_json::to_string(&PaneruStripState::extract(
+                &workspaces,
+                &displays,
+                &windows,
+                &apps,
+                insertion_point.as_deref(),
+            ))
+            .map_err(|err| err.to_string()),
+            StateQueryKind::Windows => {
+                serde_json::to_string(&PaneruWindowStats::extract(&windows, &apps))
+                    .map_err(|err| err.to_string())
+            }
+            StateQueryKind::Keybindings => {
+                serde_json::to_string(&config.keybindings_report()).map_err(|err| err.to_string())
+            }
+            kind => PaneruQueryState::extract(&workspaces, &displays, &windows, &apps)
+                .to_query_json(*kind)
+                .map_err(|err| err.to_string()),
+        };
+        let response = response.unwrap_or_else(|err| json!({ "error": err }).to_string());
         _ = respond_to.send(response);
     }
 }
@@ -198,6 +245,92 @@ fn state_subscribe_handler(
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+fn inspect_request_handler(
+    mut messages: MessageReader<Event>,
+    mut pending: ResMut<PendingInspections>,
+    mut commands: Commands,
+) {
+    const INSPECT_MESSAGE_DURATION: f32 = 2.0;
+    for event in messages.read() {
+        let Event::InspectRequest { respond_to } = event else {
+            continue;
+        };
+        pending.requests.push(respond_to.clone());
+        crate::platform::input::set_inspecting(true);
+        commands.flash_message(
+            "Click a window to inspect it".to_string(),
+            INSPECT_MESSAGE_DURATION,
+        );
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn inspect_click_handler(
+    mut messages: MessageReader<Event>,
+    mut pending: ResMut<PendingInspections>,
+    windows: Windows,
+    apps: Query<&Application>,
+    window_manager: Res<WindowManager>,
+    config: Res<Config>,
+) {
+    for event in messages.read() {
+        let Event::InspectClick { point } = event else {
+            continue;
+        };
+        let report = match window_manager.find_window_at_point(point) {
+            Ok(window_id) => describe_window(window_id, &windows, &apps, &config),
+            Err(err) => format!("no window under the pointer: {err}\n"),
+        };
+        for respond_to in pending.requests.drain(..) {
+            _ = respond_to.send(report.clone());
+        }
+    }
+}
+
+/// Describes a window with the properties which `[windows.*]` rules match on.
+fn describe_window(
+    window_id: WinID,
+    windows: &Windows,
+    apps: &Query<&Application>,
+    config: &Config,
+) -> String {
+    let Some((window, _, app_entity)) = windows.find_parent(window_id) else {
+        return format!("window_id: {window_id}\nnot a window paneru knows about\n");
+    };
+    let app = apps.get(app_entity).ok();
+    let bundle_id = app.and_then(|app| app.bundle_id()).unwrap_or_default();
+    let title = window.title().unwrap_or_default();
+    let frame = window.frame();
+    let rules = config.matching_window_rules(&title, &bundle_id);
+
+    [
+        format!("window_id: {window_id}"),
+        format!("app: {}", app.map(|app| app.name()).unwrap_or_default()),
+        format!("bundle_id: {bundle_id}"),
+        format!("title: {title}"),
+        format!("role: {}", window.role().unwrap_or_default()),
+        format!("subrole: {}", window.subrole().unwrap_or_default()),
+        format!(
+            "frame: {},{} {}x{}",
+            frame.min.x,
+            frame.min.y,
+            frame.width(),
+            frame.height()
+        ),
+        format!(
+            "rules: {}",
+            if rules.is_empty() {
+                "none".to_string()
+            } else {
+                rules.join(", ")
+            }
+        ),
+    ]
+    .join("\n")
+        + "\n"
+}
+
 #[cfg(test)]
 fn collect_state_broadcast_events<'a>(
     events: impl IntoIterator<Item = &'a Event>,
@@ -248,6 +381,7 @@ fn collect_state_broadcast_events_for_intent(
                 "display_id": display_id,
             }));
         }
+        outgoing.extend(bindings_reloaded_event(intent));
         return outgoing;
     };
 
@@ -310,10 +444,20 @@ fn collect_state_broadcast_events_for_intent(
             "display_id": display_id,
         }));
     }
+    outgoing.extend(bindings_reloaded_event(intent));
 
     outgoing
 }
 
+fn bindings_reloaded_event(intent: &StateBroadcastIntent) -> Option<Value> {
+    intent.bindings_reloaded.map(|count| {
+        json!({
+            "event": "bindings_reloaded",
+            "bindings": count,
+        })
+    })
+}
+
 #[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
 fn state_event_broadcast_handler(
     mut messages: MessageReader<Event>,
@@ -587,4 +731,21 @@ mod tests {
         assert!(intent.requires_state());
         assert!(!intent.is_empty());
     }
+
+    #[test]
+    fn test_state_broadcast_reports_reloaded_bindings_without_state() {
+        let intent = StateBroadcastIntent::from_events(
+            [PaneruEvent::BindingsReloaded { count: 3 }].iter(),
+            StateBroadcastSignals::default(),
+        );
+        assert!(!intent.is_empty());
+        assert!(!intent.requires_state());
+
+        let mut cache = StateBroadcastCache::default();
+        let outgoing =
+            collect_state_broadcast_events_for_intent(&intent, None, &mut cache, |_| None);
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0]["event"], "bindings_reloaded");
+        assert_eq!(outgoing[0]["bindings"], 3);
+    }
 }
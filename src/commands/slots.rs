@@ -0,0 +1,307 @@
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::app::{App, PreUpdate};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::message::MessageReader;
+use bevy::ecs::system::{Commands, Query};
+use serde::{Deserialize, Serialize};
+use stdext::function_name;
+use tracing::{info, warn};
+
+use super::Command;
+use crate::ecs::SpawnCommandsExt;
+use crate::ecs::layout::{Column, StackItem};
+use crate::ecs::params::{ActiveDisplayMut, Windows};
+use crate::errors::{Error, Result};
+use crate::events::Event;
+use crate::manager::Application;
+
+const SLOTS_DIRECTORY: &str = "layouts";
+
+/// A window of a layout slot, recognised by its app and title.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SlotWindow {
+    pub bundle_id: String,
+    pub title: String,
+}
+
+/// A column of a layout slot: its stacked windows from top to bottom, and its
+/// width as a ratio of the display width.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SlotColumn {
+    pub windows: Vec<SlotWindow>,
+    pub width_ratio: Option<f64>,
+}
+
+/// An arrangement of a strip, saved under a name by `layout save <slot>` and
+/// applied again by `layout load <slot>`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct LayoutSlot {
+    pub columns: Vec<SlotColumn>,
+}
+
+impl LayoutSlot {
+    /// Returns the file of the slot `name` in the state directory.
+    pub(crate) fn path(name: &str) -> Result<PathBuf> {
+        let valid = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+        if name.is_empty() || !name.chars().all(valid) {
+            return Err(Error::InvalidInput(format!(
+                "{}: invalid layout slot name '{name}'",
+                function_name!()
+            )));
+        }
+        xdg::BaseDirectories::with_prefix("paneru")
+            .get_state_file(format!("{SLOTS_DIRECTORY}/{name}.json"))
+            .ok_or(Error::NotFound(format!(
+                "{}: no state directory",
+                function_name!()
+            )))
+    }
+
+    fn save(&self, name: &str) -> Result<()> {
+        let path = Self::path(name)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        info!("saved layout slot '{name}' to {}", path.display());
+        Ok(())
+    }
+
+    fn load(name: &str) -> Result<Self> {
+        let path = Self::path(name)?;
+        let data = fs::read_to_string(&path).map_err(|err| {
+            Error::NotFound(format!("{}: layout slot '{name}': {err}", function_name!()))
+        })?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Assigns the stack items of a strip, given by their leading window, to the
+    /// columns of the slot. Windows are matched by app and title first, then by
+    /// app alone. Returns the item indices of each column with its width, followed
+    /// by the unmatched items, each in its own column.
+    fn plan(&self, items: &[SlotWindow]) -> Vec<(Vec<usize>, Option<f64>)> {
+        let saved = self
+            .columns
+            .iter()
+            .enumerate()
+            .flat_map(|(index, column)| column.windows.iter().map(move |window| (index, window)))
+            .collect::<Vec<_>>();
+        let mut used = vec![false; items.len()];
+        let mut matches = vec![None; saved.len()];
+        for exact in [true, false] {
+            for ((_, window), matched) in saved.iter().zip(&mut matches) {
+                if matched.is_some() {
+                    continue;
+                }
+                *matched = items.iter().enumerate().position(|(index, item)| {
+                    !used[index]
+                        && item.bundle_id == window.bundle_id
+                        && (!exact || item.title == window.title)
+                });
+                if let Some(index) = *matched {
+                    used[index] = true;
+                }
+            }
+        }
+
+        let mut planned = self
+            .columns
+            .iter()
+            .map(|column| (vec![], column.width_ratio))
+            .collect::<Vec<_>>();
+        for ((column, _), matched) in saved.iter().zip(matches) {
+            if let Some(index) = matched {
+                planned[*column].0.push(index);
+            }
+        }
+        planned.retain(|(items, _)| !items.is_empty());
+        planned.extend(
+            (0..items.len())
+                .filter(|index| !used[*index])
+                .map(|index| (vec![index], None)),
+        );
+        planned
+    }
+}
+
+pub(super) fn register_slot_commands(app: &mut App) {
+    app.add_systems(PreUpdate, layout_slot_handler);
+}
+
+fn slot_window(entity: Entity, windows: &Windows, apps: &Query<&Application>) -> SlotWindow {
+    let window = windows.get(entity);
+    let bundle_id = window
+        .and_then(|window| windows.find_parent(window.id()))
+        .and_then(|(_, _, parent)| apps.get(parent).ok())
+        .and_then(|app| app.bundle_id())
+        .unwrap_or_default();
+    SlotWindow {
+        bundle_id,
+        title: window
+            .and_then(|window| window.title().ok())
+            .unwrap_or_default(),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn layout_slot_handler(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    apps: Query<&Application>,
+    mut active_display: ActiveDisplayMut,
+    mut commands: Commands,
+) {
+    for event in messages.read() {
+        let (name, result) = match event {
+            Event::Command {
+                command: Command::LayoutSave(name),
+            } => (name, save_slot(name, &windows, &apps, &mut active_display)),
+            Event::Command {
+                command: Command::LayoutLoad(name),
+            } => (
+                name,
+                load_slot(name, &windows, &apps, &mut active_display, &mut commands),
+            ),
+            _ => continue,
+        };
+        if let Err(err) = result {
+            warn!("layout slot '{name}': {err}");
+        }
+    }
+}
+
+fn save_slot(
+    name: &str,
+    windows: &Windows,
+    apps: &Query<&Application>,
+    active_display: &mut ActiveDisplayMut,
+) -> Result<()> {
+    let strip = active_display.active_strip();
+    let columns = strip
+        .columns()
+        .filter_map(|column| {
+            let leaders = match column {
+                Column::Stack(items) => items.iter().filter_map(StackItem::top).collect(),
+                column => column.top().into_iter().collect::<Vec<_>>(),
+            };
+            let width_ratio = leaders
+                .first()
+                .and_then(|entity| windows.width_ratio(*entity));
+            (!leaders.is_empty()).then(|| SlotColumn {
+                windows: leaders
+                    .into_iter()
+                    .map(|entity| slot_window(entity, windows, apps))
+                    .collect(),
+                width_ratio,
+            })
+        })
+        .collect();
+    LayoutSlot { columns }.save(name)
+}
+
+fn load_slot(
+    name: &str,
+    windows: &Windows,
+    apps: &Query<&Application>,
+    active_display: &mut ActiveDisplayMut,
+    commands: &mut Commands,
+) -> Result<()> {
+    let slot = LayoutSlot::load(name)?;
+    let display_width = active_display.bounds().width();
+    let strip = active_display.active_strip();
+    if strip.is_fullscreen() {
+        return Err(Error::InvalidInput(format!(
+            "{}: the strip is full screen",
+            function_name!()
+        )));
+    }
+
+    // Tab groups move as a whole, behind their leading window.
+    let items = strip
+        .columns()
+        .flat_map(|column| match column {
+            Column::Stack(items) => items.clone(),
+            Column::Tabs(tabs) => vec![StackItem::Tabs(tabs.clone())],
+            Column::Single(entity) | Column::Fullscren(entity) => vec![StackItem::Single(*entity)],
+        })
+        .collect::<Vec<_>>();
+    let identities = items
+        .iter()
+        .filter_map(StackItem::top)
+        .map(|entity| slot_window(entity, windows, apps))
+        .collect::<Vec<_>>();
+    let planned = slot.plan(&identities);
+
+    // The columns are reordered in place, which keeps the column groups and
+    // the orientation of the stacks.
+    strip.rearrange(
+        planned
+            .iter()
+            .map(|(column, _)| column.iter().map(|index| items[*index].clone()).collect())
+            .collect(),
+    );
+    for (column, width_ratio) in &planned {
+        let Some(ratio) = width_ratio else {
+            continue;
+        };
+        let width = (ratio * f64::from(display_width)).round() as i32;
+        for entity in column.iter().flat_map(|index| items[*index].window_iter()) {
+            if let Some(size) = windows.size(entity) {
+                commands.resize_entity(entity, size.with_x(width));
+            }
+        }
+    }
+
+    info!("loaded layout slot '{name}' with {} columns", planned.len());
+    let focused = windows.focused().map(|(_, entity)| entity);
+    if let Some(entity) = focused.or_else(|| items.first().and_then(StackItem::top)) {
+        commands.reshuffle_around(entity);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(bundle_id: &str, title: &str) -> SlotWindow {
+        SlotWindow {
+            bundle_id: bundle_id.to_string(),
+            title: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_plan_matches_title_before_app() {
+        let slot = LayoutSlot {
+            columns: vec![
+                SlotColumn {
+                    windows: vec![window("term", "logs"), window("term", "shell")],
+                    width_ratio: Some(0.5),
+                },
+                SlotColumn {
+                    windows: vec![window("editor", "main.rs")],
+                    width_ratio: None,
+                },
+                SlotColumn {
+                    windows: vec![window("browser", "docs")],
+                    width_ratio: None,
+                },
+            ],
+        };
+        let items = [
+            window("editor", "lib.rs"),
+            window("term", "shell"),
+            window("chat", "team"),
+            window("term", "build"),
+        ];
+        // The editor is matched by app alone, the unmatched "build" terminal
+        // falls back to the logs place, and the chat keeps a column at the end.
+        assert_eq!(
+            slot.plan(&items),
+            vec![(vec![3, 1], Some(0.5)), (vec![0], None), (vec![2], None)]
+        );
+    }
+}
@@ -2,9 +2,9 @@ use arc_swap::{ArcSwap, Guard};
 use bevy::ecs::resource::Resource;
 use objc2_core_foundation::{CFData, CFString};
 use regex::Regex;
-use serde::{Deserialize, Deserializer, de};
+use serde::{Deserialize, Deserializer, Serialize, de};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     env,
     ffi::c_void,
     fs::{OpenOptions, create_dir_all, read_to_string},
@@ -15,13 +15,16 @@ use std::{
     time::Duration,
 };
 use stdext::function_name;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 
 use self::decorations::BorderRadiusOption;
-use self::swipe::SwipeGestureDirection;
+use self::swipe::{SwipeGestureDirection, VerticalSwipeOptions};
 use crate::{
-    commands::{Command, Direction, MouseMove, MoveFocus, Operation, ResizeDirection},
-    manager::ProcessApi,
+    commands::{
+        Command, Direction, InsertionPlacement, MouseMove, MoveFocus, Operation, ResizeAmount,
+        ResizeDirection, SpaceOperation, SpaceTarget,
+    },
+    manager::{FrameInset, ProcessApi},
     platform::{Modifiers, OSStatus, macos_major_version},
 };
 use crate::{
@@ -124,6 +127,145 @@ pub fn discover_configuration_file() -> Option<PathBuf> {
         .find(|path| path.exists())
 }
 
+/// Top-level key listing additional configuration files. They are merged in order
+/// on top of the file which includes them, so later files override earlier ones.
+const INCLUDE_KEY: &str = "include";
+/// Top-level key holding the named profiles. The selected one is merged over the
+/// rest of the configuration.
+const PROFILE_KEY: &str = "profile";
+/// Default interval of the periodic checks for changes the OS did not notify about.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(1000);
+const DEFAULT_POLL_INTERVAL_ACTIVE: Duration = Duration::from_millis(250);
+/// Animation speed at which windows reach their target within a frame.
+const INSTANT_ANIMATION_SPEED: f64 = 1_000_000.0;
+/// How long an application may take to answer an accessibility message.
+pub const DEFAULT_AX_TIMEOUT: Duration = Duration::from_millis(1000);
+/// Applications known to answer accessibility messages slowly, with their
+/// default timeouts in milliseconds.
+const BUILTIN_AX_TIMEOUTS: [(&str, u64); 4] = [
+    ("com.microsoft.Excel", 3000),
+    ("com.microsoft.Word", 3000),
+    ("com.microsoft.Powerpoint", 3000),
+    ("com.microsoft.Outlook", 3000),
+];
+/// Bundle ids of system utilities whose windows are floated by default.
+const BUILTIN_FLOATING_BUNDLES: [&str; 7] = [
+    "com.apple.systempreferences",
+    "com.apple.SecurityAgent",
+    "com.apple.UserNotificationCenter",
+    "com.apple.coreservices.uiagent",
+    "com.apple.archiveutility",
+    "com.apple.DiskImageMounter",
+    "com.apple.ScreenSharing",
+];
+/// Window subroles which are floated by default, whatever the application.
+const BUILTIN_FLOATING_SUBROLES: [&str; 2] = ["AXSystemDialog", "AXSystemFloatingWindow"];
+
+/// Reads the configuration file at `path` along with all the files it includes.
+///
+/// # Returns
+///
+/// The merged TOML document and the list of files it was assembled from, starting with `path`.
+/// Included files which do not exist are skipped, so per-machine overrides can be optional.
+pub fn read_config_files(path: &Path) -> Result<(String, Vec<PathBuf>)> {
+    let mut files = vec![];
+    let table = read_config_table(path, &mut files)?;
+    Ok((toml::to_string(&table)?, files))
+}
+
+fn read_config_table(path: &Path, files: &mut Vec<PathBuf>) -> Result<toml::Table> {
+    if files.iter().any(|file| file == path) {
+        return Err(Error::InvalidConfig(format!(
+            "{}: {} is included more than once",
+            function_name!(),
+            path.display()
+        )));
+    }
+    files.push(path.to_path_buf());
+
+    let mut table = toml::from_str::<toml::Table>(&read_to_string(path)?)?;
+    let includes = match table.remove(INCLUDE_KEY) {
+        None => vec![],
+        Some(toml::Value::Array(includes)) => includes,
+        Some(_) => {
+            return Err(Error::InvalidConfig(format!(
+                "{}: '{INCLUDE_KEY}' in {} must be an array of paths",
+                function_name!(),
+                path.display()
+            )));
+        }
+    };
+
+    let base = path.parent().unwrap_or_else(|| Path::new("."));
+    for include in includes {
+        let Some(include) = include.as_str() else {
+            return Err(Error::InvalidConfig(format!(
+                "{}: invalid include {include} in {}",
+                function_name!(),
+                path.display()
+            )));
+        };
+        let included = resolve_include_path(base, include);
+        if !included.exists() {
+            warn!(
+                "{}: included config {} does not exist, skipping it.",
+                function_name!(),
+                included.display()
+            );
+            continue;
+        }
+        let other = read_config_table(&included, files)?;
+        merge_tables(&mut table, other);
+    }
+    Ok(table)
+}
+
+/// Removes the profiles from the configuration `input` and merges the overrides
+/// of the selected `profile` on top of it.
+fn select_profile(input: &str, profile: Option<&str>) -> Result<String> {
+    let mut table = toml::from_str::<toml::Table>(input)?;
+    let profiles = table.remove(PROFILE_KEY);
+    if let Some(name) = profile {
+        let Some(toml::Value::Table(overrides)) = profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(name))
+            .cloned()
+        else {
+            return Err(Error::InvalidConfig(format!(
+                "{}: unknown profile '{name}'",
+                function_name!()
+            )));
+        };
+        merge_tables(&mut table, overrides);
+    }
+    Ok(toml::to_string(&table)?)
+}
+
+/// Resolves an include relative to the directory of the including file, expanding a leading `~`.
+fn resolve_include_path(base: &Path, include: &str) -> PathBuf {
+    if let Some(rest) = include.strip_prefix("~/")
+        && let Ok(home) = env::var("HOME")
+    {
+        return PathBuf::from(home).join(rest);
+    }
+    base.join(include)
+}
+
+/// Recursively merges `other` into `table`. Nested tables are merged key by key,
+/// any other value (including arrays) is replaced.
+fn merge_tables(table: &mut toml::Table, other: toml::Table) {
+    for (key, value) in other {
+        match (table.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(value)) => {
+                merge_tables(existing, value);
+            }
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
 /// Returns the list of deprecated top-level `[options]` keys present in a TOML config.
 pub fn deprecated_options_in_input(input: &str) -> Result<Vec<String>> {
     const DEPRECATED_KEYS: [&str; 16] = [
@@ -205,6 +347,31 @@ fn parse_virtual_workspace_number(input: &str) -> Result<u32> {
     Ok(number - 1)
 }
 
+/// Parses a column number of the strip, counting from 1, into a zero-based index.
+fn parse_column_number(input: &str) -> Result<usize> {
+    match input.parse::<usize>() {
+        Ok(number) if number > 0 => Ok(number - 1),
+        _ => Err(Error::InvalidConfig(format!(
+            "{}: Invalid column number {input}, columns are numbered from 1",
+            function_name!()
+        ))),
+    }
+}
+
+/// Parses a `display:N` argument, where displays are numbered from 1 in arrangement order.
+fn parse_display_target(input: &str) -> Result<usize> {
+    input
+        .strip_prefix("display:")
+        .and_then(|number| number.parse::<usize>().ok())
+        .and_then(|number| number.checked_sub(1))
+        .ok_or_else(|| {
+            Error::InvalidConfig(format!(
+                "{}: Unhandled display target {input}, expected display:N",
+                function_name!()
+            ))
+        })
+}
+
 /// Parses a string into a `ResizeDirection` enum.
 fn parse_resize_direction(direction: &str) -> Result<ResizeDirection> {
     Ok(match direction {
@@ -219,11 +386,43 @@ fn parse_resize_direction(direction: &str) -> Result<ResizeDirection> {
     })
 }
 
-/// Parses a command argument vector into an `Operation` enum.
+/// Step used by the directional grow and shrink commands when no amount is given.
+const DEFAULT_GROW_AMOUNT: ResizeAmount = ResizeAmount::Percent(5.0);
+
+/// Parses a resize step, either in pixels (`50`) or as a percentage of the display (`10%`).
+fn parse_resize_amount(input: &str) -> Result<ResizeAmount> {
+    let err = || {
+        Error::InvalidConfig(format!(
+            "{}: Unhandled resize amount {input}",
+            function_name!()
+        ))
+    };
+    let amount = if let Some(percent) = input.strip_suffix('%') {
+        ResizeAmount::Percent(
+            percent
+                .parse::<f64>()
+                .ok()
+                .filter(|p| *p > 0.0 && *p <= 100.0)
+                .ok_or_else(err)?,
+        )
+    } else {
+        ResizeAmount::Pixels(
+            input
+                .parse::<i32>()
+                .ok()
+                .filter(|p| *p > 0)
+                .ok_or_else(err)?,
+        )
+    };
+    Ok(amount)
+}
+
+/// Parses the window operations which take an argument, the others are
+/// listed in `NAMED_COMMANDS`.
 ///
 /// # Arguments
 ///
-/// * `argv` - A slice of strings representing the command arguments (e.g., `["focus", "east"]`).
+/// * `argv` - A slice of strings representing the command arguments (e.g., `["focus", "index", "2"]`).
 ///
 /// # Returns
 ///
@@ -234,35 +433,35 @@ fn parse_operation(argv: &[&str]) -> Result<Operation> {
     let err = Error::InvalidConfig(format!("{}: Invalid command '{argv:?}'", function_name!()));
 
     let out = match cmd {
-        // The bindings key splits on `_`, so `window_focus_unmanaged` arrives
-        // here as ["focus", "unmanaged"] and the suffix tells us the variant.
-        "focus" => match *argv.get(1).ok_or(err.clone())? {
-            "unmanaged" => Operation::FocusUnmanaged,
-            "managed" => Operation::FocusManaged,
-            dir => Operation::Focus(parse_direction(dir)?),
+        "focus" => match argv[1..] {
+            ["index", number] => Operation::FocusIndex(parse_column_number(number)?),
+            _ => return Err(err),
         },
-        "raise" => match *argv.get(1).ok_or(err.clone())? {
-            "floating" => Operation::RaiseFloating,
+        "move" => match argv[1..] {
+            ["index", number] => Operation::MoveIndex(parse_column_number(number)?),
             _ => return Err(err),
         },
-        "togglefloatlayer" => Operation::ToggleFloatingLayer,
-        "swap" => Operation::Swap(parse_direction(argv.get(1).ok_or(err)?)?),
-        "center" => Operation::Center,
+        "center" => Operation::Center(
+            argv.get(1)
+                .map(|target| parse_display_target(target))
+                .transpose()?,
+        ),
         "resize" => Operation::Resize(
             argv.get(1)
                 .map_or(Ok(ResizeDirection::Grow), |arg| parse_resize_direction(arg))?,
         ),
-        "grow" => Operation::Resize(ResizeDirection::Grow),
-        "shrink" => Operation::Resize(ResizeDirection::Shrink),
-        "fullwidth" => Operation::FullWidth,
-        "manage" => Operation::Manage,
-        "equalize" => Operation::Equalize,
-        "balance" => Operation::Balance,
-        "stack" => Operation::Stack(true),
-        "unstack" => Operation::Stack(false),
-        "nextdisplay" => Operation::ToNextDisplay(MoveFocus::Follow),
-        "nextdisplaysend" => Operation::ToNextDisplay(MoveFocus::Stay),
-        "snap" => Operation::Snap,
+        "grow" | "shrink" if argv.len() > 1 => {
+            let direction = parse_direction(argv[1])?;
+            let amount = argv
+                .get(2)
+                .map_or(Ok(DEFAULT_GROW_AMOUNT), |arg| parse_resize_amount(arg))?;
+            if cmd == "grow" {
+                Operation::GrowEdge(direction, amount)
+            } else {
+                Operation::GrowEdge(direction, amount.negate())
+            }
+        }
+        "pull" if argv.len() > 1 => Operation::Pull(argv[1..].join(" ")),
         "virtual" => {
             let target = argv.get(1).ok_or(err)?;
             target.parse::<u32>().map_or_else(
@@ -311,24 +510,291 @@ fn parse_operation(argv: &[&str]) -> Result<Operation> {
     Ok(out)
 }
 
-/// Parses a command argument vector into a `MouseMove` enum.
-fn parse_mouse_move(argv: &[&str]) -> Result<MouseMove> {
-    let empty = "";
-    let cmd = *argv.first().unwrap_or(&empty);
-    let err = Error::InvalidConfig(format!(
-        "{}: Invalid mouse command '{argv:?}'",
-        function_name!()
-    ));
+/// Parses the arguments of a `space` command switching to a numbered space, e.g. `["focus", "2"]`.
+fn parse_space_operation(argv: &[&str]) -> Result<SpaceOperation> {
+    let err = || {
+        Error::InvalidConfig(format!(
+            "{}: Invalid space command '{argv:?}'",
+            function_name!()
+        ))
+    };
 
-    let out = match cmd {
-        "nextdisplay" => MouseMove::ToNextDisplay,
-        _ => {
-            return Err(err);
+    let out = match argv {
+        ["focus", number] => {
+            let number = number.parse::<usize>().map_err(|_| err())?;
+            if number == 0 {
+                return Err(err());
+            }
+            SpaceOperation::Focus(SpaceTarget::Number(number))
         }
+        _ => return Err(err()),
     };
     Ok(out)
 }
 
+/// The commands without arguments, named like their bindings. `parse_command`
+/// looks them up here before parsing the commands which take arguments.
+pub const NAMED_COMMANDS: [(&str, Command); 81] = [
+    ("printstate", Command::PrintState),
+    ("quit", Command::Quit),
+    ("restart", Command::Restart),
+    ("restart-daemon", Command::RestartDaemon),
+    ("profile", Command::Profile(None)),
+    ("layout_lock_toggle", Command::ToggleLayoutLock),
+    ("column_group_create", Command::GroupColumns(true)),
+    ("column_group_dissolve", Command::GroupColumns(false)),
+    ("low_power_on", Command::LowPower(Some(true))),
+    ("low_power_off", Command::LowPower(Some(false))),
+    ("low_power_auto", Command::LowPower(None)),
+    ("pause", Command::Pause(false)),
+    ("pause_keep_bindings", Command::Pause(true)),
+    ("resume", Command::Resume),
+    (
+        "mouse_nextdisplay",
+        Command::Mouse(MouseMove::ToNextDisplay),
+    ),
+    (
+        "space_focus_next",
+        Command::Space(SpaceOperation::Focus(SpaceTarget::Next)),
+    ),
+    (
+        "space_focus_prev",
+        Command::Space(SpaceOperation::Focus(SpaceTarget::Previous)),
+    ),
+    ("space_create", Command::Space(SpaceOperation::Create)),
+    ("space_destroy", Command::Space(SpaceOperation::Destroy)),
+    (
+        "window_focus_west",
+        Command::Window(Operation::Focus(Direction::West)),
+    ),
+    (
+        "window_focus_east",
+        Command::Window(Operation::Focus(Direction::East)),
+    ),
+    (
+        "window_focus_north",
+        Command::Window(Operation::Focus(Direction::North)),
+    ),
+    (
+        "window_focus_south",
+        Command::Window(Operation::Focus(Direction::South)),
+    ),
+    (
+        "window_focus_first",
+        Command::Window(Operation::Focus(Direction::First)),
+    ),
+    (
+        "window_focus_last",
+        Command::Window(Operation::Focus(Direction::Last)),
+    ),
+    (
+        "window_focus_managed",
+        Command::Window(Operation::FocusManaged),
+    ),
+    (
+        "window_focus_unmanaged",
+        Command::Window(Operation::FocusUnmanaged),
+    ),
+    (
+        "window_focus_visible_next",
+        Command::Window(Operation::FocusVisible(Direction::East)),
+    ),
+    (
+        "window_focus_visible_prev",
+        Command::Window(Operation::FocusVisible(Direction::West)),
+    ),
+    (
+        "window_raise_floating",
+        Command::Window(Operation::RaiseFloating),
+    ),
+    (
+        "window_togglefloatlayer",
+        Command::Window(Operation::ToggleFloatingLayer),
+    ),
+    ("window_pin_toggle", Command::Window(Operation::TogglePin)),
+    (
+        "window_set_insertion_point_west",
+        Command::Window(Operation::SetInsertionPoint(InsertionPlacement::West)),
+    ),
+    (
+        "window_set_insertion_point_east",
+        Command::Window(Operation::SetInsertionPoint(InsertionPlacement::East)),
+    ),
+    (
+        "window_set_insertion_point_stack",
+        Command::Window(Operation::SetInsertionPoint(InsertionPlacement::Stack)),
+    ),
+    ("window_close", Command::Window(Operation::Close)),
+    (
+        "window_close_others",
+        Command::Window(Operation::CloseOthers),
+    ),
+    (
+        "window_swap_west",
+        Command::Window(Operation::Swap(Direction::West)),
+    ),
+    (
+        "window_swap_east",
+        Command::Window(Operation::Swap(Direction::East)),
+    ),
+    (
+        "window_swap_north",
+        Command::Window(Operation::Swap(Direction::North)),
+    ),
+    (
+        "window_swap_south",
+        Command::Window(Operation::Swap(Direction::South)),
+    ),
+    (
+        "window_swap_first",
+        Command::Window(Operation::Swap(Direction::First)),
+    ),
+    (
+        "window_swap_last",
+        Command::Window(Operation::Swap(Direction::Last)),
+    ),
+    (
+        "window_move_west",
+        Command::Window(Operation::Move(Direction::West)),
+    ),
+    (
+        "window_move_east",
+        Command::Window(Operation::Move(Direction::East)),
+    ),
+    (
+        "window_move_into_stack_west",
+        Command::Window(Operation::MoveIntoStack(Direction::West)),
+    ),
+    (
+        "window_move_into_stack_east",
+        Command::Window(Operation::MoveIntoStack(Direction::East)),
+    ),
+    ("window_promote", Command::Window(Operation::Promote)),
+    ("window_demote", Command::Window(Operation::Demote)),
+    ("window_center", Command::Window(Operation::Center(None))),
+    (
+        "window_resize",
+        Command::Window(Operation::Resize(ResizeDirection::Grow)),
+    ),
+    (
+        "window_grow",
+        Command::Window(Operation::Resize(ResizeDirection::Grow)),
+    ),
+    (
+        "window_shrink",
+        Command::Window(Operation::Resize(ResizeDirection::Shrink)),
+    ),
+    (
+        "window_grow_west",
+        Command::Window(Operation::GrowEdge(Direction::West, DEFAULT_GROW_AMOUNT)),
+    ),
+    (
+        "window_grow_east",
+        Command::Window(Operation::GrowEdge(Direction::East, DEFAULT_GROW_AMOUNT)),
+    ),
+    (
+        "window_grow_north",
+        Command::Window(Operation::GrowEdge(Direction::North, DEFAULT_GROW_AMOUNT)),
+    ),
+    (
+        "window_grow_south",
+        Command::Window(Operation::GrowEdge(Direction::South, DEFAULT_GROW_AMOUNT)),
+    ),
+    (
+        "window_shrink_west",
+        Command::Window(Operation::GrowEdge(
+            Direction::West,
+            DEFAULT_GROW_AMOUNT.negate(),
+        )),
+    ),
+    (
+        "window_shrink_east",
+        Command::Window(Operation::GrowEdge(
+            Direction::East,
+            DEFAULT_GROW_AMOUNT.negate(),
+        )),
+    ),
+    (
+        "window_shrink_north",
+        Command::Window(Operation::GrowEdge(
+            Direction::North,
+            DEFAULT_GROW_AMOUNT.negate(),
+        )),
+    ),
+    (
+        "window_shrink_south",
+        Command::Window(Operation::GrowEdge(
+            Direction::South,
+            DEFAULT_GROW_AMOUNT.negate(),
+        )),
+    ),
+    ("window_fullwidth", Command::Window(Operation::FullWidth)),
+    ("window_zoom_native", Command::Window(Operation::ZoomNative)),
+    ("window_manage", Command::Window(Operation::Manage)),
+    ("window_equalize", Command::Window(Operation::Equalize)),
+    ("window_balance", Command::Window(Operation::Balance)),
+    ("window_stack", Command::Window(Operation::Stack(true))),
+    ("window_unstack", Command::Window(Operation::Stack(false))),
+    (
+        "window_stack_all",
+        Command::Window(Operation::StackAll(true)),
+    ),
+    (
+        "window_unstack_all",
+        Command::Window(Operation::StackAll(false)),
+    ),
+    (
+        "window_toggle_split_orientation",
+        Command::Window(Operation::ToggleSplitOrientation),
+    ),
+    (
+        "window_nextdisplay",
+        Command::Window(Operation::ToNextDisplay(MoveFocus::Follow)),
+    ),
+    (
+        "window_nextdisplaysend",
+        Command::Window(Operation::ToNextDisplay(MoveFocus::Stay)),
+    ),
+    ("window_snap", Command::Window(Operation::Snap)),
+    ("window_fit_display", Command::Window(Operation::FitDisplay)),
+    (
+        "window_virtual_north",
+        Command::Window(Operation::Virtual(Direction::North)),
+    ),
+    (
+        "window_virtual_south",
+        Command::Window(Operation::Virtual(Direction::South)),
+    ),
+    (
+        "window_virtualmove_north",
+        Command::Window(Operation::VirtualMove(Direction::North, MoveFocus::Follow)),
+    ),
+    (
+        "window_virtualmove_south",
+        Command::Window(Operation::VirtualMove(Direction::South, MoveFocus::Follow)),
+    ),
+    (
+        "window_virtualsend_north",
+        Command::Window(Operation::VirtualMove(Direction::North, MoveFocus::Stay)),
+    ),
+    (
+        "window_virtualsend_south",
+        Command::Window(Operation::VirtualMove(Direction::South, MoveFocus::Stay)),
+    ),
+];
+
+/// Names of the commands without arguments, written like binding names.
+/// `paneru send-cmd` completes them in the shell and accepts them as they are.
+pub const COMMAND_NAMES: [&str; NAMED_COMMANDS.len()] = {
+    let mut names = [""; NAMED_COMMANDS.len()];
+    let mut index = 0;
+    while index < names.len() {
+        names[index] = NAMED_COMMANDS[index].0;
+        index += 1;
+    }
+    names
+};
+
 /// Parses a command argument vector into a `Command` enum.
 ///
 /// # Arguments
@@ -339,19 +805,36 @@ fn parse_mouse_move(argv: &[&str]) -> Result<MouseMove> {
 ///
 /// `Ok(Command)` if the arguments represent a valid command, otherwise `Err(Error::InvalidConfig)`.
 pub fn parse_command(argv: &[&str]) -> Result<Command> {
+    if let Some((_, command)) = NAMED_COMMANDS
+        .iter()
+        .find(|(name, _)| name.split('_').eq(argv.iter().copied()))
+    {
+        return Ok(command.clone());
+    }
+
     let empty = "";
     let cmd = *argv.first().unwrap_or(&empty);
 
     let out = match cmd {
-        "printstate" => Command::PrintState,
         "window" => Command::Window(parse_operation(&argv[1..])?),
-        "mouse" => Command::Mouse(parse_mouse_move(&argv[1..])?),
-        "quit" => Command::Quit,
-        "restart" => Command::Restart,
+        "space" => Command::Space(parse_space_operation(&argv[1..])?),
+        // Bindings split their key on `_`, so the name is joined back together.
+        "profile" => Command::Profile((argv.len() > 1).then(|| argv[1..].join("_"))),
+        "layout" if argv.len() > 2 && argv[1] == "save" => Command::LayoutSave(argv[2..].join("_")),
+        "layout" if argv.len() > 2 && argv[1] == "load" => Command::LayoutLoad(argv[2..].join("_")),
+        // The combination may arrive split into words, as in `send keys cmd - w`.
+        "send" if argv.len() > 2 && argv[1] == "keys" => {
+            let keys = argv[2..].concat();
+            if let Some((modifiers, _)) = keys.rsplit_once('-') {
+                parse_modifiers(modifiers)?;
+            }
+            Command::SendKeys(keys)
+        }
         _ => {
-            return Err(Error::InvalidConfig(format!(
-                "{}: Unhandled command '{argv:?}'",
-                function_name!()
+            return Err(Error::InvalidInput(format!(
+                "{}: unknown command '{}'",
+                function_name!(),
+                argv.join(" ")
             )));
         }
     };
@@ -360,6 +843,8 @@ pub fn parse_command(argv: &[&str]) -> Result<Command> {
 
 /// `Config` manages the application's configuration, including options, keybindings, and window-specific parameters.
 /// It provides methods for loading, reloading, and querying configuration settings.
+/// Clones share the configuration, so the clone held by the input tap sees a
+/// reload as soon as it is stored, without dropping a key press in between.
 #[derive(Clone, Debug, Resource)]
 pub struct Config {
     inner: Arc<ArcSwap<InnerConfig>>,
@@ -376,12 +861,28 @@ impl Config {
     ///
     /// `Ok(Self)` if the configuration is loaded successfully, otherwise `Err(Error)` with an error message.
     pub fn new(path: &Path) -> Result<Self> {
-        let input = read_to_string(path)?;
+        let (input, files) = read_config_files(path)?;
+        let mut inner = InnerConfig::load(&input, None)?;
+        inner.files = files;
         Ok(Config {
-            inner: Arc::new(ArcSwap::from_pointee(InnerConfig::new(&input)?)),
+            inner: Arc::new(ArcSwap::from_pointee(inner)),
         })
     }
 
+    /// Loads only the `[options]` of the configuration file. Unlike `Config::new`,
+    /// the keybindings are not resolved, which needs the keyboard layout and so
+    /// the main thread. Used for the options read on startup, like the socket.
+    pub fn read_options(path: &Path) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct OptionsOnly {
+            #[serde(default)]
+            options: MainOptions,
+        }
+        let (input, _) = read_config_files(path)?;
+        let OptionsOnly { options } = toml::from_str(&select_profile(&input, None)?)?;
+        Ok((options, vec![]).into())
+    }
+
     /// Reloads the configuration from the specified path, updating the internal options and keybindings.
     ///
     /// # Arguments
@@ -392,12 +893,32 @@ impl Config {
     ///
     /// `Ok(())` if the configuration is reloaded successfully, otherwise `Err(Error)` with an error message.
     pub fn reload_config(&mut self, path: &Path) -> Result<()> {
-        let input = read_to_string(path)?;
-        let new = InnerConfig::new(&input)?;
+        let profile = self.profile();
+        self.switch_profile(path, profile.as_deref())
+    }
+
+    /// Reloads the configuration from `path` with the overrides of the named
+    /// `profile` applied, or with none of them when it is `None`. The current
+    /// configuration is kept if the profile does not exist.
+    pub fn switch_profile(&mut self, path: &Path, profile: Option<&str>) -> Result<()> {
+        let (input, files) = read_config_files(path)?;
+        let mut new = InnerConfig::load(&input, profile)?;
+        new.files = files;
         self.inner.store(Arc::new(new));
         Ok(())
     }
 
+    /// Returns the name of the active profile, if any.
+    pub fn profile(&self) -> Option<String> {
+        self.inner().profile.clone()
+    }
+
+    /// Returns the configuration file and all the files it includes, which need
+    /// to be watched for changes.
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.inner().files.clone()
+    }
+
     /// Returns a read guard to the inner `InnerConfig` for read-only access.
     ///
     /// # Returns
@@ -425,10 +946,31 @@ impl Config {
             .animation_speed
             // If unset, set it to something high, so the move happens immediately,
             // effectively disabling animation.
-            .unwrap_or(1_000_000.0)
+            .unwrap_or(INSTANT_ANIMATION_SPEED)
             .max(0.0)
     }
 
+    /// Returns true if any display has its own animation settings.
+    pub fn has_display_animations(&self) -> bool {
+        !self.options().display_animations.is_empty()
+    }
+
+    /// Animation speed on the display with the given UUID: its entry in
+    /// `display_animations`, otherwise `animation_speed`.
+    pub fn display_animation_speed(&self, uuid: &str) -> f64 {
+        match self.options().display_animations.get(uuid) {
+            Some(DisplayAnimation {
+                animations: Some(false),
+                ..
+            }) => INSTANT_ANIMATION_SPEED,
+            Some(DisplayAnimation {
+                animation_speed: Some(speed),
+                ..
+            }) => speed.max(0.0),
+            _ => self.animation_speed(),
+        }
+    }
+
     /// Finds a keybinding matching the given `keycode` and `modifier` mask.
     ///
     /// # Arguments
@@ -446,7 +988,69 @@ impl Config {
             .values()
             .flat_map(|binds| binds.all())
             .find_map(|bind| {
-                (bind.code == keycode && bind.modifiers.matches(mask))
+                (bind.mouse.is_none() && bind.code == keycode && bind.modifiers.matches(mask))
+                    .then_some(bind.command.clone())
+            })
+    }
+
+    /// Describes every key and mouse binding, sorted by name, so that a reload
+    /// can tell whether the bindings changed.
+    pub fn bindings_summary(&self) -> Vec<String> {
+        let config = self.inner();
+        let mut summary = config
+            .bindings
+            .iter()
+            .flat_map(|(name, binds)| {
+                binds
+                    .all()
+                    .into_iter()
+                    .map(move |bind| format!("{name}: {bind:?}"))
+            })
+            .collect::<Vec<_>>();
+        summary.sort_unstable();
+        summary
+    }
+
+    /// Lists the key and mouse bindings of the configuration, sorted by command.
+    pub fn bindings_info(&self) -> Vec<BindingInfo> {
+        describe_bindings(&self.inner().bindings)
+    }
+
+    /// Describes the bindings in effect and those of every profile of the
+    /// configuration file, for `paneru query keybindings`. The profiles are
+    /// resolved when the configuration is loaded.
+    pub fn keybindings_report(&self) -> KeybindingsReport {
+        KeybindingsReport {
+            active_profile: self.profile(),
+            bindings: self.bindings_info(),
+            profiles: self.inner().profile_bindings.clone(),
+        }
+    }
+
+    /// Resolves a key combination of a `send_keys` command, like `"cmd+shift-t"`,
+    /// with the keyboard layout read when the configuration was loaded.
+    pub fn resolve_key_combination(&self, input: &str) -> Result<(u8, Modifiers)> {
+        resolve_keybinding_str(input, &self.inner().virtual_keys)
+    }
+
+    /// Finds a binding of a mouse button or scroll direction matching the `modifier` mask.
+    ///
+    /// # Arguments
+    ///
+    /// * `trigger` - The mouse button or scroll direction.
+    /// * `mask` - The modifier mask held during the mouse event.
+    ///
+    /// # Returns
+    ///
+    /// `Some(Command)` if a matching binding is found, otherwise `None`.
+    pub fn find_mouse_bind(&self, trigger: MouseTrigger, mask: Modifiers) -> Option<Command> {
+        let config = self.inner();
+        config
+            .bindings
+            .values()
+            .flat_map(|binds| binds.all())
+            .find_map(|bind| {
+                (bind.mouse == Some(trigger) && bind.modifiers.matches(mask))
                     .then_some(bind.command.clone())
             })
     }
@@ -470,27 +1074,36 @@ impl Config {
             .map(|windows| {
                 windows
                     .values()
-                    .filter(|params| {
-                        let bundle_match =
-                            params.bundle_id.as_ref().map(|id| id.as_str() == bundle_id);
-                        bundle_match.is_none_or(|m| m) && params.title.is_match(title)
-                    })
+                    .filter(|params| params.matches(title, bundle_id))
                     .cloned()
                     .collect::<Vec<_>>()
             })
             .unwrap_or_default()
     }
 
+    /// Returns the names of the `[windows.*]` rules matching a window, sorted by name.
+    pub fn matching_window_rules(&self, title: &str, bundle_id: &str) -> Vec<String> {
+        let mut names = self
+            .inner()
+            .windows
+            .as_ref()
+            .map(|windows| {
+                windows
+                    .iter()
+                    .filter(|(_, params)| params.matches(title, bundle_id))
+                    .map(|(name, _)| name.clone())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
     /// Returns `true` if any window rule for the given bundle ID requests that the
     /// process be forcibly managed even when macOS reports it as unobservable.
     pub fn should_force_manage_process(&self, process: &dyn ProcessApi) -> bool {
         self.inner().windows.as_ref().is_some_and(|windows| {
-            let Some(bundle_id) = process
-                .application()
-                .as_ref()
-                .and_then(|app| app.bundleIdentifier())
-                .map(|id| id.to_string())
-            else {
+            let Some(bundle_id) = process.bundle_id() else {
                 return false;
             };
             windows.values().any(|params| {
@@ -503,6 +1116,97 @@ impl Config {
         })
     }
 
+    /// Returns `true` if the process is listed in `ignore_apps` and should never be observed.
+    pub fn ignores_process(&self, process: &dyn ProcessApi) -> bool {
+        process.bundle_id().is_some_and(|bundle_id| {
+            self.options()
+                .ignore_apps
+                .iter()
+                .any(|ignored| *ignored == bundle_id)
+        })
+    }
+
+    /// Returns `true` if the application is listed in `presentation_apps`.
+    pub fn is_presentation_app(&self, bundle_id: &str) -> bool {
+        self.options()
+            .presentation_apps
+            .iter()
+            .any(|app| app == bundle_id)
+    }
+
+    /// Returns true if only the displays in `managed_displays` are tiled.
+    pub fn has_managed_displays(&self) -> bool {
+        !self.options().managed_displays.is_empty()
+    }
+
+    /// Returns `true` if the windows of the display with the given UUID and
+    /// number, counted from the left starting at 1, are tiled.
+    pub fn manages_display(&self, uuid: &str, number: usize) -> bool {
+        let managed = &self.options().managed_displays;
+        managed.is_empty()
+            || managed.iter().any(|display| {
+                display.eq_ignore_ascii_case(uuid) || display.parse::<usize>() == Ok(number)
+            })
+    }
+
+    /// Returns `true` if windows go back to their frame from before they were tiled.
+    pub fn restore_original_frames(&self) -> bool {
+        self.options().restore_original_frames.unwrap_or(false)
+    }
+
+    /// Returns `true` if the floating rules are evaluated again on title changes.
+    pub fn float_rules_follow_title(&self) -> bool {
+        self.options().float_rules_follow_title.unwrap_or(false)
+    }
+
+    /// Returns `true` if paneru saves power while on battery.
+    pub fn low_power_on_battery(&self) -> bool {
+        self.options().low_power_on_battery.unwrap_or(false)
+    }
+
+    /// Returns the path of the command socket from the `socket_path` option,
+    /// with a leading `~` expanded.
+    pub fn socket_path(&self) -> Option<PathBuf> {
+        let path = self.options().socket_path.clone()?;
+        if let Some(rest) = path.strip_prefix("~/")
+            && let Ok(home) = env::var("HOME")
+        {
+            return Some(PathBuf::from(home).join(rest));
+        }
+        Some(PathBuf::from(path))
+    }
+
+    /// Returns the token granting other users read-only access to the socket.
+    pub fn socket_query_token(&self) -> Option<String> {
+        self.options()
+            .socket_query_token
+            .clone()
+            .filter(|token| !token.is_empty())
+    }
+
+    /// Returns `true` if clicks on the zoom button toggle the full width of windows.
+    pub fn zoom_button_full_width(&self) -> bool {
+        self.options().zoom_button_full_width.unwrap_or(false)
+    }
+
+    /// Returns the accessibility messaging timeout of an application: its entry
+    /// in `ax_timeouts`, a built-in one for known slow apps, or `ax_timeout_ms`.
+    pub fn ax_timeout(&self, bundle_id: Option<&str>) -> Duration {
+        let options = self.options();
+        let millis = bundle_id.and_then(|bundle_id| {
+            options.ax_timeouts.get(bundle_id).copied().or_else(|| {
+                BUILTIN_AX_TIMEOUTS
+                    .iter()
+                    .find(|(slow, _)| *slow == bundle_id)
+                    .map(|(_, millis)| *millis)
+            })
+        });
+        millis
+            .or(options.ax_timeout_ms)
+            .map_or(DEFAULT_AX_TIMEOUT, Duration::from_millis)
+            .clamp(Duration::from_millis(100), Duration::from_secs(10))
+    }
+
     pub fn sliver_height(&self) -> f64 {
         self.options().sliver_height.unwrap_or(1.0).clamp(0.1, 1.0)
     }
@@ -527,6 +1231,17 @@ impl Config {
         self.options().preset_column_widths
     }
 
+    /// Returns the preset column widths for the number of visible columns,
+    /// falling back to `preset_column_widths`.
+    pub fn size_ratios(&self, columns: usize) -> Vec<f64> {
+        let mut options = self.options();
+        options
+            .size_ratios_by_count
+            .remove(&columns)
+            .filter(|widths| !widths.is_empty())
+            .unwrap_or(options.preset_column_widths)
+    }
+
     pub fn swipe_gesture_direction(&self) -> SwipeGestureDirection {
         let config = self.inner();
         config
@@ -560,6 +1275,21 @@ impl Config {
             .is_none_or(|vertical| vertical)
     }
 
+    /// Returns the commands bound to vertical swipes with the given number of fingers.
+    pub fn swipe_vertical_bindings(&self, fingers: usize) -> Option<VerticalSwipeOptions> {
+        let vertical = self
+            .inner()
+            .swipe
+            .as_ref()
+            .and_then(|swipe| swipe.vertical.clone())
+            .filter(VerticalSwipeOptions::is_bound)?;
+        vertical
+            .fingers
+            .or_else(|| self.swipe_gesture_fingers())
+            .is_none_or(|configured| configured == fingers)
+            .then_some(vertical)
+    }
+
     pub fn has_dim_inactive_color(&self) -> bool {
         let config = self.inner();
         config
@@ -711,6 +1441,17 @@ impl Config {
             .clamp(1.0, 10.0)
     }
 
+    /// Largest distance a strip snaps to a column edge after a swipe.
+    pub fn swipe_snap_threshold(&self) -> i32 {
+        self.inner()
+            .swipe
+            .as_ref()
+            .and_then(|swipe| swipe.snap_threshold)
+            .map_or(i32::MAX, |threshold| {
+                i32::try_from(threshold).unwrap_or(i32::MAX)
+            })
+    }
+
     pub fn mouse_resize_modifier(&self) -> Option<Modifiers> {
         self.options().mouse_resize_modifier
     }
@@ -741,6 +1482,18 @@ impl Config {
             .unwrap_or(MissingWindowBehavior::Ignore)
     }
 
+    /// Returns the rescue hotkeys, which are registered independently of the
+    /// event tap, resolved with the keyboard layout read when the configuration
+    /// was loaded.
+    pub fn rescue_hotkeys(&self) -> Vec<(RescueAction, u8, Modifiers)> {
+        let config = self.inner();
+        config
+            .rescue
+            .clone()
+            .unwrap_or_default()
+            .resolve(&config.virtual_keys)
+    }
+
     pub fn swipe_scroll_modifier(&self) -> Modifiers {
         let config = self.inner();
         config
@@ -805,14 +1558,41 @@ impl Config {
         self.options().window_resize_cycle.unwrap_or(true)
     }
 
+    pub fn retroactive_window_rules(&self) -> bool {
+        self.options().retroactive_window_rules.unwrap_or(true)
+    }
+
     pub fn auto_center(&self) -> bool {
         self.options().auto_center.is_some_and(|center| center)
     }
 
+    pub fn center_focused_column(&self) -> CenterFocusedColumn {
+        self.options().center_focused_column.unwrap_or_default()
+    }
+
+    pub fn focus_strategy(&self) -> FocusStrategy {
+        self.options().focus_strategy.unwrap_or_default()
+    }
+
     pub fn horizontal_mouse_warp(&self) -> Option<i16> {
         self.options().horizontal_mouse_warp
     }
 
+    /// Whether `window_focus_west` and `window_focus_east` stay among the
+    /// columns on screen, wrapping around at the edges.
+    pub fn focus_wrap_visible(&self) -> bool {
+        // Default is disabled.
+        self.options()
+            .focus_wrap_visible
+            .is_some_and(|enabled| enabled)
+    }
+
+    /// Whether `window_focus_west` and `window_focus_east` continue on the
+    /// neighbouring display once they reach the edge of the strip.
+    pub fn focus_wraps_displays(&self) -> bool {
+        self.options().focus_wraps_displays.unwrap_or(false)
+    }
+
     /// Returns `true` if focus should follow the mouse based on the current configuration.
     /// If the configuration option is not set, it defaults to `true`.
     pub fn focus_follows_mouse(&self) -> bool {
@@ -820,6 +1600,11 @@ impl Config {
         self.options().focus_follows_mouse.is_none_or(|ffm| ffm)
     }
 
+    /// Returns `true` if an open sheet or drawer takes the focus in place of its window.
+    pub fn focus_child_windows(&self) -> bool {
+        self.options().focus_child_windows.unwrap_or(true)
+    }
+
     /// Returns `true` if the mouse cursor should follow the focused window based on the current configuration.
     /// If the configuration option is not set, it defaults to `true`.
     pub fn mouse_follows_focus(&self) -> bool {
@@ -831,6 +1616,21 @@ impl Config {
         self.options().horizontal_mouse_warp_offset.unwrap_or(0)
     }
 
+    /// Interval of the periodic checks for changes the OS did not notify about.
+    pub fn poll_interval(&self) -> Duration {
+        self.options()
+            .poll_interval_ms
+            .map_or(DEFAULT_POLL_INTERVAL, Duration::from_millis)
+    }
+
+    /// Interval of the periodic checks shortly after user input.
+    pub fn poll_interval_active(&self) -> Duration {
+        self.options()
+            .poll_interval_active_ms
+            .map_or(DEFAULT_POLL_INTERVAL_ACTIVE, Duration::from_millis)
+            .min(self.poll_interval())
+    }
+
     pub fn reap_empty_workspaces(&self) -> bool {
         // Default is disabled..
         self.options()
@@ -838,6 +1638,38 @@ impl Config {
             .is_some_and(|reap| reap)
     }
 
+    /// Whether empty macOS spaces are closed, after `empty_space_grace` and
+    /// keeping `keep_empty_spaces` of them on each display.
+    pub fn close_empty_spaces(&self) -> bool {
+        self.options().close_empty_spaces.unwrap_or(false)
+    }
+
+    /// How long a space stays empty before it is closed.
+    pub fn empty_space_grace(&self) -> Duration {
+        Duration::from_millis(self.options().empty_space_grace_ms.unwrap_or(10_000))
+    }
+
+    /// How many empty spaces each display keeps.
+    pub fn keep_empty_spaces(&self) -> usize {
+        self.options().keep_empty_spaces.unwrap_or(1)
+    }
+
+    /// Checks the built-in rules floating system utility windows, unless they
+    /// were disabled with `disable_builtin_rules`.
+    pub fn builtin_floating(&self, bundle_id: &str, subrole: &str) -> bool {
+        !self
+            .options()
+            .disable_builtin_rules
+            .is_some_and(|disabled| disabled)
+            && (BUILTIN_FLOATING_BUNDLES.contains(&bundle_id)
+                || BUILTIN_FLOATING_SUBROLES.contains(&subrole))
+    }
+
+    pub fn notifications_enabled(&self) -> bool {
+        // Default is enabled.
+        self.options().notifications.is_none_or(|enabled| enabled)
+    }
+
     pub fn native_tabs_enabled(&self) -> bool {
         // Default is enabled.
         !self
@@ -876,6 +1708,37 @@ impl Config {
             .insert_windows_mid_strip
             .is_some_and(|enabled| enabled)
     }
+
+    /// Number of columns after which a strip spills over to the next virtual workspace.
+    pub fn strip_max_columns(&self) -> Option<usize> {
+        // Default is disabled.
+        self.options()
+            .strip_max_columns
+            .filter(|columns| *columns > 0)
+    }
+
+    /// Total width of the columns, in display widths, after which a strip
+    /// spills over to the next virtual workspace.
+    pub fn strip_max_width(&self) -> Option<f64> {
+        // Default is disabled.
+        self.options()
+            .strip_max_width
+            .filter(|widths| *widths >= 1.0)
+    }
+
+    pub fn strip_overflow_column(&self) -> OverflowColumn {
+        self.options().strip_overflow_column.unwrap_or_default()
+    }
+
+    pub fn crash_journal_size(&self) -> Option<usize> {
+        // Default is disabled.
+        self.options().crash_journal_size.filter(|size| *size > 0)
+    }
+
+    pub fn log_json(&self) -> bool {
+        // Default is disabled.
+        self.options().log_json.is_some_and(|json| json)
+    }
 }
 
 fn parse_hex_color(hex: &str) -> (f64, f64, f64) {
@@ -962,6 +1825,19 @@ struct InnerConfig {
     swipe: Option<swipe::SwipeOptions>,
     padding: Option<padding::PaddingOptions>,
     restore: Option<RestoreOptions>,
+    rescue: Option<RescueOptions>,
+    /// Files this configuration was read from.
+    #[serde(skip)]
+    files: Vec<PathBuf>,
+    /// Name of the profile applied on top of the files.
+    #[serde(skip)]
+    profile: Option<String>,
+    /// Bindings of every profile of the files, by profile name.
+    #[serde(skip)]
+    profile_bindings: BTreeMap<String, Vec<BindingInfo>>,
+    /// Key names of the keyboard layout, read when the configuration was loaded.
+    #[serde(skip)]
+    virtual_keys: Vec<(String, u8)>,
 }
 
 impl InnerConfig {
@@ -978,6 +1854,39 @@ impl InnerConfig {
         InnerConfig::parse_config(input)
     }
 
+    /// Loads the configuration `input` with the overrides of `profile` applied,
+    /// and resolves the bindings of every profile for `paneru query keybindings`.
+    /// The keyboard layout is read once, which needs the main thread, and kept
+    /// for the key combinations of `send_keys`.
+    fn load(input: &str, profile: Option<&str>) -> Result<InnerConfig> {
+        let virtual_keys = generate_virtual_keymap();
+        let mut config = InnerConfig::parse_config_with_virtual_keys(
+            &select_profile(input, profile)?,
+            &virtual_keys,
+        )?;
+        let names = toml::from_str::<toml::Table>(input)?
+            .get(PROFILE_KEY)
+            .and_then(toml::Value::as_table)
+            .map(|profiles| profiles.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        for name in names {
+            let other = select_profile(input, Some(&name)).and_then(|input| {
+                InnerConfig::parse_config_with_virtual_keys(&input, &virtual_keys)
+            });
+            match other {
+                Ok(other) => {
+                    config
+                        .profile_bindings
+                        .insert(name, describe_bindings(&other.bindings));
+                }
+                Err(err) => error!("{}: profile '{name}': {err}", function_name!()),
+            }
+        }
+        config.profile = profile.map(str::to_string);
+        config.virtual_keys = virtual_keys;
+        Ok(config)
+    }
+
     /// Parses the configuration from a string `input`.
     /// It populates the `code` and `command` fields of `Keybinding` by looking up virtual keys and literal keycodes.
     ///
@@ -1009,9 +1918,12 @@ impl InnerConfig {
             for binding in bindings.all_mut() {
                 binding.command = parse_command(&argv)?;
 
-                if let Some(code) = keycode_for_key_name(&binding.key, virtual_keys) {
+                if let Some(trigger) = MouseTrigger::from_key_name(&binding.key) {
+                    binding.mouse = Some(trigger);
+                    debug!("bind: {binding:?}");
+                } else if let Some(code) = keycode_for_key_name(&binding.key, virtual_keys) {
                     binding.code = code;
-                    info!("bind: {binding:?}");
+                    debug!("bind: {binding:?}");
                 } else {
                     error!("{}: invalid key '{}'", function_name!(), &binding.key);
                 }
@@ -1043,6 +1955,54 @@ impl InnerConfig {
     }
 }
 
+/// Controls whether the strip scrolls to center the focused column.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CenterFocusedColumn {
+    /// Scroll only as far as needed to bring the focused column into view.
+    #[default]
+    Never,
+    /// Center the focused column when the strip overflows the display and the
+    /// column is not entirely on screen.
+    OnOverflow,
+    /// Keep the focused column centered, letting the other columns overflow both edges.
+    Always,
+}
+
+/// Which column leaves a strip which grew past `strip_max_columns` or `strip_max_width`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowColumn {
+    /// The leftmost column, usually the one opened first.
+    #[default]
+    First,
+    /// The rightmost column, usually the one opened last.
+    Last,
+}
+
+/// Animation settings of a single display, see `display_animations`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct DisplayAnimation {
+    /// Overrides `animation_speed` on this display.
+    pub animation_speed: Option<f64>,
+    /// Moves and resizes windows on this display instantly when `false`.
+    pub animations: Option<bool>,
+}
+
+/// How windows are given the keyboard focus.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusStrategy {
+    /// Post synthesized events where the macOS version supports them, and stop
+    /// after the first failure.
+    #[default]
+    Auto,
+    /// Always post synthesized events, which focus windows without raising them.
+    EventRecord,
+    /// Only bring the application to the front, raising the focused window.
+    FrontProcess,
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum MissingWindowBehavior {
@@ -1056,6 +2016,59 @@ pub struct RestoreOptions {
     pub missing_windows: Option<MissingWindowBehavior>,
 }
 
+/// Bindings registered as Carbon hotkeys instead of going through the event
+/// tap, so they keep working when the tap gets disabled or dies.
+#[derive(Clone, Debug, Deserialize, Default)]
+pub struct RescueOptions {
+    pub quit: Option<String>,
+    pub restart: Option<String>,
+    pub reenable_tap: Option<String>,
+}
+
+/// The actions available through the rescue hotkeys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RescueAction {
+    Quit,
+    Restart,
+    ReenableTap,
+}
+
+impl RescueOptions {
+    /// Resolves the rescue bindings into `(action, keycode, modifiers)` triples.
+    /// Unset bindings use the defaults, an empty string disables a binding.
+    fn resolve(&self, virtual_keys: &[(String, u8)]) -> Vec<(RescueAction, u8, Modifiers)> {
+        [
+            (
+                RescueAction::Quit,
+                self.quit.as_deref(),
+                "ctrl+alt+cmd+shift-q",
+            ),
+            (
+                RescueAction::Restart,
+                self.restart.as_deref(),
+                "ctrl+alt+cmd+shift-r",
+            ),
+            (
+                RescueAction::ReenableTap,
+                self.reenable_tap.as_deref(),
+                "ctrl+alt+cmd+shift-t",
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(action, input, default)| {
+            let input = input.unwrap_or(default);
+            if input.is_empty() {
+                return None;
+            }
+            resolve_keybinding_str(input, virtual_keys)
+                .inspect_err(|err| error!("rescue: {err}"))
+                .ok()
+                .map(|(code, modifiers)| (action, code, modifiers))
+        })
+        .collect()
+    }
+}
+
 /// `MainOptions` represents the primary configuration options for the window manager.
 /// These options control various behaviors such as mouse focus, gesture recognition, and window animation.
 #[derive(Deserialize, Clone, Debug, Default)]
@@ -1064,6 +2077,9 @@ pub struct MainOptions {
     pub focus_follows_mouse: Option<bool>,
     /// Enables or disables mouse follows focus behavior.
     pub mouse_follows_focus: Option<bool>,
+    /// Gives the focus to an open sheet or drawer of a focused window, instead
+    /// of the window itself. Default: true.
+    pub focus_child_windows: Option<bool>,
     /// Warps the mouse to the closest screen when at the edge.
     pub horizontal_mouse_warp: Option<i16>,
     /// Vertical pixel offset applied to the warp landing position, signed by
@@ -1075,10 +2091,27 @@ pub struct MainOptions {
     /// A list of preset column widths (as ratios) used for resizing windows.
     #[serde(default = "default_preset_column_widths")]
     pub preset_column_widths: Vec<f64>,
+    /// Preset column widths used instead of `preset_column_widths` when the
+    /// given number of columns is visible, keyed by the column count.
+    #[serde(default, deserialize_with = "deserialize_size_ratios_by_count")]
+    pub size_ratios_by_count: BTreeMap<usize, Vec<f64>>,
     /// The animation speed for window movements in pixels per second.
     pub animation_speed: Option<f64>,
+    /// Animation settings of displays, keyed by display UUID.
+    #[serde(default)]
+    pub display_animations: HashMap<String, DisplayAnimation>,
     /// Automatically center the window when switching focus with keyboard.
     pub auto_center: Option<bool>,
+    /// When to scroll the strip so that the focused column sits in the middle of the display.
+    pub center_focused_column: Option<CenterFocusedColumn>,
+    /// How windows are given the keyboard focus.
+    pub focus_strategy: Option<FocusStrategy>,
+    /// Make focusing west and east cycle among the columns on screen instead
+    /// of scrolling to hidden ones. Default: false.
+    pub focus_wrap_visible: Option<bool>,
+    /// Make focusing west and east at the edge of the strip continue with the
+    /// nearest column of the display on that side. Default: false.
+    pub focus_wraps_displays: Option<bool>,
     /// Height of off-screen window slivers as a ratio (0.0–1.0) of the display height.
     /// Lower values hide the window's corner radius at screen edges.
     /// Default: 1.0 (full height).
@@ -1131,6 +2164,33 @@ pub struct MainOptions {
     /// Default: true.
     pub reap_empty_workspaces: Option<bool>,
 
+    /// Close macOS spaces which stayed empty for `empty_space_grace_ms`,
+    /// keeping `keep_empty_spaces` of them on each display. Default: false.
+    pub close_empty_spaces: Option<bool>,
+
+    /// How long in milliseconds a space has to be empty before it is closed.
+    /// Default: 10000.
+    pub empty_space_grace_ms: Option<u64>,
+
+    /// Number of empty spaces left open on each display. Default: 1.
+    pub keep_empty_spaces: Option<usize>,
+
+    /// Interval in milliseconds of the periodic checks for window and space
+    /// changes which were not notified. Default: 1000.
+    pub poll_interval_ms: Option<u64>,
+
+    /// Interval in milliseconds of the same checks during the two seconds after
+    /// user input. Default: 250.
+    pub poll_interval_active_ms: Option<u64>,
+
+    /// Show macOS notifications for problems which need the user's attention,
+    /// like a lost permission or an invalid configuration. Default: true.
+    pub notifications: Option<bool>,
+
+    /// Disable the built-in rules which float system utility windows, like
+    /// System Settings panels and system alerts. Default: false.
+    pub disable_builtin_rules: Option<bool>,
+
     /// Disable detection of native macOS tabs. When set, newly-spawned windows are
     /// never auto-merged into a tab group with an existing same-app sibling.
     /// Default: false.
@@ -1146,6 +2206,82 @@ pub struct MainOptions {
     /// shifting the rest) instead of appending it to the end of the strip.
     /// Off by default.
     pub insert_windows_mid_strip: Option<bool>,
+
+    /// Move a column to the next virtual workspace, creating it if needed,
+    /// once a strip has more than this many columns. Off by default.
+    pub strip_max_columns: Option<usize>,
+
+    /// Move a column to the next virtual workspace once the columns of a strip
+    /// are wider than this many display widths. Off by default.
+    pub strip_max_width: Option<f64>,
+
+    /// Which column is moved when a strip overflows. Default: first.
+    pub strip_overflow_column: Option<OverflowColumn>,
+
+    /// Bundle identifiers of applications which are never observed or managed,
+    /// e.g. apps whose accessibility calls stall on launch.
+    #[serde(default)]
+    pub ignore_apps: Vec<String>,
+
+    /// Displays whose windows are tiled, by UUID or by number counted from the
+    /// left starting at 1. Windows on other displays are left alone. Default:
+    /// empty, all displays are managed.
+    #[serde(default)]
+    pub managed_displays: Vec<String>,
+
+    /// Bundle identifiers of applications, e.g. Keynote or games, which
+    /// suspend window management and keybindings while they are frontmost.
+    #[serde(default)]
+    pub presentation_apps: Vec<String>,
+
+    /// How long in milliseconds an application may take to answer an
+    /// accessibility message before the call is given up. Default: 1000.
+    pub ax_timeout_ms: Option<u64>,
+
+    /// Accessibility timeouts in milliseconds of slow applications, keyed by
+    /// bundle identifier. A few known slow apps get 3000 by default.
+    #[serde(default)]
+    pub ax_timeouts: HashMap<String, u64>,
+
+    /// Re-evaluate the window rules of existing windows when the configuration
+    /// is reloaded, applying changed `floating`, `width` and `index` rules.
+    /// Default: true.
+    pub retroactive_window_rules: Option<bool>,
+
+    /// Number of recent events kept in the crash journal, which is written to
+    /// the state directory if the daemon panics. Disabled when unset or 0.
+    pub crash_journal_size: Option<usize>,
+
+    /// Emit log lines as JSON objects instead of the compact text format.
+    /// Only read on startup. Default: false.
+    pub log_json: Option<bool>,
+
+    /// Move windows back to the frame they had before they were tiled, when
+    /// they stop being managed and when Paneru exits. Default: false.
+    pub restore_original_frames: Option<bool>,
+
+    /// Evaluate the floating rules again when a window changes its title, so
+    /// a placeholder window matched by a rule stops floating once it shows its
+    /// document, and the other way around. Default: false.
+    pub float_rules_follow_title: Option<bool>,
+
+    /// Save power while the Mac runs on its battery, as in the macOS Low Power
+    /// Mode. Default: false.
+    pub low_power_on_battery: Option<bool>,
+
+    /// Path of the command socket, shared by the daemon and the CLI. Only read
+    /// on startup. Default: `paneru.sock` in `$XDG_RUNTIME_DIR`, or else in
+    /// `~/Library/Application Support/paneru`.
+    pub socket_path: Option<String>,
+
+    /// Token letting other users run read-only requests, like queries and
+    /// subscriptions, on the command socket. Only read on startup. Default: none,
+    /// only the user running paneru may connect.
+    pub socket_query_token: Option<String>,
+
+    /// Clicking the green zoom button of a tiled window toggles its full width,
+    /// instead of the zoom of macOS. Default: false.
+    pub zoom_button_full_width: Option<bool>,
 }
 
 /// Returns a default set of column widths.
@@ -1153,12 +2289,123 @@ pub fn default_preset_column_widths() -> Vec<f64> {
     vec![0.25, 0.33333, 0.50, 0.66667, 0.75]
 }
 
+/// A mouse button or scroll wheel direction, bound in place of a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseTrigger {
+    /// Numbered from one like the key names, i.e. `mouse3` is the middle button.
+    Button(u8),
+    ScrollUp,
+    ScrollDown,
+}
+
+impl MouseTrigger {
+    /// Parses key names like `mouse4` or `scroll_up`. The left and right
+    /// buttons can not be bound.
+    fn from_key_name(key: &str) -> Option<Self> {
+        match key {
+            "scroll_up" => Some(MouseTrigger::ScrollUp),
+            "scroll_down" => Some(MouseTrigger::ScrollDown),
+            _ => key
+                .strip_prefix("mouse")?
+                .parse::<u8>()
+                .ok()
+                .filter(|button| *button >= 3)
+                .map(MouseTrigger::Button),
+        }
+    }
+}
+
+/// A binding as reported by `paneru query keybindings`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BindingInfo {
+    /// Name of the command in `[bindings]`, e.g. `window_focus_west`.
+    pub command: String,
+    /// The binding in its normalized configuration form, e.g. `alt+shift-h`.
+    pub binding: String,
+    pub key: String,
+    /// Raw keycode of the key, `None` for mouse bindings.
+    pub keycode: Option<u8>,
+    pub modifiers: Vec<&'static str>,
+}
+
+/// The bindings in effect and the bindings of each profile.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct KeybindingsReport {
+    pub active_profile: Option<String>,
+    pub bindings: Vec<BindingInfo>,
+    pub profiles: BTreeMap<String, Vec<BindingInfo>>,
+}
+
+/// Names of the modifiers in the form `parse_modifiers` accepts, preferring
+/// the side-less name when both sides are set.
+fn modifier_names(modifiers: Modifiers) -> Vec<&'static str> {
+    let groups = [
+        (
+            Modifiers::LCTRL,
+            Modifiers::RCTRL,
+            ["ctrl", "lctrl", "rctrl"],
+        ),
+        (Modifiers::LALT, Modifiers::RALT, ["alt", "lalt", "ralt"]),
+        (
+            Modifiers::LSHIFT,
+            Modifiers::RSHIFT,
+            ["shift", "lshift", "rshift"],
+        ),
+        (Modifiers::LCMD, Modifiers::RCMD, ["cmd", "lcmd", "rcmd"]),
+    ];
+    let mut names = groups
+        .into_iter()
+        .filter_map(|(left, right, [both, left_name, right_name])| {
+            match (modifiers.contains(left), modifiers.contains(right)) {
+                (true, true) => Some(both),
+                (true, false) => Some(left_name),
+                (false, true) => Some(right_name),
+                (false, false) => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    if modifiers.contains(Modifiers::FN) {
+        names.push("fn");
+    }
+    if modifiers.contains(Modifiers::CAPS) {
+        names.push("caps");
+    }
+    names
+}
+
+fn describe_bindings(bindings: &HashMap<String, OneOrMore>) -> Vec<BindingInfo> {
+    let mut described = bindings
+        .iter()
+        .flat_map(|(command, binds)| {
+            binds.all().into_iter().map(move |bind| {
+                let modifiers = modifier_names(bind.modifiers);
+                let binding = if modifiers.is_empty() {
+                    bind.key.clone()
+                } else {
+                    format!("{}-{}", modifiers.join("+"), bind.key)
+                };
+                BindingInfo {
+                    command: command.clone(),
+                    binding,
+                    key: bind.key.clone(),
+                    keycode: bind.mouse.is_none().then_some(bind.code),
+                    modifiers,
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    described.sort_by(|a, b| (&a.command, &a.binding).cmp(&(&b.command, &b.binding)));
+    described
+}
+
 /// `Keybinding` represents a keyboard shortcut and the command it triggers.
 /// It includes the key, its raw keycode, modifier keys, and the associated command.
+/// Mouse buttons and scroll directions are bound the same way, setting `mouse`.
 #[derive(Debug)]
 pub struct Keybinding {
     pub key: String,
     pub code: u8,
+    pub mouse: Option<MouseTrigger>,
     pub modifiers: Modifiers,
     pub command: Command,
 }
@@ -1194,6 +2441,7 @@ impl<'de> Deserialize<'de> for Keybinding {
         Ok(Keybinding {
             key: key.unwrap().to_string(),
             code: 0,
+            mouse: None,
             modifiers,
             command: Command::Quit,
         })
@@ -1219,6 +2467,9 @@ pub struct WindowParams {
     pub vertical_padding: Option<i32>,
     pub horizontal_padding: Option<i32>,
     pub dont_focus: Option<bool>,
+    /// If `true`, the window neither takes the focus nor moves the strip when
+    /// it opens, and focus it grabs right after goes back to the focused window.
+    pub prevent_focus_steal: Option<bool>,
     /// An optional initial width ratio (0.0–1.0) relative to the display width.
     /// Overrides the default column width when the window is first managed.
     pub width: Option<f64>,
@@ -1227,6 +2478,32 @@ pub struct WindowParams {
     pub grid: Option<String>,
     /// Per-window override for the active window border corner radius.
     pub border_radius: Option<f64>,
+    /// Minimum width which resizing and the layout never go below: pixels,
+    /// or a ratio of the display width.
+    pub min_width: Option<SizeLimit>,
+    /// Minimum height which resizing and the layout never go below: pixels,
+    /// or a ratio of the display height.
+    pub min_height: Option<SizeLimit>,
+    /// If `true`, the application closes its windows without asking, so
+    /// `window_close_others` may close them. Other windows are left open.
+    pub close_with_others: Option<bool>,
+    /// Number of the macOS space, counting from 1 on the display, which new
+    /// windows are moved to when they open.
+    pub space: Option<usize>,
+    /// If `true`, switches to the space of `space` along with the new window.
+    pub follow: Option<bool>,
+    /// Order in which windows give up width when the columns do not fit the
+    /// display, or height when a stacked column is too short for all of them:
+    /// lower values shrink first, and `0` never shrinks below the size the
+    /// window asked for. Unset counts as `1`.
+    pub shrink_priority: Option<u32>,
+    /// Preset widths which `window_resize` cycles through for this window,
+    /// instead of `preset_column_widths`.
+    pub size_ratios: Option<Vec<f64>>,
+    /// Invisible margins included in the frame the window reports: a single
+    /// value for all sides, `[top, right, bottom, left]`, or `"auto"`.
+    #[serde(default, deserialize_with = "deserialize_frame_inset")]
+    pub frame_inset: Option<FrameInsetOption>,
     /// Keyboard shortcuts that should be passed through to this app instead of
     /// being intercepted by paneru. Uses the same `"modifier+modifier-key"`
     /// format as `[bindings]` (e.g. `"ctrl+alt-h"`).
@@ -1249,14 +2526,28 @@ impl WindowParams {
             vertical_padding: None,
             horizontal_padding: None,
             dont_focus: None,
+            prevent_focus_steal: None,
             width: None,
             grid: None,
             border_radius: None,
+            min_width: None,
+            min_height: None,
+            close_with_others: None,
+            space: None,
+            follow: None,
+            shrink_priority: None,
+            size_ratios: None,
+            frame_inset: None,
             bindings_passthrough: Vec::new(),
             parsed_passthrough: Vec::new(),
         }
     }
 
+    /// A rule matches when its bundle ID (if any) and title regex match.
+    fn matches(&self, title: &str, bundle_id: &str) -> bool {
+        self.bundle_id.as_deref().is_none_or(|id| id == bundle_id) && self.title.is_match(title)
+    }
+
     /// Returns the resolved passthrough keybindings for this window rule.
     pub fn passthrough_keys(&self) -> &[(u8, Modifiers)] {
         &self.parsed_passthrough
@@ -1282,6 +2573,93 @@ impl WindowParams {
     }
 }
 
+/// A minimum window extent, either in pixels or as a ratio of the display.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum SizeLimit {
+    Pixels(i32),
+    Ratio(f64),
+}
+
+impl SizeLimit {
+    /// Returns the limit in pixels, relative to the `extent` of the display.
+    pub fn to_pixels(self, extent: i32) -> i32 {
+        match self {
+            SizeLimit::Pixels(pixels) => pixels.max(0),
+            SizeLimit::Ratio(ratio) => (f64::from(extent) * ratio.clamp(0.0, 1.0)).round() as i32,
+        }
+    }
+}
+
+/// How the frame inset of a window is determined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameInsetOption {
+    /// Compare the reported frame with the window server bounds.
+    Auto,
+    Fixed(FrameInset),
+}
+
+fn deserialize_frame_inset<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<FrameInsetOption>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawInset {
+        Name(String),
+        Uniform(i32),
+        Sides([i32; 4]),
+    }
+
+    let option = match RawInset::deserialize(deserializer)? {
+        RawInset::Name(name) if name == "auto" => FrameInsetOption::Auto,
+        RawInset::Name(name) => {
+            return Err(de::Error::custom(format!("invalid frame_inset '{name}'")));
+        }
+        RawInset::Uniform(all) => FrameInsetOption::Fixed(FrameInset {
+            top: all,
+            right: all,
+            bottom: all,
+            left: all,
+        }),
+        RawInset::Sides([top, right, bottom, left]) => FrameInsetOption::Fixed(FrameInset {
+            top,
+            right,
+            bottom,
+            left,
+        }),
+    };
+    Ok(Some(option))
+}
+
+/// Deserializes the `size_ratios_by_count` table, whose keys have to be column
+/// counts of at least one.
+fn deserialize_size_ratios_by_count<'de, D>(
+    deserializer: D,
+) -> std::result::Result<BTreeMap<usize, Vec<f64>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    HashMap::<String, Vec<f64>>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(count, widths)| {
+            count
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|count| *count > 0)
+                .map(|count| (count, widths))
+                .ok_or_else(|| {
+                    de::Error::custom(format!(
+                        "invalid column count '{count}' in size_ratios_by_count"
+                    ))
+                })
+        })
+        .collect()
+}
+
 /// Deserializes a regular expression from a string for window titles.
 fn deserialize_title<'de, D>(deserializer: D) -> std::result::Result<Regex, D::Error>
 where
@@ -1365,6 +2743,8 @@ fn parse_modifiers(input: &str) -> Result<Modifiers> {
             "lctrl" => Modifiers::LCTRL,
             "rctrl" => Modifiers::RCTRL,
             "fn" => Modifiers::FN,
+            "hyper" => Modifiers::HYPER,
+            "caps" => Modifiers::CAPS,
             _ => {
                 return Err(Error::InvalidConfig(format!(
                     "{}: Invalid modifier: {modifier}",
@@ -1810,6 +3190,16 @@ index = 1
     assert_eq!(defaults.swipe_deceleration(), 4.0);
 }
 
+#[test]
+fn test_parse_hyper_and_caps_modifiers() {
+    assert_eq!(parse_modifiers("hyper").unwrap(), Modifiers::HYPER);
+    assert_eq!(
+        parse_modifiers("caps + alt").unwrap(),
+        Modifiers::CAPS | Modifiers::ALT
+    );
+    assert!(parse_modifiers("capslock").is_err());
+}
+
 #[test]
 fn test_config_parsing_absolute_virtual_workspace_bindings() {
     let input = r#"
@@ -1866,120 +3256,384 @@ fn test_parse_resize_commands() {
 }
 
 #[test]
-fn test_parse_restart_command() {
+fn test_parse_grow_edge_commands() {
     assert!(matches!(
-        parse_command(&["restart"]).unwrap(),
-        Command::Restart
+        parse_command(&["window", "grow", "east", "50"]).unwrap(),
+        Command::Window(Operation::GrowEdge(
+            Direction::East,
+            ResizeAmount::Pixels(50)
+        ))
+    ));
+    assert!(matches!(
+        parse_command(&["window", "shrink", "north", "10%"]).unwrap(),
+        Command::Window(Operation::GrowEdge(Direction::North, ResizeAmount::Percent(p)))
+            if (p + 10.0).abs() < f64::EPSILON
+    ));
+    assert!(matches!(
+        parse_command(&["window", "grow", "west"]).unwrap(),
+        Command::Window(Operation::GrowEdge(Direction::West, amount))
+            if amount == DEFAULT_GROW_AMOUNT
     ));
+    assert!(parse_command(&["window", "grow", "east", "0"]).is_err());
+    assert!(parse_command(&["window", "grow", "east", "-5%"]).is_err());
+    assert!(parse_command(&["window", "grow", "sideways"]).is_err());
 }
 
 #[test]
-fn test_parse_absolute_virtual_workspace_commands() {
+fn test_parse_promote_commands() {
     assert!(matches!(
-        parse_command(&["window", "virtualnum", "3"]).unwrap(),
-        Command::Window(Operation::VirtualNumber(2))
+        parse_command(&["window", "promote"]).unwrap(),
+        Command::Window(Operation::Promote)
     ));
-    assert!(parse_command(&["workspace", "virtual", "3"]).is_err());
     assert!(matches!(
-        parse_command(&["window", "virtualmove", "3"]).unwrap(),
-        Command::Window(Operation::VirtualMoveNumber(2, MoveFocus::Follow))
+        parse_command(&["window", "demote"]).unwrap(),
+        Command::Window(Operation::Demote)
     ));
+}
+
+#[test]
+fn test_parse_window_pull() {
     assert!(matches!(
-        parse_command(&["window", "virtualsend", "3"]).unwrap(),
-        Command::Window(Operation::VirtualMoveNumber(2, MoveFocus::Stay))
+        parse_command(&["window", "pull", "team", "chat"]).unwrap(),
+        Command::Window(Operation::Pull(query)) if query == "team chat"
     ));
+    assert!(parse_command(&["window", "pull"]).is_err());
+}
+
+#[test]
+fn test_parse_zoom_native() {
     assert!(matches!(
-        parse_command(&["window", "virtualmovenum", "3"]).unwrap(),
-        Command::Window(Operation::VirtualMoveNumber(2, MoveFocus::Follow))
+        parse_command(&["window", "zoom", "native"]).unwrap(),
+        Command::Window(Operation::ZoomNative)
     ));
+    assert!(parse_command(&["window", "zoom"]).is_err());
+}
+
+#[test]
+fn test_parse_fit_display() {
     assert!(matches!(
-        parse_command(&["window", "virtualsendnum", "3"]).unwrap(),
-        Command::Window(Operation::VirtualMoveNumber(2, MoveFocus::Stay))
+        parse_command(&["window", "fit", "display"]).unwrap(),
+        Command::Window(Operation::FitDisplay)
     ));
+    assert!(parse_command(&["window", "fit"]).is_err());
 }
 
 #[test]
-#[allow(clippy::float_cmp)]
-fn test_grid_ratios() {
-    use regex::Regex;
-
-    let make = |grid: Option<&str>| WindowParams {
-        title: Regex::new(".*").unwrap(),
-        bundle_id: None,
-        floating: None,
-        manage: None,
-        index: None,
-        vertical_padding: None,
-        horizontal_padding: None,
-        dont_focus: None,
-        width: None,
-        grid: grid.map(Into::into),
-        border_radius: None,
-        bindings_passthrough: vec![],
-        parsed_passthrough: vec![],
-    };
-
-    // Standard 2x2 grid, cell (1,1), span 1x1 → bottom-right quarter.
-    assert_eq!(
-        make(Some("2:2:1:1:1:1")).grid_ratios(),
-        Some((0.5, 0.5, 0.5, 0.5))
-    );
-
-    // 3x3 grid, cell (0,0), span 2x1 → top-left, 2/3 width, 1/3 height.
-    assert_eq!(
-        make(Some("3:3:0:0:2:1")).grid_ratios(),
-        Some((0.0, 0.0, 2.0 / 3.0, 1.0 / 3.0))
-    );
-
-    // Full screen: 1x1 grid, cell (0,0), span 1x1.
-    assert_eq!(
-        make(Some("1:1:0:0:1:1")).grid_ratios(),
-        Some((0.0, 0.0, 1.0, 1.0))
-    );
-
-    // Invalid: too few parts.
-    assert_eq!(make(Some("2:2:1:1")).grid_ratios(), None);
+fn test_parse_stack_all() {
+    assert!(matches!(
+        parse_command(&["window", "stack", "all"]).unwrap(),
+        Command::Window(Operation::StackAll(true))
+    ));
+    assert!(matches!(
+        parse_command(&["window", "unstack", "all"]).unwrap(),
+        Command::Window(Operation::StackAll(false))
+    ));
+    assert!(matches!(
+        parse_command(&["window", "unstack"]).unwrap(),
+        Command::Window(Operation::Stack(false))
+    ));
+}
 
-    // Invalid: zero columns.
-    assert_eq!(make(Some("0:2:0:0:1:1")).grid_ratios(), None);
+#[test]
+fn test_parse_pin_toggle() {
+    assert!(matches!(
+        parse_command(&["window", "pin", "toggle"]).unwrap(),
+        Command::Window(Operation::TogglePin)
+    ));
+    assert!(parse_command(&["window", "pin"]).is_err());
+}
 
-    // No grid set.
-    assert_eq!(make(None).grid_ratios(), None);
+#[test]
+fn test_parse_layout_lock_toggle() {
+    assert!(matches!(
+        parse_command(&["layout", "lock", "toggle"]).unwrap(),
+        Command::ToggleLayoutLock
+    ));
+    assert!(parse_command(&["layout", "lock"]).is_err());
 }
 
 #[test]
-fn test_parse_hex_color_valid() {
-    assert_eq!(
-        parse_hex_color("#89b4fa"),
-        (
-            f64::from(0x89) / 255.0,
-            f64::from(0xb4) / 255.0,
-            f64::from(0xfa) / 255.0
-        )
-    );
-    assert_eq!(parse_hex_color("#000000"), (0.0, 0.0, 0.0));
-    assert_eq!(parse_hex_color("#FFFFFF"), (1.0, 1.0, 1.0));
-    assert_eq!(parse_hex_color("#FF0000"), (1.0, 0.0, 0.0));
+fn test_parse_send_keys() {
+    assert!(matches!(
+        parse_command(&["send", "keys", "cmd+shift-t"]).unwrap(),
+        Command::SendKeys(keys) if keys == "cmd+shift-t"
+    ));
+    assert!(matches!(
+        parse_command(&["send", "keys", "cmd", "-", "w"]).unwrap(),
+        Command::SendKeys(keys) if keys == "cmd-w"
+    ));
+    assert!(parse_command(&["send", "keys", "cmmd-w"]).is_err());
+    assert!(parse_command(&["send", "keys"]).is_err());
 }
 
+/// The key combinations of `send_keys` resolve with the keyboard layout read
+/// when the configuration was loaded.
 #[test]
-fn test_parse_hex_color_no_hash() {
+fn test_send_keys_use_loaded_layout() {
+    let config = Config {
+        inner: Arc::new(ArcSwap::from_pointee(InnerConfig {
+            virtual_keys: vec![("t".to_string(), 99)],
+            ..Default::default()
+        })),
+    };
     assert_eq!(
-        parse_hex_color("89b4fa"),
-        (
-            f64::from(0x89) / 255.0,
-            f64::from(0xb4) / 255.0,
-            f64::from(0xfa) / 255.0
-        )
+        config.resolve_key_combination("cmd+shift-t").unwrap(),
+        (99, Modifiers::CMD | Modifiers::SHIFT)
     );
-    assert_eq!(parse_hex_color("FF0000"), (1.0, 0.0, 0.0));
+    assert!(config.resolve_key_combination("cmd-nokey").is_err());
 }
 
 #[test]
-fn test_parse_hex_color_invalid_length() {
-    // Short strings fall back to white.
-    assert_eq!(parse_hex_color("#FFF"), (1.0, 1.0, 1.0));
-    assert_eq!(parse_hex_color(""), (1.0, 1.0, 1.0));
+fn test_parse_low_power() {
+    assert!(matches!(
+        parse_command(&["low", "power", "on"]).unwrap(),
+        Command::LowPower(Some(true))
+    ));
+    assert!(matches!(
+        parse_command(&["low", "power", "auto"]).unwrap(),
+        Command::LowPower(None)
+    ));
+    assert!(parse_command(&["low", "power"]).is_err());
+}
+
+#[test]
+fn test_parse_pause() {
+    assert!(matches!(
+        parse_command(&["pause"]).unwrap(),
+        Command::Pause(false)
+    ));
+    assert!(matches!(
+        parse_command(&["pause", "keep", "bindings"]).unwrap(),
+        Command::Pause(true)
+    ));
+    assert!(matches!(
+        parse_command(&["resume"]).unwrap(),
+        Command::Resume
+    ));
+    assert!(parse_command(&["pause", "now"]).is_err());
+}
+
+#[test]
+fn test_parse_column_index() {
+    assert!(matches!(
+        parse_command(&["window", "focus", "index", "3"]).unwrap(),
+        Command::Window(Operation::FocusIndex(2))
+    ));
+    assert!(matches!(
+        parse_command(&["window", "move", "index", "1"]).unwrap(),
+        Command::Window(Operation::MoveIndex(0))
+    ));
+    assert!(parse_command(&["window", "focus", "index", "0"]).is_err());
+    assert!(parse_command(&["window", "focus", "index"]).is_err());
+    assert!(parse_command(&["window", "move", "index", "last"]).is_err());
+}
+
+#[test]
+fn test_parse_layout_slots() {
+    assert!(matches!(
+        parse_command(&["layout", "save", "code", "review"]).unwrap(),
+        Command::LayoutSave(name) if name == "code_review"
+    ));
+    assert!(matches!(
+        parse_command(&["layout", "load", "coding"]).unwrap(),
+        Command::LayoutLoad(name) if name == "coding"
+    ));
+    assert!(parse_command(&["layout", "save"]).is_err());
+}
+
+#[test]
+fn test_parse_column_group() {
+    assert!(matches!(
+        parse_command(&["column", "group", "create"]).unwrap(),
+        Command::GroupColumns(true)
+    ));
+    assert!(matches!(
+        parse_command(&["column", "group", "dissolve"]).unwrap(),
+        Command::GroupColumns(false)
+    ));
+    assert!(parse_command(&["column", "group"]).is_err());
+}
+
+#[test]
+fn test_parse_focus_visible() {
+    assert!(matches!(
+        parse_command(&["window", "focus", "visible", "next"]).unwrap(),
+        Command::Window(Operation::FocusVisible(Direction::East))
+    ));
+    assert!(matches!(
+        parse_command(&["window", "focus", "visible", "prev"]).unwrap(),
+        Command::Window(Operation::FocusVisible(Direction::West))
+    ));
+    assert!(parse_command(&["window", "focus", "visible"]).is_err());
+}
+
+#[test]
+fn test_parse_toggle_split_orientation() {
+    assert!(matches!(
+        parse_command(&["window", "toggle", "split", "orientation"]).unwrap(),
+        Command::Window(Operation::ToggleSplitOrientation)
+    ));
+    assert!(parse_command(&["window", "toggle", "split"]).is_err());
+}
+
+#[test]
+fn test_parse_move_commands() {
+    assert!(matches!(
+        parse_command(&["window", "move", "east"]).unwrap(),
+        Command::Window(Operation::Move(Direction::East))
+    ));
+    assert!(matches!(
+        parse_command(&["window", "move", "into", "stack", "west"]).unwrap(),
+        Command::Window(Operation::MoveIntoStack(Direction::West))
+    ));
+    assert!(parse_command(&["window", "move", "north"]).is_err());
+    assert!(parse_command(&["window", "move", "into", "stack"]).is_err());
+}
+
+#[test]
+fn test_parse_insertion_point_commands() {
+    assert!(matches!(
+        parse_command(&["window", "set", "insertion", "point", "stack"]).unwrap(),
+        Command::Window(Operation::SetInsertionPoint(InsertionPlacement::Stack))
+    ));
+    assert!(matches!(
+        parse_command(&["window", "set", "insertion", "point", "west"]).unwrap(),
+        Command::Window(Operation::SetInsertionPoint(InsertionPlacement::West))
+    ));
+    assert!(parse_command(&["window", "set", "insertion", "point", "north"]).is_err());
+    assert!(parse_command(&["window", "set", "insertion"]).is_err());
+}
+
+#[test]
+fn test_parse_close_commands() {
+    assert!(matches!(
+        parse_command(&["window", "close"]).unwrap(),
+        Command::Window(Operation::Close)
+    ));
+    assert!(matches!(
+        parse_command(&["window", "close", "others"]).unwrap(),
+        Command::Window(Operation::CloseOthers)
+    ));
+    assert!(parse_command(&["window", "close", "all"]).is_err());
+}
+
+#[test]
+fn test_parse_center_display_target() {
+    assert!(matches!(
+        parse_command(&["window", "center"]).unwrap(),
+        Command::Window(Operation::Center(None))
+    ));
+    assert!(matches!(
+        parse_command(&["window", "center", "display:2"]).unwrap(),
+        Command::Window(Operation::Center(Some(1)))
+    ));
+    assert!(parse_command(&["window", "center", "display:0"]).is_err());
+    assert!(parse_command(&["window", "center", "2"]).is_err());
+}
+
+#[test]
+fn test_parse_restart_command() {
+    assert!(matches!(
+        parse_command(&["restart"]).unwrap(),
+        Command::Restart
+    ));
+}
+
+#[test]
+fn test_parse_absolute_virtual_workspace_commands() {
+    assert!(matches!(
+        parse_command(&["window", "virtualnum", "3"]).unwrap(),
+        Command::Window(Operation::VirtualNumber(2))
+    ));
+    assert!(parse_command(&["workspace", "virtual", "3"]).is_err());
+    assert!(matches!(
+        parse_command(&["window", "virtualmove", "3"]).unwrap(),
+        Command::Window(Operation::VirtualMoveNumber(2, MoveFocus::Follow))
+    ));
+    assert!(matches!(
+        parse_command(&["window", "virtualsend", "3"]).unwrap(),
+        Command::Window(Operation::VirtualMoveNumber(2, MoveFocus::Stay))
+    ));
+    assert!(matches!(
+        parse_command(&["window", "virtualmovenum", "3"]).unwrap(),
+        Command::Window(Operation::VirtualMoveNumber(2, MoveFocus::Follow))
+    ));
+    assert!(matches!(
+        parse_command(&["window", "virtualsendnum", "3"]).unwrap(),
+        Command::Window(Operation::VirtualMoveNumber(2, MoveFocus::Stay))
+    ));
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn test_grid_ratios() {
+    let make = |grid: Option<&str>| {
+        let mut params = WindowParams::new(".*", None);
+        params.grid = grid.map(Into::into);
+        params
+    };
+
+    // Standard 2x2 grid, cell (1,1), span 1x1 → bottom-right quarter.
+    assert_eq!(
+        make(Some("2:2:1:1:1:1")).grid_ratios(),
+        Some((0.5, 0.5, 0.5, 0.5))
+    );
+
+    // 3x3 grid, cell (0,0), span 2x1 → top-left, 2/3 width, 1/3 height.
+    assert_eq!(
+        make(Some("3:3:0:0:2:1")).grid_ratios(),
+        Some((0.0, 0.0, 2.0 / 3.0, 1.0 / 3.0))
+    );
+
+    // Full screen: 1x1 grid, cell (0,0), span 1x1.
+    assert_eq!(
+        make(Some("1:1:0:0:1:1")).grid_ratios(),
+        Some((0.0, 0.0, 1.0, 1.0))
+    );
+
+    // Invalid: too few parts.
+    assert_eq!(make(Some("2:2:1:1")).grid_ratios(), None);
+
+    // Invalid: zero columns.
+    assert_eq!(make(Some("0:2:0:0:1:1")).grid_ratios(), None);
+
+    // No grid set.
+    assert_eq!(make(None).grid_ratios(), None);
+}
+
+#[test]
+fn test_parse_hex_color_valid() {
+    assert_eq!(
+        parse_hex_color("#89b4fa"),
+        (
+            f64::from(0x89) / 255.0,
+            f64::from(0xb4) / 255.0,
+            f64::from(0xfa) / 255.0
+        )
+    );
+    assert_eq!(parse_hex_color("#000000"), (0.0, 0.0, 0.0));
+    assert_eq!(parse_hex_color("#FFFFFF"), (1.0, 1.0, 1.0));
+    assert_eq!(parse_hex_color("#FF0000"), (1.0, 0.0, 0.0));
+}
+
+#[test]
+fn test_parse_hex_color_no_hash() {
+    assert_eq!(
+        parse_hex_color("89b4fa"),
+        (
+            f64::from(0x89) / 255.0,
+            f64::from(0xb4) / 255.0,
+            f64::from(0xfa) / 255.0
+        )
+    );
+    assert_eq!(parse_hex_color("FF0000"), (1.0, 0.0, 0.0));
+}
+
+#[test]
+fn test_parse_hex_color_invalid_length() {
+    // Short strings fall back to white.
+    assert_eq!(parse_hex_color("#FFF"), (1.0, 1.0, 1.0));
+    assert_eq!(parse_hex_color(""), (1.0, 1.0, 1.0));
     assert_eq!(parse_hex_color("#FF"), (1.0, 1.0, 1.0));
 }
 
@@ -2028,6 +3682,114 @@ fn test_first_launch_creates_parseable_config_without_overwriting_it() {
     std::fs::remove_dir_all(directory).unwrap();
 }
 
+#[test]
+fn test_reload_reaches_config_clones() {
+    let path = std::env::temp_dir().join(format!(
+        "paneru-reload-{}-{}.toml",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    std::fs::write(&path, "[bindings]\nwindow_focus_west = \"alt - h\"\n").unwrap();
+    let mut config = Config::new(&path).unwrap();
+    // The input tap holds a clone like this one.
+    let tap_config = config.clone();
+    let before = tap_config.bindings_summary();
+    assert_eq!(before.len(), 1);
+
+    std::fs::write(
+        &path,
+        "[bindings]\nwindow_focus_west = \"alt - h\"\nwindow_focus_east = \"alt - l\"\n",
+    )
+    .unwrap();
+    config.reload_config(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let after = tap_config.bindings_summary();
+    assert_eq!(after.len(), 2);
+    assert_eq!(after, config.bindings_summary());
+}
+
+/// The bindings of the profiles are resolved when the configuration loads, so
+/// `paneru query keybindings` does not read the files again.
+#[test]
+fn test_keybindings_report_resolved_on_load() {
+    let path = std::env::temp_dir().join(format!(
+        "paneru-report-{}-{}.toml",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    std::fs::write(
+        &path,
+        "[bindings]\nwindow_focus_west = \"alt - h\"\n\n[profile.laptop.bindings]\nwindow_focus_east = \"alt - l\"\n",
+    )
+    .unwrap();
+    let config = Config::new(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let report = config.keybindings_report();
+    assert_eq!(report.active_profile, None);
+    assert_eq!(report.bindings.len(), 1);
+    assert_eq!(report.profiles.len(), 1);
+    assert_eq!(report.profiles["laptop"].len(), 2);
+}
+
+#[test]
+fn test_config_includes_are_merged_in_order() {
+    let directory = std::env::temp_dir().join(format!(
+        "paneru-includes-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    std::fs::create_dir_all(&directory).unwrap();
+    let main = directory.join("paneru.toml");
+    std::fs::write(
+        &main,
+        "include = [\"focus.toml\", \"local.toml\", \"missing.toml\"]\n\n[options]\nauto_center = true\nsliver_width = 10\n",
+    )
+    .unwrap();
+    std::fs::write(
+        directory.join("focus.toml"),
+        "[options]\nfocus_follows_mouse = false\n",
+    )
+    .unwrap();
+    std::fs::write(
+        directory.join("local.toml"),
+        "[options]\nauto_center = false\n",
+    )
+    .unwrap();
+
+    let config = Config::new(&main).unwrap();
+    assert!(!config.auto_center());
+    assert!(!config.focus_follows_mouse());
+    assert_eq!(config.sliver_width(), 10);
+    assert_eq!(
+        config.files(),
+        vec![
+            main.clone(),
+            directory.join("focus.toml"),
+            directory.join("local.toml")
+        ]
+    );
+
+    std::fs::write(
+        directory.join("local.toml"),
+        "include = [\"paneru.toml\"]\n",
+    )
+    .unwrap();
+    assert!(Config::new(&main).is_err());
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
 #[test]
 fn test_window_rules_manage() {
     let input = r#"
@@ -2071,42 +3833,215 @@ fn test_restore_config_defaults() {
 }
 
 #[test]
-fn test_restore_config_explicit_values() {
-    let config = Config::try_from(
-        r#"
+fn test_profile_overrides() {
+    let input = r#"
 [options]
-
-[restore]
-enabled = false
-startup_grace_ms = 750
-missing_windows = "ignore"
+focus_follows_mouse = true
 
 [bindings]
-"#,
-    )
-    .expect("config should parse");
-
-    assert!(!config.restore_enabled());
-    assert_eq!(config.restore_startup_grace(), Duration::from_millis(750));
-    assert_eq!(
-        config.restore_missing_windows(),
-        MissingWindowBehavior::Ignore
-    );
-}
+window_focus_west = "cmd-h"
 
-#[test]
-fn test_restore_config_rejects_unsupported_missing_window_policy() {
-    let err = Config::try_from(
-        r#"
-[options]
+[profile.laptop.options]
+focus_follows_mouse = false
 
-[restore]
-missing_windows = "reserve"
+[profile.laptop.bindings]
+window_focus_west = "alt-h"
+"#;
 
-[bindings]
-"#,
-    )
-    .expect_err("unsupported restore missing-window policy should fail");
+    let virtual_keys = test_virtual_keymap();
+    let load = |profile| Config {
+        inner: Arc::new(ArcSwap::from_pointee(
+            InnerConfig::parse_config_with_virtual_keys(
+                &select_profile(input, profile).expect("profile should apply"),
+                &virtual_keys,
+            )
+            .expect("Failed to parse config"),
+        )),
+    };
+    let keycode = virtual_keycode()
+        .find_map(|(key, code)| (*key == "h").then_some(*code))
+        .unwrap();
+
+    let base = load(None);
+    assert_eq!(base.options().focus_follows_mouse, Some(true));
+    assert!(matches!(
+        base.find_keybind(keycode, Modifiers::CMD),
+        Some(Command::Window(Operation::Focus(Direction::West)))
+    ));
+
+    let laptop = load(Some("laptop"));
+    assert_eq!(laptop.options().focus_follows_mouse, Some(false));
+    assert!(laptop.find_keybind(keycode, Modifiers::CMD).is_none());
+    assert!(matches!(
+        laptop.find_keybind(keycode, Modifiers::ALT),
+        Some(Command::Window(Operation::Focus(Direction::West)))
+    ));
+
+    assert!(select_profile(input, Some("work")).is_err());
+    assert!(matches!(
+        parse_command(&["profile", "home", "office"]).unwrap(),
+        Command::Profile(Some(name)) if name == "home_office"
+    ));
+    assert!(matches!(
+        parse_command(&["profile"]).unwrap(),
+        Command::Profile(None)
+    ));
+}
+
+#[test]
+fn test_bindings_info() {
+    let input = r#"
+[options]
+
+[bindings]
+window_focus_west = ["lalt+shift-h", "ctrl+alt-tab"]
+window_center = "mouse3"
+"#;
+    let config = Config {
+        inner: Arc::new(ArcSwap::from_pointee(
+            InnerConfig::parse_config_with_virtual_keys(input, &test_virtual_keymap())
+                .expect("Failed to parse config"),
+        )),
+    };
+    let bindings = config
+        .bindings_info()
+        .into_iter()
+        .map(|info| (info.command, info.binding, info.keycode.is_some()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        bindings,
+        vec![
+            ("window_center".to_string(), "mouse3".to_string(), false),
+            (
+                "window_focus_west".to_string(),
+                "ctrl+alt-tab".to_string(),
+                true
+            ),
+            (
+                "window_focus_west".to_string(),
+                "lalt+shift-h".to_string(),
+                true
+            ),
+        ]
+    );
+    assert_eq!(
+        modifier_names(Modifiers::HYPER | Modifiers::FN),
+        vec!["ctrl", "alt", "shift", "cmd", "fn"]
+    );
+}
+
+#[test]
+fn test_poll_intervals() {
+    let config = Config::try_from("[options]\n\n[bindings]\n").expect("config should parse");
+    assert_eq!(config.poll_interval(), Duration::from_millis(1000));
+    assert_eq!(config.poll_interval_active(), Duration::from_millis(250));
+
+    // The interval after input is never slower than the resting one.
+    let config = Config::try_from("[options]\npoll_interval_ms = 100\n\n[bindings]\n")
+        .expect("config should parse");
+    assert_eq!(config.poll_interval(), Duration::from_millis(100));
+    assert_eq!(config.poll_interval_active(), Duration::from_millis(100));
+}
+
+#[test]
+fn test_swipe_vertical_bindings() {
+    let config = Config::try_from(
+        "[swipe.gesture]\nfingers_count = 3\n\n[swipe.vertical]\nfingers = 4\ncommand = \"window_stack\"\nup = \"window_fullwidth\"\n",
+    )
+    .expect("config should parse");
+
+    assert!(config.swipe_vertical_bindings(3).is_none());
+    let bindings = config
+        .swipe_vertical_bindings(4)
+        .expect("4 finger swipes are bound");
+    assert!(matches!(
+        bindings.command(true),
+        Some(Command::Window(Operation::FullWidth))
+    ));
+    assert!(matches!(
+        bindings.command(false),
+        Some(Command::Window(Operation::Stack(true)))
+    ));
+
+    // Without `fingers`, the bindings follow the swipe gesture.
+    let config = Config::try_from(
+        "[swipe.gesture]\nfingers_count = 3\n\n[swipe.vertical]\ndown = \"window_unstack\"\n",
+    )
+    .expect("config should parse");
+    let bindings = config
+        .swipe_vertical_bindings(3)
+        .expect("3 finger swipes are bound");
+    assert!(bindings.command(true).is_none());
+
+    assert!(Config::try_from("[swipe.vertical]\ncommand = \"window_bogus\"\n").is_err());
+}
+
+#[test]
+fn test_center_focused_column_option() {
+    let config = Config::try_from("[options]\n\n[bindings]\n").expect("config should parse");
+    assert_eq!(config.center_focused_column(), CenterFocusedColumn::Never);
+
+    let config =
+        Config::try_from("[options]\ncenter_focused_column = \"on_overflow\"\n\n[bindings]\n")
+            .expect("config should parse");
+    assert_eq!(
+        config.center_focused_column(),
+        CenterFocusedColumn::OnOverflow
+    );
+
+    assert!(
+        Config::try_from("[options]\ncenter_focused_column = \"sometimes\"\n\n[bindings]\n")
+            .is_err()
+    );
+}
+
+#[test]
+fn test_focus_strategy_option() {
+    let config = Config::try_from("[options]\n\n[bindings]\n").expect("config should parse");
+    assert_eq!(config.focus_strategy(), FocusStrategy::Auto);
+
+    let config = Config::try_from("[options]\nfocus_strategy = \"front_process\"\n\n[bindings]\n")
+        .expect("config should parse");
+    assert_eq!(config.focus_strategy(), FocusStrategy::FrontProcess);
+}
+
+#[test]
+fn test_restore_config_explicit_values() {
+    let config = Config::try_from(
+        r#"
+[options]
+
+[restore]
+enabled = false
+startup_grace_ms = 750
+missing_windows = "ignore"
+
+[bindings]
+"#,
+    )
+    .expect("config should parse");
+
+    assert!(!config.restore_enabled());
+    assert_eq!(config.restore_startup_grace(), Duration::from_millis(750));
+    assert_eq!(
+        config.restore_missing_windows(),
+        MissingWindowBehavior::Ignore
+    );
+}
+
+#[test]
+fn test_restore_config_rejects_unsupported_missing_window_policy() {
+    let err = Config::try_from(
+        r#"
+[options]
+
+[restore]
+missing_windows = "reserve"
+
+[bindings]
+"#,
+    )
+    .expect_err("unsupported restore missing-window policy should fail");
 
     assert!(err.to_string().contains("unknown variant"));
 }
@@ -2131,3 +4066,503 @@ window_grow = "alt - minus"
         Some(Command::Window(Operation::Resize(ResizeDirection::Grow)))
     ));
 }
+
+#[test]
+fn test_ignore_apps() {
+    let process_with_bundle = |bundle_id: &'static str| {
+        let mut process = crate::manager::MockProcessApi::new();
+        process
+            .expect_bundle_id()
+            .returning(move || Some(bundle_id.to_string()));
+        process
+    };
+
+    let config: Config = (
+        MainOptions {
+            ignore_apps: vec!["com.example.bad".to_string()],
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+    assert!(config.ignores_process(&process_with_bundle("com.example.bad")));
+    assert!(!config.ignores_process(&process_with_bundle("com.example.good")));
+
+    let config = Config::default();
+    assert!(!config.ignores_process(&process_with_bundle("com.example.bad")));
+}
+
+#[test]
+fn test_managed_displays() {
+    let uuid = "37D8832A-2D66-02CA-B9F7-8F30A301B230";
+    let config = Config::default();
+    assert!(config.manages_display(uuid, 2));
+
+    let config: Config = (
+        MainOptions {
+            managed_displays: vec![uuid.to_lowercase(), "3".to_string()],
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+    assert!(config.manages_display(uuid, 1));
+    assert!(config.manages_display("other", 3));
+    assert!(!config.manages_display("other", 1));
+}
+
+#[test]
+fn test_socket_path() {
+    assert_eq!(Config::default().socket_path(), None);
+
+    let config: Config = (
+        MainOptions {
+            socket_path: Some("~/run/paneru.sock".to_string()),
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+    let home = env::var("HOME").unwrap();
+    assert_eq!(
+        config.socket_path(),
+        Some(PathBuf::from(home).join("run/paneru.sock"))
+    );
+}
+
+#[test]
+fn test_command_names_parse() {
+    for (name, command) in &NAMED_COMMANDS {
+        let argv = name.split('_').collect::<Vec<_>>();
+        let parsed =
+            parse_command(&argv).unwrap_or_else(|err| panic!("{name} does not parse: {err}"));
+        assert_eq!(format!("{parsed:?}"), format!("{command:?}"), "{name}");
+    }
+}
+
+/// Every kind of command the parser accepts without a free argument has a name
+/// in `COMMAND_NAMES`. The commands tried are the listed ones with one of their
+/// words replaced, added or removed.
+#[test]
+fn test_command_names_cover_parser() {
+    // `Window(Focus(West))` is of the kind `Window(Focus`.
+    let kind = |command: &Command| {
+        let debug = format!("{command:?}");
+        debug.split('(').take(2).collect::<Vec<_>>().join("(")
+    };
+    let takes_argument = |command: &Command| {
+        matches!(
+            command,
+            Command::Profile(Some(_))
+                | Command::LayoutSave(_)
+                | Command::LayoutLoad(_)
+                | Command::SendKeys(_)
+                | Command::Window(Operation::Pull(_))
+        )
+    };
+
+    let mut vocabulary = COMMAND_NAMES
+        .iter()
+        .flat_map(|name| name.split('_'))
+        .collect::<Vec<_>>();
+    vocabulary.sort_unstable();
+    vocabulary.dedup();
+
+    let listed = NAMED_COMMANDS
+        .iter()
+        .map(|(_, command)| kind(command))
+        .collect::<std::collections::HashSet<_>>();
+    for name in COMMAND_NAMES {
+        let words = name.split('_').collect::<Vec<_>>();
+        let mut variants = (0..words.len())
+            .map(|index| {
+                let mut removed = words.clone();
+                removed.remove(index);
+                removed
+            })
+            .collect::<Vec<_>>();
+        for &word in &vocabulary {
+            for index in 0..=words.len() {
+                let mut inserted = words.clone();
+                inserted.insert(index, word);
+                variants.push(inserted);
+                if index < words.len() {
+                    let mut replaced = words.clone();
+                    replaced[index] = word;
+                    variants.push(replaced);
+                }
+            }
+        }
+        for argv in variants.iter().filter(|argv| !argv.is_empty()) {
+            if let Ok(command) = parse_command(argv)
+                && !takes_argument(&command)
+            {
+                assert!(
+                    listed.contains(&kind(&command)),
+                    "{} parses into {command:?}, which has no name in COMMAND_NAMES",
+                    argv.join("_")
+                );
+            }
+        }
+    }
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn test_display_animation_speed() {
+    let config = Config::try_from(
+        r#"
+[options]
+animation_speed = 20
+
+[options.display_animations."37D8832A-2D66-02CA-B9F7-8F30A301B230"]
+animation_speed = 8
+
+[options.display_animations."9C3A1F52-7B1E-4C8D-A2F0-5D6E7F8091A2"]
+animations = false
+
+[bindings]
+"#,
+    )
+    .unwrap();
+    assert!(config.has_display_animations());
+    assert_eq!(
+        config.display_animation_speed("37D8832A-2D66-02CA-B9F7-8F30A301B230"),
+        8.0
+    );
+    assert_eq!(
+        config.display_animation_speed("9C3A1F52-7B1E-4C8D-A2F0-5D6E7F8091A2"),
+        INSTANT_ANIMATION_SPEED
+    );
+    assert_eq!(config.display_animation_speed("unknown"), 20.0);
+}
+
+#[test]
+fn test_ax_timeouts() {
+    let config = Config::try_from(
+        r#"
+[options]
+ax_timeout_ms = 500
+
+[options.ax_timeouts]
+"com.example.slow" = 5000
+"com.microsoft.Excel" = 60000
+
+[bindings]
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.ax_timeout(Some("com.example.fast")),
+        Duration::from_millis(500)
+    );
+    assert_eq!(config.ax_timeout(None), Duration::from_millis(500));
+    assert_eq!(
+        config.ax_timeout(Some("com.example.slow")),
+        Duration::from_secs(5)
+    );
+    // Overrides of built-in timeouts are still capped.
+    assert_eq!(
+        config.ax_timeout(Some("com.microsoft.Excel")),
+        Duration::from_secs(10)
+    );
+
+    let config = Config::default();
+    assert_eq!(
+        config.ax_timeout(Some("com.example.fast")),
+        DEFAULT_AX_TIMEOUT
+    );
+    assert_eq!(
+        config.ax_timeout(Some("com.microsoft.Word")),
+        Duration::from_secs(3)
+    );
+}
+
+#[test]
+fn test_rescue_hotkeys() {
+    let config = InnerConfig::parse_config_with_virtual_keys(
+        r#"
+[options]
+
+[bindings]
+
+[rescue]
+restart = "ctrl+alt-f12"
+reenable_tap = ""
+"#,
+        &[],
+    )
+    .unwrap();
+    let hotkeys = config.rescue.unwrap_or_default().resolve(&[]);
+    let q_keycode = virtual_keycode()
+        .find_map(|(key, code)| (*key == "q").then_some(*code))
+        .unwrap();
+
+    assert_eq!(hotkeys.len(), 2);
+    assert!(hotkeys.iter().any(|(action, code, modifiers)| {
+        *action == RescueAction::Quit
+            && *code == q_keycode
+            && *modifiers == Modifiers::CTRL | Modifiers::ALT | Modifiers::CMD | Modifiers::SHIFT
+    }));
+    assert!(hotkeys.iter().any(|(action, code, modifiers)| {
+        *action == RescueAction::Restart
+            && *code == 0x6f
+            && *modifiers == Modifiers::CTRL | Modifiers::ALT
+    }));
+}
+
+#[test]
+fn test_parse_space_commands() {
+    assert!(matches!(
+        parse_command(&["space", "focus", "next"]),
+        Ok(Command::Space(SpaceOperation::Focus(SpaceTarget::Next)))
+    ));
+    assert!(matches!(
+        parse_command(&["space", "focus", "prev"]),
+        Ok(Command::Space(SpaceOperation::Focus(SpaceTarget::Previous)))
+    ));
+    assert!(matches!(
+        parse_command(&["space", "focus", "3"]),
+        Ok(Command::Space(SpaceOperation::Focus(SpaceTarget::Number(
+            3
+        ))))
+    ));
+    assert!(matches!(
+        parse_command(&["space", "create"]),
+        Ok(Command::Space(SpaceOperation::Create))
+    ));
+    assert!(matches!(
+        parse_command(&["space", "destroy"]),
+        Ok(Command::Space(SpaceOperation::Destroy))
+    ));
+    assert!(parse_command(&["space", "focus", "0"]).is_err());
+    assert!(parse_command(&["space", "focus"]).is_err());
+}
+
+#[test]
+fn test_size_ratios_by_count() {
+    let config = Config::try_from(
+        r#"
+[options]
+preset_column_widths = [0.25, 0.5]
+size_ratios_by_count = { 2 = [0.5, 0.67, 0.33], 3 = [0.33, 0.5] }
+
+[bindings]
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.size_ratios(2), vec![0.5, 0.67, 0.33]);
+    assert_eq!(config.size_ratios(3), vec![0.33, 0.5]);
+    assert_eq!(config.size_ratios(1), vec![0.25, 0.5]);
+}
+
+#[test]
+fn test_size_ratios_by_count_rejects_invalid_counts() {
+    for key in ["two", "0"] {
+        let input =
+            format!("[options]\nsize_ratios_by_count = {{ {key} = [0.5] }}\n\n[bindings]\n");
+        assert!(
+            Config::try_from(input.as_str()).is_err(),
+            "{key} is accepted"
+        );
+    }
+}
+
+#[test]
+fn test_mouse_bindings() {
+    assert_eq!(MouseTrigger::from_key_name("mouse1"), None);
+    assert_eq!(
+        MouseTrigger::from_key_name("mouse4"),
+        Some(MouseTrigger::Button(4))
+    );
+
+    let config = Config::try_from(
+        r#"
+[options]
+
+[bindings]
+window_focus_west = ["mouse4", "cmd - scroll_up"]
+window_focus_east = ["mouse5", "cmd - scroll_down"]
+"#,
+    )
+    .unwrap();
+
+    assert!(matches!(
+        config.find_mouse_bind(MouseTrigger::Button(4), Modifiers::empty()),
+        Some(Command::Window(Operation::Focus(Direction::West)))
+    ));
+    assert!(matches!(
+        config.find_mouse_bind(MouseTrigger::ScrollDown, Modifiers::CMD),
+        Some(Command::Window(Operation::Focus(Direction::East)))
+    ));
+    assert!(
+        config
+            .find_mouse_bind(MouseTrigger::ScrollUp, Modifiers::empty())
+            .is_none()
+    );
+    // Mouse bindings do not shadow the keycode 0.
+    assert!(config.find_keybind(0, Modifiers::empty()).is_none());
+}
+
+#[test]
+fn test_window_rules_min_size() {
+    let config = Config::try_from(
+        r#"
+[options]
+
+[windows.wezterm]
+title = ".*"
+bundle_id = "com.github.wez.wezterm"
+min_width = 600
+min_height = 0.5
+"#,
+    )
+    .unwrap();
+    let props = config.find_window_properties("title", "com.github.wez.wezterm");
+    assert_eq!(props[0].min_width, Some(SizeLimit::Pixels(600)));
+    assert_eq!(props[0].min_height, Some(SizeLimit::Ratio(0.5)));
+    assert_eq!(SizeLimit::Pixels(600).to_pixels(1000), 600);
+    assert_eq!(SizeLimit::Ratio(0.5).to_pixels(1000), 500);
+    assert_eq!(SizeLimit::Ratio(2.0).to_pixels(1000), 1000);
+}
+
+#[test]
+fn test_window_rules_size_ratios() {
+    let config = Config::try_from(
+        r#"
+[options]
+
+[windows.neovide]
+title = ".*"
+bundle_id = "com.neovide.neovide"
+size_ratios = [0.5, 0.7]
+"#,
+    )
+    .unwrap();
+    let props = config.find_window_properties("title", "com.neovide.neovide");
+    assert_eq!(props[0].size_ratios, Some(vec![0.5, 0.7]));
+    let props = config.find_window_properties("title", "com.example.other");
+    assert!(props.iter().all(|props| props.size_ratios.is_none()));
+}
+
+#[test]
+fn test_window_rules_frame_inset() {
+    let config = Config::try_from(
+        r#"
+[options]
+
+[windows.pwa]
+title = ".*"
+bundle_id = "com.google.Chrome.app"
+frame_inset = [1, 8, 8, 8]
+
+[windows.electron]
+title = ".*"
+bundle_id = "com.electron.app"
+frame_inset = "auto"
+
+[windows.uniform]
+title = ".*"
+bundle_id = "com.uniform.app"
+frame_inset = 4
+"#,
+    )
+    .unwrap();
+    let inset = |bundle_id| {
+        config
+            .find_window_properties("title", bundle_id)
+            .iter()
+            .find_map(|params| params.frame_inset)
+    };
+
+    assert_eq!(
+        inset("com.google.Chrome.app"),
+        Some(FrameInsetOption::Fixed(FrameInset {
+            top: 1,
+            right: 8,
+            bottom: 8,
+            left: 8,
+        }))
+    );
+    assert_eq!(inset("com.electron.app"), Some(FrameInsetOption::Auto));
+    assert_eq!(
+        inset("com.uniform.app"),
+        Some(FrameInsetOption::Fixed(FrameInset {
+            top: 4,
+            right: 4,
+            bottom: 4,
+            left: 4,
+        }))
+    );
+    assert_eq!(inset("com.other.app"), None);
+
+    assert!(
+        Config::try_from(
+            r#"
+[options]
+
+[windows.bad]
+title = ".*"
+frame_inset = "sometimes"
+"#
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_socket_query_token() {
+    assert_eq!(Config::default().socket_query_token(), None);
+
+    let config: Config = (
+        MainOptions {
+            socket_query_token: Some(String::new()),
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+    assert_eq!(config.socket_query_token(), None);
+}
+
+#[test]
+fn test_read_options_skips_bindings() {
+    let path = std::env::temp_dir().join(format!(
+        "paneru-options-{}-{}.toml",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    std::fs::write(
+        &path,
+        "[options]\nsocket_query_token = \"secret\"\n\n[bindings]\nwindow_fly = \"alt - h\"\n",
+    )
+    .unwrap();
+    let config = Config::read_options(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    // The invalid binding is not looked at.
+    assert_eq!(
+        config.unwrap().socket_query_token(),
+        Some("secret".to_string())
+    );
+}
+
+#[test]
+fn test_close_empty_spaces_options() {
+    let config = Config::default();
+    assert!(!config.close_empty_spaces());
+    assert_eq!(config.empty_space_grace(), Duration::from_secs(10));
+    assert_eq!(config.keep_empty_spaces(), 1);
+
+    let config = Config::try_from(
+        "[options]\nclose_empty_spaces = true\nempty_space_grace_ms = 500\nkeep_empty_spaces = 0\n\n[bindings]\n",
+    )
+    .expect("config should parse");
+    assert!(config.close_empty_spaces());
+    assert_eq!(config.empty_space_grace(), Duration::from_millis(500));
+    assert_eq!(config.keep_empty_spaces(), 0);
+}
@@ -1,5 +1,7 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
+use crate::commands::Command;
+use crate::config::parse_command;
 use crate::{config::deserialize_modifier, platform::Modifiers};
 
 #[derive(Clone, Debug, Deserialize)]
@@ -23,8 +25,13 @@ pub struct SwipeOptions {
     #[allow(dead_code)]
     pub continuous: Option<bool>,
 
+    /// Largest distance in pixels a strip moves at the end of a swipe to line
+    /// a column edge up with the display edge. 0 disables snapping. Default: unlimited.
+    pub snap_threshold: Option<u32>,
+
     pub gesture: Option<GestureOptions>,
     pub scroll: Option<ScrollOptions>,
+    pub vertical: Option<VerticalSwipeOptions>,
 }
 
 #[derive(Deserialize, Clone, Debug, Default)]
@@ -51,3 +58,52 @@ pub struct ScrollOptions {
     #[serde(default, deserialize_with = "deserialize_modifier")]
     pub vertical_modifier: Option<Modifiers>,
 }
+
+/// Commands run by vertical multi-finger swipes, instead of switching virtual workspaces.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct VerticalSwipeOptions {
+    /// The number of fingers which run the commands. Defaults to the
+    /// number of fingers of the swipe gesture.
+    pub fingers: Option<usize>,
+
+    /// Command run by a swipe in either direction, named like a binding, e.g. "window_fullwidth".
+    #[serde(default, deserialize_with = "deserialize_command")]
+    pub command: Option<Command>,
+
+    /// Command run by swiping up, instead of `command`.
+    #[serde(default, deserialize_with = "deserialize_command")]
+    pub up: Option<Command>,
+
+    /// Command run by swiping down, instead of `command`.
+    #[serde(default, deserialize_with = "deserialize_command")]
+    pub down: Option<Command>,
+}
+
+impl VerticalSwipeOptions {
+    /// Returns the command for a swipe of the fingers upwards or downwards.
+    pub fn command(&self, up: bool) -> Option<&Command> {
+        if up {
+            self.up.as_ref()
+        } else {
+            self.down.as_ref()
+        }
+        .or(self.command.as_ref())
+    }
+
+    pub fn is_bound(&self) -> bool {
+        self.command.is_some() || self.up.is_some() || self.down.is_some()
+    }
+}
+
+fn deserialize_command<'de, D>(deserializer: D) -> Result<Option<Command>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let Some(name) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    let argv = name.split('_').collect::<Vec<_>>();
+    parse_command(&argv)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
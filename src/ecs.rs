@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::sync::mpsc::Receiver;
 use std::time::{Duration, Instant};
 
@@ -11,6 +13,7 @@ use bevy::ecs::query::{Added, Changed, With};
 use bevy::ecs::resource::Resource;
 use bevy::ecs::schedule::common_conditions::{not, resource_exists};
 use bevy::ecs::system::{Commands, EntityCommands, Query, Res, SystemId};
+use bevy::math::IRect;
 use bevy::prelude::Event as BevyEvent;
 use bevy::tasks::Task;
 use bevy::time::Timer;
@@ -23,10 +26,13 @@ use bevy::{
 use derive_more::{Deref, DerefMut};
 use tracing::{Level, instrument};
 
-use crate::commands::register_commands;
-use crate::config::{CONFIGURATION_FILE, Config, WindowParams};
+use crate::commands::{InsertionPlacement, register_commands};
+use crate::config::{
+    CONFIGURATION_FILE, Config, FrameInsetOption, WindowParams, read_config_files,
+};
 use crate::ecs::display::FloatingLayer;
 use crate::ecs::layout::LayoutStrip;
+use crate::ecs::presentation::PresentationMode;
 use crate::ecs::state::PaneruState;
 use crate::errors::Result;
 use crate::events::{Event, EventSender};
@@ -39,12 +45,19 @@ use crate::platform::{Modifiers, PlatformCallbacks, WinID, WorkspaceId};
 
 pub mod display;
 pub mod focus;
+pub mod journal;
 pub mod layout;
 pub mod mouse;
 pub mod params;
+pub mod polling;
+pub mod presentation;
+pub mod recorder;
+pub mod relaunch;
 pub(crate) mod restore;
 pub mod scroll;
+pub mod spaces;
 pub mod state;
+mod storm;
 mod systems;
 mod triggers;
 pub mod workspace;
@@ -59,6 +72,8 @@ pub mod workspace;
 #[allow(clippy::too_many_lines)]
 pub fn register_systems(app: &mut bevy::app::App) {
     const LOW_POWER_MODE_CHECK_SEC: u64 = 60;
+    const ACCESSIBILITY_CHECK_SEC: u64 = 2;
+    const WINDOW_SERVER_CHECK_SEC: u64 = 5;
 
     let not_swiping = |scrolling: Query<&Scrolling, With<ActiveWorkspaceMarker>>| {
         scrolling
@@ -104,6 +119,7 @@ pub fn register_systems(app: &mut bevy::app::App) {
             (
                 triggers::apply_window_defaults,
                 systems::detect_tabbed_windows.run_if(native_tabs_enabled),
+                systems::recount_native_tabs.run_if(native_tabs_enabled),
                 triggers::apply_window_positions,
             )
                 .chain(),
@@ -121,13 +137,24 @@ pub fn register_systems(app: &mut bevy::app::App) {
             systems::retry_front_switch,
             systems::update_low_power_state
                 .run_if(resource_exists::<LowPowerMode>)
+                .run_if(presentation::session_active)
                 .run_if(on_timer(Duration::from_secs(LOW_POWER_MODE_CHECK_SEC))),
+            systems::power_source_changed,
+            systems::check_accessibility
+                .run_if(presentation::session_active)
+                .run_if(on_timer(Duration::from_secs(ACCESSIBILITY_CHECK_SEC))),
+            systems::check_window_server
+                .run_if(presentation::session_active)
+                .run_if(on_timer(Duration::from_secs(WINDOW_SERVER_CHECK_SEC))),
+            systems::show_alerts,
+            systems::publish_title_bars,
             (
                 systems::window_resized_update_frame,
                 systems::window_moved_update_frame,
             )
                 .chain()
                 .run_if(not_swiping),
+            systems::floating_window_update_frame,
             systems::cleanup_on_exit,
             restore::tick_restore_grace,
             state::periodic_state_save.run_if(on_timer(Duration::from_secs(300))),
@@ -142,12 +169,14 @@ pub fn register_systems(app: &mut bevy::app::App) {
                 systems::commit_window_position.run_if(not(resource_exists::<Initializing>)),
                 systems::verify_window_position.run_if(not(resource_exists::<Initializing>)),
             )
-                .chain(),
+                .chain()
+                .run_if(not(resource_exists::<PresentationMode>)),
             (
                 systems::animate_resize_entities,
                 systems::commit_window_size.run_if(not(resource_exists::<Initializing>)),
             )
-                .chain(),
+                .chain()
+                .run_if(not(resource_exists::<PresentationMode>)),
             (
                 systems::update_overlays
                     .after(systems::animate_entities)
@@ -185,6 +214,7 @@ pub fn register_triggers(app: &mut bevy::app::App) {
         .add_observer(triggers::send_message_trigger)
         .add_observer(triggers::window_removal_trigger)
         .add_observer(triggers::cleanup_timeout_trigger)
+        .add_observer(triggers::reapply_window_rules_trigger)
         .add_observer(restore::restore_window_state);
 }
 
@@ -205,6 +235,11 @@ pub struct FlashMessage(pub String);
 #[derive(Component)]
 pub struct ActiveDisplayMarker;
 
+/// Marks a display left out of `managed_displays`. Its windows float and its
+/// strips stay empty.
+#[derive(Component)]
+pub struct UnmanagedDisplay;
+
 /// Marker component signifying a freshly created process, application, or window.
 #[derive(Component)]
 pub struct FreshMarker;
@@ -234,6 +269,11 @@ pub struct ReshuffleAroundMarker;
 #[derive(Component)]
 pub struct EnsureVisibleMarker;
 
+/// Marker on a strip which keeps sliding after the fingers left the trackpad.
+/// It slows down with the configured deceleration and settles on a column edge.
+#[derive(Component)]
+pub struct SwipeMomentum;
+
 #[derive(Component, Debug)]
 pub struct Scrolling {
     pub velocity: f64,
@@ -267,6 +307,34 @@ pub struct Bounds(pub Size);
 #[derive(Component, Clone, Debug, Deref, DerefMut)]
 pub struct WidthRatio(pub f64);
 
+/// The frame a window had when Paneru first saw it, restored with
+/// `restore_original_frames`.
+#[derive(Component, Clone, Copy, Debug, Deref, DerefMut)]
+pub struct OriginalFrame(pub IRect);
+
+/// Placement of the next spawned window, set by `window_set_insertion_point`
+/// and consumed by the first window managed after it.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InsertionPoint {
+    pub target: Entity,
+    pub placement: InsertionPlacement,
+}
+
+/// Minimum size of a window from its `min_width` and `min_height` rules,
+/// which the strip layout never shrinks it below.
+#[derive(Component, Clone, Copy, Debug, Deref, DerefMut)]
+pub struct MinimumSize(pub Size);
+
+/// Order in which a window gives up its size when the strip is too narrow or
+/// its stack too short, from its `shrink_priority` rule.
+#[derive(Component, Clone, Copy, Debug, Deref, DerefMut)]
+pub struct ShrinkPriority(pub u32);
+
+/// Preset widths `window_resize` cycles through for a window, from its
+/// `size_ratios` rule.
+#[derive(Component, Clone, Debug, Deref, DerefMut)]
+pub struct SizeRatios(pub Vec<f64>);
+
 /// Marks a window entity that is currently on a native macOS fullscreen space.
 /// The window has been removed from its tiled position in the strip.
 /// `order` gives the sequence in which windows went fullscreen (0, 1, 2, …)
@@ -278,6 +346,38 @@ pub struct NativeFullscreenMarker {
     pub index: usize,
 }
 
+/// Keeps a column at a fixed distance from the left edge of the viewport, while
+/// the rest of the strip scrolls around it.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Pinned(pub i32);
+
+/// Number of tabs in the native tab bar of a window, like the ones of Safari or Terminal.
+/// Refreshed once the window title settles after a change, which happens whenever tabs
+/// are switched, opened or closed.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NativeTabs(pub usize);
+
+/// Marks a window whose title changed, so its native tabs are counted once the
+/// title settled. Some apps change their title many times a second, and counting
+/// walks the accessibility tree of the window.
+#[derive(Component)]
+pub struct RecountNativeTabs(pub Timer);
+
+impl Default for RecountNativeTabs {
+    fn default() -> Self {
+        const RECOUNT_NATIVE_TABS_MS: u64 = 200;
+        Self(Timer::new(
+            Duration::from_millis(RECOUNT_NATIVE_TABS_MS),
+            bevy::time::TimerMode::Once,
+        ))
+    }
+}
+
+/// Freezes the layout of a strip: windows keep wherever the user put them until the
+/// strip is unlocked. Window lifecycle is still tracked, so the strip stays consistent.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct LayoutLocked;
+
 #[derive(Component)]
 pub struct FullWidthMarker {
     pub width_ratio: f64,
@@ -288,17 +388,22 @@ pub struct FullWidthMarker {
 pub enum Unmanaged {
     /// The window is floating and not part of the tiling layout.
     Floating,
-    /// The window is minimized.
-    Minimized,
+    /// The window is minimized. Holds the space it was minimized on, which is
+    /// where macOS brings it back.
+    Minimized(Option<WorkspaceId>),
     /// The window is hidden.
     Hidden,
 }
 
+/// Remembers where a window was tiled before it was minimized, hidden or floated,
+/// so it can return to the same column once it is managed again.
 #[derive(Clone, Component, Copy, Debug)]
 pub struct PreviousManagedStrip {
     pub workspace_id: WorkspaceId,
     pub virtual_index: u32,
     pub index: usize,
+    /// Width relative to the display, recorded when the window was floated.
+    pub width_ratio: Option<f64>,
 }
 
 /// Wrapper component for a `ProcessApi` trait object, enabling dynamic dispatch for process-related operations within Bevy.
@@ -357,6 +462,22 @@ impl Timeout {
 #[derive(Component)]
 pub struct StrayFocusEvent(pub WinID);
 
+/// Guards a window whose rule prevents it from stealing focus, for a moment after
+/// it opened. Focus the window takes meanwhile goes back to `previous`, unless
+/// paneru focused it or the user clicked it, which drops the guard.
+#[derive(Component)]
+pub struct FocusStealGuard {
+    pub previous: Entity,
+    pub since: Instant,
+}
+
+impl FocusStealGuard {
+    pub fn active(&self) -> bool {
+        const FOCUS_STEAL_GUARD_SEC: u64 = 2;
+        self.since.elapsed() < Duration::from_secs(FOCUS_STEAL_GUARD_SEC)
+    }
+}
+
 /// Component used as a retry mechanism when `focused_window_id()` fails during
 /// an `ApplicationFrontSwitched` event (e.g. transient `kAXErrorCannotComplete`).
 #[derive(Component)]
@@ -365,7 +486,13 @@ pub struct RetryFrontSwitch(pub Entity);
 #[derive(Component)]
 pub struct BruteforceWindows(Task<Vec<Window>>);
 
-#[derive(Component, Debug)]
+/// Discovers the windows of an application which was running at startup. The
+/// application is handed to the task, and returned to its entity along with
+/// the windows it found.
+#[derive(Component)]
+pub struct DiscoverWindows(Task<(Application, Result<(Vec<Window>, Vec<WinID>)>)>);
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DockPosition {
     Bottom(i32),
     Left(i32),
@@ -407,8 +534,75 @@ impl VerifyWindowPosition {
     }
 }
 
-#[derive(Deref, DerefMut, Resource)]
-pub struct LowPowerMode(pub bool);
+/// Polling schedule for a freshly launched process which is not ready yet.
+/// Checks start out frequent and back off exponentially, until the retry budget
+/// runs out. After that only the KVO notifications re-arm the polling.
+#[derive(Component)]
+pub struct ReadyBackoff {
+    timer: Timer,
+    attempts: u8,
+}
+
+impl Default for ReadyBackoff {
+    fn default() -> Self {
+        Self {
+            timer: Timer::new(Self::INITIAL_INTERVAL, bevy::time::TimerMode::Once),
+            attempts: 0,
+        }
+    }
+}
+
+impl ReadyBackoff {
+    const INITIAL_INTERVAL: Duration = Duration::from_millis(10);
+    const MAX_INTERVAL: Duration = Duration::from_secs(1);
+    const MAX_ATTEMPTS: u8 = 10;
+
+    /// Advances the timer, returning `true` when the next check is due.
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        self.timer.tick(delta).is_finished()
+    }
+
+    /// Schedules the next check with a doubled interval. Returns `false` once the
+    /// retry budget is exhausted.
+    pub fn backoff(&mut self) -> bool {
+        self.attempts = self.attempts.saturating_add(1);
+        if self.attempts >= Self::MAX_ATTEMPTS {
+            return false;
+        }
+        let interval = (self.timer.duration() * 2).min(Self::MAX_INTERVAL);
+        self.timer = Timer::new(interval, bevy::time::TimerMode::Once);
+        true
+    }
+
+    pub fn attempts(&self) -> u8 {
+        self.attempts
+    }
+}
+
+/// Power saving: windows move without animations, the event loop wakes up
+/// less often and reshuffles are batched. Follows the macOS Low Power Mode and,
+/// with `low_power_on_battery`, the battery, unless forced by a command.
+#[derive(Default, Resource)]
+pub struct LowPowerMode {
+    /// The macOS Low Power Mode is enabled.
+    pub system: bool,
+    /// The Mac runs on its battery.
+    pub on_battery: bool,
+    /// Set by the `low_power_on` and `low_power_off` commands.
+    pub forced: Option<bool>,
+}
+
+impl LowPowerMode {
+    pub fn active(&self, config: &Config) -> bool {
+        self.forced
+            .unwrap_or(self.system || (self.on_battery && config.low_power_on_battery()))
+    }
+}
+
+/// Present while the Accessibility permission is revoked. Windows are left alone
+/// until it is granted again.
+#[derive(Resource)]
+pub struct AccessibilityLost;
 
 #[derive(Resource)]
 pub struct SystemTheme {
@@ -448,6 +642,19 @@ pub struct SendMessageTrigger(pub Event);
 #[derive(BevyEvent)]
 pub struct RestoreWindowState;
 
+/// Carries the window rule outcomes from before a configuration reload, so only
+/// the rules which changed are applied to the existing windows.
+#[derive(BevyEvent)]
+pub struct ReapplyWindowRules(pub Vec<(Entity, WindowRuleOutcome)>);
+
+/// The parts of the window rules which are applied to already managed windows.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WindowRuleOutcome {
+    pub floating: bool,
+    pub width_ratio: Option<f64>,
+    pub insertion: Option<usize>,
+}
+
 pub trait SpawnCommandsExt {
     fn reposition_entity(&mut self, entity: Entity, origin: Origin);
 
@@ -506,7 +713,9 @@ impl SpawnCommandsExt for Commands<'_, '_> {
     #[instrument(level = Level::TRACE, skip(self))]
     fn focus_entity(&mut self, entity: Entity, raise: bool) {
         if let Ok(mut entity_commands) = self.get_entity(entity) {
-            entity_commands.try_insert(FocusedMarker);
+            entity_commands
+                .try_insert(FocusedMarker)
+                .try_remove::<FocusStealGuard>();
             self.trigger(focus::FocusWindow { entity, raise });
         }
     }
@@ -540,9 +749,17 @@ impl SpawnCommandsExt for Commands<'_, '_> {
     }
 }
 
-pub fn setup_bevy_app(sender: EventSender, receiver: Receiver<Event>) -> Result<BevyApp> {
-    let window_manager: Box<dyn WindowManagerApi> = Box::new(WindowManagerOS::new(sender.clone()));
-    let watcher = window_manager.setup_config_watcher(CONFIGURATION_FILE.as_path())?;
+pub fn setup_bevy_app(
+    sender: EventSender,
+    receiver: Receiver<Event>,
+    record: Option<PathBuf>,
+) -> Result<BevyApp> {
+    let window_manager: Arc<dyn WindowManagerApi> = Arc::new(WindowManagerOS::new(sender.clone()));
+    // Watch the included files as well, falling back to the main file alone
+    // when the configuration is broken, so that fixing it triggers a reload.
+    let files = read_config_files(CONFIGURATION_FILE.as_path())
+        .map_or_else(|_| vec![CONFIGURATION_FILE.clone()], |(_, files)| files);
+    let watcher = window_manager.setup_config_watcher(&files)?;
 
     let mut app = BevyApp::new();
 
@@ -564,7 +781,15 @@ pub fn setup_bevy_app(sender: EventSender, receiver: Receiver<Event>) -> Result<
         .add_plugins(layout::LayoutEventsPlugin)
         .add_plugins(focus::FocusEventsPlugin)
         .add_plugins(display::DisplayEventsPlugin)
+        .add_plugins(journal::JournalPlugin)
+        .add_plugins(presentation::PresentationPlugin)
+        .add_plugins(relaunch::RelaunchPlugin)
+        .add_plugins(spaces::EmptySpacesPlugin)
+        .add_plugins(polling::WindowPollingPlugin)
         .add_plugins((register_triggers, register_systems, register_commands));
+    if let Some(path) = record {
+        app.add_plugins(recorder::RecorderPlugin { path });
+    }
 
     let mut platform_callbacks = PlatformCallbacks::new(sender);
     platform_callbacks.setup_handlers()?;
@@ -578,19 +803,23 @@ pub fn setup_bevy_app(sender: EventSender, receiver: Receiver<Event>) -> Result<
         .insert_non_send_resource(menu_bar_manager)
         .insert_non_send_resource(receiver);
 
-    if let Some(previous_state) =
-        PaneruState::load_from_file(&PaneruState::default_state_file_path())
+    if let Some(previous_state) = PaneruState::take_handoff()
+        .or_else(|| PaneruState::load_from_file(&PaneruState::default_state_file_path()))
     {
         app.insert_resource(previous_state);
     }
 
     // Do not insert this in mocks.
-    app.insert_resource(LowPowerMode(false));
+    app.init_resource::<LowPowerMode>();
+
+    if let Some(journal) = app.world().get_resource::<journal::CrashJournal>() {
+        journal.install_panic_hook();
+    }
 
     Ok(app)
 }
 
-struct WindowProperties {
+pub(crate) struct WindowProperties {
     params: Vec<WindowParams>,
 }
 
@@ -598,7 +827,18 @@ impl WindowProperties {
     pub fn new(app: &Application, window: &Window, config: &Config) -> Self {
         let bundle_id = app.bundle_id().unwrap_or_default();
         let title = window.title().unwrap_or_default();
-        let params = config.find_window_properties(&title, &bundle_id);
+        let mut params = config.find_window_properties(&title, &bundle_id);
+
+        // Built-in rules come last, so any user rule deciding how to manage the
+        // window takes precedence.
+        let decided = params
+            .iter()
+            .any(|props| props.floating.is_some() || props.manage.is_some());
+        if !decided && config.builtin_floating(&bundle_id, &window.subrole().unwrap_or_default()) {
+            let mut builtin = WindowParams::new(".*", None);
+            builtin.floating = Some(true);
+            params.push(builtin);
+        }
         Self { params }
     }
 
@@ -620,10 +860,60 @@ impl WindowProperties {
             .unwrap_or(false)
     }
 
+    pub fn prevent_focus_steal(&self) -> bool {
+        self.params
+            .iter()
+            .find_map(|props| props.prevent_focus_steal)
+            .unwrap_or(false)
+    }
+
     pub fn border_radius(&self) -> Option<f64> {
         self.params.iter().find_map(|p| p.border_radius)
     }
 
+    /// Resolves the minimum size rules against the `viewport` of the display.
+    pub fn min_size(&self, viewport: IRect) -> Size {
+        let width = self.params.iter().find_map(|props| props.min_width);
+        let height = self.params.iter().find_map(|props| props.min_height);
+        Size::new(
+            width.map_or(0, |width| width.to_pixels(viewport.width())),
+            height.map_or(0, |height| height.to_pixels(viewport.height())),
+        )
+    }
+
+    pub fn shrink_priority(&self) -> Option<u32> {
+        self.params.iter().find_map(|props| props.shrink_priority)
+    }
+
+    pub fn size_ratios(&self) -> Option<Vec<f64>> {
+        self.params
+            .iter()
+            .find_map(|props| props.size_ratios.clone())
+            .filter(|ratios| !ratios.is_empty())
+    }
+
+    pub fn close_with_others(&self) -> bool {
+        self.params
+            .iter()
+            .find_map(|props| props.close_with_others)
+            .unwrap_or(false)
+    }
+
+    /// The space number new windows open on, and whether to follow them there.
+    pub fn space(&self) -> Option<(usize, bool)> {
+        let space = self.params.iter().find_map(|props| props.space)?;
+        let follow = self
+            .params
+            .iter()
+            .find_map(|props| props.follow)
+            .unwrap_or(false);
+        Some((space, follow))
+    }
+
+    pub fn frame_inset(&self) -> Option<FrameInsetOption> {
+        self.params.iter().find_map(|p| p.frame_inset)
+    }
+
     pub fn grid_ratios(&self) -> Option<(f64, f64, f64, f64)> {
         self.params.iter().find_map(WindowParams::grid_ratios)
     }
@@ -639,6 +929,14 @@ impl WindowProperties {
         self.params.iter().find_map(|props| props.width)
     }
 
+    pub fn outcome(&self) -> WindowRuleOutcome {
+        WindowRuleOutcome {
+            floating: self.floating(),
+            width_ratio: self.width_ratio(),
+            insertion: self.insertion(),
+        }
+    }
+
     pub fn vertical_padding(&self) -> i32 {
         self.params
             .iter()
@@ -653,3 +951,41 @@ impl WindowProperties {
             .unwrap_or(0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MainOptions;
+
+    #[test]
+    fn test_low_power_mode_active() {
+        let on_battery_config: Config = (
+            MainOptions {
+                low_power_on_battery: Some(true),
+                ..Default::default()
+            },
+            vec![],
+        )
+            .into();
+        let config = Config::default();
+
+        let mut mode = LowPowerMode {
+            on_battery: true,
+            ..Default::default()
+        };
+        // The battery only counts when asked for.
+        assert!(!mode.active(&config));
+        assert!(mode.active(&on_battery_config));
+
+        mode.system = true;
+        assert!(mode.active(&config));
+
+        // The commands override both.
+        mode.forced = Some(false);
+        assert!(!mode.active(&on_battery_config));
+        mode.system = false;
+        mode.on_battery = false;
+        mode.forced = Some(true);
+        assert!(mode.active(&config));
+    }
+}
@@ -2,12 +2,12 @@ use bevy::app::{App, Plugin, PreUpdate, Update};
 use bevy::ecs::component::Component;
 use bevy::ecs::entity::Entity;
 use bevy::ecs::hierarchy::ChildOf;
-use bevy::ecs::lifecycle::Add;
+use bevy::ecs::lifecycle::{Add, RemovedComponents};
 use bevy::ecs::message::{MessageReader, MessageWriter};
 use bevy::ecs::observer::On;
-use bevy::ecs::query::{Has, With};
+use bevy::ecs::query::{Changed, Has, Or, With, Without};
 use bevy::ecs::system::{Commands, Local, NonSend, Query, Res};
-use bevy::math::IRect;
+use bevy::math::{IRect, IVec2};
 use bevy::platform::collections::HashSet;
 use objc2_app_kit::NSScreen;
 use objc2_core_graphics::CGDirectDisplayID;
@@ -18,25 +18,60 @@ use tracing::{Level, debug, error, instrument, warn};
 
 use crate::config::Config;
 use crate::ecs::layout::LayoutStrip;
+use crate::ecs::params::Windows;
 use crate::ecs::{
-    ActiveDisplayMarker, ActiveWorkspaceMarker, ReadDisplayProperties, RefreshWindowSizes,
-    SendMessageTrigger, SpawnCommandsExt, Timeout,
+    ActiveDisplayMarker, ActiveWorkspaceMarker, Bounds, DockPosition, Initializing, Position,
+    ReadDisplayProperties, RefreshWindowSizes, RepositionMarker, ResizeMarker, SendMessageTrigger,
+    SpawnCommandsExt, Timeout, Unmanaged, UnmanagedDisplay,
 };
 use crate::events::Event;
-use crate::manager::{Display, WindowManager, irect_from};
+use crate::manager::{Display, Origin, Size, WindowManager, irect_from};
 use crate::platform::{PlatformCallbacks, WorkspaceId};
 use crate::util::read_screen_property;
 
 const ORPHANED_SPACES_TIMEOUT_SEC: u64 = 30;
+/// The Dock and the menubar animate into their new place after a preference change,
+/// so the visible frame is only re-measured once they had time to settle.
+const SCREEN_SETTLE_DELAY_MS: u64 = 600;
+
+/// Frames and pending moves of the windows on a display, rescaled when its
+/// resolution or scaling changes and shifted when its origin moves.
+type ScaledWindows<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Position,
+        &'static mut Bounds,
+        Option<&'static mut RepositionMarker>,
+        Option<&'static mut ResizeMarker>,
+    ),
+    Without<LayoutStrip>,
+>;
+
+/// Positions and pending moves of the layout strips.
+type StripPositions<'w, 's> = Query<
+    'w,
+    's,
+    (&'static mut Position, Option<&'static mut RepositionMarker>),
+    With<LayoutStrip>,
+>;
 
 pub struct DisplayEventsPlugin;
 
 impl Plugin for DisplayEventsPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(PreUpdate, display_change_handler);
-        app.add_systems(Update, reconcile_displays)
-            .add_observer(read_display_properties_trigger)
-            .add_observer(cleanup_active_display_marker);
+        app.add_systems(
+            Update,
+            (
+                reconcile_displays,
+                screen_change_handler,
+                display_geometry_changed,
+                mark_unmanaged_displays,
+            ),
+        )
+        .add_observer(read_display_properties_trigger)
+        .add_observer(cleanup_active_display_marker);
     }
 }
 
@@ -95,10 +130,11 @@ fn display_change_handler(
 /// Full reconciliation of the ECS display set against the OS truth.
 ///
 /// Runs on events where the per-display add/remove/move flags are unreliable or
-/// absent: waking from sleep, resolution / arrangement changes, and configuration
-/// events. Rather than trust a single `display_id` flag, it diffs the live
-/// `present_displays()` list against the spawned `Display` entities and applies
-/// the same add / remove / move primitives the event handlers use. It also
+/// absent: waking from sleep, resolution / arrangement changes, a new main
+/// display, and configuration events. Rather than trust a single `display_id`
+/// flag, it diffs the live `present_displays()` list against the spawned
+/// `Display` entities and applies the same add / remove / move primitives the
+/// event handlers use. It also
 /// forces the active workspace to re-tile, because macOS relocates windows while
 /// asleep even when the display set is unchanged.
 #[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
@@ -107,6 +143,8 @@ pub(crate) fn reconcile_displays(
     workspaces: Query<(&LayoutStrip, Entity, Option<&ChildOf>)>,
     mut displays: Query<(&mut Display, Entity)>,
     active_strips: Query<Entity, (With<LayoutStrip>, With<ActiveWorkspaceMarker>)>,
+    mut scaled_windows: ScaledWindows,
+    mut strip_positions: StripPositions,
     window_manager: Res<WindowManager>,
     mut retries: Local<u8>,
     mut commands: Commands,
@@ -123,6 +161,7 @@ pub(crate) fn reconcile_displays(
                 | Event::DisplayMoved { .. }
                 | Event::DisplayResized { .. }
                 | Event::DisplayConfigured { .. }
+                | Event::MainDisplayChanged { .. }
         )
     });
     if !needs_reconcile {
@@ -190,6 +229,8 @@ pub(crate) fn reconcile_displays(
             &mut displays,
             &window_manager,
             &workspaces,
+            &mut scaled_windows,
+            &mut strip_positions,
             &mut commands,
         );
     }
@@ -281,6 +322,8 @@ fn move_display(
     displays: &mut Query<(&mut Display, Entity)>,
     window_manager: &Res<WindowManager>,
     existing_strips: &Query<(&LayoutStrip, Entity, Option<&ChildOf>)>,
+    scaled_windows: &mut ScaledWindows,
+    strip_positions: &mut StripPositions,
     commands: &mut Commands,
 ) {
     debug!("Display Moved: {display_id:?}");
@@ -299,9 +342,32 @@ fn move_display(
     else {
         return;
     };
+    let previous_bounds = display.bounds();
     *display = moved_display;
     commands.trigger(ReadDisplayProperties(display_entity));
 
+    let bounds = display.bounds();
+    if previous_bounds.size() != bounds.size() {
+        rescale_windows(
+            display_entity,
+            previous_bounds,
+            bounds,
+            existing_strips,
+            scaled_windows,
+            strip_positions,
+        );
+    } else if previous_bounds.min != bounds.min {
+        // The windows stay where they are on the screen, but their coordinates
+        // change, e.g. when another display became the main display.
+        shift_windows(
+            display_entity,
+            bounds.min - previous_bounds.min,
+            existing_strips,
+            scaled_windows,
+            strip_positions,
+        );
+    }
+
     reparent_existing_workspaces(
         &workspace_ids,
         display_entity,
@@ -311,6 +377,99 @@ fn move_display(
     );
 }
 
+/// Scales a size or an offset into a display from its previous to its current dimensions.
+fn rescale(value: IVec2, from: IVec2, to: IVec2) -> IVec2 {
+    let scale = |value: i32, from: i32, to: i32| {
+        if from == 0 {
+            return value;
+        }
+        (f64::from(value) * f64::from(to) / f64::from(from)).round() as i32
+    };
+    IVec2::new(scale(value.x, from.x, to.x), scale(value.y, from.y, to.y))
+}
+
+/// Rescales the windows of a display after its resolution or scaling changed.
+/// Their frames still have the old dimensions, and re-measuring them would
+/// derive the width ratios from the stale sizes. Instead the frames, strip
+/// positions and pending moves are scaled along with the display, relative to
+/// its origin, which keeps the ratios intact. The changed `Bounds` and
+/// `Position` are committed to the OS windows, and `display_geometry_changed`
+/// re-tiles the strips of the changed display.
+fn rescale_windows(
+    display_entity: Entity,
+    previous: IRect,
+    current: IRect,
+    strips: &Query<(&LayoutStrip, Entity, Option<&ChildOf>)>,
+    scaled_windows: &mut ScaledWindows,
+    strip_positions: &mut StripPositions,
+) {
+    debug!(
+        "display {display_entity} resized from {:?} to {:?}, rescaling windows",
+        previous.size(),
+        current.size()
+    );
+    let (from, to) = (previous.size(), current.size());
+    let origin = |position: Origin| current.min + rescale(position - previous.min, from, to);
+    let size = |size: Size| rescale(size, from, to);
+
+    for (strip, strip_entity, _) in strips
+        .iter()
+        .filter(|(_, _, child)| child.is_some_and(|child| child.parent() == display_entity))
+    {
+        if let Ok((mut position, reposition)) = strip_positions.get_mut(strip_entity) {
+            position.0 = origin(position.0);
+            if let Some(mut reposition) = reposition {
+                reposition.0 = origin(reposition.0);
+            }
+        }
+        for entity in strip.all_windows() {
+            let Ok((mut position, mut bounds, reposition, resize)) = scaled_windows.get_mut(entity)
+            else {
+                continue;
+            };
+            position.0 = origin(position.0);
+            bounds.0 = size(bounds.0);
+            if let Some(mut reposition) = reposition {
+                reposition.0 = origin(reposition.0);
+            }
+            if let Some(mut resize) = resize {
+                resize.0 = size(resize.0);
+            }
+        }
+    }
+}
+
+/// Shifts the strips of a display, their windows and any pending moves by
+/// `offset`, after the origin of the display moved in the global coordinates.
+fn shift_windows(
+    display_entity: Entity,
+    offset: IVec2,
+    strips: &Query<(&LayoutStrip, Entity, Option<&ChildOf>)>,
+    scaled_windows: &mut ScaledWindows,
+    strip_positions: &mut StripPositions,
+) {
+    debug!("display {display_entity} moved by {offset}, re-anchoring its strips");
+    for (strip, strip_entity, _) in strips
+        .iter()
+        .filter(|(_, _, child)| child.is_some_and(|child| child.parent() == display_entity))
+    {
+        if let Ok((mut position, reposition)) = strip_positions.get_mut(strip_entity) {
+            position.0 += offset;
+            if let Some(mut reposition) = reposition {
+                reposition.0 += offset;
+            }
+        }
+        for entity in strip.all_windows() {
+            if let Ok((mut position, _, reposition, _)) = scaled_windows.get_mut(entity) {
+                position.0 += offset;
+                if let Some(mut reposition) = reposition {
+                    reposition.0 += offset;
+                }
+            }
+        }
+    }
+}
+
 fn reparent_existing_workspaces(
     workspace_ids: &[WorkspaceId],
     display_entity: Entity,
@@ -365,15 +524,144 @@ impl FloatingLayer {
     }
 }
 
+/// Re-measures all displays when the Dock preferences change (auto-hide toggled,
+/// moved to another edge, resized), the Dock restarts or the menubar auto-hide
+/// setting is flipped.
+#[allow(clippy::needless_pass_by_value)]
+fn screen_change_handler(mut messages: MessageReader<Event>, mut commands: Commands) {
+    if !messages.read().any(|event| {
+        matches!(
+            event,
+            Event::DockDidChangePref { .. }
+                | Event::DockDidRestart { .. }
+                | Event::MenuBarHiddenChanged { .. }
+        )
+    }) {
+        return;
+    }
+
+    debug!("screen layout changed, re-measuring in {SCREEN_SETTLE_DELAY_MS}ms");
+    let remeasure = |displays: Query<Entity, With<Display>>, mut commands: Commands| {
+        for entity in displays {
+            commands.trigger(ReadDisplayProperties(entity));
+        }
+    };
+    let system_id = commands.register_system(remeasure);
+    Timeout::callback(
+        Duration::from_millis(SCREEN_SETTLE_DELAY_MS),
+        system_id,
+        &mut commands,
+    );
+}
+
+/// Re-tiles the workspaces of a display after its Dock position or menubar changed,
+/// so the strips grow into (or move out of) the space reserved for them.
+#[allow(clippy::needless_pass_by_value, clippy::type_complexity)]
+fn display_geometry_changed(
+    changed: Query<Entity, (With<Display>, Or<(Changed<Display>, Changed<DockPosition>)>)>,
+    mut workspaces: Query<(&mut LayoutStrip, &ChildOf, Has<ActiveWorkspaceMarker>)>,
+    windows: Windows,
+    initializing: Option<Res<Initializing>>,
+    mut commands: Commands,
+) {
+    // Still consume the change ticks during startup, the initial layout takes care of these.
+    if changed.is_empty() || initializing.is_some() {
+        return;
+    }
+
+    let focused = windows.focused().map(|(_, entity)| entity);
+    for (mut strip, child_of, active) in &mut workspaces {
+        if !changed.contains(child_of.parent()) {
+            continue;
+        }
+        debug!(
+            "display geometry changed, re-tiling workspace {}",
+            strip.id()
+        );
+        strip.set_changed();
+
+        if active
+            && let Some(entity) = focused
+                .filter(|entity| strip.contains(*entity))
+                .or_else(|| strip.first().ok().and_then(|column| column.top()))
+        {
+            commands.reshuffle_around(entity);
+        }
+    }
+}
+
+/// Marks the displays left out of `managed_displays` with `UnmanagedDisplay`.
+/// Displays are numbered by their arrangement, so the marks are checked again
+/// whenever a display is added, moved or removed, and when the configuration
+/// changes.
+#[allow(clippy::needless_pass_by_value)]
+fn mark_unmanaged_displays(
+    displays: Query<(&Display, Entity, Has<UnmanagedDisplay>)>,
+    changed: Query<(), Changed<Display>>,
+    mut removed: RemovedComponents<Display>,
+    config: Res<Config>,
+    mut commands: Commands,
+) {
+    let removed = removed.read().count() > 0;
+    if !config.is_changed() && changed.is_empty() && !removed {
+        return;
+    }
+
+    let mut arranged = displays.iter().collect::<Vec<_>>();
+    arranged.sort_by_key(|(display, _, _)| (display.bounds().min.x, display.bounds().min.y));
+    for (index, (display, entity, marked)) in arranged.into_iter().enumerate() {
+        let managed = !config.has_managed_displays() || {
+            let uuid = Display::uuid_from_id(display.id())
+                .map(|uuid| uuid.to_string())
+                .unwrap_or_default();
+            config.manages_display(&uuid, index + 1)
+        };
+        if managed != marked {
+            continue;
+        }
+        let Ok(mut entity_commands) = commands.get_entity(entity) else {
+            continue;
+        };
+        debug!("display {} managed: {managed}", display.id());
+        if managed {
+            entity_commands.try_remove::<UnmanagedDisplay>();
+        } else {
+            entity_commands.try_insert(UnmanagedDisplay);
+        }
+    }
+}
+
+/// Floats the windows which ended up in a strip of an unmanaged display, e.g.
+/// moved there or tiled before the display was left out of `managed_displays`,
+/// so that the strips of the display stay empty.
+#[allow(clippy::needless_pass_by_value)]
+pub(super) fn release_unmanaged_display_windows(
+    strips: Query<(&LayoutStrip, &ChildOf)>,
+    unmanaged_displays: Query<(), With<UnmanagedDisplay>>,
+    mut commands: Commands,
+) {
+    for (strip, child_of) in strips {
+        if !unmanaged_displays.contains(child_of.parent()) || strip.is_fullscreen() {
+            continue;
+        }
+        for entity in strip.all_windows() {
+            debug!("window {entity} is on an unmanaged display, floating it.");
+            if let Ok(mut entity_commands) = commands.get_entity(entity) {
+                entity_commands.try_insert(Unmanaged::Floating);
+            }
+        }
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 fn read_display_properties_trigger(
     trigger: On<ReadDisplayProperties>,
-    mut displays: Query<(&mut Display, Entity)>,
+    mut displays: Query<(&mut Display, Entity, Option<&DockPosition>)>,
     platform: Option<NonSend<Pin<Box<PlatformCallbacks>>>>,
     config: Option<Res<Config>>,
     mut commands: Commands,
 ) {
-    let Ok((mut display, entity)) = displays.get_mut(trigger.event().0) else {
+    let Ok((mut display, entity, current_dock)) = displays.get_mut(trigger.event().0) else {
         return;
     };
     let display_id = display.id();
@@ -389,15 +677,34 @@ fn read_display_properties_trigger(
         debug!("notch on display {display_id}: {insets:?}");
         insets.top as i32
     });
-    if let Some(height) = notch {
+    if let Some(height) = notch
+        && display.notch_height() != height
+    {
         display.set_notch_height(height);
     }
 
+    // With "Automatically hide menu bar" the visible frame reaches the top edge of the screen.
+    let menubar_hidden = read_screen_property(&screens, display_id, |screen| {
+        let frame = screen.frame();
+        let visible_frame = screen.visibleFrame();
+        let top_inset = (frame.origin.y + frame.size.height)
+            - (visible_frame.origin.y + visible_frame.size.height);
+        top_inset < 1.0
+    });
+    if let Some(hidden) = menubar_hidden
+        && display.menubar_hidden() != hidden
+    {
+        debug!("menubar on display {display_id} hidden: {hidden}");
+        display.set_menubar_hidden(hidden);
+    }
+
     let dock = read_screen_property(&screens, display_id, |screen| {
         let visible_frame = irect_from(screen.visibleFrame());
         display.locate_dock(&visible_frame)
     });
-    if let Some(dock) = dock {
+    if let Some(dock) = dock
+        && current_dock != Some(&dock)
+    {
         debug!("dock on display {display_id}: {:?}", dock);
         if let Ok(mut entity_commands) = commands.get_entity(entity) {
             entity_commands.try_insert(dock);
@@ -406,6 +713,31 @@ fn read_display_properties_trigger(
 
     if let Some(config) = config {
         let height = config.menubar_height();
-        display.set_menubar_height_override(height);
+        if display.menubar_height_override() != height {
+            display.set_menubar_height_override(height);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rescale_keeps_proportions() {
+        let from = IVec2::new(3024, 1964);
+        let to = IVec2::new(1512, 982);
+        assert_eq!(
+            rescale(IVec2::new(1512, 1964), from, to),
+            IVec2::new(756, 982)
+        );
+        assert_eq!(
+            rescale(IVec2::new(1000, 500), from, from),
+            IVec2::new(1000, 500)
+        );
+        assert_eq!(
+            rescale(IVec2::new(100, 100), IVec2::ZERO, to),
+            IVec2::new(100, 100)
+        );
     }
 }
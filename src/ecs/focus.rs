@@ -1,7 +1,8 @@
 use std::collections::HashMap;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use bevy::app::{App, Plugin, PostUpdate};
+use bevy::ecs::component::Component;
 use bevy::ecs::entity::Entity;
 use bevy::ecs::hierarchy::ChildOf;
 use bevy::ecs::lifecycle::{Add, Remove};
@@ -40,6 +41,8 @@ pub struct TierMemory {
 #[derive(Default, Resource)]
 pub struct FocusHistory {
     by_workspace: HashMap<WorkspaceId, TierMemory>,
+    /// Workspaces in the order they were last focused, the most recent last.
+    recent: Vec<WorkspaceId>,
 }
 
 impl FocusHistory {
@@ -49,6 +52,8 @@ impl FocusHistory {
         entity: Entity,
         unmanaged: Option<&Unmanaged>,
     ) {
+        self.recent.retain(|recent| *recent != workspace);
+        self.recent.push(workspace);
         let slot = self.by_workspace.entry(workspace).or_default();
         match unmanaged {
             None => slot.last_managed = Some(entity),
@@ -69,6 +74,15 @@ impl FocusHistory {
             .and_then(|t| t.last_floating)
     }
 
+    /// Returns the last managed window of the most recently focused workspace other than `current`.
+    pub fn previous_managed(&self, current: WorkspaceId) -> Option<Entity> {
+        self.recent
+            .iter()
+            .rev()
+            .filter(|workspace| **workspace != current)
+            .find_map(|workspace| self.last_managed(*workspace))
+    }
+
     pub fn forget(&mut self, entity: Entity) {
         for slot in self.by_workspace.values_mut() {
             if slot.last_managed == Some(entity) {
@@ -82,9 +96,76 @@ impl FocusHistory {
 
     pub fn forget_workspace(&mut self, workspace: WorkspaceId) {
         self.by_workspace.remove(&workspace);
+        self.recent.retain(|recent| *recent != workspace);
+    }
+}
+
+/// When paneru first saw a window, and how often and how long it was focused
+/// since then. Times are kept in milliseconds since the Unix epoch.
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FocusStats {
+    created_at: u64,
+    last_focused_at: Option<u64>,
+    focus_count: u32,
+    focused_total: u64,
+    focused_since: Option<u64>,
+}
+
+impl FocusStats {
+    pub fn new(now: u64) -> Self {
+        Self {
+            created_at: now,
+            ..Self::default()
+        }
+    }
+
+    fn focused(&mut self, now: u64) {
+        if self.focused_since.is_none() {
+            self.focus_count += 1;
+            self.last_focused_at = Some(now);
+            self.focused_since = Some(now);
+        }
+    }
+
+    fn unfocused(&mut self, now: u64) {
+        if let Some(since) = self.focused_since.take() {
+            self.focused_total += now.saturating_sub(since);
+        }
+    }
+
+    /// Unix timestamp in seconds of when the window appeared.
+    pub fn created_at(&self) -> u64 {
+        self.created_at / 1000
+    }
+
+    /// Unix timestamp in seconds of when the window was last focused.
+    pub fn last_focused_at(&self) -> Option<u64> {
+        self.last_focused_at.map(|at| at / 1000)
+    }
+
+    pub fn focus_count(&self) -> u32 {
+        self.focus_count
+    }
+
+    /// Seconds the window has been focused in total, including its current focus.
+    pub fn focused_secs(&self, now: u64) -> u64 {
+        let current = self
+            .focused_since
+            .map_or(0, |since| now.saturating_sub(since));
+        (self.focused_total + current) / 1000
     }
 }
 
+/// Milliseconds since the Unix epoch, the time base of `FocusStats`.
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
 pub struct FocusEventsPlugin;
 
 impl Plugin for FocusEventsPlugin {
@@ -105,7 +186,10 @@ impl Plugin for FocusEventsPlugin {
             .add_observer(maintain_focus_singleton)
             .add_observer(virtual_strip_activated)
             .add_observer(stray_focus_observer)
-            .add_observer(focus_window_trigger);
+            .add_observer(focus_window_trigger)
+            .add_observer(window_stats_trigger)
+            .add_observer(focus_stats_trigger)
+            .add_observer(focus_stats_remove_trigger);
     }
 }
 
@@ -275,6 +359,41 @@ fn dim_remove_window_trigger(
     }
 }
 
+#[allow(clippy::needless_pass_by_value)]
+fn window_stats_trigger(trigger: On<Add, Window>, mut commands: Commands) {
+    if let Ok(mut entity_commands) = commands.get_entity(trigger.event().entity) {
+        entity_commands.insert_if_new(FocusStats::new(now_millis()));
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn focus_stats_trigger(
+    trigger: On<Add, FocusedMarker>,
+    mut stats: Query<&mut FocusStats>,
+    mut commands: Commands,
+) {
+    let entity = trigger.event().entity;
+    let now = now_millis();
+    if let Ok(mut stats) = stats.get_mut(entity) {
+        stats.focused(now);
+    } else if let Ok(mut entity_commands) = commands.get_entity(entity) {
+        // Focused before its stats were added along with the window.
+        let mut stats = FocusStats::new(now);
+        stats.focused(now);
+        entity_commands.insert_if_new(stats);
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn focus_stats_remove_trigger(
+    trigger: On<Remove, FocusedMarker>,
+    mut stats: Query<&mut FocusStats>,
+) {
+    if let Ok(mut stats) = stats.get_mut(trigger.event().entity) {
+        stats.unfocused(now_millis());
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 #[instrument(level = Level::DEBUG, skip_all, fields(trigger))]
 fn virtual_strip_activated(
@@ -293,11 +412,23 @@ fn virtual_strip_activated(
 }
 
 #[allow(clippy::needless_pass_by_value)]
-fn focus_window_trigger(trigger: On<FocusWindow>, windows: Windows, apps: Query<&Application>) {
+fn focus_window_trigger(
+    trigger: On<FocusWindow>,
+    windows: Windows,
+    apps: Query<&Application>,
+    window_manager: Res<WindowManager>,
+    config: Res<Config>,
+) {
     let FocusWindow { entity, raise } = *trigger.event();
-    let Some(window) = windows.get(entity) else {
+    let Some(mut window) = windows.get(entity) else {
         return;
     };
+    if config.focus_child_windows()
+        && let Some(child) = child_window(window, &windows, &window_manager)
+    {
+        debug!("focusing child {} of {}", child.id(), window.id());
+        window = child;
+    }
     let Some(psn) = windows.psn(window.id(), &apps) else {
         return;
     };
@@ -305,12 +436,32 @@ fn focus_window_trigger(trigger: On<FocusWindow>, windows: Windows, apps: Query<
         && let Some((focused_window, _)) = windows.focused()
         && let Some(focused_psn) = windows.psn(focused_window.id(), &apps)
     {
-        window.focus_without_raise(psn, focused_window, focused_psn);
+        window.focus_without_raise(psn, focused_window, focused_psn, config.focus_strategy());
     } else {
-        window.focus_with_raise(psn);
+        window.focus_with_raise(psn, config.focus_strategy());
     }
 }
 
+/// Returns the open sheet or drawer of a window, which is keyed in its place.
+fn child_window<'a>(
+    window: &Window,
+    windows: &'a Windows,
+    window_manager: &WindowManager,
+) -> Option<&'a Window> {
+    window_manager
+        .get_associated_windows(window.id())
+        .into_iter()
+        .find_map(|child_id| {
+            windows.find(child_id).and_then(|(child, _)| {
+                child
+                    .child_role()
+                    .inspect_err(|err| warn!("getting role of {child_id}: {err}"))
+                    .is_ok_and(|is_child| is_child)
+                    .then_some(child)
+            })
+        })
+}
+
 #[allow(clippy::needless_pass_by_value)]
 #[instrument(level = Level::DEBUG, skip_all)]
 fn recover_lost_focus(
@@ -360,6 +511,24 @@ mod tests {
     use super::*;
     use bevy::ecs::world::World;
 
+    #[test]
+    fn focus_stats_accumulate() {
+        let mut stats = FocusStats::new(1_000);
+        stats.focused(2_000);
+        // Repeated focus events of the same focus are counted once.
+        stats.focused(2_500);
+        assert_eq!(stats.focused_secs(5_000), 3);
+        stats.unfocused(5_000);
+        stats.unfocused(9_000);
+        stats.focused(10_000);
+        stats.unfocused(12_000);
+
+        assert_eq!(stats.created_at(), 1);
+        assert_eq!(stats.last_focused_at(), Some(10));
+        assert_eq!(stats.focus_count(), 2);
+        assert_eq!(stats.focused_secs(20_000), 5);
+    }
+
     #[test]
     fn record_and_read_per_tier() {
         let mut world = World::new();
@@ -380,7 +549,7 @@ mod tests {
         let entity = world.spawn(()).id();
         let mut history = FocusHistory::default();
 
-        history.record(1, entity, Some(&Unmanaged::Minimized));
+        history.record(1, entity, Some(&Unmanaged::Minimized(None)));
         history.record(1, entity, Some(&Unmanaged::Hidden));
 
         assert_eq!(history.last_managed(1), None);
@@ -419,6 +588,27 @@ mod tests {
         assert_eq!(history.last_managed(2), Some(other));
     }
 
+    #[test]
+    fn previous_managed_prefers_most_recent_workspace() {
+        let mut world = World::new();
+        let a = world.spawn(()).id();
+        let b = world.spawn(()).id();
+        let c = world.spawn(()).id();
+        let mut history = FocusHistory::default();
+
+        history.record(1, a, None);
+        history.record(2, b, None);
+        history.record(3, c, None);
+        assert_eq!(history.previous_managed(3), Some(b));
+
+        history.record(1, a, None);
+        assert_eq!(history.previous_managed(3), Some(a));
+
+        history.forget_workspace(1);
+        history.forget(b);
+        assert_eq!(history.previous_managed(3), None);
+    }
+
     #[test]
     fn forget_workspace_drops_entry() {
         let mut world = World::new();
@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::hierarchy::ChildOf;
+use bevy::ecs::message::MessageReader;
+use bevy::ecs::query::{Changed, Has};
+use bevy::ecs::resource::Resource;
+use bevy::ecs::schedule::IntoScheduleConfigs as _;
+use bevy::ecs::system::{Query, Res};
+use bevy::time::common_conditions::on_timer;
+use serde::Serialize;
+use tracing::debug;
+
+use crate::config::Config;
+use crate::ecs::focus::now_millis;
+use crate::ecs::layout::LayoutStrip;
+use crate::ecs::params::Windows;
+use crate::ecs::state::PaneruState;
+use crate::ecs::{ActiveDisplayMarker, ActiveWorkspaceMarker};
+use crate::events::Event;
+use crate::manager::{Application, Display};
+
+pub const JOURNAL_FILE_NAME: &str = "crash-journal.json";
+/// How many layout snapshots are kept alongside the events.
+const JOURNAL_SNAPSHOT_COUNT: usize = 4;
+/// Layout snapshots are expensive, so they are taken at most this often.
+const JOURNAL_SNAPSHOT_FREQ_MS: u64 = 500;
+
+#[derive(Clone, Debug, Serialize)]
+struct JournalEntry {
+    timestamp_ms: u64,
+    event: String,
+}
+
+/// Ring buffer of recent events and layout snapshots. Lives behind a mutex so
+/// the panic hook can flush it from whichever thread happens to panic.
+#[derive(Debug, Default, Serialize)]
+pub struct JournalBuffer {
+    panic: Option<String>,
+    events: VecDeque<JournalEntry>,
+    snapshots: VecDeque<PaneruState>,
+}
+
+impl JournalBuffer {
+    fn push_event(&mut self, capacity: usize, event: String) {
+        while self.events.len() >= capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(JournalEntry {
+            timestamp_ms: now_millis(),
+            event,
+        });
+    }
+
+    fn push_snapshot(&mut self, state: PaneruState) {
+        while self.snapshots.len() >= JOURNAL_SNAPSHOT_COUNT {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(state);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.events.is_empty() && self.snapshots.is_empty()
+    }
+}
+
+/// Shared handle to the crash journal. Cloned into the panic hook on startup.
+#[derive(Clone, Default, Resource)]
+pub struct CrashJournal(Arc<Mutex<JournalBuffer>>);
+
+impl CrashJournal {
+    pub fn record_event(&self, capacity: usize, event: &Event) {
+        if let Ok(mut buffer) = self.0.lock() {
+            buffer.push_event(capacity, format!("{event:?}"));
+        }
+    }
+
+    pub fn record_snapshot(&self, state: PaneruState) {
+        if let Ok(mut buffer) = self.0.lock() {
+            buffer.push_snapshot(state);
+        }
+    }
+
+    /// Writes the journal to `path`. Returns `Ok(false)` when there was nothing to write.
+    pub fn flush(&self, path: &Path, panic: Option<String>) -> std::io::Result<bool> {
+        // Never block inside the panic hook: if the panicking thread held the
+        // lock, give up instead of deadlocking.
+        let Ok(mut buffer) = self.0.try_lock() else {
+            return Ok(false);
+        };
+        if buffer.is_empty() {
+            return Ok(false);
+        }
+        buffer.panic = panic;
+        let json = serde_json::to_string_pretty(&*buffer).map_err(std::io::Error::other)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, json)?;
+        Ok(true)
+    }
+
+    /// Chains a panic hook which flushes the journal before the default handler runs.
+    pub fn install_panic_hook(&self) {
+        let journal = self.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info: &PanicHookInfo<'_>| {
+            let Some(path) = CrashJournal::default_file_path() else {
+                previous(info);
+                return;
+            };
+            match journal.flush(&path, Some(info.to_string())) {
+                Ok(true) => eprintln!("paneru: crash journal written to {}", path.display()),
+                Ok(false) => (),
+                Err(err) => eprintln!("paneru: unable to write crash journal: {err}"),
+            }
+            previous(info);
+        }));
+    }
+
+    /// Location of the journal, or `None` when there is no XDG state directory.
+    pub fn default_file_path() -> Option<PathBuf> {
+        xdg::BaseDirectories::with_prefix("paneru").get_state_file(JOURNAL_FILE_NAME)
+    }
+}
+
+pub struct JournalPlugin;
+
+impl Plugin for JournalPlugin {
+    fn build(&self, app: &mut App) {
+        let journal_enabled = |config: Option<Res<Config>>| {
+            config.is_some_and(|config| config.crash_journal_size().is_some())
+        };
+
+        app.init_resource::<CrashJournal>();
+        app.add_systems(
+            Update,
+            (
+                record_journal_events,
+                record_journal_snapshot
+                    .run_if(on_timer(Duration::from_millis(JOURNAL_SNAPSHOT_FREQ_MS))),
+            )
+                .run_if(journal_enabled),
+        );
+    }
+}
+
+/// Appends every incoming event, except the high frequency pointer noise, to the journal.
+#[allow(clippy::needless_pass_by_value)]
+fn record_journal_events(
+    mut messages: MessageReader<Event>,
+    journal: Res<CrashJournal>,
+    config: Res<Config>,
+) {
+    let Some(capacity) = config.crash_journal_size() else {
+        return;
+    };
+    for event in messages.read() {
+        if matches!(
+            event,
+            Event::MouseMoved { .. }
+                | Event::MouseDragged { .. }
+                | Event::Swipe { .. }
+                | Event::Scroll { .. }
+        ) {
+            continue;
+        }
+        journal.record_event(capacity, event);
+    }
+}
+
+/// Snapshots the layout, but only if some strip changed since the last snapshot.
+#[allow(clippy::needless_pass_by_value, clippy::type_complexity)]
+fn record_journal_snapshot(
+    changed: Query<(), Changed<LayoutStrip>>,
+    workspaces: Query<(Option<&ChildOf>, &LayoutStrip, Has<ActiveWorkspaceMarker>)>,
+    displays: Query<(&Display, Entity, Has<ActiveDisplayMarker>)>,
+    windows: Windows,
+    apps: Query<&Application>,
+    journal: Res<CrashJournal>,
+) {
+    if changed.is_empty() {
+        return;
+    }
+    debug!("recording layout snapshot in the crash journal");
+    journal.record_snapshot(PaneruState::extract(
+        &workspaces,
+        &displays,
+        &windows,
+        &apps,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_ring_buffer_drops_oldest() {
+        let journal = CrashJournal::default();
+        for _ in 0..5 {
+            journal.record_event(3, &Event::SpaceChanged);
+        }
+        journal.record_event(3, &Event::ThemeChanged);
+
+        let buffer = journal.0.lock().unwrap();
+        assert_eq!(buffer.events.len(), 3);
+        assert_eq!(buffer.events.back().unwrap().event, "ThemeChanged");
+    }
+
+    #[test]
+    fn test_journal_flush_writes_panic_message() {
+        let journal = CrashJournal::default();
+        let path =
+            std::env::temp_dir().join(format!("paneru-journal-test-{}.json", std::process::id()));
+        assert!(!journal.flush(&path, None).unwrap());
+
+        journal.record_event(8, &Event::SpaceChanged);
+        assert!(journal.flush(&path, Some("boom".to_string())).unwrap());
+
+        let written = fs::read_to_string(&path).unwrap();
+        _ = fs::remove_file(&path);
+        assert!(written.contains("boom"));
+        assert!(written.contains("SpaceChanged"));
+    }
+}
@@ -1,27 +1,38 @@
 use bevy::app::{App, Plugin, Update};
-use bevy::ecs::change_detection::{DetectChanges, DetectChangesMut, Ref};
+use bevy::ecs::change_detection::{DetectChanges, DetectChangesMut, Ref, Tick};
 use bevy::ecs::component::Component;
 use bevy::ecs::entity::{Entity, EntityHashMap, EntityHashSet};
 use bevy::ecs::hierarchy::ChildOf;
+use bevy::ecs::lifecycle::RemovedComponents;
 use bevy::ecs::query::{Changed, Has, Or, With, Without};
-use bevy::ecs::schedule::IntoScheduleConfigs as _;
 use bevy::ecs::schedule::common_conditions::{not, resource_exists};
-use bevy::ecs::system::{Commands, ParamSet, Populated, Query, Res};
-use bevy::math::IRect;
+use bevy::ecs::schedule::{IntoScheduleConfigs as _, SystemCondition as _};
+use bevy::ecs::system::{Commands, ParamSet, Populated, Query, Res, SystemChangeTick};
+use bevy::math::{IRect, IVec2};
+use bevy::time::common_conditions::on_timer;
+use std::cmp::Ordering;
 use std::collections::{HashMap, VecDeque};
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
 use stdext::function_name;
 use tracing::{Level, instrument, trace};
 
-use crate::config::Config;
+use crate::config::{CenterFocusedColumn, Config};
 use crate::ecs::params::Windows;
+use crate::ecs::systems::low_power_active;
 use crate::ecs::{
-    ActiveWorkspaceMarker, Bounds, DockPosition, EnsureVisibleMarker, Initializing, LayoutPosition,
-    Position, RepositionMarker, ReshuffleAroundMarker, Scrolling, SpawnCommandsExt,
+    ActiveWorkspaceMarker, Bounds, DockPosition, EnsureVisibleMarker, Initializing, LayoutLocked,
+    LayoutPosition, MinimumSize, Pinned, Position, RepositionMarker, ReshuffleAroundMarker,
+    Scrolling, ShrinkPriority, SpawnCommandsExt,
 };
 use crate::errors::{Error, Result};
-use crate::manager::{Display, Origin, Window};
+use crate::manager::{Display, Origin, Size, Window};
 use crate::platform::WorkspaceId;
 
+/// While saving power, reshuffles requested in between are handled together
+/// at this interval.
+const LOW_POWER_RESHUFFLE_INTERVAL: Duration = Duration::from_millis(250);
+
 pub struct LayoutEventsPlugin;
 
 impl Plugin for LayoutEventsPlugin {
@@ -32,12 +43,16 @@ impl Plugin for LayoutEventsPlugin {
                 // Wait for finish_setup before tiling: until then every window
                 // sits in the active strip regardless of its real display.
                 (
+                    super::display::release_unmanaged_display_windows,
                     sync_tab_group_frames,
                     layout_sizes_changed,
                     layout_strip_changed,
-                    reshuffle_layout_strip,
+                    reshuffle_layout_strip
+                        .run_if(not(low_power_active).or(on_timer(LOW_POWER_RESHUFFLE_INTERVAL))),
                     ensure_visible_in_strip,
                     position_layout_strips,
+                    pinned_columns_changed,
+                    layout_unlocked,
                     position_layout_windows,
                 )
                     .chain()
@@ -223,6 +238,40 @@ impl Iterator for ColumnWindowIter<'_> {
     }
 }
 
+/// How the items stacked in a column share its space.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColumnOrientation {
+    /// The items are stacked from top to bottom, sharing the height of the strip.
+    #[default]
+    Vertical,
+    /// The items sit side by side, sharing the width of the column.
+    Horizontal,
+}
+
+/// The arrangement of a strip before `window_stack_all` collapsed it into a
+/// single column, kept on the strip until `window_unstack_all` restores it.
+#[derive(Component, Clone, Debug)]
+pub struct StackedArrangement {
+    columns: Vec<Column>,
+    groups: Vec<Vec<Entity>>,
+    horizontal: EntityHashSet,
+    /// Widths of the windows before they were stacked.
+    pub widths: Vec<(Entity, i32)>,
+}
+
+/// Turns the items left of a stack back into a column: a plain column for a
+/// single item, or nothing when none are left.
+fn column_of_items(mut items: Vec<StackItem>) -> Option<Column> {
+    match items.len() {
+        0 => None,
+        1 => Some(match items.remove(0) {
+            StackItem::Single(id) => Column::Single(id),
+            StackItem::Tabs(tabs) => Column::Tabs(tabs),
+        }),
+        _ => Some(Column::Stack(items)),
+    }
+}
+
 /// `LayoutStrip` manages a horizontal strip of `Panel`s, where each panel can contain a single window or a stack of windows.
 /// It provides methods for manipulating the arrangement and access to windows within the pane.
 #[derive(Component, Debug, Default)]
@@ -230,6 +279,11 @@ pub struct LayoutStrip {
     id: WorkspaceId,
     pub virtual_index: u32,
     columns: VecDeque<Column>,
+    /// Windows of the column groups. A group spans the adjacent columns holding
+    /// its windows, and those columns are swapped and moved as a unit.
+    groups: Vec<Vec<Entity>>,
+    /// Windows of the stacked columns laid out side by side.
+    horizontal: EntityHashSet,
 }
 
 impl LayoutStrip {
@@ -238,6 +292,8 @@ impl LayoutStrip {
             id,
             virtual_index,
             columns: VecDeque::new(),
+            groups: Vec::new(),
+            horizontal: EntityHashSet::default(),
         }
     }
 
@@ -248,6 +304,8 @@ impl LayoutStrip {
             id,
             virtual_index: 0,
             columns,
+            groups: Vec::new(),
+            horizontal: EntityHashSet::default(),
         }
     }
 
@@ -440,6 +498,11 @@ impl LayoutStrip {
                     }
                 }
             }
+            for group in &mut self.groups {
+                group.retain(|id| *id != entity);
+            }
+            self.prune_groups();
+            self.horizontal.remove(&entity);
         }
     }
 
@@ -472,6 +535,13 @@ impl LayoutStrip {
         self.columns.swap(left, right);
     }
 
+    /// Moves a column to another index, shifting the columns in between by one.
+    pub fn move_column(&mut self, from: usize, to: usize) {
+        if let Some(column) = self.columns.remove(from) {
+            self.columns.insert(to.min(self.columns.len()), column);
+        }
+    }
+
     /// Returns the number of panels in the pane.
     ///
     /// # Returns
@@ -523,6 +593,33 @@ impl LayoutStrip {
             .and_then(|col| col.at_or_last(stack_pos))
     }
 
+    /// Returns the window stacked directly above the entity, or `None` when the entity
+    /// is not stacked or already at the top of its stack.
+    pub fn above(&self, entity: Entity) -> Option<Entity> {
+        self.stacked_neighbour(entity, |position| position.checked_sub(1))
+    }
+
+    /// Returns the window stacked directly below the entity, or `None` when the entity
+    /// is not stacked or already at the bottom of its stack.
+    pub fn below(&self, entity: Entity) -> Option<Entity> {
+        self.stacked_neighbour(entity, |position| position.checked_add(1))
+    }
+
+    fn stacked_neighbour<F>(&self, entity: Entity, step: F) -> Option<Entity>
+    where
+        F: Fn(usize) -> Option<usize>,
+    {
+        let index = self.index_of(entity).ok()?;
+        let Some(Column::Stack(stack)) = self.columns.get(index) else {
+            return None;
+        };
+        let position = stack.iter().position(|item| item.contains(entity))?;
+        // A tab group occupies a single stack slot; its top is the visible tab.
+        step(position)
+            .and_then(|position| stack.get(position))
+            .and_then(StackItem::top)
+    }
+
     /// Stacks the window with the given ID onto the panel to its left.
     /// If the window is already in a stack or is the leftmost window, no action is taken.
     ///
@@ -548,6 +645,7 @@ impl LayoutStrip {
             Column::Stack(items) => items,
         };
 
+        let orientation = self.orientation(&self.columns[index - 1]);
         let target_column = self.columns.remove(index - 1).unwrap();
         let new_column = match target_column {
             Column::Fullscren(_) => return Ok(()),
@@ -561,6 +659,7 @@ impl LayoutStrip {
         };
 
         self.columns.insert(index - 1, new_column);
+        self.set_orientation(index - 1, orientation);
         Ok(())
     }
 
@@ -585,6 +684,9 @@ impl LayoutStrip {
                 .ok_or(Error::NotFound(format!("Entity {entity} not in stack")))?;
 
             let removed_item = items.remove(item_index);
+            for entity in removed_item.window_iter() {
+                self.horizontal.remove(&entity);
+            }
 
             // Re-insert the unstacked item as a single/tabs panel
             let unstacked_column = match removed_item {
@@ -613,6 +715,465 @@ impl LayoutStrip {
         }
     }
 
+    /// Collapses all columns of the strip into a single stack, from left to right.
+    ///
+    /// # Returns
+    ///
+    /// The previous arrangement, or `None` if there is nothing to stack or the
+    /// strip is full screen.
+    pub fn stack_all(&mut self) -> Option<StackedArrangement> {
+        if self.columns.len() < 2 || self.is_fullscreen() {
+            return None;
+        }
+        let arrangement = StackedArrangement {
+            columns: self.columns.iter().cloned().collect(),
+            groups: std::mem::take(&mut self.groups),
+            horizontal: std::mem::take(&mut self.horizontal),
+            widths: vec![],
+        };
+        let items = self
+            .columns
+            .drain(..)
+            .flat_map(|column| match column {
+                Column::Single(id) | Column::Fullscren(id) => vec![StackItem::Single(id)],
+                Column::Tabs(tabs) => vec![StackItem::Tabs(tabs)],
+                Column::Stack(items) => items,
+            })
+            .collect();
+        self.columns.push_back(Column::Stack(items));
+        Some(arrangement)
+    }
+
+    /// Splits every stack of the strip into columns of their own, from top to bottom.
+    pub fn unstack_all(&mut self) {
+        self.columns = self
+            .columns
+            .drain(..)
+            .flat_map(|column| match column {
+                Column::Stack(items) => items
+                    .into_iter()
+                    .filter_map(|item| column_of_items(vec![item]))
+                    .collect(),
+                column => vec![column],
+            })
+            .collect();
+        self.horizontal.clear();
+    }
+
+    /// Restores an arrangement saved by `stack_all`. Windows which have left
+    /// the strip since are skipped, and windows which joined it are placed in
+    /// columns of their own after the restored ones.
+    pub fn restore_arrangement(&mut self, arrangement: StackedArrangement) {
+        let present = self.all_windows();
+        let keep = |entity: &Entity| present.contains(entity);
+        let mut restored = arrangement
+            .columns
+            .into_iter()
+            .filter_map(|column| match column {
+                Column::Single(id) | Column::Fullscren(id) => {
+                    keep(&id).then_some(Column::Single(id))
+                }
+                Column::Tabs(mut tabs) => {
+                    tabs.retain(keep);
+                    (!tabs.is_empty()).then_some(Column::Tabs(tabs))
+                }
+                Column::Stack(items) => column_of_items(
+                    items
+                        .into_iter()
+                        .filter_map(|item| match item {
+                            StackItem::Single(id) => keep(&id).then_some(StackItem::Single(id)),
+                            StackItem::Tabs(mut tabs) => {
+                                tabs.retain(keep);
+                                (!tabs.is_empty()).then_some(StackItem::Tabs(tabs))
+                            }
+                        })
+                        .collect(),
+                ),
+            })
+            .collect::<VecDeque<_>>();
+        self.groups = arrangement.groups;
+        for group in &mut self.groups {
+            group.retain(keep);
+        }
+        self.horizontal = arrangement.horizontal;
+        self.horizontal.retain(keep);
+
+        let restored_windows = restored
+            .iter()
+            .flat_map(Column::window_iter)
+            .collect::<EntityHashSet>();
+        for column in self.columns.drain(..) {
+            let joined = match column {
+                Column::Stack(items) => items,
+                Column::Tabs(tabs) => vec![StackItem::Tabs(tabs)],
+                Column::Single(id) | Column::Fullscren(id) => vec![StackItem::Single(id)],
+            };
+            restored.extend(
+                joined
+                    .into_iter()
+                    .filter(|item| item.window_iter().all(|id| !restored_windows.contains(&id)))
+                    .filter_map(|item| column_of_items(vec![item])),
+            );
+        }
+        self.columns = restored;
+        self.prune_groups();
+    }
+
+    /// Lays out the windows of the strip in other columns, each given by its
+    /// stack items from top to bottom. Groups whose windows still fill adjacent
+    /// columns are kept, and so is the orientation of the stacks.
+    pub fn rearrange(&mut self, columns: Vec<Vec<StackItem>>) {
+        self.columns = columns.into_iter().filter_map(column_of_items).collect();
+        let columns = &self.columns;
+        self.groups.retain(|group| {
+            let held = columns
+                .iter()
+                .map(|column| column.window_iter().any(|entity| group.contains(&entity)))
+                .collect::<Vec<_>>();
+            let first = held.iter().position(|held| *held);
+            let last = held.iter().rposition(|held| *held);
+            matches!((first, last), (Some(first), Some(last))
+                if first < last && held[first..=last].iter().all(|held| *held))
+        });
+        self.horizontal.retain(|entity| {
+            columns.iter().any(|column| {
+                matches!(column, Column::Stack(_)) && column.window_iter().any(|id| id == *entity)
+            })
+        });
+    }
+
+    /// Takes the stack item holding the window out of its column. A column left
+    /// with a single item collapses back into a plain column.
+    ///
+    /// # Returns
+    ///
+    /// The removed item, and whether the rest of its column stayed in the strip.
+    fn take_item(&mut self, entity: Entity) -> Result<(StackItem, bool)> {
+        let index = self.index_of(entity)?;
+        let Some(column) = self.columns.remove(index) else {
+            return Err(Error::NotFound(format!("{}: {entity}", function_name!())));
+        };
+        let mut items = match column {
+            Column::Single(id) | Column::Fullscren(id) => {
+                return Ok((StackItem::Single(id), false));
+            }
+            Column::Tabs(tabs) => return Ok((StackItem::Tabs(tabs), false)),
+            Column::Stack(items) => items,
+        };
+        let position = items
+            .iter()
+            .position(|item| item.contains(entity))
+            .ok_or(Error::NotFound(format!("Entity {entity} not in stack")))?;
+        let item = items.remove(position);
+        for entity in item.window_iter() {
+            self.horizontal.remove(&entity);
+        }
+        let rest = match items.len() {
+            0 => return Ok((item, false)),
+            1 => match items.remove(0) {
+                StackItem::Single(id) => Column::Single(id),
+                StackItem::Tabs(tabs) => Column::Tabs(tabs),
+            },
+            _ => Column::Stack(items),
+        };
+        self.columns.insert(index, rest);
+        Ok((item, true))
+    }
+
+    /// Moves the window past the whole neighbouring column, into a column of its own.
+    /// The neighbouring column is kept intact, even when it is a stack. A stacked window
+    /// first leaves its own stack, landing right next to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - Entity of the window to move.
+    /// * `east` - Moves the window to the right when `true`, otherwise to the left.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the move is successful or not needed, otherwise `Err(Error)` if the window is not found.
+    pub fn move_past(&mut self, entity: Entity, east: bool) -> Result<()> {
+        let index = self.index_of(entity)?;
+        if matches!(self.columns.get(index), Some(Column::Fullscren(_))) {
+            return Ok(());
+        }
+        let (item, rest_stayed) = self.take_item(entity)?;
+        let column = match item {
+            StackItem::Single(id) => Column::Single(id),
+            StackItem::Tabs(tabs) => Column::Tabs(tabs),
+        };
+        let target = match (rest_stayed, east) {
+            (_, true) => index + 1,
+            (true, false) => index,
+            (false, false) => index.saturating_sub(1),
+        };
+        self.columns.insert(target.min(self.columns.len()), column);
+        Ok(())
+    }
+
+    /// Pushes the window onto the bottom of the neighbouring column, turning it into
+    /// a stack if needed. Nothing happens at the edges of the strip or next to a
+    /// fullscreen column.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - Entity of the window to move.
+    /// * `east` - Uses the column to the right when `true`, otherwise the one to the left.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the move is successful or not needed, otherwise `Err(Error)` if the window is not found.
+    pub fn move_into_stack(&mut self, entity: Entity, east: bool) -> Result<()> {
+        let index = self.index_of(entity)?;
+        let neighbour = if east {
+            index + 1
+        } else if let Some(index) = index.checked_sub(1) {
+            index
+        } else {
+            return Ok(());
+        };
+        if !matches!(self.columns.get(neighbour), Some(column) if !matches!(column, Column::Fullscren(_)))
+            || matches!(self.columns.get(index), Some(Column::Fullscren(_)))
+        {
+            return Ok(());
+        }
+
+        let (item, rest_stayed) = self.take_item(entity)?;
+        // The neighbour on the right shifts left when the whole column was taken out.
+        let neighbour = if east && !rest_stayed {
+            neighbour - 1
+        } else {
+            neighbour
+        };
+        let orientation = self
+            .columns
+            .get(neighbour)
+            .map(|column| self.orientation(column))
+            .unwrap_or_default();
+        let Some(column) = self.columns.remove(neighbour) else {
+            return Err(Error::NotFound(format!(
+                "{}: {neighbour}",
+                function_name!()
+            )));
+        };
+        let mut items = match column {
+            Column::Single(id) | Column::Fullscren(id) => vec![StackItem::Single(id)],
+            Column::Tabs(tabs) => vec![StackItem::Tabs(tabs)],
+            Column::Stack(items) => items,
+        };
+        items.push(item);
+        self.columns.insert(neighbour, Column::Stack(items));
+        self.set_orientation(neighbour, orientation);
+        Ok(())
+    }
+
+    /// Returns the group the column belongs to, if any.
+    fn group_of(&self, column: &Column) -> Option<&Vec<Entity>> {
+        self.groups
+            .iter()
+            .find(|group| column.window_iter().any(|entity| group.contains(&entity)))
+    }
+
+    /// Returns the columns which move together with the column at `index`:
+    /// the adjacent columns of its group, or only the column itself.
+    pub fn group_range(&self, index: usize) -> RangeInclusive<usize> {
+        let Some(group) = self
+            .columns
+            .get(index)
+            .and_then(|column| self.group_of(column))
+        else {
+            return index..=index;
+        };
+        let in_group = |at: usize| {
+            self.columns
+                .get(at)
+                .is_some_and(|column| column.window_iter().any(|entity| group.contains(&entity)))
+        };
+        let mut start = index;
+        while start > 0 && in_group(start - 1) {
+            start -= 1;
+        }
+        let mut end = index;
+        while in_group(end + 1) {
+            end += 1;
+        }
+        start..=end
+    }
+
+    /// Returns `true` if the column of the window is part of a group.
+    pub fn is_grouped(&self, entity: Entity) -> bool {
+        self.index_of(entity)
+            .is_ok_and(|index| self.group_range(index).count() > 1)
+    }
+
+    /// Groups the column of the window with the column to its right. When
+    /// either of them is already grouped, the groups are merged.
+    pub fn group_with_next(&mut self, entity: Entity) -> Result<()> {
+        let own = self.group_range(self.index_of(entity)?);
+        let next = *own.end() + 1;
+        let range = *own.start()..=*self.group_range(next).end();
+        if next >= self.columns.len()
+            || self
+                .columns
+                .range(range.clone())
+                .any(|column| matches!(column, Column::Fullscren(_)))
+        {
+            return Err(Error::InvalidInput(format!(
+                "{}: no column to group with {entity}",
+                function_name!()
+            )));
+        }
+        let windows = self
+            .columns
+            .range(range)
+            .flat_map(Column::window_iter)
+            .collect::<Vec<_>>();
+        self.groups
+            .retain(|group| !group.iter().any(|entity| windows.contains(entity)));
+        self.groups.push(windows);
+        Ok(())
+    }
+
+    /// Dissolves the group of the window's column, if it is in one.
+    pub fn ungroup(&mut self, entity: Entity) {
+        let Ok(index) = self.index_of(entity) else {
+            return;
+        };
+        let windows = self
+            .columns
+            .range(self.group_range(index))
+            .flat_map(Column::window_iter)
+            .collect::<Vec<_>>();
+        self.groups
+            .retain(|group| !group.iter().any(|entity| windows.contains(entity)));
+    }
+
+    /// Moves the window's column, or its whole group, past the neighbouring
+    /// column or group. Returns `false` when neither side is grouped, leaving
+    /// plain swaps to the caller.
+    pub fn move_group(&mut self, entity: Entity, east: bool) -> Result<bool> {
+        let own = self.group_range(self.index_of(entity)?);
+        let own_len = own.clone().count();
+        let neighbour = if east {
+            Some(*own.end() + 1).filter(|index| *index < self.columns.len())
+        } else {
+            own.start().checked_sub(1)
+        };
+        let Some(neighbour) = neighbour else {
+            // At the edge of the strip a group stays where it is.
+            return Ok(own_len > 1);
+        };
+        let other = self.group_range(neighbour);
+        let other_len = other.clone().count();
+        if own_len == 1 && other_len == 1 {
+            return Ok(false);
+        }
+
+        let (start, end, first_len) = if east {
+            (*own.start(), *other.end(), own_len)
+        } else {
+            (*other.start(), *own.end(), other_len)
+        };
+        self.columns.make_contiguous()[start..=end].rotate_left(first_len);
+        Ok(true)
+    }
+
+    /// Takes the window's column, or its whole group, out of the strip.
+    /// Returns the columns and the index where they were.
+    pub fn take_group(&mut self, entity: Entity) -> Result<(Vec<Column>, usize)> {
+        let range = self.group_range(self.index_of(entity)?);
+        let start = *range.start();
+        let columns = self.columns.drain(range).collect::<Vec<_>>();
+        self.prune_groups();
+        Ok((columns, start))
+    }
+
+    /// Appends columns taken with `take_group`, grouping them again.
+    pub fn append_group(&mut self, columns: Vec<Column>) {
+        if columns.len() > 1 {
+            self.groups
+                .push(columns.iter().flat_map(Column::window_iter).collect());
+        }
+        self.columns.extend(columns);
+    }
+
+    /// Returns how the items of a column share its space. Only stacks can be
+    /// laid out side by side.
+    pub fn orientation(&self, column: &Column) -> ColumnOrientation {
+        if matches!(column, Column::Stack(_))
+            && column
+                .window_iter()
+                .any(|entity| self.horizontal.contains(&entity))
+        {
+            ColumnOrientation::Horizontal
+        } else {
+            ColumnOrientation::Vertical
+        }
+    }
+
+    fn set_orientation(&mut self, index: usize, orientation: ColumnOrientation) {
+        let Some(column) = self.columns.get(index) else {
+            return;
+        };
+        for entity in column.window_iter() {
+            if orientation == ColumnOrientation::Horizontal {
+                self.horizontal.insert(entity);
+            } else {
+                self.horizontal.remove(&entity);
+            }
+        }
+    }
+
+    /// Switches the stacked column holding the window between top-to-bottom
+    /// and side-by-side. Nothing happens to columns without a stack.
+    ///
+    /// # Returns
+    ///
+    /// The new orientation of the column, or `Err(Error)` if the window is not found.
+    pub fn toggle_orientation(&mut self, entity: Entity) -> Result<ColumnOrientation> {
+        let index = self.index_of(entity)?;
+        let column = self.get(index)?;
+        if !matches!(column, Column::Stack(_)) {
+            return Ok(ColumnOrientation::Vertical);
+        }
+        let orientation = match self.orientation(&column) {
+            ColumnOrientation::Vertical => ColumnOrientation::Horizontal,
+            ColumnOrientation::Horizontal => ColumnOrientation::Vertical,
+        };
+        self.set_orientation(index, orientation);
+        Ok(orientation)
+    }
+
+    /// Returns the width the column takes in the strip: the widest window, or
+    /// the widths of the items added up when they sit side by side.
+    pub fn column_width<W>(&self, column: &Column, get_window_frame: &W) -> Option<i32>
+    where
+        W: Fn(Entity) -> Option<IRect>,
+    {
+        match column {
+            Column::Stack(items) if self.orientation(column) == ColumnOrientation::Horizontal => {
+                items
+                    .iter()
+                    .filter_map(|item| item.top().and_then(get_window_frame))
+                    .map(|frame| frame.width())
+                    .reduce(|sum, width| sum + width)
+            }
+            _ => column.width(get_window_frame),
+        }
+    }
+
+    /// Drops the groups which no longer span at least two columns.
+    fn prune_groups(&mut self) {
+        let columns = &self.columns;
+        self.groups.retain(|group| {
+            columns
+                .iter()
+                .filter(|column| column.window_iter().any(|entity| group.contains(&entity)))
+                .count()
+                > 1
+        });
+    }
+
     /// Returns a vector of all window IDs present in all panels within the pane, maintaining their order.
     /// For stacked panels, all windows in the stack are included.
     ///
@@ -654,11 +1215,57 @@ impl LayoutStrip {
     ) -> impl Iterator<Item = (Entity, IRect)>
     where
         W: Fn(Entity) -> Option<IRect>,
+    {
+        self.relative_positions_with_limits(
+            Size::new(i32::MAX, layout_strip_height),
+            get_window_frame,
+            |_| None,
+            |_| None,
+        )
+    }
+
+    /// Like `relative_positions`, but never sizes a window below the minimum
+    /// size returned by `get_min_size`. When windows have a shrink priority,
+    /// columns wider together than the viewport and stacks taller than it give
+    /// up their size in the order of `get_shrink_priority`, see `squeeze_sizes`.
+    pub fn relative_positions_with_limits<W, M, P>(
+        &self,
+        viewport: Size,
+        get_window_frame: &W,
+        get_min_size: M,
+        get_shrink_priority: P,
+    ) -> impl Iterator<Item = (Entity, IRect)>
+    where
+        W: Fn(Entity) -> Option<IRect>,
+        M: Fn(Entity) -> Option<Size>,
+        P: Fn(Entity) -> Option<u32>,
     {
         const MIN_WINDOW_HEIGHT: i32 = 200;
+        let layout_strip_height = viewport.y;
+
+        let get_min_frame = |entity| {
+            let mut frame = get_window_frame(entity)?;
+            if let Some(min_size) = get_min_size(entity) {
+                frame.max = frame.min + frame.size().max(min_size);
+            }
+            Some(frame)
+        };
+        let squeezed_widths = self.squeezed_widths(
+            viewport.x,
+            &get_min_frame,
+            &get_min_size,
+            &get_shrink_priority,
+        );
+        let get_window_frame = |entity| {
+            let mut frame = get_min_frame(entity)?;
+            if let Some(width) = squeezed_widths.get(&entity) {
+                frame.max.x = frame.min.x + width;
+            }
+            Some(frame)
+        };
 
-        self.column_positions(get_window_frame)
-            .filter_map(move |(column, position)| {
+        self.column_positions(&get_window_frame)
+            .filter_map(|(column, position)| {
                 let items: Vec<StackItem> = match column {
                     Column::Single(entity) | Column::Fullscren(entity) => {
                         vec![StackItem::Single(*entity)]
@@ -667,14 +1274,73 @@ impl LayoutStrip {
                     Column::Tabs(tabs) => vec![StackItem::Tabs(tabs.clone())],
                 };
 
+                if self.orientation(column) == ColumnOrientation::Horizontal {
+                    // Side by side, every item keeps its width and takes the full height.
+                    let mut next_x = position;
+                    let frames = items
+                        .iter()
+                        .filter_map(|item| {
+                            let mut frame = get_window_frame(item.top()?)?;
+                            let width = frame.width();
+                            frame.min = IVec2::new(next_x, 0);
+                            frame.max = IVec2::new(next_x + width, layout_strip_height);
+                            next_x = frame.max.x;
+                            Some(item.window_iter().map(|e| (e, frame)).collect::<Vec<_>>())
+                        })
+                        .flatten()
+                        .collect::<Vec<_>>();
+                    return Some(frames);
+                }
+
                 let current_heights = items
                     .iter()
-                    .filter_map(|item| item.top().and_then(get_window_frame))
+                    .filter_map(|item| item.top().and_then(&get_window_frame))
                     .map(|frame| frame.height())
                     .collect::<Vec<_>>();
+                let mut min_heights = items
+                    .iter()
+                    .map(|item| {
+                        item.window_iter()
+                            .filter_map(&get_min_size)
+                            .map(|size| size.y)
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    .collect::<Vec<_>>();
 
-                let heights =
-                    binpack_heights(&current_heights, MIN_WINDOW_HEIGHT, layout_strip_height)?;
+                let priorities = items
+                    .iter()
+                    .map(|item| {
+                        shrink_priority(item.window_iter().filter_map(&get_shrink_priority))
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut requested_heights = current_heights.clone();
+                if priorities.iter().any(Option::is_some) {
+                    let lowest = min_heights
+                        .iter()
+                        .map(|min_height| (*min_height).max(MIN_WINDOW_HEIGHT))
+                        .collect::<Vec<_>>();
+                    squeeze_sizes(
+                        &mut requested_heights,
+                        &priorities,
+                        &lowest,
+                        layout_strip_height,
+                    );
+                    // Windows which never shrink keep the height they asked for.
+                    for ((min_height, priority), current) in min_heights
+                        .iter_mut()
+                        .zip(&priorities)
+                        .zip(&current_heights)
+                    {
+                        if *priority == Some(0) {
+                            *min_height = (*min_height).max(*current);
+                        }
+                    }
+                }
+                let mut heights =
+                    binpack_heights(&requested_heights, MIN_WINDOW_HEIGHT, layout_strip_height)?;
+                apply_min_heights(&mut heights, &min_heights);
 
                 // Every window in a column shares the master's (top item's)
                 // width, so a window stacked onto a master of a different width
@@ -686,6 +1352,11 @@ impl LayoutStrip {
                     .and_then(StackItem::top)
                     .and_then(&get_window_frame)
                     .map(|frame| frame.width())?;
+                let column_width = column
+                    .window_iter()
+                    .filter_map(&get_min_size)
+                    .map(|size| size.x)
+                    .fold(column_width, i32::max);
 
                 let mut next_y = 0;
                 let frames = items
@@ -712,6 +1383,76 @@ impl LayoutStrip {
                 Some(frames)
             })
             .flatten()
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Narrows the columns when, together, they are wider than `viewport_width`
+    /// and a window of the strip has a shrink priority. Returns the new width of
+    /// the windows in each narrowed column. Columns with their items side by
+    /// side keep their width.
+    fn squeezed_widths<W, M, P>(
+        &self,
+        viewport_width: i32,
+        get_window_frame: &W,
+        get_min_size: &M,
+        get_shrink_priority: &P,
+    ) -> EntityHashMap<i32>
+    where
+        W: Fn(Entity) -> Option<IRect>,
+        M: Fn(Entity) -> Option<Size>,
+        P: Fn(Entity) -> Option<u32>,
+    {
+        const MIN_WINDOW_WIDTH: i32 = 200;
+
+        let columns = self
+            .columns()
+            .filter_map(|column| {
+                let width = self.column_width(column, get_window_frame)?;
+                let priority = if self.orientation(column) == ColumnOrientation::Horizontal {
+                    Some(0)
+                } else {
+                    shrink_priority(column.window_iter().filter_map(get_shrink_priority))
+                };
+                let min_width = column
+                    .window_iter()
+                    .filter_map(get_min_size)
+                    .map(|size| size.x)
+                    .fold(MIN_WINDOW_WIDTH, i32::max);
+                Some((column, width, priority, min_width))
+            })
+            .collect::<Vec<_>>();
+        let mut widths = columns
+            .iter()
+            .map(|(_, width, ..)| *width)
+            .collect::<Vec<_>>();
+        let prioritized = self.columns().any(|column| {
+            column
+                .window_iter()
+                .any(|entity| get_shrink_priority(entity).is_some())
+        });
+        if !prioritized || widths.iter().sum::<i32>() <= viewport_width {
+            return EntityHashMap::default();
+        }
+
+        let priorities = columns
+            .iter()
+            .map(|(_, _, priority, _)| *priority)
+            .collect::<Vec<_>>();
+        let min_widths = columns
+            .iter()
+            .map(|(.., min_width)| *min_width)
+            .collect::<Vec<_>>();
+        squeeze_sizes(&mut widths, &priorities, &min_widths, viewport_width);
+
+        columns
+            .iter()
+            .zip(widths)
+            .filter(|((_, width, ..), squeezed)| squeezed != width)
+            .flat_map(|((column, ..), squeezed)| {
+                column.window_iter().map(move |entity| (entity, squeezed))
+            })
+            .collect()
     }
 
     #[instrument(level = Level::TRACE, skip_all)]
@@ -722,7 +1463,7 @@ impl LayoutStrip {
         let mut left_edge = 0;
 
         self.columns().filter_map(move |column| {
-            let width = column.width(get_window_frame);
+            let width = self.column_width(column, get_window_frame);
 
             width.map(|width| {
                 let temp = left_edge;
@@ -803,6 +1544,186 @@ fn dedup_entities(entities: &[Entity]) -> Vec<Entity> {
         .collect()
 }
 
+/// Picks the next width ratio from the presets in `widths` when growing or
+/// shrinking a window with the `current` ratio. A current ratio matching a
+/// preset steps to its neighbour in the list, so presets are cycled in their
+/// configured order. Otherwise the closest larger or smaller preset is used.
+/// When `cycle` is set, stepping past either end wraps around.
+pub fn next_size_ratio(widths: &[f64], current: f64, grow: bool, cycle: bool) -> f64 {
+    const TOLERANCE: f64 = 0.05;
+
+    let first = *widths.first().unwrap_or(&0.5);
+    let last = *widths.last().unwrap_or(&first);
+    let (past_end, before_start) = if cycle { (first, last) } else { (last, first) };
+
+    if let Some(index) = widths
+        .iter()
+        .position(|ratio| (ratio - current).abs() <= TOLERANCE)
+    {
+        return if grow {
+            widths.get(index + 1).copied().unwrap_or(past_end)
+        } else {
+            index
+                .checked_sub(1)
+                .and_then(|index| widths.get(index).copied())
+                .unwrap_or(before_start)
+        };
+    }
+
+    if grow {
+        widths
+            .iter()
+            .copied()
+            .find(|&ratio| ratio > current + TOLERANCE)
+            .unwrap_or(past_end)
+    } else {
+        widths
+            .iter()
+            .rev()
+            .copied()
+            .find(|&ratio| ratio < current - TOLERANCE)
+            .unwrap_or(before_start)
+    }
+}
+
+/// Returns the origin which centers a window of `size` over the `parent`
+/// frame, shifted as little as needed to keep it within `viewport`. The
+/// parent itself may be scrolled partially or entirely off-screen.
+pub fn center_over(parent: IRect, size: IVec2, viewport: IRect) -> IVec2 {
+    let origin = parent.center() - size / 2;
+    let max = (viewport.max - size).max(viewport.min);
+    origin.clamp(viewport.min, max)
+}
+
+/// Strip offset which puts the middle of `column`, given in strip coordinates,
+/// in the middle of the viewport. `None` when `mode` leaves the strip to scroll
+/// only as far as needed: always for `Never`, and for `OnOverflow` when the strip
+/// fits the viewport or the column is already entirely on screen at `strip_x`.
+pub fn centered_strip_offset(
+    mode: CenterFocusedColumn,
+    column: IRect,
+    strip_x: i32,
+    strip_width: i32,
+    viewport: IRect,
+) -> Option<i32> {
+    let centered = viewport.center().x - column.center().x;
+    match mode {
+        CenterFocusedColumn::Never => None,
+        CenterFocusedColumn::Always => Some(centered),
+        CenterFocusedColumn::OnOverflow => {
+            let on_screen = viewport.min.x <= column.min.x + strip_x
+                && column.max.x + strip_x <= viewport.max.x;
+            (strip_width > viewport.width() && !on_screen).then_some(centered)
+        }
+    }
+}
+
+/// Strip offset closest to `offset` which lines up the left or right edge of a
+/// column with the same edge of the viewport. `columns` are the horizontal spans
+/// of the columns in strip coordinates, from left to right. Offsets which would
+/// leave empty space at either end of the strip, or are further than
+/// `max_distance` from `offset`, are not considered.
+pub fn nearest_column_snap(
+    offset: i32,
+    columns: &[(i32, i32)],
+    viewport: IRect,
+    max_distance: i32,
+) -> Option<i32> {
+    let strip_width = columns.last()?.1;
+    let lowest = (viewport.max.x - strip_width).min(viewport.min.x);
+    columns
+        .iter()
+        .flat_map(|&(left, right)| [viewport.min.x - left, viewport.max.x - right])
+        .filter(|snap| (lowest..=viewport.min.x).contains(snap))
+        .min_by_key(|snap| (snap - offset).abs())
+        .filter(|snap| (snap - offset).abs() <= max_distance)
+}
+
+/// Screen x of a column while another column of the strip is pinned at
+/// `pinned_x`. The other columns scroll as if the pinned one was taken out of
+/// the strip, and hop over the span it occupies on screen.
+pub fn flow_around_pinned(
+    x: i32,
+    width: i32,
+    after_pinned: bool,
+    pinned_x: i32,
+    pinned_width: i32,
+) -> i32 {
+    let x = if after_pinned { x - pinned_width } else { x };
+    if x + width / 2 < pinned_x {
+        x
+    } else {
+        x + pinned_width
+    }
+}
+
+/// Raises the windows below their minimum height to it, taking the space from
+/// the windows which have room to spare, starting from the bottom of the column.
+/// When the column is too short for all minimums, it overflows the viewport.
+pub fn apply_min_heights(heights: &mut [i32], min_heights: &[i32]) {
+    let min_height = |index: usize| min_heights.get(index).copied().unwrap_or(0);
+    for index in 0..heights.len() {
+        let mut deficit = min_height(index) - heights[index];
+        if deficit <= 0 {
+            continue;
+        }
+        heights[index] += deficit;
+        for donor in (0..heights.len()).rev() {
+            if donor == index || deficit == 0 {
+                continue;
+            }
+            let spare = (heights[donor] - min_height(donor)).max(0);
+            let taken = spare.min(deficit);
+            heights[donor] -= taken;
+            deficit -= taken;
+        }
+    }
+}
+
+/// The shrink priority of windows sharing a slot: `0` when one of them never
+/// shrinks, otherwise the one keeping its size the longest.
+fn shrink_priority(priorities: impl Iterator<Item = u32>) -> Option<u32> {
+    priorities.reduce(|a, b| if a == 0 || b == 0 { 0 } else { a.max(b) })
+}
+
+/// Shrinks the `sizes` of the columns or stacked windows which do not fit into
+/// `total`, taking from the lowest shrink priority first and evenly among equal
+/// ones. Sizes without a priority count as `1`, while those with priority `0`
+/// are never shrunk. No size goes below its entry in `min_sizes`.
+pub fn squeeze_sizes(sizes: &mut [i32], priorities: &[Option<u32>], min_sizes: &[i32], total: i32) {
+    let priority = |index: usize| priorities.get(index).copied().flatten().unwrap_or(1);
+    let min_size = |index: usize| min_sizes.get(index).copied().unwrap_or(0);
+    let mut excess = sizes.iter().sum::<i32>() - total;
+    let mut levels = (0..sizes.len())
+        .map(priority)
+        .filter(|priority| *priority > 0)
+        .collect::<Vec<_>>();
+    levels.sort_unstable();
+    levels.dedup();
+
+    for level in levels {
+        // Spread the excess evenly, handing the rounding remainder to the
+        // last windows, until the level runs out of room.
+        while excess > 0 {
+            let donors = (0..sizes.len())
+                .filter(|index| priority(*index) == level && sizes[*index] > min_size(*index))
+                .collect::<Vec<_>>();
+            let Ok(count) = i32::try_from(donors.len()) else {
+                break;
+            };
+            if count == 0 {
+                break;
+            }
+            let share = (excess / count).max(1);
+            for index in donors.into_iter().rev() {
+                let taken = share.min(sizes[index] - min_size(index)).min(excess);
+                sizes[index] -= taken;
+                excess -= taken;
+            }
+        }
+    }
+}
+
 fn binpack_heights(heights: &[i32], min_height: i32, total_height: i32) -> Option<Vec<i32>> {
     let mut count = heights.len();
     let mut output = vec![];
@@ -956,6 +1877,8 @@ fn layout_strip_changed(
         (&Position, &mut Bounds, &mut LayoutPosition),
         (Without<LayoutStrip>, With<Window>),
     >,
+    minimum_sizes: Query<&MinimumSize>,
+    shrink_priorities: Query<&ShrinkPriority>,
     displays: Query<(&Display, Option<&DockPosition>)>,
     config: Res<Config>,
 ) {
@@ -965,6 +1888,13 @@ fn layout_strip_changed(
             .map(|(position, bounds, _)| IRect::from_corners(position.0, position.0 + bounds.0))
             .ok()
     };
+    let get_min_size = |entity| minimum_sizes.get(entity).ok().map(|min_size| min_size.0);
+    let get_shrink_priority = |entity| {
+        shrink_priorities
+            .get(entity)
+            .ok()
+            .map(|priority| priority.0)
+    };
 
     let changed = changed_strips
         .into_iter()
@@ -972,8 +1902,13 @@ fn layout_strip_changed(
             displays
                 .get(child_of.parent())
                 .map(|(display, dock)| {
-                    let height = display.actual_display_bounds(dock, &config).height();
-                    layout_strip.relative_positions(height, &get_window_frame)
+                    let viewport = display.actual_display_bounds(dock, &config).size();
+                    layout_strip.relative_positions_with_limits(
+                        viewport,
+                        &get_window_frame,
+                        get_min_size,
+                        get_shrink_priority,
+                    )
                 })
                 .ok()
         })
@@ -992,31 +1927,85 @@ fn layout_strip_changed(
     }
 }
 
-#[allow(clippy::needless_pass_by_value, clippy::type_complexity)]
+/// Picks the reshuffle to carry out in each strip. Only the most recently
+/// requested one counts, the earlier ones are superseded by it. Requests of the
+/// same frame go to the focused window, if it is among them.
+fn latest_reshuffles(
+    requested: impl IntoIterator<Item = (Entity, Entity, Tick)>,
+    focused: Option<Entity>,
+    this_run: Tick,
+) -> EntityHashMap<Entity> {
+    let mut latest = EntityHashMap::<(Entity, Tick)>::default();
+    for (entity, strip_entity, tick) in requested {
+        let supersedes = latest.get(&strip_entity).is_none_or(|(_, since)| {
+            let newer = tick.is_newer_than(*since, this_run);
+            let same_frame = !newer && !since.is_newer_than(tick, this_run);
+            newer || (same_frame && focused == Some(entity))
+        });
+        if supersedes {
+            latest.insert(strip_entity, (entity, tick));
+        }
+    }
+    latest
+        .into_iter()
+        .map(|(strip_entity, (entity, _))| (strip_entity, entity))
+        .collect()
+}
+
+#[allow(
+    clippy::needless_pass_by_value,
+    clippy::type_complexity,
+    clippy::too_many_arguments
+)]
 #[instrument(level = Level::DEBUG, skip_all)]
 fn reshuffle_layout_strip(
-    markers: Query<(Entity, &LayoutPosition), With<ReshuffleAroundMarker>>,
+    markers: Query<(Entity, &LayoutPosition, Ref<ReshuffleAroundMarker>)>,
     strips: Query<(
         &LayoutStrip,
         Entity,
         &Position,
+        Option<&RepositionMarker>,
         &ChildOf,
         Option<Ref<ActiveWorkspaceMarker>>,
+        Has<LayoutLocked>,
     )>,
     displays: Query<(&Display, Option<&DockPosition>)>,
     windows: Windows,
     config: Res<Config>,
+    ticks: SystemChangeTick,
     mut commands: Commands,
 ) {
-    markers.into_iter().for_each(|(entity, layout_position)| {
+    if markers.is_empty() {
+        return;
+    }
+    let started = Instant::now();
+    for (entity, _, _) in &markers {
         if let Ok(mut cmd) = commands.get_entity(entity) {
             cmd.try_remove::<ReshuffleAroundMarker>();
         }
-        let Some((strip, strip_entity, active_strip, child, active_marker)) =
-            strips.into_iter().find(|strip| strip.0.contains(entity))
+    }
+    let requested = markers.iter().filter_map(|(entity, _, marker)| {
+        strips
+            .iter()
+            .find(|strip| strip.0.contains(entity))
+            .map(|strip| (entity, strip.1, marker.last_changed()))
+    });
+    let focused = windows.focused().map(|(_, entity)| entity);
+    let latest = latest_reshuffles(requested, focused, ticks.this_run());
+
+    latest.into_iter().for_each(|(strip_entity, entity)| {
+        let Ok((_, layout_position, _)) = markers.get(entity) else {
+            return;
+        };
+        let Ok((strip, strip_entity, active_strip, in_flight, child, active_marker, locked)) =
+            strips.get(strip_entity)
         else {
             return;
         };
+        if locked {
+            trace!("reshuffle_layout_strip: layout of {strip_entity} is locked");
+            return;
+        }
 
         if active_marker.is_some_and(|m| m.is_added()) {
             trace!("reshuffle_layout_strip: skipping newly active workspace {strip_entity}");
@@ -1029,6 +2018,21 @@ fn reshuffle_layout_strip(
         let Some(mut frame) = windows.moving_frame(entity) else {
             return;
         };
+        // While the strip is still scrolling, its windows lag behind it. Take the
+        // window where the strip is heading, so that the new reshuffle retargets
+        // the running animation instead of fighting it.
+        let destination = in_flight.map_or(active_strip.0, |marker| marker.0);
+        if in_flight.is_some() {
+            let x = destination.x + layout_position.0.x;
+            frame = IRect::from_corners(frame.min.with_x(x), frame.max.with_x(x + frame.width()));
+        }
+        let retarget = |commands: &mut Commands, origin: Origin| {
+            if origin == destination {
+                trace!("reshuffle_layout_strip: {strip_entity} already heads to {origin}");
+            } else {
+                commands.reposition_entity(strip_entity, origin);
+            }
+        };
 
         let size = frame.size();
         let visible_width = display_bounds.intersect(frame).width();
@@ -1040,22 +2044,26 @@ fn reshuffle_layout_strip(
         frame.max = frame.min + size;
 
         let mut strip_position = (frame.min - layout_position.0).with_y(display_bounds.min.y);
+        let total_strip_width = strip_width(strip, &windows);
+
+        let column = IRect::from_corners(layout_position.0, layout_position.0 + size);
+        if let Some(x) = centered_strip_offset(
+            config.center_focused_column(),
+            column,
+            destination.x,
+            total_strip_width.unwrap_or(0),
+            display_bounds,
+        ) {
+            trace!("reshuffle_layout_strip: centering entity {entity}, offset {x}");
+            retarget(&mut commands, strip_position.with_x(x));
+            return;
+        }
 
         // Enforce the edge invariant when auto-center is off: the leftmost
         // window must touch the left edge and the rightmost the right edge
         // if more than 1 windows in workspace.
         if !config.auto_center()
-            && let Some(total_strip_width) = strip
-                .last()
-                .ok()
-                .and_then(|column| column.top())
-                .and_then(|last| {
-                    windows
-                        .layout_position(last)
-                        .map(|position| position.0.x)
-                        .zip(windows.moving_frame(last).map(|frame| frame.width()))
-                })
-                .map(|(last_x, last_width)| last_x + last_width)
+            && let Some(total_strip_width) = total_strip_width
         {
             strip_position.x = if display_bounds.width() < total_strip_width {
                 strip_position.x.clamp(
@@ -1079,15 +2087,31 @@ fn reshuffle_layout_strip(
 
             // Do not move the window if the hidden fraction is lower than threshold
             // or if the layout strip movement is shorter than the hidden width.
-            let strip_movement = (active_strip.x - strip_position.x).abs();
+            let strip_movement = (destination.x - strip_position.x).abs();
             if hidden_fraction <= hidden_ratio && frame.width() - visible_width >= strip_movement {
                 return;
             }
         }
 
         trace!("reshuffle_layout_strip: triggered for entity {entity}, offset {strip_position}");
-        commands.reposition_entity(strip_entity, strip_position);
+        retarget(&mut commands, strip_position);
     });
+    crate::metrics::record_reshuffle(started.elapsed());
+}
+
+/// Right edge of the last column of the strip, in strip coordinates.
+fn strip_width(strip: &LayoutStrip, windows: &Windows) -> Option<i32> {
+    strip
+        .last()
+        .ok()
+        .and_then(|column| column.top())
+        .and_then(|last| {
+            windows
+                .layout_position(last)
+                .map(|position| position.0.x)
+                .zip(windows.moving_frame(last).map(|frame| frame.width()))
+        })
+        .map(|(last_x, last_width)| last_x + last_width)
 }
 
 /// Scrolls the strip the minimum amount needed to keep `EnsureVisibleMarker`
@@ -1106,6 +2130,7 @@ fn ensure_visible_in_strip(
         &Position,
         &ChildOf,
         Option<Ref<ActiveWorkspaceMarker>>,
+        Has<LayoutLocked>,
     )>,
     displays: Query<(&Display, Option<&DockPosition>)>,
     windows: Windows,
@@ -1116,11 +2141,14 @@ fn ensure_visible_in_strip(
         if let Ok(mut cmd) = commands.get_entity(entity) {
             cmd.try_remove::<EnsureVisibleMarker>();
         }
-        let Some((_, strip_entity, strip_position, child, active_marker)) =
+        let Some((strip, strip_entity, strip_position, child, active_marker, locked)) =
             strips.into_iter().find(|s| s.0.contains(entity))
         else {
             return;
         };
+        if locked {
+            continue;
+        }
 
         if active_marker.is_some_and(|m| m.is_added()) {
             trace!("ensure_visible_in_strip: skipping newly active workspace {strip_entity}");
@@ -1134,6 +2162,18 @@ fn ensure_visible_in_strip(
         };
         let viewport = display.actual_display_bounds(dock, &config);
 
+        let column = IRect::from_corners(layout_position.0, layout_position.0 + size);
+        if let Some(x) = centered_strip_offset(
+            config.center_focused_column(),
+            column,
+            strip_position.x,
+            strip_width(strip, &windows).unwrap_or(0),
+            viewport,
+        ) {
+            commands.reposition_entity(strip_entity, strip_position.0.with_x(x));
+            return;
+        }
+
         // Where the entity would appear if the strip stays put.
         let candidate_min = layout_position.0 + strip_position.0;
         // Clamp into the viewport. If already on-screen, this is a no-op and
@@ -1173,6 +2213,71 @@ struct StripWindowContext {
     swiping: bool,
     display_entity: Entity,
     stacked: bool,
+    /// The pinned column of the strip and where the window's column is relative to it.
+    pin: Option<(PinnedColumn, Ordering)>,
+    /// The strip layout is locked, so the window follows the strip without animating.
+    locked: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct PinnedColumn {
+    index: usize,
+    /// Distance of the column from the left edge of the viewport.
+    offset: i32,
+    width: i32,
+}
+
+/// Finds the pinned column of a strip, from the first window carrying `Pinned`.
+fn pinned_column(strip: &LayoutStrip, pinned: &Query<(&Pinned, &Window)>) -> Option<PinnedColumn> {
+    strip.columns().enumerate().find_map(|(index, column)| {
+        column.window_iter().find_map(|entity| {
+            pinned
+                .get(entity)
+                .ok()
+                .map(|(Pinned(offset), window)| PinnedColumn {
+                    index,
+                    offset: *offset,
+                    width: window.frame().width(),
+                })
+        })
+    })
+}
+
+/// Puts the windows of a strip back into their layout slots once it gets unlocked.
+#[allow(clippy::needless_pass_by_value)]
+fn layout_unlocked(
+    mut unlocked: RemovedComponents<LayoutLocked>,
+    strips: Query<&LayoutStrip>,
+    mut windows: Query<&mut LayoutPosition, (With<Window>, Without<LayoutStrip>)>,
+) {
+    for strip in unlocked.read().filter_map(|entity| strips.get(entity).ok()) {
+        for entity in strip.all_windows() {
+            if let Ok(mut position) = windows.get_mut(entity) {
+                position.set_changed();
+            }
+        }
+    }
+}
+
+/// Marks the windows of a strip for re-positioning when one of its columns
+/// gets pinned or unpinned.
+#[allow(clippy::needless_pass_by_value)]
+fn pinned_columns_changed(
+    pinned: Query<Entity, Changed<Pinned>>,
+    mut unpinned: RemovedComponents<Pinned>,
+    strips: Query<&LayoutStrip>,
+    mut windows: Query<&mut LayoutPosition, (With<Window>, Without<LayoutStrip>)>,
+) {
+    for entity in pinned.iter().chain(unpinned.read()) {
+        let Some(strip) = strips.iter().find(|strip| strip.contains(entity)) else {
+            continue;
+        };
+        for entity in strip.all_windows() {
+            if let Ok(mut position) = windows.get_mut(entity) {
+                position.set_changed();
+            }
+        }
+    }
 }
 
 fn insert_strip_window_contexts(
@@ -1211,6 +2316,8 @@ fn insert_column_window_contexts(
                     swiping,
                     display_entity,
                     stacked,
+                    pin: None,
+                    locked: false,
                 },
             );
         }
@@ -1235,6 +2342,8 @@ fn insert_column_window_contexts(
                         swiping,
                         display_entity,
                         stacked,
+                        pin: None,
+                        locked: false,
                     },
                 );
             }
@@ -1259,6 +2368,8 @@ fn insert_stack_item_window_contexts(
                     swiping,
                     display_entity,
                     stacked,
+                    pin: None,
+                    locked: false,
                 },
             );
         }
@@ -1271,6 +2382,8 @@ fn insert_stack_item_window_contexts(
                         swiping,
                         display_entity,
                         stacked,
+                        pin: None,
+                        locked: false,
                     },
                 );
             }
@@ -1287,30 +2400,63 @@ fn position_layout_windows(
         (Entity, &Window, &LayoutPosition, &mut Position, &mut Bounds),
         (Changed<LayoutPosition>, With<Window>, Without<LayoutStrip>),
     >,
-    workspaces: Query<(&LayoutStrip, &Position, Has<Scrolling>, &ChildOf), With<LayoutStrip>>,
+    workspaces: Query<
+        (
+            &LayoutStrip,
+            Ref<Position>,
+            Has<Scrolling>,
+            Has<LayoutLocked>,
+            &ChildOf,
+        ),
+        With<LayoutStrip>,
+    >,
     displays: Query<(&Display, Option<&DockPosition>)>,
+    pinned: Query<(&Pinned, &Window)>,
     config: Res<Config>,
     mut commands: Commands,
 ) {
     let offscreen_sliver_width = config.sliver_width();
     let (_, pad_right, _, pad_left) = config.edge_padding();
     let mut strip_contexts = EntityHashMap::default();
-    for (layout_strip, Position(strip_position), swiping, child_of) in &workspaces {
+    for (layout_strip, strip_position, swiping, locked, child_of) in &workspaces {
+        // A locked strip leaves its windows where the user put them, and only
+        // repositions them when the strip itself moves, e.g. on a workspace switch.
+        if locked && !strip_position.is_changed() {
+            continue;
+        }
         insert_strip_window_contexts(
             &mut strip_contexts,
             layout_strip,
-            *strip_position,
+            strip_position.0,
             swiping,
             child_of.parent(),
         );
+        if locked {
+            for entity in layout_strip.all_windows() {
+                if let Some(context) = strip_contexts.get_mut(&entity) {
+                    context.locked = true;
+                }
+            }
+        }
+
+        let Some(pin) = pinned_column(layout_strip, &pinned) else {
+            continue;
+        };
+        for (index, column) in layout_strip.columns().enumerate() {
+            for entity in column.window_iter() {
+                if let Some(context) = strip_contexts.get_mut(&entity) {
+                    context.pin = Some((pin, index.cmp(&pin.index)));
+                }
+            }
+        }
     }
 
     for (entity, window, layout_position, mut position, mut bounds) in positioned_windows {
         let Some(context) = strip_contexts.get(&entity) else {
-            return;
+            continue;
         };
         let Ok((display, dock)) = displays.get(context.display_entity) else {
-            return;
+            continue;
         };
         let viewport = display.actual_display_bounds(dock, &config);
         // Gets 80% of the display height as threshold.
@@ -1327,6 +2473,21 @@ fn position_layout_windows(
         frame.min += context.strip_position;
         frame.max += context.strip_position;
 
+        if let Some((pin, ordering)) = context.pin {
+            let pinned_x = viewport.min.x + pin.offset;
+            frame.min.x = match ordering {
+                Ordering::Equal => pinned_x,
+                Ordering::Less | Ordering::Greater => flow_around_pinned(
+                    frame.min.x,
+                    width,
+                    ordering == Ordering::Greater,
+                    pinned_x,
+                    pin.width,
+                ),
+            };
+            frame.max.x = frame.min.x + width;
+        }
+
         let mut offscreen = false;
         if frame.max.x <= viewport.min.x + h_pad {
             // Window hidden to the left — position so exactly
@@ -1377,7 +2538,10 @@ fn position_layout_windows(
             // window's target converges back to its old visual position as the strip settles, while
             // the other window slides past.
             let offscreen_move = position.0.y.abs_diff(frame.min.y) > vertical_move_threshold;
-            if context.swiping || offscreen_move && !config.virtual_workspace_animations() {
+            if context.swiping
+                || context.locked
+                || offscreen_move && !config.virtual_workspace_animations()
+            {
                 position.0 = frame.min;
                 if let Ok(mut entity_commands) = commands.get_entity(entity) {
                     entity_commands.try_remove::<RepositionMarker>();
@@ -1394,6 +2558,218 @@ mod tests {
     use super::*;
     use bevy::prelude::*;
 
+    #[test]
+    fn test_center_over() {
+        let viewport = IRect::new(0, 25, 1000, 800);
+        let size = IVec2::new(200, 100);
+
+        let parent = IRect::new(100, 100, 500, 500);
+        assert_eq!(center_over(parent, size, viewport), IVec2::new(200, 250));
+
+        // Parents hidden off-screen pull the dialog only up to the edge.
+        let parent = IRect::new(-900, 100, -100, 500);
+        assert_eq!(center_over(parent, size, viewport), IVec2::new(0, 250));
+        let parent = IRect::new(900, 0, 1700, 100);
+        assert_eq!(center_over(parent, size, viewport), IVec2::new(800, 25));
+
+        // Dialogs larger than the viewport stick to its top left corner.
+        let size = IVec2::new(1200, 100);
+        let parent = IRect::new(100, 100, 500, 500);
+        assert_eq!(center_over(parent, size, viewport), IVec2::new(0, 250));
+    }
+
+    #[test]
+    fn test_centered_strip_offset() {
+        let viewport = IRect::new(0, 25, 1000, 800);
+        let column = IRect::new(800, 0, 1200, 775);
+
+        assert_eq!(
+            centered_strip_offset(CenterFocusedColumn::Never, column, 0, 2000, viewport),
+            None
+        );
+        assert_eq!(
+            centered_strip_offset(CenterFocusedColumn::Always, column, 0, 2000, viewport),
+            Some(-500)
+        );
+        // Always centers even when the whole strip would fit.
+        assert_eq!(
+            centered_strip_offset(CenterFocusedColumn::Always, column, 0, 900, viewport),
+            Some(-500)
+        );
+
+        // Partly off screen in an overflowing strip.
+        assert_eq!(
+            centered_strip_offset(CenterFocusedColumn::OnOverflow, column, 0, 2000, viewport),
+            Some(-500)
+        );
+        // Already entirely on screen.
+        assert_eq!(
+            centered_strip_offset(
+                CenterFocusedColumn::OnOverflow,
+                column,
+                -400,
+                2000,
+                viewport
+            ),
+            None
+        );
+        // The strip fits the viewport, so there is nothing to center.
+        let column = IRect::new(400, 0, 800, 775);
+        assert_eq!(
+            centered_strip_offset(CenterFocusedColumn::OnOverflow, column, 200, 900, viewport),
+            None
+        );
+    }
+
+    #[test]
+    fn test_nearest_column_snap() {
+        let viewport = IRect::new(0, 25, 1000, 800);
+        let columns = [(0, 400), (400, 1000), (1000, 1600)];
+
+        // Left edges of the columns.
+        assert_eq!(
+            nearest_column_snap(-20, &columns, viewport, i32::MAX),
+            Some(0)
+        );
+        assert_eq!(
+            nearest_column_snap(-380, &columns, viewport, i32::MAX),
+            Some(-400)
+        );
+        // The right edge of the last column touches the viewport.
+        assert_eq!(
+            nearest_column_snap(-590, &columns, viewport, i32::MAX),
+            Some(-600)
+        );
+        // Never past the ends of the strip.
+        assert_eq!(
+            nearest_column_snap(-900, &columns, viewport, i32::MAX),
+            Some(-600)
+        );
+        assert_eq!(
+            nearest_column_snap(300, &columns, viewport, i32::MAX),
+            Some(0)
+        );
+
+        // A strip narrower than the viewport stays pinned to the left edge.
+        assert_eq!(
+            nearest_column_snap(-100, &columns[..1], viewport, i32::MAX),
+            Some(0)
+        );
+        assert_eq!(nearest_column_snap(0, &[], viewport, i32::MAX), None);
+
+        // Snaps further than the threshold are left alone.
+        assert_eq!(nearest_column_snap(-150, &columns, viewport, 100), None);
+        assert_eq!(
+            nearest_column_snap(-350, &columns, viewport, 100),
+            Some(-400)
+        );
+        assert_eq!(nearest_column_snap(-20, &columns, viewport, 0), None);
+    }
+
+    #[test]
+    fn test_apply_min_heights() {
+        // The short window takes the missing space from the one below it.
+        let mut heights = vec![100, 500, 400];
+        apply_min_heights(&mut heights, &[300, 0, 0]);
+        assert_eq!(heights, vec![300, 500, 200]);
+
+        // Donors never go below their own minimum.
+        let mut heights = vec![100, 500, 400];
+        apply_min_heights(&mut heights, &[300, 0, 350]);
+        assert_eq!(heights, vec![300, 350, 350]);
+
+        // Impossible minimums overflow the column instead of being violated.
+        let mut heights = vec![500, 500];
+        apply_min_heights(&mut heights, &[600, 600]);
+        assert_eq!(heights, vec![600, 600]);
+    }
+
+    #[test]
+    fn test_squeeze_sizes() {
+        let min_sizes = [100; 3];
+
+        // The windows with the lowest priority give up their size first.
+        let mut sizes = vec![400, 400, 400];
+        squeeze_sizes(&mut sizes, &[None, Some(5), None], &min_sizes, 1000);
+        assert_eq!(sizes, vec![300, 400, 300]);
+
+        // Once they reach their minimum, the rest is taken from the next level.
+        let mut sizes = vec![400, 400, 400];
+        squeeze_sizes(&mut sizes, &[None, Some(5), None], &min_sizes, 500);
+        assert_eq!(sizes, vec![100, 300, 100]);
+
+        // Priority 0 never shrinks, even when the rest cannot make room.
+        let mut sizes = vec![500, 500];
+        squeeze_sizes(&mut sizes, &[Some(0), Some(3)], &min_sizes, 500);
+        assert_eq!(sizes, vec![500, 100]);
+
+        // Sizes which fit are left alone.
+        let mut sizes = vec![200, 200];
+        squeeze_sizes(&mut sizes, &[Some(2), Some(2)], &min_sizes, 600);
+        assert_eq!(sizes, vec![200, 200]);
+    }
+
+    #[test]
+    fn test_layout_squeezes_overflowing_columns() {
+        let mut world = World::new();
+        let entities = world.spawn_batch(vec![(), (), ()]).collect::<Vec<Entity>>();
+
+        let mut strip = LayoutStrip::default();
+        for &e in &entities {
+            strip.append(e);
+        }
+        let get_window_frame = |_| Some(IRect::new(0, 0, 500, 600));
+        let get_shrink_priority = |e: Entity| (e == entities[0]).then_some(2);
+
+        let out: Vec<_> = strip
+            .relative_positions_with_limits(
+                Size::new(1200, 600),
+                &get_window_frame,
+                |_| None,
+                get_shrink_priority,
+            )
+            .collect();
+        let frame = |e: Entity| out.iter().find(|(entity, _)| *entity == e).unwrap().1;
+
+        // The column with the higher priority keeps its width.
+        assert_eq!(frame(entities[0]).width(), 500);
+        assert_eq!(frame(entities[1]).width(), 350);
+        assert_eq!(frame(entities[2]).width(), 350);
+        assert_eq!(frame(entities[1]).min.x, 500);
+        assert_eq!(frame(entities[2]).min.x, 850);
+
+        // Without priorities, the strip overflows the viewport as usual.
+        let out: Vec<_> = strip
+            .relative_positions_with_limits(
+                Size::new(1200, 600),
+                &get_window_frame,
+                |_| None,
+                |_| None,
+            )
+            .collect();
+        assert!(out.iter().all(|(_, frame)| frame.width() == 500));
+    }
+
+    #[test]
+    fn test_next_size_ratio() {
+        let sorted = [0.25, 0.5, 0.75];
+        assert_eq!(next_size_ratio(&sorted, 0.5, true, true), 0.75);
+        assert_eq!(next_size_ratio(&sorted, 0.75, true, true), 0.25);
+        assert_eq!(next_size_ratio(&sorted, 0.75, true, false), 0.75);
+        assert_eq!(next_size_ratio(&sorted, 0.25, false, true), 0.75);
+        assert_eq!(next_size_ratio(&sorted, 0.25, false, false), 0.25);
+        // Not matching a preset picks the closest one in the direction.
+        assert_eq!(next_size_ratio(&sorted, 0.4, true, true), 0.5);
+        assert_eq!(next_size_ratio(&sorted, 0.4, false, true), 0.25);
+
+        // Unsorted presets are cycled in their configured order.
+        let ordered = [0.5, 0.67, 0.33];
+        assert_eq!(next_size_ratio(&ordered, 0.5, true, true), 0.67);
+        assert_eq!(next_size_ratio(&ordered, 0.67, true, true), 0.33);
+        assert_eq!(next_size_ratio(&ordered, 0.33, true, true), 0.5);
+        assert_eq!(next_size_ratio(&ordered, 0.5, false, true), 0.33);
+    }
+
     fn setup_world_and_strip() -> (World, LayoutStrip, Vec<Entity>) {
         let mut world = World::new();
         let entities = world.spawn_batch(vec![(), (), ()]).collect::<Vec<Entity>>();
@@ -1445,6 +2821,23 @@ mod tests {
         assert!(!single_window.stacked);
     }
 
+    #[test]
+    fn test_flow_around_pinned() {
+        // Unscrolled strip with the middle column pinned where it already is.
+        assert_eq!(flow_around_pinned(0, 400, false, 400, 400), 0);
+        assert_eq!(flow_around_pinned(800, 400, true, 400, 400), 800);
+
+        // Scrolled one column to the right: the next column slides under the
+        // pinned one and comes out to its left.
+        assert_eq!(flow_around_pinned(-400, 400, false, 400, 400), -400);
+        assert_eq!(flow_around_pinned(400, 400, true, 400, 400), 0);
+        assert_eq!(flow_around_pinned(800, 400, true, 400, 400), 800);
+
+        // Columns left of the pinned one in the strip hop over it when they
+        // scroll past its center.
+        assert_eq!(flow_around_pinned(250, 400, false, 400, 400), 650);
+    }
+
     #[test]
     fn test_window_pane_index_of() {
         let (_world, strip, entities) = setup_world_and_strip();
@@ -1453,6 +2846,29 @@ mod tests {
         assert_eq!(strip.index_of(entities[2]).unwrap(), 2);
     }
 
+    #[test]
+    fn test_stacked_neighbours() {
+        let (mut world, mut strip, entities) = setup_world_and_strip();
+        let extra = world.spawn_empty().id();
+        strip.append(extra);
+        strip.stack(entities[1]).unwrap();
+        strip.stack(entities[2]).unwrap();
+        strip.convert_to_tabs(entities[2], extra).unwrap();
+
+        assert_eq!(strip.above(entities[0]), None);
+        assert_eq!(strip.below(entities[0]), Some(entities[1]));
+        assert_eq!(strip.above(entities[1]), Some(entities[0]));
+        // The tab group is entered through its leading tab.
+        assert_eq!(strip.below(entities[1]), Some(extra));
+        // Moving up from any tab in the group lands on the window above it.
+        assert_eq!(strip.above(entities[2]), Some(entities[1]));
+        assert_eq!(strip.below(extra), None);
+
+        let (_world, strip, entities) = setup_world_and_strip();
+        assert_eq!(strip.above(entities[1]), None);
+        assert_eq!(strip.below(entities[1]), None);
+    }
+
     #[test]
     fn test_window_pane_swap() {
         let (_world, mut strip, entities) = setup_world_and_strip();
@@ -1461,6 +2877,52 @@ mod tests {
         assert_eq!(strip.index_of(entities[0]).unwrap(), 2);
     }
 
+    #[test]
+    fn test_column_groups_move_as_a_unit() {
+        let mut world = World::new();
+        let e = world.spawn_batch(vec![(); 4]).collect::<Vec<Entity>>();
+        let mut strip = LayoutStrip::default();
+        for entity in &e {
+            strip.append(*entity);
+        }
+        let order = |strip: &LayoutStrip| strip.all_columns();
+
+        // Ungrouped columns are left to plain swaps.
+        assert!(!strip.move_group(e[0], true).unwrap());
+
+        strip.group_with_next(e[1]).unwrap();
+        assert!(strip.is_grouped(e[2]));
+        assert_eq!(strip.group_range(2), 1..=2);
+
+        // A single column passes the whole group.
+        assert!(strip.move_group(e[0], true).unwrap());
+        assert_eq!(order(&strip), vec![e[1], e[2], e[0], e[3]]);
+
+        // The group passes a single column.
+        assert!(strip.move_group(e[2], true).unwrap());
+        assert_eq!(order(&strip), vec![e[0], e[1], e[2], e[3]]);
+        assert!(strip.move_group(e[1], false).unwrap());
+        assert_eq!(order(&strip), vec![e[1], e[2], e[0], e[3]]);
+        // At the edge of the strip nothing moves.
+        assert!(strip.move_group(e[1], false).unwrap());
+        assert_eq!(order(&strip), vec![e[1], e[2], e[0], e[3]]);
+
+        let (columns, start) = strip.take_group(e[2]).unwrap();
+        assert_eq!((columns.len(), start), (2, 0));
+        let mut other = LayoutStrip::default();
+        other.append_group(columns);
+        assert!(other.is_grouped(e[1]));
+
+        // Closing a window dissolves a group left with a single column.
+        other.remove(e[2]);
+        assert!(!other.is_grouped(e[1]));
+
+        strip.group_with_next(e[0]).unwrap();
+        strip.ungroup(e[3]);
+        assert!(!strip.is_grouped(e[0]));
+        assert!(strip.group_with_next(e[3]).is_err());
+    }
+
     #[test]
     fn test_window_pane_stack_and_unstack() {
         let (_world, mut strip, entities) = setup_world_and_strip();
@@ -1637,6 +3099,46 @@ mod tests {
         assert_eq!(e3_frame.height(), 600);
     }
 
+    #[test]
+    fn test_layout_respects_minimum_size() {
+        let mut world = World::new();
+        let entities = world.spawn_batch(vec![(), (), ()]).collect::<Vec<Entity>>();
+
+        let mut strip = LayoutStrip::default();
+        for &e in &entities {
+            strip.append(e);
+        }
+        // [Stack(e0, e1), Single(e2)]
+        strip.stack(entities[1]).unwrap();
+
+        let get_window_frame = |e: Entity| {
+            if e == entities[0] {
+                Some(IRect::new(0, 0, 300, 400))
+            } else {
+                Some(IRect::new(0, 0, 300, 200))
+            }
+        };
+        let get_min_size = |e: Entity| (e == entities[1]).then_some(Size::new(500, 350));
+
+        let out: Vec<_> = strip
+            .relative_positions_with_limits(
+                Size::new(2000, 600),
+                &get_window_frame,
+                get_min_size,
+                |_| None,
+            )
+            .collect();
+        let frame = |e: Entity| out.iter().find(|(entity, _)| *entity == e).unwrap().1;
+
+        // The stacked window keeps its minimum height, taken from the master.
+        assert_eq!(frame(entities[1]).height(), 350);
+        assert_eq!(frame(entities[0]).height(), 250);
+        // The whole column widens to the minimum width, pushing the next one.
+        assert_eq!(frame(entities[0]).width(), 500);
+        assert_eq!(frame(entities[1]).width(), 500);
+        assert_eq!(frame(entities[2]).min.x, 500);
+    }
+
     #[test]
     fn test_tabs_in_stack() {
         let mut world = World::new();
@@ -1758,6 +3260,45 @@ mod tests {
         assert_eq!(heights.len(), 2);
     }
 
+    #[test]
+    fn test_horizontal_column_places_items_side_by_side() {
+        let mut world = World::new();
+        let entities = world.spawn_batch(vec![(), (), ()]).collect::<Vec<Entity>>();
+        let mut strip = LayoutStrip::default();
+        for &e in &entities {
+            strip.append(e);
+        }
+        let get_window_frame = |_| Some(IRect::new(0, 0, 300, 250));
+
+        strip.stack(entities[1]).unwrap();
+        assert_eq!(
+            strip.toggle_orientation(entities[0]).unwrap(),
+            ColumnOrientation::Horizontal
+        );
+        let out: Vec<_> = strip.relative_positions(500, &get_window_frame).collect();
+        let frames: Vec<_> = out
+            .iter()
+            .map(|(_, f)| (f.min.x, f.width(), f.height()))
+            .collect();
+        assert_eq!(
+            frames,
+            vec![(0, 300, 500), (300, 300, 500), (600, 300, 500)]
+        );
+
+        // A window leaving the column does not take the orientation along.
+        strip.unstack(entities[1]).unwrap();
+        strip.stack(entities[2]).unwrap();
+        assert_eq!(
+            strip.orientation(&strip.get(1).unwrap()),
+            ColumnOrientation::Vertical
+        );
+        // Single columns have nothing to lay out side by side.
+        assert_eq!(
+            strip.toggle_orientation(entities[0]).unwrap(),
+            ColumnOrientation::Vertical
+        );
+    }
+
     /// When window frames include padding (logical frame is wider than the visual
     /// window), columns must be placed edge-to-edge using the full logical width.
     /// This ensures the visual gap between windows equals the sum of their padding.
@@ -2078,4 +3619,204 @@ mod tests {
         assert_eq!(strip.right_neighbour(leader), Some(b));
         assert_eq!(strip.right_neighbour(follower), Some(b));
     }
+
+    #[test]
+    fn test_move_past_keeps_neighbouring_stack() {
+        let mut world = World::new();
+        let a = world.spawn_empty().id();
+        let b = world.spawn_empty().id();
+        let c = world.spawn_empty().id();
+
+        let mut strip = LayoutStrip::default();
+        strip.append(a);
+        strip.append(b);
+        strip.append(c);
+        strip.stack(c).unwrap();
+
+        // a jumps over the whole [b, c] stack instead of joining it.
+        strip.move_past(a, true).unwrap();
+        assert_eq!(strip.len(), 2);
+        assert_eq!(strip.index_of(a).unwrap(), 1);
+        match strip.get(0).unwrap() {
+            Column::Stack(items) => {
+                assert_eq!(items, vec![StackItem::Single(b), StackItem::Single(c)]);
+            }
+            other => panic!("expected Stack column, got {other:?}"),
+        }
+
+        // Already at the edge.
+        strip.move_past(a, true).unwrap();
+        assert_eq!(strip.index_of(a).unwrap(), 1);
+
+        strip.move_past(a, false).unwrap();
+        assert_eq!(strip.index_of(a).unwrap(), 0);
+        assert_eq!(strip.index_of(b).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_move_past_leaves_own_stack() {
+        let mut world = World::new();
+        let a = world.spawn_empty().id();
+        let b = world.spawn_empty().id();
+        let c = world.spawn_empty().id();
+
+        let mut strip = LayoutStrip::default();
+        strip.append(a);
+        strip.append(b);
+        strip.append(c);
+        strip.stack(b).unwrap();
+
+        // b leaves the [a, b] stack and lands right next to it.
+        strip.move_past(b, true).unwrap();
+        assert_eq!(strip.len(), 3);
+        assert!(matches!(strip.get(0).unwrap(), Column::Single(id) if id == a));
+        assert!(matches!(strip.get(1).unwrap(), Column::Single(id) if id == b));
+        assert!(matches!(strip.get(2).unwrap(), Column::Single(id) if id == c));
+
+        strip.stack(b).unwrap();
+        strip.move_past(a, false).unwrap();
+        assert!(matches!(strip.get(0).unwrap(), Column::Single(id) if id == a));
+        assert!(matches!(strip.get(1).unwrap(), Column::Single(id) if id == b));
+    }
+
+    #[test]
+    fn test_move_into_stack() {
+        let mut world = World::new();
+        let a = world.spawn_empty().id();
+        let b = world.spawn_empty().id();
+        let c = world.spawn_empty().id();
+
+        let mut strip = LayoutStrip::default();
+        strip.append(a);
+        strip.append(b);
+        strip.append(c);
+
+        // No neighbour to the west of the first column.
+        strip.move_into_stack(a, false).unwrap();
+        assert_eq!(strip.len(), 3);
+
+        strip.move_into_stack(a, true).unwrap();
+        assert_eq!(strip.len(), 2);
+        match strip.get(0).unwrap() {
+            Column::Stack(items) => {
+                assert_eq!(items, vec![StackItem::Single(b), StackItem::Single(a)]);
+            }
+            other => panic!("expected Stack column, got {other:?}"),
+        }
+
+        // Moving a stacked window into the next stack collapses the one it left.
+        strip.move_into_stack(b, true).unwrap();
+        assert_eq!(strip.len(), 2);
+        assert!(matches!(strip.get(0).unwrap(), Column::Single(id) if id == a));
+        match strip.get(1).unwrap() {
+            Column::Stack(items) => {
+                assert_eq!(items, vec![StackItem::Single(c), StackItem::Single(b)]);
+            }
+            other => panic!("expected Stack column, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_stack_all_restores_arrangement() {
+        let mut world = World::new();
+        let e = world.spawn_batch(vec![(); 5]).collect::<Vec<Entity>>();
+
+        let mut strip = LayoutStrip::default();
+        for entity in &e[..4] {
+            strip.append(*entity);
+        }
+        strip.stack(e[2]).unwrap();
+        // [a, Stack(b, c), d]
+        let arrangement = strip.stack_all().unwrap();
+        assert_eq!(strip.len(), 1);
+        assert_eq!(strip.all_windows(), e[..4].to_vec());
+        assert!(strip.stack_all().is_none());
+
+        // d closes and e joins the stack meanwhile.
+        strip.remove(e[3]);
+        strip.append(e[4]);
+        strip.stack(e[4]).unwrap();
+        strip.restore_arrangement(arrangement);
+        assert_eq!(strip.len(), 3);
+        assert!(matches!(strip.get(0).unwrap(), Column::Single(id) if id == e[0]));
+        match strip.get(1).unwrap() {
+            Column::Stack(items) => {
+                assert_eq!(
+                    items,
+                    vec![StackItem::Single(e[1]), StackItem::Single(e[2])]
+                );
+            }
+            other => panic!("expected Stack column, got {other:?}"),
+        }
+        assert!(matches!(strip.get(2).unwrap(), Column::Single(id) if id == e[4]));
+    }
+
+    #[test]
+    fn test_unstack_all() {
+        let mut world = World::new();
+        let e = world.spawn_batch(vec![(); 3]).collect::<Vec<Entity>>();
+
+        let mut strip = LayoutStrip::default();
+        for entity in &e {
+            strip.append(*entity);
+        }
+        strip.stack_all();
+        strip.unstack_all();
+        assert_eq!(strip.len(), 3);
+        assert_eq!(strip.all_columns(), e);
+    }
+
+    #[test]
+    fn test_move_column() {
+        let mut world = World::new();
+        let e = world.spawn_batch(vec![(); 4]).collect::<Vec<Entity>>();
+
+        let mut strip = LayoutStrip::default();
+        for entity in &e {
+            strip.append(*entity);
+        }
+        strip.move_column(3, 0);
+        assert_eq!(strip.all_columns(), vec![e[3], e[0], e[1], e[2]]);
+        strip.move_column(0, 2);
+        assert_eq!(strip.all_columns(), vec![e[0], e[1], e[3], e[2]]);
+        // Indices past the end move the column to the end.
+        strip.move_column(0, 9);
+        assert_eq!(strip.all_columns(), vec![e[1], e[3], e[2], e[0]]);
+    }
+
+    #[test]
+    fn test_latest_reshuffles() {
+        let mut world = World::new();
+        let e = world.spawn_batch(vec![(); 5]).collect::<Vec<Entity>>();
+        let (strip, other_strip) = (e[3], e[4]);
+        let this_run = Tick::new(10);
+
+        // The latest request of a strip supersedes the earlier ones.
+        let latest = latest_reshuffles(
+            [
+                (e[0], strip, Tick::new(9)),
+                (e[1], strip, Tick::new(8)),
+                (e[2], other_strip, Tick::new(5)),
+            ],
+            None,
+            this_run,
+        );
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest.get(&strip), Some(&e[0]));
+        assert_eq!(latest.get(&other_strip), Some(&e[2]));
+
+        // Requests of the same frame go to the focused window.
+        let latest = latest_reshuffles(
+            [(e[0], strip, Tick::new(9)), (e[1], strip, Tick::new(9))],
+            Some(e[1]),
+            this_run,
+        );
+        assert_eq!(latest.get(&strip), Some(&e[1]));
+        let latest = latest_reshuffles(
+            [(e[1], strip, Tick::new(9)), (e[0], strip, Tick::new(9))],
+            Some(e[1]),
+            this_run,
+        );
+        assert_eq!(latest.get(&strip), Some(&e[1]));
+    }
 }
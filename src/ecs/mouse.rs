@@ -5,15 +5,15 @@ use bevy::ecs::query::With;
 use bevy::ecs::schedule::IntoScheduleConfigs as _;
 use bevy::ecs::system::{Commands, Local, Query, Res, Single};
 use std::time::{Duration, Instant};
-use tracing::{debug, trace, warn};
+use tracing::{debug, trace};
 
 use super::{MouseHeldMarker, Timeout};
 use crate::config::Config;
 use crate::ecs::layout::LayoutStrip;
 use crate::ecs::params::{GlobalState, Windows};
 use crate::ecs::{
-    ActiveWorkspaceMarker, DockPosition, MissionControlActive, Position, Scrolling,
-    SpawnCommandsExt,
+    ActiveWorkspaceMarker, DockPosition, FocusStealGuard, MissionControlActive, Position,
+    Scrolling, SpawnCommandsExt,
 };
 use crate::events::Event;
 use crate::manager::{Display, Origin, WindowManager, origin_from};
@@ -141,24 +141,7 @@ fn mouse_moved_trigger(
             continue;
         };
 
-        let child_window = window_manager
-            .get_associated_windows(window_id)
-            .into_iter()
-            .find_map(|child_wid| {
-                windows.find(child_wid).and_then(|(window, _)| {
-                    window
-                        .child_role()
-                        .inspect_err(|err| {
-                            warn!("getting role {window_id}: {err}");
-                        })
-                        .is_ok_and(|child| child)
-                        .then_some(window)
-                })
-            });
-        if let Some(child) = child_window {
-            debug!("found child of {}: {}", child.id(), window.id());
-        }
-
+        // An open sheet of the window is keyed by focus_window_trigger.
         // Do not reshuffle windows due to moved mouse focus.
         global_state.set_skip_reshuffle(true);
         global_state.set_ffm_flag(Some(window.id()));
@@ -202,6 +185,11 @@ fn mouse_down_trigger(
             continue;
         };
 
+        // The click hands the focus to the window, which a guard must not undo.
+        if let Ok(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.try_remove::<FocusStealGuard>();
+        }
+
         // Stop any ongoing scroll.
         for (entity, scroll) in active_workspace {
             if scroll.is_some()
@@ -2,7 +2,7 @@ use bevy::{
     ecs::{
         entity::Entity,
         hierarchy::ChildOf,
-        query::{With, Without},
+        query::{Has, With, Without},
         system::{Query, Res, ResMut, Single, SystemParam},
         world::Mut,
     },
@@ -16,8 +16,9 @@ use crate::{
     config::Config,
     ecs::{
         ActiveWorkspaceMarker, Bounds, DockPosition, FocusedMarker, FullWidthMarker, Initializing,
-        LayoutPosition, NativeFullscreenMarker, Position, RepositionMarker, ResizeMarker,
-        Unmanaged, WidthRatio, layout::LayoutStrip,
+        LayoutPosition, NativeFullscreenMarker, NativeTabs, Position, RepositionMarker,
+        ResizeMarker, Unmanaged, UnmanagedDisplay, WidthRatio, focus::FocusStats,
+        layout::LayoutStrip,
     },
     manager::{Application, Display, Origin, Size, Window},
     platform::{ProcessSerialNumber, WinID},
@@ -100,7 +101,8 @@ pub struct ActiveDisplay<'w, 's> {
         With<ActiveDisplayMarker>,
     >,
     /// A query for all other `Display` components that are not marked as active.
-    other_displays: Query<'w, 's, &'static Display, Without<ActiveDisplayMarker>>,
+    other_displays:
+        Query<'w, 's, (&'static Display, Has<UnmanagedDisplay>), Without<ActiveDisplayMarker>>,
 }
 
 impl ActiveDisplay<'_, '_> {
@@ -120,7 +122,14 @@ impl ActiveDisplay<'_, '_> {
 
     /// Returns an iterator over immutable references to all other displays (non-active).
     pub fn other(&self) -> impl Iterator<Item = &Display> {
-        self.other_displays.iter()
+        self.other_displays.iter().map(|(display, _)| display)
+    }
+
+    /// Returns the other displays whose windows are tiled, see `managed_displays`.
+    pub fn other_managed(&self) -> impl Iterator<Item = &Display> {
+        self.other_displays
+            .iter()
+            .filter_map(|(display, unmanaged)| (!unmanaged).then_some(display))
     }
 
     pub fn active_strip(&self) -> &LayoutStrip {
@@ -164,7 +173,8 @@ pub struct ActiveDisplayMut<'w, 's> {
         With<ActiveDisplayMarker>,
     >,
     /// A query for all other `Display` components that are not marked as active.
-    other_displays: Query<'w, 's, &'static mut Display, Without<ActiveDisplayMarker>>,
+    other_displays:
+        Query<'w, 's, (&'static mut Display, Has<UnmanagedDisplay>), Without<ActiveDisplayMarker>>,
 }
 
 impl ActiveDisplayMut<'_, '_> {
@@ -178,7 +188,14 @@ impl ActiveDisplayMut<'_, '_> {
 
     /// Returns an iterator over mutable references to all other displays (non-active).
     pub fn other(&mut self) -> impl Iterator<Item = Mut<'_, Display>> {
-        self.other_displays.iter_mut()
+        self.other_displays.iter_mut().map(|(display, _)| display)
+    }
+
+    /// Returns the other displays whose windows are tiled, see `managed_displays`.
+    pub fn other_managed(&mut self) -> impl Iterator<Item = Mut<'_, Display>> {
+        self.other_displays
+            .iter_mut()
+            .filter_map(|(display, unmanaged)| (!unmanaged).then_some(display))
     }
 
     pub fn active_strip(&mut self) -> &mut LayoutStrip {
@@ -235,6 +252,8 @@ pub struct Windows<'w, 's> {
         ),
         With<Window>,
     >,
+    native_tabs: Query<'w, 's, &'static NativeTabs, With<Window>>,
+    focus_stats: Query<'w, 's, &'static FocusStats, With<Window>>,
 }
 
 impl Windows<'_, '_> {
@@ -292,6 +311,15 @@ impl Windows<'_, '_> {
             })
     }
 
+    /// Number of native tabs the window had when last checked.
+    pub fn native_tabs(&self, entity: Entity) -> Option<usize> {
+        self.native_tabs.get(entity).ok().map(|tabs| tabs.0)
+    }
+
+    pub fn focus_stats(&self, entity: Entity) -> Option<&FocusStats> {
+        self.focus_stats.get(entity).ok()
+    }
+
     pub fn full_width(&self, entity: Entity) -> Option<&FullWidthMarker> {
         self.previous_size
             .get(entity)
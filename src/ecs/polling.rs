@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::{With, Without};
+use bevy::ecs::schedule::IntoScheduleConfigs as _;
+use bevy::ecs::schedule::common_conditions::{not, resource_exists};
+use bevy::ecs::system::{Commands, Local, Query, Res};
+use bevy::time::common_conditions::on_timer;
+use tracing::{debug, info};
+
+use super::{ActiveWorkspaceMarker, FreshMarker, Initializing, SpawnWindowTrigger};
+use crate::config::Config;
+use crate::ecs::layout::LayoutStrip;
+use crate::ecs::params::Windows;
+use crate::manager::{Application, WindowManager};
+use crate::platform::WinID;
+
+/// How often the window lists of the applications are compared with the known windows.
+const WINDOW_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Marks an application which does not report its new windows with
+/// `AXCreated`, so its window list is polled instead.
+#[derive(Component)]
+pub struct BrokenNotifications;
+
+pub struct WindowPollingPlugin;
+
+impl Plugin for WindowPollingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            poll_application_windows
+                .run_if(not(resource_exists::<Initializing>))
+                .run_if(on_timer(WINDOW_POLL_INTERVAL)),
+        );
+    }
+}
+
+/// Picks the windows of an application to spawn from its window list. Windows of
+/// an app with broken notifications are taken as soon as they show up. Otherwise
+/// they have to be missing for two polls in a row, which is when the app is
+/// known to have skipped their notification. Returns the windows, and whether
+/// the app should now be flagged.
+fn unannounced_windows(
+    listed: &[WinID],
+    known: impl Fn(WinID) -> bool,
+    missing: &HashSet<WinID>,
+    broken: bool,
+) -> (Vec<WinID>, bool) {
+    let unknown = listed
+        .iter()
+        .copied()
+        .filter(|window_id| !known(*window_id))
+        .collect::<Vec<_>>();
+    if broken {
+        return (unknown, false);
+    }
+    if unknown.iter().any(|window_id| missing.contains(window_id)) {
+        return (unknown, true);
+    }
+    (vec![], false)
+}
+
+/// Finds windows the applications never announced. The frontmost app is checked
+/// for windows it did not report, which flags it with `BrokenNotifications`.
+/// The flagged apps are then polled for new windows, which are spawned through
+/// `SpawnWindowTrigger` as if their notification had arrived. The window ids
+/// come from the window server, and the app is only asked for the windows
+/// which turn out to be missing.
+#[allow(clippy::needless_pass_by_value)]
+fn poll_application_windows(
+    apps: Query<(Entity, &Application, Option<&BrokenNotifications>), Without<FreshMarker>>,
+    strips: Query<&LayoutStrip, With<ActiveWorkspaceMarker>>,
+    windows: Windows,
+    window_manager: Res<WindowManager>,
+    config: Res<Config>,
+    mut missing: Local<HashSet<WinID>>,
+    mut attempted: Local<HashSet<WinID>>,
+    mut commands: Commands,
+) {
+    // The app only reports the windows on the spaces shown on the displays.
+    let spaces = strips.iter().map(LayoutStrip::id).collect::<Vec<_>>();
+    let mut still_missing = HashSet::new();
+    let mut listed_ids = HashSet::new();
+    for (entity, app, broken) in &apps {
+        let broken = broken.is_some();
+        // Only the app in front, where the user opens windows, is checked for
+        // broken notifications.
+        if !broken && !app.is_frontmost() {
+            continue;
+        }
+        // An app without windows is an error for the window server.
+        let ids = window_manager
+            .application_window_ids(app, &spaces)
+            .unwrap_or_default();
+        listed_ids.extend(ids.iter().copied());
+
+        let known =
+            |window_id: WinID| windows.find(window_id).is_some() || attempted.contains(&window_id);
+        let (spawn, flag) = unannounced_windows(&ids, &known, &missing, broken);
+        if !broken {
+            still_missing.extend(ids.iter().copied().filter(|id| !known(*id)));
+        }
+        if spawn.is_empty() {
+            continue;
+        }
+        // Listing the windows is an accessibility round trip per window, so
+        // it is left for when some are missing.
+        let listed = app.window_list_of(&spawn, &config);
+        if listed.is_empty() {
+            continue;
+        }
+        if flag {
+            info!("{app} does not report its new windows, polling its window list.");
+            if let Ok(mut entity_commands) = commands.get_entity(entity) {
+                entity_commands.try_insert(BrokenNotifications);
+            }
+        }
+        debug!("{app}: polling found windows {spawn:?}");
+        attempted.extend(listed.iter().map(|window| window.id()));
+        commands.trigger(SpawnWindowTrigger(listed));
+    }
+    *missing = still_missing;
+    // Windows which were rejected are not spawned again while they are listed.
+    attempted.retain(|window_id| listed_ids.contains(window_id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unannounced_windows() {
+        let known = |window_id| window_id < 10;
+        let missing = HashSet::from([11]);
+
+        // A window missing for the first time may still be announced.
+        assert_eq!(
+            unannounced_windows(&[1, 12], known, &missing, false),
+            (vec![], false)
+        );
+        // Still missing on the next poll, the app is flagged.
+        assert_eq!(
+            unannounced_windows(&[1, 11, 12], known, &missing, false),
+            (vec![11, 12], true)
+        );
+        // Flagged apps have their new windows spawned right away.
+        assert_eq!(
+            unannounced_windows(&[1, 12], known, &missing, true),
+            (vec![12], false)
+        );
+    }
+}
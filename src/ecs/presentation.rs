@@ -0,0 +1,236 @@
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::message::MessageReader;
+use bevy::ecs::resource::Resource;
+use bevy::ecs::schedule::IntoScheduleConfigs as _;
+use bevy::ecs::system::{Commands, Local, Query, Res};
+use bevy::time::common_conditions::on_timer;
+use std::time::Duration;
+use tracing::info;
+
+use super::{BProcess, SendMessageTrigger, SpawnCommandsExt};
+use crate::commands::Command;
+use crate::config::Config;
+use crate::ecs::params::Windows;
+use crate::events::Event;
+use crate::manager::WindowManager;
+use crate::platform::ProcessSerialNumber;
+use crate::platform::input::set_input_suspended;
+
+const DISPLAY_CAPTURE_CHECK_MS: u64 = 1000;
+
+/// Present while window management is suspended for a presentation or a game.
+/// Keys and gestures pass through to the frontmost app, and layout changes are
+/// only applied to the windows once it resumes.
+#[derive(Clone, Copy, Debug, PartialEq, Resource)]
+pub enum PresentationMode {
+    /// An app listed in `presentation_apps` is frontmost.
+    App(ProcessSerialNumber),
+    /// A display is captured for exclusive use, as full screen games do.
+    DisplayCaptured,
+    /// Another user's session is active, after fast user switching.
+    SessionInactive,
+    /// Paused by `paneru pause`, until `paneru resume`.
+    Paused { keep_bindings: bool },
+}
+
+/// Run condition for the polling systems, which have nothing to poll while the
+/// session belongs to another user.
+pub fn session_active(presentation: Option<Res<PresentationMode>>) -> bool {
+    presentation.is_none_or(|mode| *mode != PresentationMode::SessionInactive)
+}
+
+pub struct PresentationPlugin;
+
+impl Plugin for PresentationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                presentation_app_trigger,
+                session_trigger,
+                pause_command_handler,
+                display_capture_watcher
+                    .run_if(session_active)
+                    .run_if(on_timer(Duration::from_millis(DISPLAY_CAPTURE_CHECK_MS))),
+            ),
+        );
+    }
+}
+
+fn enter_presentation(mode: PresentationMode, commands: &mut Commands) {
+    info!("suspending window management: {mode:?}");
+    set_input_suspended(!matches!(
+        mode,
+        PresentationMode::Paused {
+            keep_bindings: true
+        }
+    ));
+    crate::metrics::set_paused(matches!(mode, PresentationMode::Paused { .. }));
+    commands.insert_resource(mode);
+}
+
+fn leave_presentation(windows: &Windows, commands: &mut Commands) {
+    info!("resuming window management");
+    set_input_suspended(false);
+    crate::metrics::set_paused(false);
+    commands.remove_resource::<PresentationMode>();
+    // Lay the strip out again, in case the presentation moved windows around.
+    if let Some((_, entity)) = windows.focused() {
+        commands.reshuffle_around(entity);
+    }
+}
+
+/// Suspends management while an app of `presentation_apps` is frontmost, and
+/// resumes it when an app not in the list comes to the front or the app quits.
+#[allow(clippy::needless_pass_by_value)]
+fn presentation_app_trigger(
+    mut messages: MessageReader<Event>,
+    processes: Query<&BProcess>,
+    presentation: Option<Res<PresentationMode>>,
+    windows: Windows,
+    config: Res<Config>,
+    mut commands: Commands,
+) {
+    let mut current = presentation.map(|mode| *mode);
+    for event in messages.read() {
+        match event {
+            Event::ApplicationFrontSwitched { psn } => {
+                let presenting = processes
+                    .iter()
+                    .find(|process| process.psn() == *psn)
+                    .and_then(|process| process.bundle_id())
+                    .is_some_and(|bundle_id| config.is_presentation_app(&bundle_id));
+                match current {
+                    None if presenting => {
+                        current = Some(PresentationMode::App(*psn));
+                        enter_presentation(PresentationMode::App(*psn), &mut commands);
+                    }
+                    Some(PresentationMode::App(presenter)) if presenter != *psn => {
+                        if presenting {
+                            // Another presenter took over, management stays suspended.
+                            info!("presentation continues in another app");
+                            current = Some(PresentationMode::App(*psn));
+                            commands.insert_resource(PresentationMode::App(*psn));
+                        } else {
+                            current = None;
+                            leave_presentation(&windows, &mut commands);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            Event::ApplicationTerminated { psn }
+                if current == Some(PresentationMode::App(*psn)) =>
+            {
+                current = None;
+                leave_presentation(&windows, &mut commands);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Suspends management while the user switched to another login session. The
+/// displays may have changed in the meantime, so they are checked again on return.
+/// A suspension which was in place before the switch is restored on return, and a
+/// pause is left in place throughout, as only `paneru resume` ends it.
+#[allow(clippy::needless_pass_by_value)]
+fn session_trigger(
+    mut messages: MessageReader<Event>,
+    presentation: Option<Res<PresentationMode>>,
+    windows: Windows,
+    mut previous: Local<Option<PresentationMode>>,
+    mut commands: Commands,
+) {
+    let mut current = presentation.map(|mode| *mode);
+    for event in messages.read() {
+        match event {
+            Event::SessionResigned
+                if !matches!(
+                    current,
+                    Some(PresentationMode::SessionInactive | PresentationMode::Paused { .. })
+                ) =>
+            {
+                *previous = current;
+                current = Some(PresentationMode::SessionInactive);
+                enter_presentation(PresentationMode::SessionInactive, &mut commands);
+            }
+            Event::SessionActivated
+                if matches!(
+                    current,
+                    Some(PresentationMode::SessionInactive | PresentationMode::Paused { .. })
+                ) =>
+            {
+                if current == Some(PresentationMode::SessionInactive) {
+                    current = previous.take();
+                    match current {
+                        Some(mode) => enter_presentation(mode, &mut commands),
+                        None => leave_presentation(&windows, &mut commands),
+                    }
+                }
+                commands.trigger(SendMessageTrigger(Event::SystemWoke {
+                    msg: "login session active again".to_string(),
+                }));
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Pauses the management on `paneru pause` until `paneru resume`. A pause takes
+/// over from the other reasons to suspend, so only a resume ends it.
+#[allow(clippy::needless_pass_by_value)]
+fn pause_command_handler(
+    mut messages: MessageReader<Event>,
+    presentation: Option<Res<PresentationMode>>,
+    windows: Windows,
+    mut commands: Commands,
+) {
+    let mut current = presentation.map(|mode| *mode);
+    for event in messages.read() {
+        match event {
+            Event::Command {
+                command: Command::Pause(keep_bindings),
+            } => {
+                let mode = PresentationMode::Paused {
+                    keep_bindings: *keep_bindings,
+                };
+                if current != Some(mode) {
+                    current = Some(mode);
+                    enter_presentation(mode, &mut commands);
+                }
+            }
+            Event::Command {
+                command: Command::Resume,
+            } => {
+                if matches!(current, Some(PresentationMode::Paused { .. })) {
+                    current = None;
+                    leave_presentation(&windows, &mut commands);
+                } else {
+                    info!("window management is not paused");
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Suspends management while a display is captured for exclusive use, which
+/// full screen games and some screen sharing apps do. Any other suspension, a
+/// pause in particular, is left alone.
+#[allow(clippy::needless_pass_by_value)]
+fn display_capture_watcher(
+    presentation: Option<Res<PresentationMode>>,
+    window_manager: Res<WindowManager>,
+    windows: Windows,
+    mut commands: Commands,
+) {
+    let captured = window_manager.display_captured();
+    match presentation.as_deref() {
+        None if captured => enter_presentation(PresentationMode::DisplayCaptured, &mut commands),
+        Some(PresentationMode::DisplayCaptured) if !captured => {
+            leave_presentation(&windows, &mut commands);
+        }
+        _ => (),
+    }
+}
@@ -0,0 +1,503 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::message::MessageReader;
+use bevy::ecs::resource::Resource;
+use bevy::ecs::system::ResMut;
+#[cfg(any(test, feature = "replay"))]
+use objc2_core_foundation::CGPoint;
+use objc2_core_graphics::CGDirectDisplayID;
+use serde::{Deserialize, Serialize};
+use stdext::function_name;
+use tracing::{info, warn};
+
+use crate::ecs::state::HANDOFF_ENV;
+use crate::errors::{Error, Result};
+use crate::events::Event;
+use crate::manager::ax_window_id;
+#[cfg(any(test, feature = "replay"))]
+use crate::platform::Modifiers;
+use crate::platform::{ProcessSerialNumber, WinID, WorkspaceId};
+
+/// A platform event in a form which can be written to a recording and read back.
+/// Commands, socket requests and events carrying OS handles are not recorded.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    ApplicationTerminated {
+        psn: ProcessSerialNumber,
+    },
+    ApplicationFrontSwitched {
+        psn: ProcessSerialNumber,
+    },
+    ApplicationVisible {
+        pid: i32,
+    },
+    ApplicationHidden {
+        pid: i32,
+    },
+
+    /// Only the id of the new window is kept, a replay has to make up the rest.
+    WindowCreated {
+        window_id: WinID,
+    },
+    WindowDestroyed {
+        window_id: WinID,
+    },
+    WindowFocused {
+        window_id: WinID,
+    },
+    WindowMoved {
+        window_id: WinID,
+    },
+    WindowResized {
+        window_id: WinID,
+    },
+    WindowMinimized {
+        window_id: WinID,
+    },
+    WindowDeminimized {
+        window_id: WinID,
+    },
+    WindowTitleChanged {
+        window_id: WinID,
+    },
+
+    MouseDown {
+        x: f64,
+        y: f64,
+        modifiers: u16,
+    },
+    MouseUp {
+        x: f64,
+        y: f64,
+        modifiers: u16,
+    },
+    MouseDragged {
+        x: f64,
+        y: f64,
+        modifiers: u16,
+    },
+    MouseMoved {
+        x: f64,
+        y: f64,
+        modifiers: u16,
+    },
+
+    Swipe {
+        delta: f64,
+        fingers: usize,
+    },
+    VerticalSwipe {
+        delta: f64,
+        fingers: usize,
+    },
+    VerticalScrollTick {
+        delta: f64,
+    },
+    Scroll {
+        delta: f64,
+    },
+    TouchpadDown,
+    TouchpadUp,
+
+    SpaceCreated {
+        space_id: WorkspaceId,
+    },
+    SpaceDestroyed {
+        space_id: WorkspaceId,
+    },
+    SpaceChanged,
+
+    DisplayAdded {
+        display_id: CGDirectDisplayID,
+    },
+    DisplayRemoved {
+        display_id: CGDirectDisplayID,
+    },
+    DisplayMoved {
+        display_id: CGDirectDisplayID,
+    },
+    DisplayResized {
+        display_id: CGDirectDisplayID,
+    },
+    DisplayConfigured {
+        display_id: CGDirectDisplayID,
+    },
+    MainDisplayChanged {
+        display_id: CGDirectDisplayID,
+    },
+    DisplayChanged,
+
+    MissionControlShowAllWindows,
+    MissionControlShowFrontWindows,
+    MissionControlShowDesktop,
+    MissionControlExit,
+
+    MenuOpened {
+        window_id: WinID,
+    },
+    MenuClosed {
+        window_id: WinID,
+    },
+    SystemWoke,
+}
+
+impl RecordedEvent {
+    /// Converts an incoming event, or returns `None` for events which are not recorded.
+    #[allow(clippy::too_many_lines)]
+    pub fn from_event(event: &Event) -> Option<Self> {
+        let recorded = match event {
+            Event::ApplicationTerminated { psn } => Self::ApplicationTerminated { psn: *psn },
+            Event::ApplicationFrontSwitched { psn } => Self::ApplicationFrontSwitched { psn: *psn },
+            Event::ApplicationVisible { pid } => Self::ApplicationVisible { pid: *pid },
+            Event::ApplicationHidden { pid } => Self::ApplicationHidden { pid: *pid },
+
+            Event::WindowCreated { element } => Self::WindowCreated {
+                window_id: ax_window_id(element.as_ptr()).ok()?,
+            },
+            Event::WindowDestroyed { window_id } => Self::WindowDestroyed {
+                window_id: *window_id,
+            },
+            Event::WindowFocused { window_id } => Self::WindowFocused {
+                window_id: *window_id,
+            },
+            Event::WindowMoved { window_id } => Self::WindowMoved {
+                window_id: *window_id,
+            },
+            Event::WindowResized { window_id } => Self::WindowResized {
+                window_id: *window_id,
+            },
+            Event::WindowMinimized { window_id } => Self::WindowMinimized {
+                window_id: *window_id,
+            },
+            Event::WindowDeminimized { window_id } => Self::WindowDeminimized {
+                window_id: *window_id,
+            },
+            Event::WindowTitleChanged { window_id } => Self::WindowTitleChanged {
+                window_id: *window_id,
+            },
+
+            Event::MouseDown { point, modifiers } => Self::MouseDown {
+                x: point.x,
+                y: point.y,
+                modifiers: modifiers.bits(),
+            },
+            Event::MouseUp { point, modifiers } => Self::MouseUp {
+                x: point.x,
+                y: point.y,
+                modifiers: modifiers.bits(),
+            },
+            Event::MouseDragged { point, modifiers } => Self::MouseDragged {
+                x: point.x,
+                y: point.y,
+                modifiers: modifiers.bits(),
+            },
+            Event::MouseMoved { point, modifiers } => Self::MouseMoved {
+                x: point.x,
+                y: point.y,
+                modifiers: modifiers.bits(),
+            },
+
+            Event::Swipe { delta, fingers } => Self::Swipe {
+                delta: *delta,
+                fingers: *fingers,
+            },
+            Event::VerticalSwipe { delta, fingers } => Self::VerticalSwipe {
+                delta: *delta,
+                fingers: *fingers,
+            },
+            Event::VerticalScrollTick { delta } => Self::VerticalScrollTick { delta: *delta },
+            Event::Scroll { delta } => Self::Scroll { delta: *delta },
+            Event::TouchpadDown => Self::TouchpadDown,
+            Event::TouchpadUp => Self::TouchpadUp,
+
+            Event::SpaceCreated { space_id } => Self::SpaceCreated {
+                space_id: *space_id,
+            },
+            Event::SpaceDestroyed { space_id } => Self::SpaceDestroyed {
+                space_id: *space_id,
+            },
+            Event::SpaceChanged => Self::SpaceChanged,
+
+            Event::DisplayAdded { display_id } => Self::DisplayAdded {
+                display_id: *display_id,
+            },
+            Event::DisplayRemoved { display_id } => Self::DisplayRemoved {
+                display_id: *display_id,
+            },
+            Event::DisplayMoved { display_id } => Self::DisplayMoved {
+                display_id: *display_id,
+            },
+            Event::DisplayResized { display_id } => Self::DisplayResized {
+                display_id: *display_id,
+            },
+            Event::DisplayConfigured { display_id } => Self::DisplayConfigured {
+                display_id: *display_id,
+            },
+            Event::MainDisplayChanged { display_id } => Self::MainDisplayChanged {
+                display_id: *display_id,
+            },
+            Event::DisplayChanged => Self::DisplayChanged,
+
+            Event::MissionControlShowAllWindows => Self::MissionControlShowAllWindows,
+            Event::MissionControlShowFrontWindows => Self::MissionControlShowFrontWindows,
+            Event::MissionControlShowDesktop => Self::MissionControlShowDesktop,
+            Event::MissionControlExit => Self::MissionControlExit,
+
+            Event::MenuOpened { window_id } => Self::MenuOpened {
+                window_id: *window_id,
+            },
+            Event::MenuClosed { window_id } => Self::MenuClosed {
+                window_id: *window_id,
+            },
+            Event::SystemWoke { .. } => Self::SystemWoke,
+            _ => return None,
+        };
+        Some(recorded)
+    }
+
+    /// Converts the recorded event back into an event for the ECS. Returns
+    /// `None` for `WindowCreated`, whose window has to be created by the replay.
+    #[cfg(any(test, feature = "replay"))]
+    pub fn into_event(self) -> Option<Event> {
+        let point = |x, y| CGPoint::new(x, y);
+        let event = match self {
+            Self::ApplicationTerminated { psn } => Event::ApplicationTerminated { psn },
+            Self::ApplicationFrontSwitched { psn } => Event::ApplicationFrontSwitched { psn },
+            Self::ApplicationVisible { pid } => Event::ApplicationVisible { pid },
+            Self::ApplicationHidden { pid } => Event::ApplicationHidden { pid },
+
+            Self::WindowCreated { .. } => return None,
+            Self::WindowDestroyed { window_id } => Event::WindowDestroyed { window_id },
+            Self::WindowFocused { window_id } => Event::WindowFocused { window_id },
+            Self::WindowMoved { window_id } => Event::WindowMoved { window_id },
+            Self::WindowResized { window_id } => Event::WindowResized { window_id },
+            Self::WindowMinimized { window_id } => Event::WindowMinimized { window_id },
+            Self::WindowDeminimized { window_id } => Event::WindowDeminimized { window_id },
+            Self::WindowTitleChanged { window_id } => Event::WindowTitleChanged { window_id },
+
+            Self::MouseDown { x, y, modifiers } => Event::MouseDown {
+                point: point(x, y),
+                modifiers: Modifiers::from_bits_truncate(modifiers),
+            },
+            Self::MouseUp { x, y, modifiers } => Event::MouseUp {
+                point: point(x, y),
+                modifiers: Modifiers::from_bits_truncate(modifiers),
+            },
+            Self::MouseDragged { x, y, modifiers } => Event::MouseDragged {
+                point: point(x, y),
+                modifiers: Modifiers::from_bits_truncate(modifiers),
+            },
+            Self::MouseMoved { x, y, modifiers } => Event::MouseMoved {
+                point: point(x, y),
+                modifiers: Modifiers::from_bits_truncate(modifiers),
+            },
+
+            Self::Swipe { delta, fingers } => Event::Swipe { delta, fingers },
+            Self::VerticalSwipe { delta, fingers } => Event::VerticalSwipe { delta, fingers },
+            Self::VerticalScrollTick { delta } => Event::VerticalScrollTick { delta },
+            Self::Scroll { delta } => Event::Scroll { delta },
+            Self::TouchpadDown => Event::TouchpadDown,
+            Self::TouchpadUp => Event::TouchpadUp,
+
+            Self::SpaceCreated { space_id } => Event::SpaceCreated { space_id },
+            Self::SpaceDestroyed { space_id } => Event::SpaceDestroyed { space_id },
+            Self::SpaceChanged => Event::SpaceChanged,
+
+            Self::DisplayAdded { display_id } => Event::DisplayAdded { display_id },
+            Self::DisplayRemoved { display_id } => Event::DisplayRemoved { display_id },
+            Self::DisplayMoved { display_id } => Event::DisplayMoved { display_id },
+            Self::DisplayResized { display_id } => Event::DisplayResized { display_id },
+            Self::DisplayConfigured { display_id } => Event::DisplayConfigured { display_id },
+            Self::MainDisplayChanged { display_id } => Event::MainDisplayChanged { display_id },
+            Self::DisplayChanged => Event::DisplayChanged,
+
+            Self::MissionControlShowAllWindows => Event::MissionControlShowAllWindows,
+            Self::MissionControlShowFrontWindows => Event::MissionControlShowFrontWindows,
+            Self::MissionControlShowDesktop => Event::MissionControlShowDesktop,
+            Self::MissionControlExit => Event::MissionControlExit,
+
+            Self::MenuOpened { window_id } => Event::MenuOpened { window_id },
+            Self::MenuClosed { window_id } => Event::MenuClosed { window_id },
+            Self::SystemWoke => Event::SystemWoke { msg: String::new() },
+        };
+        Some(event)
+    }
+}
+
+/// One line of a recording: an event and when it arrived, in milliseconds
+/// since the recording started.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEntry {
+    pub offset_ms: u64,
+    #[serde(flatten)]
+    pub event: RecordedEvent,
+}
+
+/// Reads a recording written by `paneru launch --record`, one JSON entry per line.
+pub fn read_recording(path: &Path) -> Result<Vec<RecordedEntry>> {
+    BufReader::new(File::open(path)?)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().is_ok_and(|line| !line.trim().is_empty()))
+        .map(|(index, line)| {
+            serde_json::from_str(&line?).map_err(|err| {
+                Error::InvalidInput(format!(
+                    "{}: {}:{}: {err}",
+                    function_name!(),
+                    path.display(),
+                    index + 1
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Appends the incoming platform events to a recording file.
+#[derive(Resource)]
+pub struct EventRecorder {
+    writer: BufWriter<File>,
+    started: Instant,
+    /// Where the offsets continue from, when a recording is resumed.
+    base_ms: u64,
+}
+
+impl EventRecorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            started: Instant::now(),
+            base_ms: 0,
+        })
+    }
+
+    /// Continues the recording of the daemon this one took over from, after
+    /// its last entry.
+    pub fn resume(path: &Path) -> Result<Self> {
+        let base_ms = read_recording(path)
+            .ok()
+            .and_then(|entries| entries.last().map(|entry| entry.offset_ms))
+            .unwrap_or_default();
+        Ok(Self {
+            writer: BufWriter::new(OpenOptions::new().create(true).append(true).open(path)?),
+            started: Instant::now(),
+            base_ms,
+        })
+    }
+
+    fn record(&mut self, event: RecordedEvent) -> Result<()> {
+        let elapsed = u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let entry = RecordedEntry {
+            offset_ms: self.base_ms.saturating_add(elapsed),
+            event,
+        };
+        writeln!(self.writer, "{}", serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+}
+
+/// Records the platform events into the file given with `paneru launch --record`.
+pub struct RecorderPlugin {
+    pub path: PathBuf,
+}
+
+impl Plugin for RecorderPlugin {
+    fn build(&self, app: &mut App) {
+        // A daemon taking over from a restarted one carries on its recording.
+        let recorder = if std::env::var_os(HANDOFF_ENV).is_some() {
+            EventRecorder::resume(&self.path)
+        } else {
+            EventRecorder::create(&self.path)
+        };
+        match recorder {
+            Ok(recorder) => {
+                info!("recording events to {}", self.path.display());
+                app.insert_resource(recorder)
+                    .add_systems(Update, record_events);
+            }
+            Err(err) => warn!("unable to record events to {}: {err}", self.path.display()),
+        }
+    }
+}
+
+fn record_events(mut messages: MessageReader<Event>, mut recorder: ResMut<EventRecorder>) {
+    let mut recorded = false;
+    for event in messages.read().filter_map(RecordedEvent::from_event) {
+        _ = recorder
+            .record(event)
+            .inspect_err(|err| warn!("recording event: {err}"));
+        recorded = true;
+    }
+    // Flush every batch, so that the recording survives a crash.
+    if recorded {
+        _ = recorder
+            .writer
+            .flush()
+            .inspect_err(|err| warn!("flushing recording: {err}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorded_events_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "paneru-recording-test-{}.jsonl",
+            std::process::id()
+        ));
+        let mut recorder = EventRecorder::create(&path).unwrap();
+        let events = [
+            Event::SpaceChanged,
+            Event::WindowFocused { window_id: 7 },
+            Event::MouseDown {
+                point: CGPoint::new(10.0, 20.5),
+                modifiers: Modifiers::LSHIFT,
+            },
+        ];
+        for event in &events {
+            recorder
+                .record(RecordedEvent::from_event(event).unwrap())
+                .unwrap();
+        }
+        assert!(RecordedEvent::from_event(&Event::ProcessesLoaded).is_none());
+        recorder.writer.flush().unwrap();
+
+        let entries = read_recording(&path).unwrap();
+        _ = std::fs::remove_file(&path);
+        assert_eq!(entries.len(), 3);
+        assert!(matches!(
+            entries[1].event.clone().into_event(),
+            Some(Event::WindowFocused { window_id: 7 })
+        ));
+        assert!(matches!(
+            entries[2].event.clone().into_event(),
+            Some(Event::MouseDown { point, modifiers })
+                if (point.y - 20.5).abs() < f64::EPSILON && modifiers == Modifiers::LSHIFT
+        ));
+        assert!(entries.windows(2).all(|w| w[0].offset_ms <= w[1].offset_ms));
+    }
+
+    #[test]
+    fn test_resumed_recording_continues() {
+        let path =
+            std::env::temp_dir().join(format!("paneru-resume-test-{}.jsonl", std::process::id()));
+        std::fs::write(&path, "{\"offset_ms\":5000,\"event\":\"space_changed\"}\n").unwrap();
+        let mut recorder = EventRecorder::resume(&path).unwrap();
+        recorder
+            .record(RecordedEvent::from_event(&Event::SpaceChanged).unwrap())
+            .unwrap();
+        recorder.writer.flush().unwrap();
+
+        let entries = read_recording(&path);
+        _ = std::fs::remove_file(&path);
+        let entries = entries.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[1].offset_ms >= 5000);
+    }
+}
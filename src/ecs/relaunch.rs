@@ -0,0 +1,238 @@
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::entity::Entity;
+use bevy::ecs::hierarchy::Children;
+use bevy::ecs::message::MessageReader;
+use bevy::ecs::resource::Resource;
+use bevy::ecs::schedule::IntoScheduleConfigs as _;
+use bevy::ecs::system::{Query, Res, ResMut};
+use bevy::time::Time;
+use tracing::debug;
+
+use super::BProcess;
+use super::triggers::{application_event_trigger, window_destroyed_trigger};
+use crate::ecs::layout::LayoutStrip;
+use crate::ecs::params::Windows;
+use crate::events::Event;
+use crate::manager::Application;
+use crate::platform::{Pid, WorkspaceId};
+
+/// How long the windows of a terminated app keep their place in the strip.
+const TOMBSTONE_GRACE: Duration = Duration::from_secs(60);
+/// Windows closing this long before their app terminates were closed by the
+/// quitting app, not by the user.
+const TOMBSTONE_PENDING: Duration = Duration::from_secs(5);
+
+/// Where a window of a terminated app used to be tiled.
+#[derive(Clone, Debug)]
+pub struct Tombstone {
+    pid: Pid,
+    bundle_id: String,
+    title: String,
+    pub workspace_id: WorkspaceId,
+    pub virtual_index: u32,
+    pub index: usize,
+    pub width_ratio: Option<f64>,
+    buried: Duration,
+    /// Set once the app has terminated. Until then the window may just have
+    /// been closed by the user.
+    confirmed: bool,
+}
+
+/// Places of the windows of recently terminated apps, so that the windows of a
+/// relaunched app, e.g. a browser after an update, return to their columns.
+#[derive(Debug, Default, Resource)]
+pub struct Tombstones(Vec<Tombstone>);
+
+impl Tombstones {
+    fn expire(&mut self, now: Duration) {
+        self.0.retain(|stone| {
+            let grace = if stone.confirmed {
+                TOMBSTONE_GRACE
+            } else {
+                TOMBSTONE_PENDING
+            };
+            now.saturating_sub(stone.buried) < grace
+        });
+    }
+
+    /// Takes the place of a window of a terminated app in the strip `strip`: the
+    /// one with the same title, otherwise the leftmost window of the app. Places
+    /// in other strips are kept for the windows opening there.
+    pub fn adopt(
+        &mut self,
+        bundle_id: &str,
+        title: &str,
+        strip: (WorkspaceId, u32),
+        now: Duration,
+    ) -> Option<Tombstone> {
+        self.expire(now);
+        let candidates = || {
+            self.0.iter().enumerate().filter(|(_, stone)| {
+                stone.confirmed
+                    && stone.bundle_id == bundle_id
+                    && (stone.workspace_id, stone.virtual_index) == strip
+            })
+        };
+        let position = candidates()
+            .find(|(_, stone)| stone.title == title)
+            .or_else(|| candidates().min_by_key(|(_, stone)| stone.index))
+            .map(|(position, _)| position)?;
+        Some(self.0.remove(position))
+    }
+}
+
+pub struct RelaunchPlugin;
+
+impl Plugin for RelaunchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tombstones>().add_systems(
+            Update,
+            bury_windows
+                .before(application_event_trigger)
+                .before(window_destroyed_trigger),
+        );
+    }
+}
+
+/// Records the places of the windows of terminating apps, before the windows
+/// are removed from their strips.
+#[allow(clippy::needless_pass_by_value)]
+fn bury_windows(
+    mut messages: MessageReader<Event>,
+    processes: Query<(&BProcess, &Children)>,
+    apps: Query<(&Application, &Children)>,
+    workspaces: Query<&LayoutStrip>,
+    windows: Windows,
+    time: Res<Time>,
+    mut tombstones: ResMut<Tombstones>,
+) {
+    let now = time.elapsed();
+    let bury = |app: &Application, entity: Entity, confirmed: bool| {
+        let bundle_id = app.bundle_id()?;
+        let window = windows.get(entity)?;
+        let (strip, index) = workspaces
+            .iter()
+            .find_map(|strip| strip.index_of(entity).ok().map(|index| (strip, index)))?;
+        debug!("burying window {} of {bundle_id} at {index}", window.id());
+        Some(Tombstone {
+            pid: app.pid(),
+            bundle_id,
+            title: window.title().unwrap_or_default(),
+            workspace_id: strip.id(),
+            virtual_index: strip.virtual_index,
+            index,
+            width_ratio: windows.width_ratio(entity),
+            buried: now,
+            confirmed,
+        })
+    };
+
+    for event in messages.read() {
+        match event {
+            // Apps often close their windows before they terminate.
+            Event::WindowDestroyed { window_id } => {
+                if let Some((window, entity, parent)) = windows.find_parent(*window_id)
+                    && !window.is_alive()
+                    && let Ok((app, _)) = apps.get(parent)
+                {
+                    tombstones.0.extend(bury(app, entity, false));
+                }
+            }
+            Event::ApplicationTerminated { psn } => {
+                let Some((process, children)) =
+                    processes.iter().find(|(process, _)| process.psn() == *psn)
+                else {
+                    continue;
+                };
+                let pid = process.pid();
+                for stone in &mut tombstones.0 {
+                    stone.confirmed |= stone.pid == pid;
+                }
+                for (app, app_windows) in apps.iter_many(children) {
+                    for entity in app_windows {
+                        tombstones.0.extend(bury(app, *entity, true));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRIP: (WorkspaceId, u32) = (1, 0);
+
+    fn stone(title: &str, index: usize, confirmed: bool) -> Tombstone {
+        Tombstone {
+            pid: 1,
+            bundle_id: "com.example.browser".to_string(),
+            title: title.to_string(),
+            workspace_id: STRIP.0,
+            virtual_index: 0,
+            index,
+            width_ratio: None,
+            buried: Duration::ZERO,
+            confirmed,
+        }
+    }
+
+    #[test]
+    fn test_adopt_prefers_matching_title() {
+        let mut tombstones = Tombstones(vec![
+            stone("News", 3, true),
+            stone("Mail", 1, true),
+            stone("Closed", 0, false),
+        ]);
+        let now = Duration::from_secs(1);
+
+        let adopted = tombstones.adopt("com.example.browser", "News", STRIP, now);
+        assert_eq!(adopted.map(|stone| stone.index), Some(3));
+        // An unknown title takes the leftmost place of a window the app closed.
+        let adopted = tombstones.adopt("com.example.browser", "New Tab", STRIP, now);
+        assert_eq!(adopted.map(|stone| stone.index), Some(1));
+        assert!(
+            tombstones
+                .adopt("com.example.browser", "", STRIP, now)
+                .is_none()
+        );
+        assert!(
+            tombstones
+                .adopt("com.example.other", "News", STRIP, now)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_adopt_keeps_places_in_other_strips() {
+        let mut tombstones = Tombstones(vec![stone("News", 3, true)]);
+        let now = Duration::from_secs(1);
+
+        assert!(
+            tombstones
+                .adopt("com.example.browser", "News", (2, 0), now)
+                .is_none()
+        );
+        assert!(
+            tombstones
+                .adopt("com.example.browser", "News", (1, 1), now)
+                .is_none()
+        );
+        let adopted = tombstones.adopt("com.example.browser", "News", STRIP, now);
+        assert_eq!(adopted.map(|stone| stone.index), Some(3));
+    }
+
+    #[test]
+    fn test_tombstones_expire() {
+        let mut tombstones = Tombstones(vec![stone("News", 3, true)]);
+        assert!(
+            tombstones
+                .adopt("com.example.browser", "News", STRIP, TOMBSTONE_GRACE)
+                .is_none()
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use bevy::app::{App, Plugin, Update};
 use bevy::ecs::entity::Entity;
 use bevy::ecs::message::MessageReader;
-use bevy::ecs::query::{With, Without};
+use bevy::ecs::query::{Has, With, Without};
 use bevy::ecs::schedule::IntoScheduleConfigs as _;
 use bevy::ecs::system::{Commands, Local, Populated, Res, Single};
 use bevy::math::IRect;
@@ -10,12 +10,13 @@ use std::time::{Duration, Instant};
 use tracing::{Level, instrument};
 
 use crate::commands::{Command, Direction, Operation};
-use crate::config::Config;
 use crate::config::swipe::SwipeGestureDirection;
-use crate::ecs::layout::{Column, LayoutStrip};
+use crate::config::{CenterFocusedColumn, Config};
+use crate::ecs::layout::{Column, LayoutStrip, nearest_column_snap};
 use crate::ecs::params::{ActiveDisplay, Windows};
 use crate::ecs::{
     ActiveWorkspaceMarker, MissionControlActive, Position, Scrolling, SendMessageTrigger,
+    SpawnCommandsExt, SwipeMomentum,
 };
 use crate::errors::Result;
 use crate::events::Event;
@@ -37,6 +38,7 @@ impl Plugin for ScrollEventsPlugin {
                 (
                     swipe_gesture.run_if(mission_control_inactive),
                     apply_inertia,
+                    finish_swipe_momentum,
                     apply_snap_force,
                     scrolling_integrator,
                     apply_scrolling_constraints,
@@ -62,8 +64,13 @@ fn swipe_gesture(
     mut commands: Commands,
 ) {
     let swipe_sensitivity = config.swipe_sensitivity();
+    // Fingers which rested on the trackpad for longer than this before lifting
+    // off do not fling the strip.
+    const FLICK_WINDOW: Duration = Duration::from_millis(80);
+
     let mut total_delta = 0.0;
     let mut touchpad_down = false;
+    let mut touchpad_up = false;
     let mut has_scroll_event = false;
 
     // Normalization: Touchpad deltas are typically small fractions.
@@ -81,6 +88,7 @@ fn swipe_gesture(
                 touchpad_down = true;
                 total_delta = 0.0;
             }
+            Event::TouchpadUp => touchpad_up = true,
             Event::Scroll { delta } => {
                 total_delta += *delta * scroll_scale;
                 has_scroll_event = true;
@@ -97,7 +105,7 @@ fn swipe_gesture(
         }
     }
 
-    if !touchpad_down && !has_scroll_event {
+    if !touchpad_down && !touchpad_up && !has_scroll_event {
         return;
     }
 
@@ -107,6 +115,23 @@ fn swipe_gesture(
         scrolling.velocity = 0.0;
         scrolling.is_user_swiping = true;
         scrolling.last_event = Instant::now();
+        if let Ok(mut entity_commands) = commands.get_entity(*entity) {
+            entity_commands.try_remove::<SwipeMomentum>();
+        }
+    }
+
+    if touchpad_up
+        && !has_scroll_event
+        && let Some(scrolling) = scrolling.as_mut()
+        && scrolling.is_user_swiping
+    {
+        scrolling.is_user_swiping = false;
+        if scrolling.last_event.elapsed() > FLICK_WINDOW {
+            scrolling.velocity = 0.0;
+        }
+        if let Ok(mut entity_commands) = commands.get_entity(*entity) {
+            entity_commands.try_insert(SwipeMomentum);
+        }
     }
 
     if has_scroll_event {
@@ -145,7 +170,7 @@ fn swipe_gesture(
 #[allow(clippy::needless_pass_by_value)]
 #[instrument(level = Level::TRACE, skip_all)]
 pub(super) fn swiping_timeout(
-    strips: Populated<(Entity, &mut Scrolling), With<LayoutStrip>>,
+    strips: Populated<(Entity, &mut Scrolling, Has<SwipeMomentum>), With<LayoutStrip>>,
     active_display: ActiveDisplay,
     time: Res<Time>,
     window_manager: Res<WindowManager>,
@@ -156,11 +181,13 @@ pub(super) fn swiping_timeout(
     let dt = time.delta_secs_f64();
     let viewport_width = f64::from(active_display.bounds().width());
 
-    for (entity, mut scroll) in strips {
+    for (entity, mut scroll, momentum) in strips {
         if scroll.last_event.elapsed() > FINGER_LIFT_THRESHOLD {
             scroll.is_user_swiping = false;
 
-            if scroll.velocity.abs() * dt * viewport_width < MIN_VELOCITY_PX
+            // Strips in momentum are settled by `finish_swipe_momentum`.
+            if !momentum
+                && scroll.velocity.abs() * dt * viewport_width < MIN_VELOCITY_PX
                 && let Ok(mut entity_commands) = commands.get_entity(entity)
             {
                 entity_commands.try_remove::<Scrolling>();
@@ -197,6 +224,55 @@ fn apply_inertia(
     }
 }
 
+/// Ends the momentum of a strip once it has slowed down, and settles it with
+/// a column edge on the edge of the display.
+#[allow(clippy::needless_pass_by_value)]
+#[instrument(level = Level::TRACE, skip_all)]
+fn finish_swipe_momentum(
+    strips: Populated<(Entity, &LayoutStrip, &Position, &Scrolling), With<SwipeMomentum>>,
+    active_display: ActiveDisplay,
+    windows: Windows,
+    config: Res<Config>,
+    mut commands: Commands,
+) {
+    // In viewport widths per second.
+    const MOMENTUM_STOP_VELOCITY: f64 = 0.05;
+
+    let viewport = active_display.actual_bounds(&config);
+    for (entity, strip, position, scroll) in strips {
+        if scroll.velocity.abs() >= MOMENTUM_STOP_VELOCITY {
+            continue;
+        }
+        let Ok(mut entity_commands) = commands.get_entity(entity) else {
+            continue;
+        };
+        if config.auto_center() || config.center_focused_column() == CenterFocusedColumn::Always {
+            // The snap force centers a column instead.
+            entity_commands.try_remove::<SwipeMomentum>();
+            continue;
+        }
+        entity_commands.try_remove::<(SwipeMomentum, Scrolling)>();
+
+        let columns = strip
+            .all_columns()
+            .into_iter()
+            .filter_map(|entity| {
+                let left = windows.layout_position(entity)?.0.x;
+                Some((left, left + windows.moving_frame(entity)?.width()))
+            })
+            .collect::<Vec<_>>();
+        if let Some(x) = nearest_column_snap(
+            position.x,
+            &columns,
+            viewport,
+            config.swipe_snap_threshold(),
+        ) && x != position.x
+        {
+            commands.reposition_entity(entity, position.0.with_x(x));
+        }
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 #[instrument(level = Level::TRACE, skip_all)]
 fn apply_snap_force(
@@ -209,7 +285,7 @@ fn apply_snap_force(
     const CENTER_MAGNETIC_FORCE: f64 = 10.0;
     const SNAP_DISPLAY_RATIO: f64 = 0.45;
 
-    if !config.auto_center() {
+    if !config.auto_center() && config.center_focused_column() != CenterFocusedColumn::Always {
         return;
     }
 
@@ -353,6 +429,8 @@ struct VerticalGestureState {
     accumulated: f64,
     last_event: Option<Instant>,
     fired: bool,
+    /// Number of fingers of the swipe in progress.
+    fingers: usize,
 }
 
 #[allow(clippy::needless_pass_by_value)]
@@ -384,11 +462,13 @@ fn vertical_swipe_gesture(
                 switch_virtual_workspace(*delta, &config, &mut commands);
             }
             Event::VerticalSwipe { delta, fingers }
-                if config
-                    .swipe_gesture_fingers()
-                    .is_none_or(|fingers_configured| fingers_configured == *fingers) =>
+                if config.swipe_vertical_bindings(*fingers).is_some()
+                    || config
+                        .swipe_gesture_fingers()
+                        .is_none_or(|fingers_configured| fingers_configured == *fingers) =>
             {
                 state.last_event = Some(Instant::now());
+                state.fingers = *fingers;
 
                 if !state.fired {
                     state.accumulated += delta;
@@ -402,7 +482,16 @@ fn vertical_swipe_gesture(
     // during horizontal swipes doesn't trigger a workspace switch.
     let threshold = 0.15 / config.swipe_sensitivity();
     if state.accumulated.abs() >= threshold {
-        switch_virtual_workspace(state.accumulated, &config, &mut commands);
+        if let Some(bindings) = config.swipe_vertical_bindings(state.fingers) {
+            // Fingers moving up on the trackpad give a negative delta.
+            if let Some(command) = bindings.command(state.accumulated < 0.0) {
+                commands.trigger(SendMessageTrigger(Event::Command {
+                    command: command.clone(),
+                }));
+            }
+        } else {
+            switch_virtual_workspace(state.accumulated, &config, &mut commands);
+        }
         state.accumulated = 0.0;
         state.fired = true;
     }
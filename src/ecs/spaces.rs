@@ -0,0 +1,160 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::hierarchy::Children;
+use bevy::ecs::schedule::IntoScheduleConfigs as _;
+use bevy::ecs::schedule::common_conditions::{not, resource_exists};
+use bevy::ecs::system::{Local, Query, Res};
+use bevy::time::Time;
+use bevy::time::common_conditions::on_timer;
+use tracing::{info, warn};
+
+use super::Initializing;
+use crate::config::Config;
+use crate::ecs::layout::LayoutStrip;
+use crate::manager::{Display, WindowManager};
+use crate::platform::WorkspaceId;
+
+/// How often the spaces are checked for windows.
+const EMPTY_SPACE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct EmptySpacesPlugin;
+
+impl Plugin for EmptySpacesPlugin {
+    fn build(&self, app: &mut App) {
+        let enabled =
+            |config: Option<Res<Config>>| config.is_some_and(|config| config.close_empty_spaces());
+
+        app.add_systems(
+            Update,
+            close_empty_spaces
+                .run_if(enabled)
+                .run_if(not(resource_exists::<Initializing>))
+                .run_if(on_timer(EMPTY_SPACE_CHECK_INTERVAL)),
+        );
+    }
+}
+
+/// Picks the space to close on a display. `spaces` holds the spaces of the
+/// display in Mission Control order, with the time each one became empty. While
+/// more than `keep` of them are empty, the rightmost one which stayed empty for
+/// `grace` is closed, unless it is the current space.
+fn space_to_close(
+    spaces: &[(WorkspaceId, Option<Duration>)],
+    current: WorkspaceId,
+    now: Duration,
+    grace: Duration,
+    keep: usize,
+) -> Option<WorkspaceId> {
+    let empty = spaces.iter().filter(|(_, since)| since.is_some()).count();
+    if empty <= keep {
+        return None;
+    }
+    spaces
+        .iter()
+        .rev()
+        .find(|(space_id, since)| {
+            *space_id != current && since.is_some_and(|since| now.saturating_sub(since) >= grace)
+        })
+        .map(|(space_id, _)| *space_id)
+}
+
+/// Closes the macOS spaces left without windows, at most one per display at a
+/// time, because every space is closed through Mission Control.
+#[allow(clippy::needless_pass_by_value)]
+fn close_empty_spaces(
+    displays: Query<(&Display, &Children)>,
+    strips: Query<&LayoutStrip>,
+    window_manager: Res<WindowManager>,
+    config: Res<Config>,
+    time: Res<Time>,
+    mut empty_since: Local<HashMap<WorkspaceId, Duration>>,
+) {
+    let now = time.elapsed();
+    let mut seen = HashSet::new();
+    for (display, children) in &displays {
+        let (Ok(current), Ok(space_ids)) = (
+            window_manager.active_display_space(display.id()),
+            window_manager.display_spaces(display.id()),
+        ) else {
+            continue;
+        };
+        let display_strips = strips.iter_many(children).collect::<Vec<_>>();
+        let spaces = space_ids
+            .into_iter()
+            .filter_map(|space_id| {
+                let space_strips = display_strips
+                    .iter()
+                    .filter(|strip| strip.id() == space_id)
+                    .collect::<Vec<_>>();
+                // The windows of a space which was never visited are not known yet.
+                if space_strips.is_empty() || space_strips.iter().any(|strip| strip.is_fullscreen())
+                {
+                    return None;
+                }
+                // Windows paneru does not manage, e.g. of ignored apps, keep the space too.
+                let empty = space_strips.iter().all(|strip| strip.len() == 0)
+                    && window_manager
+                        .windows_in_workspace(space_id)
+                        .is_ok_and(|ids| {
+                            ids.into_iter()
+                                .all(|id| window_manager.is_desktop_element(id))
+                        });
+                seen.insert(space_id);
+                if !empty {
+                    empty_since.remove(&space_id);
+                    return Some((space_id, None));
+                }
+                Some((space_id, Some(*empty_since.entry(space_id).or_insert(now))))
+            })
+            .collect::<Vec<_>>();
+
+        let Some(space_id) = space_to_close(
+            &spaces,
+            current,
+            now,
+            config.empty_space_grace(),
+            config.keep_empty_spaces(),
+        ) else {
+            continue;
+        };
+        info!("closing empty space {space_id} on display {}", display.id());
+        if let Err(err) = window_manager.remove_space(display.id(), space_id) {
+            warn!("unable to close empty space {space_id}: {err}");
+            // Try again once another grace period passed.
+            empty_since.insert(space_id, now);
+        } else {
+            empty_since.remove(&space_id);
+        }
+    }
+    empty_since.retain(|space_id, _| seen.contains(space_id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRACE: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn test_space_to_close() {
+        let now = Duration::from_secs(60);
+        let spaces = [
+            (1, None),
+            (2, Some(Duration::from_secs(5))),
+            (3, Some(Duration::from_secs(55))),
+            (4, Some(Duration::from_secs(10))),
+        ];
+        // The rightmost space past its grace period goes first.
+        assert_eq!(space_to_close(&spaces, 1, now, GRACE, 1), Some(4));
+        // The current space stays, as do spaces which only just became empty.
+        assert_eq!(space_to_close(&spaces, 4, now, GRACE, 1), Some(2));
+        assert_eq!(space_to_close(&spaces, 1, now, GRACE, 3), None);
+        assert_eq!(
+            space_to_close(&spaces[..2], 2, now, GRACE, 0),
+            None,
+            "the current space is never closed"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use bevy::app::AppExit;
@@ -15,15 +16,24 @@ use objc2_core_graphics::CGDirectDisplayID;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 
+use crate::commands::InsertionPlacement;
+use crate::ecs::focus::now_millis;
 use crate::ecs::layout::{Column, LayoutStrip, StackItem};
 use crate::ecs::params::Windows;
-use crate::ecs::{ActiveDisplayMarker, ActiveWorkspaceMarker};
+use crate::ecs::{ActiveDisplayMarker, ActiveWorkspaceMarker, InsertionPoint};
 use crate::manager::Application;
 use crate::manager::Display;
 use crate::platform::{Pid, ProcessSerialNumber, WinID, WorkspaceId};
 
 pub const STATE_FILE_NAME: &str = "state.json";
 const SUPPORTED_STATE_VERSION: u32 = 2;
+/// Environment variable pointing the re-executed daemon to the state handed
+/// over by its predecessor.
+pub const HANDOFF_ENV: &str = "PANERU_HANDOFF_STATE";
+
+/// Set once the state was handed over and the daemon should re-execute itself
+/// after the app exits.
+static HANDOFF_REQUESTED: AtomicBool = AtomicBool::new(false);
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Resource)]
 pub struct PaneruState {
@@ -99,6 +109,9 @@ pub enum StateQueryKind {
     State,
     VirtualWorkspaces,
     Active,
+    Strip,
+    Keybindings,
+    Windows,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -138,6 +151,70 @@ pub struct PaneruWindowState {
     pub floating: bool,
 }
 
+/// How a window was used since paneru first saw it, for `query windows`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PaneruWindowStats {
+    pub window_id: WinID,
+    pub bundle_id: String,
+    pub app_name: String,
+    pub title: String,
+    pub floating: bool,
+    pub created_at: u64,
+    pub last_focused_at: Option<u64>,
+    pub focus_count: u32,
+    pub focused_secs: u64,
+}
+
+/// The columns of the active layout strip, ordered from left to right.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct PaneruStripState {
+    pub display_id: Option<CGDirectDisplayID>,
+    pub native_workspace_id: Option<WorkspaceId>,
+    pub virtual_workspace_number: Option<u32>,
+    pub columns: Vec<PaneruColumnState>,
+    /// Where the next spawned window goes, if `window_set_insertion_point` is pending.
+    pub insertion_point: Option<PaneruInsertionPoint>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PaneruInsertionPoint {
+    pub window_id: WinID,
+    pub placement: InsertionPlacement,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PaneruColumnKind {
+    Single,
+    Stack,
+    Tabs,
+    Fullscreen,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PaneruColumnState {
+    pub index: usize,
+    pub kind: PaneruColumnKind,
+    pub focused: bool,
+    /// Windows from the top of the column to its bottom.
+    pub windows: Vec<PaneruStripWindowState>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PaneruStripWindowState {
+    pub window_id: WinID,
+    pub bundle_id: String,
+    pub bundle_path: Option<String>,
+    pub app_name: String,
+    pub title: String,
+    pub focused: bool,
+    /// Position of the window's stack item within its column.
+    pub stack_index: usize,
+    /// Number of tabs in the window's native tab bar, if it has one.
+    pub native_tabs: Option<usize>,
+    pub frame: SavedRect,
+}
+
 impl From<IRect> for SavedRect {
     fn from(rect: IRect) -> Self {
         Self {
@@ -339,6 +416,38 @@ impl PaneruState {
             .expect("XDG state directory should be available")
     }
 
+    /// Returns the path of the temporary file used for handing the state over
+    /// to a re-executed daemon.
+    pub fn handoff_file_path() -> PathBuf {
+        std::env::temp_dir().join(format!("paneru-handoff-{}.json", std::process::id()))
+    }
+
+    /// Saves the state for the next daemon and marks it for re-execution once
+    /// the app exits.
+    pub fn hand_off(&self) -> Result<PathBuf, std::io::Error> {
+        let path = Self::handoff_file_path();
+        self.save_to_file(&path)?;
+        HANDOFF_REQUESTED.store(true, Ordering::Release);
+        Ok(path)
+    }
+
+    /// Loads the state from `path` and removes the file, so that it is only
+    /// consumed once.
+    pub fn take_from_file(path: &Path) -> Option<Self> {
+        let state = Self::load_from_file(path);
+        _ = fs::remove_file(path)
+            .inspect_err(|err| warn!("removing handoff state {}: {err}", path.display()));
+        state
+    }
+
+    /// Takes over the state handed over by the previous daemon, if this
+    /// process was started by a `restart-daemon` command.
+    pub fn take_handoff() -> Option<Self> {
+        let path = PathBuf::from(std::env::var_os(HANDOFF_ENV)?);
+        info!("taking over handoff state from {}", path.display());
+        Self::take_from_file(&path)
+    }
+
     #[cfg(test)]
     pub fn find_match(
         &self,
@@ -534,6 +643,124 @@ impl PaneruQueryState {
             StateQueryKind::State => serde_json::to_string(self),
             StateQueryKind::VirtualWorkspaces => serde_json::to_string(&self.virtual_workspaces),
             StateQueryKind::Active => serde_json::to_string(&self.active),
+            StateQueryKind::Strip | StateQueryKind::Keybindings | StateQueryKind::Windows => Err(
+                serde::ser::Error::custom(format!("{kind:?} is not part of the state document")),
+            ),
+        }
+    }
+}
+
+impl PaneruWindowStats {
+    /// Lists every window known to paneru, oldest first.
+    pub fn extract(windows: &Windows, apps: &Query<&Application>) -> Vec<Self> {
+        let now = now_millis();
+        let mut stats = windows
+            .iter()
+            .filter_map(|(window, entity)| {
+                let stats = windows.focus_stats(entity)?;
+                let (_, _, unmanaged) = windows.get_managed(entity)?;
+                let app = windows
+                    .find_parent(window.id())
+                    .and_then(|(_, _, app_entity)| apps.get(app_entity).ok());
+                Some(Self {
+                    window_id: window.id(),
+                    bundle_id: app.and_then(|app| app.bundle_id()).unwrap_or_default(),
+                    app_name: app.map(|app| app.name().to_string()).unwrap_or_default(),
+                    title: window.title().unwrap_or_default(),
+                    floating: unmanaged.is_some(),
+                    created_at: stats.created_at(),
+                    last_focused_at: stats.last_focused_at(),
+                    focus_count: stats.focus_count(),
+                    focused_secs: stats.focused_secs(now),
+                })
+            })
+            .collect::<Vec<_>>();
+        stats.sort_by_key(|window| (window.created_at, window.window_id));
+        stats
+    }
+}
+
+impl PaneruStripState {
+    #[allow(clippy::type_complexity)]
+    pub fn extract(
+        workspaces: &Query<(&ChildOf, &LayoutStrip, Has<ActiveWorkspaceMarker>)>,
+        displays: &Query<(&Display, Entity, Has<ActiveDisplayMarker>)>,
+        windows: &Windows,
+        apps: &Query<&Application>,
+        insertion_point: Option<&InsertionPoint>,
+    ) -> Self {
+        let Some((display_id, display_entity)) = displays
+            .iter()
+            .find(|(_, _, active)| *active)
+            .map(|(display, entity, _)| (display.id(), entity))
+        else {
+            return Self::default();
+        };
+        let Some(strip) = workspaces
+            .iter()
+            .find(|(child, _, active)| *active && child.parent() == display_entity)
+            .map(|(_, strip, _)| strip)
+        else {
+            return Self {
+                display_id: Some(display_id),
+                ..Self::default()
+            };
+        };
+
+        let focused_entity = windows.focused().map(|(_, entity)| entity);
+        let columns = strip
+            .columns()
+            .enumerate()
+            .map(|(index, column)| {
+                let kind = match column {
+                    Column::Single(_) => PaneruColumnKind::Single,
+                    Column::Stack(_) => PaneruColumnKind::Stack,
+                    Column::Tabs(_) => PaneruColumnKind::Tabs,
+                    Column::Fullscren(_) => PaneruColumnKind::Fullscreen,
+                };
+                let column_windows = column
+                    .window_iter()
+                    .filter_map(|entity| {
+                        let window = windows.get(entity)?;
+                        let (_, _, app_entity) = windows.find_parent(window.id())?;
+                        let app = apps.get(app_entity).ok()?;
+                        Some(PaneruStripWindowState {
+                            window_id: window.id(),
+                            bundle_id: app.bundle_id().unwrap_or_default(),
+                            bundle_path: app.bundle_path(),
+                            app_name: app.name().to_string(),
+                            title: window.title().unwrap_or_default(),
+                            focused: focused_entity == Some(entity),
+                            stack_index: column.position_of(entity).unwrap_or_default(),
+                            native_tabs: windows.native_tabs(entity),
+                            frame: windows.frame(entity).unwrap_or_default().into(),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                PaneruColumnState {
+                    index,
+                    kind,
+                    focused: column_windows.iter().any(|window| window.focused),
+                    windows: column_windows,
+                }
+            })
+            .collect();
+
+        Self {
+            display_id: Some(display_id),
+            native_workspace_id: Some(strip.id()),
+            virtual_workspace_number: Some(strip.virtual_index + 1),
+            columns,
+            insertion_point: insertion_point
+                .filter(|point| strip.contains(point.target))
+                .and_then(|point| {
+                    windows
+                        .get(point.target)
+                        .map(|window| PaneruInsertionPoint {
+                            window_id: window.id(),
+                            placement: point.placement,
+                        })
+                }),
         }
     }
 }
@@ -545,6 +772,11 @@ fn now_timestamp() -> u64 {
         .as_secs()
 }
 
+/// Returns true if the daemon handed its state over and is about to re-execute.
+pub fn handoff_requested() -> bool {
+    HANDOFF_REQUESTED.load(Ordering::Acquire)
+}
+
 #[allow(clippy::needless_pass_by_value)]
 pub fn periodic_state_save(
     workspaces: Query<(Option<&ChildOf>, &LayoutStrip, Has<ActiveWorkspaceMarker>)>,
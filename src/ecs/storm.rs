@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use tracing::{debug, warn};
+
+use crate::events::Event;
+use crate::platform::WinID;
+
+/// Frame notifications of a window within `STORM_PERIOD` above which it is muted.
+const STORM_THRESHOLD: u32 = 200;
+const STORM_PERIOD: Duration = Duration::from_secs(1);
+/// How long the frame notifications of a flooding window are ignored.
+const STORM_MUTE: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+struct WindowRate {
+    since: Instant,
+    count: u32,
+    muted_until: Option<Instant>,
+}
+
+/// Guards against apps flooding the moved and resized notifications of their
+/// windows, e.g. while they animate them on their own. Repeated notifications of
+/// a window within a frame are collapsed into one, and a window exceeding
+/// `STORM_THRESHOLD` notifications in a period is muted for a while.
+#[derive(Debug, Default)]
+pub struct EventStormGuard {
+    windows: HashMap<WinID, WindowRate>,
+}
+
+fn frame_event(event: &Event) -> Option<(WinID, bool)> {
+    match event {
+        Event::WindowMoved { window_id } => Some((*window_id, false)),
+        Event::WindowResized { window_id } => Some((*window_id, true)),
+        _ => None,
+    }
+}
+
+impl EventStormGuard {
+    /// Filters the events received during a frame. Only the last move and the last
+    /// resize of each window are kept, and the frame notifications of muted windows
+    /// are dropped. Once a window is unmuted, its frame is refreshed by a move and a
+    /// resize notification appended to the events.
+    pub fn filter(&mut self, events: &mut Vec<Event>, now: Instant) {
+        for event in events.iter() {
+            let Some((window_id, _)) = frame_event(event) else {
+                continue;
+            };
+            let rate = self.windows.entry(window_id).or_insert(WindowRate {
+                since: now,
+                count: 0,
+                muted_until: None,
+            });
+            if now.duration_since(rate.since) > STORM_PERIOD {
+                rate.since = now;
+                rate.count = 0;
+            }
+            rate.count += 1;
+            if rate.count > STORM_THRESHOLD && rate.muted_until.is_none() {
+                warn!(
+                    "window {window_id} sent more than {STORM_THRESHOLD} frame notifications, \
+                     muting it for {}s.",
+                    STORM_MUTE.as_secs()
+                );
+                rate.muted_until = Some(now + STORM_MUTE);
+            }
+        }
+
+        let mut last = HashMap::new();
+        for (index, event) in events.iter().enumerate() {
+            if let Some(key) = frame_event(event) {
+                last.insert(key, index);
+            }
+        }
+        let muted = |window_id: WinID| {
+            self.windows
+                .get(&window_id)
+                .is_some_and(|rate| rate.muted_until.is_some_and(|until| now < until))
+        };
+        let mut index = 0;
+        events.retain(|event| {
+            let keep = frame_event(event)
+                .is_none_or(|key| last.get(&key) == Some(&index) && !muted(key.0));
+            index += 1;
+            keep
+        });
+
+        for (window_id, rate) in &mut self.windows {
+            if rate.muted_until.is_some_and(|until| now >= until) {
+                debug!("unmuting window {window_id}.");
+                rate.muted_until = None;
+                rate.since = now;
+                rate.count = 0;
+                events.push(Event::WindowMoved {
+                    window_id: *window_id,
+                });
+                events.push(Event::WindowResized {
+                    window_id: *window_id,
+                });
+            }
+        }
+        // Forget the quiet windows.
+        self.windows.retain(|_, rate| {
+            rate.muted_until.is_some() || now.duration_since(rate.since) <= STORM_PERIOD
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(events: &[Event]) -> Vec<(WinID, bool)> {
+        events.iter().filter_map(frame_event).collect()
+    }
+
+    #[test]
+    fn test_bursts_are_collapsed() {
+        let mut guard = EventStormGuard::default();
+        let mut events = vec![
+            Event::WindowMoved { window_id: 1 },
+            Event::WindowResized { window_id: 1 },
+            Event::WindowMoved { window_id: 2 },
+            Event::WindowMoved { window_id: 1 },
+            Event::WindowResized { window_id: 1 },
+        ];
+        guard.filter(&mut events, Instant::now());
+        assert_eq!(ids(&events), vec![(2, false), (1, false), (1, true)]);
+    }
+
+    #[test]
+    fn test_flooding_window_is_muted() {
+        let mut guard = EventStormGuard::default();
+        let start = Instant::now();
+        let mut events = (0..=STORM_THRESHOLD)
+            .map(|_| Event::WindowMoved { window_id: 1 })
+            .chain([Event::WindowMoved { window_id: 2 }])
+            .collect::<Vec<_>>();
+        guard.filter(&mut events, start);
+        assert_eq!(ids(&events), vec![(2, false)]);
+
+        let mut events = vec![Event::WindowResized { window_id: 1 }];
+        guard.filter(&mut events, start + Duration::from_millis(500));
+        assert!(events.is_empty());
+
+        // Once unmuted, the window's frame is read again.
+        let mut events = vec![];
+        guard.filter(&mut events, start + STORM_MUTE);
+        assert_eq!(ids(&events), vec![(1, false), (1, true)]);
+    }
+}
@@ -1,48 +1,61 @@
 use bevy::app::AppExit;
 use bevy::ecs::change_detection::{DetectChanges, DetectChangesMut};
-use bevy::ecs::entity::Entity;
+use bevy::ecs::entity::{Entity, EntityHashMap};
 use bevy::ecs::hierarchy::{ChildOf, Children};
 use bevy::ecs::message::{MessageReader, MessageWriter};
 use bevy::ecs::query::{Added, Changed, Has, Or, With, Without};
 use bevy::ecs::system::{
     Commands, Local, NonSend, NonSendMut, Populated, Query, Res, ResMut, Single,
 };
-use bevy::math::IRect;
+use bevy::ecs::world::EntityWorldMut;
+use bevy::math::{IRect, IVec2};
 use bevy::tasks::AsyncComputeTaskPool;
 use bevy::tasks::futures_lite::future;
 use bevy::time::Time;
+use objc2_core_foundation::CFRetained;
+use objc2_core_graphics::CGDirectDisplayID;
 use objc2_foundation::NSPoint;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::pin::Pin;
 use std::sync::mpsc::{Receiver, RecvTimeoutError};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{Level, debug, error, info, instrument, trace, warn};
 
 use super::{
-    ActiveDisplayMarker, BProcess, ExistingMarker, FreshMarker, RepositionMarker, ResizeMarker,
-    RetryFrontSwitch, SpawnWindowTrigger, Timeout, VerifyWindowPosition,
+    AccessibilityLost, ActiveDisplayMarker, BProcess, DiscoverWindows, ExistingMarker, FreshMarker,
+    NativeTabs, ReadyBackoff, RecountNativeTabs, RepositionMarker, ResizeMarker, RetryFrontSwitch,
+    SpawnWindowTrigger, Timeout, VerifyWindowPosition,
 };
+use crate::commands::Command;
 
 use crate::config::{Config, decorations::BorderRadiusOption};
-use crate::ecs::layout::LayoutStrip;
+use crate::ecs::layout::{LayoutStrip, center_over};
 use crate::ecs::params::{ActiveDisplay, Windows};
+use crate::ecs::presentation::PresentationMode;
+use crate::ecs::state::handoff_requested;
+use crate::ecs::storm::EventStormGuard;
 use crate::ecs::{
-    ActiveWorkspaceMarker, Bounds, BruteforceWindows, FlashMessage, Initializing, LowPowerMode,
-    MissionControlActive, Position, ReadDisplayProperties, RestoreWindowState, Scrolling,
-    SendMessageTrigger, SpawnCommandsExt, Unmanaged, WidthRatio, WindowProperties,
+    ActiveWorkspaceMarker, Bounds, BruteforceWindows, DockPosition, FlashMessage, Initializing,
+    LayoutLocked, LowPowerMode, MissionControlActive, OriginalFrame, Position,
+    ReadDisplayProperties, RestoreWindowState, Scrolling, SendMessageTrigger, SpawnCommandsExt,
+    Unmanaged, WidthRatio, WindowProperties,
 };
 use crate::events::Event;
 use crate::manager::{
-    Application, Display, Process, Window, WindowManager, WindowOS, bruteforce_windows,
+    Application, Display, Process, Window, WindowApi, WindowManager, WindowOS, bruteforce_windows,
 };
 use crate::overlay::{FlashMessageManager, OverlayManager};
 use crate::platform::{PlatformCallbacks, WinID};
+use crate::util::AXUIWrapper;
 
 const ANIAMTE_SNAP_THRESHOLD: f32 = 5.0;
 const LOOP_MAX_TIMEOUT_FRAME_ACTIVE_MS: u32 = 16;
 const LOOP_MAX_TIMEOUT_LOWPOWER_MS: u32 = 500;
 const LOOP_MAX_TIMEOUT_MS: u32 = 50;
 const LOOP_TIMEOUT_STEP: u32 = 1;
+/// Height of the top of a window holding its zoom button. Toolbars unified
+/// with the title bar push the button further down.
+const TITLE_BAR_HEIGHT: i32 = 52;
 
 /// Gathers all present displays and spawns them as entities in the Bevy world.
 /// The currently active display (identified by `window_manager.active_display_id()`) is marked with `ActiveDisplayMarker`.
@@ -87,16 +100,19 @@ pub fn gather_displays(window_manager: Res<WindowManager>, mut commands: Command
 ///
 /// * `window_manager` - The `WindowManager` resource for creating new application instances.
 /// * `process_query` - A query for existing `BProcess` entities marked with `ExistingMarker`.
+/// * `config` - The configuration, giving the accessibility timeout of each application.
 /// * `commands` - Bevy commands to spawn entities and manage components.
 #[allow(clippy::needless_pass_by_value)]
 #[instrument(level = Level::DEBUG, skip_all)]
 pub(crate) fn add_existing_process(
     window_manager: Res<WindowManager>,
     processes: Populated<(Entity, &BProcess), With<ExistingMarker>>,
+    config: Res<Config>,
     mut commands: Commands,
 ) {
     for (entity, process) in processes {
-        let Ok(app) = window_manager.new_application(&*process.0) else {
+        let ax_timeout = config.ax_timeout(process.bundle_id().as_deref());
+        let Ok(app) = window_manager.new_application(&*process.0, ax_timeout) else {
             error!("creating aplication from process '{}'", process.name());
             return;
         };
@@ -107,22 +123,25 @@ pub(crate) fn add_existing_process(
     }
 }
 
-/// Adds an existing application to the window manager. This is used during initial setup.
-/// It observes the application, adds its windows to the manager, and then triggers `SpawnWindowTrigger` events for newly found windows.
-/// The `ExistingMarker` is removed from the application entity after processing.
+/// Adds existing applications to the window manager. This is used during initial setup.
+/// It observes the applications and hands each of them to a `DiscoverWindows` task on the
+/// `AsyncComputeTaskPool`, which finds their windows in the background. `finish_setup`
+/// merges the windows of every app as soon as its task is done, so the windows of the
+/// first apps are managed while the others are still being discovered.
+/// The `ExistingMarker` is removed from the application entities after processing.
 ///
 /// # Arguments
 ///
 /// * `window_manager` - The `WindowManager` resource for interacting with window management logic.
-/// * `displays` - A query for all `Display` entities, used to gather all existing space IDs.
-/// * `app_query` - A query for existing `Application` entities marked with `ExistingMarker`.
+/// * `workspaces` - A query for all `LayoutStrip` entities, used to gather all existing space IDs.
+/// * `fresh_apps` - A query for existing `Application` entities marked with `ExistingMarker`.
 /// * `commands` - Bevy commands to spawn entities and manage components.
 #[allow(clippy::needless_pass_by_value)]
 #[instrument(level = Level::DEBUG, skip_all)]
 pub(crate) fn add_existing_application(
     window_manager: Res<WindowManager>,
     workspaces: Query<&LayoutStrip>,
-    fresh_apps: Populated<(&mut Application, Entity), With<ExistingMarker>>,
+    mut fresh_apps: Populated<(&mut Application, Entity), With<ExistingMarker>>,
     config: Res<Config>,
     mut commands: Commands,
 ) {
@@ -130,26 +149,59 @@ pub(crate) fn add_existing_application(
         .into_iter()
         .map(LayoutStrip::id)
         .collect::<Vec<_>>();
-    let thread_pool = AsyncComputeTaskPool::get();
 
-    for (mut app, entity) in fresh_apps {
-        let mut offscreen_windows = vec![];
-
-        if app.observe().is_ok_and(|result| result)
-            && let Ok((found_windows, offscreen)) = window_manager
-                .find_existing_application_windows(&mut app, &spaces, &config)
-                .inspect_err(|err| warn!("{err}"))
-        {
-            offscreen_windows.extend(offscreen);
-            commands.trigger(SpawnWindowTrigger(found_windows));
+    for (mut app, entity) in fresh_apps.iter_mut() {
+        let Ok(mut entity_commands) = commands.get_entity(entity) else {
+            continue;
+        };
+        entity_commands.try_remove::<ExistingMarker>();
+        // Observers are registered from the main thread, only the discovery runs in the background.
+        if !app.observe().is_ok_and(|result| result) {
+            continue;
         }
+
+        // Every window is an accessibility round trip, which adds up with many running apps.
+        let (window_manager, spaces, config) =
+            (window_manager.0.clone(), spaces.clone(), config.clone());
+        entity_commands.queue(move |mut entity: EntityWorldMut| {
+            let Some(mut app) = entity.take::<Application>() else {
+                return;
+            };
+            let task = AsyncComputeTaskPool::get().spawn(async move {
+                let found =
+                    window_manager.find_existing_application_windows(&mut app, &spaces, &config);
+                (app, found)
+            });
+            entity.insert(DiscoverWindows(task));
+        });
+    }
+}
+
+/// Returns the applications of finished `DiscoverWindows` tasks to their entities, and
+/// spawns the windows they found. Windows which are not reachable over accessibility yet
+/// are handed to a `BruteforceWindows` task.
+fn merge_discovered_windows(
+    discoveries: &mut Query<(Entity, &mut DiscoverWindows)>,
+    config: &Config,
+    commands: &mut Commands,
+) {
+    let thread_pool = AsyncComputeTaskPool::get();
+    for (entity, mut job) in discoveries.iter_mut() {
+        let Some((app, found)) = future::block_on(future::poll_once(&mut job.0)) else {
+            continue;
+        };
+        let (pid, bundle_id) = (app.pid(), app.bundle_id());
         if let Ok(mut entity_commands) = commands.get_entity(entity) {
-            entity_commands.try_remove::<ExistingMarker>();
+            entity_commands
+                .try_remove::<DiscoverWindows>()
+                .try_insert(app);
         }
+        let Ok((found_windows, offscreen_windows)) = found.inspect_err(|err| warn!("{err}")) else {
+            continue;
+        };
+        commands.trigger(SpawnWindowTrigger(found_windows));
 
         if !offscreen_windows.is_empty() {
-            let pid = app.pid();
-            let bundle_id = app.bundle_id();
             let config = config.clone();
             let bruteforce_task = thread_pool.spawn(async move {
                 bruteforce_windows(pid, bundle_id.as_deref(), offscreen_windows, &config)
@@ -174,27 +226,28 @@ pub(crate) fn add_existing_application(
 pub(crate) fn finish_setup(
     process_query: Query<Entity, With<ExistingMarker>>,
     windows: Windows,
+    mut discoveries: Query<(Entity, &mut DiscoverWindows)>,
     mut bruteforce_tasks: Query<(Entity, &mut BruteforceWindows)>,
     mut workspaces: Query<(&mut LayoutStrip, Has<ActiveWorkspaceMarker>, &ChildOf)>,
     window_manager: Res<WindowManager>,
+    config: Res<Config>,
     mut commands: Commands,
 ) {
-    if !process_query.is_empty() {
-        // The other two add_* functions are still running..
-        return;
-    }
+    merge_discovered_windows(&mut discoveries, &config, &mut commands);
 
-    // Reap the bruteforced windows.
-    if !bruteforce_tasks.is_empty() {
-        for (entity, mut job) in &mut bruteforce_tasks {
-            if let Some(found_windows) = future::block_on(future::poll_once(&mut job.0)) {
-                commands.trigger(SpawnWindowTrigger(found_windows));
-                if let Ok(mut entity_commands) = commands.get_entity(entity) {
-                    entity_commands.try_despawn();
-                }
+    // Reap the bruteforced windows as they come in, while other apps are still added.
+    for (entity, mut job) in &mut bruteforce_tasks {
+        if let Some(found_windows) = future::block_on(future::poll_once(&mut job.0)) {
+            commands.trigger(SpawnWindowTrigger(found_windows));
+            if let Ok(mut entity_commands) = commands.get_entity(entity) {
+                entity_commands.try_despawn();
             }
         }
-        // Wait for the next tick to finish initialization.
+    }
+
+    if !process_query.is_empty() || !discoveries.is_empty() || !bruteforce_tasks.is_empty() {
+        // The other two add_* functions are still running, or windows are being
+        // discovered or bruteforced. Wait for the next tick to finish initialization.
         return;
     }
 
@@ -205,6 +258,7 @@ pub(crate) fn finish_setup(
 
     for (mut strip, active_strip, _) in &mut workspaces {
         debug!("space {}: before refresh {strip:?}", strip.id());
+        let space_id = strip.id();
         let workspace_windows = window_manager
             .windows_in_workspace(strip.id())
             .inspect_err(|err| {
@@ -218,7 +272,7 @@ pub(crate) fn finish_setup(
                     .filter(|(window, entity)| {
                         if window.is_minimized() {
                             if let Ok(mut entity_commands) = commands.get_entity(*entity) {
-                                entity_commands.try_insert(Unmanaged::Minimized);
+                                entity_commands.try_insert(Unmanaged::Minimized(Some(space_id)));
                             }
                             false
                         } else {
@@ -256,7 +310,8 @@ pub(crate) fn finish_setup(
 /// Handles the event when a new application is launched. It creates a `Process` and `Application` object,
 /// observes the application for events, and adds its windows to the manager.
 /// This system processes `BProcess` entities marked with `FreshMarker`.
-/// If the process is not yet ready, it continues observing it. If ready, it attempts to create and observe an `Application`.
+/// If the process is not yet ready, it continues observing it and checks again following the
+/// `ReadyBackoff` schedule. If ready, it attempts to create and observe an `Application`.
 /// A `Timeout` is added to the application if it takes too long to become observable.
 ///
 /// # Arguments
@@ -267,19 +322,26 @@ pub(crate) fn finish_setup(
 #[allow(clippy::needless_pass_by_value)]
 pub(super) fn add_launched_process(
     window_manager: Res<WindowManager>,
-    fresh_processes: Populated<(Entity, &mut BProcess, Has<Children>), With<FreshMarker>>,
+    fresh_processes: Populated<
+        (Entity, &mut BProcess, &mut ReadyBackoff, Has<Children>),
+        With<FreshMarker>,
+    >,
     config: Res<Config>,
+    clock: Res<Time>,
     mut commands: Commands,
 ) {
     const APP_OBSERVABLE_TIMEOUT_SEC: u64 = 5;
     let mut already_seen = HashSet::new();
 
-    for (entity, mut process, children) in fresh_processes {
+    for (entity, mut process, mut backoff, children) in fresh_processes {
         let process = &mut *process.0;
 
         if !already_seen.insert(process.psn()) {
             continue;
         }
+        if !backoff.tick(clock.delta()) {
+            continue;
+        }
 
         if config.should_force_manage_process(process) {
             debug!(
@@ -290,6 +352,16 @@ pub(super) fn add_launched_process(
         }
 
         if !process.ready() {
+            if !backoff.backoff() {
+                debug!(
+                    "'{}' not ready after {} checks, waiting for notifications.",
+                    process.name(),
+                    backoff.attempts()
+                );
+                if let Ok(mut entity_commands) = commands.get_entity(entity) {
+                    entity_commands.try_remove::<ReadyBackoff>();
+                }
+            }
             continue;
         }
 
@@ -301,7 +373,8 @@ pub(super) fn add_launched_process(
             continue;
         }
 
-        let Ok(mut app) = window_manager.new_application(process) else {
+        let ax_timeout = config.ax_timeout(process.bundle_id().as_deref());
+        let Ok(mut app) = window_manager.new_application(process, ax_timeout) else {
             error!("creating aplication from process '{}'", process.name());
             return;
         };
@@ -460,10 +533,94 @@ pub(super) fn retry_front_switch(
     }
 }
 
+/// Animation speeds of the displays which have their own in `display_animations`.
+/// Empty when no display has its own settings.
+#[derive(Default)]
+struct DisplaySpeeds {
+    /// The displays with their bounds and speeds.
+    displays: Vec<(Entity, IRect, f64)>,
+    /// The display of the strip of every tiled window, which also holds for the
+    /// windows parked off screen.
+    windows: EntityHashMap<Entity>,
+}
+
+impl DisplaySpeeds {
+    /// Looks up the settings of the displays. Their UUIDs are cached in `uuids`,
+    /// as creating them is a round trip to the window server.
+    fn new(
+        displays: &Query<(Entity, &Display)>,
+        strips: &Query<(&LayoutStrip, &ChildOf)>,
+        config: &Config,
+        uuids: &mut HashMap<CGDirectDisplayID, Option<String>>,
+    ) -> Self {
+        if !config.has_display_animations() {
+            return Self::default();
+        }
+        let displays = displays
+            .iter()
+            .filter_map(|(entity, display)| {
+                let uuid = uuids
+                    .entry(display.id())
+                    .or_insert_with(|| {
+                        Display::uuid_from_id(display.id())
+                            .ok()
+                            .map(|uuid| uuid.to_string())
+                    })
+                    .as_deref()?;
+                Some((
+                    entity,
+                    display.bounds(),
+                    config.display_animation_speed(uuid),
+                ))
+            })
+            .collect();
+        let windows = strips
+            .iter()
+            .flat_map(|(strip, child_of)| {
+                strip
+                    .all_windows()
+                    .into_iter()
+                    .map(move |entity| (entity, child_of.parent()))
+            })
+            .collect();
+        Self { displays, windows }
+    }
+
+    /// The speed of an entity: the one of its display when it is a strip, the one
+    /// of the display of its strip when it is a tiled window, and otherwise the
+    /// one of the display under `center`.
+    fn rate(&self, entity: Entity, parent: Option<Entity>, center: Option<IVec2>) -> Option<f64> {
+        let of_display = |display: Entity| {
+            self.displays
+                .iter()
+                .find(|(entity, _, _)| *entity == display)
+        };
+        parent
+            .and_then(of_display)
+            .or_else(|| self.windows.get(&entity).copied().and_then(of_display))
+            .or_else(|| {
+                let center = center?;
+                self.displays
+                    .iter()
+                    .find(|(_, bounds, _)| bounds.contains(center))
+            })
+            .map(|(_, _, rate)| *rate)
+    }
+}
+
+/// Fraction of the remaining distance an animation at `rate` covers within `delta`.
+fn animation_step(rate: f64, delta: f64) -> f32 {
+    // Frame-rate-independent exponential smoothing (ease-out).
+    // `animation_speed` is the decay rate (per second); higher = snappier.
+    // t = 1 - e^(-rate*dt) is the fraction of remaining distance consumed this frame.
+    (1.0 - (-rate * delta).exp()).clamp(0.0, 1.0) as f32
+}
+
 /// Animates window movement.
 /// This is a Bevy system that runs on `Update`. It smoothly moves windows to their target
 /// positions, as indicated by the `RepositionMarker` component.
-/// Animation speed is controlled by the `animation_speed` in the `Config`.
+/// Animation speed is controlled by the `animation_speed` in the `Config`, or the
+/// `display_animations` entry of the display the entity moves to.
 /// When a window reaches its target position, the `RepositionMarker` is removed.
 ///
 /// # Arguments
@@ -473,23 +630,44 @@ pub(super) fn retry_front_switch(
 /// * `time` - The Bevy `Time` resource for calculating delta time.
 /// * `config` - The `Config` resource, used for animation speed.
 /// * `commands` - Bevy commands to remove the `RepositionMarker` when animation is complete.
-#[allow(clippy::needless_pass_by_value)]
+#[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
 #[instrument(level = Level::TRACE, skip_all)]
 pub(super) fn animate_entities(
-    animate: Populated<(&mut Position, Entity, &RepositionMarker)>,
+    animate: Populated<(
+        &mut Position,
+        Entity,
+        &RepositionMarker,
+        Option<&Bounds>,
+        Option<&ChildOf>,
+    )>,
+    displays: Query<(Entity, &Display)>,
+    strips: Query<(&LayoutStrip, &ChildOf)>,
     time: Res<Time>,
     config: Res<Config>,
+    low_power_mode: Option<Res<LowPowerMode>>,
+    mut uuids: Local<HashMap<CGDirectDisplayID, Option<String>>>,
     mut commands: Commands,
 ) {
-    // Frame-rate-independent exponential smoothing (ease-out).
-    // `animation_speed` is the decay rate (per second); higher = snappier.
-    // t = 1 - e^(-rate*dt) is the fraction of remaining distance consumed this frame.
     let rate = config.animation_speed();
-    let t = (1.0 - (-rate * time.delta_secs_f64()).exp()).clamp(0.0, 1.0) as f32;
+    let display_speeds = DisplaySpeeds::new(&displays, &strips, &config, &mut uuids);
+    // Saving power, windows jump to their place in a single frame.
+    let instant = low_power_mode.is_some_and(|low_power| low_power.active(&config));
+    crate::metrics::record_animation_frame(time.delta());
+
+    animate.into_iter().for_each(
+        |(mut position, entity, RepositionMarker(origin), bounds, child_of)| {
+            // Strips belong to their display, tiled windows to the display of their
+            // strip, and the others to the display they move onto.
+            let center = *origin + bounds.map_or(IVec2::ZERO, |bounds| bounds.0 / 2);
+            let rate = display_speeds
+                .rate(entity, child_of.map(ChildOf::parent), Some(center))
+                .unwrap_or(rate);
+            let t = if instant {
+                1.0
+            } else {
+                animation_step(rate, time.delta_secs_f64())
+            };
 
-    animate
-        .into_iter()
-        .for_each(|(mut position, entity, RepositionMarker(origin))| {
             let target = origin.as_vec2();
             let current = position.0.as_vec2();
             let lerped = current.lerp(target, t);
@@ -511,7 +689,8 @@ pub(super) fn animate_entities(
             if finished && let Ok(mut entity_commands) = commands.get_entity(entity) {
                 entity_commands.try_remove::<RepositionMarker>();
             }
-        });
+        },
+    );
 }
 
 /// Animates window resizing.
@@ -524,21 +703,34 @@ pub(super) fn animate_entities(
 /// * `windows` - A `Populated` query for `(&mut Window, Entity, &ResizeMarker)` components.
 /// * `active_display` - An `ActiveDisplay` system parameter providing immutable access to the active display.
 /// * `commands` - Bevy commands to remove the `ResizeMarker` when resizing is complete.
-#[allow(clippy::needless_pass_by_value)]
+#[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
 #[instrument(level = Level::TRACE, skip_all)]
 pub(super) fn animate_resize_entities(
-    animate: Populated<(&mut Bounds, Entity, &ResizeMarker)>,
+    animate: Populated<(&mut Bounds, Entity, &ResizeMarker, Option<&Position>)>,
+    displays: Query<(Entity, &Display)>,
+    strips: Query<(&LayoutStrip, &ChildOf)>,
     time: Res<Time>,
     config: Res<Config>,
+    low_power_mode: Option<Res<LowPowerMode>>,
+    mut uuids: Local<HashMap<CGDirectDisplayID, Option<String>>>,
     mut commands: Commands,
 ) {
     // Matches animate_entities: exponential ease-out, frame-rate independent.
     let rate = config.animation_speed();
-    let t = (1.0 - (-rate * time.delta_secs_f64()).exp()).clamp(0.0, 1.0) as f32;
+    let display_speeds = DisplaySpeeds::new(&displays, &strips, &config, &mut uuids);
+    let instant = low_power_mode.is_some_and(|low_power| low_power.active(&config));
 
     animate
         .into_iter()
-        .for_each(|(mut bounds, entity, ResizeMarker(size))| {
+        .for_each(|(mut bounds, entity, ResizeMarker(size), position)| {
+            let center = position.map(|position| position.0 + bounds.0 / 2);
+            let rate = display_speeds.rate(entity, None, center).unwrap_or(rate);
+            let t = if instant {
+                1.0
+            } else {
+                animation_step(rate, time.delta_secs_f64())
+            };
+
             let target = size.as_vec2();
             let current = bounds.0.as_vec2();
             let lerped = current.lerp(target, t);
@@ -562,10 +754,14 @@ pub(super) fn animate_resize_entities(
 }
 
 #[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
+#[instrument(level = Level::TRACE, skip_all, fields(events))]
 pub(super) fn pump_events(
     mut exit: MessageWriter<AppExit>,
     mut messages: MessageWriter<Event>,
     low_power_mode: Option<Res<LowPowerMode>>,
+    config: Option<Res<Config>>,
+    accessibility_lost: Option<Res<AccessibilityLost>>,
+    presentation: Option<Res<PresentationMode>>,
     incoming_events: Option<NonSend<Receiver<Event>>>,
     platform: Option<NonSendMut<Pin<Box<PlatformCallbacks>>>>,
     repositioning: Query<(), With<RepositionMarker>>,
@@ -573,6 +769,7 @@ pub(super) fn pump_events(
     scrolling: Query<(), With<Scrolling>>,
     flash_messages: Query<(), With<FlashMessage>>,
     mut timeout: Local<u32>,
+    mut storm_guard: Local<EventStormGuard>,
 ) {
     let Some((ref mut platform, incoming_events)) = platform.zip(incoming_events) else {
         // No platform interface or incoming event pipe - probably executing in a unit test.
@@ -600,12 +797,24 @@ pub(super) fn pump_events(
             }
             Err(RecvTimeoutError::Timeout) => {
                 received_events.extend(pending_mouse.take());
+                if accessibility_lost.is_some() {
+                    received_events.retain(handled_without_accessibility);
+                }
+                storm_guard.filter(&mut received_events, Instant::now());
+                tracing::Span::current().record("events", received_events.len());
+                if !received_events.is_empty() {
+                    crate::metrics::record_events(received_events.len());
+                }
                 messages.write_batch(received_events);
-                let frame_active = !repositioning.is_empty()
-                    || !resizing.is_empty()
+                // Animations wait for the presentation to end, so they don't keep the loop busy.
+                let frame_active = (presentation.is_none()
+                    && (!repositioning.is_empty() || !resizing.is_empty()))
                     || !scrolling.is_empty()
                     || !flash_messages.is_empty();
-                let low_power = low_power_mode.is_some_and(|low_power| low_power.0);
+                let low_power = low_power_mode
+                    .as_ref()
+                    .zip(config.as_ref())
+                    .is_some_and(|(low_power, config)| low_power.active(config));
                 let timeout_limit = if frame_active {
                     LOOP_MAX_TIMEOUT_FRAME_ACTIVE_MS
                 } else if low_power {
@@ -634,7 +843,7 @@ pub(super) fn window_resized_update_frame(
         ),
         Without<LayoutStrip>,
     >,
-    mut workspaces: Query<(&LayoutStrip, &mut Position)>,
+    mut workspaces: Query<(&LayoutStrip, &mut Position, Has<LayoutLocked>)>,
 ) {
     for event in messages.read() {
         let Event::WindowResized { window_id } = event else {
@@ -647,7 +856,8 @@ pub(super) fn window_resized_update_frame(
         else {
             continue;
         };
-        if matches!(unmanaged, Some(Unmanaged::Minimized | Unmanaged::Hidden)) {
+        if unmanaged.is_some() {
+            // Floating windows are handled by floating_window_update_frame.
             continue;
         }
         let Ok(new_frame) = window.update_frame() else {
@@ -655,7 +865,7 @@ pub(super) fn window_resized_update_frame(
         };
         let active_strip = workspaces
             .iter_mut()
-            .find(|(strip, _)| strip.contains(entity));
+            .find(|(strip, _, _)| strip.contains(entity));
         let tabbed = active_strip
             .as_ref()
             .is_some_and(|strip| strip.0.tabbed(entity));
@@ -670,10 +880,14 @@ pub(super) fn window_resized_update_frame(
         }
 
         // If the window was resized, shift LayoutStrip slightly to avoid moving right corner.
-        let Some((strip, mut strip_position)) = active_strip else {
+        let Some((strip, mut strip_position, locked)) = active_strip else {
             // Floating window, don't nudge the strip.
             continue;
         };
+        if locked {
+            // Moving the strip would pull the hand-arranged windows back into the layout.
+            continue;
+        }
         if tabbed {
             // Native tabs share a single layout slot. Keep the strip anchored
             // and let the tab sync/layout systems propagate the new size.
@@ -719,7 +933,8 @@ pub(super) fn window_moved_update_frame(
         else {
             continue;
         };
-        if matches!(unmanaged, Some(Unmanaged::Minimized | Unmanaged::Hidden)) {
+        if unmanaged.is_some() {
+            // Floating windows are handled by floating_window_update_frame.
             continue;
         }
         let Ok(new_frame) = window.update_frame() else {
@@ -733,6 +948,41 @@ pub(super) fn window_moved_update_frame(
     }
 }
 
+/// Keeps the frames of floating windows in sync with the OS. Both moves and resizes can shift
+/// the origin and the size, and unlike the strip members they are tracked during swipes too.
+#[allow(clippy::needless_pass_by_value)]
+#[instrument(level = Level::TRACE, skip_all)]
+pub(super) fn floating_window_update_frame(
+    mut messages: MessageReader<Event>,
+    mut windows: Query<(&mut Window, &mut Position, &mut Bounds, &Unmanaged), Without<LayoutStrip>>,
+) {
+    for event in messages.read() {
+        let (Event::WindowMoved { window_id } | Event::WindowResized { window_id }) = event else {
+            continue;
+        };
+
+        let Some((mut window, mut position, mut bounds, unmanaged)) = windows
+            .iter_mut()
+            .find(|window| window.0.id() == *window_id)
+        else {
+            continue;
+        };
+        if !matches!(unmanaged, Unmanaged::Floating) {
+            continue;
+        }
+        let Ok(new_frame) = window.update_frame() else {
+            continue;
+        };
+
+        if position.0 != new_frame.min {
+            position.0 = new_frame.min;
+        }
+        if bounds.0 != new_frame.size() {
+            bounds.0 = new_frame.size();
+        }
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 pub(crate) fn gather_initial_processes(
     receiver: Option<NonSendMut<Receiver<Event>>>,
@@ -764,6 +1014,13 @@ pub(crate) fn gather_initial_processes(
         }
     }
     while let Some(mut process) = initial_processes.pop() {
+        if initial_config
+            .as_ref()
+            .is_some_and(|c| c.ignores_process(&**process))
+        {
+            debug!("Ignoring existing process '{}'.", process.name());
+            continue;
+        }
         let forced = initial_config
             .as_ref()
             .is_some_and(|c| c.should_force_manage_process(&**process));
@@ -961,17 +1218,24 @@ pub(super) fn commit_window_size(
 
 /// Restores user-visible window state before Paneru shuts down: clears any
 /// brightness dim, removes the dim/border overlay window, and centers every
-/// managed window on the display its frame center falls in.
+/// managed window on the display its frame center falls in. With
+/// `restore_original_frames`, windows go back to the frame they had before
+/// Paneru tiled them instead, if it is still on a display. Windows are left in
+/// place when the layout is handed over to a re-executed daemon.
 #[allow(clippy::needless_pass_by_value)]
 pub(super) fn cleanup_on_exit(
     mut exit_events: MessageReader<AppExit>,
-    mut all_windows: Query<&mut Window>,
+    mut all_windows: Query<(&mut Window, Option<&OriginalFrame>)>,
     displays: Query<&Display>,
     window_manager: Res<WindowManager>,
+    config: Res<Config>,
     mut overlay_mgr: Option<NonSendMut<OverlayManager>>,
 ) {
     for _ in exit_events.read() {
-        let ids = all_windows.iter().map(|w| w.id()).collect::<Vec<_>>();
+        let ids = all_windows
+            .iter()
+            .map(|(window, _)| window.id())
+            .collect::<Vec<_>>();
         info!("exit cleanup: restoring {} window(s)", ids.len());
         window_manager.dim_windows(&ids, 0.0);
 
@@ -979,12 +1243,32 @@ pub(super) fn cleanup_on_exit(
             overlay_mgr.remove_all();
         }
 
+        if handoff_requested() {
+            // The next daemon takes over the layout, keep the windows in place.
+            return;
+        }
+
         let display_bounds = displays.iter().map(Display::bounds).collect::<Vec<_>>();
         if display_bounds.is_empty() {
             return;
         }
 
-        for mut window in &mut all_windows {
+        for (mut window, original) in &mut all_windows {
+            if config.restore_original_frames()
+                && let Some(OriginalFrame(original)) = original
+                && display_bounds
+                    .iter()
+                    .any(|bounds| bounds.contains(original.center()))
+            {
+                info!(
+                    "exit cleanup: window {} -> original frame {original:?}",
+                    window.id()
+                );
+                window.resize(original.size());
+                window.reposition(original.min);
+                continue;
+            }
+
             let frame = window.frame();
             let center = frame.center();
             let bounds = display_bounds
@@ -1085,33 +1369,257 @@ pub(crate) fn update_flash_messages(
     }
 }
 
+/// Watches for the Accessibility permission being revoked while running. Without
+/// it every AX call fails, so window events are ignored until the permission
+/// is granted again. Then the daemon restarts in place to pick up all the
+/// changes it missed, keeping the layout.
+#[allow(clippy::needless_pass_by_value)]
+pub(crate) fn check_accessibility(
+    window_manager: Res<WindowManager>,
+    lost: Option<Res<AccessibilityLost>>,
+    mut messages: MessageWriter<Event>,
+    mut commands: Commands,
+) {
+    match (window_manager.is_trusted(), lost.is_some()) {
+        (false, false) => {
+            error!("Accessibility permission was revoked, pausing window management.");
+            messages.write(Event::Alert {
+                title: "Paneru lost Accessibility permission".to_string(),
+                message: "Allow Paneru in System Settings > Privacy & Security > Accessibility. \
+                          It resumes automatically."
+                    .to_string(),
+            });
+            commands.insert_resource(AccessibilityLost);
+        }
+        (true, true) => {
+            info!("Accessibility permission granted again, resuming.");
+            commands.remove_resource::<AccessibilityLost>();
+            messages.write(Event::Command {
+                command: Command::RestartDaemon,
+            });
+        }
+        _ => (),
+    }
+}
+
+/// Watches the connection to the window server. After a WindowServer restart
+/// the connection and all the window ids are stale, so the daemon restarts in
+/// place. The new process waits for the session to come back, then gathers the
+/// displays, apps and windows from scratch.
+#[allow(clippy::needless_pass_by_value)]
+pub(crate) fn check_window_server(
+    window_manager: Res<WindowManager>,
+    mut restarting: Local<bool>,
+    mut messages: MessageWriter<Event>,
+) {
+    if *restarting || window_manager.connection_alive() {
+        return;
+    }
+    error!("Lost the connection to the window server, restarting.");
+    *restarting = true;
+    messages.write(Event::Command {
+        command: Command::RestartDaemon,
+    });
+}
+
+/// Shows the alerts as macOS notifications, unless they are disabled in the configuration.
+#[allow(clippy::needless_pass_by_value)]
+pub(crate) fn show_alerts(
+    mut messages: MessageReader<Event>,
+    window_manager: Res<WindowManager>,
+    config: Res<Config>,
+) {
+    for event in messages.read() {
+        if let Event::Alert { title, message } = event {
+            warn!("{title}: {message}");
+            if config.notifications_enabled() {
+                window_manager.notify_user(title, message);
+            }
+        }
+    }
+}
+
+/// Shares the title bars of the tiled windows on the active strip with the input
+/// tap, which looks for a zoom button only under the clicks landing on them.
+#[allow(clippy::needless_pass_by_value)]
+pub(super) fn publish_title_bars(
+    active_display: ActiveDisplay,
+    windows: Windows,
+    config: Res<Config>,
+    mut published: Local<Vec<IRect>>,
+) {
+    let title_bars = if config.zoom_button_full_width() {
+        active_display
+            .active_strip()
+            .all_windows()
+            .into_iter()
+            .filter_map(|entity| windows.frame(entity))
+            .map(|frame| {
+                IRect::new(
+                    frame.min.x,
+                    frame.min.y,
+                    frame.max.x,
+                    frame.min.y + TITLE_BAR_HEIGHT,
+                )
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+    if title_bars != *published {
+        crate::platform::input::set_title_bars(title_bars.clone());
+        *published = title_bars;
+    }
+}
+
+/// Events which are still handled while the Accessibility permission is missing.
+fn handled_without_accessibility(event: &Event) -> bool {
+    match event {
+        Event::Command { command } => !matches!(
+            command,
+            Command::Window(_) | Command::Mouse(_) | Command::Space(_)
+        ),
+        Event::ConfigRefresh(_)
+        | Event::Alert { .. }
+        | Event::StateQuery { .. }
+        | Event::StateSubscribe { .. } => true,
+        _ => false,
+    }
+}
+
 pub(crate) fn update_low_power_state(low_power_mode: Option<ResMut<LowPowerMode>>) {
     let Some(mut state) = low_power_mode else {
         return;
     };
     let process_info = objc2_foundation::NSProcessInfo::processInfo();
-    state.0 = process_info.isLowPowerModeEnabled();
+    let enabled = process_info.isLowPowerModeEnabled();
+    if state.system != enabled {
+        state.system = enabled;
+    }
+}
+
+/// Keeps track of the power source, reported by the platform on startup and
+/// whenever the Mac switches between its battery and AC power.
+#[allow(clippy::needless_pass_by_value)]
+pub(crate) fn power_source_changed(
+    mut messages: MessageReader<Event>,
+    low_power_mode: Option<ResMut<LowPowerMode>>,
+    config: Res<Config>,
+) {
+    let Some(mut state) = low_power_mode else {
+        return;
+    };
+    for event in messages.read() {
+        if let Event::PowerSourceChanged { on_battery } = event {
+            state.on_battery = *on_battery;
+            info!(
+                "running on {}, power saving {}.",
+                if *on_battery { "battery" } else { "AC power" },
+                if state.active(&config) { "on" } else { "off" }
+            );
+        }
+    }
+}
+
+/// Run condition: the power saving is active.
+#[allow(clippy::needless_pass_by_value)]
+pub(crate) fn low_power_active(
+    low_power_mode: Option<Res<LowPowerMode>>,
+    config: Option<Res<Config>>,
+) -> bool {
+    low_power_mode
+        .zip(config)
+        .is_some_and(|(low_power, config)| low_power.active(&config))
 }
 
 #[allow(clippy::needless_pass_by_value)]
 #[instrument(level = Level::DEBUG, skip_all)]
-pub(crate) fn window_creation_event(mut messages: MessageReader<Event>, mut commands: Commands) {
+pub(crate) fn window_creation_event(
+    mut messages: MessageReader<Event>,
+    windows: Windows,
+    displays: Query<(&Display, Option<&DockPosition>)>,
+    config: Res<Config>,
+    mut commands: Commands,
+) {
     for event in messages.read() {
         let Event::WindowCreated { element } = event else {
             continue;
         };
 
-        if let Ok(window) = WindowOS::new(element)
-            .inspect_err(|err| {
+        match WindowOS::new(element) {
+            Ok(window) => {
+                commands.trigger(SpawnWindowTrigger(vec![Window::new(Box::new(window))]));
+            }
+            Err(err) => {
                 trace!("not adding window {element:?}: {err}");
-            })
-            .map(|window| Window::new(Box::new(window)))
-        {
-            commands.trigger(SpawnWindowTrigger(vec![window]));
+                place_dialog(element, &windows, &displays, &config);
+            }
         }
     }
 }
 
+/// Centers a newly created dialog over its parent window, keeping it on the
+/// display of the parent even when the parent column is scrolled out of view.
+fn place_dialog(
+    element: &CFRetained<AXUIWrapper>,
+    windows: &Windows,
+    displays: &Query<(&Display, Option<&DockPosition>)>,
+    config: &Config,
+) {
+    let Ok((mut dialog, parent_id)) = WindowOS::new_dialog(element)
+        .inspect_err(|err| trace!("not placing window {element:?}: {err}"))
+    else {
+        return;
+    };
+    let Some((parent, _)) = windows.find(parent_id) else {
+        return;
+    };
+    let Ok(frame) = dialog.update_frame().inspect_err(|err| debug!("{err}")) else {
+        return;
+    };
+
+    // Hidden columns still keep a sliver on their display, so the display
+    // showing the largest part of the parent is the one it belongs to.
+    let parent_frame = parent.frame();
+    let Some(viewport) = displays
+        .iter()
+        .map(|(display, dock)| (display, dock, display.bounds().intersect(parent_frame)))
+        .filter(|(_, _, overlap)| !overlap.is_empty())
+        .max_by_key(|(_, _, overlap)| overlap.width() * overlap.height())
+        .map(|(display, dock, _)| display.actual_display_bounds(dock, config))
+    else {
+        return;
+    };
+    let origin = center_over(parent_frame, frame.size(), viewport);
+    debug!(
+        "placing dialog {} over {parent_id} at {origin}",
+        dialog.id()
+    );
+    dialog.reposition(origin);
+}
+
+/// Counts the native tabs of the windows whose title settled after a change.
+#[allow(clippy::needless_pass_by_value)]
+pub(crate) fn recount_native_tabs(
+    windows: Populated<(&Window, Entity, &mut RecountNativeTabs)>,
+    clock: Res<Time>,
+    mut commands: Commands,
+) {
+    for (window, entity, mut recount) in windows {
+        if !recount.0.tick(clock.delta()).is_finished() {
+            continue;
+        }
+        let Ok(mut entity_commands) = commands.get_entity(entity) else {
+            continue;
+        };
+        entity_commands.try_remove::<RecountNativeTabs>();
+        match window.native_tab_count() {
+            Some(count) => entity_commands.try_insert(NativeTabs(count)),
+            None => entity_commands.try_remove::<NativeTabs>(),
+        };
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 pub(crate) fn detect_tabbed_windows(
     created: Populated<(Entity, &Position, &Bounds, &ChildOf), Added<Window>>,
@@ -6,32 +6,39 @@ use bevy::ecs::observer::On;
 use bevy::ecs::query::{Added, Has, With};
 use bevy::ecs::system::{Commands, NonSendMut, Populated, Query, Res, ResMut, Single};
 use bevy::math::IRect;
+use bevy::time::Time;
 use notify::event::{DataChange, MetadataKind, ModifyKind};
 use notify::{EventKind, Watcher};
 use std::cmp::Ordering;
-use std::time::Duration;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
 use tracing::{Level, debug, error, info, instrument, trace, warn};
 
 use super::{
-    ActiveDisplayMarker, BProcess, FocusedMarker, FreshMarker, MissionControlActive,
-    PreviousManagedStrip, RetryFrontSwitch, SpawnWindowTrigger, StrayFocusEvent, SystemTheme,
-    Timeout, Unmanaged,
+    ActiveDisplayMarker, BProcess, FocusStealGuard, FocusedMarker, FreshMarker,
+    MissionControlActive, PreviousManagedStrip, ReadyBackoff, RetryFrontSwitch, SpawnWindowTrigger,
+    StrayFocusEvent, SystemTheme, Timeout, Unmanaged,
 };
-use crate::config::Config;
+use crate::commands::{Command, InsertionPlacement, SpaceTarget};
+use crate::config::{CONFIGURATION_FILE, Config, FrameInsetOption};
 use crate::ecs::focus::FocusHistory;
-use crate::ecs::layout::LayoutStrip;
+use crate::ecs::layout::{ColumnOrientation, LayoutStrip};
 use crate::ecs::params::{ActiveDisplay, GlobalState, Windows};
+use crate::ecs::relaunch::Tombstones;
 use crate::ecs::state::PaneruState;
 use crate::ecs::{
-    ActiveWorkspaceMarker, Bounds, DockPosition, Initializing, LayoutPosition, Position,
-    ResizeMarker, RestoreWindowState, Scrolling, SendMessageTrigger, SpawnCommandsExt,
-    VerifyWindowPosition, WidthRatio, WindowProperties,
+    ActiveWorkspaceMarker, Bounds, DockPosition, Initializing, InsertionPoint, LayoutLocked,
+    LayoutPosition, MinimumSize, NativeTabs, OriginalFrame, Position, ReapplyWindowRules,
+    RecountNativeTabs, ResizeMarker, RestoreWindowState, Scrolling, SelectedVirtualMarker,
+    SendMessageTrigger, ShrinkPriority, SizeRatios, SpawnCommandsExt, UnmanagedDisplay,
+    VerifyWindowPosition, WidthRatio, WindowProperties, WindowRuleOutcome,
 };
 use crate::events::Event;
 use crate::manager::{
     Application, Display, Origin, Process, Size, Window, WindowManager, WindowPadding,
+    most_overlapping, origin_from,
 };
-use crate::platform::WinID;
+use crate::platform::{PlatformCallbacks, WinID, WorkspaceId};
 use crate::util::symlink_target;
 
 /// Computes the passthrough keybinding set for the given window/app and
@@ -56,6 +63,10 @@ pub(super) fn front_switched_trigger(
     mut messages: MessageReader<Event>,
     processes: Query<(&BProcess, &Children)>,
     applications: Query<&Application>,
+    windows: Windows,
+    strips: Query<(&LayoutStrip, &ChildOf, Has<ActiveWorkspaceMarker>)>,
+    active_display: Query<Entity, With<ActiveDisplayMarker>>,
+    focus_history: Res<FocusHistory>,
     window_manager: Res<WindowManager>,
     mut config: GlobalState,
     mut commands: Commands,
@@ -90,11 +101,35 @@ pub(super) fn front_switched_trigger(
         if let Ok(focused_id) = app.focused_window_id().inspect_err(|err| {
             warn!("can not get current focus: {err}");
         }) {
-            if let Some(point) = window_manager.cursor_position()
-                && window_manager
-                    .find_window_at_point(&point)
-                    .is_ok_and(|window_id| window_id != focused_id)
+            let cursor = window_manager.cursor_position();
+            let under_cursor =
+                cursor.and_then(|point| window_manager.find_window_at_point(&point).ok());
+            // A click on the window itself switches displays deliberately, as does
+            // moving the focus to another display, which brings the pointer along.
+            let deliberate = under_cursor == Some(focused_id)
+                || cursor
+                    .zip(windows.find(focused_id))
+                    .is_some_and(|(point, (window, _))| {
+                        window.frame().contains(origin_from(point))
+                    });
+            if !deliberate
+                && let Some(entity) = seat_on_active_display(
+                    focused_id,
+                    app_entity,
+                    &windows,
+                    &strips,
+                    &active_display,
+                    &focus_history,
+                )
             {
+                debug!(
+                    "'{}' has windows on several displays, focusing the one on the active display.",
+                    process.name()
+                );
+                commands.focus_entity(entity, true);
+                continue;
+            }
+            if under_cursor.is_some_and(|window_id| window_id != focused_id) {
                 // Window got focus without mouse movement - probably with a Cmd-Tab.
                 // If so, bring it into view.
                 config.set_skip_reshuffle(false);
@@ -119,6 +154,51 @@ pub(super) fn front_switched_trigger(
     }
 }
 
+/// Activating an application (e.g. from its Dock icon) raises all of its windows and
+/// focuses whichever one it had focused last, even on another display. When the
+/// application also has a window in the active strip, that one should get the focus
+/// instead, so that the layout of the other display stays put.
+///
+/// # Returns
+///
+/// The window of the application to focus on the active display, if the focus would
+/// otherwise go to another display.
+fn seat_on_active_display(
+    focused_id: WinID,
+    app_entity: Entity,
+    windows: &Windows,
+    strips: &Query<(&LayoutStrip, &ChildOf, Has<ActiveWorkspaceMarker>)>,
+    active_display: &Query<Entity, With<ActiveDisplayMarker>>,
+    focus_history: &FocusHistory,
+) -> Option<Entity> {
+    let active_display = active_display.single().ok()?;
+    let (_, focused) = windows.find(focused_id)?;
+    let (_, focused_strip, _) = strips
+        .iter()
+        .find(|(strip, _, _)| strip.contains(focused))?;
+    if focused_strip.parent() == active_display {
+        return None;
+    }
+
+    let (active_strip, _, _) = strips
+        .iter()
+        .find(|(_, child, active)| *active && child.parent() == active_display)?;
+    let seats = windows
+        .managed_iter()
+        .filter(|(_, entity, child)| child.parent() == app_entity && active_strip.contains(*entity))
+        .map(|(_, entity, _)| entity)
+        .collect::<Vec<_>>();
+    focus_history
+        .last_managed(active_strip.id())
+        .filter(|entity| seats.contains(entity))
+        .or_else(|| {
+            active_strip
+                .all_windows()
+                .into_iter()
+                .find(|entity| seats.contains(entity))
+        })
+}
+
 #[allow(clippy::needless_pass_by_value)]
 pub(super) fn theme_change_trigger(
     mut messages: MessageReader<Event>,
@@ -181,6 +261,7 @@ pub(super) fn window_focused_trigger(
     windows: Windows,
     mut workspaces: Query<(Entity, &mut LayoutStrip, Has<ActiveWorkspaceMarker>)>,
     mut focus_history: ResMut<FocusHistory>,
+    guards: Query<&FocusStealGuard>,
     config: Res<Config>,
     global_state: GlobalState,
     mut commands: Commands,
@@ -230,6 +311,18 @@ pub(super) fn window_focused_trigger(
             continue;
         }
 
+        if let Ok(guard) = guards.get(entity)
+            && guard.active()
+            && guard.previous != entity
+        {
+            debug!(
+                "window {window_id} took the focus on its own, returning it to {}",
+                guard.previous
+            );
+            commands.focus_entity(guard.previous, true);
+            continue;
+        }
+
         let managed = windows
             .get_managed(entity)
             .and_then(|(_, _, managed)| managed);
@@ -377,6 +470,8 @@ pub(super) fn mission_control_trigger(
 pub(super) fn application_event_trigger(
     mut messages: MessageReader<Event>,
     processes: Query<(&BProcess, Entity)>,
+    fresh: Query<(), With<FreshMarker>>,
+    config: Res<Config>,
     mut commands: Commands,
 ) {
     const PROCESS_READY_TIMEOUT_SEC: u64 = 5;
@@ -390,6 +485,10 @@ pub(super) fn application_event_trigger(
         match event {
             Event::ApplicationLaunched { psn, observer } if find_process(*psn).is_none() => {
                 let process: BProcess = Process::new(psn, observer.clone()).into();
+                if config.ignores_process(&**process) {
+                    debug!("Ignoring launched process '{}'.", process.name());
+                    continue;
+                }
                 let timeout = Timeout::new(
                     Duration::from_secs(PROCESS_READY_TIMEOUT_SEC),
                     Some(format!(
@@ -398,7 +497,18 @@ pub(super) fn application_event_trigger(
                     )),
                     &mut commands,
                 );
-                commands.spawn((FreshMarker, timeout, process));
+                commands.spawn((FreshMarker, ReadyBackoff::default(), timeout, process));
+            }
+
+            // The KVO observers re-send the launch once the process finished launching or
+            // changed its activation policy, so check it again right away.
+            Event::ApplicationLaunched { psn, .. } => {
+                if let Some((_, entity)) = find_process(*psn)
+                    && fresh.contains(entity)
+                    && let Ok(mut entity_commands) = commands.get_entity(entity)
+                {
+                    entity_commands.try_insert(ReadyBackoff::default());
+                }
             }
 
             Event::ApplicationTerminated { psn } => {
@@ -428,6 +538,8 @@ pub(super) fn dispatch_application_messages(
     windows: Windows,
     applications: Query<(&Application, &Children)>,
     unmanaged_query: Query<&Unmanaged>,
+    workspaces: Query<&LayoutStrip>,
+    config: Res<Config>,
     mut commands: Commands,
 ) {
     let find_window = |window_id| windows.find(window_id);
@@ -438,19 +550,55 @@ pub(super) fn dispatch_application_messages(
                 if let Some((_, entity)) = find_window(*window_id)
                     && let Ok(mut entity_commands) = commands.get_entity(entity)
                 {
-                    entity_commands.try_insert(Unmanaged::Minimized);
+                    let space = workspaces
+                        .iter()
+                        .find(|strip| strip.contains(entity))
+                        .map(LayoutStrip::id);
+                    entity_commands.try_insert(Unmanaged::Minimized(space));
                 }
             }
 
             Event::WindowDeminimized { window_id } => {
                 if let Some((_, entity)) = find_window(*window_id)
-                    && matches!(unmanaged_query.get(entity), Ok(Unmanaged::Minimized))
+                    && matches!(unmanaged_query.get(entity), Ok(Unmanaged::Minimized(_)))
                     && let Ok(mut entity_commands) = commands.get_entity(entity)
                 {
                     entity_commands.try_remove::<Unmanaged>();
                 }
             }
 
+            Event::WindowTitleChanged { window_id } => {
+                if let Some((window, entity)) = find_window(*window_id) {
+                    let app = config
+                        .float_rules_follow_title()
+                        .then(|| windows.find_parent(*window_id))
+                        .flatten()
+                        .and_then(|(_, _, parent)| applications.get(parent).ok())
+                        .map(|(app, _)| app);
+                    let floating =
+                        app.map(|app| WindowProperties::new(app, window, &config).floating());
+                    window.refresh_title();
+                    // Only the floating rules follow the title, the rest stay as applied.
+                    if let Some((app, floating)) = app.zip(floating) {
+                        let current = WindowProperties::new(app, window, &config).outcome();
+                        if current.floating != floating {
+                            let previous = WindowRuleOutcome {
+                                floating,
+                                ..current
+                            };
+                            commands.trigger(ReapplyWindowRules(vec![(entity, previous)]));
+                        }
+                    }
+                    // Switching, opening and closing tabs all change the title.
+                    // Inserting the marker again restarts its timer.
+                    if config.native_tabs_enabled()
+                        && let Ok(mut entity_commands) = commands.get_entity(entity)
+                    {
+                        entity_commands.try_insert(RecountNativeTabs::default());
+                    }
+                }
+            }
+
             Event::ApplicationHidden { pid } => {
                 let Some((_, children)) = applications.iter().find(|(app, _)| app.pid() == *pid)
                 else {
@@ -497,6 +645,8 @@ pub(super) fn window_unmanaged_trigger(
     apps: Query<(Entity, &Application)>,
     workspaces: Query<&mut LayoutStrip>,
     active_display: Single<(&Display, Option<&DockPosition>), With<ActiveDisplayMarker>>,
+    displays: Query<(&Display, Has<UnmanagedDisplay>)>,
+    original_frames: Query<&OriginalFrame>,
     config: Res<Config>,
     initializing: Option<Res<Initializing>>,
     mut commands: Commands,
@@ -565,13 +715,27 @@ pub(super) fn window_unmanaged_trigger(
 
     // Skip the active-display reposition/resize during init; the strip
     // removal below still has to run.
-    if let Some((rx, ry, rw, rh)) = properties.grid_ratios() {
+    if on_unmanaged_display(frame, &displays) {
+        debug!("Entity {entity} stays in place on an unmanaged display.");
+    } else if let Some((rx, ry, rw, rh)) = properties.grid_ratios() {
         let x = (f64::from(display_bounds.width()) * rx) as i32;
         let y = (f64::from(display_bounds.height()) * ry) as i32;
         let w = (f64::from(display_bounds.width()) * rw) as i32;
         let h = (f64::from(display_bounds.height()) * rh) as i32;
         commands.reposition_entity(entity, Origin::new(x, y));
         commands.resize_entity(entity, Size::new(w, h));
+    } else if initializing.is_none()
+        && config.restore_original_frames()
+        && let Ok(OriginalFrame(original)) = original_frames.get(entity)
+    {
+        // Put the window back where it was before it was tiled.
+        let target_frame = clamp_origin_to_bounds(*original, original.size(), display_bounds);
+        if target_frame.size() != frame.size() {
+            commands.resize_entity(entity, target_frame.size());
+        }
+        if target_frame.min != frame.min {
+            commands.reposition_entity(entity, target_frame.min);
+        }
     } else if initializing.is_none() && !properties.floating() {
         let max_width = display_bounds.width() * UNMANAGED_MAX_SCREEN_RATIO_NUM
             / UNMANAGED_MAX_SCREEN_RATIO_DEN;
@@ -611,11 +775,13 @@ pub(super) fn window_minimized_trigger(
     windows: Windows,
     workspaces: Query<(&mut LayoutStrip, Has<ActiveWorkspaceMarker>)>,
     active_display: Single<&Display, With<ActiveDisplayMarker>>,
+    focus_history: Res<FocusHistory>,
+    window_manager: Res<WindowManager>,
     mut config: GlobalState,
     mut commands: Commands,
 ) {
     let entity = trigger.event().entity;
-    if let Some((_, _, Some(Unmanaged::Minimized | Unmanaged::Hidden))) =
+    if let Some((_, _, Some(Unmanaged::Minimized(_) | Unmanaged::Hidden))) =
         windows.get_managed(entity)
     {
         debug!("Entity {entity} is minimized or hidden.");
@@ -628,6 +794,8 @@ pub(super) fn window_minimized_trigger(
                     &windows,
                     &strip,
                     &display_bounds,
+                    &focus_history,
+                    &window_manager,
                     &mut config,
                     &mut commands,
                 );
@@ -640,6 +808,7 @@ pub(super) fn window_minimized_trigger(
                         workspace_id: strip.id(),
                         virtual_index: strip.virtual_index,
                         index,
+                        width_ratio: None,
                     });
                 }
                 strip.remove(entity);
@@ -667,10 +836,7 @@ pub(super) fn window_managed_trigger(
     }
     let entity = trigger.event().entity;
 
-    if windows
-        .get(entity)
-        .is_some_and(|window| window.role().is_err())
-    {
+    if windows.get(entity).is_some_and(|window| !window.is_alive()) {
         // The marker was removed because the windows was destroyed.
         return;
     }
@@ -683,6 +849,12 @@ pub(super) fn window_managed_trigger(
         .ok()
         .map(|previous| previous.index);
 
+    let previous = previous_strips.get(entity).ok().copied();
+    let minimized_on = match windows.get_managed(entity) {
+        Some((_, _, Some(Unmanaged::Minimized(space)))) => *space,
+        _ => None,
+    };
+    let mut resized = false;
     if let Some(window) = windows.get(entity)
         && let Some((_, app)) = windows
             .find_parent(window.id())
@@ -696,12 +868,18 @@ pub(super) fn window_managed_trigger(
             let width = (f64::from(padded_width) * width_ratio).round() as i32;
             let height = display_bounds.height();
             commands.resize_entity(entity, Size::new(width, height));
+            resized = true;
         }
 
         insert_at = properties.insertion().or(insert_at);
     }
 
-    let previous = previous_strips.get(entity).ok().copied();
+    // A window toggled back from floating gets the width it had while tiled.
+    if !resized && let Some(width_ratio) = previous.and_then(|previous| previous.width_ratio) {
+        let width = (f64::from(display.bounds().width()) * width_ratio).round() as i32;
+        commands.resize_entity(entity, Size::new(width, display_bounds.height()));
+    }
+
     for (mut strip, _) in &mut workspaces {
         strip.remove(entity);
     }
@@ -718,6 +896,20 @@ pub(super) fn window_managed_trigger(
         }
     }
 
+    // macOS brings a minimized window back on the space it was minimized on,
+    // which is not necessarily the active one.
+    if !restored
+        && let Some(space) = minimized_on
+        && let Some((mut strip, _)) = workspaces
+            .iter_mut()
+            .filter(|(strip, _)| strip.id() == space)
+            .min_by_key(|(strip, active)| (!*active, strip.virtual_index))
+    {
+        let index = insert_at.unwrap_or(strip.len());
+        strip.insert_at(index, entity);
+        restored = true;
+    }
+
     if !restored
         && let Some((mut active_strip, _)) = workspaces.iter_mut().find(|(_, active)| *active)
     {
@@ -767,6 +959,7 @@ pub(super) fn window_destroyed_trigger(
     windows: Windows,
     active_display: ActiveDisplay,
     mut apps: Query<&mut Application>,
+    window_manager: Res<WindowManager>,
     mut config: GlobalState,
     mut focus_history: ResMut<FocusHistory>,
     mut commands: Commands,
@@ -780,7 +973,7 @@ pub(super) fn window_destroyed_trigger(
             debug!("Duplicate event: window {window_id} already destroyed.");
             continue;
         };
-        if window.role().is_ok() {
+        if window.is_alive() {
             debug!("Window still present, this was SLS workspace change.");
             continue;
         }
@@ -796,6 +989,8 @@ pub(super) fn window_destroyed_trigger(
             &windows,
             active_display.active_strip(),
             &active_display.bounds(),
+            &focus_history,
+            &window_manager,
             &mut config,
             &mut commands,
         );
@@ -809,12 +1004,16 @@ pub(super) fn window_destroyed_trigger(
     }
 }
 
-/// Moves the focus away to a neighbour window.
+/// Moves the focus away to a neighbour window. When no other window is tiled in the strip,
+/// the focus goes to the window last focused on the previous workspace, or to the desktop.
+#[allow(clippy::too_many_arguments)]
 fn give_away_focus(
     entity: Entity,
     windows: &Windows,
     active_strip: &LayoutStrip,
     viewport: &IRect,
+    focus_history: &FocusHistory,
+    window_manager: &WindowManager,
     config: &mut GlobalState,
     commands: &mut Commands,
 ) {
@@ -856,6 +1055,29 @@ fn give_away_focus(
         // guards would reject a fabricated event. focus_entity calls the
         // AX API to raise the neighbour and inserts FocusedMarker directly.
         commands.focus_entity(neighbour, true);
+        return;
+    }
+
+    // Otherwise the focus would stay with the dead window and keyboard input
+    // goes nowhere until the user clicks somewhere.
+    if windows
+        .focused()
+        .is_none_or(|(_, focused)| focused != entity)
+    {
+        return;
+    }
+    config.set_ffm_flag(None);
+    if let Some(previous) = focus_history
+        .previous_managed(active_strip.id())
+        .filter(|&previous| previous != entity && windows.get(previous).is_some())
+    {
+        debug!("No window left in the strip, focusing {previous} on the previous workspace.");
+        commands.focus_entity(previous, true);
+    } else {
+        debug!("No window left to focus, activating the desktop.");
+        _ = window_manager
+            .focus_desktop()
+            .inspect_err(|err| warn!("focusing the desktop: {err}"));
     }
 }
 
@@ -934,6 +1156,7 @@ pub(super) fn spawn_window_trigger(
             width_ratio,
             window,
             layout_position,
+            OriginalFrame(frame),
             ChildOf(app_entity),
         ));
     }
@@ -945,13 +1168,14 @@ pub(super) fn spawn_window_trigger(
 
 #[allow(clippy::needless_pass_by_value)]
 pub(super) fn apply_window_defaults(
-    added: Populated<(&mut Window, &mut Position, &mut Bounds, &ChildOf), Added<Window>>,
+    added: Populated<(Entity, &mut Window, &mut Position, &mut Bounds, &ChildOf), Added<Window>>,
     apps: Query<(Entity, &Application)>,
     active_display: ActiveDisplay,
     config: Res<Config>,
     initializing: Option<Res<Initializing>>,
+    mut commands: Commands,
 ) {
-    for (ref mut window, mut position, mut bounds, child) in added {
+    for (entity, ref mut window, mut position, mut bounds, child) in added {
         let Ok((_, app)) = apps.get(child.parent()) else {
             continue;
         };
@@ -979,6 +1203,30 @@ pub(super) fn apply_window_defaults(
         let hpadding = properties.horizontal_padding();
         window.set_padding(WindowPadding::Vertical(vpadding.clamp(0, 50)));
         window.set_padding(WindowPadding::Horizontal(hpadding.clamp(0, 50)));
+        let inset = properties.frame_inset().and_then(|inset| match inset {
+            FrameInsetOption::Auto => window.detect_frame_inset(),
+            FrameInsetOption::Fixed(inset) => Some(inset),
+        });
+        if let Some(inset) = inset {
+            window.set_frame_inset(inset);
+        }
+        let min_size = properties.min_size(active_display.actual_bounds(&config));
+        if min_size != Size::ZERO {
+            window.set_min_size(min_size);
+            if let Ok(mut entity_commands) = commands.get_entity(entity) {
+                entity_commands.try_insert(MinimumSize(min_size));
+            }
+        }
+        if let Some(priority) = properties.shrink_priority()
+            && let Ok(mut entity_commands) = commands.get_entity(entity)
+        {
+            entity_commands.try_insert(ShrinkPriority(priority));
+        }
+        if let Some(ratios) = properties.size_ratios()
+            && let Ok(mut entity_commands) = commands.get_entity(entity)
+        {
+            entity_commands.try_insert(SizeRatios(ratios));
+        }
         if let Ok(frame) = window.update_frame() {
             position.0 = frame.min;
             bounds.0 = frame.size();
@@ -1003,21 +1251,42 @@ pub(super) fn apply_window_defaults(
     }
 }
 
-#[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
+#[allow(
+    clippy::needless_pass_by_value,
+    clippy::too_many_arguments,
+    clippy::too_many_lines
+)]
 #[instrument(level = Level::DEBUG, skip_all)]
 pub(super) fn apply_window_positions(
     added: Populated<Entity, Added<Window>>,
-    mut workspaces: Query<(&mut LayoutStrip, Has<ActiveWorkspaceMarker>)>,
+    mut workspaces: Query<(
+        &mut LayoutStrip,
+        Has<ActiveWorkspaceMarker>,
+        Has<SelectedVirtualMarker>,
+        Has<LayoutLocked>,
+    )>,
     windows: Windows,
     apps: Query<&Application>,
+    active_display: Query<&Display, With<ActiveDisplayMarker>>,
+    displays: Query<(&Display, Has<UnmanagedDisplay>)>,
+    window_manager: Res<WindowManager>,
     config: Res<Config>,
     initializing: Option<Res<Initializing>>,
     restore: Option<Res<crate::ecs::restore::SessionRestore>>,
     restoration: Option<Res<PaneruState>>,
+    insertion_point: Option<Res<InsertionPoint>>,
+    mut tombstones: Option<ResMut<Tombstones>>,
+    time: Res<Time>,
     mut commands: Commands,
 ) {
+    let mut insertion_point = insertion_point
+        .filter(|_| initializing.is_none())
+        .map(|point| *point);
     for entity in added {
-        if workspaces.iter().any(|(strip, _)| strip.tabbed(entity)) {
+        if workspaces
+            .iter()
+            .any(|(strip, _, _, _)| strip.tabbed(entity))
+        {
             debug!("Ignoring tabbed {entity} attributes.");
             continue;
         }
@@ -1045,10 +1314,14 @@ pub(super) fn apply_window_positions(
         // During startup, the window is already inserted into some strip.
         let allready_inserted = workspaces
             .iter_mut()
-            .find_map(|(strip, _)| strip.contains(entity).then_some(strip));
+            .find_map(|(strip, _, _, _)| strip.contains(entity).then_some(strip));
         let properties = WindowProperties::new(app, window, &config);
+        let owner = windows
+            .frame(entity)
+            .and_then(|frame| owning_display(frame, &displays));
+        let unmanaged_display = owner.is_some_and(|(_, unmanaged)| unmanaged);
 
-        if properties.floating() {
+        if properties.floating() || unmanaged_display {
             if let Ok(mut entity_commands) = commands.get_entity(entity) {
                 // Avoid managing window if it's floating.
                 entity_commands.try_insert(Unmanaged::Floating);
@@ -1059,46 +1332,175 @@ pub(super) fn apply_window_positions(
             continue;
         }
 
+        // A window with a space rule goes to the strip of that space instead.
         if allready_inserted.is_none()
-            && let Some(mut strip) = workspaces
+            && initializing.is_none()
+            && let Some((space, follow)) = properties.space()
+            && let Ok(display) = active_display.single()
+            && let Some(space_id) = rule_space_id(display, space, &window_manager)
+            && let Some((mut strip, active, _, _)) = workspaces
                 .iter_mut()
-                .find_map(|(strip, active)| active.then_some(strip))
+                .find(|(strip, _, _, _)| strip.id() == space_id && strip.virtual_index == 0)
+            && !active
         {
-            // Attempt inserting the window at a pre-defined position.
-            let insert_at = properties.insertion().map_or_else(
-                || {
-                    // Otherwise attempt inserting it after the current focus.
-                    let focused_window = windows.focused();
-                    // Insert to the right of the currently focused window
-                    focused_window
-                        .and_then(|(_, entity)| strip.index_of(entity).ok())
-                        .and_then(|insert_at| {
-                            (insert_at + 1 < strip.len()).then_some(insert_at + 1)
-                        })
-                },
-                Some,
-            );
+            debug!("New window {entity} moved to space {space} ({space_id})");
+            if let Err(err) = window_manager.move_window_to_space(window.id(), space_id) {
+                warn!(
+                    "unable to move window {} to space {space}: {err}",
+                    window.id()
+                );
+            } else {
+                strip.append(entity);
+                if follow {
+                    _ = window_manager
+                        .focus_space(SpaceTarget::Number(space))
+                        .inspect_err(|err| warn!("unable to switch to space {space}: {err}"));
+                    commands.focus_entity(entity, true);
+                }
+                continue;
+            }
+        }
+
+        let native_tabs = config
+            .native_tabs_enabled()
+            .then(|| window.native_tab_count())
+            .flatten();
+        if let Some(count) = native_tabs
+            && let Ok(mut entity_commands) = commands.get_entity(entity)
+        {
+            entity_commands.try_insert(NativeTabs(count));
+        }
+
+        // A window straddling displays goes to the one holding most of it,
+        // which is not necessarily the active one.
+        let owner_space = owner
+            .filter(|(display, _)| {
+                initializing.is_none()
+                    && active_display
+                        .single()
+                        .is_ok_and(|active| active.id() != display.id())
+            })
+            .and_then(|(display, _)| window_manager.active_display_space(display.id()).ok());
+        if allready_inserted.is_none()
+            && let Some((mut strip, locked)) =
+                workspaces
+                    .iter_mut()
+                    .find_map(|(strip, active, selected, locked)| {
+                        let target = owner_space
+                            .map_or(active, |space_id| selected && strip.id() == space_id);
+                        target.then_some((strip, locked))
+                    })
+        {
+            // A pending insertion point is consumed by the first new window,
+            // even when its target column has since left the strip.
+            let pending = insertion_point.take_if(|_| !locked).and_then(|point| {
+                commands.remove_resource::<InsertionPoint>();
+                strip
+                    .index_of(point.target)
+                    .ok()
+                    .map(|index| (index, point.placement))
+            });
+
+            if locked {
+                // Keep tracking the window, but leave the hand-arranged columns alone.
+                debug!("New window {entity} appended to locked strip");
+                strip.append(entity);
+            } else if let Some((index, placement)) = pending {
+                debug!("New window {entity} placed {placement:?} of column {index}");
+                match placement {
+                    InsertionPlacement::West => strip.insert_at(index, entity),
+                    InsertionPlacement::East => strip.insert_at(index + 1, entity),
+                    InsertionPlacement::Stack => {
+                        strip.insert_at(index + 1, entity);
+                        _ = strip
+                            .stack(entity)
+                            .inspect_err(|err| warn!("unable to stack {entity}: {err}"));
+                    }
+                }
+            } else {
+                // A tab torn off into its own window lands next to the window it came from.
+                let torn_off = (initializing.is_none() && config.native_tabs_enabled())
+                    .then(|| torn_off_origin(entity, parent, &strip, &windows, &mut commands))
+                    .flatten()
+                    .and_then(|origin| strip.index_of(origin).ok())
+                    .map(|index| index + 1);
+
+                // A window of a relaunched app returns to its former column.
+                let adopted = tombstones
+                    .as_mut()
+                    .filter(|_| initializing.is_none())
+                    .and_then(|tombstones| {
+                        let bundle_id = app.bundle_id()?;
+                        let title = window.title().unwrap_or_default();
+                        tombstones.adopt(
+                            &bundle_id,
+                            &title,
+                            (strip.id(), strip.virtual_index),
+                            time.elapsed(),
+                        )
+                    });
+                if let Some(stone) = &adopted {
+                    debug!("New window {entity} returns to column {}", stone.index);
+                    if properties.width_ratio().is_none()
+                        && let Some(width_ratio) = stone.width_ratio
+                        && let Ok(display) = active_display.single()
+                        && let Some(size) = windows.size(entity)
+                    {
+                        let width =
+                            (f64::from(display.bounds().width()) * width_ratio).round() as i32;
+                        commands.resize_entity(entity, size.with_x(width));
+                    }
+                }
+                let readopted = adopted.map(|stone| stone.index.min(strip.len()));
+
+                // Attempt inserting the window at a pre-defined position.
+                let insert_at = properties
+                    .insertion()
+                    .or(torn_off)
+                    .or(readopted)
+                    .map_or_else(
+                        || {
+                            // Otherwise attempt inserting it after the current focus.
+                            let focused_window = windows.focused();
+                            // Insert to the right of the currently focused window
+                            focused_window
+                                .and_then(|(_, entity)| strip.index_of(entity).ok())
+                                .and_then(|insert_at| {
+                                    (insert_at + 1 < strip.len()).then_some(insert_at + 1)
+                                })
+                        },
+                        Some,
+                    );
 
-            debug!("New window {entity} adding at {}", *strip);
-            match insert_at {
-                Some(after) => {
-                    debug!("New window inserted at {after}");
-                    strip.insert_at(after, entity);
+                debug!("New window {entity} adding at {}", *strip);
+                match insert_at {
+                    Some(after) => {
+                        debug!("New window inserted at {after}");
+                        strip.insert_at(after, entity);
+                    }
+                    None => strip.append(entity),
                 }
-                None => strip.append(entity),
             }
         }
 
         // During init, skip per-window reshuffles. finish_setup does a single
         // reshuffle after all windows are added.
         if initializing.is_none() {
-            if properties.dont_focus() {
+            if properties.dont_focus() || properties.prevent_focus_steal() {
                 if let Some((focus, prev)) = windows.focused() {
                     debug!(
                         "Not focusing new window {entity}, keeping focus on '{}'",
                         focus.title().unwrap_or_default()
                     );
                     commands.focus_entity(prev, true);
+                    if properties.prevent_focus_steal()
+                        && let Ok(mut entity_commands) = commands.get_entity(entity)
+                    {
+                        entity_commands.try_insert(FocusStealGuard {
+                            previous: prev,
+                            since: Instant::now(),
+                        });
+                    }
                 }
             } else {
                 debug!("Synthesizing WindowFocused for newly spawned window {entity}");
@@ -1110,68 +1512,186 @@ pub(super) fn apply_window_positions(
     }
 }
 
+/// Returns the display holding most of `frame`, and whether it is left out of
+/// `managed_displays`.
+fn owning_display<'a>(
+    frame: IRect,
+    displays: &'a Query<(&Display, Has<UnmanagedDisplay>)>,
+) -> Option<(&'a Display, bool)> {
+    let displays = displays.iter().collect::<Vec<_>>();
+    most_overlapping(frame, displays.iter().map(|(display, _)| display.bounds()))
+        .map(|index| displays[index])
+}
+
+/// Returns `true` if most of `frame` lies on a display left out of
+/// `managed_displays`.
+fn on_unmanaged_display(frame: IRect, displays: &Query<(&Display, Has<UnmanagedDisplay>)>) -> bool {
+    owning_display(frame, displays).is_some_and(|(_, unmanaged)| unmanaged)
+}
+
+/// Resolves the space number of a window rule to the ID of that space on `display`.
+fn rule_space_id(
+    display: &Display,
+    space: usize,
+    window_manager: &WindowManager,
+) -> Option<WorkspaceId> {
+    let spaces = window_manager
+        .display_spaces(display.id())
+        .inspect_err(|err| warn!("unable to list spaces of display {}: {err}", display.id()))
+        .ok()?;
+    let space_id = space
+        .checked_sub(1)
+        .and_then(|index| spaces.get(index).copied());
+    if space_id.is_none() {
+        warn!("display {} has no space {space}", display.id());
+    }
+    space_id
+}
+
+/// Finds the window a new window was torn off from: a sibling from the same application
+/// whose native tab bar lost a tab. Refreshes the tab counts of the siblings on the way.
+fn torn_off_origin(
+    entity: Entity,
+    app_entity: Entity,
+    strip: &LayoutStrip,
+    windows: &Windows,
+    commands: &mut Commands,
+) -> Option<Entity> {
+    let mut origin = None;
+    for (window, sibling, child_of) in windows.managed_iter() {
+        if sibling == entity || child_of.parent() != app_entity || !strip.contains(sibling) {
+            continue;
+        }
+        let Some(known) = windows.native_tabs(sibling) else {
+            continue;
+        };
+        // The tab bar of some apps disappears along with the second to last tab.
+        let count = window.native_tab_count();
+        if count == Some(known) {
+            continue;
+        }
+        if let Ok(mut entity_commands) = commands.get_entity(sibling) {
+            match count {
+                Some(count) => entity_commands.try_insert(NativeTabs(count)),
+                None => entity_commands.try_remove::<NativeTabs>(),
+            };
+        }
+        if origin.is_none() && count.unwrap_or(1) < known {
+            debug!("window {entity} was torn off {sibling}");
+            origin = Some(sibling);
+        }
+    }
+    origin
+}
+
 #[allow(clippy::needless_pass_by_value)]
 pub(super) fn refresh_configuration_trigger(
     mut messages: MessageReader<Event>,
     window_manager: Res<WindowManager>,
     mut config: ResMut<Config>,
     mut watcher: Option<NonSendMut<Box<dyn Watcher>>>,
+    mut platform: Option<NonSendMut<Pin<Box<PlatformCallbacks>>>>,
     windows: Windows,
     mut displays: Query<&mut Display>,
     applications: Query<&Application>,
+    mut commands: Commands,
 ) {
     for event in messages.read() {
-        let Event::ConfigRefresh(event) = event else {
-            continue;
-        };
-
-        let Some(ref mut watcher) = watcher else {
-            continue;
-        };
+        let (paths, profile) = match event {
+            Event::ConfigRefresh(event) => {
+                let Some(ref mut watcher) = watcher else {
+                    continue;
+                };
 
-        match &event.kind {
-            EventKind::Modify(
-                // When using the RecommendedWatcher, the event triggers on file data.
-                // When using PollWatcher, it triggers on modification time.
-                ModifyKind::Metadata(MetadataKind::WriteTime)
-                | ModifyKind::Data(DataChange::Content),
-            ) => (),
-            EventKind::Remove(_) => {
-                for path in &event.paths {
-                    _ = watcher.unwatch(path).inspect_err(|err| {
-                        error!("unwatching the config '{}': {err}", path.display());
-                    });
+                match &event.kind {
+                    EventKind::Modify(
+                        // When using the RecommendedWatcher, the event triggers on file data.
+                        // When using PollWatcher, it triggers on modification time.
+                        ModifyKind::Metadata(MetadataKind::WriteTime)
+                        | ModifyKind::Data(DataChange::Content),
+                    ) => (),
+                    EventKind::Remove(_) => {
+                        for path in &event.paths {
+                            _ = watcher.unwatch(path).inspect_err(|err| {
+                                error!("unwatching the config '{}': {err}", path.display());
+                            });
+                        }
+                        continue;
+                    }
+                    _ => continue,
                 }
-                continue;
+                (event.paths.as_slice(), None)
             }
+            Event::Command {
+                command: Command::Profile(profile),
+            } => (&[][..], Some(profile.as_deref())),
             _ => continue,
+        };
+
+        // Included files are merged into the main one, so any change reloads
+        // the whole configuration starting from the main file.
+        let previous_files = config.files();
+        let previous_rules = window_rule_outcomes(&windows, &applications, &config);
+        let previous_bindings = config.bindings_summary();
+        let previous_hotkeys = config.rescue_hotkeys();
+        let reloaded = if let Some(profile) = profile {
+            info!(
+                "Switching to configuration profile '{}'",
+                profile.unwrap_or("default")
+            );
+            config.switch_profile(CONFIGURATION_FILE.as_path(), profile)
+        } else {
+            info!(
+                "Reloading configuration file; {}",
+                CONFIGURATION_FILE.display()
+            );
+            config.reload_config(CONFIGURATION_FILE.as_path())
+        };
+        if let Err(err) = reloaded {
+            error!("loading config '{}': {err}", CONFIGURATION_FILE.display());
+            commands.trigger(SendMessageTrigger(Event::Alert {
+                title: "Paneru configuration error".to_string(),
+                message: format!("{err}. The previous configuration stays in effect."),
+            }));
         }
 
-        for path in &event.paths {
-            if let Some(symlink) = symlink_target(path) {
-                debug!(
-                    "symlink '{}' changed, replacing the watcher.",
-                    symlink.display()
-                );
-                if let Ok(new_watcher) =
-                    window_manager
-                        .setup_config_watcher(path)
-                        .inspect_err(|err| {
-                            error!("watching the config '{}': {err}", path.display());
-                        })
-                {
-                    **watcher = new_watcher;
-                }
+        let bindings = config.bindings_summary();
+        if bindings != previous_bindings {
+            info!("bindings reloaded, {} configured", bindings.len());
+            commands.trigger(SendMessageTrigger(Event::BindingsReloaded {
+                count: bindings.len(),
+            }));
+        }
+        if let Some(ref mut platform) = platform
+            && config.rescue_hotkeys() != previous_hotkeys
+        {
+            debug!("rescue hotkeys changed, registering them again.");
+            platform.register_rescue_hotkeys(&config);
+        }
+
+        // Includes could have been added or removed, or a symlink replaced.
+        let files = config.files();
+        if let Some(ref mut watcher) = watcher
+            && (files != previous_files || paths.iter().any(|path| symlink_target(path).is_some()))
+        {
+            debug!("configuration files changed, replacing the watcher.");
+            if let Ok(new_watcher) = window_manager
+                .setup_config_watcher(&files)
+                .inspect_err(|err| error!("watching the config files: {err}"))
+            {
+                **watcher = new_watcher;
             }
-            info!("Reloading configuration file; {}", path.display());
-            _ = config.reload_config(path.as_path()).inspect_err(|err| {
-                error!("loading config '{}': {err}", path.display());
-            });
+        }
+
+        if config.retroactive_window_rules() {
+            commands.trigger(ReapplyWindowRules(previous_rules));
         }
 
         let height = config.menubar_height();
         for mut display in &mut displays {
-            display.set_menubar_height_override(height);
+            if display.menubar_height_override() != height {
+                display.set_menubar_height_override(height);
+            }
         }
 
         // Recompute passthrough keys for the currently focused window.
@@ -1185,6 +1705,114 @@ pub(super) fn refresh_configuration_trigger(
     }
 }
 
+/// Evaluates the window rules for all the windows, so they can be compared after a reload.
+fn window_rule_outcomes(
+    windows: &Windows,
+    applications: &Query<&Application>,
+    config: &Config,
+) -> Vec<(Entity, WindowRuleOutcome)> {
+    windows
+        .iter()
+        .filter_map(|(window, entity)| {
+            let (_, _, parent) = windows.find_parent(window.id())?;
+            let app = applications.get(parent).ok()?;
+            Some((entity, WindowProperties::new(app, window, config).outcome()))
+        })
+        .collect()
+}
+
+/// Applies the window rules which changed with a configuration reload to the existing windows.
+/// Only rules whose outcome differs from the previous configuration are applied, so a window
+/// the user floated or resized by hand is left alone unless its own rules changed.
+#[allow(clippy::needless_pass_by_value)]
+pub(super) fn reapply_window_rules_trigger(
+    trigger: On<ReapplyWindowRules>,
+    windows: Windows,
+    apps: Query<&Application>,
+    mut workspaces: Query<(&mut LayoutStrip, &ChildOf)>,
+    displays: Query<(&Display, Option<&DockPosition>)>,
+    config: Res<Config>,
+    mut commands: Commands,
+) {
+    for (entity, previous) in &trigger.event().0 {
+        let Some((window, _, unmanaged)) = windows.get_managed(*entity) else {
+            continue;
+        };
+        let Some(app) = windows
+            .find_parent(window.id())
+            .and_then(|(_, _, parent)| apps.get(parent).ok())
+        else {
+            continue;
+        };
+        let current = WindowProperties::new(app, window, &config).outcome();
+        if current == *previous {
+            continue;
+        }
+        debug!(
+            "Window rules for '{}' changed, reapplying.",
+            window.title().unwrap_or_default()
+        );
+
+        match unmanaged {
+            None if current.floating && !previous.floating => {
+                // window_unmanaged_trigger takes it out of the strip.
+                let previous_strip = workspaces.iter().find_map(|(strip, _)| {
+                    strip
+                        .index_of(*entity)
+                        .ok()
+                        .map(|index| PreviousManagedStrip {
+                            workspace_id: strip.id(),
+                            virtual_index: strip.virtual_index,
+                            index,
+                            width_ratio: windows.width_ratio(*entity),
+                        })
+                });
+                if let Ok(mut entity_commands) = commands.get_entity(*entity) {
+                    if let Some(previous_strip) = previous_strip {
+                        entity_commands.try_insert(previous_strip);
+                    }
+                    entity_commands.try_insert(Unmanaged::Floating);
+                }
+            }
+            None => {
+                if let Some(width_ratio) = current.width_ratio
+                    && current.width_ratio != previous.width_ratio
+                    && let Some(size) = windows.size(*entity)
+                    && let Some((display, dock)) = workspaces
+                        .iter()
+                        .find(|(strip, _)| strip.contains(*entity))
+                        .and_then(|(_, child_of)| displays.get(child_of.parent()).ok())
+                {
+                    let bounds = display.actual_display_bounds(dock, &config);
+                    let (_, pad_right, _, pad_left) = config.edge_padding();
+                    let padded_width = bounds.width() - pad_left - pad_right;
+                    let width = (f64::from(padded_width) * width_ratio).round() as i32;
+                    commands.resize_entity(*entity, Size::new(width, size.y));
+                }
+                if let Some(index) = current.insertion
+                    && current.insertion != previous.insertion
+                    && let Some((mut strip, _)) = workspaces
+                        .iter_mut()
+                        .find(|(strip, _)| strip.contains(*entity))
+                    && let Ok(from) = strip.index_of(*entity)
+                {
+                    // Like `set index`, this moves the whole column, keeping stacks intact.
+                    let to = index.min(strip.len().saturating_sub(1));
+                    strip.move_column(from, to);
+                }
+                commands.reshuffle_around(*entity);
+            }
+            Some(Unmanaged::Floating) if previous.floating && !current.floating => {
+                // window_managed_trigger tiles it again, honoring the width and index rules.
+                if let Ok(mut entity_commands) = commands.get_entity(*entity) {
+                    entity_commands.try_remove::<Unmanaged>();
+                }
+            }
+            Some(_) => (),
+        }
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 pub(super) fn window_removal_trigger(
     trigger: On<Remove, Window>,
@@ -1266,11 +1894,13 @@ pub(super) fn window_resize_verifier(
         // note that those windows might have max window constraints or isn't resiable.
         // so we need to ignore cases where windows are failing to expand to the target.
         if width_ord == Ordering::Less {
+            // Side by side, the windows of a column do not share its width.
             let Some(column) = layout_strips.iter().find_map(|strip| {
                 strip
                     .index_of(entity)
                     .ok()
                     .and_then(|idx| strip.get(idx).ok())
+                    .filter(|column| strip.orientation(column) == ColumnOrientation::Vertical)
             }) else {
                 continue;
             };
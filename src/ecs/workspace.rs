@@ -6,18 +6,19 @@ use bevy::ecs::hierarchy::ChildOf;
 use bevy::ecs::lifecycle::Add;
 use bevy::ecs::message::MessageReader;
 use bevy::ecs::observer::On;
-use bevy::ecs::query::{Added, Has, With, Without};
+use bevy::ecs::query::{Added, Changed, Has, With, Without};
+use bevy::ecs::resource::Resource;
 use bevy::ecs::schedule::IntoScheduleConfigs as _;
 use bevy::ecs::schedule::common_conditions::{not, resource_exists};
 use bevy::ecs::system::{Commands, Local, ParamSet, Populated, Query, Res, ResMut, Single};
-use bevy::time::common_conditions::on_timer;
+use bevy::time::Time;
 use std::collections::HashSet;
 use std::time::Duration;
 use tracing::{Level, debug, error, instrument, warn};
 
 use super::{ActiveDisplayMarker, SpawnWindowTrigger};
 use crate::commands::{Direction, MoveFocus, Operation, filter_window_operations};
-use crate::config::Config;
+use crate::config::{Config, DEFAULT_POLL_INTERVAL, OverflowColumn};
 use crate::ecs::focus::FocusHistory;
 use crate::ecs::layout::LayoutStrip;
 use crate::ecs::params::{ActiveDisplay, Windows};
@@ -35,16 +36,18 @@ pub struct WorkspaceEventsPlugin;
 
 impl Plugin for WorkspaceEventsPlugin {
     fn build(&self, app: &mut App) {
-        const REFRESH_WINDOW_CHECK_FREQ_MS: u64 = 1000;
-        const DISPLAY_CHANGE_CHECK_FREQ_MS: u64 = 1000;
-
         let reap_workspaces = |config: Option<Res<Config>>| {
             config.is_some_and(|config| config.reap_empty_workspaces())
         };
 
+        app.init_resource::<PollBoost>();
         app.add_systems(
             PreUpdate,
-            (switch_virtual_workspace_bind, move_virtual_workspace_bind),
+            (
+                switch_virtual_workspace_bind,
+                move_virtual_workspace_bind,
+                boost_polling_on_input,
+            ),
         );
         app.add_systems(
             Update,
@@ -54,16 +57,15 @@ impl Plugin for WorkspaceEventsPlugin {
                 workspace_change_handler,
                 workspace_created_handler,
                 show_active_workspace,
+                paginate_overflowing_strip
+                    .before(handle_virtual_window_moves)
+                    .run_if(not(resource_exists::<Initializing>)),
                 handle_virtual_window_moves,
                 detect_moved_windows.run_if(not(resource_exists::<Initializing>)),
-                refresh_workspace_window_sizes.run_if(on_timer(Duration::from_millis(
-                    REFRESH_WINDOW_CHECK_FREQ_MS,
-                ))),
+                refresh_workspace_window_sizes.run_if(poll_due),
                 find_orphaned_workspaces
                     .after(crate::ecs::display::reconcile_displays)
-                    .run_if(on_timer(Duration::from_millis(
-                        DISPLAY_CHANGE_CHECK_FREQ_MS,
-                    ))),
+                    .run_if(poll_due),
             ),
         );
         app.add_systems(PostUpdate, workspace_destroyed_handler);
@@ -72,6 +74,60 @@ impl Plugin for WorkspaceEventsPlugin {
     }
 }
 
+/// How long the periodic checks keep running at the faster interval after input.
+const POLL_BOOST_DURATION: Duration = Duration::from_secs(2);
+
+/// Remaining time during which the periodic checks run at the faster interval,
+/// because user input is likely to be followed by window or space changes.
+#[derive(Default, Resource)]
+struct PollBoost(Duration);
+
+#[allow(clippy::needless_pass_by_value)]
+fn boost_polling_on_input(
+    mut messages: MessageReader<Event>,
+    time: Res<Time>,
+    mut boost: ResMut<PollBoost>,
+) {
+    let input = messages.read().any(|event| {
+        matches!(
+            event,
+            Event::Command { .. }
+                | Event::MouseDown { .. }
+                | Event::MouseUp { .. }
+                | Event::TouchpadUp
+        )
+    });
+    boost.0 = if input {
+        POLL_BOOST_DURATION
+    } else {
+        boost.0.saturating_sub(time.delta())
+    };
+}
+
+/// Run condition pacing the periodic checks which catch window and space changes
+/// the OS did not notify about. Each system using it keeps its own timer.
+#[allow(clippy::needless_pass_by_value)]
+fn poll_due(
+    mut elapsed: Local<Duration>,
+    time: Res<Time>,
+    boost: Res<PollBoost>,
+    config: Option<Res<Config>>,
+) -> bool {
+    let interval = config.map_or(DEFAULT_POLL_INTERVAL, |config| {
+        if boost.0.is_zero() {
+            config.poll_interval()
+        } else {
+            config.poll_interval_active()
+        }
+    });
+    *elapsed += time.delta();
+    if *elapsed < interval {
+        return false;
+    }
+    *elapsed = Duration::ZERO;
+    true
+}
+
 /// Marker component to move a window to a specific virtual index on its current workspace.
 #[derive(Component)]
 struct VirtualMoveMarker {
@@ -270,11 +326,14 @@ fn detect_moved_windows(
     }
 }
 
+/// Removes the strips of a destroyed space. A space spanning all displays has
+/// a strip on each of them.
 #[allow(clippy::needless_pass_by_value)]
 #[instrument(level = Level::DEBUG, skip_all, fields(trigger))]
 fn workspace_destroyed_handler(
     mut messages: MessageReader<Event>,
     mut workspaces: Populated<(&mut LayoutStrip, Entity, Option<&NativeFullscreenMarker>)>,
+    window_manager: Res<WindowManager>,
     mut focus_history: ResMut<FocusHistory>,
     mut commands: Commands,
 ) {
@@ -282,58 +341,71 @@ fn workspace_destroyed_handler(
         let Event::SpaceDestroyed { space_id } = event else {
             continue;
         };
-        focus_history.forget_workspace(*space_id);
+        for workspace_id in window_manager.space_workspaces(*space_id) {
+            focus_history.forget_workspace(workspace_id);
+            remove_workspace(workspace_id, &mut workspaces, &mut commands);
+        }
+    }
+}
 
-        let Some((entity, fullscreen)) =
-            workspaces.iter().find_map(|(strip, entity, fullscreen)| {
-                let window = strip.first().ok().and_then(|col| col.top());
-                (strip.id() == *space_id).then_some((entity, window.zip(fullscreen.cloned())))
-            })
-        else {
-            continue;
-        };
+/// Despawns the strip of a workspace, returning a native fullscreen window on
+/// it to the strip it came from.
+fn remove_workspace(
+    workspace_id: WorkspaceId,
+    workspaces: &mut Query<(&mut LayoutStrip, Entity, Option<&NativeFullscreenMarker>)>,
+    commands: &mut Commands,
+) {
+    let Some((entity, fullscreen)) = workspaces.iter().find_map(|(strip, entity, fullscreen)| {
+        let window = strip.first().ok().and_then(|col| col.top());
+        (strip.id() == workspace_id).then_some((entity, window.zip(fullscreen.cloned())))
+    }) else {
+        return;
+    };
 
-        if let Some((
-            window,
-            NativeFullscreenMarker {
-                layout_strip,
-                workspace_id,
-                index,
-            },
-        )) = fullscreen
-        {
-            let mut strip = workspaces
+    if let Some((
+        window,
+        NativeFullscreenMarker {
+            layout_strip,
+            workspace_id,
+            index,
+        },
+    )) = fullscreen
+    {
+        let mut strip = workspaces
+            .iter_mut()
+            .find_map(|(strip, entity, _)| (entity == layout_strip).then_some(strip));
+        if strip.is_none() {
+            strip = workspaces
                 .iter_mut()
-                .find_map(|(strip, entity, _)| (entity == layout_strip).then_some(strip));
-            if strip.is_none() {
-                strip = workspaces
-                    .iter_mut()
-                    .find_map(|(strip, _, _)| (strip.id() == workspace_id).then_some(strip));
-            }
-
-            debug!(
-                "previously fullscreened window {entity} inserted at {}",
-                index
-            );
-            if let Some(mut strip) = strip {
-                strip.insert_at(index, window);
-                commands.reshuffle_around(window);
-            }
+                .find_map(|(strip, _, _)| (strip.id() == workspace_id).then_some(strip));
         }
 
-        if let Ok(mut entity_commands) = commands.get_entity(entity) {
-            debug!("Workspace destroyed {space_id} {entity}");
-            entity_commands.try_despawn();
+        debug!(
+            "previously fullscreened window {entity} inserted at {}",
+            index
+        );
+        if let Some(mut strip) = strip {
+            strip.insert_at(index, window);
+            commands.reshuffle_around(window);
         }
     }
+
+    if let Ok(mut entity_commands) = commands.get_entity(entity) {
+        debug!("Workspace destroyed {workspace_id} {entity}");
+        entity_commands.try_despawn();
+    }
 }
 
+/// Adds the strips of a new space, to the display listing it. A space spanning
+/// all displays gets a strip on each of them.
 #[allow(clippy::needless_pass_by_value)]
 #[instrument(level = Level::DEBUG, skip_all, fields(trigger))]
 fn workspace_created_handler(
     mut messages: MessageReader<Event>,
     active_display: Single<(&Display, Entity), With<ActiveDisplayMarker>>,
+    displays: Query<(&Display, Entity)>,
     workspaces: Query<&LayoutStrip>,
+    window_manager: Res<WindowManager>,
     mut commands: Commands,
 ) {
     for event in messages.read() {
@@ -341,15 +413,29 @@ fn workspace_created_handler(
             continue;
         };
 
-        if workspaces.into_iter().any(|strip| strip.id() == *space_id) {
-            warn!("Workspace {space_id} already exists!");
-            continue;
+        let present = window_manager.present_displays();
+        for workspace_id in window_manager.space_workspaces(*space_id) {
+            if workspaces
+                .into_iter()
+                .any(|strip| strip.id() == workspace_id)
+            {
+                warn!("Workspace {workspace_id} already exists!");
+                continue;
+            }
+            debug!("Workspace create {workspace_id}");
+            let (display, display_entity) = present
+                .iter()
+                .find(|(_, workspace_ids)| workspace_ids.contains(&workspace_id))
+                .and_then(|(listing, _)| {
+                    displays
+                        .iter()
+                        .find(|(display, _)| display.id() == listing.id())
+                })
+                .unwrap_or(*active_display);
+            let strip = LayoutStrip::new(workspace_id, 0);
+            let origin = display.bounds().min;
+            commands.spawn_layout_strip(strip, origin, display_entity, false);
         }
-        debug!("Workspace create {space_id}");
-        let (active_display, display_entity) = *active_display;
-        let strip = LayoutStrip::new(*space_id, 0);
-        let origin = active_display.bounds().min;
-        commands.spawn_layout_strip(strip, origin, display_entity, false);
     }
 }
 
@@ -580,7 +666,7 @@ fn handle_virtual_window_moves(
     config: Res<Config>,
     mut commands: Commands,
 ) {
-    let Some((workspace_id, source_entity)) = workspaces
+    let Some((workspace_id, active_entity)) = workspaces
         .iter()
         .find_map(|(entity, strip, _, active, _)| active.then_some((strip.id(), entity)))
     else {
@@ -590,6 +676,13 @@ fn handle_virtual_window_moves(
     let (display_entity, active_display, dock) = *active_display;
     let viewport = active_display.actual_display_bounds(dock, &config);
     for (window_entity, move_marker) in &moved_windows {
+        // Pagination can move a window between two hidden strips of the space.
+        let source_entity = workspaces
+            .iter()
+            .find_map(|(entity, strip, _, _, _)| {
+                (strip.id() == workspace_id && strip.contains(window_entity)).then_some(entity)
+            })
+            .unwrap_or(active_entity);
         let moving_entities = workspaces
             .get(source_entity)
             .ok()
@@ -624,9 +717,8 @@ fn handle_virtual_window_moves(
         // With `insert_windows_mid_strip`, the window keeps its current on-screen
         // x. For an existing destination, work out the column slot nearest that x
         // and the scroll offset that lands it there; for a new strip the lone
-        // window just sits at that x.
-        let moved_left = config
-            .insert_windows_mid_strip()
+        // window just sits at that x. A window of a hidden strip has no such x.
+        let moved_left = (config.insert_windows_mid_strip() && source_entity == active_entity)
             .then(|| windows.moving_frame(window_entity).map(|frame| frame.min.x))
             .flatten();
         let mid_placement = moved_left.and_then(|moved_left| {
@@ -714,7 +806,12 @@ fn handle_virtual_window_moves(
 
         if stay && let Some(neighbour) = source_neighbour {
             // Layout chain repositions the window offscreen with its hidden strip.
-            commands.focus_entity(neighbour, false);
+            match windows.focused() {
+                Some((_, focused)) if !moving_entities.contains(&focused) => {
+                    commands.reshuffle_around(focused);
+                }
+                _ => commands.focus_entity(neighbour, false),
+            }
 
             // Force position change on the hidden strip, so it hides the moved window.
             if let Ok(mut position) = workspaces
@@ -740,6 +837,85 @@ fn handle_virtual_window_moves(
     }
 }
 
+/// Moves a column of an overflowing strip of the active space to the next
+/// virtual workspace, creating it if needed, once the strip has more columns
+/// than `strip_max_columns` or is wider than `strip_max_width` display widths.
+/// Hidden strips are checked too, as moving a window there with
+/// `MoveFocus::Stay` can overflow them. The focus follows the column only when
+/// it was focused, so opening a window can page the strip along.
+#[allow(clippy::needless_pass_by_value)]
+fn paginate_overflowing_strip(
+    changed: Query<(), Changed<LayoutStrip>>,
+    strips: Query<&LayoutStrip>,
+    moving: Query<(), With<VirtualMoveMarker>>,
+    active_display: ActiveDisplay,
+    windows: Windows,
+    config: Res<Config>,
+    mut commands: Commands,
+) {
+    let (max_columns, max_width) = (config.strip_max_columns(), config.strip_max_width());
+    if changed.is_empty() || !moving.is_empty() || (max_columns.is_none() && max_width.is_none()) {
+        return;
+    }
+
+    let viewport_width = active_display.actual_bounds(&config).width();
+    let overflows = |strip: &LayoutStrip| {
+        let strip_width: i32 = strip
+            .all_columns()
+            .into_iter()
+            .filter_map(|column| windows.size(column))
+            .map(|size| size.x)
+            .sum();
+        strip.len() > 1
+            && (max_columns.is_some_and(|max| strip.len() > max)
+                || max_width
+                    .is_some_and(|max| f64::from(strip_width) > max * f64::from(viewport_width)))
+    };
+    // One column moves at a time, from the lowest overflowing strip, so that
+    // a cascade into the following strips never creates the same one twice.
+    let workspace_id = active_display.active_strip().id();
+    let Some(strip) = strips
+        .iter()
+        .filter(|strip| strip.id() == workspace_id && overflows(strip))
+        .min_by_key(|strip| strip.virtual_index)
+    else {
+        return;
+    };
+
+    let column = match config.strip_overflow_column() {
+        OverflowColumn::First => strip.first(),
+        OverflowColumn::Last => strip.last(),
+    };
+    // Tabs move along with their top window. Of a stack only the top window
+    // moves at a time, the rest follow while the strip still overflows.
+    let Ok(column) = column else {
+        return;
+    };
+    let Some(entity) = column.top() else {
+        return;
+    };
+    let focused = windows.focused().map(|(_, entity)| entity);
+    let move_focus = if column.window_iter().any(|entity| Some(entity) == focused) {
+        MoveFocus::Follow
+    } else {
+        MoveFocus::Stay
+    };
+    let target_virtual_index = strip.virtual_index + 1;
+    debug!(
+        "strip {} overflows, moving a column to {target_virtual_index}",
+        strip.id()
+    );
+    if let Ok(mut entity_commands) = commands.get_entity(entity) {
+        entity_commands.try_insert(VirtualMoveMarker {
+            target_virtual_index,
+            move_focus,
+        });
+    }
+    if move_focus == MoveFocus::Follow && config.workspace_popup_status() {
+        commands.flash_message(format!("{}", target_virtual_index + 1), 1.0);
+    }
+}
+
 /// Picks where in `strip` a window currently at on-screen x `moved_left` should
 /// be inserted so it keeps that position. Inserting at column `i` lands the
 /// window at that column's left edge, so we choose the column boundary nearest
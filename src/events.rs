@@ -6,7 +6,7 @@ use std::os::unix::net::UnixStream;
 use std::sync::mpsc::{Receiver, Sender, channel};
 use std::sync::{Arc, Mutex};
 
-use crate::commands::Command;
+use crate::commands::{Command, Expectation, PropertyRequest, WindowTarget};
 use crate::config::Config;
 use crate::ecs::state::StateQueryKind;
 use crate::errors::Result;
@@ -119,6 +119,9 @@ pub enum Event {
     DisplayResized { display_id: CGDirectDisplayID },
     /// A display's configuration has changed.
     DisplayConfigured { display_id: CGDirectDisplayID },
+    /// A display became the main display, which moves the origin of the global
+    /// coordinates and the menubar.
+    MainDisplayChanged { display_id: CGDirectDisplayID },
     /// The overall display arrangement has changed.
     DisplayChanged,
 
@@ -144,6 +147,12 @@ pub enum Event {
     MenuBarHiddenChanged { msg: String },
     /// The system has woken from sleep.
     SystemWoke { msg: String },
+    /// Another user's login session became active, by fast user switching.
+    SessionResigned,
+    /// The login session of the daemon is active again.
+    SessionActivated,
+    /// The Mac switched between its battery and AC power.
+    PowerSourceChanged { on_battery: bool },
 
     /// The system appearance (Light/Dark mode) has changed.
     ThemeChanged,
@@ -151,6 +160,12 @@ pub enum Event {
     /// A command has been issued to the window manager.
     Command { command: Command },
 
+    /// A configuration reload changed the bindings, of which there are now `count`.
+    BindingsReloaded { count: usize },
+
+    /// A critical problem the user should act on, shown as a macOS notification.
+    Alert { title: String, message: String },
+
     /// A structured state query has been issued by a socket client.
     StateQuery {
         kind: StateQueryKind,
@@ -159,6 +174,25 @@ pub enum Event {
 
     /// A socket client has subscribed to line-delimited state events.
     StateSubscribe { stream: Arc<Mutex<UnixStream>> },
+
+    /// A socket client waits for the next click on a window, to describe that window.
+    InspectRequest { respond_to: Sender<String> },
+
+    /// The mouse was clicked while inspecting windows. The click does not reach the window.
+    InspectClick { point: CGPoint },
+
+    /// The green zoom button of a window was clicked. The click does not reach the window.
+    ZoomButtonClicked { point: CGPoint },
+
+    /// A socket client reads or changes a property of a window.
+    WindowPropertyRequest {
+        target: WindowTarget,
+        request: PropertyRequest,
+        respond_to: Sender<Result<String>>,
+    },
+
+    /// A socket client waits for a window to open, to run actions on it.
+    ExpectWindow { expectation: Expectation },
 }
 
 /// `EventSender` is a thin wrapper around a `std::sync::mpsc::Sender` for `Event`s.
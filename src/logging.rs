@@ -0,0 +1,112 @@
+use std::sync::OnceLock;
+
+use tracing_subscriber::filter::Directive;
+use tracing_subscriber::{EnvFilter, Registry, fmt, prelude::*, reload};
+
+use crate::config::{Config, discover_configuration_file};
+use crate::errors::{Error, Result};
+
+/// Crate prefix of all the module targets, so `ecs::focus=debug` can be used
+/// instead of spelling out `paneru::ecs::focus=debug`.
+const TARGET_PREFIX: &str = "paneru";
+
+/// Handle to the active log filter, used to swap it while the daemon is running.
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Sets up the global tracing subscriber. The filter is taken from `RUST_LOG`
+/// (defaulting to `info`) and can later be adjusted with `set_log_level`.
+/// JSON output is selected by the `log_json` option in the configuration file.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let (filter, handle) = reload::Layer::new(filter);
+    let registry = tracing_subscriber::registry().with(filter);
+
+    let json = discover_configuration_file()
+        .and_then(|path| Config::read_options(&path).ok())
+        .is_some_and(|config| config.log_json());
+    if json {
+        registry
+            .with(
+                fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(true)
+                    .with_file(true)
+                    .with_line_number(true)
+                    .with_writer(std::io::stderr),
+            )
+            .init();
+    } else {
+        registry
+            .with(
+                fmt::layer()
+                    .with_level(true)
+                    .with_line_number(true)
+                    .with_file(true)
+                    .with_target(true)
+                    .with_thread_ids(false)
+                    .with_writer(std::io::stderr)
+                    .compact(),
+            )
+            .init();
+    }
+    _ = FILTER_HANDLE.set(handle);
+}
+
+/// Adds filtering directives to the running log filter, e.g. `ecs::focus=debug`
+/// or a bare `trace` to change the default level. Later directives for the same
+/// module override earlier ones.
+pub fn set_log_level<'a>(directives: impl IntoIterator<Item = &'a str>) -> Result<()> {
+    let directives = directives
+        .into_iter()
+        .map(parse_directive)
+        .collect::<Result<Vec<_>>>()?;
+    if directives.is_empty() {
+        return Err(Error::InvalidInput(
+            "expected <module>=<level> directives".to_string(),
+        ));
+    }
+
+    let handle = FILTER_HANDLE
+        .get()
+        .ok_or_else(|| Error::NotFound("log filter is not initialized".to_string()))?;
+    handle.modify(|filter| {
+        *filter = directives
+            .into_iter()
+            .fold(std::mem::take(filter), EnvFilter::add_directive);
+    })?;
+    Ok(())
+}
+
+/// Parses a single directive, qualifying the module path with the crate name
+/// when it is not already.
+fn parse_directive(input: &str) -> Result<Directive> {
+    let qualified = match input.split_once('=') {
+        Some((module, level)) if module != TARGET_PREFIX && !module.starts_with("paneru::") => {
+            format!("{TARGET_PREFIX}::{module}={level}")
+        }
+        _ => input.to_string(),
+    };
+    qualified
+        .parse::<Directive>()
+        .map_err(|err| Error::InvalidInput(format!("log directive '{input}': {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directive_qualifies_modules() {
+        let directive = parse_directive("ecs::focus=debug").unwrap();
+        assert_eq!(directive.to_string(), "paneru::ecs::focus=debug");
+
+        let directive = parse_directive("paneru::reader=trace").unwrap();
+        assert_eq!(directive.to_string(), "paneru::reader=trace");
+
+        let directive = parse_directive("warn").unwrap();
+        assert_eq!(directive.to_string(), "warn");
+
+        assert!(parse_directive("ecs=loud").is_err());
+    }
+}
@@ -1,19 +1,26 @@
 #![allow(clippy::cast_possible_truncation)]
 
-use clap::{Parser, Subcommand};
+use clap::builder::{PossibleValue, TypedValueParser};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use std::io::Write;
+use std::path::PathBuf;
 use tracing::{error, warn};
-use tracing_subscriber::{EnvFilter, fmt, prelude::*};
 
 mod commands;
 mod config;
 mod ecs;
 mod errors;
 mod events;
+mod logging;
 mod manager;
 mod menubar;
+mod metrics;
 mod overlay;
 mod platform;
 mod reader;
+#[cfg(any(test, feature = "replay"))]
+mod replay;
 mod util;
 
 #[cfg(test)]
@@ -25,7 +32,7 @@ use events::EventSender;
 
 use ecs::state::StateQueryKind;
 use errors::Result;
-use platform::service;
+use platform::{WinID, service};
 use reader::CommandReader;
 
 use crate::ecs::setup_bevy_app;
@@ -42,16 +49,25 @@ pub struct Paneru {
     /// The subcommand to execute (e.g., `launch`, `install`, `send-cmd`).
     #[clap(subcommand)]
     subcmd: Option<SubCmd>,
+
+    /// The command socket of the daemon, overriding the `socket_path` option.
+    /// The daemon and the commands talking to it have to use the same socket.
+    #[arg(long, global = true, value_name = "PATH")]
+    socket: Option<PathBuf>,
 }
 
 /// `SubCmd` enumerates the available command-line subcommands for `paneru`.
 /// These subcommands allow users to launch the daemon, install/uninstall it as a service,
 /// start/stop/restart the service, or send commands to a running daemon.
-#[derive(Clone, Debug, Default, Subcommand)]
+#[derive(Clone, Debug, Subcommand)]
 pub enum SubCmd {
     /// Launches the `paneru` daemon directly in the console (default behavior).
-    #[default]
-    Launch,
+    Launch {
+        /// Records the platform events into a file, one JSON object per line,
+        /// for `paneru replay`.
+        #[arg(long, value_name = "FILE")]
+        record: Option<PathBuf>,
+    },
 
     /// Installs the `paneru` daemon as a background service.
     Install,
@@ -71,12 +87,24 @@ pub enum SubCmd {
     /// Restarts the `paneru` background service.
     Restart,
 
-    /// Sends a command via a Unix socket to the running `paneru` daemon.
+    /// Restarts the running daemon in place, keeping the current layout.
+    RestartDaemon,
+
+    /// Sends a command via a Unix socket to the running `paneru` daemon, e.g.
+    /// `window focus east`, or written like a binding name: `window_focus_east`.
     SendCmd {
-        #[arg(trailing_var_arg = true)]
+        #[arg(
+            trailing_var_arg = true,
+            required = true,
+            value_parser = CommandNameParser,
+            hide_possible_values = true
+        )]
         cmd: Vec<String>,
     },
 
+    /// Prints a completion script for the shell, e.g. `paneru completions zsh > _paneru`.
+    Completions { shell: Shell },
+
     /// Queries structured state from the running daemon.
     Query {
         #[clap(subcommand)]
@@ -88,6 +116,76 @@ pub enum SubCmd {
         #[arg(long)]
         json: bool,
     },
+
+    /// Prints internal counters of the running daemon.
+    Stats {
+        /// Print in the Prometheus text format instead of JSON.
+        #[arg(long)]
+        prometheus: bool,
+    },
+
+    /// Switches the running daemon to a configuration profile, or back to the
+    /// base configuration when no name is given.
+    Profile { name: Option<String> },
+
+    /// Pauses the window management of the running daemon, e.g. while recording
+    /// the screen. `paneru stats` shows whether it is paused.
+    Pause {
+        /// Keep handling the key bindings instead of passing them to the apps.
+        #[arg(long)]
+        keep_bindings: bool,
+    },
+
+    /// Resumes the window management after `paneru pause`.
+    Resume,
+
+    /// Waits for a click on a window, then prints its bundle id, title, role and
+    /// the matching window rules, and copies them to the clipboard.
+    Inspect,
+
+    /// Waits for the next window matching `--bundle` and `--title`, and runs the
+    /// `;`-separated actions of `--then` on it, e.g. `window_set_index 0; window_center`.
+    Expect {
+        #[arg(long)]
+        bundle: Option<String>,
+        /// A regular expression matching the window title.
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(long)]
+        then: String,
+    },
+
+    /// Reads or changes a property of a window, given by its id or `focused`.
+    Window {
+        target: String,
+        #[clap(subcommand)]
+        property: WindowPropertyCmd,
+    },
+
+    /// Adjusts the log verbosity of the running daemon, e.g. `ecs::focus=debug`.
+    LogLevel {
+        #[arg(required = true)]
+        directives: Vec<String>,
+    },
+
+    /// Replays a recording made with `paneru launch --record` against the
+    /// mocked window manager, and prints the strips it ends up with.
+    #[cfg(feature = "replay")]
+    Replay { file: PathBuf },
+}
+
+impl Default for SubCmd {
+    fn default() -> Self {
+        Self::Launch { record: None }
+    }
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum WindowPropertyCmd {
+    /// Prints a property: `width`, `frame`, `floating` or `index`.
+    Get { property: String },
+    /// Changes a property: `width` (a ratio of the display), `floating` or `index`.
+    Set { property: String, value: String },
 }
 
 #[derive(Clone, Debug, Subcommand)]
@@ -107,6 +205,23 @@ pub enum QueryCmd {
         #[arg(long)]
         json: bool,
     },
+    /// Prints the columns of the active strip from left to right.
+    Strip {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Prints the key and mouse bindings, including those of every profile.
+    Keybindings {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Prints every window with when it appeared and how much it was focused.
+    Windows {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Captures a thumbnail of a window and prints the path of the PNG file.
+    Preview { window_id: WinID },
 }
 
 /// The main entry point of the `paneru` application.
@@ -116,38 +231,34 @@ pub enum QueryCmd {
 ///
 /// `Ok(())` if the application runs successfully, otherwise `Err(Error)`.
 fn main() -> Result<()> {
-    tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
-        .with(
-            fmt::layer()
-                .with_level(true)
-                .with_line_number(true)
-                .with_file(true)
-                .with_target(true)
-                .with_thread_ids(false)
-                .with_writer(std::io::stderr)
-                .compact(),
-        )
-        .init();
+    logging::init();
 
     let service = || service::Service::try_new(service::ID);
 
-    let subcmd = Paneru::parse().subcmd.unwrap_or_default();
+    let Paneru { subcmd, socket } = Paneru::parse();
+    if let Some(socket) = socket {
+        CommandReader::set_socket_path(socket);
+    }
+    let subcmd = subcmd.unwrap_or_default();
     maybe_warn_deprecated_options_for_service(&subcmd);
 
     match subcmd {
-        SubCmd::Launch => {
+        SubCmd::Launch { record } => {
             let (sender, receiver) = EventSender::new();
-            let sender_c = sender.clone();
-            // bevy's `TerminalCtrlCHandlerPlugin` was not fast enough. maybe because of its use of `Relaxed` atomic variable?
-            ctrlc::set_handler(move || {
-                let _ = sender_c.send(events::Event::Exit); // just drop the err. we are exiting anyway.
-            })
-            .expect("setting Ctrl-C handler should succeed");
+            platform::signals::forward_signals(sender.clone())?;
             CommandReader::new(sender.clone()).start();
-            match setup_bevy_app(sender, receiver) {
+            platform::wait_for_session();
+            match setup_bevy_app(sender, receiver, record) {
                 Ok(mut app) => {
                     app.run();
+                    drop(app);
+                    if ecs::state::handoff_requested() {
+                        let err = service::Service::exec_handoff(
+                            &ecs::state::PaneruState::handoff_file_path(),
+                            CommandReader::socket_path(),
+                        );
+                        error!("Error re-executing Paneru: {err}");
+                    }
                 }
                 Err(err) => {
                     error!(
@@ -163,22 +274,147 @@ fn main() -> Result<()> {
         SubCmd::Start => service()?.start()?,
         SubCmd::Stop => service()?.stop()?,
         SubCmd::Restart => service()?.restart()?,
-        SubCmd::SendCmd { cmd } => CommandReader::send_command(cmd)?,
+        SubCmd::RestartDaemon => CommandReader::send_command(["restart-daemon".to_string()])?,
+        SubCmd::SendCmd { cmd } => {
+            let cmd = split_command_name(cmd);
+            let argv = cmd.iter().map(String::as_str).collect::<Vec<_>>();
+            // Rejected commands fail here, so scripts see a non-zero exit status.
+            CommandReader::validate_request(&argv)?;
+            CommandReader::send_command(cmd)?;
+        }
+        SubCmd::Completions { shell } => {
+            clap_complete::generate(
+                shell,
+                &mut Paneru::command(),
+                "paneru",
+                &mut std::io::stdout(),
+            );
+        }
+        SubCmd::Profile { name } => {
+            CommandReader::send_command(std::iter::once("profile".to_string()).chain(name))?;
+        }
+        SubCmd::Pause { keep_bindings } => {
+            let args: &[&str] = if keep_bindings {
+                &["pause", "keep", "bindings"]
+            } else {
+                &["pause"]
+            };
+            CommandReader::send_command(args.iter().map(|arg| (*arg).to_string()))?;
+        }
+        SubCmd::Resume => CommandReader::send_command(["resume".to_string()])?,
         SubCmd::Query { query } => {
-            let output = CommandReader::send_query(query.kind())?;
+            let output = match query {
+                QueryCmd::Preview { window_id } => CommandReader::send_preview(window_id)?,
+                query => query
+                    .kind()
+                    .map(CommandReader::send_query)
+                    .transpose()?
+                    .unwrap_or_default(),
+            };
             print!("{output}");
         }
         SubCmd::Subscribe { json: _ } => CommandReader::subscribe_json()?,
+        SubCmd::Stats { prometheus } => {
+            let output = CommandReader::send_stats(prometheus)?;
+            print!("{output}");
+        }
+        SubCmd::LogLevel { directives } => {
+            let output = CommandReader::send_log_level(directives)?;
+            print!("{output}");
+        }
+        SubCmd::Expect {
+            bundle,
+            title,
+            then,
+        } => {
+            let args = [
+                ("--bundle", bundle),
+                ("--title", title),
+                ("--then", Some(then)),
+            ]
+            .into_iter()
+            .filter_map(|(flag, value)| value.map(|value| [flag.to_string(), value]))
+            .flatten();
+            CommandReader::send_expect(args)?;
+        }
+        SubCmd::Window { target, property } => {
+            let args = match property {
+                WindowPropertyCmd::Get { property } => vec![target, "get".to_string(), property],
+                WindowPropertyCmd::Set { property, value } => {
+                    vec![target, "set".to_string(), property, value]
+                }
+            };
+            print!("{}", CommandReader::send_window_property(args)?);
+        }
+        #[cfg(feature = "replay")]
+        SubCmd::Replay { file } => replay::replay_headless(&file)?,
+        SubCmd::Inspect => {
+            println!("Click a window to inspect it.");
+            let output = CommandReader::send_inspect()?;
+            print!("{output}");
+            _ = copy_to_clipboard(&output)
+                .inspect_err(|err| eprintln!("paneru: copying to the clipboard: {err}"));
+        }
+    }
+    Ok(())
+}
+
+/// Offers the command names for completing `send-cmd`. Any word is accepted,
+/// the whole command is validated once all of it is known.
+#[derive(Clone)]
+struct CommandNameParser;
+
+impl TypedValueParser for CommandNameParser {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> std::result::Result<String, clap::Error> {
+        clap::builder::StringValueParser::new().parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            config::COMMAND_NAMES.into_iter().map(PossibleValue::new),
+        ))
+    }
+}
+
+/// Splits a command written like a binding name, e.g. `window_focus_east`,
+/// into its words. Only the first word is split, the arguments are kept.
+fn split_command_name(cmd: Vec<String>) -> Vec<String> {
+    let mut words = cmd.into_iter();
+    let Some(name) = words.next() else {
+        return vec![];
+    };
+    name.split('_').map(str::to_string).chain(words).collect()
+}
+
+fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut child = std::process::Command::new("pbcopy")
+        .stdin(std::process::Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
     }
+    child.wait()?;
     Ok(())
 }
 
 impl QueryCmd {
-    fn kind(&self) -> StateQueryKind {
+    /// Returns the state document to request, or `None` for queries answered outside the ECS.
+    fn kind(&self) -> Option<StateQueryKind> {
         match self {
-            QueryCmd::State { json: _ } => StateQueryKind::State,
-            QueryCmd::VirtualWorkspaces { json: _ } => StateQueryKind::VirtualWorkspaces,
-            QueryCmd::Active { json: _ } => StateQueryKind::Active,
+            QueryCmd::State { json: _ } => Some(StateQueryKind::State),
+            QueryCmd::VirtualWorkspaces { json: _ } => Some(StateQueryKind::VirtualWorkspaces),
+            QueryCmd::Active { json: _ } => Some(StateQueryKind::Active),
+            QueryCmd::Strip { json: _ } => Some(StateQueryKind::Strip),
+            QueryCmd::Keybindings { json: _ } => Some(StateQueryKind::Keybindings),
+            QueryCmd::Windows { json: _ } => Some(StateQueryKind::Windows),
+            QueryCmd::Preview { .. } => None,
         }
     }
 }
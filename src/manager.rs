@@ -1,47 +1,64 @@
-use accessibility_sys::{AXIsProcessTrustedWithOptions, kAXTrustedCheckOptionPrompt};
+use accessibility_sys::{
+    AXIsProcessTrusted, AXIsProcessTrustedWithOptions, AXUIElementCreateSystemWide,
+    kAXErrorCannotComplete, kAXFocusedApplicationAttribute, kAXTrustedCheckOptionPrompt,
+};
 use bevy::ecs::resource::Resource;
 use bevy::math::{IRect, IVec2};
+use block2::RcBlock;
 use core::ptr::NonNull;
 use derive_more::{DerefMut, with_trait::Deref};
 use mockall::automock;
 use notify::{RecursiveMode, Watcher};
+use objc2::runtime::Bool;
+use objc2_app_kit::{NSApplicationActivationOptions, NSRunningApplication};
 use objc2_core_foundation::{
     CFArray, CFDictionary, CFMutableData, CFNumber, CFNumberType, CFRetained, CFString, CFType,
     CGPoint, CGRect, CGSize, kCFBooleanTrue,
 };
 use objc2_core_graphics::{
     CGAssociateMouseAndMouseCursorPosition, CGDirectDisplayID, CGDisplayBounds,
-    CGGetActiveDisplayList, CGWarpMouseCursorPosition, CGWindowListCopyWindowInfo,
-    CGWindowListOption, kCGNullWindowID, kCGWindowNumber,
+    CGDisplayIsCaptured, CGGetActiveDisplayList, CGWarpMouseCursorPosition, CGWindowLevelForKey,
+    CGWindowLevelKey, CGWindowListCopyWindowInfo, CGWindowListOption, kCGNullWindowID,
+    kCGWindowNumber,
+};
+use objc2_foundation::{NSError, NSString, NSUUID};
+use objc2_user_notifications::{
+    UNAuthorizationOptions, UNMutableNotificationContent, UNNotificationRequest,
+    UNUserNotificationCenter,
 };
-use std::path::Path;
+use std::path::PathBuf;
 use std::ptr::null_mut;
 use std::slice::from_raw_parts_mut;
+use std::sync::{Arc, Once};
 use std::time::Duration;
 use stdext::function_name;
 use tracing::{Level, debug, error, instrument, trace, warn};
 
+use crate::commands::SpaceTarget;
 use crate::config::Config;
 use crate::errors::{Error, Result};
 use crate::events::{Event, EventSender};
 use crate::manager::skylight::SLSSetWindowListBrightness;
-use crate::platform::{ConnID, Pid, ProcessSerialNumber, WinID, WorkspaceId};
+use crate::platform::input;
+use crate::platform::{ConnID, Modifiers, Pid, ProcessSerialNumber, WinID, WorkspaceId};
 use crate::util::{AXUIWrapper, MacResult, create_array, symlink_target};
 use app::ApplicationOS;
 pub use app::{Application, ApplicationApi};
 pub use display::Display;
+pub use preview::capture_window_preview;
 pub use process::{Process, ProcessApi};
-pub use skylight::AXUIElementCopyAttributeValue;
 use skylight::{
-    _AXUIElementCreateWithRemoteToken, SLSCopyActiveMenuBarDisplayIdentifier,
-    SLSCopyAssociatedWindows, SLSCopyManagedDisplaySpaces, SLSCopyWindowsWithOptionsAndTags,
-    SLSFindWindowAndOwner, SLSGetConnectionIDForPSN, SLSGetCurrentCursorLocation,
-    SLSGetDisplayMenubarHeight, SLSGetSpaceManagementMode, SLSMainConnectionID,
-    SLSManagedDisplayGetCurrentSpace, SLSSpaceGetType, SLSWindowIteratorAdvance,
-    SLSWindowIteratorGetAttributes, SLSWindowIteratorGetParentID, SLSWindowIteratorGetTags,
-    SLSWindowIteratorGetWindowID, SLSWindowQueryResultCopyWindows, SLSWindowQueryWindows,
+    _AXUIElementCreateWithRemoteToken, AXUIElementCopyAttributeValue,
+    SLSCopyActiveMenuBarDisplayIdentifier, SLSCopyAssociatedWindows, SLSCopyManagedDisplaySpaces,
+    SLSCopyWindowsWithOptionsAndTags, SLSFindWindowAndOwner, SLSGetActiveSpace,
+    SLSGetConnectionIDForPSN, SLSGetCurrentCursorLocation, SLSGetDisplayMenubarHeight,
+    SLSGetSpaceManagementMode, SLSGetWindowBounds, SLSGetWindowLevel, SLSMainConnectionID,
+    SLSManagedDisplayGetCurrentSpace, SLSMoveWindowsToManagedSpace, SLSSpaceGetType,
+    SLSWindowIteratorAdvance, SLSWindowIteratorGetAttributes, SLSWindowIteratorGetParentID,
+    SLSWindowIteratorGetTags, SLSWindowIteratorGetWindowID, SLSWindowQueryResultCopyWindows,
+    SLSWindowQueryWindows,
 };
-pub use windows::{Window, WindowApi, WindowOS, WindowPadding, ax_window_id};
+pub use windows::{FrameInset, Window, WindowApi, WindowOS, WindowPadding, ax_window_id};
 
 #[cfg(test)]
 pub use process::MockProcessApi;
@@ -49,11 +66,18 @@ pub use process::MockProcessApi;
 pub use windows::MockWindowApi;
 
 pub(crate) mod app;
+pub(crate) mod ax;
 mod display;
+mod focus;
+mod preview;
 mod process;
 mod skylight;
+mod spaces;
 mod windows;
 
+/// Finder owns the desktop, activating it moves the focus there.
+const FINDER_BUNDLE_ID: &str = "com.apple.finder";
+
 pub type Origin = IVec2;
 pub type Size = IVec2;
 
@@ -74,6 +98,38 @@ pub fn irect_from(rect: CGRect) -> IRect {
     IRect::from_center_size(origin_from(mid), size_from(rect.size))
 }
 
+/// Returns the index of the bounds sharing the largest area with `frame`. A
+/// window straddling several displays belongs to the one returned for it.
+pub fn most_overlapping(frame: IRect, bounds: impl IntoIterator<Item = IRect>) -> Option<usize> {
+    bounds
+        .into_iter()
+        .enumerate()
+        .map(|(index, bounds)| {
+            let overlap = bounds.intersect(frame);
+            (
+                index,
+                i64::from(overlap.width()) * i64::from(overlap.height()),
+            )
+        })
+        .filter(|(_, area)| *area > 0)
+        .max_by_key(|(_, area)| *area)
+        .map(|(index, _)| index)
+}
+
+/// Returns the IDs of the displays currently active.
+fn active_display_ids() -> Vec<CGDirectDisplayID> {
+    let mut count = 0u32;
+    unsafe {
+        CGGetActiveDisplayList(0, null_mut(), &raw mut count);
+    }
+    let mut displays = vec![0; count as usize];
+    unsafe {
+        CGGetActiveDisplayList(count, displays.as_mut_ptr(), &raw mut count);
+    }
+    displays.truncate(count as usize);
+    displays
+}
+
 /// Defines the interface for a window manager, abstracting OS-specific operations.
 #[automock]
 pub trait WindowManagerApi: Send + Sync {
@@ -82,11 +138,16 @@ pub trait WindowManagerApi: Send + Sync {
     /// # Arguments
     ///
     /// * `process` - A reference to the `ProcessApi` trait object representing the application's process.
+    /// * `ax_timeout` - How long the application may take to answer an accessibility message.
     ///
     /// # Returns
     ///
     /// `Ok(Application)` if the application is successfully created, otherwise `Err(Error)`.
-    fn new_application(&self, process: &dyn ProcessApi) -> Result<Application>;
+    fn new_application(
+        &self,
+        process: &dyn ProcessApi,
+        ax_timeout: Duration,
+    ) -> Result<Application>;
     /// Retrieves a list of window IDs associated with a parent window.
     ///
     /// # Arguments
@@ -145,6 +206,22 @@ pub trait WindowManagerApi: Send + Sync {
         spaces: &[WorkspaceId],
         config: &Config,
     ) -> Result<(Vec<Window>, Vec<WinID>)>;
+    /// Lists the ids of the windows an application has on the given spaces,
+    /// from the window server, without asking the application itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `app` - A reference to the `Application` whose windows are listed.
+    /// * `spaces` - A slice of space IDs to query for windows.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(Vec<WinID>)` containing the window IDs, otherwise `Err(Error)`.
+    fn application_window_ids(
+        &self,
+        app: &Application,
+        spaces: &[WorkspaceId],
+    ) -> Result<Vec<WinID>>;
     /// Finds the `WinID` of a window at a given screen point.
     ///
     /// # Arguments
@@ -173,7 +250,11 @@ pub trait WindowManagerApi: Send + Sync {
     /// `Ok(())` if the exit event is sent successfully, otherwise `Err(Error)`.
     fn quit(&self) -> Result<()>;
 
-    fn setup_config_watcher(&self, path: &Path) -> Result<Box<dyn Watcher>>;
+    fn setup_config_watcher(&self, paths: &[PathBuf]) -> Result<Box<dyn Watcher>>;
+
+    /// Activates Finder, which hands the keyboard focus to the desktop when no
+    /// other window is left to focus.
+    fn focus_desktop(&self) -> Result<()>;
 
     /// Returns the current cursor position in absolute CG coordinates,
     /// or `None` if the position cannot be determined.
@@ -181,19 +262,63 @@ pub trait WindowManagerApi: Send + Sync {
 
     fn dim_windows(&self, windows: &[WinID], level: f32);
 
+    /// Switches to another macOS space on the active display.
+    fn focus_space(&self, target: SpaceTarget) -> Result<()>;
+
+    /// Adds a new macOS space to the active display.
+    fn create_space(&self) -> Result<()>;
+
+    /// Removes the current macOS space of the active display.
+    fn destroy_space(&self) -> Result<()>;
+
+    /// Removes a macOS space of a display, which does not have to be its current one.
+    fn remove_space(&self, display_id: CGDirectDisplayID, space_id: WorkspaceId) -> Result<()>;
+
+    /// Types a key combination into the focused window.
+    fn send_keys(&self, keycode: u8, modifiers: Modifiers) -> Result<()>;
+
+    /// Returns the spaces of a display, in the order of Mission Control.
+    fn display_spaces(&self, display_id: CGDirectDisplayID) -> Result<Vec<WorkspaceId>>;
+
+    /// Translates a space id reported by the OS into the workspaces tracked for
+    /// it: the space itself, or one per display when a space spans all displays.
+    fn space_workspaces(&self, space_id: WorkspaceId) -> Vec<WorkspaceId>;
+
+    /// Moves a window to another space without switching to it.
+    fn move_window_to_space(&self, window_id: WinID, space_id: WorkspaceId) -> Result<()>;
+
     fn windows_on_screen(&self) -> Option<Vec<WinID>>;
+
+    /// Returns `true` for the windows macOS draws on every space itself, like
+    /// the desktop and the menubar.
+    fn is_desktop_element(&self, window_id: WinID) -> bool;
+
+    /// Checks, without prompting, whether the Accessibility permission is still granted.
+    fn is_trusted(&self) -> bool;
+
+    /// Whether the window server still answers on the connection of the daemon.
+    /// The connection goes stale when the WindowServer restarts.
+    fn connection_alive(&self) -> bool;
+
+    /// Whether an application captured any display for its exclusive use.
+    fn display_captured(&self) -> bool;
+
+    /// Shows a macOS notification to the user.
+    fn notify_user(&self, title: &str, message: &str);
 }
 
 /// `WindowManager` is a Bevy resource that holds a boxed `WindowManagerApi` trait object.
 /// It allows for dynamic dispatch to the OS-specific window management implementation.
 #[derive(Deref, DerefMut, Resource)]
-pub struct WindowManager(pub Box<dyn WindowManagerApi>);
+pub struct WindowManager(pub Arc<dyn WindowManagerApi>);
 
 /// `WindowManagerOS` is the macOS-specific implementation of the `WindowManagerApi` trait.
 /// It directly interacts with the macOS `SkyLight` and Accessibility APIs to manage windows.
 pub struct WindowManagerOS {
     main_cid: ConnID,
     event_sender: EventSender,
+    /// "Displays have separate Spaces" is disabled, a single space spans all displays.
+    spanning_spaces: bool,
 }
 
 impl WindowManagerOS {
@@ -210,13 +335,64 @@ impl WindowManagerOS {
     pub fn new(event_sender: EventSender) -> Self {
         let main_cid = unsafe { SLSMainConnectionID() };
         debug!("My connection id: {main_cid}");
+        let spanning_spaces = !check_separate_spaces();
 
         Self {
             main_cid,
             event_sender,
+            spanning_spaces,
+        }
+    }
+
+    /// Workspace of a display in one of its spaces. When a space spans all the
+    /// displays, every display gets its own workspace of it.
+    fn display_workspace(
+        &self,
+        space_id: WorkspaceId,
+        display_id: CGDirectDisplayID,
+    ) -> WorkspaceId {
+        if self.spanning_spaces {
+            (WorkspaceId::from(display_id) << 32) | (space_id & SPACE_ID_MASK)
+        } else {
+            space_id
+        }
+    }
+
+    /// Splits a workspace into its space and, for a spanning space, the display
+    /// it was handed out for.
+    fn workspace_space(
+        &self,
+        workspace_id: WorkspaceId,
+    ) -> (WorkspaceId, Option<CGDirectDisplayID>) {
+        if self.spanning_spaces {
+            let display_id = CGDirectDisplayID::try_from(workspace_id >> 32).ok();
+            (
+                workspace_id & SPACE_ID_MASK,
+                display_id.filter(|id| *id != 0),
+            )
+        } else {
+            (workspace_id, None)
         }
     }
 
+    /// The spaces holding the given workspaces, each listed once.
+    fn workspace_spaces(&self, workspaces: &[WorkspaceId]) -> Vec<WorkspaceId> {
+        let mut spaces = workspaces
+            .iter()
+            .map(|workspace_id| self.workspace_space(*workspace_id).0)
+            .collect::<Vec<_>>();
+        spaces.sort_unstable();
+        spaces.dedup();
+        spaces
+    }
+
+    /// Retrieves the space currently shown on a display, as known to the OS.
+    fn current_space(&self, display_id: CGDirectDisplayID) -> Result<WorkspaceId> {
+        Display::uuid_from_id(display_id).map(|uuid| unsafe {
+            SLSManagedDisplayGetCurrentSpace(self.main_cid, &raw const *uuid)
+        })
+    }
+
     /// Retrieves a list of space IDs for a given display UUID.
     /// It queries the `SkyLight` API for managed display spaces and filters by the provided UUID.
     ///
@@ -315,9 +491,13 @@ impl WindowManagerOS {
 }
 
 impl WindowManagerApi for WindowManagerOS {
-    fn new_application(&self, process: &dyn ProcessApi) -> Result<Application> {
+    fn new_application(
+        &self,
+        process: &dyn ProcessApi,
+        ax_timeout: Duration,
+    ) -> Result<Application> {
         let connection = self.connection_for_process(process.psn());
-        ApplicationOS::new(connection, process, &self.event_sender)
+        ApplicationOS::new(connection, process, ax_timeout, &self.event_sender)
             .map(|app| Application::new(Box::new(app)))
     }
 
@@ -337,19 +517,7 @@ impl WindowManagerApi for WindowManagerOS {
     /// A `Vec<Self>` containing `Display` objects for all present displays.
     #[instrument(level = Level::DEBUG, skip_all, ret)]
     fn present_displays(&self) -> Vec<(Display, Vec<WorkspaceId>)> {
-        let mut count = 0u32;
-        unsafe {
-            CGGetActiveDisplayList(0, null_mut(), &raw mut count);
-        }
-        if count < 1 {
-            return vec![];
-        }
-        let mut displays = Vec::with_capacity(count.try_into().unwrap());
-        unsafe {
-            CGGetActiveDisplayList(count, displays.as_mut_ptr(), &raw mut count);
-            displays.set_len(count.try_into().unwrap());
-        }
-        displays
+        active_display_ids()
             .into_iter()
             .filter_map(|id| {
                 let bounds = CGDisplayBounds(id);
@@ -358,7 +526,10 @@ impl WindowManagerApi for WindowManagerOS {
                 debug!("menubar height: {menubar_height}");
                 let workspaces = Display::uuid_from_id(id)
                     .and_then(|uuid| self.display_space_list(uuid.as_ref()))
-                    .ok()?;
+                    .ok()?
+                    .into_iter()
+                    .map(|space_id| self.display_workspace(space_id, id))
+                    .collect();
 
                 Some((
                     Display::new(id, irect_from(bounds), menubar_height.cast_signed()),
@@ -385,13 +556,12 @@ impl WindowManagerApi for WindowManagerOS {
     ///
     /// `Ok(u64)` with the space ID if successful, otherwise `Err(Error)`.
     fn active_display_space(&self, display_id: CGDirectDisplayID) -> Result<WorkspaceId> {
-        Display::uuid_from_id(display_id).map(|uuid| unsafe {
-            SLSManagedDisplayGetCurrentSpace(self.main_cid, &raw const *uuid)
-        })
+        self.current_space(display_id)
+            .map(|space_id| self.display_workspace(space_id, display_id))
     }
 
     fn is_fullscreen_space(&self, display_id: CGDirectDisplayID) -> bool {
-        self.active_display_space(display_id)
+        self.current_space(display_id)
             .is_ok_and(|space_id| unsafe { SLSSpaceGetType(self.main_cid, space_id) } == 4)
     }
 
@@ -427,7 +597,8 @@ impl WindowManagerApi for WindowManagerOS {
         spaces: &[WorkspaceId],
         config: &Config,
     ) -> Result<(Vec<Window>, Vec<WinID>)> {
-        let global_window_list = existing_application_window_list(self.main_cid, app, spaces)?;
+        let spaces = self.workspace_spaces(spaces);
+        let global_window_list = existing_application_window_list(self.main_cid, app, &spaces)?;
         if global_window_list.is_empty() {
             return Err(Error::InvalidInput(format!("No windows found for {app}")));
         }
@@ -452,6 +623,26 @@ impl WindowManagerApi for WindowManagerOS {
         Ok((found_windows, offscreen_windows))
     }
 
+    /// Lists the window IDs of an application with `SkyLight`, which is much
+    /// cheaper than building its windows from the accessibility API.
+    ///
+    /// # Arguments
+    ///
+    /// * `app` - A reference to the `Application` whose windows are listed.
+    /// * `spaces` - A slice of space IDs to query.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(Vec<WinID>)` containing the window IDs, otherwise `Err(Error)`.
+    fn application_window_ids(
+        &self,
+        app: &Application,
+        spaces: &[WorkspaceId],
+    ) -> Result<Vec<WinID>> {
+        let spaces = self.workspace_spaces(spaces);
+        existing_application_window_list(self.main_cid, app, &spaces)
+    }
+
     /// Finds a window at a given screen point using `SkyLight` API.
     ///
     /// # Arguments
@@ -504,7 +695,55 @@ impl WindowManagerApi for WindowManagerOS {
 
     /// Returns a list of windows in a given workspace.
     fn windows_in_workspace(&self, space_id: WorkspaceId) -> Result<Vec<WinID>> {
-        space_window_list_for_connection(self.main_cid, &[space_id], None, true)
+        let (space_id, spanning_display) = self.workspace_space(space_id);
+        let display_ids = active_display_ids();
+        let display_bounds = display_ids
+            .iter()
+            .map(|id| irect_from(CGDisplayBounds(*id)))
+            .collect::<Vec<_>>();
+        // Windows straddling two displays belong to the one holding most of
+        // them, otherwise they could be claimed by both.
+        let owner = |window_id: WinID| {
+            let mut frame = CGRect::default();
+            unsafe { SLSGetWindowBounds(self.main_cid, window_id, &mut frame) }
+                .to_result(function_name!())
+                .ok()
+                .and_then(|()| most_overlapping(irect_from(frame), display_bounds.iter().copied()))
+                .map(|index| display_ids[index])
+        };
+
+        // A spanning space holds the windows of all displays, keep the ones
+        // mostly on this display.
+        if let Some(display_id) = spanning_display {
+            let windows = space_window_list_for_connection(self.main_cid, &[space_id], None, true)?;
+            return Ok(windows
+                .into_iter()
+                .filter(|window_id| owner(*window_id) == Some(display_id))
+                .collect());
+        }
+
+        // With separate spaces, only the spaces shown side by side share
+        // windows. A space in the background keeps all of its windows.
+        let Some(display_id) = display_ids.iter().copied().find(|id| {
+            self.current_space(*id)
+                .is_ok_and(|current| current == space_id)
+        }) else {
+            return space_window_list_for_connection(self.main_cid, &[space_id], None, true);
+        };
+        let shown = display_ids
+            .iter()
+            .filter_map(|id| self.current_space(*id).ok())
+            .collect::<Vec<_>>();
+        let windows = space_window_list_for_connection(self.main_cid, &shown, None, true)?;
+        let own = space_window_list_for_connection(self.main_cid, &[space_id], None, true)?;
+        Ok(windows
+            .into_iter()
+            .filter(|window_id| match owner(*window_id) {
+                Some(owner) => owner == display_id,
+                // Off all displays, the window stays with its own space.
+                None => own.contains(window_id),
+            })
+            .collect())
     }
 
     fn quit(&self) -> Result<()> {
@@ -519,31 +758,119 @@ impl WindowManagerApi for WindowManagerOS {
         Some(cursor)
     }
 
-    fn setup_config_watcher(&self, path: &Path) -> Result<Box<dyn Watcher>> {
+    fn setup_config_watcher(&self, paths: &[PathBuf]) -> Result<Box<dyn Watcher>> {
         let setup = notify::Config::default()
             .with_poll_interval(Duration::from_secs(3))
             .with_follow_symlinks(false);
         let config_handler = ConfigHandler(self.event_sender.clone());
-        let symlink = symlink_target(path);
-
-        let mut watcher = if let Some(symlink) = symlink {
-            setup.with_follow_symlinks(true);
-            let mut watcher = notify::PollWatcher::new(config_handler, setup)?;
-            debug!("watching symlink target {} for changes.", symlink.display());
-            watcher.watch(&symlink, RecursiveMode::NonRecursive)?;
+        let symlinks = paths
+            .iter()
+            .filter_map(|path| symlink_target(path))
+            .collect::<Vec<_>>();
 
-            Ok::<Box<dyn Watcher>, Error>(Box::new(watcher))
-        } else {
+        let mut watcher = if symlinks.is_empty() {
             Ok::<Box<dyn Watcher>, Error>(Box::new(notify::RecommendedWatcher::new(
                 config_handler,
                 setup,
             )?))
+        } else {
+            setup.with_follow_symlinks(true);
+            let mut watcher = notify::PollWatcher::new(config_handler, setup)?;
+            for symlink in &symlinks {
+                debug!("watching symlink target {} for changes.", symlink.display());
+                watcher.watch(symlink, RecursiveMode::NonRecursive)?;
+            }
+
+            Ok::<Box<dyn Watcher>, Error>(Box::new(watcher))
         }?;
-        debug!("watching config file {} for changes.", path.display());
-        watcher.watch(path, RecursiveMode::NonRecursive)?;
+        for path in paths {
+            debug!("watching config file {} for changes.", path.display());
+            watcher.watch(path, RecursiveMode::NonRecursive)?;
+        }
         Ok(watcher)
     }
 
+    fn focus_desktop(&self) -> Result<()> {
+        let bundle_id = NSString::from_str(FINDER_BUNDLE_ID);
+        let finder = NSRunningApplication::runningApplicationsWithBundleIdentifier(&bundle_id)
+            .firstObject()
+            .ok_or_else(|| {
+                Error::NotFound(format!("{}: Finder is not running", function_name!()))
+            })?;
+        if finder.activateWithOptions(NSApplicationActivationOptions::empty()) {
+            Ok(())
+        } else {
+            Err(Error::Generic(format!(
+                "{}: unable to activate Finder",
+                function_name!()
+            )))
+        }
+    }
+
+    fn focus_space(&self, target: SpaceTarget) -> Result<()> {
+        let display_id = self.active_display_id()?;
+        let uuid = Display::uuid_from_id(display_id)?;
+        let current = self.current_space(display_id)?;
+        let spaces = self.display_space_list(&uuid)?;
+        spaces::focus_space(self.main_cid, &uuid, &spaces, current, target)
+    }
+
+    fn create_space(&self) -> Result<()> {
+        spaces::create_space(self.main_cid)
+    }
+
+    fn destroy_space(&self) -> Result<()> {
+        let display_id = self.active_display_id()?;
+        let uuid = Display::uuid_from_id(display_id)?;
+        let current = self.current_space(display_id)?;
+        let spaces = self.display_space_list(&uuid)?;
+        spaces::destroy_space(self.main_cid, &uuid, &spaces, current, current)
+    }
+
+    fn remove_space(&self, display_id: CGDirectDisplayID, space_id: WorkspaceId) -> Result<()> {
+        if self.spanning_spaces {
+            return Err(Error::InvalidInput(format!(
+                "{}: space {space_id} spans all displays",
+                function_name!()
+            )));
+        }
+        let uuid = Display::uuid_from_id(display_id)?;
+        let current = self.current_space(display_id)?;
+        let spaces = self.display_space_list(&uuid)?;
+        spaces::destroy_space(self.main_cid, &uuid, &spaces, current, space_id)
+    }
+
+    fn send_keys(&self, keycode: u8, modifiers: Modifiers) -> Result<()> {
+        input::post_keys(keycode, modifiers)
+    }
+
+    fn display_spaces(&self, display_id: CGDirectDisplayID) -> Result<Vec<WorkspaceId>> {
+        let uuid = Display::uuid_from_id(display_id)?;
+        Ok(self
+            .display_space_list(&uuid)?
+            .into_iter()
+            .map(|space_id| self.display_workspace(space_id, display_id))
+            .collect())
+    }
+
+    fn space_workspaces(&self, space_id: WorkspaceId) -> Vec<WorkspaceId> {
+        if self.spanning_spaces {
+            active_display_ids()
+                .into_iter()
+                .map(|display_id| self.display_workspace(space_id, display_id))
+                .collect()
+        } else {
+            vec![space_id]
+        }
+    }
+
+    fn move_window_to_space(&self, window_id: WinID, space_id: WorkspaceId) -> Result<()> {
+        let (space_id, _) = self.workspace_space(space_id);
+        let windows = create_array(&[window_id], CFNumberType::SInt32Type)?;
+        unsafe { SLSMoveWindowsToManagedSpace(self.main_cid, &windows, space_id) };
+        Ok(())
+    }
+
     /// level: 0.0 = normal, 1.0 = bright, -1.0 = dark
     fn dim_windows(&self, windows: &[WinID], level: f32) {
         let Ok(count) = isize::try_from(windows.len()) else {
@@ -573,6 +900,83 @@ impl WindowManagerApi for WindowManagerOS {
                 .collect::<Vec<_>>()
         })
     }
+
+    fn is_desktop_element(&self, window_id: WinID) -> bool {
+        let mut level = 0;
+        unsafe { SLSGetWindowLevel(self.main_cid, window_id, &mut level) }
+            .to_result(function_name!())
+            .is_ok_and(|()| {
+                [
+                    CGWindowLevelKey::DesktopWindowLevelKey,
+                    CGWindowLevelKey::DesktopIconWindowLevelKey,
+                    CGWindowLevelKey::MainMenuWindowLevelKey,
+                    CGWindowLevelKey::StatusWindowLevelKey,
+                ]
+                .into_iter()
+                .any(|key| CGWindowLevelForKey(key) == level)
+            })
+    }
+
+    fn is_trusted(&self) -> bool {
+        unsafe { AXIsProcessTrusted() }
+    }
+
+    fn connection_alive(&self) -> bool {
+        // Every session has an active space, a dead connection has none.
+        unsafe { SLSGetActiveSpace(self.main_cid) != 0 }
+    }
+
+    #[allow(deprecated)]
+    fn display_captured(&self) -> bool {
+        active_display_ids().into_iter().any(CGDisplayIsCaptured)
+    }
+
+    fn notify_user(&self, title: &str, message: &str) {
+        _ = post_notification(title, message)
+            .inspect_err(|err| warn!("showing notification: {err}"));
+    }
+}
+
+/// Posts a notification through the User Notifications framework. The first
+/// one asks the user to allow Paneru's notifications.
+fn post_notification(title: &str, message: &str) -> Result<()> {
+    static AUTHORIZATION: Once = Once::new();
+
+    // The framework throws when the process has no bundle it recognizes.
+    let center = objc2::exception::catch(UNUserNotificationCenter::currentNotificationCenter)
+        .map_err(|err| {
+            Error::Generic(format!(
+                "{}: notification center is not available: {err:?}",
+                function_name!()
+            ))
+        })?;
+    AUTHORIZATION.call_once(|| {
+        let completion = RcBlock::new(|granted: Bool, _: *mut NSError| {
+            if !granted.as_bool() {
+                warn!("notifications were not allowed, problems are only logged.");
+            }
+        });
+        center.requestAuthorizationWithOptions_completionHandler(
+            UNAuthorizationOptions::Alert | UNAuthorizationOptions::Sound,
+            &completion,
+        );
+    });
+
+    let content = UNMutableNotificationContent::new();
+    content.setTitle(&NSString::from_str(title));
+    content.setBody(&NSString::from_str(message));
+    let request = UNNotificationRequest::requestWithIdentifier_content_trigger(
+        &NSUUID::new().UUIDString(),
+        &content,
+        None,
+    );
+    let completion = RcBlock::new(|error: *mut NSError| {
+        if let Some(error) = unsafe { error.as_ref() } {
+            warn!("showing notification: {}", error.localizedDescription());
+        }
+    });
+    center.addNotificationRequest_withCompletionHandler(&request, Some(&completion));
+    Ok(())
 }
 
 /// Retrieves a list of window IDs for specified spaces and connection, with an option to include minimized windows.
@@ -800,8 +1204,50 @@ pub fn check_ax_privilege() -> bool {
     }
 }
 
+/// Lists the services of the login session which are not available yet: the
+/// window server connection, the Dock, which owns the spaces and Mission
+/// Control, and the accessibility server. A LaunchAgent started at login can
+/// run before they are up.
+///
+/// # Returns
+///
+/// The names of the missing services, empty once the session is ready.
+pub fn missing_session_services() -> Vec<&'static str> {
+    let mut missing = vec![];
+    if unsafe { SLSMainConnectionID() } == 0 {
+        missing.push("window server");
+    }
+    let dock = NSString::from_str("com.apple.dock");
+    if NSRunningApplication::runningApplicationsWithBundleIdentifier(&dock).is_empty() {
+        missing.push("Dock");
+    }
+    // Only a server which can not answer counts as missing, a lacking
+    // permission is reported by `check_ax_privilege`.
+    let ax_ready = AXUIWrapper::from_retained(unsafe { AXUIElementCreateSystemWide() }).is_ok_and(
+        |system_wide| {
+            let attribute = CFString::from_static_str(kAXFocusedApplicationAttribute);
+            let mut value = null_mut();
+            let result = unsafe {
+                AXUIElementCopyAttributeValue(system_wide.as_ptr(), &attribute, &mut value)
+            };
+            if let Some(value) = NonNull::new(value) {
+                drop(unsafe { CFRetained::from_raw(value) });
+            }
+            result != kAXErrorCannotComplete
+        },
+    );
+    if !ax_ready {
+        missing.push("accessibility");
+    }
+    missing
+}
+
+/// Space ids fit in the lower half of a workspace id, see `WindowManagerOS::display_workspace`.
+const SPACE_ID_MASK: WorkspaceId = 0xffff_ffff;
+
 /// Checks if the macOS "Displays have separate Spaces" option is enabled.
-/// This is crucial for the window manager's functionality, as Paneru relies on independent spaces per display.
+/// Without it a single space spans all the displays, and Paneru falls back to
+/// one strip per display in each space.
 ///
 /// # Returns
 ///
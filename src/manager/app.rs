@@ -10,13 +10,14 @@ use std::ffi::c_void;
 use std::pin::Pin;
 use std::ptr::null_mut;
 use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Duration;
 use stdext::sync::rw_lock::RwLockExt;
 
 use stdext::function_name;
 use tracing::{debug, error};
 
 use super::skylight::_SLPSGetFrontProcess;
-use super::{ProcessApi, Window, WindowOS, ax_window_id};
+use super::{ProcessApi, Window, WindowOS, ax, ax_window_id};
 use crate::config::Config;
 use crate::errors::{Error, Result};
 use crate::events::{Event, EventSender};
@@ -44,12 +45,13 @@ pub static AX_NOTIFICATIONS: LazyLock<Vec<&str>> = LazyLock::new(|| {
 
 /// A static `LazyLock` that holds a list of `AXNotification` strings to be observed for window-specific events.
 /// These notifications are related to individual window lifecycle events,
-/// such as a window being destroyed, miniaturized (minimized), or deminiaturized (restored).
+/// such as a window being destroyed, miniaturized (minimized), deminiaturized (restored) or retitled.
 pub static AX_WINDOW_NOTIFICATIONS: LazyLock<Vec<&str>> = LazyLock::new(|| {
     vec![
         accessibility_sys::kAXUIElementDestroyedNotification,
         accessibility_sys::kAXWindowMiniaturizedNotification,
         accessibility_sys::kAXWindowDeminiaturizedNotification,
+        accessibility_sys::kAXTitleChangedNotification,
     ]
 });
 
@@ -77,6 +79,14 @@ pub trait ApplicationApi: Send + Sync {
     ///
     /// Returns an `Error` if the window list cannot be retrieved.
     fn window_list(&self, config: &Config) -> Vec<Window>;
+    /// Returns the windows of this application with the given ids. The other
+    /// windows are left out before anything else is read from them.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_ids` - The IDs of the windows to return.
+    /// * `config` - The current Paneru configuration, used to evaluate window rules.
+    fn window_list_of(&self, window_ids: &[WinID], config: &Config) -> Vec<Window>;
     /// Starts observing application-level accessibility notifications.
     ///
     /// # Errors
@@ -103,6 +113,8 @@ pub trait ApplicationApi: Send + Sync {
     fn is_frontmost(&self) -> bool;
     /// Returns the bundle identifier of the application.
     fn bundle_id(&self) -> Option<String>;
+    /// Returns the filesystem path of the application bundle, if it has one.
+    fn bundle_path(&self) -> Option<String>;
     /// Returns the display name of the application.
     fn name(&self) -> &str;
 }
@@ -144,6 +156,7 @@ pub struct ApplicationOS {
     connection: Option<ConnID>,
     handler: AxObserverHandler,
     bundle_id: Option<String>,
+    bundle_path: Option<String>,
     name: String,
 }
 
@@ -152,6 +165,7 @@ impl Drop for ApplicationOS {
     fn drop(&mut self) {
         self.handler
             .remove_observer(&ObserverType::Application, &self.element, &AX_NOTIFICATIONS);
+        ax::forget_messaging_timeout(self.pid);
     }
 }
 
@@ -169,6 +183,7 @@ impl ApplicationOS {
     ///
     /// * `connection` - The main connection ID for the `SkyLight` API.
     /// * `process` - A reference to the `Process` associated with this application.
+    /// * `ax_timeout` - How long the application may take to answer an accessibility message.
     /// * `events` - An `EventSender` to send events from the `AXObserver`.
     ///
     /// # Returns
@@ -177,17 +192,26 @@ impl ApplicationOS {
     pub fn new(
         connection: Option<ConnID>,
         process: &dyn ProcessApi,
+        ax_timeout: Duration,
         events: &EventSender,
     ) -> Result<Self> {
         let refer = unsafe {
             let ptr = AXUIElementCreateApplication(process.pid());
             AXUIWrapper::retain(ptr)?
         };
+        // A slow application then only delays its own windows.
+        ax::set_messaging_timeout(&refer, process.pid(), ax_timeout);
         let bundle_id = process
             .application()
             .as_ref()
             .and_then(|app| app.bundleIdentifier())
             .map(|id| id.to_string());
+        let bundle_path = process
+            .application()
+            .as_ref()
+            .and_then(|app| app.bundleURL())
+            .and_then(|url| url.path())
+            .map(|path| path.to_string());
         Ok(Self {
             element: refer,
             psn: process.psn(),
@@ -195,6 +219,7 @@ impl ApplicationOS {
             connection,
             handler: AxObserverHandler::new(process.pid(), events.clone())?,
             bundle_id,
+            bundle_path,
             name: process.name().to_string(),
         })
     }
@@ -258,6 +283,31 @@ impl ApplicationApi for ApplicationOS {
             .unwrap_or_default()
     }
 
+    /// Retrieves the windows of the application with the given IDs.
+    ///
+    /// # Returns
+    ///
+    /// A `Vec<Window>` with the windows which could be created.
+    fn window_list_of(&self, window_ids: &[WinID], config: &Config) -> Vec<Window> {
+        let bundle_id = self.bundle_id.as_deref();
+        self.element
+            .windows()
+            .map(|windows| {
+                windows
+                    .into_iter()
+                    .filter(|element| {
+                        ax_window_id(element.as_ptr())
+                            .is_ok_and(|window_id| window_ids.contains(&window_id))
+                    })
+                    .flat_map(|element| {
+                        WindowOS::new_with_config(&element, config, bundle_id)
+                            .map(|window| Window::new(Box::new(window)))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Registers observers for general application-level accessibility notifications (e.g., `kAXCreatedNotification`).
     ///
     /// # Returns
@@ -329,6 +379,10 @@ impl ApplicationApi for ApplicationOS {
         self.bundle_id.clone()
     }
 
+    fn bundle_path(&self) -> Option<String> {
+        self.bundle_path.clone()
+    }
+
     fn name(&self) -> &str {
         &self.name
     }
@@ -374,7 +428,8 @@ impl ObserverContext {
     fn notify_app(&self, notification: &str, element: AXUIElementRef) {
         match notification {
             accessibility_sys::kAXTitleChangedNotification => {
-                // TODO: WindowTitleChanged does not have a valid window as its element reference.
+                // Not a valid window as its element reference, the window observers
+                // deliver WindowTitleChanged instead.
                 return;
             }
             accessibility_sys::kAXCreatedNotification => {
@@ -429,6 +484,9 @@ impl ObserverContext {
             accessibility_sys::kAXUIElementDestroyedNotification => {
                 Event::WindowDestroyed { window_id }
             }
+            accessibility_sys::kAXTitleChangedNotification => {
+                Event::WindowTitleChanged { window_id }
+            }
 
             _ => {
                 error!("unhandled window notification: {notification:?}");
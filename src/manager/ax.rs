@@ -0,0 +1,490 @@
+use accessibility_sys::{
+    AXUIElementCopyElementAtPosition, AXUIElementCreateSystemWide, AXUIElementGetPid,
+    AXUIElementSetMessagingTimeout, kAXZoomButtonSubrole,
+};
+use core::ptr::NonNull;
+use objc2_core_foundation::{CFRetained, CFString, CFType, CGPoint};
+use std::collections::{HashMap, HashSet};
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::thread;
+use std::time::Duration;
+use stdext::function_name;
+use tracing::{debug, warn};
+
+use super::skylight::{
+    AXUIElementCopyAttributeValue, AXUIElementPerformAction, AXUIElementSetAttributeValue,
+};
+use crate::config::DEFAULT_AX_TIMEOUT;
+use crate::errors::{Error, Result};
+use crate::platform::Pid;
+use crate::util::{AXUIAttributes, AXUIWrapper, MacResult};
+
+/// How much longer than the messaging timeout a caller waits for a worker, so
+/// that the watchdog only fires for calls the timeout did not stop.
+const AX_CALL_GRACE: Duration = Duration::from_millis(500);
+const AX_WORKERS: usize = 4;
+
+/// A call for a worker. It returns `true` when the watchdog gave up on it and
+/// put another worker in its place, which tells the worker to retire.
+type Job = Box<dyn FnOnce() -> bool + Send>;
+
+/// Where a job is, as seen by the worker running it and the caller waiting for it.
+struct JobState(AtomicU8);
+
+impl JobState {
+    const QUEUED: u8 = 0;
+    const RUNNING: u8 = 1;
+    const DONE: u8 = 2;
+    const CANCELLED: u8 = 3;
+    const ABANDONED: u8 = 4;
+
+    fn new() -> Arc<Self> {
+        Arc::new(JobState(AtomicU8::new(Self::QUEUED)))
+    }
+
+    fn advance(&self, from: u8, to: u8) -> bool {
+        self.0
+            .compare_exchange(from, to, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Called by the worker before the call, `false` if the caller gave up on it.
+    fn start(&self) -> bool {
+        self.advance(Self::QUEUED, Self::RUNNING)
+    }
+
+    /// Called by the worker after the call, `true` if it was abandoned meanwhile.
+    fn finish(&self) -> bool {
+        !self.advance(Self::RUNNING, Self::DONE)
+    }
+
+    /// Drops a job which no worker has picked up yet.
+    fn cancel(&self) -> bool {
+        self.advance(Self::QUEUED, Self::CANCELLED)
+    }
+
+    /// Leaves a running job to its worker, which retires once it returns.
+    fn abandon(&self) -> bool {
+        self.advance(Self::RUNNING, Self::ABANDONED)
+    }
+}
+
+/// A small pool of threads running the accessibility calls, which all wait for
+/// the application to answer. Callers wait for the result with a watchdog, so
+/// an unresponsive application stalls at most a worker, never the caller. A
+/// worker stuck in an abandoned call is replaced right away, so that hung
+/// applications do not hold up the calls to the others.
+struct AxWorkers {
+    jobs: Sender<Job>,
+    receiver: Arc<Mutex<Receiver<Job>>>,
+    /// Numbers the worker threads, for their names.
+    spawned: AtomicUsize,
+    /// Applications whose last call was abandoned by the watchdog. Further
+    /// calls fail right away, until the abandoned call finally returns.
+    unresponsive: Arc<Mutex<HashSet<Pid>>>,
+    /// Messaging timeouts of applications which were given their own.
+    timeouts: Mutex<HashMap<Pid, Duration>>,
+}
+
+static AX_WORKERS_POOL: LazyLock<AxWorkers> = LazyLock::new(|| {
+    if let Ok(system_wide) = AXUIWrapper::from_retained(unsafe { AXUIElementCreateSystemWide() }) {
+        // Setting the timeout on the system-wide element applies it to all elements,
+        // instead of the system default of six seconds.
+        unsafe {
+            AXUIElementSetMessagingTimeout(system_wide.as_ptr(), DEFAULT_AX_TIMEOUT.as_secs_f32());
+        };
+    }
+    AxWorkers::new(AX_WORKERS)
+});
+
+impl AxWorkers {
+    fn new(count: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let workers = AxWorkers {
+            jobs: sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            spawned: AtomicUsize::new(0),
+            unresponsive: Arc::new(Mutex::new(HashSet::new())),
+            timeouts: Mutex::new(HashMap::new()),
+        };
+        for _ in 0..count {
+            workers.spawn_worker();
+        }
+        workers
+    }
+
+    fn spawn_worker(&self) {
+        let index = self.spawned.fetch_add(1, Ordering::Relaxed);
+        let receiver = self.receiver.clone();
+        _ = thread::Builder::new()
+            .name(format!("ax-worker-{index}"))
+            .spawn(move || Self::work(&receiver))
+            .inspect_err(|err| warn!("unable to spawn ax worker: {err}"));
+    }
+
+    fn messaging_timeout(&self, pid: Pid) -> Option<Duration> {
+        self.timeouts
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(&pid)
+            .copied()
+    }
+
+    /// How long a caller waits for a call to the application `pid`.
+    fn call_timeout(&self, pid: Pid) -> Duration {
+        self.messaging_timeout(pid).unwrap_or(DEFAULT_AX_TIMEOUT) + AX_CALL_GRACE
+    }
+
+    fn is_unresponsive(&self, pid: Pid) -> bool {
+        self.unresponsive
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .contains(&pid)
+    }
+
+    fn work(receiver: &Mutex<Receiver<Job>>) {
+        loop {
+            let job = receiver
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .recv();
+            let Ok(job) = job else {
+                break;
+            };
+            if job() {
+                debug!("abandoned ax call returned, retiring its worker");
+                break;
+            }
+        }
+    }
+
+    /// Hands a job to the workers, or runs it right away without them.
+    fn submit(&self, job: Job) {
+        if let Err(mpsc::SendError(job)) = self.jobs.send(job) {
+            job();
+        }
+    }
+
+    /// Runs `call` for the application `pid` on a worker and waits at most
+    /// `timeout` for its result. A call still waiting for a worker by then is
+    /// dropped, which does not count against its application.
+    fn run<T, F>(&self, pid: Pid, timeout: Duration, call: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T> + Send + 'static,
+    {
+        if self.is_unresponsive(pid) {
+            return Err(Error::Generic(format!(
+                "{}: application {pid} is not responding.",
+                function_name!()
+            )));
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let unresponsive = self.unresponsive.clone();
+        let state = JobState::new();
+        let job_state = state.clone();
+        self.submit(Box::new(move || {
+            if !job_state.start() {
+                return false;
+            }
+            let result = call();
+            // Sending under the lock pairs with the watchdog below: either the
+            // caller still receives the result, or it has already given up.
+            let mut unresponsive = unresponsive
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            if sender.send(result).is_err() && unresponsive.remove(&pid) {
+                debug!("application {pid} is responding again");
+            }
+            job_state.finish()
+        }));
+
+        if let Ok(result) = receiver.recv_timeout(timeout) {
+            return result;
+        }
+        let mut unresponsive = self
+            .unresponsive
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Ok(result) = receiver.try_recv() {
+            return result;
+        }
+        if state.cancel() {
+            return Err(Error::Generic(format!(
+                "{}: no ax worker was free for application {pid} within {timeout:?}.",
+                function_name!()
+            )));
+        }
+        drop(receiver);
+        warn!("application {pid} did not answer within {timeout:?}, ignoring it for now");
+        unresponsive.insert(pid);
+        if state.abandon() {
+            self.spawn_worker();
+        }
+        Err(Error::Generic(format!(
+            "{}: application {pid} timed out.",
+            function_name!()
+        )))
+    }
+}
+
+fn element_pid(element: &CFRetained<AXUIWrapper>) -> Result<Pid> {
+    let mut pid: Pid = 0;
+    unsafe { AXUIElementGetPid(element.as_ptr(), &raw mut pid) }.to_result(function_name!())?;
+    Ok(pid)
+}
+
+/// Sets how long the application `pid` may take to answer an accessibility
+/// message sent to `element`. Elements created later for the same application,
+/// e.g. its windows, are given the same timeout by `apply_messaging_timeout`.
+pub(crate) fn set_messaging_timeout(
+    element: &CFRetained<AXUIWrapper>,
+    pid: Pid,
+    timeout: Duration,
+) {
+    AX_WORKERS_POOL
+        .timeouts
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .insert(pid, timeout);
+    unsafe { AXUIElementSetMessagingTimeout(element.as_ptr(), timeout.as_secs_f32()) };
+}
+
+/// Gives `element` the messaging timeout of its application, if it has one.
+pub(crate) fn apply_messaging_timeout(element: &CFRetained<AXUIWrapper>) {
+    let Ok(pid) = element_pid(element) else {
+        return;
+    };
+    if let Some(timeout) = AX_WORKERS_POOL.messaging_timeout(pid) {
+        unsafe { AXUIElementSetMessagingTimeout(element.as_ptr(), timeout.as_secs_f32()) };
+    }
+}
+
+/// Forgets the messaging timeout of an application which went away.
+pub(crate) fn forget_messaging_timeout(pid: Pid) {
+    AX_WORKERS_POOL
+        .timeouts
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .remove(&pid);
+}
+
+/// Returns the application of `element`, unless it stopped answering calls.
+fn responsive_pid(element: &CFRetained<AXUIWrapper>) -> Result<Pid> {
+    let pid = element_pid(element)?;
+    if AX_WORKERS_POOL.is_unresponsive(pid) {
+        return Err(Error::Generic(format!(
+            "{}: application {pid} is not responding.",
+            function_name!()
+        )));
+    }
+    Ok(pid)
+}
+
+/// Copies the value of an accessibility attribute on a worker thread.
+/// The value is returned untyped, the caller knows its actual type.
+pub(crate) fn copy_attribute(
+    element: &CFRetained<AXUIWrapper>,
+    name: &CFRetained<CFString>,
+) -> Result<CFRetained<AXUIWrapper>> {
+    let pid = element_pid(element)?;
+    let element = element.clone();
+    let name = name.clone();
+    AX_WORKERS_POOL.run(pid, AX_WORKERS_POOL.call_timeout(pid), move || {
+        copy_attribute_now(&element, &name)
+    })
+}
+
+fn copy_attribute_now(
+    element: &CFRetained<AXUIWrapper>,
+    name: &CFRetained<CFString>,
+) -> Result<CFRetained<AXUIWrapper>> {
+    let mut attribute: *mut CFType = null_mut();
+    unsafe { AXUIElementCopyAttributeValue(element.as_ptr(), name, &mut attribute) }
+        .to_result(function_name!())?;
+    AXUIWrapper::from_retained(attribute)
+}
+
+/// Copies the value of an accessibility attribute on a worker thread, waiting
+/// at most `wait` for it. A slow answer does not mark the application as
+/// unresponsive: `answered` receives the value whenever it
+/// arrives, or the error if the call could not be made.
+pub(crate) fn copy_attribute_detached<F>(
+    element: &CFRetained<AXUIWrapper>,
+    name: &CFRetained<CFString>,
+    wait: Duration,
+    answered: F,
+) -> Result<CFRetained<AXUIWrapper>>
+where
+    F: FnOnce(&Result<CFRetained<AXUIWrapper>>) + Send + 'static,
+{
+    if let Err(err) = responsive_pid(element) {
+        let result = Err(err);
+        answered(&result);
+        return result;
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let element = element.clone();
+    let name = name.clone();
+    let state = JobState::new();
+    let job_state = state.clone();
+    AX_WORKERS_POOL.submit(Box::new(move || {
+        job_state.start();
+        let result = copy_attribute_now(&element, &name);
+        answered(&result);
+        _ = sender.send(result);
+        job_state.finish()
+    }));
+    receiver.recv_timeout(wait).unwrap_or_else(|_| {
+        // The answer is still awaited, but not on a worker the others need.
+        if state.abandon() {
+            AX_WORKERS_POOL.spawn_worker();
+        }
+        Err(Error::Generic(format!(
+            "{}: no answer within {wait:?}.",
+            function_name!()
+        )))
+    })
+}
+
+/// Sets the value of an accessibility attribute on a worker thread.
+pub(crate) fn set_attribute(
+    element: &CFRetained<AXUIWrapper>,
+    name: &CFRetained<CFString>,
+    value: &CFType,
+) -> Result<()> {
+    let pid = element_pid(element)?;
+    let element = element.clone();
+    let name = name.clone();
+    let value = AXUIWrapper::retain(NonNull::from(value).as_ptr())?;
+    AX_WORKERS_POOL.run(pid, AX_WORKERS_POOL.call_timeout(pid), move || {
+        unsafe { AXUIElementSetAttributeValue(element.as_ptr(), &name, value.as_ref()) }
+            .to_result(function_name!())
+    })
+}
+
+/// Performs an accessibility action, e.g. pressing a button, on a worker thread.
+pub(crate) fn perform_action(
+    element: &CFRetained<AXUIWrapper>,
+    action: &'static str,
+) -> Result<()> {
+    let pid = element_pid(element)?;
+    let element = element.clone();
+    AX_WORKERS_POOL.run(pid, AX_WORKERS_POOL.call_timeout(pid), move || {
+        let action = CFString::from_static_str(action);
+        unsafe { AXUIElementPerformAction(element.as_ptr(), &action) }.to_result(function_name!())
+    })
+}
+
+/// Returns `true` if the accessibility element at `point` is the green zoom
+/// button of a window.
+pub(crate) fn zoom_button_at(point: CGPoint) -> bool {
+    let Ok(system_wide) = AXUIWrapper::from_retained(unsafe { AXUIElementCreateSystemWide() })
+    else {
+        return false;
+    };
+    let mut element = null_mut();
+    #[allow(clippy::cast_possible_truncation)]
+    let result = unsafe {
+        AXUIElementCopyElementAtPosition(
+            system_wide.as_ptr(),
+            point.x as f32,
+            point.y as f32,
+            &raw mut element,
+        )
+    };
+    result.to_result(function_name!()).is_ok()
+        && AXUIWrapper::from_retained(element)
+            .and_then(|element| element.subrole())
+            .is_ok_and(|subrole| subrole == kAXZoomButtonSubrole)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_unresponsive_application_fails_fast_until_it_answers() {
+        let workers = AxWorkers::new(2);
+        let timeout = Duration::from_millis(50);
+        let (release, hung) = channel::<()>();
+
+        let result = workers.run(1, timeout, move || {
+            _ = hung.recv();
+            Ok(())
+        });
+        assert!(result.is_err());
+
+        // Calls to the hung application fail right away, others still work.
+        assert!(workers.run(1, timeout, || Ok(1)).is_err());
+        assert_eq!(workers.run(2, timeout, || Ok(2)).ok(), Some(2));
+
+        release.send(()).unwrap();
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(workers.run(1, timeout, || Ok(3)).ok(), Some(3));
+    }
+
+    #[test]
+    fn test_abandoned_worker_is_replaced() {
+        let workers = AxWorkers::new(1);
+        let timeout = Duration::from_millis(50);
+        let (release, hung) = channel::<()>();
+
+        assert!(
+            workers
+                .run(1, timeout, move || {
+                    _ = hung.recv();
+                    Ok(())
+                })
+                .is_err()
+        );
+        // The only worker is stuck, another one answers in its place.
+        assert_eq!(workers.run(2, timeout, || Ok(2)).ok(), Some(2));
+        release.send(()).unwrap();
+    }
+
+    #[test]
+    fn test_queued_call_does_not_mark_application() {
+        let workers = AxWorkers::new(1);
+        let (release, hung) = channel::<()>();
+
+        thread::scope(|scope| {
+            let busy = scope.spawn(|| {
+                workers.run(1, Duration::from_secs(5), move || {
+                    _ = hung.recv();
+                    Ok(())
+                })
+            });
+            thread::sleep(Duration::from_millis(50));
+            // Waiting for the busy worker is not the fault of the application.
+            assert!(workers.run(2, Duration::from_millis(50), || Ok(2)).is_err());
+            assert!(!workers.is_unresponsive(2));
+            release.send(()).unwrap();
+            assert!(busy.join().unwrap().is_ok());
+        });
+        assert_eq!(
+            workers.run(2, Duration::from_millis(50), || Ok(2)).ok(),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_call_timeout_follows_messaging_timeout() {
+        let workers = AxWorkers::new(1);
+        assert_eq!(workers.call_timeout(1), DEFAULT_AX_TIMEOUT + AX_CALL_GRACE);
+
+        workers
+            .timeouts
+            .lock()
+            .unwrap()
+            .insert(1, Duration::from_secs(4));
+        assert_eq!(workers.call_timeout(1), Duration::from_millis(4500));
+        assert_eq!(workers.call_timeout(2), DEFAULT_AX_TIMEOUT + AX_CALL_GRACE);
+    }
+}
@@ -24,6 +24,8 @@ pub struct Display {
     menubar_height: i32,
     /// Optional config override for the menubar height.
     menubar_height_override: Option<i32>,
+    /// Whether the menubar is automatically hidden, so it does not reserve any space.
+    menubar_hidden: bool,
     notch_height: i32,
 }
 
@@ -46,6 +48,7 @@ impl Display {
             bounds,
             menubar_height,
             menubar_height_override: None,
+            menubar_hidden: false,
             notch_height: 0,
         }
     }
@@ -127,15 +130,36 @@ impl Display {
     }
 
     pub fn menubar_height(&self) -> i32 {
+        let system_height = if self.menubar_hidden {
+            0
+        } else {
+            self.menubar_height
+        };
         self.menubar_height_override
-            .unwrap_or(self.menubar_height)
+            .unwrap_or(system_height)
             .max(self.notch_height)
     }
 
+    pub fn menubar_height_override(&self) -> Option<i32> {
+        self.menubar_height_override
+    }
+
     pub fn set_menubar_height_override(&mut self, height: Option<i32>) {
         self.menubar_height_override = height;
     }
 
+    pub fn menubar_hidden(&self) -> bool {
+        self.menubar_hidden
+    }
+
+    pub fn set_menubar_hidden(&mut self, hidden: bool) {
+        self.menubar_hidden = hidden;
+    }
+
+    pub fn notch_height(&self) -> i32 {
+        self.notch_height
+    }
+
     pub fn set_notch_height(&mut self, height: i32) {
         self.notch_height = height;
     }
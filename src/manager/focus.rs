@@ -0,0 +1,183 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use stdext::function_name;
+use tracing::{debug, warn};
+
+use super::skylight::{_SLPSSetFrontProcessWithOptions, SLPSPostEventRecordTo};
+use crate::config::FocusStrategy;
+use crate::errors::Result;
+use crate::platform::{ProcessSerialNumber, WinID, macos_major_version};
+use crate::util::MacResult;
+
+// const CPS_ALL_WINDOWS: u32 = 0x100;
+const CPS_USER_GENERATED: u32 = 0x200;
+// const CPS_NO_WINDOWS: u32 = 0x400;
+
+/// Set once posting event records failed, after which `FocusStrategy::Auto`
+/// sticks to the front process path for the rest of the session.
+static EVENT_RECORDS_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// A way of moving the keyboard focus to a window.
+pub(super) trait FocusMethod {
+    /// Focuses `window_id` of the process `psn`. `focused` is the currently
+    /// focused window and its process, if any.
+    fn focus(
+        &self,
+        psn: &ProcessSerialNumber,
+        window_id: WinID,
+        focused: Option<(WinID, ProcessSerialNumber)>,
+    ) -> Result<()>;
+}
+
+/// Brings the process to the front with the window, and makes the window key
+/// by posting synthesized event records to the application. Focuses windows
+/// without raising them, but crashes the window server on some macOS versions.
+struct EventRecordFocus;
+
+impl EventRecordFocus {
+    /// Tells the application that focus moves from window `from` to window `to`.
+    fn post_focus_change(
+        psn: &ProcessSerialNumber,
+        from: WinID,
+        focused_psn: &ProcessSerialNumber,
+        to: WinID,
+    ) -> Result<()> {
+        let mut event_bytes = [0u8; 0xf8];
+        event_bytes[0x04] = 0xf8;
+        event_bytes[0x08] = 0x0d;
+
+        event_bytes[0x8a] = 0x02;
+        event_bytes[0x3c..0x40].copy_from_slice(&from.to_ne_bytes());
+        unsafe { SLPSPostEventRecordTo(focused_psn, event_bytes.as_ptr().cast()) }
+            .to_result(function_name!())?;
+
+        // Artificially delay the activation. This is necessary because some
+        // applications appear to be confused if both of the events appear instantaneously.
+        thread::sleep(Duration::from_millis(20));
+
+        event_bytes[0x8a] = 0x01;
+        event_bytes[0x3c..0x40].copy_from_slice(&to.to_ne_bytes());
+        unsafe { SLPSPostEventRecordTo(psn, event_bytes.as_ptr().cast()) }
+            .to_result(function_name!())
+    }
+
+    /// Makes the window the key window for its application.
+    fn make_key_window(psn: &ProcessSerialNumber, window_id: WinID) -> Result<()> {
+        let mut event_bytes = [0u8; 0xf8];
+        event_bytes[0x04] = 0xf8;
+        event_bytes[0x3a] = 0x10;
+        event_bytes[0x3c..0x40].copy_from_slice(&window_id.to_ne_bytes());
+        event_bytes[0x20..0x30].fill(0xff);
+
+        event_bytes[0x08] = 0x01;
+        unsafe { SLPSPostEventRecordTo(psn, event_bytes.as_ptr().cast()) }
+            .to_result(function_name!())?;
+
+        event_bytes[0x08] = 0x02;
+        unsafe { SLPSPostEventRecordTo(psn, event_bytes.as_ptr().cast()) }
+            .to_result(function_name!())
+    }
+}
+
+impl FocusMethod for EventRecordFocus {
+    fn focus(
+        &self,
+        psn: &ProcessSerialNumber,
+        window_id: WinID,
+        focused: Option<(WinID, ProcessSerialNumber)>,
+    ) -> Result<()> {
+        // The process is brought to the front even when the focus change could
+        // not be posted, so the window is focused either way.
+        let posted = match focused {
+            Some((focused_id, focused_psn)) if focused_psn == *psn => {
+                Self::post_focus_change(psn, focused_id, &focused_psn, window_id)
+            }
+            _ => Ok(()),
+        };
+        unsafe { _SLPSSetFrontProcessWithOptions(psn, window_id, CPS_USER_GENERATED) }
+            .to_result(function_name!())?;
+        posted.and_then(|()| Self::make_key_window(psn, window_id))
+    }
+}
+
+/// Only brings the process to the front with the window. The window server
+/// makes it key, but usually also raises it.
+struct FrontProcessFocus;
+
+impl FocusMethod for FrontProcessFocus {
+    fn focus(
+        &self,
+        psn: &ProcessSerialNumber,
+        window_id: WinID,
+        _focused: Option<(WinID, ProcessSerialNumber)>,
+    ) -> Result<()> {
+        unsafe { _SLPSSetFrontProcessWithOptions(psn, window_id, CPS_USER_GENERATED) }
+            .to_result(function_name!())
+    }
+}
+
+/// Whether `strategy` posts synthesized event records on macOS `major_version`,
+/// given whether posting them `failed` before.
+fn uses_event_records(strategy: FocusStrategy, major_version: u32, failed: bool) -> bool {
+    // On macOS 14 (Sonoma), CGSEncodeEventRecord serializes the raw event
+    // buffer via NSKeyedArchiver, misinterpreting 0xFF fill as an ObjC class pointer,
+    // causing SIGABRT. See https://github.com/karinushka/paneru/issues/123
+    if major_version == 14 {
+        return false;
+    }
+    match strategy {
+        FocusStrategy::EventRecord => true,
+        FocusStrategy::FrontProcess => false,
+        FocusStrategy::Auto => !failed,
+    }
+}
+
+/// Focuses a window with the method selected by `strategy`. When the automatic
+/// strategy fails to post event records, it falls back to the front process
+/// method, now and for the rest of the session.
+pub(super) fn focus_window(
+    strategy: FocusStrategy,
+    psn: &ProcessSerialNumber,
+    window_id: WinID,
+    focused: Option<(WinID, ProcessSerialNumber)>,
+) {
+    let event_records = uses_event_records(
+        strategy,
+        macos_major_version(),
+        EVENT_RECORDS_FAILED.load(Ordering::Relaxed),
+    );
+    let method: &dyn FocusMethod = if event_records {
+        &EventRecordFocus
+    } else {
+        &FrontProcessFocus
+    };
+    let Err(err) = method.focus(psn, window_id, focused) else {
+        return;
+    };
+    if event_records && strategy == FocusStrategy::Auto {
+        EVENT_RECORDS_FAILED.store(true, Ordering::Relaxed);
+        warn!("focusing window {window_id} failed, no longer posting event records: {err}");
+        _ = FrontProcessFocus
+            .focus(psn, window_id, focused)
+            .inspect_err(|err| debug!("{err}"));
+    } else {
+        debug!("focusing window {window_id}: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uses_event_records() {
+        assert!(uses_event_records(FocusStrategy::Auto, 15, false));
+        assert!(!uses_event_records(FocusStrategy::Auto, 15, true));
+        assert!(uses_event_records(FocusStrategy::EventRecord, 15, true));
+        assert!(!uses_event_records(FocusStrategy::FrontProcess, 15, false));
+        // Sonoma crashes on event records, whatever the strategy.
+        assert!(!uses_event_records(FocusStrategy::Auto, 14, false));
+        assert!(!uses_event_records(FocusStrategy::EventRecord, 14, false));
+    }
+}
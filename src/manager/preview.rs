@@ -0,0 +1,105 @@
+use core::ptr::NonNull;
+use objc2::AnyThread;
+use objc2_app_kit::{NSBitmapImageFileType, NSBitmapImageRep};
+use objc2_core_foundation::{CFRetained, CGPoint, CGRect, CGSize};
+use objc2_core_graphics::{
+    CGBitmapContextCreate, CGBitmapContextCreateImage, CGColorSpace, CGContext, CGImage,
+    CGImageAlphaInfo, CGInterpolationQuality,
+};
+use objc2_foundation::NSDictionary;
+use std::ptr::null_mut;
+use stdext::function_name;
+
+use super::skylight::{
+    SLSCaptureWindowsContentsToRectWithOptions, SLSGetWindowBounds, SLSMainConnectionID,
+};
+use crate::errors::{Error, Result};
+use crate::platform::WinID;
+use crate::util::MacResult;
+
+/// Capture only the window itself, leaving out its shadow.
+const CAPTURE_IGNORE_GLOBAL_CLIP_SHAPE: u32 = 1 << 11;
+/// Capture at point resolution rather than the Retina backing store.
+const CAPTURE_NOMINAL_RESOLUTION: u32 = 1 << 9;
+
+/// Captures a window's contents and returns them as PNG bytes, scaled down so
+/// that the longer side is at most `max_size` pixels.
+///
+/// Works for windows on inactive spaces and for windows hidden behind others,
+/// which makes it suitable for previewing the tucked-away windows of a stack.
+pub fn capture_window_preview(window_id: WinID, max_size: u32) -> Result<Vec<u8>> {
+    let cid = unsafe { SLSMainConnectionID() };
+    let mut bounds = CGRect::default();
+    unsafe { SLSGetWindowBounds(cid, window_id, &mut bounds) }.to_result(function_name!())?;
+
+    let mut image: *mut CGImage = null_mut();
+    unsafe {
+        SLSCaptureWindowsContentsToRectWithOptions(
+            cid,
+            &raw const window_id,
+            1,
+            bounds,
+            CAPTURE_IGNORE_GLOBAL_CLIP_SHAPE | CAPTURE_NOMINAL_RESOLUTION,
+            &raw mut image,
+        )
+    }
+    .to_result(function_name!())?;
+    let image = NonNull::new(image)
+        .map(|image| unsafe { CFRetained::from_raw(image) })
+        .ok_or_else(|| {
+            Error::NotFound(format!(
+                "{}: no contents for window {window_id}",
+                function_name!()
+            ))
+        })?;
+
+    let scaled = downscale(&image, max_size)?;
+    encode_png(&scaled)
+}
+
+/// Redraws `image` into a smaller bitmap, keeping its aspect ratio. Images
+/// already within `max_size` are returned unchanged.
+fn downscale(image: &CFRetained<CGImage>, max_size: u32) -> Result<CFRetained<CGImage>> {
+    let width = CGImage::width(Some(image));
+    let height = CGImage::height(Some(image));
+    let longest = width.max(height);
+    if longest == 0 || longest <= max_size as usize {
+        return Ok(image.clone());
+    }
+
+    let scale = f64::from(max_size) / longest as f64;
+    let scaled_width = ((width as f64 * scale).round() as usize).max(1);
+    let scaled_height = ((height as f64 * scale).round() as usize).max(1);
+
+    let color_space = CGColorSpace::new_device_rgb();
+    let context = unsafe {
+        CGBitmapContextCreate(
+            null_mut(),
+            scaled_width,
+            scaled_height,
+            8,
+            0,
+            color_space.as_deref(),
+            CGImageAlphaInfo::PremultipliedLast.0,
+        )
+    }
+    .ok_or_else(|| Error::Generic(format!("{}: creating bitmap context", function_name!())))?;
+
+    CGContext::set_interpolation_quality(Some(&context), CGInterpolationQuality::High);
+    let rect = CGRect::new(
+        CGPoint::ZERO,
+        CGSize::new(scaled_width as f64, scaled_height as f64),
+    );
+    CGContext::draw_image(Some(&context), rect, Some(image));
+
+    CGBitmapContextCreateImage(Some(&context))
+        .ok_or_else(|| Error::Generic(format!("{}: creating scaled image", function_name!())))
+}
+
+fn encode_png(image: &CGImage) -> Result<Vec<u8>> {
+    let bitmap = NSBitmapImageRep::initWithCGImage(NSBitmapImageRep::alloc(), image);
+    let properties = NSDictionary::new();
+    unsafe { bitmap.representationUsingType_properties(NSBitmapImageFileType::PNG, &properties) }
+        .map(|data| data.to_vec())
+        .ok_or_else(|| Error::Generic(format!("{}: encoding PNG", function_name!())))
+}
@@ -91,6 +91,8 @@ pub trait ProcessApi: Send + Sync {
     ///
     /// `Some(Retained<NSRunningApplication>)` if an `NSRunningApplication` is available, otherwise `None`.
     fn application(&self) -> Option<Retained<NSRunningApplication>>;
+    /// Returns the bundle identifier of the application, if it has one.
+    fn bundle_id(&self) -> Option<String>;
     /// Checks if the process is ready for full window management.
     /// This typically involves ensuring the application has finished launching and is observable.
     ///
@@ -141,6 +143,15 @@ impl ProcessApi for ProcessOS {
         self.inner.application.clone()
     }
 
+    /// Returns the bundle identifier of the inner `Process` application.
+    fn bundle_id(&self) -> Option<String> {
+        self.inner
+            .application
+            .as_ref()
+            .and_then(|app| app.bundleIdentifier())
+            .map(|id| id.to_string())
+    }
+
     /// Delegates the `ready` call to the inner `Process`.
     fn ready(&mut self) -> bool {
         self.inner.ready()
@@ -2,9 +2,9 @@ use std::{ffi::c_void, os::raw::c_float, ptr::NonNull};
 
 use accessibility_sys::AXUIElementRef;
 use objc2_core_foundation::{
-    CFArray, CFDictionary, CFMutableData, CFNumber, CFString, CFType, CFUUID, CGPoint,
+    CFArray, CFDictionary, CFMutableData, CFNumber, CFString, CFType, CFUUID, CGPoint, CGRect,
 };
-use objc2_core_graphics::{CGDirectDisplayID, CGError};
+use objc2_core_graphics::{CGDirectDisplayID, CGError, CGImage};
 
 use crate::platform::{CFStringRef, ConnID, OSStatus, ProcessSerialNumber, WinID};
 
@@ -50,7 +50,23 @@ unsafe extern "C" {
     ///
     /// # Original signature
     /// extern `CGError` SLSGetWindowBounds(int cid, `uint32_t` wid, `CGRect` *frame);
-    // pub fn SLSGetWindowBounds(cid: ConnID, window_id: WinID, frame: &mut CGRect) -> CGError;
+    pub fn SLSGetWindowBounds(cid: ConnID, window_id: WinID, frame: &mut CGRect) -> CGError;
+
+    /// Retrieves the level a window is drawn at.
+    ///
+    /// # Arguments
+    ///
+    /// * `cid` - The `ConnID` of the connection.
+    /// * `window_id` - The `WinID` of the window.
+    /// * `level` - A mutable reference where the window level will be stored.
+    ///
+    /// # Returns
+    ///
+    /// A `CGError` indicating success or failure.
+    ///
+    /// # Original signature
+    /// extern `CGError` SLSGetWindowLevel(int cid, `uint32_t` wid, int *level);
+    pub fn SLSGetWindowLevel(cid: ConnID, window_id: WinID, level: &mut i32) -> CGError;
 
     /// Moves a window to a new position at the compositor level (no IPC to the app).
     ///
@@ -150,6 +166,18 @@ unsafe extern "C" {
     /// extern int SLSSpaceGetType(int cid, `uint64_t` `space_id`);
     pub fn SLSSpaceGetType(cid: ConnID, space_id: u64) -> i32;
 
+    /// Moves windows to a space, without switching to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `cid` - The `ConnID` of the connection.
+    /// * `windows` - A `CFArray` of window IDs to move.
+    /// * `space_id` - The ID of the destination space.
+    ///
+    /// # Original signature
+    /// extern void SLSMoveWindowsToManagedSpace(int cid, `CFArrayRef` windows, `uint64_t` sid);
+    pub fn SLSMoveWindowsToManagedSpace(cid: ConnID, windows: &CFArray, space_id: u64);
+
     /// Copies the active menu bar display identifier.
     /// This function returns a `CFStringRef` that identifies the display where the primary menu bar is currently active.
     ///
@@ -219,6 +247,20 @@ unsafe extern "C" {
     /// extern int SLSGetSpaceManagementMode(int cid);
     pub fn SLSGetSpaceManagementMode(cid: ConnID) -> i32;
 
+    /// Retrieves the space which is currently active.
+    ///
+    /// # Arguments
+    ///
+    /// * `cid` - The `ConnID` of the connection.
+    ///
+    /// # Returns
+    ///
+    /// A `u64` representing the active space ID, or 0 if the connection is no longer valid.
+    ///
+    /// # Original signature
+    /// extern `uint64_t` SLSGetActiveSpace(int cid);
+    pub fn SLSGetActiveSpace(cid: ConnID) -> u64;
+
     /// Copies a list of managed display spaces.
     /// This function returns an array of dictionaries, where each dictionary describes a managed display and its associated spaces.
     ///
@@ -541,4 +583,68 @@ unsafe extern "C" {
         brightness_levels: *const c_float,
         count: isize,
     ) -> CGError;
+
+    /// Captures the contents of windows into a single image, even if they are
+    /// obscured or on another space.
+    ///
+    /// # Arguments
+    ///
+    /// * `cid` - The `ConnID` of the connection.
+    /// * `window_list` - A pointer to the window IDs to capture.
+    /// * `count` - The number of window IDs in `window_list`.
+    /// * `rect` - The area to capture, in screen coordinates.
+    /// * `options` - A bitmask of capture options.
+    /// * `image` - Receives the captured image, owned by the caller.
+    ///
+    /// # Returns
+    ///
+    /// A `CGError` indicating success or failure.
+    ///
+    /// # Original signature
+    /// extern `CGError` SLSCaptureWindowsContentsToRectWithOptions(int cid, `uint32_t` *wid,
+    ///   `uint32_t` count, `CGRect` rect, `uint32_t` options, `CGImageRef` *image);
+    pub fn SLSCaptureWindowsContentsToRectWithOptions(
+        cid: ConnID,
+        window_list: *const WinID,
+        count: u32,
+        rect: CGRect,
+        options: u32,
+        image: *mut *mut CGImage,
+    ) -> CGError;
+
+    /// Creates a new space and returns its ID, or zero on failure.
+    ///
+    /// # Original signature
+    /// extern `uint64_t` SLSSpaceCreate(int cid, int unknown, `CFDictionaryRef` options);
+    pub fn SLSSpaceCreate(cid: ConnID, unknown: i32, options: *const CFDictionary) -> u64;
+
+    /// Destroys a space. Its windows are moved to another space by the window server.
+    ///
+    /// # Original signature
+    /// extern void SLSSpaceDestroy(int cid, `uint64_t` sid);
+    pub fn SLSSpaceDestroy(cid: ConnID, space_id: u64);
+
+    /// Sets the level a space is shown at, `0` for the regular desktops.
+    ///
+    /// # Original signature
+    /// extern void SLSSpaceSetAbsoluteLevel(int cid, `uint64_t` sid, int level);
+    pub fn SLSSpaceSetAbsoluteLevel(cid: ConnID, space_id: u64, level: i32);
+
+    /// Makes a space the current one of a display.
+    ///
+    /// # Original signature
+    /// extern void SLSManagedDisplaySetCurrentSpace(int cid, `CFStringRef` uuid, `uint64_t` sid);
+    pub fn SLSManagedDisplaySetCurrentSpace(cid: ConnID, uuid: CFStringRef, space_id: u64);
+
+    /// Shows the windows of the spaces in the array.
+    ///
+    /// # Original signature
+    /// extern void SLSShowSpaces(int cid, `CFArrayRef` spaces);
+    pub fn SLSShowSpaces(cid: ConnID, spaces: &CFArray);
+
+    /// Hides the windows of the spaces in the array.
+    ///
+    /// # Original signature
+    /// extern void SLSHideSpaces(int cid, `CFArrayRef` spaces);
+    pub fn SLSHideSpaces(cid: ConnID, spaces: &CFArray);
 }
@@ -0,0 +1,139 @@
+use objc2_core_foundation::{CFNumberType, CFString};
+use std::ptr::null;
+use stdext::function_name;
+use tracing::debug;
+
+use super::skylight::{
+    SLSHideSpaces, SLSManagedDisplaySetCurrentSpace, SLSShowSpaces, SLSSpaceCreate,
+    SLSSpaceDestroy, SLSSpaceGetType, SLSSpaceSetAbsoluteLevel,
+};
+use crate::commands::SpaceTarget;
+use crate::errors::{Error, Result};
+use crate::platform::{ConnID, WorkspaceId};
+use crate::util::create_array;
+
+/// The type `SLSSpaceGetType` returns for regular desktops.
+const SPACE_TYPE_USER: i32 = 0;
+
+/// Picks the space `target` refers to, from the space list of a display.
+fn target_space(
+    spaces: &[WorkspaceId],
+    current: WorkspaceId,
+    target: SpaceTarget,
+) -> Result<WorkspaceId> {
+    let position = spaces.iter().position(|space| *space == current);
+    let index = match target {
+        SpaceTarget::Next => position.map(|index| index + 1),
+        SpaceTarget::Previous => position.and_then(|index| index.checked_sub(1)),
+        SpaceTarget::Number(number) => number.checked_sub(1),
+    };
+    index
+        .and_then(|index| spaces.get(index))
+        .copied()
+        .ok_or(Error::NotFound(format!(
+            "{}: no space {target:?} from space {current}",
+            function_name!()
+        )))
+}
+
+/// Makes `space_id` the current space of the display, hiding the previous one.
+fn switch_space(
+    cid: ConnID,
+    uuid: &CFString,
+    current: WorkspaceId,
+    space_id: WorkspaceId,
+) -> Result<()> {
+    debug!("switching from space {current} to {space_id}");
+    let shown = create_array(&[space_id], CFNumberType::SInt64Type)?;
+    let hidden = create_array(&[current], CFNumberType::SInt64Type)?;
+    unsafe {
+        SLSShowSpaces(cid, &shown);
+        SLSManagedDisplaySetCurrentSpace(cid, &raw const *uuid, space_id);
+        SLSHideSpaces(cid, &hidden);
+    }
+    Ok(())
+}
+
+/// Switches the display to another space of its list.
+pub(super) fn focus_space(
+    cid: ConnID,
+    uuid: &CFString,
+    spaces: &[WorkspaceId],
+    current: WorkspaceId,
+    target: SpaceTarget,
+) -> Result<()> {
+    let space_id = target_space(spaces, current, target)?;
+    if space_id == current {
+        return Ok(());
+    }
+    switch_space(cid, uuid, current, space_id)
+}
+
+/// Adds a new desktop, which the window server places on the display.
+pub(super) fn create_space(cid: ConnID) -> Result<()> {
+    let space_id = unsafe { SLSSpaceCreate(cid, 1, null()) };
+    if space_id == 0 {
+        return Err(Error::Generic(format!(
+            "{}: unable to create a space",
+            function_name!()
+        )));
+    }
+    // Places the space with the regular desktops, below the fullscreen ones.
+    unsafe { SLSSpaceSetAbsoluteLevel(cid, space_id, 0) };
+    debug!("created space {space_id}");
+    Ok(())
+}
+
+/// Removes a desktop of the display. The current space is switched away from
+/// first, to its left neighbour, or the right one when it is the first.
+pub(super) fn destroy_space(
+    cid: ConnID,
+    uuid: &CFString,
+    spaces: &[WorkspaceId],
+    current: WorkspaceId,
+    space_id: WorkspaceId,
+) -> Result<()> {
+    if unsafe { SLSSpaceGetType(cid, space_id) } != SPACE_TYPE_USER {
+        return Err(Error::InvalidInput(format!(
+            "{}: space {space_id} is not a desktop",
+            function_name!()
+        )));
+    }
+    if spaces.len() < 2 {
+        return Err(Error::InvalidInput(format!(
+            "{}: space {space_id} is the last one of the display",
+            function_name!()
+        )));
+    }
+    if space_id == current {
+        let neighbour = target_space(spaces, current, SpaceTarget::Previous)
+            .or_else(|_| target_space(spaces, current, SpaceTarget::Next))?;
+        switch_space(cid, uuid, current, neighbour)?;
+    }
+    debug!("destroying space {space_id}");
+    unsafe { SLSSpaceDestroy(cid, space_id) };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_space() {
+        let spaces = [3, 5, 8];
+        assert_eq!(target_space(&spaces, 5, SpaceTarget::Next).ok(), Some(8));
+        assert_eq!(
+            target_space(&spaces, 5, SpaceTarget::Previous).ok(),
+            Some(3)
+        );
+        assert_eq!(
+            target_space(&spaces, 5, SpaceTarget::Number(1)).ok(),
+            Some(3)
+        );
+        assert!(target_space(&spaces, 8, SpaceTarget::Next).is_err());
+        assert!(target_space(&spaces, 3, SpaceTarget::Previous).is_err());
+        assert!(target_space(&spaces, 3, SpaceTarget::Number(0)).is_err());
+        assert!(target_space(&spaces, 3, SpaceTarget::Number(4)).is_err());
+    }
+}
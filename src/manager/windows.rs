@@ -1,8 +1,9 @@
 use accessibility_sys::{
     AXUIElementCreateApplication, AXUIElementRef, AXValueCreate, AXValueGetValue,
-    kAXFloatingWindowSubrole, kAXPositionAttribute, kAXRaiseAction, kAXSizeAttribute,
-    kAXStandardWindowSubrole, kAXUnknownSubrole, kAXValueTypeCGPoint, kAXValueTypeCGSize,
-    kAXWindowRole,
+    kAXCloseButtonAttribute, kAXFloatingWindowSubrole, kAXMainWindowAttribute,
+    kAXPositionAttribute, kAXPressAction, kAXRaiseAction, kAXSizeAttribute,
+    kAXStandardWindowSubrole, kAXTitleAttribute, kAXUnknownSubrole, kAXValueTypeCGPoint,
+    kAXValueTypeCGSize, kAXWindowRole, kAXZoomButtonAttribute,
 };
 use bevy::ecs::component::Component;
 use bevy::math::IRect;
@@ -14,22 +15,21 @@ use objc2_core_foundation::{
     kCFBooleanFalse, kCFBooleanTrue,
 };
 use std::collections::HashMap;
-use std::ptr::null_mut;
-use std::sync::{LazyLock, Mutex, OnceLock};
-use std::thread;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
 use std::time::Duration;
 use stdext::function_name;
 use tracing::{Level, debug, instrument, trace, warn};
 
+use super::focus;
 use super::skylight::{
-    _AXUIElementGetWindow, _SLPSSetFrontProcessWithOptions, AXUIElementCopyAttributeValue,
-    AXUIElementPerformAction, AXUIElementSetAttributeValue, SLPSPostEventRecordTo,
-    SLSWindowIteratorAdvance,
+    _AXUIElementGetWindow, AXUIElementPerformAction, SLSGetWindowBounds, SLSMainConnectionID,
+    SLSWindowIteratorAdvance, SLSWindowIteratorGetParentID,
 };
-use crate::config::Config;
+use crate::config::{Config, FocusStrategy};
 use crate::errors::{Error, Result};
-use crate::manager::{Origin, Size, irect_from};
-use crate::platform::{Pid, ProcessSerialNumber, WinID, macos_major_version};
+use crate::manager::{Origin, Size, ax, irect_from};
+use crate::platform::{Pid, ProcessSerialNumber, WinID};
 use crate::util::{AXUIAttributes, AXUIWrapper, MacResult};
 
 /// Per-PID ref-count for the `AXEnhancedUserInterface` workaround. Tracks how many
@@ -38,22 +38,42 @@ use crate::util::{AXUIAttributes, AXUIWrapper, MacResult};
 static ENHANCED_UI_REFCOUNT: LazyLock<Mutex<HashMap<Pid, usize>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
+/// How long to wait for an application to answer a title query. A busy or hung
+/// application keeps its cached title and the query finishes in the background.
+const TITLE_QUERY_TIMEOUT: Duration = Duration::from_millis(50);
+
 #[derive(Debug)]
 pub enum WindowPadding {
     Vertical(i32),
     Horizontal(i32),
 }
 
+/// Invisible margins which an application includes in the frame it reports,
+/// e.g. shadows or resize borders of windows drawing their own chrome. The
+/// visible window is the reported frame shrunk by these amounts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameInset {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
 #[automock]
 pub trait WindowApi: Send + Sync {
     fn id(&self) -> WinID;
     fn frame(&self) -> IRect;
     fn element(&self) -> Option<CFRetained<AXUIWrapper>>;
     fn title(&self) -> Result<String>;
+    /// Re-reads the cached title from the application, after it was changed.
+    fn refresh_title(&self);
     fn identifier(&self) -> Result<String>;
     fn child_role(&self) -> Result<bool>;
     fn role(&self) -> Result<String>;
     fn subrole(&self) -> Result<String>;
+    /// Asks the application whether the window still exists. Unlike the cached
+    /// `role`, this is answered by the application every time.
+    fn is_alive(&self) -> bool;
     fn is_minimized(&self) -> bool;
     fn is_full_screen(&self) -> bool;
     fn reposition(&mut self, origin: Origin);
@@ -64,17 +84,30 @@ pub trait WindowApi: Send + Sync {
         psn: ProcessSerialNumber,
         currently_focused: &Window,
         focused_psn: ProcessSerialNumber,
+        strategy: FocusStrategy,
     );
-    fn focus_with_raise(&self, psn: ProcessSerialNumber);
+    fn focus_with_raise(&self, psn: ProcessSerialNumber, strategy: FocusStrategy);
     /// Raises the window in the OS z-order without changing focus. Used to
     /// shuffle the floating-vs-tiled tier order. Best-effort: AX raise can't
     /// lift a window above another app's frontmost window.
     fn raise_without_focus(&self);
+    /// Closes the window by pressing its close button, as the user would.
+    fn close(&self) -> Result<()>;
+    /// Zooms the window the way macOS does, by pressing its zoom button.
+    fn zoom_native(&self) -> Result<()>;
     fn pid(&self) -> Result<Pid>;
     fn set_padding(&mut self, padding: WindowPadding);
     fn horizontal_padding(&self) -> i32;
     fn vertical_padding(&self) -> i32;
+    fn set_frame_inset(&mut self, inset: FrameInset);
+    /// Sets the size below which the window is never resized.
+    fn set_min_size(&mut self, size: Size);
+    /// Detects the frame inset by comparing the accessibility frame with the
+    /// window server bounds of the window.
+    fn detect_frame_inset(&self) -> Option<FrameInset>;
     fn border_radius(&self) -> Option<f64>;
+    /// Number of tabs in the window's native tab bar, or `None` if it has no tab bar.
+    fn native_tab_count(&self) -> Option<usize>;
 }
 
 #[derive(Component, Deref, DerefMut)]
@@ -111,10 +144,6 @@ pub fn ax_window_id(element_ref: AXUIElementRef) -> Result<WinID> {
     Ok(window_id)
 }
 
-// const CPS_ALL_WINDOWS: u32 = 0x100;
-const CPS_USER_GENERATED: u32 = 0x200;
-// const CPS_NO_WINDOWS: u32 = 0x400;
-
 #[derive(Debug)]
 pub struct WindowOS {
     id: WinID,
@@ -122,9 +151,15 @@ pub struct WindowOS {
     frame: IRect,
     vertical_padding: i32,
     horizontal_padding: i32,
+    inset: FrameInset,
+    min_size: Size,
     border_radius: OnceLock<Option<f64>>,
     pid: OnceLock<Result<Pid>>,
     app_reference: OnceLock<Option<CFRetained<AXUIWrapper>>>,
+    role: OnceLock<String>,
+    subrole: OnceLock<String>,
+    title: Arc<Mutex<Option<String>>>,
+    title_pending: Arc<AtomicBool>,
 }
 
 impl WindowOS {
@@ -160,18 +195,7 @@ impl WindowOS {
         config: &Config,
         bundle_id: Option<&str>,
     ) -> Result<Self> {
-        let id = ax_window_id(element.as_ptr())?;
-        let window = Self {
-            id,
-            ax_element: element.clone(),
-            frame: IRect::default(),
-            vertical_padding: 0,
-            horizontal_padding: 0,
-            border_radius: OnceLock::new(),
-            pid: OnceLock::new(),
-            app_reference: OnceLock::new(),
-        };
-
+        let window = Self::from_element(element)?;
         let forced = window.is_forced_manage(config, bundle_id);
 
         if window.is_unknown() && !forced {
@@ -202,6 +226,73 @@ impl WindowOS {
         Ok(window)
     }
 
+    /// Creates a `Window` instance for a dialog, which is not managed but placed
+    /// over its parent window.
+    ///
+    /// # Returns
+    ///
+    /// `Ok((Window, WinID))` with the dialog and the id of its parent window,
+    /// otherwise `Err(Error)` if the element is not a dialog or has no parent.
+    pub fn new_dialog(element: &CFRetained<AXUIWrapper>) -> Result<(Self, WinID)> {
+        const DIALOG_SUBROLES: [&str; 2] = ["AXDialog", "AXSystemDialog"];
+
+        // Sheets are not handled here, the system attaches them to their parent.
+        let window = Self::from_element(element)?;
+        let subrole = window.subrole()?;
+        if !DIALOG_SUBROLES.contains(&subrole.as_str()) {
+            return Err(Error::invalid_window(&format!(
+                "window {} with subrole {subrole} is not a dialog",
+                window.id()
+            )));
+        }
+        let parent = window.parent_id().ok_or(Error::NotFound(format!(
+            "{}: no parent for dialog {}",
+            function_name!(),
+            window.id()
+        )))?;
+        Ok((window, parent))
+    }
+
+    fn from_element(element: &CFRetained<AXUIWrapper>) -> Result<Self> {
+        let id = ax_window_id(element.as_ptr())?;
+        ax::apply_messaging_timeout(element);
+        Ok(Self {
+            id,
+            ax_element: element.clone(),
+            frame: IRect::default(),
+            vertical_padding: 0,
+            horizontal_padding: 0,
+            inset: FrameInset::default(),
+            min_size: Size::ZERO,
+            border_radius: OnceLock::new(),
+            pid: OnceLock::new(),
+            app_reference: OnceLock::new(),
+            role: OnceLock::new(),
+            subrole: OnceLock::new(),
+            title: Arc::new(Mutex::new(None)),
+            title_pending: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Finds the window this one belongs to: the parent window known to the
+    /// window server, otherwise the main window of the application.
+    fn parent_id(&self) -> Option<WinID> {
+        if let Some(iterator) = super::window_iterator_for_id(self.id)
+            && unsafe { SLSWindowIteratorAdvance(&raw const *iterator) }
+        {
+            let parent: WinID = unsafe { SLSWindowIteratorGetParentID(&raw const *iterator) };
+            if parent != 0 {
+                return Some(parent);
+            }
+        }
+        let main_window = CFString::from_static_str(kAXMainWindowAttribute);
+        self.app_reference()?
+            .get_attribute::<AXUIWrapper>(&main_window)
+            .and_then(|main_window| ax_window_id(main_window.as_ptr()))
+            .ok()
+            .filter(|parent| *parent != self.id)
+    }
+
     /// Checks whether a configured window rule forces this window to be managed
     /// despite having a non-standard role/subrole.
     fn is_forced_manage(&self, config: &Config, bundle_id: Option<&str>) -> bool {
@@ -245,12 +336,80 @@ impl WindowOS {
                 self.pid()
                     .map(|pid| unsafe { AXUIElementCreateApplication(pid) })
                     .and_then(AXUIWrapper::from_retained)
+                    .inspect(ax::apply_messaging_timeout)
                     .inspect_err(|err| warn!("error getting app reference: {err}"))
                     .ok()
             })
             .clone()
     }
 
+    /// Reads the frame of the window as reported by the application.
+    fn ax_frame(&self) -> Result<IRect> {
+        let position = ax::copy_attribute(
+            &self.ax_element,
+            &CFString::from_static_str(kAXPositionAttribute),
+        )?;
+        let size = ax::copy_attribute(
+            &self.ax_element,
+            &CFString::from_static_str(kAXSizeAttribute),
+        )?;
+
+        let mut frame = CGRect::default();
+        unsafe {
+            AXValueGetValue(
+                position.as_ptr(),
+                kAXValueTypeCGPoint,
+                NonNull::from(&mut frame.origin).as_ptr().cast(),
+            );
+            AXValueGetValue(
+                size.as_ptr(),
+                kAXValueTypeCGSize,
+                NonNull::from(&mut frame.size).as_ptr().cast(),
+            );
+        }
+        Ok(irect_from(frame))
+    }
+
+    fn cached_title(&self) -> Result<String> {
+        self.title
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+            .ok_or(Error::NotFound(format!(
+                "{}: title of window {} is not known yet.",
+                function_name!(),
+                self.id
+            )))
+    }
+
+    /// Reads the title from the application on an AX worker and caches it.
+    /// When the application does not answer within `TITLE_QUERY_TIMEOUT`, the
+    /// previously cached title is returned and the worker updates the cache
+    /// once the application responds. Only one query per window is in flight,
+    /// so a hung application does not tie up the workers.
+    fn query_title(&self) -> Result<String> {
+        if self.title_pending.swap(true, Ordering::AcqRel) {
+            return self.cached_title();
+        }
+
+        let cache = self.title.clone();
+        let pending = self.title_pending.clone();
+        let name = CFString::from_static_str(kAXTitleAttribute);
+        ax::copy_attribute_detached(&self.ax_element, &name, TITLE_QUERY_TIMEOUT, move |title| {
+            if let Ok(title) = title {
+                *cache
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(title_string(title));
+            }
+            pending.store(false, Ordering::Release);
+        })
+        .map(|title| title_string(&title))
+        .or_else(|err| {
+            debug!("window {}: {err}, using cached title", self.id);
+            self.cached_title()
+        })
+    }
+
     /// Disables `AXEnhancedUserInterface` on this window's app if it is currently enabled.
     ///
     /// Uses a per-PID ref-count so that concurrent operations on windows of the same app
@@ -275,13 +434,8 @@ impl WindowOS {
             .get_attribute::<CFBoolean>(&attr)
             .is_ok_and(|v| CFBoolean::value(&v));
         if enabled {
-            unsafe {
-                AXUIElementSetAttributeValue(
-                    app_element.as_ptr(),
-                    attr.as_ref(),
-                    kCFBooleanFalse.unwrap(),
-                );
-            }
+            _ = ax::set_attribute(&app_element, &attr, kCFBooleanFalse.unwrap())
+                .inspect_err(|err| trace!("{err}"));
             counts.insert(pid, 1);
         }
     }
@@ -303,42 +457,29 @@ impl WindowOS {
         counts.remove(&pid);
         if let Some(app_element) = self.app_reference() {
             let attr = CFString::from_static_str("AXEnhancedUserInterface");
-            unsafe {
-                AXUIElementSetAttributeValue(
-                    app_element.as_ptr(),
-                    attr.as_ref(),
-                    kCFBooleanTrue.unwrap(),
-                );
-            }
+            _ = ax::set_attribute(&app_element, &attr, kCFBooleanTrue.unwrap())
+                .inspect_err(|err| trace!("{err}"));
         }
     }
+}
 
-    /// Makes the window the key window for its application by sending synthesized events.
-    ///
-    /// # Arguments
-    ///
-    /// * `psn` - The process serial number of the application.
-    fn make_key_window(&self, psn: &ProcessSerialNumber) {
-        // Reason: On macOS 14 (Sonoma), CGSEncodeEventRecord serializes the raw event
-        // buffer via NSKeyedArchiver, misinterpreting 0xFF fill as an ObjC class pointer,
-        // causing SIGABRT. See https://github.com/karinushka/paneru/issues/123
-        if macos_major_version() == 14 {
-            debug!("make_key_window: skipped on macOS 14 (Sonoma) to prevent crash");
-            return;
-        }
-        let window_id = self.id();
-        let mut event_bytes = [0u8; 0xf8];
-        event_bytes[0x04] = 0xf8;
-        event_bytes[0x3a] = 0x10;
-        event_bytes[0x3c..0x40].copy_from_slice(&window_id.to_ne_bytes());
-        event_bytes[0x20..0x30].fill(0xff);
-
-        event_bytes[0x08] = 0x01;
-        unsafe { SLPSPostEventRecordTo(psn, event_bytes.as_ptr().cast()) };
+fn title_string(title: &CFRetained<AXUIWrapper>) -> String {
+    let title: CFRetained<CFString> =
+        unsafe { CFRetained::from_raw(CFRetained::into_raw(title.clone()).cast()) };
+    title.to_string()
+}
 
-        event_bytes[0x08] = 0x02;
-        unsafe { SLPSPostEventRecordTo(psn, event_bytes.as_ptr().cast()) };
-    }
+/// Returns an attribute which does not change during the window's lifetime,
+/// querying the application only until it answered successfully once.
+fn cached_attribute(
+    cache: &OnceLock<String>,
+    query: impl FnOnce() -> Result<String>,
+) -> Result<String> {
+    if let Some(value) = cache.get() {
+        return Ok(value.clone());
+    }
+    let value = query()?;
+    Ok(cache.get_or_init(|| value).clone())
 }
 
 impl WindowApi for WindowOS {
@@ -375,7 +516,16 @@ impl WindowApi for WindowOS {
     ///
     /// `Ok(String)` with the window title if successful, otherwise `Err(Error)`.
     fn title(&self) -> Result<String> {
-        self.ax_element.title()
+        match self.cached_title() {
+            Ok(title) => Ok(title),
+            Err(_) => self.query_title(),
+        }
+    }
+
+    fn refresh_title(&self) {
+        _ = self
+            .query_title()
+            .inspect_err(|err| debug!("window {}: {err}", self.id));
     }
 
     fn identifier(&self) -> Result<String> {
@@ -396,7 +546,7 @@ impl WindowApi for WindowOS {
     ///
     /// `Ok(String)` with the window role if successful, otherwise `Err(Error)`.
     fn role(&self) -> Result<String> {
-        self.ax_element.role()
+        cached_attribute(&self.role, || self.ax_element.role())
     }
 
     /// Retrieves the subrole of the window (e.g., "`AXStandardWindow`").
@@ -405,7 +555,11 @@ impl WindowApi for WindowOS {
     ///
     /// `Ok(String)` with the window subrole if successful, otherwise `Err(Error)`.
     fn subrole(&self) -> Result<String> {
-        self.ax_element.subrole()
+        cached_attribute(&self.subrole, || self.ax_element.subrole())
+    }
+
+    fn is_alive(&self) -> bool {
+        self.ax_element.role().is_ok()
     }
 
     #[instrument(level = Level::DEBUG, ret)]
@@ -425,8 +579,8 @@ impl WindowApi for WindowOS {
         }
         self.disable_enhanced_ui();
         let mut point = CGPoint::new(
-            f64::from(origin.x + self.horizontal_padding),
-            f64::from(origin.y + self.vertical_padding),
+            f64::from(origin.x + self.horizontal_padding - self.inset.left),
+            f64::from(origin.y + self.vertical_padding - self.inset.top),
         );
         let position_ref = unsafe {
             AXValueCreate(
@@ -435,13 +589,12 @@ impl WindowApi for WindowOS {
             )
         };
         if let Ok(position) = AXUIWrapper::retain(position_ref) {
-            unsafe {
-                AXUIElementSetAttributeValue(
-                    self.ax_element.as_ptr(),
-                    CFString::from_static_str(kAXPositionAttribute).as_ref(),
-                    position.as_ref(),
-                )
-            };
+            _ = ax::set_attribute(
+                &self.ax_element,
+                &CFString::from_static_str(kAXPositionAttribute),
+                position.as_ref(),
+            )
+            .inspect_err(|err| trace!("{err}"));
             let size = self.frame.size();
             self.frame.min = origin;
             self.frame.max = origin + size;
@@ -451,13 +604,14 @@ impl WindowApi for WindowOS {
 
     #[instrument(level = Level::TRACE)]
     fn resize(&mut self, size: Size) {
+        let size = size.max(self.min_size);
         if self.frame.size() == size {
             trace!("already correct size.");
             return;
         }
         self.disable_enhanced_ui();
-        let width_padding = 2 * self.horizontal_padding;
-        let height_padding = 2 * self.vertical_padding;
+        let width_padding = 2 * self.horizontal_padding - self.inset.left - self.inset.right;
+        let height_padding = 2 * self.vertical_padding - self.inset.top - self.inset.bottom;
         let mut cgsize = CGSize::new(
             f64::from(size.x - width_padding),
             f64::from(size.y - height_padding),
@@ -469,13 +623,12 @@ impl WindowApi for WindowOS {
             )
         };
         if let Ok(position) = AXUIWrapper::retain(size_ref) {
-            unsafe {
-                AXUIElementSetAttributeValue(
-                    self.ax_element.as_ptr(),
-                    CFString::from_static_str(kAXSizeAttribute).as_ref(),
-                    position.as_ref(),
-                )
-            };
+            _ = ax::set_attribute(
+                &self.ax_element,
+                &CFString::from_static_str(kAXSizeAttribute),
+                position.as_ref(),
+            )
+            .inspect_err(|err| trace!("{err}"));
             self.frame.max = self.frame.min + size;
         }
         self.reenable_enhanced_ui();
@@ -492,56 +645,17 @@ impl WindowApi for WindowOS {
     ///
     /// `Ok(())` if the frame is updated successfully, otherwise `Err(Error)`.
     fn update_frame(&mut self) -> Result<IRect> {
-        let window_ref = self.ax_element.as_ptr();
-
-        let position = unsafe {
-            let mut position_ref: *mut CFType = null_mut();
-            AXUIElementCopyAttributeValue(
-                window_ref,
-                CFString::from_static_str(kAXPositionAttribute).as_ref(),
-                &mut position_ref,
-            )
-            .to_result(function_name!())?;
-            AXUIWrapper::retain(position_ref)?
-        };
-        let size = unsafe {
-            let mut size_ref: *mut CFType = null_mut();
-            AXUIElementCopyAttributeValue(
-                window_ref,
-                CFString::from_static_str(kAXSizeAttribute).as_ref(),
-                &mut size_ref,
-            )
-            .to_result(function_name!())?;
-            AXUIWrapper::retain(size_ref)?
-        };
-
-        let mut frame = CGRect::default();
-        unsafe {
-            AXValueGetValue(
-                position.as_ptr(),
-                kAXValueTypeCGPoint,
-                NonNull::from(&mut frame.origin).as_ptr().cast(),
-            );
-            AXValueGetValue(
-                size.as_ptr(),
-                kAXValueTypeCGSize,
-                NonNull::from(&mut frame.size).as_ptr().cast(),
-            );
-        }
-        // if (CGRectEqualToRect(new_frame, window->frame)) {
-        //     debug("%s:DEBOUNCED %s %d\n", __FUNCTION__, window->application->name, window->id);
-        // }
-        self.frame = irect_from(frame);
+        self.frame = self.ax_frame()?;
 
-        self.frame.min.x -= self.horizontal_padding;
-        self.frame.min.y -= self.vertical_padding;
-        self.frame.max.x += self.horizontal_padding;
-        self.frame.max.y += self.vertical_padding;
+        self.frame.min.x += self.inset.left - self.horizontal_padding;
+        self.frame.min.y += self.inset.top - self.vertical_padding;
+        self.frame.max.x += self.horizontal_padding - self.inset.right;
+        self.frame.max.y += self.vertical_padding - self.inset.bottom;
 
         Ok(self.frame)
     }
 
-    /// Focuses the window without raising it. This involves sending specific events to the process.
+    /// Focuses the window without raising it, as far as the focus `strategy` allows.
     ///
     /// # Arguments
     ///
@@ -552,45 +666,16 @@ impl WindowApi for WindowOS {
         psn: ProcessSerialNumber,
         currently_focused: &Window,
         focused_psn: ProcessSerialNumber,
+        strategy: FocusStrategy,
     ) {
-        let window_id = self.id();
-        debug!("{window_id}");
-        if focused_psn == psn {
-            let mut event_bytes = [0u8; 0xf8];
-            event_bytes[0x04] = 0xf8;
-            event_bytes[0x08] = 0x0d;
-
-            event_bytes[0x8a] = 0x02;
-            event_bytes[0x3c..0x40].copy_from_slice(&currently_focused.id().to_ne_bytes());
-            unsafe {
-                SLPSPostEventRecordTo(&focused_psn, event_bytes.as_ptr().cast());
-            }
-
-            // Artificially delay the activation. This is necessary because some
-            // applications appear to be confused if both of the events appear instantaneously.
-            thread::sleep(Duration::from_millis(20));
-
-            event_bytes[0x8a] = 0x01;
-            event_bytes[0x3c..0x40].copy_from_slice(&window_id.to_ne_bytes());
-            unsafe {
-                SLPSPostEventRecordTo(&psn, event_bytes.as_ptr().cast());
-            }
-        }
-
-        unsafe {
-            _SLPSSetFrontProcessWithOptions(&psn, window_id, CPS_USER_GENERATED);
-        }
-        self.make_key_window(&psn);
+        let focused = Some((currently_focused.id(), focused_psn));
+        focus::focus_window(strategy, &psn, self.id(), focused);
     }
 
     /// Focuses the window and raises it to the front.
     #[instrument(level = Level::DEBUG)]
-    fn focus_with_raise(&self, psn: ProcessSerialNumber) {
-        let window_id = self.id();
-        unsafe {
-            _SLPSSetFrontProcessWithOptions(&psn, window_id, CPS_USER_GENERATED);
-        }
-        self.make_key_window(&psn);
+    fn focus_with_raise(&self, psn: ProcessSerialNumber, strategy: FocusStrategy) {
+        focus::focus_window(strategy, &psn, self.id(), None);
         let element_ref = self.ax_element.as_ptr();
         let action = CFString::from_static_str(kAXRaiseAction);
         unsafe { AXUIElementPerformAction(element_ref, &action) };
@@ -603,6 +688,20 @@ impl WindowApi for WindowOS {
         unsafe { AXUIElementPerformAction(element_ref, &action) };
     }
 
+    #[instrument(level = Level::DEBUG)]
+    fn close(&self) -> Result<()> {
+        let name = CFString::from_static_str(kAXCloseButtonAttribute);
+        let button = ax::copy_attribute(&self.ax_element, &name)?;
+        ax::perform_action(&button, kAXPressAction)
+    }
+
+    #[instrument(level = Level::DEBUG)]
+    fn zoom_native(&self) -> Result<()> {
+        let name = CFString::from_static_str(kAXZoomButtonAttribute);
+        let button = ax::copy_attribute(&self.ax_element, &name)?;
+        ax::perform_action(&button, kAXPressAction)
+    }
+
     fn pid(&self) -> Result<Pid> {
         self.pid
             .get_or_init(|| {
@@ -634,6 +733,34 @@ impl WindowApi for WindowOS {
         self.vertical_padding
     }
 
+    fn set_frame_inset(&mut self, inset: FrameInset) {
+        self.inset = inset;
+    }
+
+    fn set_min_size(&mut self, size: Size) {
+        self.min_size = size;
+    }
+
+    fn detect_frame_inset(&self) -> Option<FrameInset> {
+        let mut bounds = CGRect::default();
+        unsafe { SLSGetWindowBounds(SLSMainConnectionID(), self.id, &mut bounds) }
+            .to_result(function_name!())
+            .inspect_err(|err| debug!("{err}"))
+            .ok()?;
+        let server = irect_from(bounds);
+        let reported = self.ax_frame().inspect_err(|err| debug!("{err}")).ok()?;
+        // Only the parts of the reported frame outside of the window server
+        // bounds are invisible.
+        let inset = FrameInset {
+            top: (server.min.y - reported.min.y).max(0),
+            right: (reported.max.x - server.max.x).max(0),
+            bottom: (reported.max.y - server.max.y).max(0),
+            left: (server.min.x - reported.min.x).max(0),
+        };
+        debug!("window {} frame inset {inset:?}", self.id);
+        Some(inset)
+    }
+
     // Based on:
     // - https://github.com/y3owk1n/rift/blob/cca067145f0282b532e848bb63d26a38c61f3c14/src/sys/window_server.rs#L175
     // - https://github.com/FelixKratz/JankyBorders/blob/a56a76a8a6ed77325f03655b23fcf525144d120b/src/windows.c#L67
@@ -665,4 +792,22 @@ impl WindowApi for WindowOS {
             radii.get(0)?.as_i64().map(|v| v as f64)
         })
     }
+
+    /// Counts the tabs of the native tab bar, an `AXTabGroup` among the window's
+    /// children whose tabs are radio buttons. Not cached, as tabs come and go.
+    fn native_tab_count(&self) -> Option<usize> {
+        let tab_group = self
+            .ax_element
+            .children()
+            .ok()?
+            .into_iter()
+            .find(|child| child.role().is_ok_and(|role| role == "AXTabGroup"))?;
+        let tabs = tab_group
+            .children()
+            .ok()?
+            .iter()
+            .filter(|child| child.role().is_ok_and(|role| role == "AXRadioButton"))
+            .count();
+        Some(tabs)
+    }
 }
@@ -0,0 +1,277 @@
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Length of the window over which the event rate is measured.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Process wide counters. They are plain atomics, because they are bumped from
+/// the ECS systems as well as from the OS wrappers, which have no access to the world.
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+struct RateWindow {
+    started: Instant,
+    count: u64,
+    rate: f64,
+}
+
+struct Metrics {
+    started: Instant,
+    events_total: AtomicU64,
+    reshuffles_total: AtomicU64,
+    reshuffle_nanos_total: AtomicU64,
+    ax_failures_total: AtomicU64,
+    animation_frames_total: AtomicU64,
+    animation_frame_nanos_total: AtomicU64,
+    animation_frame_nanos_max: AtomicU64,
+    paused: AtomicBool,
+    event_rate: Mutex<RateWindow>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            events_total: AtomicU64::new(0),
+            reshuffles_total: AtomicU64::new(0),
+            reshuffle_nanos_total: AtomicU64::new(0),
+            ax_failures_total: AtomicU64::new(0),
+            animation_frames_total: AtomicU64::new(0),
+            animation_frame_nanos_total: AtomicU64::new(0),
+            animation_frame_nanos_max: AtomicU64::new(0),
+            paused: AtomicBool::new(false),
+            event_rate: Mutex::new(RateWindow {
+                started: now,
+                count: 0,
+                rate: 0.0,
+            }),
+        }
+    }
+}
+
+fn as_nanos(duration: Duration) -> u64 {
+    duration.as_nanos().try_into().unwrap_or(u64::MAX)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn nanos_to_ms(nanos: u64) -> f64 {
+    nanos as f64 / 1_000_000.0
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn average_ms(total_nanos: u64, count: u64) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        nanos_to_ms(total_nanos) / count as f64
+    }
+}
+
+/// Records a batch of events pulled from the OS event channel.
+#[allow(clippy::cast_precision_loss)]
+pub fn record_events(count: usize) {
+    let count = count as u64;
+    METRICS.events_total.fetch_add(count, Ordering::Relaxed);
+
+    let mut window = METRICS
+        .event_rate
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    window.count += count;
+    let elapsed = window.started.elapsed();
+    if elapsed >= RATE_WINDOW {
+        window.rate = window.count as f64 / elapsed.as_secs_f64();
+        window.started = Instant::now();
+        window.count = 0;
+    }
+}
+
+/// Records the time spent reshuffling a layout strip.
+pub fn record_reshuffle(duration: Duration) {
+    METRICS.reshuffles_total.fetch_add(1, Ordering::Relaxed);
+    METRICS
+        .reshuffle_nanos_total
+        .fetch_add(as_nanos(duration), Ordering::Relaxed);
+}
+
+/// Records a failed accessibility call.
+pub fn record_ax_failure() {
+    METRICS.ax_failures_total.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records the interval between two consecutive animation frames.
+pub fn record_animation_frame(delta: Duration) {
+    let nanos = as_nanos(delta);
+    METRICS
+        .animation_frames_total
+        .fetch_add(1, Ordering::Relaxed);
+    METRICS
+        .animation_frame_nanos_total
+        .fetch_add(nanos, Ordering::Relaxed);
+    METRICS
+        .animation_frame_nanos_max
+        .fetch_max(nanos, Ordering::Relaxed);
+}
+
+/// Records whether the window management is paused by `paneru pause`.
+pub fn set_paused(paused: bool) {
+    METRICS.paused.store(paused, Ordering::Relaxed);
+}
+
+/// Point-in-time copy of the counters, as returned by `paneru stats`.
+#[derive(Clone, Debug, Serialize)]
+pub struct MetricsSnapshot {
+    pub uptime_secs: u64,
+    pub events_total: u64,
+    pub events_per_sec: f64,
+    pub reshuffles_total: u64,
+    pub reshuffle_avg_ms: f64,
+    pub ax_failures_total: u64,
+    pub animation_frames_total: u64,
+    pub animation_frame_avg_ms: f64,
+    pub animation_frame_max_ms: f64,
+    pub paused: bool,
+}
+
+impl MetricsSnapshot {
+    #[allow(clippy::cast_precision_loss)]
+    pub fn capture() -> Self {
+        let events_per_sec = {
+            let window = METRICS
+                .event_rate
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            let elapsed = window.started.elapsed();
+            // The rate is only refreshed when events arrive, so a quiet period
+            // has to be accounted for here.
+            if elapsed >= RATE_WINDOW * 2 {
+                window.count as f64 / elapsed.as_secs_f64()
+            } else {
+                window.rate
+            }
+        };
+        let reshuffles_total = METRICS.reshuffles_total.load(Ordering::Relaxed);
+        let animation_frames_total = METRICS.animation_frames_total.load(Ordering::Relaxed);
+
+        Self {
+            uptime_secs: METRICS.started.elapsed().as_secs(),
+            events_total: METRICS.events_total.load(Ordering::Relaxed),
+            events_per_sec,
+            reshuffles_total,
+            reshuffle_avg_ms: average_ms(
+                METRICS.reshuffle_nanos_total.load(Ordering::Relaxed),
+                reshuffles_total,
+            ),
+            ax_failures_total: METRICS.ax_failures_total.load(Ordering::Relaxed),
+            animation_frames_total,
+            animation_frame_avg_ms: average_ms(
+                METRICS.animation_frame_nanos_total.load(Ordering::Relaxed),
+                animation_frames_total,
+            ),
+            animation_frame_max_ms: nanos_to_ms(
+                METRICS.animation_frame_nanos_max.load(Ordering::Relaxed),
+            ),
+            paused: METRICS.paused.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Renders the snapshot in the Prometheus text exposition format.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn to_prometheus(&self) -> String {
+        let metrics: [(&str, &str, &str, f64); 10] = [
+            (
+                "uptime_seconds",
+                "gauge",
+                "Seconds since the daemon started.",
+                self.uptime_secs as f64,
+            ),
+            (
+                "events_total",
+                "counter",
+                "Events received from the OS.",
+                self.events_total as f64,
+            ),
+            (
+                "events_per_second",
+                "gauge",
+                "Events received during the last second.",
+                self.events_per_sec,
+            ),
+            (
+                "reshuffles_total",
+                "counter",
+                "Layout strip reshuffles.",
+                self.reshuffles_total as f64,
+            ),
+            (
+                "reshuffle_avg_milliseconds",
+                "gauge",
+                "Average duration of a reshuffle.",
+                self.reshuffle_avg_ms,
+            ),
+            (
+                "ax_failures_total",
+                "counter",
+                "Failed accessibility calls.",
+                self.ax_failures_total as f64,
+            ),
+            (
+                "animation_frames_total",
+                "counter",
+                "Animated frames.",
+                self.animation_frames_total as f64,
+            ),
+            (
+                "animation_frame_avg_milliseconds",
+                "gauge",
+                "Average interval between animated frames.",
+                self.animation_frame_avg_ms,
+            ),
+            (
+                "animation_frame_max_milliseconds",
+                "gauge",
+                "Longest interval between animated frames.",
+                self.animation_frame_max_ms,
+            ),
+            (
+                "paused",
+                "gauge",
+                "Whether the window management is paused.",
+                f64::from(u8::from(self.paused)),
+            ),
+        ];
+
+        let mut output = String::new();
+        for (name, kind, help, value) in metrics {
+            _ = writeln!(output, "# HELP paneru_{name} {help}");
+            _ = writeln!(output, "# TYPE paneru_{name} {kind}");
+            _ = writeln!(output, "paneru_{name} {value}");
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prometheus_format() {
+        record_reshuffle(Duration::from_millis(2));
+        record_ax_failure();
+        let snapshot = MetricsSnapshot::capture();
+        assert!(snapshot.reshuffles_total >= 1);
+        assert!(snapshot.ax_failures_total >= 1);
+
+        let text = snapshot.to_prometheus();
+        assert!(text.contains("# TYPE paneru_reshuffles_total counter\n"));
+        assert!(text.contains(&format!(
+            "paneru_ax_failures_total {}\n",
+            snapshot.ax_failures_total
+        )));
+    }
+}
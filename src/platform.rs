@@ -8,17 +8,22 @@ use objc2_core_foundation::CFString;
 use objc2_foundation::{NSDate, NSDefaultRunLoopMode, NSProcessInfo};
 use std::ffi::c_void;
 use std::pin::Pin;
-use tracing::error;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
 
 use crate::config::{CONFIGURATION_FILE, Config};
 use crate::errors::{Error, Result};
 use crate::events::{Event, EventSender};
-use crate::manager::{check_ax_privilege, check_separate_spaces};
+use crate::manager::{check_ax_privilege, check_separate_spaces, missing_session_services};
 use crate::platform::display::PinnedDisplayHandler;
+use crate::platform::hotkey::PinnedRescueHotkeys;
 use crate::platform::input::PinnedInputHandler;
 use crate::platform::notify::{NotifyHandler, PinnedNotifyHandler};
+use crate::platform::power::{PinnedPowerHandler, PowerHandler};
 use crate::platform::process::PinnedProcessHandler;
 use display::DisplayHandler;
+use hotkey::RescueHotkeys;
 use input::InputHandler;
 use mission_control::MissionControlHandler;
 use process::ProcessHandler;
@@ -26,11 +31,14 @@ pub use process::ProcessSerialNumber;
 pub use workspace::WorkspaceObserver;
 
 mod display;
+mod hotkey;
 pub(crate) mod input;
 mod mission_control;
 pub mod notify;
+mod power;
 mod process;
 pub mod service;
+pub mod signals;
 mod workspace;
 
 /// Type alias for `OSStatus`, a 32-bit integer error code used by macOS system services.
@@ -46,6 +54,41 @@ pub type CFStringRef = *const CFString;
 
 pub type WorkspaceId = u64;
 
+/// First and longest pause between the checks of the login session.
+const SESSION_BACKOFF_MIN: Duration = Duration::from_millis(100);
+const SESSION_BACKOFF_MAX: Duration = Duration::from_secs(5);
+/// How long to wait for the login session before starting anyway.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Waits with a growing backoff until the window server, the Dock and the
+/// accessibility server are available. At login the launch agent can start
+/// before them, and failing the setup would leave it to launchd restarts.
+/// After `SESSION_TIMEOUT` the setup goes ahead and reports what is missing.
+pub fn wait_for_session() {
+    let start = Instant::now();
+    let mut backoff = SESSION_BACKOFF_MIN;
+    loop {
+        let missing = missing_session_services();
+        if missing.is_empty() {
+            if backoff > SESSION_BACKOFF_MIN {
+                info!("login session ready after {:?}", start.elapsed());
+            }
+            return;
+        }
+        if start.elapsed() >= SESSION_TIMEOUT {
+            warn!(
+                "starting without {} after waiting {}s",
+                missing.join(", "),
+                SESSION_TIMEOUT.as_secs()
+            );
+            return;
+        }
+        debug!("waiting {backoff:?} for {}", missing.join(", "));
+        thread::sleep(backoff);
+        backoff = (backoff * 2).min(SESSION_BACKOFF_MAX);
+    }
+}
+
 bitflags::bitflags! {
     #[derive(Clone, Copy, Debug, PartialEq)]
     pub struct Modifiers: u16 {
@@ -58,10 +101,14 @@ bitflags::bitflags! {
         const LCTRL  = 1 << 6;
         const RCTRL  = 1 << 7;
         const FN     = 1 << 8;
+        /// Caps Lock is engaged.
+        const CAPS   = 1 << 9;
         const ALT   = Self::LALT.bits() | Self::RALT.bits();
         const SHIFT = Self::LSHIFT.bits() | Self::RSHIFT.bits();
         const CMD   = Self::LCMD.bits() | Self::RCMD.bits();
         const CTRL  = Self::LCTRL.bits() | Self::RCTRL.bits();
+        /// All four modifiers, which is what a Caps Lock key remapped to hyper sends.
+        const HYPER = Self::ALT.bits() | Self::SHIFT.bits() | Self::CMD.bits() | Self::CTRL.bits();
     }
 }
 
@@ -146,6 +193,8 @@ pub struct PlatformCallbacks {
     process_handler: Option<PinnedProcessHandler>,
     /// Handler for low-level input events (keyboard, mouse, gestures).
     event_handler: Option<PinnedInputHandler>,
+    /// Rescue hotkeys which keep working without the event tap.
+    rescue_hotkeys: Option<PinnedRescueHotkeys>,
     /// Observer for `NSWorkspace` and distributed notifications.
     workspace_observer: Retained<WorkspaceObserver>,
     /// Handler for Mission Control accessibility events.
@@ -153,6 +202,8 @@ pub struct PlatformCallbacks {
     /// Handler for Core Graphics display reconfiguration events.
     display_handler: Option<PinnedDisplayHandler>,
     notify_handler: Option<PinnedNotifyHandler>,
+    /// Handler for IOKit power source notifications.
+    power_handler: Option<PinnedPowerHandler>,
 }
 
 impl PlatformCallbacks {
@@ -184,10 +235,12 @@ impl PlatformCallbacks {
             cocoa_app,
             process_handler: None,
             event_handler: None,
+            rescue_hotkeys: None,
             workspace_observer,
             mission_control_observer: MissionControlHandler::new(events.clone()),
             display_handler: None,
             notify_handler: None,
+            power_handler: None,
             events,
         })
     }
@@ -213,18 +266,24 @@ impl PlatformCallbacks {
         }
 
         if !check_separate_spaces() {
-            error!("Option 'display has separate spaces' disabled.");
-            return Err(Error::InvalidConfig(
-                "Option 'display has separate spaces' disabled.".to_string(),
-            ));
+            warn!(
+                "Option 'Displays have separate Spaces' is disabled, tiling each display as a \
+                 single strip. Moving windows between spaces works best with the option enabled."
+            );
         }
 
         let config = Config::new(CONFIGURATION_FILE.as_path())?;
         self.events.send(Event::InitialConfig(config.clone()))?;
-        self.event_handler = Some(InputHandler::new(self.events.clone(), config).start()?);
+        self.event_handler = Some(InputHandler::new(self.events.clone(), config.clone()).start()?);
+        self.register_rescue_hotkeys(&config);
 
         self.notify_handler = Some(NotifyHandler::new(self.events.clone()).start()?);
         self.display_handler = Some(DisplayHandler::new(self.events.clone()).start()?);
+        // Only the low power mode depends on the power source, the rest works without it.
+        self.power_handler = PowerHandler::new(self.events.clone())
+            .start()
+            .inspect_err(|err| warn!("unable to watch the power source: {err}"))
+            .ok();
         self.process_handler = Some(
             ProcessHandler::new(self.events.clone(), self.workspace_observer.clone()).start()?,
         );
@@ -234,6 +293,22 @@ impl PlatformCallbacks {
         self.events.send(Event::ProcessesLoaded)
     }
 
+    /// Registers the rescue hotkeys of the configuration, replacing the ones
+    /// registered before. A failure is only logged, as the bindings of the
+    /// event tap keep working without them.
+    pub fn register_rescue_hotkeys(&mut self, config: &Config) {
+        // The previous hotkeys go first, the new ones may use the same keys.
+        self.rescue_hotkeys = None;
+        let tap_port = self
+            .event_handler
+            .as_ref()
+            .and_then(|handler| handler.tap_port());
+        self.rescue_hotkeys = RescueHotkeys::new(self.events.clone(), tap_port)
+            .start(&config.rescue_hotkeys())
+            .inspect_err(|err| error!("unable to register the rescue hotkeys: {err}"))
+            .ok();
+    }
+
     pub fn pump_cocoa_event_loop(&mut self, timeout: f64) {
         autoreleasepool(|_| {
             let until_date = NSDate::dateWithTimeIntervalSinceNow(timeout);
@@ -264,7 +339,13 @@ impl Modifiers {
     /// For each modifier group (alt, shift, cmd, ctrl):
     ///   - If the binding requires the group, the event must have at least one matching side bit.
     ///   - If the binding does NOT require the group, the event must not have any bits from that group.
+    ///
+    /// Caps Lock stays engaged between key presses, so it is only checked by bindings requiring it.
     pub fn matches(self, event: Modifiers) -> bool {
+        if self.contains(Modifiers::CAPS) && !event.contains(Modifiers::CAPS) {
+            return false;
+        }
+
         const GROUPS: [Modifiers; 5] = [
             Modifiers::ALT,
             Modifiers::SHIFT,
@@ -405,6 +486,26 @@ mod tests {
         assert!(!want_fn.matches(Modifiers::LALT | Modifiers::FN));
     }
 
+    #[test]
+    fn matches_caps_lock_only_when_bound() {
+        let caps_h = Modifiers::CAPS;
+        assert!(caps_h.matches(Modifiers::CAPS));
+        assert!(!caps_h.matches(Modifiers::empty()));
+        assert!(!caps_h.matches(Modifiers::CAPS | Modifiers::LALT));
+        // An engaged Caps Lock does not get in the way of other bindings.
+        assert!(Modifiers::ALT.matches(Modifiers::LALT | Modifiers::CAPS));
+        assert!(Modifiers::empty().matches(Modifiers::CAPS));
+    }
+
+    #[test]
+    fn matches_hyper() {
+        assert!(
+            Modifiers::HYPER
+                .matches(Modifiers::LALT | Modifiers::LSHIFT | Modifiers::LCMD | Modifiers::LCTRL)
+        );
+        assert!(!Modifiers::HYPER.matches(Modifiers::LALT | Modifiers::LCMD | Modifiers::LCTRL));
+    }
+
     #[test]
     fn matches_fn_combined_with_other_groups() {
         let fn_alt = Modifiers::FN | Modifiers::ALT;
@@ -114,6 +114,10 @@ impl DisplayHandler {
             Event::DisplayAdded { display_id }
         } else if flags.contains(CGDisplayChangeSummaryFlags::RemoveFlag) {
             Event::DisplayRemoved { display_id }
+        } else if flags.contains(CGDisplayChangeSummaryFlags::SetMainFlag) {
+            // Usually combined with MovedFlag, but every display has to be
+            // measured again, not only the ones reported as moved.
+            Event::MainDisplayChanged { display_id }
         } else if flags.contains(CGDisplayChangeSummaryFlags::MovedFlag) {
             Event::DisplayMoved { display_id }
         } else if flags.contains(CGDisplayChangeSummaryFlags::DesktopShapeChangedFlag) {
@@ -0,0 +1,257 @@
+use core::ptr::NonNull;
+use objc2_core_foundation::{CFMachPort, CFRetained};
+use objc2_core_graphics::CGEvent;
+use scopeguard::ScopeGuard;
+use std::ffi::c_void;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use stdext::function_name;
+use tracing::{debug, error, info, warn};
+
+use super::process::{
+    EventTypeSpec, GetApplicationEventTarget, GetEventParameter, InstallEventHandler, ProcessEvent,
+    ProcessEventHandler, ProcessEventTarget, RemoveEventHandler,
+};
+use crate::commands::Command;
+use crate::config::RescueAction;
+use crate::errors::{Error, Result};
+use crate::events::{Event, EventSender};
+use crate::platform::{Modifiers, OSStatus};
+
+/// Identifies a registered hotkey. The signature is shared by all of paneru's
+/// hotkeys, the id is the index into the registered actions.
+#[repr(C)]
+#[derive(Default)]
+struct EventHotKeyID {
+    signature: u32,
+    id: u32,
+}
+
+/// An opaque type representing a Carbon hotkey reference.
+#[repr(C)]
+struct EventHotKeyRef {
+    _opaque: [u8; 0],
+}
+
+unsafe extern "C" {
+    /// Registers a global hotkey, delivered as a `kEventHotKeyPressed` event to `target`.
+    ///
+    /// # Original signature
+    /// extern `OSStatus`
+    /// `RegisterEventHotKey`(
+    ///   `UInt32`            inHotKeyCode,
+    ///   `UInt32`            inHotKeyModifiers,
+    ///   `EventHotKeyID`     inHotKeyID,
+    ///   `EventTargetRef`    inTarget,
+    ///   `OptionBits`        inOptions,
+    ///   `EventHotKeyRef` *  outRef)
+    fn RegisterEventHotKey(
+        keycode: u32,
+        modifiers: u32,
+        hotkey_id: EventHotKeyID,
+        target: *const ProcessEventTarget,
+        options: u32,
+        hotkey_ref: *mut *const EventHotKeyRef,
+    ) -> OSStatus;
+
+    /// Unregisters a hotkey previously registered with `RegisterEventHotKey`.
+    ///
+    /// # Original signature
+    /// extern `OSStatus` UnregisterEventHotKey(EventHotKeyRef inHotKey)
+    fn UnregisterEventHotKey(hotkey_ref: *const EventHotKeyRef) -> OSStatus;
+}
+
+const HOTKEY_SIGNATURE: &[u8; 4] = b"pnru";
+
+/// `RescueHotkeys` registers a few critical bindings through the Carbon hotkey
+/// API. These are delivered through the application event loop rather than the
+/// `CGEventTap`, so they still work when the tap has been disabled or died.
+pub(super) struct RescueHotkeys {
+    /// The `EventSender` for dispatching the quit and restart commands.
+    events: EventSender,
+    /// The event tap port, re-enabled by `RescueAction::ReenableTap`.
+    tap_port: Option<CFRetained<CFMachPort>>,
+    /// Registered actions, indexed by the hotkey id.
+    actions: Vec<RescueAction>,
+    // Prevents from being Unpin automatically
+    _pin: PhantomPinned,
+}
+
+pub(super) type PinnedRescueHotkeys =
+    ScopeGuard<Pin<Box<RescueHotkeys>>, Box<dyn FnOnce(Pin<Box<RescueHotkeys>>)>>;
+
+impl RescueHotkeys {
+    /// Creates a new `RescueHotkeys` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - An `EventSender` to send the rescue commands.
+    /// * `tap_port` - The `CFMachPort` of the input event tap, if it was created.
+    pub(super) fn new(events: EventSender, tap_port: Option<CFRetained<CFMachPort>>) -> Self {
+        RescueHotkeys {
+            events,
+            tap_port,
+            actions: Vec::new(),
+            _pin: PhantomPinned,
+        }
+    }
+
+    /// Installs the hotkey event handler and registers the given hotkeys.
+    /// Both are removed again when the returned guard is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `hotkeys` - The `(action, keycode, modifiers)` triples to register.
+    pub(super) fn start(
+        mut self,
+        hotkeys: &[(RescueAction, u8, Modifiers)],
+    ) -> Result<PinnedRescueHotkeys> {
+        const KEYBOARD_CLASS: &[u8; 4] = b"keyb";
+        const HOTKEY_PRESSED: u32 = 5;
+
+        self.actions = hotkeys.iter().map(|(action, _, _)| *action).collect();
+
+        let target = unsafe { GetApplicationEventTarget() };
+        let events = [EventTypeSpec {
+            event_class: u32::from_be_bytes(*KEYBOARD_CLASS),
+            event_kind: HOTKEY_PRESSED,
+        }];
+
+        let mut pinned = Box::pin(self);
+        let this = unsafe { NonNull::new_unchecked(pinned.as_mut().get_unchecked_mut()) }.as_ptr();
+        let mut handler: *const ProcessEventHandler = std::ptr::null();
+        let result = unsafe {
+            InstallEventHandler(
+                target,
+                Self::callback,
+                events.len().try_into()?,
+                events.as_ptr(),
+                this.cast(),
+                &raw mut handler,
+            )
+        };
+        if result != 0 || handler.is_null() {
+            return Err(Error::PermissionDenied(format!(
+                "{}: Error registering hotkey event handler.",
+                function_name!()
+            )));
+        }
+
+        let mut registered = Vec::new();
+        for (id, (action, keycode, modifiers)) in hotkeys.iter().enumerate() {
+            let hotkey_id = EventHotKeyID {
+                signature: u32::from_be_bytes(*HOTKEY_SIGNATURE),
+                id: id.try_into()?,
+            };
+            let mut hotkey: *const EventHotKeyRef = std::ptr::null();
+            let result = unsafe {
+                RegisterEventHotKey(
+                    u32::from(*keycode),
+                    carbon_modifiers(*modifiers),
+                    hotkey_id,
+                    target,
+                    0,
+                    &raw mut hotkey,
+                )
+            };
+            if result != 0 || hotkey.is_null() {
+                warn!("unable to register rescue hotkey for {action:?}: {result}");
+                continue;
+            }
+            debug!("registered rescue hotkey {action:?}");
+            registered.push(hotkey);
+        }
+
+        Ok(scopeguard::guard(
+            pinned,
+            Box::new(move |_: Pin<Box<Self>>| {
+                info!("Unregistering rescue hotkeys");
+                for hotkey in registered {
+                    unsafe { UnregisterEventHotKey(hotkey) };
+                }
+                unsafe { RemoveEventHandler(handler) };
+            }),
+        ))
+    }
+
+    /// The C-callback invoked by the Carbon event manager when one of the
+    /// registered hotkeys is pressed.
+    extern "C-unwind" fn callback(
+        _: *mut c_void,
+        event: *const ProcessEvent,
+        this: *const c_void,
+    ) -> OSStatus {
+        const DIRECT_OBJECT: &[u8; 4] = b"----";
+        const HOTKEY_ID_TYPE: &[u8; 4] = b"hkid";
+
+        let Some(this) = NonNull::new(this.cast_mut())
+            .map(|this| unsafe { this.cast::<RescueHotkeys>().as_ref() })
+        else {
+            error!("Zero passed to Rescue Hotkey Handler.");
+            return 0;
+        };
+
+        let mut hotkey_id = EventHotKeyID::default();
+        let res = unsafe {
+            GetEventParameter(
+                event,
+                u32::from_be_bytes(*DIRECT_OBJECT),
+                u32::from_be_bytes(*HOTKEY_ID_TYPE),
+                std::ptr::null_mut(),
+                size_of::<EventHotKeyID>().try_into().unwrap_or_default(),
+                std::ptr::null_mut(),
+                NonNull::from(&mut hotkey_id).as_ptr().cast(),
+            )
+        };
+        if res == 0 && hotkey_id.signature == u32::from_be_bytes(*HOTKEY_SIGNATURE) {
+            let action = usize::try_from(hotkey_id.id)
+                .ok()
+                .and_then(|id| this.actions.get(id));
+            if let Some(action) = action {
+                this.rescue(*action);
+            }
+        }
+        0
+    }
+
+    /// Performs the rescue action.
+    fn rescue(&self, action: RescueAction) {
+        info!("rescue hotkey: {action:?}");
+        let command = match action {
+            RescueAction::Quit => Command::Quit,
+            RescueAction::Restart => Command::Restart,
+            RescueAction::ReenableTap => {
+                if let Some(port) = &self.tap_port {
+                    CGEvent::tap_enable(port, true);
+                }
+                return;
+            }
+        };
+        if let Err(err) = self.events.send(Event::Command { command }) {
+            // The event loop is gone, nothing left to deliver the command to.
+            error!("error sending rescue command: {err}");
+            if action == RescueAction::Quit {
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Converts paneru modifiers into Carbon hotkey modifiers. Carbon does not
+/// distinguish between left and right modifiers, and has no `fn` modifier.
+fn carbon_modifiers(modifiers: Modifiers) -> u32 {
+    const CMD_KEY: u32 = 1 << 8;
+    const SHIFT_KEY: u32 = 1 << 9;
+    const OPTION_KEY: u32 = 1 << 11;
+    const CONTROL_KEY: u32 = 1 << 12;
+
+    [
+        (Modifiers::CMD, CMD_KEY),
+        (Modifiers::SHIFT, SHIFT_KEY),
+        (Modifiers::ALT, OPTION_KEY),
+        (Modifiers::CTRL, CONTROL_KEY),
+    ]
+    .into_iter()
+    .filter(|(modifier, _)| modifiers.intersects(*modifier))
+    .fold(0, |acc, (_, carbon)| acc | carbon)
+}
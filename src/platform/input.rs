@@ -1,9 +1,10 @@
 use arc_swap::ArcSwap;
+use bevy::math::IRect;
 use core::ptr::NonNull;
 use objc2::msg_send;
 use objc2::rc::Retained;
 use objc2_app_kit::{NSEvent, NSEventType, NSTouch, NSTouchPhase};
-use objc2_core_foundation::{CFMachPort, CFRetained, CFRunLoop, kCFRunLoopCommonModes};
+use objc2_core_foundation::{CFMachPort, CFRetained, CFRunLoop, CGPoint, kCFRunLoopCommonModes};
 use objc2_core_graphics::{
     CGEvent, CGEventField, CGEventFlags, CGEventTapLocation, CGEventTapOptions,
     CGEventTapPlacement, CGEventTapProxy, CGEventType,
@@ -14,17 +15,25 @@ use std::ffi::c_void;
 use std::marker::PhantomPinned;
 use std::pin::Pin;
 use std::ptr::null_mut;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, LazyLock};
 use std::time::{Duration, Instant};
 use stdext::function_name;
 use tracing::{error, info};
 
-use crate::config::Config;
+use crate::config::{Config, MouseTrigger};
 use crate::errors::{Error, Result};
 use crate::events::{Event, EventSender};
+use crate::manager::ax::zoom_button_at;
+use crate::manager::origin_from;
 use crate::platform::Modifiers;
 
 const NX_DEVICEFNKEYMASK: u64 = 0x0080_0100;
+const NX_ALPHASHIFTMASK: u64 = 0x0001_0000;
+
+/// Stored in the user data field of keyboard events paneru posts itself, so
+/// that they are not matched against the bindings again.
+pub(crate) const SYNTHETIC_EVENT_MARKER: i64 = 0x7061_6e65;
 
 /// The currently active set of passthrough keybindings, shared lock-free with
 /// the `CGEvent` tap callback thread via `ArcSwap`.
@@ -37,10 +46,49 @@ pub fn set_focused_passthrough(keys: Vec<(u8, Modifiers)>) {
     FOCUSED_PASSTHROUGH.store(Arc::new(keys));
 }
 
+/// The title bars of the tiled windows, where a click may land on a zoom button.
+/// Shared with the `CGEvent` tap, so that it only asks accessibility for the
+/// element under clicks landing there.
+static TITLE_BARS: LazyLock<ArcSwap<Vec<IRect>>> = LazyLock::new(|| ArcSwap::from_pointee(vec![]));
+
+/// Replaces the title bars the event tap checks on every left click. Called
+/// from the ECS thread when the tiled windows move.
+pub fn set_title_bars(title_bars: Vec<IRect>) {
+    TITLE_BARS.store(Arc::new(title_bars));
+}
+
+/// Returns `true` if `point` lies on a title bar of a tiled window.
+fn on_title_bar(point: CGPoint) -> bool {
+    let point = origin_from(point);
+    TITLE_BARS.load().iter().any(|bar| bar.contains(point))
+}
+
+/// Set while a socket client waits to inspect a window: the next left click is
+/// swallowed and reported as `Event::InspectClick` instead.
+static INSPECTING: AtomicBool = AtomicBool::new(false);
+
+/// Starts or cancels waiting for the click which picks the window to inspect.
+pub fn set_inspecting(inspecting: bool) {
+    INSPECTING.store(inspecting, Ordering::Relaxed);
+}
+
+/// Set while window management is suspended for a presentation or a game: no
+/// input is intercepted and all of it reaches the frontmost app.
+static SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+/// Suspends or resumes handling keyboard, mouse and trackpad input.
+pub fn set_input_suspended(suspended: bool) {
+    SUSPENDED.store(suspended, Ordering::Relaxed);
+}
+
 /// How long to suppress scroll wheel events after a vertical swipe gesture,
 /// covering macOS momentum scroll that continues after finger lift.
 const VERTICAL_GESTURE_SCROLL_SUPPRESS: Duration = Duration::from_millis(1200);
 
+/// Minimal time between two commands triggered by bound scroll directions, as
+/// trackpads and free spinning wheels send a stream of scroll events.
+const SCROLL_BINDING_INTERVAL: Duration = Duration::from_millis(250);
+
 const SWIPE_THRESHOLD: f64 = 0.001;
 const GESTURE_MINIMAL_FINGERS: usize = 3;
 
@@ -59,6 +107,14 @@ pub(super) struct InputHandler {
     /// are suppressed for a short window after this to prevent the OS from
     /// scrolling windows underneath (including momentum scroll after finger lift).
     last_swipe_time: Option<Instant>,
+    /// Timestamp of the last command triggered by a bound scroll direction.
+    last_scroll_binding: Option<Instant>,
+    /// Bitmask of mouse buttons whose press triggered a command, so that
+    /// their release is intercepted as well.
+    suppressed_buttons: u32,
+    /// The left click which picked a window to inspect, or pressed a zoom button,
+    /// was intercepted, so its release is intercepted as well.
+    swallow_mouse_up: bool,
     // Prevents from being Unpin automatically
     _pin: PhantomPinned,
 }
@@ -84,6 +140,9 @@ impl InputHandler {
             finger_position: None,
             tap_port: None,
             last_swipe_time: None,
+            last_scroll_binding: None,
+            suppressed_buttons: 0,
+            swallow_mouse_up: false,
             _pin: PhantomPinned,
         }
     }
@@ -101,6 +160,8 @@ impl InputHandler {
             | (1 << CGEventType::RightMouseDown.0)
             | (1 << CGEventType::RightMouseUp.0)
             | (1 << CGEventType::RightMouseDragged.0)
+            | (1 << CGEventType::OtherMouseDown.0)
+            | (1 << CGEventType::OtherMouseUp.0)
             | (1 << CGEventType::ScrollWheel.0)
             | (1 << NSEventType::Gesture.0)
             | (1 << CGEventType::KeyDown.0);
@@ -152,6 +213,11 @@ impl InputHandler {
         ))
     }
 
+    /// Returns the `CFMachPort` of the event tap, once it has been created.
+    pub(super) fn tap_port(&self) -> Option<CFRetained<CFMachPort>> {
+        self.tap_port.clone()
+    }
+
     /// The C-callback function for the `CGEventTap`. It dispatches to the `input_handler` method.
     /// This function is declared as `extern "C-unwind"`.
     ///
@@ -205,10 +271,48 @@ impl InputHandler {
         let result = match event_type {
             CGEventType::TapDisabledByTimeout | CGEventType::TapDisabledByUserInput => {
                 info!("Tap Disabled");
-                if let Some(port) = &self.tap_port {
-                    CGEvent::tap_enable(port, true);
+                match &self.tap_port {
+                    Some(port) => {
+                        CGEvent::tap_enable(port, true);
+                        if CGEvent::tap_is_enabled(port) {
+                            Ok(())
+                        } else {
+                            events.send(Event::Alert {
+                                title: "Paneru stopped receiving input".to_string(),
+                                message: "Keyboard and mouse events are not reaching Paneru. \
+                                          Restart it with 'paneru restart'."
+                                    .to_string(),
+                            })
+                        }
+                    }
+                    None => Ok(()),
                 }
-                Ok(())
+            }
+            CGEventType::LeftMouseDown if INSPECTING.swap(false, Ordering::Relaxed) => {
+                let point = CGEvent::location(Some(event));
+                self.swallow_mouse_up = true;
+                if let Err(err) = events.send(Event::InspectClick { point }) {
+                    error!("error sending event: {err}");
+                }
+                return true;
+            }
+            CGEventType::LeftMouseUp if self.swallow_mouse_up => {
+                self.swallow_mouse_up = false;
+                return true;
+            }
+            _ if SUSPENDED.load(Ordering::Relaxed) => return false,
+            CGEventType::LeftMouseDown
+                if modifiers.is_empty()
+                    && self.config.zoom_button_full_width()
+                    && on_title_bar(CGEvent::location(Some(event)))
+                    && zoom_button_at(CGEvent::location(Some(event))) =>
+            {
+                let point = CGEvent::location(Some(event));
+                self.swallow_mouse_up = true;
+                if let Err(err) = events.send(Event::ZoomButtonClicked { point }) {
+                    error!("error sending event: {err}");
+                }
+                return true;
             }
             CGEventType::LeftMouseDown | CGEventType::RightMouseDown => {
                 let point = CGEvent::location(Some(event));
@@ -226,6 +330,16 @@ impl InputHandler {
                 let point = CGEvent::location(Some(event));
                 events.send(Event::MouseMoved { point, modifiers })
             }
+            CGEventType::OtherMouseDown | CGEventType::OtherMouseUp => {
+                let down = event_type == CGEventType::OtherMouseDown;
+                return self.handle_mouse_button(event, down, modifiers);
+            }
+            CGEventType::KeyDown
+                if CGEvent::integer_value_field(Some(event), CGEventField::EventSourceUserData)
+                    == SYNTHETIC_EVENT_MARKER =>
+            {
+                Ok(())
+            }
             CGEventType::KeyDown => {
                 let keycode =
                     CGEvent::integer_value_field(Some(event), CGEventField::KeyboardEventKeycode);
@@ -266,6 +380,10 @@ impl InputHandler {
         let flags = CGEvent::flags(Some(event));
         let modifiers = get_modifiers(flags);
 
+        if self.handle_scroll_binding(event, modifiers) {
+            return true;
+        }
+
         let target_modifier = self.config.swipe_scroll_modifier();
         let vertical_mod = self.config.swipe_scroll_vertical_modifier();
 
@@ -314,6 +432,76 @@ impl InputHandler {
         false
     }
 
+    /// Sends the command bound to the scrolled direction, if there is one.
+    /// Returns true to intercept the event.
+    fn handle_scroll_binding(&mut self, event: &CGEvent, modifiers: Modifiers) -> bool {
+        let delta = CGEvent::double_value_field(
+            Some(event),
+            CGEventField::ScrollWheelEventFixedPtDeltaAxis1,
+        );
+        let trigger = if delta > SWIPE_THRESHOLD {
+            MouseTrigger::ScrollUp
+        } else if delta < -SWIPE_THRESHOLD {
+            MouseTrigger::ScrollDown
+        } else {
+            return false;
+        };
+        let Some(command) = self.config.find_mouse_bind(trigger, modifiers) else {
+            return false;
+        };
+
+        if self
+            .last_scroll_binding
+            .is_none_or(|last| last.elapsed() >= SCROLL_BINDING_INTERVAL)
+        {
+            self.last_scroll_binding = Some(Instant::now());
+            if let Some(events) = &self.events {
+                _ = events
+                    .send(Event::Command { command })
+                    .inspect_err(|err| error!("Error sending command: {err}"));
+            }
+        }
+        true
+    }
+
+    /// Sends the command bound to an extra mouse button. A press which
+    /// triggered a command is intercepted together with its release.
+    /// Returns true to intercept the event.
+    fn handle_mouse_button(&mut self, event: &CGEvent, down: bool, modifiers: Modifiers) -> bool {
+        let number =
+            CGEvent::integer_value_field(Some(event), CGEventField::MouseEventButtonNumber);
+        let Some(mask) = u32::try_from(number)
+            .ok()
+            .and_then(|number| 1u32.checked_shl(number))
+        else {
+            return false;
+        };
+
+        if !down {
+            let intercept = self.suppressed_buttons & mask != 0;
+            self.suppressed_buttons &= !mask;
+            return intercept;
+        }
+
+        // Button numbers of events start at zero, the key names start at one.
+        let Some(command) = u8::try_from(number + 1).ok().and_then(|button| {
+            self.config
+                .find_mouse_bind(MouseTrigger::Button(button), modifiers)
+        }) else {
+            return false;
+        };
+        let sent = self.events.as_ref().is_some_and(|events| {
+            events
+                .send(Event::Command { command })
+                .inspect_err(|err| error!("Error sending command: {err}"))
+                .is_ok()
+        });
+        if sent {
+            self.suppressed_buttons |= mask;
+        }
+        sent
+    }
+
     /// Handles swipe gesture events. Routes to horizontal `Swipe` or vertical
     /// `VerticalSwipe` based on axis dominance. Returns true to intercept the event.
     fn handle_swipe(&mut self, event: &CGEvent) -> bool {
@@ -394,7 +582,12 @@ impl InputHandler {
                         self.last_swipe_time = Some(Instant::now());
                     }
                 } else if y_deltas.iter().all(|p| p.abs() > SWIPE_THRESHOLD) {
-                    if !self.config.swipe_vertical() {
+                    if !self.config.swipe_vertical()
+                        && self
+                            .config
+                            .swipe_vertical_bindings(y_deltas.len())
+                            .is_none()
+                    {
                         // Do not intercept the vertical swipe
                         return false;
                     }
@@ -458,6 +651,58 @@ impl InputHandler {
     }
 }
 
+/// Converts modifiers into the flags of a keyboard event, the inverse of
+/// `get_modifiers`. Each side sets its device mask along with the mask of the
+/// modifier itself.
+pub(crate) fn event_flags(modifiers: Modifiers) -> CGEventFlags {
+    const FLAG_MASKS: [(Modifiers, u64); 8] = [
+        (Modifiers::LALT, 0x0008_0020),
+        (Modifiers::RALT, 0x0008_0040),
+        (Modifiers::LSHIFT, 0x0002_0002),
+        (Modifiers::RSHIFT, 0x0002_0004),
+        (Modifiers::LCMD, 0x0010_0008),
+        (Modifiers::RCMD, 0x0010_0010),
+        (Modifiers::LCTRL, 0x0004_0001),
+        (Modifiers::RCTRL, 0x0004_2000),
+    ];
+
+    let mut flags = FLAG_MASKS.iter().fold(0, |flags, (modifier, mask)| {
+        if modifiers.contains(*modifier) {
+            flags | mask
+        } else {
+            flags
+        }
+    });
+    if modifiers.contains(Modifiers::FN) {
+        flags |= NX_DEVICEFNKEYMASK;
+    }
+    if modifiers.contains(Modifiers::CAPS) {
+        flags |= NX_ALPHASHIFTMASK;
+    }
+    CGEventFlags(flags)
+}
+
+/// Posts a key press and release with the given modifiers, as if typed on the
+/// keyboard. The events are marked, so that our own tap does not treat them as
+/// keybindings.
+pub(crate) fn post_keys(keycode: u8, modifiers: Modifiers) -> Result<()> {
+    let flags = event_flags(modifiers);
+    for down in [true, false] {
+        let event =
+            CGEvent::new_keyboard_event(None, u16::from(keycode), down).ok_or(Error::Generic(
+                format!("{}: unable to create keyboard event", function_name!()),
+            ))?;
+        CGEvent::set_flags(Some(&event), flags);
+        CGEvent::set_integer_value_field(
+            Some(&event),
+            CGEventField::EventSourceUserData,
+            SYNTHETIC_EVENT_MARKER,
+        );
+        CGEvent::post(CGEventTapLocation::HIDEventTap, Some(&event));
+    }
+    Ok(())
+}
+
 fn get_modifiers(eventflags: CGEventFlags) -> Modifiers {
     const MODIFIER_MASKS: [(Modifiers, u64); 8] = [
         (Modifiers::LALT, 0x0000_0020),
@@ -472,9 +717,14 @@ fn get_modifiers(eventflags: CGEventFlags) -> Modifiers {
 
     // Fn key should be checked for separately, because pressing
     // some keys (i.e. leftarrow) seems to inadvertently toggling it.
-    if eventflags.0 == NX_DEVICEFNKEYMASK {
+    let caps = if eventflags.0 & NX_ALPHASHIFTMASK == 0 {
+        Modifiers::empty()
+    } else {
+        Modifiers::CAPS
+    };
+    if eventflags.0 & !NX_ALPHASHIFTMASK == NX_DEVICEFNKEYMASK {
         tracing::debug!("event flags {:#x}", eventflags.0);
-        return Modifiers::FN;
+        return Modifiers::FN | caps;
     }
 
     MODIFIER_MASKS
@@ -486,6 +736,7 @@ fn get_modifiers(eventflags: CGEventFlags) -> Modifiers {
                 modifiers
             }
         })
+        | caps
 }
 
 #[cfg(test)]
@@ -501,6 +752,19 @@ mod tests {
     const NX_DEVICELCTLKEYMASK: u64 = 0x0000_0001;
     const NX_DEVICERCTLKEYMASK: u64 = 0x0000_2000;
 
+    #[test]
+    fn event_flags_round_trip() {
+        for modifiers in [
+            Modifiers::CMD,
+            Modifiers::LALT | Modifiers::RSHIFT,
+            Modifiers::HYPER,
+            Modifiers::RCTRL | Modifiers::CAPS,
+        ] {
+            assert_eq!(get_modifiers(event_flags(modifiers)), modifiers);
+        }
+        assert_eq!(get_modifiers(event_flags(Modifiers::FN)), Modifiers::FN);
+    }
+
     #[test]
     fn no_modifiers() {
         assert_eq!(get_modifiers(CGEventFlags(0)), Modifiers::empty());
@@ -584,6 +848,14 @@ mod tests {
         assert_eq!(get_modifiers(CGEventFlags(generic_alt)), Modifiers::empty());
     }
 
+    #[test]
+    fn caps_lock_flag() {
+        assert_eq!(
+            get_modifiers(CGEventFlags(NX_ALPHASHIFTMASK | NX_DEVICELCMDKEYMASK)),
+            Modifiers::CAPS | Modifiers::LCMD
+        );
+    }
+
     #[test]
     fn secondary_fn_flag_is_not_ignored() {
         // Ensure we don't accidentally filter out the fn mask as "device independent"
@@ -0,0 +1,117 @@
+use core::ptr::NonNull;
+use objc2_core_foundation::{
+    CFRetained, CFRunLoop, CFRunLoopSource, CFString, CFType, kCFRunLoopCommonModes,
+};
+use scopeguard::ScopeGuard;
+use std::ffi::c_void;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use stdext::function_name;
+use tracing::{debug, error, info};
+
+use crate::errors::{Error, Result};
+use crate::events::{Event, EventSender};
+
+#[link(name = "IOKit", kind = "framework")]
+unsafe extern "C" {
+    fn IOPSNotificationCreateRunLoopSource(
+        callback: extern "C-unwind" fn(*mut c_void),
+        context: *mut c_void,
+    ) -> *mut CFRunLoopSource;
+    fn IOPSCopyPowerSourcesInfo() -> *mut CFType;
+    fn IOPSGetProvidingPowerSourceType(snapshot: *const CFType) -> *const CFString;
+}
+
+/// Value of `IOPSGetProvidingPowerSourceType` while the Mac runs on its battery.
+const BATTERY_POWER: &str = "Battery Power";
+
+/// Returns `true` if the Mac currently draws its power from the battery.
+fn on_battery() -> bool {
+    let Some(snapshot) = NonNull::new(unsafe { IOPSCopyPowerSourcesInfo() }) else {
+        return false;
+    };
+    let snapshot = unsafe { CFRetained::from_raw(snapshot) };
+    let source = unsafe { IOPSGetProvidingPowerSourceType(&raw const *snapshot) };
+    NonNull::new(source.cast_mut())
+        .is_some_and(|source| unsafe { source.as_ref() }.to_string() == BATTERY_POWER)
+}
+
+/// `PowerHandler` watches the power source of the Mac and sends
+/// `Event::PowerSourceChanged` when it switches between the battery and AC power.
+pub(super) struct PowerHandler {
+    events: EventSender,
+    on_battery: bool,
+    // Prevents from being Unpin automatically
+    _pin: PhantomPinned,
+}
+
+pub(super) type PinnedPowerHandler =
+    ScopeGuard<Pin<Box<PowerHandler>>, Box<dyn FnOnce(Pin<Box<PowerHandler>>)>>;
+
+impl PowerHandler {
+    pub(super) fn new(events: EventSender) -> Self {
+        Self {
+            events,
+            on_battery: on_battery(),
+            _pin: PhantomPinned,
+        }
+    }
+
+    /// Adds the power source notifications of IOKit to the main run loop, and
+    /// reports the current power source.
+    pub(super) fn start(self) -> Result<PinnedPowerHandler> {
+        info!("Registering power source handler");
+        let on_battery = self.on_battery;
+        let mut pinned = Box::pin(self);
+        let this = unsafe { NonNull::new_unchecked(pinned.as_mut().get_unchecked_mut()) }.as_ptr();
+        let source = NonNull::new(unsafe {
+            IOPSNotificationCreateRunLoopSource(Self::callback, this.cast())
+        })
+        .map(|source| unsafe { CFRetained::from_raw(source) })
+        .zip(CFRunLoop::main())
+        .ok_or(Error::PermissionDenied(format!(
+            "{}: unable to create the power source run loop source",
+            function_name!()
+        )));
+        let (run_loop_source, main_loop) = source?;
+        let loop_mode = unsafe { kCFRunLoopCommonModes };
+        CFRunLoop::add_source(&main_loop, Some(&run_loop_source), loop_mode);
+
+        pinned
+            .events
+            .send(Event::PowerSourceChanged { on_battery })?;
+        Ok(scopeguard::guard(
+            pinned,
+            Box::new(move |_: Pin<Box<Self>>| {
+                info!("Unregistering power source handler");
+                CFRunLoop::remove_source(&main_loop, Some(&run_loop_source), loop_mode);
+                CFRunLoopSource::invalidate(&run_loop_source);
+            }),
+        ))
+    }
+
+    extern "C-unwind" fn callback(context: *mut c_void) {
+        if let Some(this) =
+            NonNull::new(context).map(|this| unsafe { this.cast::<PowerHandler>().as_mut() })
+        {
+            this.power_source_changed();
+        } else {
+            error!("Zero passed to Power Handler.");
+        }
+    }
+
+    /// Called for any change of the power sources, including the charge level,
+    /// so only switching between the battery and AC power is passed on.
+    fn power_source_changed(&mut self) {
+        let on_battery = on_battery();
+        if on_battery == self.on_battery {
+            return;
+        }
+        debug!("power source changed, on battery: {on_battery}");
+        self.on_battery = on_battery;
+        _ = self
+            .events
+            .send(Event::PowerSourceChanged { on_battery })
+            .inspect_err(|err| error!("{}: {err}", function_name!()));
+    }
+}
@@ -26,7 +26,7 @@ pub struct ProcessSerialNumber {
 }
 
 /// Type alias for the callback function signature used by `InstallEventHandler` for process events.
-type ProcessCallbackFn = extern "C-unwind" fn(
+pub(super) type ProcessCallbackFn = extern "C-unwind" fn(
     this: *mut c_void,
     event: *const ProcessEvent,
     context: *const c_void,
@@ -42,7 +42,7 @@ unsafe extern "C" {
     ///
     /// # Original signature
     /// extern `EventTargetRef` GetApplicationEventTarget(void)
-    fn GetApplicationEventTarget() -> *const ProcessEventTarget;
+    pub(super) fn GetApplicationEventTarget() -> *const ProcessEventTarget;
 
     /// Installs an event handler for a specific event target and event types.
     /// This function sets up a callback to be invoked when specified Carbon events occur.
@@ -70,7 +70,7 @@ unsafe extern "C" {
     ///   const `EventTypeSpec` *  inList,
     ///   void *                 inUserData,
     ///   `EventHandlerRef` *      outRef)
-    fn InstallEventHandler(
+    pub(super) fn InstallEventHandler(
         target: *const ProcessEventTarget,
         handler: ProcessCallbackFn,
         event_len: u32,
@@ -91,7 +91,7 @@ unsafe extern "C" {
     ///
     /// # Original signature
     /// extern `OSStatus` RemoveEventHandler(EventHandlerRef inHandlerRef)
-    fn RemoveEventHandler(handler_ref: *const ProcessEventHandler) -> OSStatus;
+    pub(super) fn RemoveEventHandler(handler_ref: *const ProcessEventHandler) -> OSStatus;
 
     /// Gets a piece of data from the given event, if it exists.
     /// The Carbon Event Manager will automatically use `AppleEvent` coercion handlers to convert
@@ -127,7 +127,7 @@ unsafe extern "C" {
     ///   `ByteCount`         inBufferSize,
     ///   `ByteCount` *       outActualSize,       /* can be NULL */
     ///   void *            outData)             /* can be NULL */
-    fn GetEventParameter(
+    pub(super) fn GetEventParameter(
         event: *const ProcessEvent,
         param_name: u32,
         param_type: u32,
@@ -190,28 +190,28 @@ unsafe extern "C" {
 /// Specifies a Carbon event by its class and kind.
 /// Used for registering and matching events with event handlers.
 #[repr(C)]
-struct EventTypeSpec {
+pub(super) struct EventTypeSpec {
     /// The event class (e.g., `kEventClassApplication`).
-    event_class: u32,
+    pub(super) event_class: u32,
     /// The event kind within its class (e.g., `kEventAppLaunched`).
-    event_kind: u32,
+    pub(super) event_kind: u32,
 }
 
 /// An opaque type representing a Carbon event handler reference.
 #[repr(C)]
-struct ProcessEventHandler {
+pub(super) struct ProcessEventHandler {
     _opaque: [u8; 0],
 }
 
 /// An opaque type representing a Carbon event target reference.
 #[repr(C)]
-struct ProcessEventTarget {
+pub(super) struct ProcessEventTarget {
     _opaque: [u8; 0],
 }
 
 /// An opaque type representing a Carbon event.
 #[repr(C)]
-struct ProcessEvent {
+pub(super) struct ProcessEvent {
     _opaque: [u8; 0],
 }
 
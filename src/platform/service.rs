@@ -1,12 +1,16 @@
 use std::{
-    env, fs,
+    env,
+    ffi::OsString,
+    fs,
     io::{Error, ErrorKind, Result, Write},
+    os::unix::process::CommandExt,
     path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 
 use tracing::{info, warn};
 
+use crate::ecs::state::HANDOFF_ENV;
 use crate::util::exe_path;
 
 /// The bundle identifier for the `paneru` service.
@@ -191,12 +195,32 @@ impl Service {
         Ok(())
     }
 
+    /// Replaces the running daemon with a fresh instance of the executable,
+    /// pointing it to the state handed over in `state_path` and keeping the
+    /// command socket at `socket_path`. The daemon is launched with the same
+    /// arguments, so a recording goes on. The process id is kept, so launchd
+    /// keeps tracking the service.
+    /// Only returns if the exec failed.
+    pub fn exec_handoff(state_path: &Path, socket_path: &Path) -> Error {
+        let Some(bin_path) = exe_path() else {
+            return Error::new(ErrorKind::NotFound, "Cannot find current executable path.");
+        };
+        Command::new(bin_path)
+            .arg("--socket")
+            .arg(socket_path)
+            .args(launch_arguments(env::args_os().skip(1)))
+            .env(HANDOFF_ENV, state_path)
+            .exec()
+    }
+
     /// Generates the content of the launchd plist file for this service.
     /// This string is formatted with the service name, executable path, and log paths.
     #[must_use]
     pub fn launchd_plist(&self) -> String {
         let xdg_config_home = env::var("XDG_CONFIG_HOME")
             .unwrap_or_else(|_| format!("{}/.config", self.home_dir.display()));
+        // The daemon finds its command socket where the shell of the user does.
+        let xdg_runtime_dir = env::var("XDG_RUNTIME_DIR").unwrap_or_default();
         format!(
             include_str!("../../assets/launchd.plist"),
             name = self.raw.name,
@@ -204,6 +228,43 @@ impl Service {
             out_log_path = self.raw.out_log_path,
             error_log_path = self.raw.error_log_path,
             xdg_config_home = xdg_config_home,
+            xdg_runtime_dir = xdg_runtime_dir,
         )
     }
 }
+
+/// The arguments the daemon was started with, less the socket, which the
+/// handoff passes on its own in case the configuration has changed it since.
+fn launch_arguments(args: impl IntoIterator<Item = OsString>) -> Vec<OsString> {
+    let mut args = args.into_iter();
+    let mut kept = vec![];
+    while let Some(arg) = args.next() {
+        if arg == "--socket" {
+            args.next();
+        } else if !arg.as_encoded_bytes().starts_with(b"--socket=") {
+            kept.push(arg);
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_launch_arguments() {
+        let args = |list: &[&str]| list.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(
+            launch_arguments(args(&[
+                "--socket", "/tmp/a", "launch", "--record", "x.jsonl"
+            ])),
+            args(&["launch", "--record", "x.jsonl"])
+        );
+        assert_eq!(
+            launch_arguments(args(&["launch", "--socket=/tmp/a"])),
+            args(&["launch"])
+        );
+        assert!(launch_arguments(args(&[])).is_empty());
+    }
+}
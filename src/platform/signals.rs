@@ -0,0 +1,86 @@
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::FromRawFd;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::thread;
+
+use libc::{SIGHUP, SIGINT, SIGTERM, c_int};
+use notify::EventKind;
+use notify::event::{DataChange, ModifyKind};
+use tracing::{error, info};
+
+use crate::config::CONFIGURATION_FILE;
+use crate::errors::Result;
+use crate::events::{Event, EventSender};
+
+/// Write end of the pipe handing the signals over to the forwarding thread.
+static SIGNAL_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+extern "C" fn on_signal(signal: c_int) {
+    // Only async-signal-safe calls are allowed here.
+    let byte = u8::try_from(signal).unwrap_or_default();
+    unsafe {
+        libc::write(
+            SIGNAL_PIPE.load(Ordering::Relaxed),
+            (&raw const byte).cast(),
+            1,
+        );
+    }
+}
+
+/// Forwards the signals sent by service managers like launchd or `brew services`
+/// to the event loop: SIGTERM and SIGINT shut Paneru down cleanly, and SIGHUP
+/// reloads the configuration.
+///
+/// # Returns
+///
+/// `Ok(())` if the handlers are installed, otherwise `Err(Error)`.
+pub fn forward_signals(events: EventSender) -> Result<()> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let [read_fd, write_fd] = fds;
+    // The daemon re-executes itself on a restart, which must not inherit the pipe.
+    for fd in fds {
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            let err = std::io::Error::last_os_error();
+            unsafe {
+                libc::close(read_fd);
+                libc::close(write_fd);
+            }
+            return Err(err.into());
+        }
+    }
+    SIGNAL_PIPE.store(write_fd, Ordering::Relaxed);
+    let handler = on_signal as extern "C" fn(c_int);
+    for signal in [SIGTERM, SIGINT, SIGHUP] {
+        unsafe { libc::signal(signal, handler as libc::sighandler_t) };
+    }
+
+    let mut pipe = unsafe { File::from_raw_fd(read_fd) };
+    thread::Builder::new()
+        .name("signals".to_string())
+        .spawn(move || {
+            let mut signal = [0u8];
+            while pipe.read_exact(&mut signal).is_ok() {
+                let event = if c_int::from(signal[0]) == SIGHUP {
+                    info!("received SIGHUP, reloading the configuration");
+                    Event::ConfigRefresh(
+                        notify::Event::new(EventKind::Modify(ModifyKind::Data(
+                            DataChange::Content,
+                        )))
+                        .add_path(CONFIGURATION_FILE.clone()),
+                    )
+                } else {
+                    info!("received signal {}, shutting down", signal[0]);
+                    Event::Exit
+                };
+                if let Err(err) = events.send(event) {
+                    error!("forwarding signal: {err}");
+                    break;
+                }
+            }
+        })?;
+    Ok(())
+}
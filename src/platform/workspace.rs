@@ -102,6 +102,26 @@ define_class!(
             _ = self.ivars().events.send(msg);
         }
 
+        /// Called when the user switches to another login session.
+        ///
+        /// # Arguments
+        ///
+        /// * `_notification` - The notification object.
+        #[unsafe(method(sessionDidResignActive:))]
+        fn session_resigned(&self, _notification: &NSObject) {
+            _ = self.ivars().events.send(Event::SessionResigned);
+        }
+
+        /// Called when the user switches back to this login session.
+        ///
+        /// # Arguments
+        ///
+        /// * `_notification` - The notification object.
+        #[unsafe(method(sessionDidBecomeActive:))]
+        fn session_activated(&self, _notification: &NSObject) {
+            _ = self.ivars().events.send(Event::SessionActivated);
+        }
+
         /// Called when the menu bar hiding state changes.
         ///
         /// # Arguments
@@ -247,6 +267,14 @@ impl WorkspaceObserver {
                 "NSWorkspaceDidUnhideApplicationNotification",
             ),
             (sel!(didWake:), "NSWorkspaceDidWakeNotification"),
+            (
+                sel!(sessionDidResignActive:),
+                "NSWorkspaceSessionDidResignActiveNotification",
+            ),
+            (
+                sel!(sessionDidBecomeActive:),
+                "NSWorkspaceSessionDidBecomeActiveNotification",
+            ),
         ];
         let shared_ws = NSWorkspace::sharedWorkspace();
         let notification_center = shared_ws.notificationCenter();
@@ -1,26 +1,91 @@
-use std::io::{Read, Write};
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
 use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc::channel;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
-use std::{fs, thread};
-use tracing::{debug, error};
+use std::{env, fs, thread};
+use tracing::{debug, debug_span, error, info, warn};
 
-use crate::config::parse_command;
+use crate::commands::{
+    Command, Expectation, PropertyRequest, WindowTarget, parse_expect_request,
+    parse_property_request,
+};
+use crate::config::{Config, discover_configuration_file, parse_command};
 use crate::ecs::state::StateQueryKind;
-use crate::errors::Result;
+use crate::errors::{Error, Result};
 use crate::events::{Event, EventSender};
+use crate::manager::capture_window_preview;
+use crate::metrics::MetricsSnapshot;
+use crate::platform::WinID;
+use protocol::{Frame, Status, VERSION};
+
+mod protocol;
+
+/// Longest side, in pixels, of the thumbnails returned by `query preview`.
+const PREVIEW_MAX_SIZE: u32 = 320;
+/// How long `paneru inspect` waits for the click which picks a window.
+const INSPECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long a client may take to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+/// File name of the command socket in its directory.
+const SOCKET_NAME: &str = "paneru.sock";
+
+/// Environment variable with the query token presented by the CLI.
+const TOKEN_ENV: &str = "PANERU_TOKEN";
+
+/// Path of the command socket, resolved once by the daemon and its clients alike.
+static SOCKET_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Returns the per-user default socket: in `$XDG_RUNTIME_DIR` when it is set,
+/// otherwise in the Application Support directory of the user.
+fn default_socket_path() -> PathBuf {
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+        .or_else(|| {
+            env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join("Library/Application Support/paneru"))
+        })
+        .unwrap_or_else(|| env::temp_dir().join(format!("paneru-{}", unsafe { libc::getuid() })))
+        .join(SOCKET_NAME)
+}
+
+/// Sent in plain text to clients of the unframed protocol, which print whatever they read.
+const UNFRAMED_REJECTION: &str =
+    "Unsupported request: this paneru client is older than the daemon, please update it.\n";
 
 /// `CommandReader` is responsible for sending and receiving commands via a Unix socket.
 /// It acts as an IPC mechanism for the `paneru` application, allowing external processes
 /// or the CLI client to communicate with the running daemon.
 pub struct CommandReader {
     events: EventSender,
+    /// Lets other users run read-only requests, from the `socket_query_token` option.
+    query_token: Option<String>,
 }
 
 impl CommandReader {
-    /// The path to the Unix socket used for inter-process communication.
-    const SOCKET_PATH: &str = "/tmp/paneru.socket";
+    /// Uses the socket given by the `--socket` flag, instead of the one from the
+    /// `socket_path` option or the default. Has to be called before the socket
+    /// is first used.
+    pub fn set_socket_path(path: PathBuf) {
+        if SOCKET_PATH.set(path).is_err() {
+            warn!("the command socket is already in use, ignoring --socket.");
+        }
+    }
+
+    /// Returns the path of the Unix socket used for inter-process communication.
+    pub fn socket_path() -> &'static Path {
+        SOCKET_PATH.get_or_init(|| {
+            discover_configuration_file()
+                .and_then(|path| Config::read_options(&path).ok())
+                .and_then(|config| config.socket_path())
+                .unwrap_or_else(default_socket_path)
+        })
+    }
 
     /// Sends a command and its arguments to the running `paneru` application via a Unix socket.
     /// The arguments are serialized and sent as a byte stream.
@@ -31,43 +96,125 @@ impl CommandReader {
     ///
     /// # Returns
     ///
-    /// `Ok(())` if the command is sent successfully, otherwise `Err(Error)` if an I/O error occurs or the connection fails.
+    /// `Ok(())` if the daemon accepted the command, otherwise `Err(Error)` with the daemon's
+    /// error message, or if an I/O error occurs or the connection fails.
     pub fn send_command(params: impl IntoIterator<Item = String>) -> Result<()> {
-        let _stream = Self::send_socket_request(params)?;
+        Self::send_request(params)?;
         Ok(())
     }
 
+    /// Checks a request the way the daemon parses it, so that `send-cmd`
+    /// rejects a mistyped command before anything is sent.
+    pub fn validate_request(argv: &[&str]) -> Result<()> {
+        Request::parse(argv).map(|_| ())
+    }
+
     pub fn send_query(kind: StateQueryKind) -> Result<String> {
         let args = match kind {
             StateQueryKind::State => ["query", "state", "--json"],
             StateQueryKind::VirtualWorkspaces => ["query", "virtual-workspaces", "--json"],
             StateQueryKind::Active => ["query", "active", "--json"],
+            StateQueryKind::Strip => ["query", "strip", "--json"],
+            StateQueryKind::Keybindings => ["query", "keybindings", "--json"],
+            StateQueryKind::Windows => ["query", "windows", "--json"],
         };
-        let mut stream = Self::send_socket_request(args.into_iter().map(str::to_string))?;
-        let mut output = String::new();
-        stream.read_to_string(&mut output)?;
-        Ok(output)
+        let (response, _) = Self::send_request(args.into_iter().map(str::to_string))?;
+        Ok(response.text())
     }
 
     pub fn subscribe_json() -> Result<()> {
-        let mut stream =
-            Self::send_socket_request(["subscribe", "--json"].into_iter().map(str::to_string))?;
+        let (_, mut stream) =
+            Self::send_request(["subscribe", "--json"].into_iter().map(str::to_string))?;
         std::io::copy(&mut stream, &mut std::io::stdout())?;
         Ok(())
     }
 
-    fn send_socket_request(params: impl IntoIterator<Item = String>) -> Result<UnixStream> {
-        let output = params
+    pub fn send_stats(prometheus: bool) -> Result<String> {
+        let args: &[&str] = if prometheus {
+            &["stats", "--prometheus"]
+        } else {
+            &["stats"]
+        };
+        let (response, _) = Self::send_request(args.iter().map(|arg| (*arg).to_string()))?;
+        Ok(response.text())
+    }
+
+    pub fn send_preview(window_id: WinID) -> Result<String> {
+        let args = [
+            "query".to_string(),
+            "preview".to_string(),
+            window_id.to_string(),
+        ];
+        let (response, _) = Self::send_request(args)?;
+        Ok(response.text())
+    }
+
+    /// Waits for a click on a window and returns the daemon's description of it.
+    pub fn send_inspect() -> Result<String> {
+        let (response, _) = Self::send_request(["inspect".to_string()])?;
+        Ok(response.text())
+    }
+
+    /// Sends a `window <target> get|set ...` request and returns the daemon's answer.
+    pub fn send_window_property(args: impl IntoIterator<Item = String>) -> Result<String> {
+        let args = std::iter::once("window".to_string()).chain(args);
+        let (response, _) = Self::send_request(args)?;
+        Ok(response.text())
+    }
+
+    /// Registers a one-shot rule for the next window matching `expect ...`.
+    pub fn send_expect(args: impl IntoIterator<Item = String>) -> Result<()> {
+        let args = std::iter::once("expect".to_string()).chain(args);
+        Self::send_request(args).map(|_| ())
+    }
+
+    pub fn send_log_level(directives: Vec<String>) -> Result<String> {
+        let args = std::iter::once("log-level".to_string()).chain(directives);
+        let (response, _) = Self::send_request(args)?;
+        Ok(response.text())
+    }
+
+    /// Sends a framed request and waits for its response. The stream is returned
+    /// as well, so that `Status::Stream` responses can keep reading from it.
+    fn send_request(params: impl IntoIterator<Item = String>) -> Result<(Frame, UnixStream)> {
+        let params = params.into_iter().collect::<Vec<_>>();
+        // Another user's daemon is queried with the token it was given.
+        let token = env::var(TOKEN_ENV)
+            .ok()
+            .map(|token| ["--token".to_string(), token])
             .into_iter()
-            .flat_map(|param| [param.as_bytes(), &[0]].concat())
-            .collect::<Vec<_>>();
-        let size: u32 = output.len().try_into()?;
-        debug!("{:?} {output:?}", size.to_le_bytes());
+            .flatten();
+        let request = Frame::request(std::process::id(), token.chain(params.iter().cloned()));
+        // The token is left out, so that it does not end up in the logs.
+        debug!("request {}: {params:?}", request.request_id);
 
-        let mut stream = UnixStream::connect(CommandReader::SOCKET_PATH)?;
-        stream.write_all(&size.to_le_bytes())?;
-        stream.write_all(&output)?;
-        Ok(stream)
+        let path = Self::socket_path();
+        let mut stream = UnixStream::connect(path).map_err(|err| {
+            Error::IO(format!(
+                "connecting to {}: {err}. Is the daemon running?",
+                path.display()
+            ))
+        })?;
+        request.write_to(&mut stream)?;
+        let response = Frame::read_from(&mut stream).map_err(|err| {
+            Error::IO(format!(
+                "reading response: {err}. Is the daemon running an older version of paneru?"
+            ))
+        })?;
+        if response.request_id != request.request_id {
+            return Err(Error::InvalidInput(format!(
+                "response to request {} does not match request {}",
+                response.request_id, request.request_id
+            )));
+        }
+        match response.status {
+            Status::Ok | Status::Stream => Ok((response, stream)),
+            Status::Error => Err(Error::InvalidInput(response.text().trim_end().to_string())),
+            Status::Unsupported => Err(Error::InvalidInput(format!(
+                "the daemon does not support this request: {}",
+                response.text()
+            ))),
+        }
     }
 
     /// Creates a new `CommandReader` instance.
@@ -80,12 +227,18 @@ impl CommandReader {
     ///
     /// A new `CommandReader`.
     pub fn new(events: EventSender) -> Self {
-        CommandReader { events }
+        let query_token = discover_configuration_file()
+            .and_then(|path| Config::read_options(&path).ok())
+            .and_then(|config| config.socket_query_token());
+        CommandReader {
+            events,
+            query_token,
+        }
     }
 
     /// Starts the `CommandReader` in a new thread, listening for incoming commands on a Unix socket.
     /// Any errors encountered in the runner thread are logged.
-    pub fn start(mut self) {
+    pub fn start(self) {
         thread::spawn(move || {
             if let Err(err) = self.runner() {
                 error!("{err}");
@@ -94,95 +247,394 @@ impl CommandReader {
     }
 
     /// The main runner function for the `CommandReader` thread. It binds to a Unix socket,
-    /// listens for incoming connections, reads command size and data, and dispatches them as `Event::Command`.
+    /// listens for incoming connections, reads one request frame from each and answers it,
+    /// dispatching commands as `Event::Command`.
     /// This loop continues indefinitely until an unrecoverable error occurs.
     ///
     /// # Returns
     ///
     /// `Ok(())` if the runner completes successfully (though it's typically a long-running loop),
     /// otherwise `Err(Error)` if a binding or I/O error occurs.
-    fn runner(&mut self) -> Result<()> {
-        _ = fs::remove_file(CommandReader::SOCKET_PATH);
-        let listener = UnixListener::bind(CommandReader::SOCKET_PATH)?;
+    fn runner(&self) -> Result<()> {
+        let path = Self::socket_path();
+        // Only the user may write to the directory of the socket. With a query
+        // token, other users may pass through it to reach the socket.
+        if let Some(parent) = path.parent() {
+            private_socket_dir(parent, self.query_token.is_some())?;
+        }
+        _ = fs::remove_file(path);
+        // The socket is bound with the process umask, which other threads share,
+        // and its mode is set right after. Until then the private directory keeps
+        // others away, and a user passing through a shared one needs the token.
+        let listener = UnixListener::bind(path)?;
+        // With a query token, other users get to connect and present it.
+        let mode = if self.query_token.is_some() {
+            0o666
+        } else {
+            0o600
+        };
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+        info!("listening for commands on {}", path.display());
 
         for stream in listener.incoming() {
             let Ok(mut stream) = stream.inspect_err(|err| error!("reading stream {err}")) else {
                 continue;
             };
-            let mut buffer = [0u8; 4];
+            // A stalled client must not block the socket for everyone else.
+            _ = stream
+                .set_read_timeout(Some(REQUEST_TIMEOUT))
+                .inspect_err(|err| error!("setting request timeout: {err}"));
 
-            if !full_read(&mut stream, buffer.len(), &mut buffer) {
-                continue;
-            }
-            let size = u32::from_le_bytes(buffer) as usize;
-            let mut buffer = vec![0u8; size];
+            let request = match Frame::read_from(&mut stream) {
+                Ok(request) => request,
+                Err(Error::InvalidInput(msg)) => {
+                    warn!("rejecting unframed request: {msg}");
+                    _ = stream.write_all(UNFRAMED_REJECTION.as_bytes());
+                    continue;
+                }
+                Err(err) => {
+                    error!("reading request: {err}");
+                    continue;
+                }
+            };
 
-            if !full_read(&mut stream, buffer.len(), &mut buffer) {
+            if request.version != VERSION {
+                let message = format!(
+                    "protocol version {} is not supported, expected {VERSION}",
+                    request.version
+                );
+                respond(
+                    &mut stream,
+                    request.request_id,
+                    Status::Unsupported,
+                    message,
+                );
                 continue;
             }
-            let argv = buffer
-                .split(|c| *c == 0)
-                .filter(|s| !s.is_empty())
-                .map(|s| String::from_utf8_lossy(s).to_string())
-                .collect::<Vec<_>>();
-            let argv_ref = argv.iter().map(String::as_str).collect::<Vec<_>>();
-
-            if let Some(kind) = parse_query_request(&argv_ref) {
-                let (tx, rx) = channel();
-                _ = self
-                    .events
-                    .send(Event::StateQuery {
-                        kind,
-                        respond_to: tx,
-                    })
-                    .inspect_err(|err| {
-                        error!("sending state query: {err}");
-                    });
-
-                match rx.recv_timeout(Duration::from_secs(2)) {
-                    Ok(response) => {
-                        _ = stream.write_all(response.as_bytes());
-                        _ = stream.write_all(b"\n");
-                    }
-                    Err(err) => error!("waiting for state query response: {err}"),
-                }
+
+            let argv = request.argv();
+            let (token, argv_ref) = split_token(&argv);
+            let _span = debug_span!("socket_request", argv = ?argv_ref).entered();
+
+            let peer_uid = peer_uid(&stream);
+            if let Err(err) = authorize(peer_uid, token, self.query_token.as_deref(), &argv_ref) {
+                warn!("rejecting request from uid {peer_uid:?}: {err}");
+                respond(
+                    &mut stream,
+                    request.request_id,
+                    Status::Error,
+                    format!("{err}\n"),
+                );
                 continue;
             }
 
-            if is_subscribe_request(&argv_ref) {
-                match stream.try_clone() {
-                    Ok(clone) => {
-                        if let Err(err) = clone.set_nonblocking(true) {
-                            error!("configuring state subscriber as nonblocking: {err}");
-                            continue;
-                        }
-                        _ = self
-                            .events
-                            .send(Event::StateSubscribe {
-                                stream: Arc::new(Mutex::new(clone)),
-                            })
-                            .inspect_err(|err| {
-                                error!("registering state subscriber: {err}");
-                            });
-                    }
-                    Err(err) => error!("cloning subscriber stream: {err}"),
+            let parsed = match Request::parse(&argv_ref) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    error!("parsing request: {err}");
+                    respond(
+                        &mut stream,
+                        request.request_id,
+                        Status::Error,
+                        format!("{err}\n"),
+                    );
+                    continue;
+                }
+            };
+            match parsed {
+                Request::Subscribe => self.subscribe(request.request_id, stream),
+                Request::Inspect => self.inspect(request.request_id, stream),
+                parsed => {
+                    let (status, response) = self.handle_request(parsed);
+                    respond(&mut stream, request.request_id, status, response);
                 }
-                continue;
             }
+        }
+        Ok(())
+    }
 
-            if let Ok(command) =
-                parse_command(&argv_ref).inspect_err(|err| error!("parsing command: {err}"))
-            {
-                _ = self
-                    .events
-                    .send(Event::Command { command })
-                    .inspect_err(|err| {
-                        error!("sending command: {err}");
-                    });
+    /// Handles a single request, returning the status and payload of its response.
+    fn handle_request(&self, request: Request) -> (Status, String) {
+        let outcome = match request {
+            Request::Subscribe | Request::Inspect => Err(Error::InvalidInput(
+                "subscriptions and inspection stream their responses".to_string(),
+            )),
+            Request::Stats { prometheus } => {
+                let snapshot = MetricsSnapshot::capture();
+                Ok(if prometheus {
+                    snapshot.to_prometheus()
+                } else {
+                    serde_json::to_string(&snapshot).unwrap_or_default() + "\n"
+                })
+            }
+            Request::LogLevel(directives) => {
+                crate::logging::set_log_level(directives.iter().copied()).map(|()| {
+                    info!("log level changed: {}", directives.join(" "));
+                    "ok\n".to_string()
+                })
+            }
+            Request::Preview(window_id) => write_preview(window_id).map(|path| path + "\n"),
+            Request::Query(kind) => self.state_query(kind).map(|response| response + "\n"),
+            Request::Property(target, request) => self.window_property(target, request),
+            Request::Expect(expectation) => self
+                .events
+                .send(Event::ExpectWindow { expectation })
+                .map(|()| String::new()),
+            Request::Command(command) => self
+                .events
+                .send(Event::Command { command })
+                .map(|()| String::new()),
+        };
+        match outcome {
+            Ok(response) => (Status::Ok, response),
+            Err(err) => {
+                error!("handling request: {err}");
+                (Status::Error, format!("{err}\n"))
             }
         }
-        Ok(())
     }
+
+    /// Asks the ECS for a state document and waits for it.
+    fn state_query(&self, kind: StateQueryKind) -> Result<String> {
+        let (tx, rx) = channel();
+        self.events.send(Event::StateQuery {
+            kind,
+            respond_to: tx,
+        })?;
+        rx.recv_timeout(Duration::from_secs(2))
+            .map_err(|err| Error::IO(format!("waiting for state: {err}")))
+    }
+
+    /// Reads or changes a window property in the ECS and waits for the outcome.
+    fn window_property(&self, target: WindowTarget, request: PropertyRequest) -> Result<String> {
+        let (tx, rx) = channel();
+        self.events.send(Event::WindowPropertyRequest {
+            target,
+            request,
+            respond_to: tx,
+        })?;
+        rx.recv_timeout(Duration::from_secs(2))
+            .map_err(|err| Error::IO(format!("waiting for window property: {err}")))?
+    }
+
+    /// Registers the stream as a state subscriber. The `Status::Stream` response
+    /// is written first, so it cannot interleave with the events that follow.
+    fn subscribe(&self, request_id: u32, mut stream: UnixStream) {
+        if let Err(err) = Frame::response(request_id, Status::Stream, "").write_to(&mut stream) {
+            error!("answering subscriber: {err}");
+            return;
+        }
+        if let Err(err) = stream.set_nonblocking(true) {
+            error!("configuring state subscriber as nonblocking: {err}");
+            return;
+        }
+        _ = self
+            .events
+            .send(Event::StateSubscribe {
+                stream: Arc::new(Mutex::new(stream)),
+            })
+            .inspect_err(|err| {
+                error!("registering state subscriber: {err}");
+            });
+    }
+
+    /// Starts waiting for the click which picks a window to inspect. The answer
+    /// can take a while, so it is awaited on its own thread to keep the socket
+    /// available to other clients.
+    fn inspect(&self, request_id: u32, mut stream: UnixStream) {
+        let (tx, rx) = channel();
+        if let Err(err) = self.events.send(Event::InspectRequest { respond_to: tx }) {
+            error!("sending inspect request: {err}");
+            respond(&mut stream, request_id, Status::Error, format!("{err}\n"));
+            return;
+        }
+        thread::spawn(move || {
+            let (status, response) = match rx.recv_timeout(INSPECT_TIMEOUT) {
+                Ok(response) => (Status::Ok, response),
+                Err(err) => {
+                    crate::platform::input::set_inspecting(false);
+                    (Status::Error, format!("no window was clicked: {err}\n"))
+                }
+            };
+            respond(&mut stream, request_id, status, response);
+        });
+    }
+}
+
+/// A request of the command socket, as parsed by the daemon. The CLI parses its
+/// requests the same way before sending them, so invalid ones fail early.
+enum Request<'a> {
+    Subscribe,
+    Inspect,
+    Stats { prometheus: bool },
+    LogLevel(&'a [&'a str]),
+    Preview(WinID),
+    Query(StateQueryKind),
+    Property(WindowTarget, PropertyRequest),
+    Expect(Expectation),
+    Command(Command),
+}
+
+impl<'a> Request<'a> {
+    fn parse(argv: &'a [&'a str]) -> Result<Self> {
+        if is_subscribe_request(argv) {
+            return Ok(Self::Subscribe);
+        }
+        if argv == ["inspect"] {
+            return Ok(Self::Inspect);
+        }
+        if let Some(prometheus) = parse_stats_request(argv) {
+            return Ok(Self::Stats { prometheus });
+        }
+        if let ["log-level", directives @ ..] = argv
+            && !directives.is_empty()
+        {
+            return Ok(Self::LogLevel(directives));
+        }
+        if let Some(window_id) = parse_preview_request(argv) {
+            return Ok(Self::Preview(window_id));
+        }
+        if let Some(kind) = parse_query_request(argv) {
+            return Ok(Self::Query(kind));
+        }
+        if let Some(request) = parse_property_request(argv) {
+            return request.map(|(target, request)| Self::Property(target, request));
+        }
+        if let Some(expectation) = parse_expect_request(argv) {
+            return expectation.map(Self::Expect);
+        }
+        parse_command(argv).map(Self::Command)
+    }
+
+    /// Whether the request only reads the state of the daemon.
+    fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Self::Subscribe
+                | Self::Stats { .. }
+                | Self::Query(_)
+                | Self::Property(_, PropertyRequest::Get(_))
+        )
+    }
+}
+
+/// Creates the directory of the socket, or checks that an existing one is a
+/// real directory of the user which nobody else can write to. Otherwise another
+/// user could swap the socket for their own. A `shared` directory lets others
+/// pass through it, without listing it.
+fn private_socket_dir(dir: &Path, shared: bool) -> Result<()> {
+    let traverse = if shared { 0o011 } else { 0 };
+    let metadata = match fs::symlink_metadata(dir) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700 | traverse)
+                .create(dir)?;
+            fs::symlink_metadata(dir)?
+        }
+        Err(err) => return Err(err.into()),
+    };
+    check_socket_dir(&metadata, unsafe { libc::getuid() }).map_err(|msg| {
+        Error::PermissionDenied(format!("socket directory {}: {msg}", dir.display()))
+    })?;
+    if metadata.mode() & traverse != traverse {
+        fs::set_permissions(
+            dir,
+            fs::Permissions::from_mode((metadata.mode() & 0o777) | traverse),
+        )?;
+    }
+    if shared
+        && let Some(closed) = dir
+            .ancestors()
+            .skip(1)
+            .find(|ancestor| fs::metadata(ancestor).is_ok_and(|meta| meta.mode() & 0o001 == 0))
+    {
+        warn!(
+            "other users cannot reach the socket through {}, set socket_path to use the query token.",
+            closed.display()
+        );
+    }
+    Ok(())
+}
+
+/// Rejects a socket directory which is a symlink, belongs to another user or is
+/// writable by group or others.
+fn check_socket_dir(metadata: &fs::Metadata, uid: libc::uid_t) -> std::result::Result<(), String> {
+    if !metadata.file_type().is_dir() {
+        return Err("not a directory".to_string());
+    }
+    if metadata.uid() != uid {
+        return Err(format!("owned by uid {}", metadata.uid()));
+    }
+    if metadata.mode() & 0o022 != 0 {
+        return Err(format!(
+            "writable by others, mode {:o}",
+            metadata.mode() & 0o777
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the user id of the process on the other end of the socket.
+fn peer_uid(stream: &UnixStream) -> Option<libc::uid_t> {
+    let (mut uid, mut gid) = (0, 0);
+    let result = unsafe { libc::getpeereid(stream.as_raw_fd(), &raw mut uid, &raw mut gid) };
+    (result == 0).then_some(uid)
+}
+
+/// Takes a leading `--token <token>` off the arguments of a request.
+fn split_token(argv: &[String]) -> (Option<&str>, Vec<&str>) {
+    let argv = argv.iter().map(String::as_str).collect::<Vec<_>>();
+    match argv.as_slice() {
+        ["--token", token, rest @ ..] => (Some(*token), rest.to_vec()),
+        _ => (None, argv),
+    }
+}
+
+/// Lets the user running the daemon do anything. Other users may only run
+/// read-only requests, and only when they present the configured query token.
+fn authorize(
+    peer_uid: Option<libc::uid_t>,
+    token: Option<&str>,
+    query_token: Option<&str>,
+    argv: &[&str],
+) -> Result<()> {
+    if peer_uid == Some(unsafe { libc::getuid() }) {
+        return Ok(());
+    }
+    let Some(query_token) = query_token else {
+        return Err(Error::PermissionDenied(
+            "only the user running paneru may use its socket".to_string(),
+        ));
+    };
+    if !token.is_some_and(|token| tokens_match(token, query_token)) {
+        return Err(Error::PermissionDenied("invalid token".to_string()));
+    }
+    if !Request::parse(argv).is_ok_and(|request| request.is_read_only()) {
+        return Err(Error::PermissionDenied(format!(
+            "the token only grants queries, not '{}'",
+            argv.join(" ")
+        )));
+    }
+    Ok(())
+}
+
+/// Compares the tokens in a time which does not depend on where they differ,
+/// so that a guess cannot be refined by timing the answers.
+fn tokens_match(token: &str, expected: &str) -> bool {
+    token.len() == expected.len()
+        && token
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn respond(stream: &mut UnixStream, request_id: u32, status: Status, payload: String) {
+    _ = Frame::response(request_id, status, payload)
+        .write_to(stream)
+        .inspect_err(|err| error!("answering request {request_id}: {err}"));
 }
 
 fn parse_query_request(argv: &[&str]) -> Option<StateQueryKind> {
@@ -192,6 +644,52 @@ fn parse_query_request(argv: &[&str]) -> Option<StateQueryKind> {
             Some(StateQueryKind::VirtualWorkspaces)
         }
         ["query", "active", "--json"] | ["query", "active"] => Some(StateQueryKind::Active),
+        ["query", "strip", "--json"] | ["query", "strip"] => Some(StateQueryKind::Strip),
+        ["query", "keybindings", "--json"] | ["query", "keybindings"] => {
+            Some(StateQueryKind::Keybindings)
+        }
+        ["query", "windows", "--json"] | ["query", "windows"] => Some(StateQueryKind::Windows),
+        _ => None,
+    }
+}
+
+/// Returns the window id of a `query preview <wid>` request.
+fn parse_preview_request(argv: &[&str]) -> Option<WinID> {
+    match argv {
+        ["query", "preview", window_id] => window_id.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Captures a thumbnail of the window into a new PNG file next to the socket and
+/// returns its path. Only the user may write to that directory, and the file is
+/// never reused, so nobody can plant a link in its place.
+fn write_preview(window_id: WinID) -> Result<String> {
+    static PREVIEWS: AtomicU64 = AtomicU64::new(0);
+
+    let png = capture_window_preview(window_id, PREVIEW_MAX_SIZE)?;
+    let dir = CommandReader::socket_path()
+        .parent()
+        .ok_or_else(|| Error::NotFound("directory of the socket".to_string()))?;
+    let path = dir.join(format!(
+        "paneru-preview-{window_id}-{}-{}.png",
+        crate::ecs::focus::now_millis(),
+        PREVIEWS.fetch_add(1, Ordering::Relaxed),
+    ));
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)?;
+    file.write_all(&png)?;
+    Ok(path.display().to_string())
+}
+
+/// Returns whether Prometheus output was requested, or `None` if this is not a stats request.
+fn parse_stats_request(argv: &[&str]) -> Option<bool> {
+    match argv {
+        ["stats"] | ["stats", "--json"] => Some(false),
+        ["stats", "--prometheus"] => Some(true),
         _ => None,
     }
 }
@@ -200,14 +698,94 @@ fn is_subscribe_request(argv: &[&str]) -> bool {
     matches!(argv, ["subscribe", "--json"] | ["subscribe"])
 }
 
-fn full_read(stream: &mut UnixStream, expected: usize, buffer: &mut [u8]) -> bool {
-    if let Ok(count) = stream.read(buffer).inspect_err(|err| {
-        error!("{err}");
-    }) && count == expected
-    {
-        true
-    } else {
-        error!("short read, expected {expected}.");
-        false
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorize() {
+        let uid = unsafe { libc::getuid() };
+        let other = Some(uid + 1);
+        let query = ["query", "state"];
+        let quit = ["quit"];
+
+        assert!(authorize(Some(uid), None, None, &quit).is_ok());
+        assert!(authorize(other, None, None, &query).is_err());
+        assert!(authorize(None, None, None, &query).is_err());
+        assert!(authorize(other, Some("secret"), None, &query).is_err());
+        assert!(authorize(other, Some("wrong"), Some("secret"), &query).is_err());
+        assert!(authorize(other, Some("secret"), Some("secret"), &query).is_ok());
+        assert!(
+            authorize(
+                other,
+                Some("secret"),
+                Some("secret"),
+                &["window", "1", "get", "frame"]
+            )
+            .is_ok()
+        );
+        assert!(authorize(other, Some("secret"), Some("secret"), &quit).is_err());
+        assert!(
+            authorize(
+                other,
+                Some("secret"),
+                Some("secret"),
+                &["window", "1", "set", "width", "0.5"]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_completions_validate() {
+        for name in crate::config::COMMAND_NAMES {
+            let argv = name.split('_').collect::<Vec<_>>();
+            assert!(
+                CommandReader::validate_request(&argv).is_ok(),
+                "{name} is rejected"
+            );
+        }
+        assert!(CommandReader::validate_request(&["window", "fly"]).is_err());
+        assert!(CommandReader::validate_request(&["log-level"]).is_err());
+    }
+
+    #[test]
+    fn test_check_socket_dir() {
+        let uid = unsafe { libc::getuid() };
+        let dir = env::temp_dir().join(format!("paneru-test-socket-dir-{}", std::process::id()));
+        fs::DirBuilder::new().mode(0o700).create(&dir).unwrap();
+        let link = dir.with_extension("link");
+        _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+
+        assert!(private_socket_dir(&dir, false).is_ok());
+        let metadata = fs::symlink_metadata(&dir).unwrap();
+        assert!(check_socket_dir(&metadata, uid + 1).is_err());
+        assert!(private_socket_dir(&link, false).is_err());
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(private_socket_dir(&dir, false).is_err());
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700)).unwrap();
+        assert!(private_socket_dir(&dir, true).is_ok());
+        let mode = fs::symlink_metadata(&dir).unwrap().mode();
+        assert_eq!(mode & 0o777, 0o711);
+
+        fs::remove_file(&link).unwrap();
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secreT", "secret"));
+        assert!(!tokens_match("secret!", "secret"));
+        assert!(!tokens_match("", "secret"));
+    }
+
+    #[test]
+    fn test_split_token() {
+        let argv = ["--token", "secret", "query", "state"].map(str::to_string);
+        assert_eq!(split_token(&argv), (Some("secret"), vec!["query", "state"]));
+        let argv = ["query", "state"].map(str::to_string);
+        assert_eq!(split_token(&argv), (None, vec!["query", "state"]));
     }
 }
@@ -0,0 +1,179 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use std::io::{Read, Write};
+use stdext::function_name;
+
+use crate::errors::{Error, Result};
+
+/// Leading bytes of every frame. As a little-endian length of the unframed
+/// protocol it would be far larger than any request, so it cannot be mistaken for one.
+pub const MAGIC: [u8; 3] = *b"PNR";
+/// Version of the payload encoding. The header layout is the same for every version,
+/// so a peer can always read a frame and answer it with `Status::Unsupported`.
+pub const VERSION: u8 = 1;
+/// Largest payload accepted, guarding against allocating for a garbage length.
+const MAX_PAYLOAD: u32 = 16 * 1024 * 1024;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4 + 1 + 4;
+
+/// Outcome of a request. Requests themselves always carry `Ok`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+pub enum Status {
+    Ok = 0,
+    /// The request was understood but failed. The payload holds the error message.
+    Error = 1,
+    /// The request uses a protocol version or a request the daemon does not know.
+    Unsupported = 2,
+    /// The request was accepted and the rest of the connection is a line-delimited stream.
+    Stream = 3,
+}
+
+/// A single message on the command socket:
+///
+/// | Bytes | Field |
+/// | :--- | :--- |
+/// | 3 | `MAGIC` |
+/// | 1 | version |
+/// | 4 | request id, little-endian |
+/// | 1 | status |
+/// | 4 | payload length, little-endian |
+/// | n | payload |
+///
+/// Request payloads are NUL-separated arguments, responses are UTF-8 text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub version: u8,
+    pub request_id: u32,
+    pub status: Status,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    pub fn request(request_id: u32, params: impl IntoIterator<Item = String>) -> Self {
+        let payload = params
+            .into_iter()
+            .flat_map(|param| [param.as_bytes(), &[0]].concat())
+            .collect();
+        Frame {
+            version: VERSION,
+            request_id,
+            status: Status::Ok,
+            payload,
+        }
+    }
+
+    pub fn response(request_id: u32, status: Status, payload: impl Into<Vec<u8>>) -> Self {
+        Frame {
+            version: VERSION,
+            request_id,
+            status,
+            payload: payload.into(),
+        }
+    }
+
+    /// Splits a request payload back into its arguments.
+    pub fn argv(&self) -> Vec<String> {
+        self.payload
+            .split(|c| *c == 0)
+            .filter(|s| !s.is_empty())
+            .map(|s| String::from_utf8_lossy(s).to_string())
+            .collect()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.payload).to_string()
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+        let length: u32 = self.payload.len().try_into()?;
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(&MAGIC);
+        header.push(self.version);
+        header.extend_from_slice(&self.request_id.to_le_bytes());
+        header.push(self.status.into());
+        header.extend_from_slice(&length.to_le_bytes());
+        writer.write_all(&header)?;
+        writer.write_all(&self.payload)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads one complete frame, failing with `Error::InvalidInput` if the peer
+    /// does not speak this protocol at all.
+    pub fn read_from(reader: &mut impl Read) -> Result<Self> {
+        // The magic is checked on its own, so that a short unframed request is
+        // rejected instead of waiting for the rest of a header that never comes.
+        let mut magic = [0u8; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(Error::InvalidInput(format!(
+                "{}: missing frame header",
+                function_name!()
+            )));
+        }
+        let mut header = [0u8; HEADER_LEN - MAGIC.len()];
+        reader.read_exact(&mut header)?;
+        let (version, rest) = (header[0], &header[1..]);
+        let request_id = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+        let status = Status::try_from(rest[4]).map_err(|err| {
+            Error::InvalidInput(format!("{}: unknown status {err}", function_name!()))
+        })?;
+        let length = u32::from_le_bytes([rest[5], rest[6], rest[7], rest[8]]);
+        if length > MAX_PAYLOAD {
+            return Err(Error::InvalidInput(format!(
+                "{}: payload of {length} bytes is too large",
+                function_name!()
+            )));
+        }
+        let mut payload = vec![0u8; length as usize];
+        reader.read_exact(&mut payload)?;
+        Ok(Frame {
+            version,
+            request_id,
+            status,
+            payload,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let request = Frame::request(7, ["window".to_string(), "focus".to_string()]);
+        let mut buffer = Vec::new();
+        request.write_to(&mut buffer).unwrap();
+
+        let read = Frame::read_from(&mut Cursor::new(buffer)).unwrap();
+        assert_eq!(read, request);
+        assert_eq!(read.argv(), vec!["window", "focus"]);
+    }
+
+    #[test]
+    fn test_frame_rejects_unframed_request() {
+        // A request of the unframed protocol: a length followed by the arguments,
+        // shorter than a frame header.
+        let mut buffer = 5u32.to_le_bytes().to_vec();
+        buffer.extend_from_slice(b"quit\0");
+        assert!(buffer.len() < HEADER_LEN);
+        assert!(matches!(
+            Frame::read_from(&mut Cursor::new(buffer)),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_frame_truncated_payload() {
+        let mut buffer = Vec::new();
+        Frame::response(1, Status::Ok, "ok\n")
+            .write_to(&mut buffer)
+            .unwrap();
+        buffer.pop();
+        assert!(matches!(
+            Frame::read_from(&mut Cursor::new(buffer)),
+            Err(Error::IO(_))
+        ));
+    }
+}
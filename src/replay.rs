@@ -0,0 +1,67 @@
+//! A virtual macOS, built on the mocked platform APIs, which drives the whole
+//! ECS schedule. The tests use it through `TestHarness`, and
+//! `paneru replay`, built with the `replay` feature, feeds recordings through
+//! it. Everything only the tests need is left out of that build.
+
+use std::path::Path;
+
+use bevy::ecs::world::World;
+
+use crate::ecs::layout::LayoutStrip;
+use crate::ecs::recorder::read_recording;
+use crate::errors::Result;
+
+pub(crate) mod harness;
+mod mocks;
+
+pub(crate) use harness::*;
+pub(crate) use mocks::*;
+
+pub(crate) const TEST_PROCESS_ID: i32 = 1;
+pub(crate) const TEST_DISPLAY_ID: u32 = 1;
+pub(crate) const TEST_WORKSPACE_ID: u64 = 2;
+pub(crate) const TEST_DISPLAY_WIDTH: i32 = 1024;
+pub(crate) const TEST_DISPLAY_HEIGHT: i32 = 768;
+
+#[cfg(test)]
+pub(crate) const EXT_DISPLAY_ID: u32 = 2;
+#[cfg(test)]
+pub(crate) const EXT_WORKSPACE_ID: u64 = 20;
+#[cfg(test)]
+pub(crate) const EXT_DISPLAY_WIDTH: i32 = 1920;
+#[cfg(test)]
+pub(crate) const EXT_DISPLAY_HEIGHT: i32 = 1200;
+
+pub(crate) const TEST_MENUBAR_HEIGHT: i32 = 20;
+pub(crate) const TEST_WINDOW_WIDTH: i32 = 400;
+pub(crate) const TEST_WINDOW_HEIGHT: i32 = 1000;
+
+/// Replays a recording made with `paneru launch --record` without a desktop,
+/// and prints the strips it ends up with.
+pub fn replay_headless(path: &Path) -> Result<()> {
+    let entries = read_recording(path)?;
+    let count = entries.len();
+    let mut harness = TestHarness::new();
+    harness.replay(entries);
+    println!("replayed {count} events from {}", path.display());
+    print!("{}", describe_strips(harness.world()));
+    Ok(())
+}
+
+/// Lists the window ids in the columns of every strip, one strip per line.
+fn describe_strips(world: &mut World) -> String {
+    let mut strips = world
+        .query::<&LayoutStrip>()
+        .iter(world)
+        .map(|strip| (strip.id(), strip.virtual_index))
+        .collect::<Vec<_>>();
+    strips.sort_unstable();
+    strips
+        .into_iter()
+        .filter(|(_, virtual_index)| *virtual_index == 0)
+        .map(|(space_id, _)| {
+            let columns = strip_columns(world, space_id);
+            format!("space {space_id}: {columns:?}\n")
+        })
+        .collect()
+}
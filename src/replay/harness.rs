@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 
 use bevy::prelude::*;
@@ -11,9 +11,15 @@ use crate::commands::register_commands;
 use crate::config::Config;
 use crate::ecs::display::DisplayEventsPlugin;
 use crate::ecs::focus::FocusEventsPlugin;
-use crate::ecs::layout::LayoutEventsPlugin;
+use crate::ecs::layout::{LayoutEventsPlugin, LayoutStrip};
 use crate::ecs::mouse::MouseEventsPlugin;
+use crate::ecs::polling::WindowPollingPlugin;
+use crate::ecs::presentation::PresentationPlugin;
+use crate::ecs::recorder::{RecordedEntry, RecordedEvent};
+use crate::ecs::relaunch::RelaunchPlugin;
 use crate::ecs::scroll::ScrollEventsPlugin;
+use crate::ecs::spaces::EmptySpacesPlugin;
+#[cfg(test)]
 use crate::ecs::state::PaneruState;
 use crate::ecs::workspace::WorkspaceEventsPlugin;
 use crate::ecs::{
@@ -22,14 +28,18 @@ use crate::ecs::{
 };
 use crate::events::Event;
 use crate::manager::{Window, WindowManager};
-use crate::platform::{Pid, WinID, WorkspaceId};
+#[cfg(test)]
+use crate::platform::Pid;
+use crate::platform::{WinID, WorkspaceId};
 
 use super::*;
 
+#[cfg(test)]
 type VerifierFunc = Box<dyn FnMut(&mut World, MockState)>;
 pub(crate) struct TestHarness {
     pub(crate) app: App,
     pub(crate) mock_state: MockState,
+    #[cfg(test)]
     pub(crate) verifiers: HashMap<usize, VerifierFunc>,
 }
 
@@ -58,11 +68,12 @@ impl TestHarness {
         world.spawn((ExistingMarker, ChildOf(process_entity), application));
 
         let wm = mock_state.create_window_manager();
-        world.insert_resource(WindowManager(Box::new(wm)));
+        world.insert_resource(WindowManager(Arc::new(wm)));
 
         Self {
             app,
             mock_state,
+            #[cfg(test)]
             verifiers: HashMap::new(),
         }
     }
@@ -71,6 +82,50 @@ impl TestHarness {
         self.app.world_mut()
     }
 
+    /// Feeds a recording made with `paneru launch --record` through the virtual OS.
+    /// Windows are created on the test workspace with the default frame, and
+    /// the gaps between the events are replayed as 100ms updates, so a replay
+    /// behaves the same on every run.
+    pub(crate) fn replay(&mut self, entries: Vec<RecordedEntry>) {
+        let mut last_offset = entries.first().map_or(0, |entry| entry.offset_ms);
+        for entry in entries {
+            match entry.event {
+                RecordedEvent::WindowCreated { window_id } => {
+                    let frame = IRect::new(0, 0, TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT);
+                    let window = self.mock_state.spawn_window(
+                        TEST_PROCESS_ID,
+                        TEST_WORKSPACE_ID,
+                        window_id,
+                        frame,
+                    );
+                    self.app
+                        .world_mut()
+                        .trigger(SpawnWindowTrigger(vec![window]));
+                }
+                RecordedEvent::WindowDestroyed { window_id } => {
+                    self.mock_state.os_destroy_window(window_id);
+                }
+                event => {
+                    if let Some(event) = event.into_event() {
+                        self.app.world_mut().write_message::<Event>(event);
+                    }
+                }
+            }
+
+            let updates = (entry.offset_ms.saturating_sub(last_offset) / 100).clamp(1, 50);
+            last_offset = entry.offset_ms;
+            for _ in 0..updates {
+                self.app.update();
+                for event in self.mock_state.drain_events() {
+                    self.app.world_mut().write_message::<Event>(event);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl TestHarness {
     pub(crate) fn with_windows(mut self, count: i32) -> Self {
         let pid = TEST_PROCESS_ID;
 
@@ -138,6 +193,12 @@ impl TestHarness {
         self
     }
 
+    /// Lets a single space span all displays, each display tiling a workspace of it.
+    pub(crate) fn with_spanning_spaces(self) -> Self {
+        self.mock_state.set_spanning_spaces(true);
+        self
+    }
+
     #[allow(unused)]
     pub(crate) fn with_app<F>(mut self, pid: Pid, bundle_id: &str, name: &str, f: F) -> Self
     where
@@ -233,6 +294,10 @@ fn setup_world() -> App {
         .add_plugins(LayoutEventsPlugin)
         .add_plugins(FocusEventsPlugin)
         .add_plugins(DisplayEventsPlugin)
+        .add_plugins(PresentationPlugin)
+        .add_plugins(RelaunchPlugin)
+        .add_plugins(WindowPollingPlugin)
+        .add_plugins(EmptySpacesPlugin)
         .add_plugins((register_triggers, register_systems, register_commands));
 
     bevy_app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
@@ -242,6 +307,7 @@ fn setup_world() -> App {
     bevy_app
 }
 
+#[cfg(test)]
 pub(crate) fn find_window_entity(window_id: WinID, world: &mut World) -> Entity {
     let mut query = world.query::<(&Window, Entity)>();
     query
@@ -250,6 +316,30 @@ pub(crate) fn find_window_entity(window_id: WinID, world: &mut World) -> Entity
         .map_or_else(|| panic!("window {window_id} not found"), |(_, e)| e)
 }
 
+/// Returns the window ids of each column of the space's first strip, from
+/// left to right, so that tests can assert on the whole layout at once.
+pub(crate) fn strip_columns(world: &mut World, workspace_id: WorkspaceId) -> Vec<Vec<WinID>> {
+    let ids = world
+        .query::<(&Window, Entity)>()
+        .iter(world)
+        .map(|(window, entity)| (entity, window.id()))
+        .collect::<HashMap<_, _>>();
+    let mut query = world.query::<&LayoutStrip>();
+    let strip = query
+        .iter(world)
+        .find(|strip| strip.id() == workspace_id && strip.virtual_index == 0)
+        .unwrap_or_else(|| panic!("no strip for workspace {workspace_id}"));
+    strip
+        .columns()
+        .map(|column| {
+            column
+                .window_iter()
+                .filter_map(|entity| ids.get(&entity).copied())
+                .collect()
+        })
+        .collect()
+}
+
 #[macro_export]
 macro_rules! assert_window_at {
     ($world:expr, $id:expr, $x:expr, $y:expr) => {{
@@ -305,7 +395,7 @@ macro_rules! assert_focused {
 #[macro_export]
 macro_rules! assert_on_workspace {
     ($world:expr, $window_id:expr, $workspace_id:expr) => {{
-        let entity = $crate::tests::harness::find_window_entity($window_id, $world);
+        let entity = $crate::replay::harness::find_window_entity($window_id, $world);
         let mut query = $world.query::<&$crate::ecs::layout::LayoutStrip>();
         let found = query
             .iter($world)
@@ -321,7 +411,7 @@ macro_rules! assert_on_workspace {
 #[macro_export]
 macro_rules! assert_not_on_workspace {
     ($world:expr, $window_id:expr, $workspace_id:expr) => {{
-        let entity = $crate::tests::harness::find_window_entity($window_id, $world);
+        let entity = $crate::replay::harness::find_window_entity($window_id, $world);
         let mut query = $world.query::<&$crate::ecs::layout::LayoutStrip>();
         let found = query
             .iter($world)
@@ -2,7 +2,9 @@ use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, RwLock};
 
 use bevy::prelude::*;
+#[cfg(test)]
 use objc2_core_foundation::CGPoint;
+#[cfg(test)]
 use objc2_core_graphics::CGDirectDisplayID;
 use stdext::prelude::RwLockExt;
 
@@ -10,13 +12,21 @@ use crate::errors::Error;
 use crate::events::Event;
 use crate::manager::app::MockApplicationApi;
 use crate::manager::{
-    Application, Display, MockProcessApi, MockWindowApi, MockWindowManagerApi, Origin, Size,
-    Window, origin_to,
+    Application, Display, MockProcessApi, MockWindowApi, MockWindowManagerApi, Origin, Window,
+    origin_to,
 };
-use crate::platform::{Modifiers, Pid, ProcessSerialNumber, WinID, WorkspaceId};
+use crate::platform::{Pid, ProcessSerialNumber, WinID, WorkspaceId};
+#[cfg(test)]
+use crate::{manager::Size, platform::Modifiers};
 
 use super::*;
 
+/// The workspace of a display in a space spanning all displays, made up the way
+/// the window manager does it.
+pub(crate) fn spanning_workspace(display_id: u32, space_id: WorkspaceId) -> WorkspaceId {
+    (WorkspaceId::from(display_id) << 32) | space_id
+}
+
 /// Data for a mocked application.
 pub(crate) struct MockAppData {
     pub(crate) psn: ProcessSerialNumber,
@@ -25,6 +35,8 @@ pub(crate) struct MockAppData {
     pub(crate) focused_window_id: Option<WinID>,
     pub(crate) is_frontmost: bool,
     pub(crate) connection: Option<crate::platform::ConnID>,
+    /// The app lists its windows over accessibility, otherwise its window list is empty.
+    pub(crate) lists_windows: bool,
 }
 
 /// Data for a mocked window.
@@ -45,6 +57,9 @@ pub(crate) struct MockWindowData {
     pub(crate) horizontal_padding: i32,
     pub(crate) vertical_padding: i32,
     pub(crate) child_role: bool,
+    /// The window a sheet or drawer is attached to.
+    pub(crate) parent: Option<WinID>,
+    pub(crate) native_tabs: Option<usize>,
 }
 
 impl Default for MockWindowData {
@@ -65,6 +80,8 @@ impl Default for MockWindowData {
             horizontal_padding: 0,
             vertical_padding: 0,
             child_role: false,
+            parent: None,
+            native_tabs: None,
         }
     }
 }
@@ -84,6 +101,10 @@ struct MockStateInner {
     active_display_id: u32,
     cursor_position: Origin,
     event_queue: VecDeque<Event>,
+    desktop_focused: bool,
+    trusted: bool,
+    /// A space spans all displays, which get a workspace of it each.
+    spanning_spaces: bool,
 }
 
 #[derive(Clone)]
@@ -101,10 +122,14 @@ impl MockState {
                 active_display_id: 0,
                 cursor_position: Origin::ZERO,
                 event_queue: VecDeque::new(),
+                desktop_focused: false,
+                trusted: true,
+                spanning_spaces: false,
             })),
         }
     }
 
+    #[cfg(test)]
     pub(crate) fn window_visible(&self, window_id: WinID, visible: bool) {
         let mut state = self.inner.force_write();
         let window = state.windows.get_mut(&window_id).expect("finding window");
@@ -127,6 +152,7 @@ impl MockState {
                 focused_window_id: None,
                 is_frontmost: true,
                 connection: Some(0),
+                lists_windows: false,
             },
         );
     }
@@ -155,6 +181,7 @@ impl MockState {
 
     pub fn focus_window(&self, id: WinID) {
         let mut inner = self.inner.force_write();
+        inner.desktop_focused = false;
         if let Some(win) = inner.windows.get(&id) {
             let pid = win.pid;
             if let Some(app) = inner.apps.get_mut(&pid) {
@@ -170,6 +197,16 @@ impl MockState {
         }
     }
 
+    #[cfg(test)]
+    pub fn set_trusted(&self, trusted: bool) {
+        self.inner.force_write().trusted = trusted;
+    }
+
+    #[cfg(test)]
+    pub fn desktop_focused(&self) -> bool {
+        self.inner.force_read().desktop_focused
+    }
+
     pub fn add_display(&mut self, id: u32, bounds: IRect, workspaces: Vec<WorkspaceId>) {
         let mut inner = self.inner.force_write();
         if inner.displays.is_empty() {
@@ -185,6 +222,7 @@ impl MockState {
         );
     }
 
+    #[cfg(test)]
     #[allow(unused)]
     pub fn remove_display(&self, id: u32) {
         let mut inner = self.inner.force_write();
@@ -194,6 +232,39 @@ impl MockState {
         }
     }
 
+    #[cfg(test)]
+    pub fn set_spanning_spaces(&self, spanning: bool) {
+        self.inner.force_write().spanning_spaces = spanning;
+    }
+
+    #[cfg(test)]
+    pub fn set_display_bounds(&self, id: u32, bounds: IRect) {
+        if let Some(display) = self.inner.force_write().displays.get_mut(&id) {
+            display.bounds = bounds;
+        }
+    }
+
+    /// Makes another display the active one, as macOS does when a window on it
+    /// takes the focus.
+    #[cfg(test)]
+    pub fn set_active_display(&self, id: u32) {
+        let mut inner = self.inner.force_write();
+        inner.active_display_id = id;
+        inner.event_queue.push_back(Event::DisplayChanged);
+    }
+
+    /// Returns the spaces of a display, as listed in Mission Control.
+    #[cfg(test)]
+    pub fn display_spaces(&self, id: u32) -> Vec<WorkspaceId> {
+        self.inner
+            .force_read()
+            .displays
+            .get(&id)
+            .map(|display| display.workspaces.clone())
+            .unwrap_or_default()
+    }
+
+    #[cfg(test)]
     pub fn active_display(&self) -> CGDirectDisplayID {
         self.inner.force_read().active_display_id
     }
@@ -205,6 +276,7 @@ impl MockState {
 
     // --- State Mutation Methods ---
 
+    #[cfg(test)]
     pub fn update_window<F>(&self, id: WinID, f: F)
     where
         F: FnOnce(&mut MockWindowData),
@@ -215,6 +287,7 @@ impl MockState {
         }
     }
 
+    #[cfg(test)]
     #[allow(unused)]
     pub fn update_app(&self, pid: Pid, f: impl FnOnce(&mut MockAppData)) {
         let mut inner = self.inner.force_write();
@@ -225,6 +298,7 @@ impl MockState {
 
     // --- OS Behavior Methods ---
 
+    #[cfg(test)]
     #[allow(unused)]
     pub fn os_move_window(&self, id: WinID, origin: Origin) {
         let mut inner = self.inner.force_write();
@@ -238,6 +312,7 @@ impl MockState {
         }
     }
 
+    #[cfg(test)]
     #[allow(unused)]
     pub fn os_resize_window(&self, id: WinID, size: Size) {
         let mut inner = self.inner.force_write();
@@ -249,6 +324,17 @@ impl MockState {
         }
     }
 
+    /// Closes a window behind paneru's back, as when its application closes it.
+    pub fn os_destroy_window(&self, id: WinID) {
+        let mut inner = self.inner.force_write();
+        if inner.windows.remove(&id).is_some() {
+            inner
+                .event_queue
+                .push_back(Event::WindowDestroyed { window_id: id });
+        }
+    }
+
+    #[cfg(test)]
     #[allow(unused)]
     pub fn os_minimize_window(&self, id: WinID, minimized: bool) {
         let mut inner = self.inner.force_write();
@@ -268,6 +354,7 @@ impl MockState {
 
     // --- Interaction Helpers ---
 
+    #[cfg(test)]
     #[allow(unused)]
     pub fn simulate_click(&self, point: Origin) {
         let mut inner = self.inner.force_write();
@@ -282,6 +369,7 @@ impl MockState {
         });
     }
 
+    #[cfg(test)]
     #[allow(unused)]
     pub fn simulate_window_click(&self, id: WinID) {
         let inner = self.inner.force_read();
@@ -292,6 +380,7 @@ impl MockState {
         }
     }
 
+    #[cfg(test)]
     #[allow(unused)]
     pub fn simulate_drag(&self, start: Origin, end: Origin) {
         let mut inner = self.inner.force_write();
@@ -311,6 +400,7 @@ impl MockState {
         });
     }
 
+    #[cfg(test)]
     pub fn cursor_position(&self) -> IVec2 {
         self.inner.force_read().cursor_position
     }
@@ -362,9 +452,10 @@ impl MockState {
         });
 
         let s = self.clone();
-        mw.expect_focus_with_raise().returning(move |_psn| {
-            s.focus_window(id);
-        });
+        mw.expect_focus_with_raise()
+            .returning(move |_psn, _strategy| {
+                s.focus_window(id);
+            });
 
         let s = self.clone();
         mw.expect_title().returning(move || {
@@ -407,14 +498,18 @@ impl MockState {
 
         let s = self.clone();
         mw.expect_role().returning(move || {
-            Ok(s.inner
+            s.inner
                 .force_read()
                 .windows
                 .get(&id)
                 .map(|w| w.role.clone())
-                .unwrap_or_default())
+                .ok_or(Error::InvalidWindow)
         });
 
+        let s = self.clone();
+        mw.expect_is_alive()
+            .returning(move || s.inner.force_read().windows.contains_key(&id));
+
         let s = self.clone();
         mw.expect_subrole().returning(move || {
             Ok(s.inner
@@ -472,11 +567,34 @@ impl MockState {
                 .and_then(|w| w.border_radius)
         });
 
+        let s = self.clone();
+        mw.expect_native_tab_count().returning(move || {
+            s.inner
+                .force_read()
+                .windows
+                .get(&id)
+                .and_then(|w| w.native_tabs)
+        });
+
+        let s = self.clone();
+        mw.expect_close().returning(move || {
+            let mut inner = s.inner.force_write();
+            inner.windows.remove(&id).ok_or(Error::InvalidWindow)?;
+            inner
+                .event_queue
+                .push_back(Event::WindowDestroyed { window_id: id });
+            Ok(())
+        });
+
         // Fill in remaining defaults
         mw.expect_element().return_const(None);
+        mw.expect_refresh_title().return_const(());
         mw.expect_raise_without_focus().return_const(());
         mw.expect_focus_without_raise().return_const(());
         mw.expect_set_padding().return_const(());
+        mw.expect_set_frame_inset().return_const(());
+        mw.expect_set_min_size().return_const(());
+        mw.expect_detect_frame_inset().return_const(None);
 
         Window::new(Box::new(mw))
     }
@@ -507,6 +625,7 @@ impl MockState {
                 .get(&pid)
                 .map(|a| a.bundle_id.clone())
         });
+        ma.expect_bundle_path().return_const(None);
 
         let name = self
             .inner
@@ -538,7 +657,42 @@ impl MockState {
         ma.expect_observe().returning(|| Ok(true));
         ma.expect_observe_window().returning(|_| Ok(true));
         ma.expect_unobserve_window().return_const(());
-        ma.expect_window_list().returning(|_| Vec::new());
+        let s = self.clone();
+        ma.expect_window_list().returning(move |_| {
+            let ids = {
+                let inner = s.inner.force_read();
+                if !inner.apps.get(&pid).is_some_and(|a| a.lists_windows) {
+                    return Vec::new();
+                }
+                let mut ids = inner
+                    .windows
+                    .values()
+                    .filter(|w| w.pid == pid)
+                    .map(|w| w.id)
+                    .collect::<Vec<_>>();
+                ids.sort_unstable();
+                ids
+            };
+            ids.into_iter().map(|id| s.create_window(id)).collect()
+        });
+        let s = self.clone();
+        ma.expect_window_list_of().returning(move |window_ids, _| {
+            let ids = {
+                let inner = s.inner.force_read();
+                if !inner.apps.get(&pid).is_some_and(|a| a.lists_windows) {
+                    return Vec::new();
+                }
+                let mut ids = inner
+                    .windows
+                    .values()
+                    .filter(|w| w.pid == pid && window_ids.contains(&w.id))
+                    .map(|w| w.id)
+                    .collect::<Vec<_>>();
+                ids.sort_unstable();
+                ids
+            };
+            ids.into_iter().map(|id| s.create_window(id)).collect()
+        });
 
         Application::new(Box::new(ma))
     }
@@ -592,6 +746,23 @@ impl MockState {
                 Ok((windows, vec![]))
             });
 
+        let s = self.clone();
+        wm.expect_application_window_ids()
+            .returning(move |app, spaces| {
+                let pid = app.pid();
+                let mut windows = s
+                    .inner
+                    .force_read()
+                    .windows
+                    .values()
+                    .filter_map(|w| {
+                        (w.pid == pid && spaces.contains(&w.workspace_id)).then_some(w.id)
+                    })
+                    .collect::<Vec<_>>();
+                windows.sort_unstable();
+                Ok(windows)
+            });
+
         let s = self.clone();
         wm.expect_windows_in_workspace()
             .returning(move |workspace_id| {
@@ -628,7 +799,85 @@ impl MockState {
         wm.expect_cursor_position()
             .returning(move || Some(origin_to(s.inner.force_read().cursor_position)));
 
-        wm.expect_get_associated_windows().return_const(vec![]);
+        let s = self.clone();
+        wm.expect_focus_desktop().returning(move || {
+            s.inner.force_write().desktop_focused = true;
+            Ok(())
+        });
+
+        wm.expect_focus_space().returning(|_| Ok(()));
+        wm.expect_create_space().returning(|| Ok(()));
+        wm.expect_destroy_space().returning(|| Ok(()));
+        let s = self.clone();
+        wm.expect_remove_space()
+            .returning(move |display_id, space_id| {
+                if let Some(display) = s.inner.force_write().displays.get_mut(&display_id) {
+                    display.workspaces.retain(|id| *id != space_id);
+                }
+                Ok(())
+            });
+        wm.expect_is_desktop_element().return_const(false);
+        wm.expect_send_keys().returning(|_, _| Ok(()));
+
+        let s = self.clone();
+        wm.expect_display_spaces().returning(move |id| {
+            s.inner
+                .force_read()
+                .displays
+                .get(&id)
+                .map(|d| d.workspaces.clone())
+                .ok_or(Error::InvalidWindow)
+        });
+
+        let s = self.clone();
+        wm.expect_space_workspaces().returning(move |space_id| {
+            let inner = s.inner.force_read();
+            if !inner.spanning_spaces {
+                return vec![space_id];
+            }
+            let mut workspaces = inner
+                .displays
+                .keys()
+                .map(|id| spanning_workspace(*id, space_id))
+                .collect::<Vec<_>>();
+            workspaces.sort_unstable();
+            workspaces
+        });
+
+        let s = self.clone();
+        wm.expect_move_window_to_space()
+            .returning(move |window_id, space_id| {
+                let mut inner = s.inner.force_write();
+                let window = inner
+                    .windows
+                    .get_mut(&window_id)
+                    .ok_or(Error::InvalidWindow)?;
+                window.workspace_id = space_id;
+                Ok(())
+            });
+
+        let s = self.clone();
+        wm.expect_is_trusted()
+            .returning(move || s.inner.force_read().trusted);
+        wm.expect_connection_alive().return_const(true);
+        wm.expect_quit().returning(|| Ok(()));
+        wm.expect_display_captured().return_const(false);
+        wm.expect_notify_user().return_const(());
+
+        let s = self.clone();
+        wm.expect_get_associated_windows()
+            .returning(move |window_id| {
+                let mut children = s
+                    .inner
+                    .force_read()
+                    .windows
+                    .values()
+                    .filter(|window| window.parent == Some(window_id))
+                    .map(|window| window.id)
+                    .collect::<Vec<_>>();
+                children.sort_unstable();
+                children
+            });
         wm.expect_find_window_at_point().return_const(Ok(0));
 
         wm
@@ -652,6 +901,14 @@ impl MockState {
             .returning(move || s.inner.force_read().apps.get(&pid).map(|a| a.psn).unwrap());
         mp.expect_is_observable().returning(|| true);
         mp.expect_application().return_const(None);
+        let s = self.clone();
+        mp.expect_bundle_id().returning(move || {
+            s.inner
+                .force_read()
+                .apps
+                .get(&pid)
+                .map(|a| a.bundle_id.clone())
+        });
         mp.expect_ready().return_const(true);
         mp.expect_force_manage().return_const(());
 
@@ -3,13 +3,20 @@ use std::time::Duration;
 use bevy::prelude::*;
 use bevy::time::TimeUpdateStrategy;
 
-use crate::commands::{Command, MouseMove, MoveFocus, Operation};
-use crate::config::Config;
+use crate::commands::{Command, Direction, MouseMove, MoveFocus, Operation};
+use crate::config::{Config, MainOptions, WindowParams};
+use crate::ecs::focus::FocusHistory;
 use crate::ecs::layout::LayoutStrip;
-use crate::ecs::{ActiveWorkspaceMarker, DockPosition, RefreshWindowSizes, Timeout};
+use crate::ecs::{
+    ActiveDisplayMarker, ActiveWorkspaceMarker, DockPosition, RefreshWindowSizes,
+    SpawnWindowTrigger, Timeout, Unmanaged,
+};
 use crate::events::Event;
 use crate::manager::{Display, Origin, Size};
-use crate::{assert_not_on_workspace, assert_on_workspace, assert_window_at, assert_window_size};
+use crate::{
+    assert_focused, assert_not_on_workspace, assert_on_workspace, assert_window_at,
+    assert_window_size,
+};
 
 use super::*;
 
@@ -156,6 +163,182 @@ fn test_multi_workspace_orphaning() {
         .run(commands);
 }
 
+/// Starts with a window on an external display, whose strip is then orphaned
+/// when the display is unplugged.
+fn harness_with_external_window() -> TestHarness {
+    let harness = TestHarness::new().with_display(
+        EXT_DISPLAY_ID,
+        IRect::new(0, -EXT_DISPLAY_HEIGHT, EXT_DISPLAY_WIDTH, 0),
+        vec![EXT_WORKSPACE_ID],
+    );
+    let ext_origin = Origin::new(0, -EXT_DISPLAY_HEIGHT + TEST_MENUBAR_HEIGHT);
+    let size = Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT);
+    harness.mock_state.spawn_window(
+        TEST_PROCESS_ID,
+        EXT_WORKSPACE_ID,
+        100,
+        IRect::from_corners(ext_origin, ext_origin + size),
+    );
+    harness
+}
+
+fn orphan_parent(world: &mut World) -> Option<Option<Entity>> {
+    world
+        .query::<(&LayoutStrip, Option<&ChildOf>)>()
+        .iter(world)
+        .find(|(strip, _)| strip.id() == EXT_WORKSPACE_ID)
+        .map(|(_, child)| child.map(ChildOf::parent))
+}
+
+#[test]
+fn test_orphan_moves_to_display_taking_its_space() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 100 },
+        Event::DisplayRemoved {
+            display_id: EXT_DISPLAY_ID,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    harness_with_external_window()
+        .on_iteration(0, |_, mut state| {
+            // macOS moves the spaces of an unplugged display to a remaining one.
+            state.remove_display(EXT_DISPLAY_ID);
+            state.add_display(
+                TEST_DISPLAY_ID,
+                IRect::new(0, 0, TEST_DISPLAY_WIDTH, TEST_DISPLAY_HEIGHT),
+                vec![TEST_WORKSPACE_ID, EXT_WORKSPACE_ID],
+            );
+        })
+        .on_iteration(2, |world, _| {
+            let display = world
+                .query_filtered::<Entity, With<Display>>()
+                .single(world)
+                .expect("only the built-in display should be left");
+            assert_eq!(orphan_parent(world), Some(Some(display)));
+            let entity = find_window_entity(100, world);
+            assert!(world.get::<Unmanaged>(entity).is_none());
+            assert_eq!(strip_columns(world, EXT_WORKSPACE_ID), vec![vec![100]]);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_orphan_times_out_and_floats_its_windows() {
+    let mut commands = vec![
+        Event::MenuOpened { window_id: 100 },
+        Event::DisplayRemoved {
+            display_id: EXT_DISPLAY_ID,
+        },
+    ];
+    // Five updates of 100ms per command, past the 30s an orphan waits for a display.
+    commands.extend(vec![
+        Event::Command {
+            command: Command::PrintState,
+        };
+        64
+    ]);
+    let last = commands.len() - 1;
+
+    harness_with_external_window()
+        .on_iteration(0, |_, state| {
+            state.remove_display(EXT_DISPLAY_ID);
+        })
+        .on_iteration(1, |world, _| {
+            assert_eq!(orphan_parent(world), Some(None));
+        })
+        .on_iteration(last, |world, _| {
+            assert_eq!(orphan_parent(world), None, "orphan should be despawned");
+            let entity = find_window_entity(100, world);
+            assert!(matches!(
+                world.get::<Unmanaged>(entity),
+                Some(Unmanaged::Floating)
+            ));
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_empty_orphan_is_despawned() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 100 },
+        Event::DisplayRemoved {
+            display_id: EXT_DISPLAY_ID,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    harness_with_external_window()
+        .on_iteration(0, |_, state| {
+            state.remove_display(EXT_DISPLAY_ID);
+        })
+        .on_iteration(1, |world, state| {
+            assert_eq!(orphan_parent(world), Some(None));
+            state.os_destroy_window(100);
+        })
+        .on_iteration(2, |world, _| {
+            assert_eq!(
+                orphan_parent(world),
+                None,
+                "empty orphan should be despawned"
+            );
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_plugged_display_takes_new_windows() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::DisplayAdded {
+            display_id: EXT_DISPLAY_ID,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    TestHarness::new()
+        .with_windows(1)
+        .on_iteration(0, |_, mut state| {
+            state.add_display(
+                EXT_DISPLAY_ID,
+                IRect::new(0, -EXT_DISPLAY_HEIGHT, EXT_DISPLAY_WIDTH, 0),
+                vec![EXT_WORKSPACE_ID],
+            );
+        })
+        .on_iteration(1, |world, state| {
+            let displays = world
+                .query_filtered::<Entity, With<Display>>()
+                .iter(world)
+                .count();
+            assert_eq!(displays, 2, "the plugged display should be spawned");
+
+            let origin = Origin::new(0, -EXT_DISPLAY_HEIGHT + TEST_MENUBAR_HEIGHT);
+            let frame = IRect::from_corners(
+                origin,
+                origin + Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT),
+            );
+            let window = state.spawn_window(TEST_PROCESS_ID, EXT_WORKSPACE_ID, 100, frame);
+            world.trigger(SpawnWindowTrigger(vec![window]));
+        })
+        .on_iteration(2, |world, _| {
+            let display = world
+                .query::<(&Display, Entity)>()
+                .iter(world)
+                .find(|(display, _)| display.id() == EXT_DISPLAY_ID)
+                .map(|(_, entity)| entity);
+            assert_eq!(orphan_parent(world), Some(display));
+            assert_eq!(strip_columns(world, EXT_WORKSPACE_ID), vec![vec![100]]);
+            assert_eq!(strip_columns(world, TEST_WORKSPACE_ID), vec![vec![0]]);
+        })
+        .run(commands);
+}
+
 #[test]
 fn test_multi_display_no_height_crosstalk() {
     let mut harness = TestHarness::new();
@@ -337,6 +520,51 @@ fn test_mouse_to_next_display() {
         .run(commands);
 }
 
+/// A new window straddling the displays joins the strip of the display holding
+/// most of it, even when the other display is active.
+#[test]
+fn test_straddling_window_joins_display_holding_most_of_it() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    // Keeps the focus off the new window, so it stays where it was inserted.
+    let mut params = WindowParams::new(".*", None);
+    params.dont_focus = Some(true);
+    let config: Config = (MainOptions::default(), vec![params]).into();
+
+    let mut harness = TestHarness::new().with_config(config).with_windows(1);
+    harness.mock_state.add_display(
+        EXT_DISPLAY_ID,
+        IRect::new(0, -EXT_DISPLAY_HEIGHT, EXT_DISPLAY_WIDTH, 0),
+        vec![EXT_WORKSPACE_ID],
+    );
+
+    harness
+        .on_iteration(0, |world, state| {
+            // Two thirds of the window lie on the external display above.
+            let origin = Origin::new(100, -TEST_WINDOW_HEIGHT * 2 / 3);
+            let frame = IRect::from_corners(
+                origin,
+                origin + Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT),
+            );
+            let window = state.spawn_window(TEST_PROCESS_ID, EXT_WORKSPACE_ID, 100, frame);
+            world.trigger(SpawnWindowTrigger(vec![window]));
+        })
+        .on_iteration(2, |world, state| {
+            assert_eq!(state.active_display(), TEST_DISPLAY_ID);
+            assert_eq!(strip_columns(world, EXT_WORKSPACE_ID), vec![vec![100]]);
+            assert_eq!(strip_columns(world, TEST_WORKSPACE_ID), vec![vec![0]]);
+        })
+        .run(commands);
+}
+
 /// Regression test: paneru's init pass must not drag windows that live on
 /// inactive displays onto the active display. `apply_window_properties`
 /// initially appends every observed window to the active strip; if the
@@ -391,6 +619,274 @@ fn test_init_keeps_windows_on_their_real_displays() {
         .run(commands);
 }
 
+/// With `managed_displays`, the windows of the other displays float in place
+/// and nothing is sent there.
+#[test]
+fn test_unmanaged_display_keeps_no_windows() {
+    // The external display sits above the test display, so it is display 1.
+    let config: Config = (
+        MainOptions {
+            managed_displays: vec!["2".to_string()],
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+    let mut harness = TestHarness::new().with_config(config);
+    harness.mock_state.add_display(
+        EXT_DISPLAY_ID,
+        IRect::new(0, -EXT_DISPLAY_HEIGHT, EXT_DISPLAY_WIDTH, 0),
+        vec![EXT_WORKSPACE_ID],
+    );
+
+    let origin = Origin::new(0, 0);
+    let ext_origin = Origin::new(100, -EXT_DISPLAY_HEIGHT + 200);
+    let size = Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT);
+    harness.mock_state.spawn_window(
+        TEST_PROCESS_ID,
+        TEST_WORKSPACE_ID,
+        0,
+        IRect::from_corners(origin, origin + size),
+    );
+    harness.mock_state.spawn_window(
+        TEST_PROCESS_ID,
+        EXT_WORKSPACE_ID,
+        100,
+        IRect::from_corners(ext_origin, ext_origin + size),
+    );
+
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::Window(Operation::ToNextDisplay(MoveFocus::Follow)),
+        },
+    ];
+
+    harness
+        .on_iteration(0, move |world, _state| {
+            assert_on_workspace!(world, 0, TEST_WORKSPACE_ID);
+            assert_not_on_workspace!(world, 100, EXT_WORKSPACE_ID);
+            let entity = find_window_entity(100, world);
+            assert!(matches!(
+                world.get::<Unmanaged>(entity),
+                Some(Unmanaged::Floating)
+            ));
+            assert_window_at!(world, 100, ext_origin.x, ext_origin.y);
+        })
+        .on_iteration(1, move |world, state| {
+            // There is no managed display to send the window to.
+            assert_on_workspace!(world, 0, TEST_WORKSPACE_ID);
+            assert_not_on_workspace!(world, 0, EXT_WORKSPACE_ID);
+            assert_eq!(state.active_display(), TEST_DISPLAY_ID);
+        })
+        .run(commands);
+}
+
+/// Activating an application with windows on two displays (e.g. from its Dock
+/// icon) must focus its window on the active display, even if macOS hands the
+/// focus to the one on the other display.
+#[test]
+fn test_front_switch_focuses_window_on_active_display() {
+    let mut harness = TestHarness::new();
+    harness.mock_state.add_display(
+        EXT_DISPLAY_ID,
+        IRect::new(0, -EXT_DISPLAY_HEIGHT, EXT_DISPLAY_WIDTH, 0),
+        vec![EXT_WORKSPACE_ID],
+    );
+
+    let size = Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT);
+    let origin = Origin::new(0, TEST_MENUBAR_HEIGHT);
+    let ext_origin = Origin::new(0, -EXT_DISPLAY_HEIGHT + TEST_MENUBAR_HEIGHT);
+    harness.mock_state.spawn_window(
+        TEST_PROCESS_ID,
+        TEST_WORKSPACE_ID,
+        200,
+        IRect::from_corners(origin, origin + size),
+    );
+    harness.mock_state.spawn_window(
+        TEST_PROCESS_ID,
+        EXT_WORKSPACE_ID,
+        100,
+        IRect::from_corners(ext_origin, ext_origin + size),
+    );
+
+    let mut psn = None;
+    harness.mock_state.update_app(TEST_PROCESS_ID, |app| {
+        app.focused_window_id = Some(100);
+        psn = Some(app.psn);
+    });
+    let psn = psn.expect("test application");
+
+    let commands = vec![
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::ApplicationFrontSwitched { psn },
+    ];
+
+    harness
+        .on_iteration(1, move |world, state| {
+            assert_focused!(world, 200);
+            assert_on_workspace!(world, 100, EXT_WORKSPACE_ID);
+            assert_window_at!(world, 100, ext_origin.x, ext_origin.y);
+            let mut focused = None;
+            state.update_app(TEST_PROCESS_ID, |app| focused = app.focused_window_id);
+            assert_eq!(focused, Some(200));
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_mouse_to_next_display_focuses_remembered_window() {
+    let mut harness = TestHarness::new();
+    harness.mock_state.add_display(
+        EXT_DISPLAY_ID,
+        IRect::new(0, -EXT_DISPLAY_HEIGHT, EXT_DISPLAY_WIDTH, 0),
+        vec![EXT_WORKSPACE_ID],
+    );
+
+    let size = Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT);
+    let origin = Origin::new(0, TEST_MENUBAR_HEIGHT);
+    harness.mock_state.spawn_window(
+        TEST_PROCESS_ID,
+        TEST_WORKSPACE_ID,
+        200,
+        IRect::from_corners(origin, origin + size),
+    );
+    for (index, window_id) in [300, 301].into_iter().enumerate() {
+        let origin = Origin::new(
+            TEST_WINDOW_WIDTH * i32::try_from(index).unwrap(),
+            -EXT_DISPLAY_HEIGHT + TEST_MENUBAR_HEIGHT,
+        );
+        harness.mock_state.spawn_window(
+            TEST_PROCESS_ID,
+            EXT_WORKSPACE_ID,
+            window_id,
+            IRect::from_corners(origin, origin + size),
+        );
+    }
+
+    let commands = vec![
+        Event::MenuOpened { window_id: 200 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::Mouse(MouseMove::ToNextDisplay),
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    harness
+        .on_iteration(1, |world, _state| {
+            // The right window was the last one focused on the external display.
+            let entity = find_window_entity(301, world);
+            world
+                .resource_mut::<FocusHistory>()
+                .record(EXT_WORKSPACE_ID, entity, None);
+        })
+        .on_iteration(3, |world, state| {
+            assert_focused!(world, 301);
+            let entity = find_window_entity(301, world);
+            let window = world.get::<Window>(entity).expect("need window");
+            assert_eq!(state.cursor_position(), window.frame().center());
+        })
+        .run(commands);
+}
+
+/// Focus moving past the edge of the strip continues on the display beside it,
+/// and comes back once that display is the active one.
+#[test]
+fn test_focus_wraps_to_display_beside() {
+    let ext_bounds = IRect::new(
+        TEST_DISPLAY_WIDTH,
+        0,
+        TEST_DISPLAY_WIDTH + EXT_DISPLAY_WIDTH,
+        EXT_DISPLAY_HEIGHT,
+    );
+    let mut harness = TestHarness::new().with_config(
+        (
+            MainOptions {
+                focus_wraps_displays: Some(true),
+                ..Default::default()
+            },
+            vec![],
+        )
+            .into(),
+    );
+    harness
+        .mock_state
+        .add_display(EXT_DISPLAY_ID, ext_bounds, vec![EXT_WORKSPACE_ID]);
+
+    let size = Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT);
+    for (index, window_id) in [200, 201].into_iter().enumerate() {
+        let origin = Origin::new(
+            TEST_WINDOW_WIDTH * i32::try_from(index).unwrap(),
+            TEST_MENUBAR_HEIGHT,
+        );
+        harness.mock_state.spawn_window(
+            TEST_PROCESS_ID,
+            TEST_WORKSPACE_ID,
+            window_id,
+            IRect::from_corners(origin, origin + size),
+        );
+    }
+    for (index, window_id) in [300, 301].into_iter().enumerate() {
+        let origin = Origin::new(
+            TEST_DISPLAY_WIDTH + TEST_WINDOW_WIDTH * i32::try_from(index).unwrap(),
+            TEST_MENUBAR_HEIGHT,
+        );
+        harness.mock_state.spawn_window(
+            TEST_PROCESS_ID,
+            EXT_WORKSPACE_ID,
+            window_id,
+            IRect::from_corners(origin, origin + size),
+        );
+    }
+
+    let focus = |direction| Event::Command {
+        command: Command::Window(Operation::Focus(direction)),
+    };
+    let commands = vec![
+        Event::MenuOpened { window_id: 200 },
+        focus(Direction::East),
+        focus(Direction::East),
+        Event::Command {
+            command: Command::PrintState,
+        },
+        focus(Direction::West),
+    ];
+
+    let active_display = |world: &mut World| {
+        let mut query = world.query_filtered::<&Display, With<ActiveDisplayMarker>>();
+        query.single(world).expect("need active display").id()
+    };
+    harness
+        .on_iteration(1, move |world, _state| {
+            assert_focused!(world, 201);
+            assert_eq!(active_display(world), TEST_DISPLAY_ID);
+        })
+        .on_iteration(2, move |world, state| {
+            // The column facing the display we came from takes the focus.
+            assert_focused!(world, 300);
+            assert!(ext_bounds.contains(state.cursor_position()));
+            // macOS follows the focus to the other display.
+            state.set_active_display(EXT_DISPLAY_ID);
+        })
+        .on_iteration(3, move |world, _state| {
+            assert_focused!(world, 300);
+            assert_eq!(active_display(world), EXT_DISPLAY_ID);
+        })
+        .on_iteration(4, move |world, state| {
+            assert_focused!(world, 201);
+            let bounds = IRect::new(0, 0, TEST_DISPLAY_WIDTH, TEST_DISPLAY_HEIGHT);
+            assert!(bounds.contains(state.cursor_position()));
+        })
+        .run(commands);
+}
+
 /// Waking from sleep (or a resolution/configuration change) with a monitor
 /// gone should reconcile the ECS display set against the OS even though no
 /// per-display `DisplayRemoved` flag arrives: the vanished display is removed
@@ -452,6 +948,51 @@ fn test_wake_reconciles_unplugged_display() {
         .run(commands);
 }
 
+/// Making the external display the main one moves the origin of the global
+/// coordinates, and the strip of the built-in display has to follow it.
+#[test]
+fn test_main_display_change_reanchors_strips() {
+    let harness = TestHarness::new().with_windows(1).with_display(
+        EXT_DISPLAY_ID,
+        IRect::new(0, -EXT_DISPLAY_HEIGHT, EXT_DISPLAY_WIDTH, 0),
+        vec![EXT_WORKSPACE_ID],
+    );
+
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::MainDisplayChanged {
+            display_id: EXT_DISPLAY_ID,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    harness
+        .on_iteration(0, |_world, state| {
+            state.set_display_bounds(
+                EXT_DISPLAY_ID,
+                IRect::new(0, 0, EXT_DISPLAY_WIDTH, EXT_DISPLAY_HEIGHT),
+            );
+            state.set_display_bounds(
+                TEST_DISPLAY_ID,
+                IRect::new(
+                    0,
+                    EXT_DISPLAY_HEIGHT,
+                    TEST_DISPLAY_WIDTH,
+                    EXT_DISPLAY_HEIGHT + TEST_DISPLAY_HEIGHT,
+                ),
+            );
+        })
+        .on_iteration(3, |world, _state| {
+            assert_window_at!(world, 0, 0, EXT_DISPLAY_HEIGHT + TEST_MENUBAR_HEIGHT);
+        })
+        .run(commands);
+}
+
 /// Even when the display set is unchanged, waking from sleep must force the
 /// active workspace to re-tile, because macOS relocates window frames across a
 /// sleep/wake cycle.
@@ -477,3 +1018,258 @@ fn test_wake_refreshes_active_workspace() {
         })
         .run(commands);
 }
+
+/// A display changing its resolution scales the windows on it, in the OS as
+/// well, keeping their width ratios and places in the strip.
+#[test]
+fn test_resolution_change_rescales_windows() {
+    const WIDTH: i32 = 2 * TEST_DISPLAY_WIDTH;
+
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::DisplayResized {
+            display_id: TEST_DISPLAY_ID,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    TestHarness::new()
+        .with_windows(2)
+        .on_iteration(1, |world, state| {
+            assert_window_at!(world, 1, TEST_WINDOW_WIDTH, TEST_MENUBAR_HEIGHT);
+            state.set_display_bounds(
+                TEST_DISPLAY_ID,
+                IRect::new(0, 0, WIDTH, TEST_DISPLAY_HEIGHT),
+            );
+        })
+        .on_iteration(4, |world, _state| {
+            let height = TEST_DISPLAY_HEIGHT - TEST_MENUBAR_HEIGHT;
+            assert_window_size!(world, 0, 2 * TEST_WINDOW_WIDTH, height);
+            assert_window_size!(world, 1, 2 * TEST_WINDOW_WIDTH, height);
+            assert_window_at!(world, 0, 0, TEST_MENUBAR_HEIGHT);
+            assert_window_at!(world, 1, 2 * TEST_WINDOW_WIDTH, TEST_MENUBAR_HEIGHT);
+        })
+        .run(commands);
+}
+
+/// Returns the display the strip of a workspace is on.
+fn strip_display(world: &mut World, workspace_id: WorkspaceId) -> Option<u32> {
+    let parent = world
+        .query::<(&LayoutStrip, &ChildOf)>()
+        .iter(world)
+        .find_map(|(strip, child)| (strip.id() == workspace_id).then_some(child.parent()))?;
+    world
+        .query::<(&Display, Entity)>()
+        .iter(world)
+        .find_map(|(display, entity)| (entity == parent).then_some(display.id()))
+}
+
+/// With a space spanning all displays, each display tiles its own workspace of
+/// it, and the space events of the OS concern the workspaces of every display.
+#[test]
+fn test_spanning_space_events_cover_every_display() {
+    const SPACE_ID: WorkspaceId = 9;
+    let main_bounds = IRect::new(0, 0, TEST_DISPLAY_WIDTH, TEST_DISPLAY_HEIGHT);
+    let ext_bounds = IRect::new(
+        TEST_DISPLAY_WIDTH,
+        0,
+        TEST_DISPLAY_WIDTH + EXT_DISPLAY_WIDTH,
+        EXT_DISPLAY_HEIGHT,
+    );
+    let workspaces = |display_id, spaces: &[WorkspaceId]| {
+        spaces
+            .iter()
+            .map(|space_id| spanning_workspace(display_id, *space_id))
+            .collect::<Vec<_>>()
+    };
+
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::SpaceCreated { space_id: SPACE_ID },
+        Event::SpaceDestroyed { space_id: SPACE_ID },
+    ];
+
+    TestHarness::new()
+        .with_spanning_spaces()
+        .with_display(
+            TEST_DISPLAY_ID,
+            main_bounds,
+            workspaces(TEST_DISPLAY_ID, &[TEST_WORKSPACE_ID]),
+        )
+        .with_display(
+            EXT_DISPLAY_ID,
+            ext_bounds,
+            workspaces(EXT_DISPLAY_ID, &[TEST_WORKSPACE_ID]),
+        )
+        .on_iteration(0, move |_world, mut state| {
+            let spaces = [TEST_WORKSPACE_ID, SPACE_ID];
+            state.add_display(
+                TEST_DISPLAY_ID,
+                main_bounds,
+                workspaces(TEST_DISPLAY_ID, &spaces),
+            );
+            state.add_display(
+                EXT_DISPLAY_ID,
+                ext_bounds,
+                workspaces(EXT_DISPLAY_ID, &spaces),
+            );
+        })
+        .on_iteration(1, move |world, mut state| {
+            for display_id in [TEST_DISPLAY_ID, EXT_DISPLAY_ID] {
+                let workspace_id = spanning_workspace(display_id, SPACE_ID);
+                assert_eq!(strip_display(world, workspace_id), Some(display_id));
+            }
+            let spaces = [TEST_WORKSPACE_ID];
+            state.add_display(
+                TEST_DISPLAY_ID,
+                main_bounds,
+                workspaces(TEST_DISPLAY_ID, &spaces),
+            );
+            state.add_display(
+                EXT_DISPLAY_ID,
+                ext_bounds,
+                workspaces(EXT_DISPLAY_ID, &spaces),
+            );
+        })
+        .on_iteration(2, |world, _state| {
+            for display_id in [TEST_DISPLAY_ID, EXT_DISPLAY_ID] {
+                let workspace_id = spanning_workspace(display_id, SPACE_ID);
+                assert_eq!(strip_display(world, workspace_id), None);
+                assert_eq!(
+                    strip_display(world, spanning_workspace(display_id, TEST_WORKSPACE_ID)),
+                    Some(display_id)
+                );
+            }
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_dock_change_retiles_workspace() {
+    const DOCK_HEIGHT: i32 = 100;
+
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    TestHarness::new()
+        .with_windows(1)
+        .on_iteration(1, |world, _| {
+            let mut query = world.query_filtered::<Entity, With<Display>>();
+            let display = query.single(world).expect("should have one display");
+            world
+                .entity_mut(display)
+                .insert(DockPosition::Bottom(DOCK_HEIGHT));
+        })
+        .on_iteration(3, |world, _| {
+            let height = TEST_DISPLAY_HEIGHT - TEST_MENUBAR_HEIGHT - DOCK_HEIGHT;
+            assert_window_size!(world, 0, TEST_WINDOW_WIDTH, height);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_hidden_menubar_releases_top_offset() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    TestHarness::new()
+        .with_windows(1)
+        .on_iteration(1, |world, _| {
+            let mut query = world.query::<&mut Display>();
+            let mut display = query.single_mut(world).expect("should have one display");
+            display.set_menubar_hidden(true);
+        })
+        .on_iteration(3, |world, _| {
+            assert_window_at!(world, 0, 0, 0);
+            assert_window_size!(world, 0, TEST_WINDOW_WIDTH, TEST_DISPLAY_HEIGHT);
+        })
+        .run(commands);
+}
+
+/// Empty spaces are closed, but not the ones holding windows paneru leaves
+/// alone, as they are not tiled.
+#[test]
+fn test_close_empty_spaces_keeps_unmanaged_windows() {
+    const EMPTY_SPACE: WorkspaceId = 3;
+    const IGNORED_SPACE: WorkspaceId = 4;
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    let config: Config = (
+        MainOptions {
+            close_empty_spaces: Some(true),
+            empty_space_grace_ms: Some(0),
+            keep_empty_spaces: Some(0),
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+    let mut harness = TestHarness::new().with_config(config).with_windows(1);
+    harness.mock_state.add_display(
+        TEST_DISPLAY_ID,
+        IRect::new(0, 0, TEST_DISPLAY_WIDTH, TEST_DISPLAY_HEIGHT),
+        vec![TEST_WORKSPACE_ID, IGNORED_SPACE, EMPTY_SPACE],
+    );
+    // The window of a process without an application is never managed.
+    let origin = Origin::new(0, 0);
+    harness.mock_state.spawn_window(
+        TEST_PROCESS_ID + 1,
+        IGNORED_SPACE,
+        50,
+        IRect::from_corners(
+            origin,
+            origin + Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT),
+        ),
+    );
+
+    harness
+        .on_iteration(4, |_, state| {
+            assert_eq!(
+                state.display_spaces(TEST_DISPLAY_ID),
+                vec![TEST_WORKSPACE_ID, IGNORED_SPACE]
+            );
+        })
+        .run(commands);
+}
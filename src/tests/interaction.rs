@@ -3,11 +3,11 @@ use std::sync::Arc;
 use bevy::prelude::*;
 use objc2_core_foundation::CGPoint;
 
-use crate::commands::{Command, Direction, MoveFocus, Operation};
-use crate::config::{Config, MainOptions, WindowParams};
+use crate::commands::{Command, Direction, InsertionPlacement, MoveFocus, Operation};
+use crate::config::{Config, MainOptions, OverflowColumn, WindowParams};
 use crate::ecs::display::FloatingLayer;
 use crate::ecs::{ActiveWorkspaceMarker, Position, Unmanaged, layout::LayoutStrip};
-use crate::ecs::{RepositionMarker, SpawnWindowTrigger};
+use crate::ecs::{InsertionPoint, LayoutLocked, RepositionMarker, SpawnWindowTrigger};
 use crate::events::Event;
 use crate::manager::{Origin, Size, Window};
 use crate::platform::Modifiers;
@@ -57,6 +57,117 @@ fn test_dont_focus() {
         .run(commands);
 }
 
+#[test]
+fn test_prevent_focus_steal() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    let mut params = WindowParams::new(".*", None);
+    params.prevent_focus_steal = Some(true);
+    params.index = Some(100);
+    let config: Config = (MainOptions::default(), vec![params]).into();
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(3)
+        .on_iteration(1, move |world, state| {
+            let origin = Origin::new(0, 0);
+            let size = Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT);
+            let frame = IRect::from_corners(origin, origin + size);
+            let window = state.spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, 3, frame);
+            world.trigger(SpawnWindowTrigger(vec![window]));
+        })
+        .on_iteration(2, |_world, state| {
+            // The app hands the focus to its new window right after opening it.
+            state.focus_window(3);
+        })
+        .on_iteration(4, |world, _state| {
+            assert_focused!(world, 0);
+            assert_window_at!(world, 0, 0, TEST_MENUBAR_HEIGHT);
+        })
+        .run(commands);
+}
+
+/// Focusing the guarded window on purpose drops its guard, so the focus stays.
+#[test]
+fn test_focus_command_drops_focus_steal_guard() {
+    let print = || Event::Command {
+        command: Command::PrintState,
+    };
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        print(),
+        print(),
+        print(),
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::Last)),
+        },
+        print(),
+    ];
+
+    let mut params = WindowParams::new(".*", None);
+    params.prevent_focus_steal = Some(true);
+    params.index = Some(100);
+    let config: Config = (MainOptions::default(), vec![params]).into();
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(3)
+        .on_iteration(1, move |world, state| {
+            let origin = Origin::new(0, 0);
+            let size = Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT);
+            let frame = IRect::from_corners(origin, origin + size);
+            let window = state.spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, 3, frame);
+            world.trigger(SpawnWindowTrigger(vec![window]));
+        })
+        .on_iteration(2, |_world, state| {
+            state.focus_window(3);
+        })
+        .on_iteration(3, |world, _state| {
+            assert_focused!(world, 0);
+        })
+        .on_iteration(5, |world, _state| {
+            assert_focused!(world, 3);
+        })
+        .run(commands);
+}
+
+/// A click on the zoom button of a tiled window toggles its full width, and a
+/// second click brings back its width.
+#[test]
+fn test_zoom_button_toggles_full_width() {
+    let height = TEST_DISPLAY_HEIGHT - TEST_MENUBAR_HEIGHT;
+    let point = CGPoint::new(60.0, f64::from(TEST_MENUBAR_HEIGHT + 10));
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::ZoomButtonClicked { point },
+        Event::ZoomButtonClicked { point },
+    ];
+
+    TestHarness::new()
+        .with_windows(3)
+        .on_iteration(1, move |world, _state| {
+            assert_window_size!(world, 0, TEST_DISPLAY_WIDTH, height);
+        })
+        .on_iteration(2, move |world, _state| {
+            assert_window_size!(world, 0, TEST_WINDOW_WIDTH, height);
+        })
+        .run(commands);
+}
+
 #[test]
 fn test_offscreen_windows_preserve_height() {
     let expected_height = TEST_DISPLAY_HEIGHT - TEST_MENUBAR_HEIGHT;
@@ -269,7 +380,7 @@ fn test_window_swap_brings_focused_into_view() {
             command: Command::PrintState,
         },
         Event::Command {
-            command: Command::Window(Operation::Center),
+            command: Command::Window(Operation::Center(None)),
         },
         Event::Command {
             command: Command::Window(Operation::Swap(Direction::Last)),
@@ -347,6 +458,170 @@ fn test_window_swap_keeps_strip_when_in_view() {
         .run(commands);
 }
 
+#[test]
+fn test_window_promote_and_demote() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::Last)),
+        },
+        Event::Command {
+            command: Command::Window(Operation::Promote),
+        },
+        Event::Command {
+            command: Command::Window(Operation::Demote),
+        },
+    ];
+
+    let config: Config = (
+        MainOptions {
+            animation_speed: Some(10000.0),
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(2)
+        .on_iteration(2, |world, _state| {
+            assert_window_at!(world, 1, 0, TEST_MENUBAR_HEIGHT);
+            assert_window_at!(world, 0, TEST_WINDOW_WIDTH, TEST_MENUBAR_HEIGHT);
+            assert_focused!(world, 1);
+        })
+        .on_iteration(3, |world, _state| {
+            assert_window_at!(world, 0, 0, TEST_MENUBAR_HEIGHT);
+            assert_window_at!(world, 1, TEST_WINDOW_WIDTH, TEST_MENUBAR_HEIGHT);
+            assert_focused!(world, 1);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_window_focus_and_move_index() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::Window(Operation::FocusIndex(2)),
+        },
+        Event::Command {
+            command: Command::Window(Operation::MoveIndex(0)),
+        },
+        Event::Command {
+            command: Command::Window(Operation::FocusIndex(8)),
+        },
+    ];
+
+    let config: Config = (
+        MainOptions {
+            animation_speed: Some(10000.0),
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(3)
+        .on_iteration(1, |world, _state| {
+            assert_focused!(world, 2);
+        })
+        .on_iteration(2, |world, _state| {
+            assert_eq!(
+                strip_columns(world, TEST_WORKSPACE_ID),
+                vec![vec![2], vec![0], vec![1]]
+            );
+            assert_focused!(world, 2);
+        })
+        .on_iteration(3, |world, _state| {
+            // Past the end of the strip, the last column is focused.
+            assert_focused!(world, 1);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_focus_visible_wraps_among_columns_on_screen() {
+    let focus_visible = |direction| Event::Command {
+        command: Command::Window(Operation::FocusVisible(direction)),
+    };
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::First)),
+        }, // 1
+        focus_visible(Direction::East), // 2
+        focus_visible(Direction::East), // 3
+        focus_visible(Direction::East), // 4
+        focus_visible(Direction::West), // 5
+    ];
+
+    TestHarness::new()
+        .with_windows(5)
+        .on_iteration(1, |world, _state| {
+            assert_focused!(world, 0);
+        })
+        .on_iteration(2, |world, _state| {
+            assert_focused!(world, 1);
+        })
+        .on_iteration(3, |world, _state| {
+            // The partly hidden column is scrolled into view.
+            assert_focused!(world, 2);
+            assert_window_at!(
+                world,
+                2,
+                TEST_DISPLAY_WIDTH - TEST_WINDOW_WIDTH,
+                TEST_MENUBAR_HEIGHT
+            );
+        })
+        .on_iteration(4, |world, _state| {
+            // The columns beyond the edge are skipped, wrapping to the first one.
+            assert_focused!(world, 0);
+            assert_window_at!(world, 0, 0, TEST_MENUBAR_HEIGHT);
+        })
+        .on_iteration(5, |world, _state| {
+            assert_focused!(world, 2);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_float_toggle_restores_column() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::Window(Operation::Manage),
+        },
+        Event::Command {
+            command: Command::Window(Operation::Manage),
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    let config: Config = (
+        MainOptions {
+            animation_speed: Some(10000.0),
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(3)
+        .on_iteration(3, |world, _state| {
+            assert_window_at!(world, 0, 0, TEST_MENUBAR_HEIGHT);
+            assert_window_at!(world, 1, TEST_WINDOW_WIDTH, TEST_MENUBAR_HEIGHT);
+            assert_focused!(world, 0);
+        })
+        .run(commands);
+}
+
 #[test]
 fn test_rapid_focus_not_swallowed() {
     let mut harness = TestHarness::new().with_windows(5);
@@ -1364,63 +1639,174 @@ fn test_reshuffle_leftmost_pins_strip_to_left_edge_with_stale_frame() {
     );
 }
 
-/// With `virtual_workspace_animations = true`, switching away from a scrolled
-/// strip and back must restore its saved scroll position, not reset it.
-///
-/// Regression: the animated restore branch of `show_active_workspace` called
-/// `reshuffle_around(focus)` in addition to animating the strip to its saved
-/// origin. That reshuffle read stale mid-animation window frames a frame later
-/// and overwrote the restore target with a different offset, discarding the
-/// saved scroll (the strip jumped back to 0). The animated branch now restores
-/// the origin without reshuffling, mirroring the non-animated branch.
+/// With `center_focused_column = "always"`, a reshuffle around a column
+/// scrolls the strip so that the column sits in the middle of the display,
+/// even if that leaves empty space next to the first column.
 #[test]
-fn test_virtual_workspace_switch_preserves_scroll_with_animations() {
-    use crate::ecs::Position;
+fn test_center_focused_column_always() {
+    use crate::config::CenterFocusedColumn;
+    use crate::ecs::ReshuffleAroundMarker;
 
     let config: Config = (
         MainOptions {
-            auto_center: Some(false),
+            center_focused_column: Some(CenterFocusedColumn::Always),
             animation_speed: Some(30.0),
-            swipe_gesture_fingers: Some(3),
-            virtual_workspace_animations: Some(true),
             ..Default::default()
         },
         vec![],
     )
         .into();
 
-    // 5 windows @ 400px = 2000px strip on a 1024px display → scrollable.
     let mut h = TestHarness::new().with_config(config).with_windows(5);
-
-    let pump_event = |h: &mut TestHarness, ev: Event| {
-        h.app.world_mut().write_message::<Event>(ev);
-        for _ in 0..14 {
+    let run = |h: &mut TestHarness| {
+        for _ in 0..15 {
             h.app.update();
             for e in h.mock_state.drain_events() {
                 h.app.world_mut().write_message::<Event>(e);
             }
         }
     };
-    let pump = |h: &mut TestHarness, c: Command| pump_event(h, Event::Command { command: c });
-
-    // Boot and scroll the strip off the left edge to a non-zero offset.
-    pump(&mut h, Command::PrintState);
-    pump_event(
-        &mut h,
-        Event::Swipe {
-            delta: 0.4,
-            fingers: 3,
-        },
-    );
+    run(&mut h);
 
-    let strip_x_after_scroll = {
+    let strip_x = |h: &mut TestHarness| {
         let world = h.app.world_mut();
         let mut q = world.query_filtered::<&Position, With<ActiveWorkspaceMarker>>();
-        q.single(world).expect("active strip after scroll").0.x
+        q.single(world).expect("exactly one active strip").0.x
     };
-    assert_ne!(
-        strip_x_after_scroll, 0,
-        "test setup: strip should be scrolled off the left edge, got 0"
+
+    // Windows are 400 wide, so the middle of column `index` is at 400 * index + 200.
+    for index in [0, 2] {
+        let entity = find_window_entity(index, h.app.world_mut());
+        h.app
+            .world_mut()
+            .entity_mut(entity)
+            .insert(ReshuffleAroundMarker);
+        run(&mut h);
+
+        let center = strip_x(&mut h) + 400 * index + 200;
+        assert!(
+            (center - TEST_DISPLAY_WIDTH / 2).abs() <= 1,
+            "column {index} should be centered, its middle is at {center}"
+        );
+    }
+}
+
+/// Holding the focus key requests a reshuffle around the same window every
+/// frame. While the strip still scrolls, the repeated requests must keep its
+/// destination instead of restarting the animation from wherever it got to.
+#[test]
+fn test_repeated_reshuffle_keeps_strip_destination() {
+    use crate::ecs::ReshuffleAroundMarker;
+
+    let config: Config = (
+        MainOptions {
+            auto_center: Some(false),
+            animation_speed: Some(2.0),
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+
+    let mut h = TestHarness::new().with_config(config).with_windows(5);
+    let run = |h: &mut TestHarness, frames: usize| {
+        for _ in 0..frames {
+            h.app.update();
+            for e in h.mock_state.drain_events() {
+                h.app.world_mut().write_message::<Event>(e);
+            }
+        }
+    };
+    run(&mut h, 15);
+
+    let destination = |h: &mut TestHarness| {
+        let world = h.app.world_mut();
+        let mut q =
+            world.query_filtered::<Option<&RepositionMarker>, With<ActiveWorkspaceMarker>>();
+        q.single(world)
+            .expect("exactly one active strip")
+            .map(|marker| marker.0)
+    };
+
+    let rightmost = find_window_entity(4, h.app.world_mut());
+    h.app
+        .world_mut()
+        .entity_mut(rightmost)
+        .insert(ReshuffleAroundMarker);
+    run(&mut h, 1);
+    let target = destination(&mut h).expect("the strip scrolls to the rightmost window");
+
+    for _ in 0..5 {
+        h.app
+            .world_mut()
+            .entity_mut(rightmost)
+            .insert(ReshuffleAroundMarker);
+        run(&mut h, 1);
+        assert_eq!(
+            destination(&mut h),
+            Some(target),
+            "a repeated reshuffle must not move the strip's destination"
+        );
+    }
+}
+
+/// With `virtual_workspace_animations = true`, switching away from a scrolled
+/// strip and back must restore its saved scroll position, not reset it.
+///
+/// Regression: the animated restore branch of `show_active_workspace` called
+/// `reshuffle_around(focus)` in addition to animating the strip to its saved
+/// origin. That reshuffle read stale mid-animation window frames a frame later
+/// and overwrote the restore target with a different offset, discarding the
+/// saved scroll (the strip jumped back to 0). The animated branch now restores
+/// the origin without reshuffling, mirroring the non-animated branch.
+#[test]
+fn test_virtual_workspace_switch_preserves_scroll_with_animations() {
+    use crate::ecs::Position;
+
+    let config: Config = (
+        MainOptions {
+            auto_center: Some(false),
+            animation_speed: Some(30.0),
+            swipe_gesture_fingers: Some(3),
+            virtual_workspace_animations: Some(true),
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+
+    // 5 windows @ 400px = 2000px strip on a 1024px display → scrollable.
+    let mut h = TestHarness::new().with_config(config).with_windows(5);
+
+    let pump_event = |h: &mut TestHarness, ev: Event| {
+        h.app.world_mut().write_message::<Event>(ev);
+        for _ in 0..14 {
+            h.app.update();
+            for e in h.mock_state.drain_events() {
+                h.app.world_mut().write_message::<Event>(e);
+            }
+        }
+    };
+    let pump = |h: &mut TestHarness, c: Command| pump_event(h, Event::Command { command: c });
+
+    // Boot and scroll the strip off the left edge to a non-zero offset.
+    pump(&mut h, Command::PrintState);
+    pump_event(
+        &mut h,
+        Event::Swipe {
+            delta: 0.4,
+            fingers: 3,
+        },
+    );
+
+    let strip_x_after_scroll = {
+        let world = h.app.world_mut();
+        let mut q = world.query_filtered::<&Position, With<ActiveWorkspaceMarker>>();
+        q.single(world).expect("active strip after scroll").0.x
+    };
+    assert_ne!(
+        strip_x_after_scroll, 0,
+        "test setup: strip should be scrolled off the left edge, got 0"
     );
 
     // Switch to an empty VW and back.
@@ -1605,3 +1991,745 @@ fn test_stack_unstack_brings_focused_window_into_view() {
         "unstacking must bring the focused window fully back into view"
     );
 }
+
+#[test]
+fn test_vertical_focus_within_stack() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::East)),
+        },
+        Event::Command {
+            command: Command::Window(Operation::Stack(true)),
+        }, // 2
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::North)),
+        }, // 3
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::North)),
+        }, // 4
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::South)),
+        }, // 5
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::South)),
+        }, // 6
+    ];
+
+    TestHarness::new()
+        .with_windows(3)
+        .on_iteration(2, |world, _state| {
+            assert_focused!(world, 1);
+        })
+        .on_iteration(3, |world, _state| {
+            assert_focused!(world, 0);
+        })
+        .on_iteration(4, |world, _state| {
+            // Already at the top of the stack.
+            assert_focused!(world, 0);
+        })
+        .on_iteration(5, |world, _state| {
+            assert_focused!(world, 1);
+        })
+        .on_iteration(6, |world, _state| {
+            assert_focused!(world, 1);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_last_window_hands_focus_to_desktop() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    TestHarness::new()
+        .with_windows(1)
+        .on_iteration(0, |world, state| {
+            assert_focused!(world, 0);
+            assert!(!state.desktop_focused());
+            state.os_minimize_window(0, true);
+        })
+        .on_iteration(2, |_world, state| {
+            assert!(state.desktop_focused());
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_center_floating_window() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::East)),
+        },
+        Event::Command {
+            command: Command::Window(Operation::Manage),
+        },
+        Event::Command {
+            command: Command::Window(Operation::Center(None)),
+        }, // 3
+        Event::Command {
+            command: Command::Window(Operation::Center(Some(5))),
+        }, // 4
+    ];
+
+    let expected_origin = |world: &mut World| {
+        let mut query = world.query::<&Window>();
+        let window = query.iter(world).find(|window| window.id() == 1).unwrap();
+        let size = window.frame().size();
+        let center_y = (TEST_MENUBAR_HEIGHT + TEST_DISPLAY_HEIGHT) / 2;
+        (
+            TEST_DISPLAY_WIDTH / 2 - size.x / 2,
+            (center_y - size.y / 2).max(TEST_MENUBAR_HEIGHT),
+        )
+    };
+
+    let config: Config = (
+        MainOptions {
+            animation_speed: Some(10000.0),
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(3)
+        .on_iteration(3, move |world, _state| {
+            let (x, y) = expected_origin(world);
+            assert_window_at!(world, 1, x, y);
+        })
+        .on_iteration(4, move |world, _state| {
+            // There is no fifth display, so the window stays put.
+            let (x, y) = expected_origin(world);
+            assert_window_at!(world, 1, x, y);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_floating_window_frame_tracks_os_changes() {
+    use crate::ecs::Bounds;
+
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::East)),
+        },
+        Event::Command {
+            command: Command::Window(Operation::Manage),
+        }, // 2
+        Event::Command {
+            command: Command::PrintState,
+        }, // 3
+    ];
+
+    let moved = Origin::new(150, 120);
+    let resized = Size::new(320, 240);
+
+    TestHarness::new()
+        .with_windows(3)
+        .on_iteration(2, move |_world, state| {
+            // Dragging the left edge moves and resizes the window at once.
+            state.os_move_window(1, moved);
+            state.os_resize_window(1, resized);
+        })
+        .on_iteration(3, move |world, _state| {
+            let entity = find_window_entity(1, world);
+            assert!(matches!(
+                world.get::<Unmanaged>(entity),
+                Some(Unmanaged::Floating)
+            ));
+            assert_eq!(world.get::<Position>(entity).unwrap().0, moved);
+            assert_eq!(world.get::<Bounds>(entity).unwrap().0, resized);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_close_others_closes_only_windows_marked_by_a_rule() {
+    let commands = vec![
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::First)),
+        }, // 0
+        Event::Command {
+            command: Command::Window(Operation::CloseOthers),
+        }, // 1
+    ];
+
+    let mut params = WindowParams::new("^Window 2$", None);
+    params.close_with_others = Some(true);
+    let config: Config = (MainOptions::default(), vec![params]).into();
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(4)
+        .on_iteration(1, |world, _| {
+            let mut focused = world.query_filtered::<&Window, With<crate::ecs::FocusedMarker>>();
+            let focused_id = focused
+                .single(world)
+                .expect("a window should be focused")
+                .id();
+            let mut remaining = world
+                .query::<&Window>()
+                .iter(world)
+                .map(|window| window.id())
+                .collect::<Vec<_>>();
+            remaining.sort_unstable();
+            let expected = (0..4)
+                .filter(|&id| id != 2 || id == focused_id)
+                .collect::<Vec<_>>();
+            assert_eq!(remaining, expected);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_insertion_point_stacks_next_window() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 }, // 0
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::First)),
+        }, // 1
+        Event::Command {
+            command: Command::Window(Operation::SetInsertionPoint(InsertionPlacement::Stack)),
+        }, // 2
+        Event::Command {
+            command: Command::PrintState,
+        }, // 3
+    ];
+
+    TestHarness::new()
+        .with_windows(3)
+        .on_iteration(2, |world, state| {
+            assert!(world.contains_resource::<InsertionPoint>());
+            let origin = Origin::new(0, 0);
+            let size = Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT);
+            let frame = IRect::from_corners(origin, origin + size);
+            let window = state.spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, 3, frame);
+            world.trigger(SpawnWindowTrigger(vec![window]));
+        })
+        .on_iteration(3, |world, _| {
+            assert!(!world.contains_resource::<InsertionPoint>());
+            let marked = find_window_entity(0, world);
+            let spawned = find_window_entity(3, world);
+            let strip = world
+                .query_filtered::<&LayoutStrip, With<ActiveWorkspaceMarker>>()
+                .single(world)
+                .expect("getting layout strip");
+            assert_eq!(strip.len(), 3);
+            assert_eq!(strip.index_of(spawned).ok(), Some(0));
+            assert_eq!(strip.index_of(marked).ok(), Some(0));
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_layout_lock_freezes_strip() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 }, // 0
+        Event::Command {
+            command: Command::ToggleLayoutLock,
+        }, // 1
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::Last)),
+        }, // 2
+        Event::Command {
+            command: Command::ToggleLayoutLock,
+        }, // 3
+    ];
+
+    let offscreen_right = TEST_DISPLAY_WIDTH - 5;
+
+    TestHarness::new()
+        .with_windows(5)
+        .on_iteration(1, |world, state| {
+            let origin = Origin::new(0, 0);
+            let size = Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT);
+            let frame = IRect::from_corners(origin, origin + size);
+            let window = state.spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, 5, frame);
+            world.trigger(SpawnWindowTrigger(vec![window]));
+        })
+        .on_iteration(2, move |world, _| {
+            // Focusing the last column does not scroll the locked strip.
+            assert_window_at!(world, 0, 0, TEST_MENUBAR_HEIGHT);
+            assert_window_at!(world, 4, offscreen_right, TEST_MENUBAR_HEIGHT);
+
+            let spawned = find_window_entity(5, world);
+            let (strip, locked) = world
+                .query_filtered::<(&LayoutStrip, Has<LayoutLocked>), With<ActiveWorkspaceMarker>>()
+                .single(world)
+                .expect("getting layout strip");
+            assert!(locked);
+            // New windows are still tracked, appended after the existing columns.
+            assert_eq!(strip.index_of(spawned).ok(), Some(5));
+        })
+        .on_iteration(3, |world, _| {
+            let locked = world
+                .query_filtered::<Has<LayoutLocked>, With<ActiveWorkspaceMarker>>()
+                .single(world)
+                .expect("getting layout strip");
+            assert!(!locked);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_inspect_describes_clicked_window() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let commands = vec![
+        Event::InspectRequest { respond_to: tx },
+        // The mock window manager finds window 0 under any point.
+        Event::InspectClick {
+            point: CGPoint::new(10.0, 10.0),
+        },
+    ];
+
+    let params = WindowParams::new("^Window 0$", None);
+    let config: Config = (MainOptions::default(), vec![params]).into();
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(2)
+        .on_iteration(0, |_, _| {
+            crate::platform::input::set_inspecting(false);
+        })
+        .run(commands);
+
+    let report = rx
+        .try_recv()
+        .expect("inspect should answer after the click");
+    assert!(report.contains("window_id: 0\n"), "{report}");
+    assert!(report.contains("title: Window 0\n"), "{report}");
+    assert!(report.contains("rules: param0\n"), "{report}");
+}
+
+#[test]
+fn test_presentation_app_suspends_management() {
+    use crate::ecs::presentation::PresentationMode;
+    use crate::platform::ProcessSerialNumber;
+
+    const OTHER_PROCESS_ID: i32 = 2;
+    let psn = |pid: i32| ProcessSerialNumber {
+        high: 0,
+        low: pid.cast_unsigned(),
+    };
+
+    let config: Config = (
+        MainOptions {
+            presentation_apps: vec!["test".to_string()],
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+    let commands = vec![
+        Event::ApplicationFrontSwitched {
+            psn: psn(TEST_PROCESS_ID),
+        },
+        Event::ApplicationFrontSwitched {
+            psn: psn(OTHER_PROCESS_ID),
+        },
+    ];
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(2)
+        .with_app(OTHER_PROCESS_ID, "other", "OtherApp", |_| {})
+        .on_iteration(0, move |world, _| {
+            assert_eq!(
+                world.get_resource::<PresentationMode>(),
+                Some(&PresentationMode::App(psn(TEST_PROCESS_ID)))
+            );
+        })
+        .on_iteration(1, |world, _| {
+            assert!(world.get_resource::<PresentationMode>().is_none());
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_presentation_app_hands_over_to_another() {
+    use crate::ecs::presentation::PresentationMode;
+    use crate::platform::ProcessSerialNumber;
+
+    const OTHER_PROCESS_ID: i32 = 2;
+    let psn = |pid: i32| ProcessSerialNumber {
+        high: 0,
+        low: pid.cast_unsigned(),
+    };
+
+    let config: Config = (
+        MainOptions {
+            presentation_apps: vec!["test".to_string(), "other".to_string()],
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+    let commands = vec![
+        Event::ApplicationFrontSwitched {
+            psn: psn(TEST_PROCESS_ID),
+        },
+        Event::ApplicationFrontSwitched {
+            psn: psn(OTHER_PROCESS_ID),
+        },
+        Event::ApplicationTerminated {
+            psn: psn(TEST_PROCESS_ID),
+        },
+        Event::ApplicationTerminated {
+            psn: psn(OTHER_PROCESS_ID),
+        },
+    ];
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(2)
+        .with_app(OTHER_PROCESS_ID, "other", "OtherApp", |_| {})
+        .on_iteration(1, move |world, _| {
+            assert_eq!(
+                world.get_resource::<PresentationMode>(),
+                Some(&PresentationMode::App(psn(OTHER_PROCESS_ID)))
+            );
+        })
+        .on_iteration(2, move |world, _| {
+            // The first presenter quitting in the background changes nothing.
+            assert_eq!(
+                world.get_resource::<PresentationMode>(),
+                Some(&PresentationMode::App(psn(OTHER_PROCESS_ID)))
+            );
+        })
+        .on_iteration(3, |world, _| {
+            assert!(world.get_resource::<PresentationMode>().is_none());
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_session_switch_restores_presentation() {
+    use crate::ecs::presentation::PresentationMode;
+    use crate::platform::ProcessSerialNumber;
+
+    let psn = ProcessSerialNumber {
+        high: 0,
+        low: TEST_PROCESS_ID.cast_unsigned(),
+    };
+    let config: Config = (
+        MainOptions {
+            presentation_apps: vec!["test".to_string()],
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+    let commands = vec![
+        Event::ApplicationFrontSwitched { psn },
+        Event::SessionResigned,
+        Event::SessionActivated,
+    ];
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(2)
+        .on_iteration(1, |world, _| {
+            assert_eq!(
+                world.get_resource::<PresentationMode>(),
+                Some(&PresentationMode::SessionInactive)
+            );
+        })
+        .on_iteration(2, move |world, _| {
+            // The presentation is still running when the user comes back.
+            assert_eq!(
+                world.get_resource::<PresentationMode>(),
+                Some(&PresentationMode::App(psn))
+            );
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_pause_and_resume() {
+    use crate::ecs::presentation::PresentationMode;
+
+    let commands = vec![
+        Event::Command {
+            command: Command::Pause(true),
+        },
+        Event::Command {
+            command: Command::Window(Operation::Swap(Direction::East)),
+        },
+        Event::Command {
+            command: Command::Resume,
+        },
+    ];
+
+    TestHarness::new()
+        .with_windows(2)
+        .on_iteration(0, |world, _| {
+            assert_eq!(
+                world.get_resource::<PresentationMode>(),
+                Some(&PresentationMode::Paused {
+                    keep_bindings: true
+                })
+            );
+            assert_window_at!(world, 0, 0, TEST_MENUBAR_HEIGHT);
+            assert_window_at!(world, 1, TEST_WINDOW_WIDTH, TEST_MENUBAR_HEIGHT);
+        })
+        .on_iteration(1, |world, _| {
+            // The swap waits for the resume, the windows stay where they are.
+            assert_window_at!(world, 0, 0, TEST_MENUBAR_HEIGHT);
+            assert_window_at!(world, 1, TEST_WINDOW_WIDTH, TEST_MENUBAR_HEIGHT);
+        })
+        .on_iteration(2, |world, _| {
+            assert!(world.get_resource::<PresentationMode>().is_none());
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_session_switch_keeps_pause() {
+    use crate::ecs::presentation::PresentationMode;
+
+    let commands = vec![
+        Event::Command {
+            command: Command::Pause(false),
+        },
+        Event::SessionResigned,
+        Event::SessionActivated,
+    ];
+
+    TestHarness::new()
+        .with_windows(2)
+        .on_iteration(1, |world, _| {
+            assert_eq!(
+                world.get_resource::<PresentationMode>(),
+                Some(&PresentationMode::Paused {
+                    keep_bindings: false
+                })
+            );
+        })
+        .on_iteration(2, |world, _| {
+            assert_eq!(
+                world.get_resource::<PresentationMode>(),
+                Some(&PresentationMode::Paused {
+                    keep_bindings: false
+                })
+            );
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_overflowing_strip_pages_to_next_virtual_workspace() {
+    let commands = vec![
+        Event::Command {
+            command: Command::PrintState,
+        };
+        3
+    ];
+    let config: Config = (
+        MainOptions {
+            strip_max_columns: Some(2),
+            strip_overflow_column: Some(OverflowColumn::Last),
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(3)
+        .on_iteration(2, |world, _| {
+            assert_eq!(
+                strip_columns(world, TEST_WORKSPACE_ID),
+                vec![vec![0], vec![1]]
+            );
+
+            let moved = find_window_entity(2, world);
+            let mut query = world.query::<&LayoutStrip>();
+            let next = query
+                .iter(world)
+                .find(|strip| strip.virtual_index == 1)
+                .expect("a strip for the overflowing column");
+            assert_eq!(next.len(), 1);
+            assert!(next.contains(moved));
+        })
+        .run(commands);
+}
+
+/// Sending a window with `MoveFocus::Stay` to a full hidden strip pages that
+/// strip along, not only the active one.
+#[test]
+fn test_window_sent_to_full_strip_pages_it() {
+    let mut commands = vec![
+        Event::Command {
+            command: Command::PrintState,
+        };
+        3
+    ];
+    commands.push(Event::Command {
+        command: Command::Window(Operation::Focus(Direction::First)),
+    });
+    commands.push(Event::Command {
+        command: Command::Window(Operation::VirtualMoveNumber(1, MoveFocus::Stay)),
+    });
+    commands.push(Event::Command {
+        command: Command::PrintState,
+    });
+    let config: Config = (
+        MainOptions {
+            strip_max_columns: Some(2),
+            strip_overflow_column: Some(OverflowColumn::Last),
+            ..Default::default()
+        },
+        vec![],
+    )
+        .into();
+
+    let strip_lengths = |world: &mut World| {
+        let mut lengths = world
+            .query::<&LayoutStrip>()
+            .iter(world)
+            .map(|strip| (strip.virtual_index, strip.len()))
+            .collect::<Vec<_>>();
+        lengths.sort_unstable();
+        lengths
+    };
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(4)
+        .on_iteration(2, move |world, _| {
+            assert_eq!(strip_lengths(world), vec![(0, 2), (1, 2)]);
+        })
+        .on_iteration(5, move |world, _| {
+            assert_eq!(strip_lengths(world), vec![(0, 1), (1, 2), (2, 1)]);
+        })
+        .run(commands);
+}
+
+/// An app which never sends `AXCreated` has its unannounced window picked up
+/// from its window list, and is polled for the next ones from then on.
+#[test]
+fn test_window_list_polling_finds_unannounced_windows() {
+    use crate::ecs::polling::BrokenNotifications;
+    use crate::manager::Application;
+
+    let mut h = TestHarness::new().with_windows(2);
+    h.mock_state
+        .update_app(TEST_PROCESS_ID, |app| app.lists_windows = true);
+    let run = |h: &mut TestHarness, frames: usize| {
+        for _ in 0..frames {
+            h.app.update();
+            for e in h.mock_state.drain_events() {
+                h.app.world_mut().write_message::<Event>(e);
+            }
+        }
+    };
+    run(&mut h, 10);
+
+    let frame = IRect::new(0, 0, TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT);
+    let window_known = |h: &mut TestHarness, id| {
+        let world = h.app.world_mut();
+        let mut query = world.query::<&Window>();
+        query.iter(world).any(|window| window.id() == id)
+    };
+
+    // Two polls pass before the window is considered unannounced.
+    h.mock_state
+        .spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, 7, frame);
+    run(&mut h, 70);
+    assert!(window_known(&mut h, 7), "the unannounced window is managed");
+    let world = h.app.world_mut();
+    let mut query = world.query_filtered::<&Application, With<BrokenNotifications>>();
+    assert_eq!(query.iter(world).count(), 1, "the app is flagged");
+
+    // A flagged app has its next window spawned by the following poll.
+    h.mock_state
+        .spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, 8, frame);
+    run(&mut h, 35);
+    assert!(window_known(&mut h, 8));
+}
+
+/// Losing the Accessibility permission pauses the window management, and
+/// getting it back restarts the daemon in place to catch up on the changes.
+#[test]
+fn test_accessibility_loss_pauses_and_resumes() {
+    use crate::ecs::AccessibilityLost;
+    use crate::ecs::state::PaneruState;
+    use bevy::ecs::message::Messages;
+
+    let mut h = TestHarness::new().with_windows(2);
+    // Runs the frames and returns whether a restart of the daemon was requested.
+    let run = |h: &mut TestHarness, frames: usize| {
+        let mut restart = false;
+        for _ in 0..frames {
+            h.app.update();
+            restart |= h
+                .app
+                .world()
+                .resource::<Messages<Event>>()
+                .iter_current_update_messages()
+                .any(|event| {
+                    matches!(
+                        event,
+                        Event::Command {
+                            command: Command::RestartDaemon
+                        }
+                    )
+                });
+            for e in h.mock_state.drain_events() {
+                h.app.world_mut().write_message::<Event>(e);
+            }
+        }
+        restart
+    };
+    run(&mut h, 10);
+    assert!(h.app.world().get_resource::<AccessibilityLost>().is_none());
+
+    h.mock_state.set_trusted(false);
+    assert!(!run(&mut h, 25));
+    assert!(h.app.world().get_resource::<AccessibilityLost>().is_some());
+
+    h.mock_state.set_trusted(true);
+    assert!(run(&mut h, 25), "the daemon restarts once trusted again");
+    assert!(h.app.world().get_resource::<AccessibilityLost>().is_none());
+    _ = std::fs::remove_file(PaneruState::handoff_file_path());
+}
+
+#[test]
+fn test_focus_goes_to_open_sheet() {
+    let commands = vec![
+        Event::WindowFocused { window_id: 1 },
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::West)),
+        },
+    ];
+
+    let mut params = WindowParams::new("^Save$", None);
+    params.floating = Some(true);
+    let config: Config = (MainOptions::default(), vec![params]).into();
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(2)
+        .with_window(10, |window| {
+            window.title = "Save".to_string();
+            window.role = "AXSheet".to_string();
+            window.child_role = true;
+            window.parent = Some(0);
+        })
+        .on_iteration(1, |world, _| {
+            // The sheet of the window on the left takes the focus in its place.
+            assert_focused!(world, 10);
+        })
+        .run(commands);
+}
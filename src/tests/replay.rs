@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use crate::ecs::recorder::read_recording;
+
+use super::*;
+
+#[test]
+fn test_replay_recording() {
+    let recording = [
+        r#"{"offset_ms":0,"event":"window_created","window_id":10}"#,
+        r#"{"offset_ms":40,"event":"window_created","window_id":11}"#,
+        r#"{"offset_ms":300,"event":"window_focused","window_id":10}"#,
+        "",
+        r#"{"offset_ms":1200,"event":"window_destroyed","window_id":11}"#,
+        r#"{"offset_ms":1250,"event":"space_changed"}"#,
+    ];
+    let path =
+        std::env::temp_dir().join(format!("paneru-replay-test-{}.jsonl", std::process::id()));
+    std::fs::write(&path, recording.join("\n")).unwrap();
+    let entries = read_recording(&path).unwrap();
+    _ = std::fs::remove_file(&path);
+    assert_eq!(entries.len(), 5);
+
+    let mut harness = TestHarness::new();
+    harness.replay(entries);
+
+    let world = harness.world();
+    assert_eq!(strip_columns(world, TEST_WORKSPACE_ID), vec![vec![10]]);
+}
+
+/// Replays the recording named by `PANERU_REPLAY`, like `paneru replay`
+/// does: `PANERU_REPLAY=events.jsonl cargo test replay -- --ignored --nocapture`.
+#[test]
+#[ignore = "needs a recording in PANERU_REPLAY"]
+fn test_replay_recording_from_env() {
+    let path = PathBuf::from(std::env::var("PANERU_REPLAY").expect("PANERU_REPLAY is not set"));
+    replay_headless(&path).expect("replaying the recording");
+}
@@ -121,11 +121,11 @@ fn test_startup_restore_replaces_consumed_empty_virtual_zero_strip() {
     let windows = matching[0].all_windows();
     assert_eq!(windows.len(), 2);
     assert!(
-        windows.contains(&crate::tests::harness::find_window_entity(0, world)),
+        windows.contains(&crate::replay::harness::find_window_entity(0, world)),
         "restored row should contain window 0"
     );
     assert!(
-        windows.contains(&crate::tests::harness::find_window_entity(1, world)),
+        windows.contains(&crate::replay::harness::find_window_entity(1, world)),
         "restored row should contain window 1"
     );
 }
@@ -208,7 +208,7 @@ fn test_startup_restore_prefers_existing_workspace_parent_before_saved_or_active
     }
 
     let world = harness.world();
-    let restored_window = crate::tests::harness::find_window_entity(200, world);
+    let restored_window = crate::replay::harness::find_window_entity(200, world);
     let restored_entity = {
         let mut query = world.query::<(Entity, &LayoutStrip)>();
         query
@@ -284,7 +284,7 @@ fn test_startup_restore_preserves_saved_display_when_present() {
 
     harness
         .on_iteration(1, |world, _state| {
-            let restored_window = crate::tests::harness::find_window_entity(300, world);
+            let restored_window = crate::replay::harness::find_window_entity(300, world);
             let parent = restored_strip_display_parent(world, EXT_WORKSPACE_ID, 0, restored_window);
             let display = world
                 .entity(parent)
@@ -529,7 +529,7 @@ fn test_startup_restore_overrides_floating_config_for_matched_window() {
     }
 
     let world = harness.world();
-    let restored_window = crate::tests::harness::find_window_entity(0, world);
+    let restored_window = crate::replay::harness::find_window_entity(0, world);
     assert!(
         world.entity(restored_window).get::<Unmanaged>().is_none(),
         "matched restore windows should not inherit floating config"
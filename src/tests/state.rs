@@ -1,13 +1,15 @@
 use bevy::prelude::*;
 
+use crate::commands::{Command, Direction, Operation};
 use crate::ecs::layout::LayoutStrip;
 use crate::ecs::params::Windows;
 use crate::ecs::restore::CurrentWindowIdentity;
 use crate::ecs::state::{
-    PaneruQueryState, PaneruState, SavedColumn, SavedDisplay, SavedRect, SavedStackItem,
-    SavedStrip, SavedWindow, SavedWorkspace,
+    PaneruColumnKind, PaneruQueryState, PaneruState, PaneruStripState, SavedColumn, SavedDisplay,
+    SavedRect, SavedStackItem, SavedStrip, SavedWindow, SavedWorkspace,
 };
 use crate::ecs::{ActiveDisplayMarker, ActiveWorkspaceMarker};
+use crate::events::Event;
 use crate::manager::{Application, Display};
 use crate::platform::{Pid, ProcessSerialNumber, WinID};
 use crate::tests::{
@@ -134,7 +136,7 @@ fn test_state_restoration() {
 #[test]
 fn test_state_extraction() {
     use crate::ecs::state::SavedColumn;
-    use crate::tests::harness::TestHarness;
+    use crate::replay::harness::TestHarness;
 
     let mut harness = TestHarness::new().with_windows(1);
 
@@ -168,7 +170,7 @@ fn test_state_extraction() {
 
 #[test]
 fn test_state_serializes_display_and_active_virtual_workspace() {
-    use crate::tests::harness::TestHarness;
+    use crate::replay::harness::TestHarness;
 
     let mut harness = TestHarness::new().with_windows(1);
 
@@ -224,7 +226,7 @@ fn test_state_serializes_display_and_active_virtual_workspace() {
 
 #[test]
 fn test_state_extraction_includes_parentless_layout_strips() {
-    use crate::tests::harness::TestHarness;
+    use crate::replay::harness::TestHarness;
 
     let mut harness = TestHarness::new().with_windows(0);
 
@@ -308,6 +310,27 @@ fn test_state_save_is_loadable_from_path() {
     let _ = std::fs::remove_file(path);
 }
 
+#[test]
+fn test_state_take_consumes_file() {
+    let state = PaneruState {
+        version: 2,
+        timestamp: 123_456_789,
+        active_display_id: None,
+        displays: Vec::new(),
+        workspaces: Vec::new(),
+    };
+    let path = unique_state_path("handoff");
+
+    state
+        .save_to_file(&path)
+        .expect("state should save to requested path");
+
+    let taken = PaneruState::take_from_file(&path).expect("handoff state should load");
+    assert_eq!(taken, state);
+    assert!(!path.exists(), "handoff state should only be consumed once");
+    assert!(PaneruState::take_from_file(&path).is_none());
+}
+
 fn unique_state_path(name: &str) -> std::path::PathBuf {
     std::env::temp_dir().join(format!(
         "paneru-{name}-{}-{}.json",
@@ -519,7 +542,7 @@ fn current_window(
 
 #[test]
 fn test_query_state_contract_exposes_active_virtual_workspace_and_windows() {
-    use crate::tests::harness::TestHarness;
+    use crate::replay::harness::TestHarness;
 
     let mut harness = TestHarness::new().with_windows(1);
 
@@ -565,3 +588,63 @@ fn test_query_state_contract_exposes_active_virtual_workspace_and_windows() {
         "test"
     );
 }
+
+#[test]
+fn test_query_strip_lists_columns_left_to_right() {
+    use crate::replay::harness::TestHarness;
+
+    let mut harness = TestHarness::new().with_windows(3);
+    harness.run(vec![
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::East)),
+        },
+        Event::Command {
+            command: Command::Window(Operation::Stack(true)),
+        },
+    ]);
+
+    let world = harness.world();
+    let mut system_state: QueryStateExtractionState<'_, '_> = SystemState::new(world);
+    let (workspaces, displays, windows, apps) = system_state.get(world);
+
+    let strip = PaneruStripState::extract(&workspaces, &displays, &windows, &apps, None);
+
+    assert_eq!(strip.display_id, Some(TEST_DISPLAY_ID));
+    assert_eq!(strip.native_workspace_id, Some(TEST_WORKSPACE_ID));
+    assert_eq!(strip.virtual_workspace_number, Some(1));
+    assert_eq!(strip.columns.len(), 2);
+    for (index, column) in strip.columns.iter().enumerate() {
+        assert_eq!(column.index, index);
+    }
+    assert!(
+        strip.columns[0].windows[0].frame.min_x < strip.columns[1].windows[0].frame.min_x,
+        "columns should be ordered left to right"
+    );
+
+    let stack = strip
+        .columns
+        .iter()
+        .find(|column| column.kind == PaneruColumnKind::Stack)
+        .expect("one column should be a stack");
+    let stack_indices = stack
+        .windows
+        .iter()
+        .map(|window| window.stack_index)
+        .collect::<Vec<_>>();
+    assert_eq!(stack_indices, vec![0, 1]);
+    assert!(stack.focused);
+
+    let focused = strip
+        .columns
+        .iter()
+        .flat_map(|column| &column.windows)
+        .filter(|window| window.focused)
+        .count();
+    assert_eq!(focused, 1);
+    assert_eq!(stack.windows[0].bundle_id, "test");
+    assert_eq!(stack.windows[0].bundle_path, None);
+
+    let json = serde_json::to_value(&strip).expect("strip should serialize");
+    assert_eq!(json["columns"][0]["index"], 0);
+    assert!(json["columns"][0]["windows"][0]["frame"]["min_x"].is_number());
+}
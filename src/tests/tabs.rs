@@ -1,10 +1,10 @@
 use bevy::prelude::*;
 
 use crate::assert_window_size;
-use crate::commands::{Command, MoveFocus, Operation};
+use crate::commands::{Command, Direction, MoveFocus, Operation};
 use crate::config::{Config, MainOptions};
 use crate::ecs::layout::LayoutStrip;
-use crate::ecs::{ActiveWorkspaceMarker, Bounds, SpawnWindowTrigger};
+use crate::ecs::{ActiveWorkspaceMarker, Bounds, NativeTabs, SpawnWindowTrigger};
 use crate::events::Event;
 use crate::platform::WinID;
 
@@ -307,3 +307,45 @@ fn test_same_app_same_frame_native_tab_reuses_existing_column() {
         })
         .run(commands);
 }
+
+#[test]
+fn test_torn_off_tab_lands_next_to_origin() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },         // 0
+        Event::WindowTitleChanged { window_id: 0 }, // 1
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::Last)),
+        }, // 2
+        Event::Command {
+            command: Command::PrintState,
+        }, // 3
+    ];
+
+    TestHarness::new()
+        .with_windows(3)
+        .on_iteration(0, |_, state| {
+            state.update_window(0, |window| window.native_tabs = Some(3));
+        })
+        .on_iteration(2, |world, state| {
+            let origin = find_window_entity(0, world);
+            assert_eq!(world.get::<NativeTabs>(origin), Some(&NativeTabs(3)));
+
+            // Tearing off a tab leaves the origin with one tab less.
+            state.update_window(0, |window| window.native_tabs = Some(2));
+            let origin = IVec2::new(100, 100);
+            let frame = IRect::from_corners(origin, origin + IVec2::new(300, 300));
+            let window = state.spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, 3, frame);
+            world.trigger(SpawnWindowTrigger(vec![window]));
+        })
+        .on_iteration(3, |world, _| {
+            let origin = find_window_entity(0, world);
+            let torn_off = find_window_entity(3, world);
+            let strip = world
+                .query_filtered::<&LayoutStrip, With<ActiveWorkspaceMarker>>()
+                .single(world)
+                .expect("getting layout strip");
+            assert_eq!(strip.index_of(torn_off).ok(), Some(1));
+            assert_eq!(world.get::<NativeTabs>(origin), Some(&NativeTabs(2)));
+        })
+        .run(commands);
+}
@@ -1,7 +1,18 @@
-use crate::commands::{Command, Direction, Operation, ResizeDirection};
+use crate::commands::slots::LayoutSlot;
+use crate::commands::{
+    Command, Direction, Operation, ResizeDirection, parse_expect_request, parse_property_request,
+};
 use crate::config::{Config, MainOptions, WindowParams};
+use crate::ecs::layout::{ColumnOrientation, LayoutStrip};
+use crate::ecs::{
+    FocusedMarker, ReapplyWindowRules, SpawnWindowTrigger, Unmanaged, WindowRuleOutcome,
+};
 use crate::events::Event;
-use crate::{assert_window_at, assert_window_size};
+use crate::manager::{Origin, Size};
+use crate::{
+    assert_focused, assert_not_on_workspace, assert_on_workspace, assert_window_at,
+    assert_window_size,
+};
 use bevy::prelude::*;
 
 use super::*;
@@ -33,7 +44,7 @@ fn test_window_shuffle() {
             command: Command::Window(Operation::Stack(true)),
         }, // 5
         Event::Command {
-            command: Command::Window(Operation::Center),
+            command: Command::Window(Operation::Center(None)),
         }, // 6
         Event::Command {
             command: Command::Window(Operation::Focus(Direction::East)),
@@ -42,7 +53,7 @@ fn test_window_shuffle() {
             command: Command::Window(Operation::Stack(true)),
         }, // 8
         Event::Command {
-            command: Command::Window(Operation::Center),
+            command: Command::Window(Operation::Center(None)),
         }, // 9
         Event::Command {
             command: Command::PrintState,
@@ -207,3 +218,572 @@ fn test_window_resize_grow_and_shrink_cycle() {
         })
         .run(commands);
 }
+
+#[test]
+fn test_reload_applies_changed_window_rules() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    let options = MainOptions {
+        animation_speed: Some(10000.0),
+        ..Default::default()
+    };
+    let config: Config = (options.clone(), vec![]).into();
+
+    TestHarness::new()
+        .with_config(config)
+        .with_windows(3)
+        .on_iteration(1, move |world, _state| {
+            assert_window_at!(world, 2, 2 * TEST_WINDOW_WIDTH, TEST_MENUBAR_HEIGHT);
+
+            // Nothing matched the windows before the reload.
+            let previous = (0..3)
+                .map(|id| (find_window_entity(id, world), WindowRuleOutcome::default()))
+                .collect();
+            let mut params = WindowParams::new("^Window 1$", None);
+            params.floating = Some(true);
+            world.insert_resource::<Config>((options.clone(), vec![params]).into());
+            world.trigger(ReapplyWindowRules(previous));
+        })
+        .on_iteration(3, |world, _state| {
+            let floating = find_window_entity(1, world);
+            assert!(matches!(
+                world.get::<Unmanaged>(floating),
+                Some(Unmanaged::Floating)
+            ));
+            assert_window_at!(world, 0, 0, TEST_MENUBAR_HEIGHT);
+            assert_window_at!(world, 2, TEST_WINDOW_WIDTH, TEST_MENUBAR_HEIGHT);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_builtin_rules_float_utility_windows() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    for disabled in [false, true] {
+        let options = MainOptions {
+            disable_builtin_rules: Some(disabled),
+            ..Default::default()
+        };
+        let config: Config = (options, vec![]).into();
+
+        TestHarness::new()
+            .with_config(config)
+            .with_windows(2)
+            .on_iteration(0, |world, state| {
+                let origin = Origin::new(0, 0);
+                let frame = IRect::from_corners(
+                    origin,
+                    origin + Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT),
+                );
+                let window = state.spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, 2, frame);
+                state.update_window(2, |window| {
+                    window.subrole = "AXSystemFloatingWindow".to_string();
+                });
+                world.trigger(SpawnWindowTrigger(vec![window]));
+            })
+            .on_iteration(1, move |world, _| {
+                let utility = find_window_entity(2, world);
+                assert_eq!(
+                    matches!(world.get::<Unmanaged>(utility), Some(Unmanaged::Floating)),
+                    !disabled
+                );
+            })
+            .run(commands.clone());
+    }
+}
+
+#[test]
+fn test_new_window_opens_right_of_focus() {
+    let commands = vec![
+        Event::WindowFocused { window_id: 1 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    TestHarness::new()
+        .with_windows(3)
+        .on_iteration(0, |world, state| {
+            assert_focused!(world, 1);
+            let origin = Origin::new(0, 0);
+            let frame = IRect::from_corners(
+                origin,
+                origin + Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT),
+            );
+            let window = state.spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, 5, frame);
+            world.trigger(SpawnWindowTrigger(vec![window]));
+        })
+        .on_iteration(1, |world, _| {
+            let columns = strip_columns(world, TEST_WORKSPACE_ID);
+            let column_of = |id| columns.iter().position(|column| column.contains(&id));
+            assert_eq!(columns.len(), 4);
+            assert_eq!(column_of(5), column_of(1).map(|index| index + 1));
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_destroyed_window_leaves_strip() {
+    let commands = vec![
+        Event::WindowFocused { window_id: 1 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    TestHarness::new()
+        .with_windows(3)
+        .on_iteration(0, |_, state| {
+            state.os_destroy_window(1);
+        })
+        .on_iteration(1, |world, _| {
+            let columns = strip_columns(world, TEST_WORKSPACE_ID);
+            assert_eq!(columns.len(), 2);
+            assert!(columns.iter().flatten().all(|id| *id != 1));
+            // Focus moves on to one of the remaining windows.
+            let mut focused =
+                world.query_filtered::<&crate::manager::Window, With<FocusedMarker>>();
+            assert!(focused.single(world).is_ok_and(|window| window.id() != 1));
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_deminimized_window_returns_to_its_space() {
+    const SECOND_SPACE_ID: u64 = TEST_WORKSPACE_ID + 1;
+
+    let commands = vec![
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    let mut harness = TestHarness::new();
+    harness.mock_state.add_display(
+        TEST_DISPLAY_ID,
+        IRect::new(0, 0, TEST_DISPLAY_WIDTH, TEST_DISPLAY_HEIGHT),
+        vec![TEST_WORKSPACE_ID, SECOND_SPACE_ID],
+    );
+
+    // Minimized on another space before paneru started, so it was never tiled.
+    harness
+        .with_windows(2)
+        .with_window(5, |window| {
+            window.workspace_id = SECOND_SPACE_ID;
+            window.minimized = true;
+        })
+        .on_iteration(0, |_, state| {
+            state.os_minimize_window(5, false);
+        })
+        .on_iteration(1, |world, _| {
+            assert_on_workspace!(world, 5, SECOND_SPACE_ID);
+            assert_not_on_workspace!(world, 5, TEST_WORKSPACE_ID);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_set_index_moves_whole_stack() {
+    let (target, request) = parse_property_request(&["window", "1", "set", "index", "2"])
+        .unwrap()
+        .unwrap();
+    let (respond_to, response) = std::sync::mpsc::channel();
+    let commands = vec![
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::First)),
+        },
+        Event::Command {
+            command: Command::Window(Operation::Focus(Direction::East)),
+        },
+        Event::Command {
+            command: Command::Window(Operation::Stack(true)),
+        },
+        Event::WindowPropertyRequest {
+            target,
+            request,
+            respond_to,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    TestHarness::new()
+        .with_windows(4)
+        .on_iteration(4, move |world, _| {
+            assert!(response.try_recv().unwrap().is_ok());
+            let stacked = find_window_entity(0, world);
+            let entity = find_window_entity(1, world);
+            let mut query = world.query::<&LayoutStrip>();
+            let strip = query
+                .iter(world)
+                .find(|strip| strip.contains(entity))
+                .expect("window should be tiled");
+            // The stack moved as one column, instead of losing the window.
+            assert_eq!(strip.len(), 3);
+            assert_eq!(strip.index_of(entity).unwrap(), 2);
+            assert_eq!(strip.index_of(stacked).unwrap(), 2);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_expectation_applies_to_next_matching_window() {
+    let expectation = parse_expect_request(&[
+        "expect",
+        "--title",
+        "^Window [56]$",
+        "--then",
+        "window_set_index 0",
+    ])
+    .unwrap()
+    .unwrap();
+    let commands = vec![
+        Event::ExpectWindow { expectation },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    let spawn = |world: &mut World, state: MockState, window_id| {
+        let origin = Origin::new(0, 0);
+        let frame = IRect::from_corners(
+            origin,
+            origin + Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT),
+        );
+        let window = state.spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, window_id, frame);
+        world.trigger(SpawnWindowTrigger(vec![window]));
+    };
+    let column_of = |world: &mut World, window_id| {
+        let entity = find_window_entity(window_id, world);
+        let mut query = world.query::<&LayoutStrip>();
+        query
+            .iter(world)
+            .find_map(|strip| strip.index_of(entity).ok())
+            .expect("window should be tiled")
+    };
+
+    TestHarness::new()
+        .with_windows(3)
+        .on_iteration(0, move |world, state| spawn(world, state, 5))
+        .on_iteration(1, move |world, state| {
+            assert_eq!(column_of(world, 5), 0);
+            // The expectation was used up, so the next window is placed as usual.
+            spawn(world, state, 6);
+        })
+        .on_iteration(2, move |world, _| {
+            assert_ne!(column_of(world, 6), 0);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_expectation_waits_for_title_and_focus() {
+    let expectation = parse_expect_request(&[
+        "expect",
+        "--title",
+        "^Report$",
+        "--then",
+        "window_swap_first",
+    ])
+    .unwrap()
+    .unwrap();
+    let commands = vec![
+        Event::ExpectWindow { expectation },
+        Event::Command {
+            command: Command::PrintState,
+        },
+        Event::WindowTitleChanged { window_id: 5 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    TestHarness::new()
+        .with_windows(3)
+        .on_iteration(0, |world, state| {
+            let origin = Origin::new(0, 0);
+            let frame = IRect::from_corners(
+                origin,
+                origin + Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT),
+            );
+            let window = state.spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, 5, frame);
+            world.trigger(SpawnWindowTrigger(vec![window]));
+        })
+        .on_iteration(1, |world, state| {
+            // Opened untitled, so the expectation is still waiting.
+            let entity = find_window_entity(5, world);
+            let mut query = world.query::<&LayoutStrip>();
+            let index = query
+                .iter(world)
+                .find_map(|strip| strip.index_of(entity).ok());
+            assert_ne!(index, Some(0));
+
+            state.focus_window(0);
+            state.update_window(5, |window| window.title = "Report".to_string());
+        })
+        .on_iteration(3, |world, _| {
+            // The command ran once the renamed window took the focus back.
+            assert_focused!(world, 5);
+            let entity = find_window_entity(5, world);
+            let mut query = world.query::<&LayoutStrip>();
+            let index = query
+                .iter(world)
+                .find_map(|strip| strip.index_of(entity).ok());
+            assert_eq!(index, Some(0));
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_expectation_expires() {
+    let expectation = parse_expect_request(&[
+        "expect",
+        "--title",
+        "^Window 5$",
+        "--then",
+        "window_set_index 0",
+    ])
+    .unwrap()
+    .unwrap();
+    // Each command runs five updates of 100ms, long past the expectation's minute.
+    let mut commands = vec![Event::ExpectWindow { expectation }];
+    commands.extend(vec![
+        Event::Command {
+            command: Command::PrintState,
+        };
+        130
+    ]);
+    let last = commands.len() - 1;
+
+    TestHarness::new()
+        .with_windows(3)
+        .on_iteration(last - 1, |world, state| {
+            let origin = Origin::new(0, 0);
+            let frame = IRect::from_corners(
+                origin,
+                origin + Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT),
+            );
+            let window = state.spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, 5, frame);
+            world.trigger(SpawnWindowTrigger(vec![window]));
+        })
+        .on_iteration(last, |world, _| {
+            let entity = find_window_entity(5, world);
+            let mut query = world.query::<&LayoutStrip>();
+            let index = query
+                .iter(world)
+                .find_map(|strip| strip.index_of(entity).ok());
+            assert_ne!(index, Some(0));
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_window_rule_opens_on_space() {
+    const SECOND_SPACE_ID: u64 = TEST_WORKSPACE_ID + 1;
+
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::PrintState,
+        },
+    ];
+
+    let mut params = WindowParams::new("^Window 5$", None);
+    params.space = Some(2);
+    let config: Config = (MainOptions::default(), vec![params]).into();
+
+    let mut harness = TestHarness::new().with_config(config).with_windows(1);
+    harness.mock_state.add_display(
+        TEST_DISPLAY_ID,
+        IRect::new(0, 0, TEST_DISPLAY_WIDTH, TEST_DISPLAY_HEIGHT),
+        vec![TEST_WORKSPACE_ID, SECOND_SPACE_ID],
+    );
+
+    harness
+        .on_iteration(0, |world, state| {
+            let origin = Origin::new(0, 0);
+            let frame = IRect::from_corners(
+                origin,
+                origin + Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT),
+            );
+            let window = state.spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, 5, frame);
+            world.trigger(SpawnWindowTrigger(vec![window]));
+        })
+        .on_iteration(1, |world, state| {
+            assert_on_workspace!(world, 5, SECOND_SPACE_ID);
+            assert_not_on_workspace!(world, 5, TEST_WORKSPACE_ID);
+            assert_focused!(world, 0);
+            state.update_window(5, |window| {
+                assert_eq!(window.workspace_id, SECOND_SPACE_ID);
+            });
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_grouped_columns_swap_together() {
+    let commands = vec![
+        Event::WindowFocused { window_id: 2 },
+        Event::Command {
+            command: Command::GroupColumns(true),
+        },
+        Event::Command {
+            command: Command::Window(Operation::Swap(Direction::West)),
+        },
+    ];
+    let before = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let after = before.clone();
+
+    TestHarness::new()
+        .with_windows(4)
+        .on_iteration(1, move |world, _| {
+            *before.lock().unwrap() = strip_columns(world, TEST_WORKSPACE_ID);
+        })
+        .on_iteration(2, move |world, _| {
+            let before = after.lock().unwrap();
+            let index = before
+                .iter()
+                .position(|column| column.contains(&2))
+                .unwrap();
+            assert!(index > 0 && index + 1 < before.len());
+
+            // The focused column and its right neighbour pass the column on the left.
+            let mut expected = before.clone();
+            expected[index - 1..=index + 1].rotate_left(1);
+            assert_eq!(strip_columns(world, TEST_WORKSPACE_ID), expected);
+            assert_focused!(world, 2);
+        })
+        .run(commands);
+}
+
+#[test]
+fn test_layout_load_keeps_groups_and_orientation() {
+    let slot = format!("test-{}", std::process::id());
+    let commands = vec![
+        Event::WindowFocused { window_id: 1 },
+        Event::Command {
+            command: Command::Window(Operation::Stack(true)),
+        },
+        Event::Command {
+            command: Command::Window(Operation::ToggleSplitOrientation),
+        },
+        Event::Command {
+            command: Command::GroupColumns(true),
+        },
+        Event::Command {
+            command: Command::LayoutSave(slot.clone()),
+        },
+        Event::WindowFocused { window_id: 3 },
+        Event::Command {
+            command: Command::Window(Operation::Swap(Direction::West)),
+        },
+        Event::Command {
+            command: Command::LayoutLoad(slot.clone()),
+        },
+    ];
+
+    TestHarness::new()
+        .with_windows(4)
+        .on_iteration(6, |world, _| {
+            // The last window passes the grouped columns.
+            assert_eq!(
+                strip_columns(world, TEST_WORKSPACE_ID),
+                vec![vec![3], vec![0, 1], vec![2]]
+            );
+        })
+        .on_iteration(7, |world, _| {
+            assert_eq!(
+                strip_columns(world, TEST_WORKSPACE_ID),
+                vec![vec![0, 1], vec![2], vec![3]]
+            );
+            let stacked = find_window_entity(0, world);
+            let grouped = find_window_entity(2, world);
+            let mut query = world.query::<&LayoutStrip>();
+            let strip = query
+                .iter(world)
+                .find(|strip| strip.id() == TEST_WORKSPACE_ID)
+                .unwrap();
+            assert!(strip.is_grouped(grouped));
+            let column = strip.get(strip.index_of(stacked).unwrap()).unwrap();
+            assert_eq!(strip.orientation(&column), ColumnOrientation::Horizontal);
+        })
+        .run(commands);
+
+    if let Ok(path) = LayoutSlot::path(&slot) {
+        _ = std::fs::remove_file(path);
+    }
+}
+
+#[test]
+fn test_window_pull_brings_window_from_other_display() {
+    let commands = vec![
+        Event::MenuOpened { window_id: 0 },
+        Event::Command {
+            command: Command::Window(Operation::Pull("team chat".to_string())),
+        },
+    ];
+
+    let mut harness = TestHarness::new();
+    harness.mock_state.add_display(
+        EXT_DISPLAY_ID,
+        IRect::new(0, -EXT_DISPLAY_HEIGHT, EXT_DISPLAY_WIDTH, 0),
+        vec![EXT_WORKSPACE_ID],
+    );
+    let origin = Origin::new(0, 0);
+    let frame = IRect::from_corners(
+        origin,
+        origin + Size::new(TEST_WINDOW_WIDTH, TEST_WINDOW_HEIGHT),
+    );
+    for window_id in [0, 1] {
+        harness
+            .mock_state
+            .spawn_window(TEST_PROCESS_ID, TEST_WORKSPACE_ID, window_id, frame);
+    }
+    harness
+        .mock_state
+        .spawn_window(TEST_PROCESS_ID, EXT_WORKSPACE_ID, 100, frame);
+    harness.mock_state.update_window(100, |window| {
+        window.title = "Team chat".to_string();
+    });
+
+    harness
+        .on_iteration(0, |world, _| {
+            assert_on_workspace!(world, 100, EXT_WORKSPACE_ID);
+        })
+        .on_iteration(1, |world, state| {
+            // The window matched by its title lands right of the focused one.
+            assert_not_on_workspace!(world, 100, EXT_WORKSPACE_ID);
+            let columns = strip_columns(world, TEST_WORKSPACE_ID);
+            let index = columns.iter().position(|column| column == &[0]).unwrap();
+            assert_eq!(columns.get(index + 1), Some(&vec![100]));
+            assert_focused!(world, 100);
+            state.update_window(100, |window| {
+                assert_eq!(window.workspace_id, TEST_WORKSPACE_ID);
+            });
+        })
+        .run(commands);
+}
@@ -1,13 +1,14 @@
 use accessibility_sys::{
-    AXObserverGetRunLoopSource, AXUIElementRef, kAXFocusedWindowAttribute, kAXMinimizedAttribute,
-    kAXRoleAttribute, kAXSubroleAttribute, kAXTitleAttribute, kAXWindowsAttribute,
+    AXObserverGetRunLoopSource, AXUIElementRef, kAXChildrenAttribute, kAXFocusedWindowAttribute,
+    kAXMinimizedAttribute, kAXRoleAttribute, kAXSubroleAttribute, kAXTitleAttribute,
+    kAXWindowsAttribute,
 };
 use core::ptr::NonNull;
 use objc2::rc::{Retained, autoreleasepool};
 use objc2_app_kit::NSScreen;
 use objc2_core_foundation::{
     CFArray, CFBoolean, CFNumber, CFNumberType, CFRetained, CFRunLoop, CFRunLoopMode,
-    CFRunLoopSource, CFString, CFType, Type, kCFTypeArrayCallBacks,
+    CFRunLoopSource, CFString, Type, kCFTypeArrayCallBacks,
 };
 use objc2_core_graphics::{CGDirectDisplayID, CGError};
 use objc2_foundation::{NSNumber, NSString, NSUserDefaults, ns_string};
@@ -15,14 +16,13 @@ use std::{
     ffi::{CStr, OsStr, c_int, c_void},
     os::unix::ffi::OsStrExt,
     path::{Path, PathBuf},
-    ptr::null_mut,
 };
 use stdext::function_name;
 use tracing::debug;
 
 use crate::{
     errors::{Error, Result},
-    manager::{AXUIElementCopyAttributeValue, ax_window_id},
+    manager::{ax, ax_window_id},
     platform::{OSStatus, WinID},
 };
 
@@ -191,19 +191,19 @@ pub trait AXUIAttributes {
         Ok(array.to_vec())
     }
 
+    fn children(&self) -> Result<Vec<CFRetained<AXUIWrapper>>> {
+        let axname = CFString::from_static_str(kAXChildrenAttribute);
+        let array = self.get_attribute::<CFArray<AXUIWrapper>>(&axname)?;
+        Ok(array.to_vec())
+    }
+
     fn get_attribute<T: Type>(&self, name: &CFRetained<CFString>) -> Result<CFRetained<T>>;
 }
 
 impl AXUIAttributes for CFRetained<AXUIWrapper> {
     fn get_attribute<T: Type>(&self, name: &CFRetained<CFString>) -> Result<CFRetained<T>> {
-        let mut attribute: *mut CFType = null_mut();
-        unsafe { AXUIElementCopyAttributeValue(self.as_ptr(), name, &mut attribute) }
-            .to_result(function_name!())?;
-        NonNull::new(attribute)
-            .map(|ptr| unsafe { CFRetained::from_raw(ptr.cast()) })
-            .ok_or(Error::InvalidInput(format!(
-                "nullptr while getting attribute {name}.",
-            )))
+        let attribute = ax::copy_attribute(self, name)?;
+        Ok(unsafe { CFRetained::from_raw(CFRetained::into_raw(attribute).cast()) })
     }
 }
 
@@ -337,11 +337,26 @@ pub trait MacResult {
     fn to_result(self, place: &str) -> Result<()>;
 }
 
+/// Error codes of the accessibility API, from `kAXErrorFailure` (-25200) to
+/// `kAXErrorNotEnoughPrecision` (-25214) and the codes reserved after them.
+const AX_ERROR_DOMAIN: std::ops::RangeInclusive<OSStatus> = -25299..=-25200;
+
+/// Returns true if the status is an error of the accessibility API, as opposed
+/// to one of the `SkyLight` or Core Graphics calls sharing `OSStatus`.
+fn is_ax_error(status: OSStatus) -> bool {
+    AX_ERROR_DOMAIN.contains(&status)
+}
+
 impl MacResult for OSStatus {
     fn to_result(self, place: &str) -> Result<()> {
         match self {
             0 => Ok(()),
-            err => Err(Error::Generic(format!("{place}: MacOS Error Code: {err}"))),
+            err => {
+                if is_ax_error(err) {
+                    crate::metrics::record_ax_failure();
+                }
+                Err(Error::Generic(format!("{place}: MacOS Error Code: {err}")))
+            }
         }
     }
 }
@@ -373,3 +388,18 @@ where
             .then(|| getter(screen))
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_ax_error() {
+        // kAXErrorCannotComplete and kAXErrorAPIDisabled.
+        assert!(is_ax_error(-25204));
+        assert!(is_ax_error(-25211));
+        // kCGErrorIllegalArgument and a generic paramErr are not.
+        assert!(!is_ax_error(1001));
+        assert!(!is_ax_error(-50));
+    }
+}